@@ -0,0 +1,20 @@
+-- Trade busts: an operator reverses a settled trade with compensating ledger
+-- entries, the trade row stays and is marked busted
+ALTER TABLE trades ADD COLUMN IF NOT EXISTS busted_at TIMESTAMPTZ;
+ALTER TABLE trades ADD COLUMN IF NOT EXISTS bust_reason TEXT;
+
+-- Every trade seen on the public tape, busted ones included, so the market
+-- data service can rebuild the OHLCV bars a bust touches.
+-- fill_id has the same format as trades.exchange_fill_id, but covers trades
+-- between anonymous orders too, which are never settled.
+CREATE TABLE IF NOT EXISTS market_trades (
+    id BIGSERIAL PRIMARY KEY,
+    fill_id VARCHAR(100) NOT NULL UNIQUE,
+    symbol VARCHAR(20) NOT NULL,
+    price DECIMAL(20, 8) NOT NULL,
+    quantity DECIMAL(20, 8) NOT NULL,
+    traded_at TIMESTAMPTZ NOT NULL,
+    busted BOOLEAN NOT NULL DEFAULT FALSE
+);
+
+CREATE INDEX IF NOT EXISTS idx_market_trades_symbol_time ON market_trades(symbol, traded_at);
@@ -18,6 +18,7 @@ pub async fn run_migrations(pool: &PgPool) -> Result<(), sqlx::Error> {
         include_str!("../../migrations/005_nullable_trade_ids.sql"),
         include_str!("../../migrations/006_exchange_fill_id_index.sql"),
         // 007 was for removing exchange_order_id, now consolidated into 004
+        include_str!("../../migrations/008_trade_busts.sql"),
     ];
 
     for migration in migrations {
@@ -35,6 +35,17 @@ impl Balance {
         .await
     }
 
+    /// Balances of several users in the given assets, ordered by user then asset
+    pub async fn get_for_users(pool: &PgPool, user_ids: &[Uuid], assets: &[&str]) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as::<_, Self>(
+            "SELECT * FROM balances WHERE user_id = ANY($1) AND asset = ANY($2) ORDER BY user_id, asset"
+        )
+        .bind(user_ids)
+        .bind(assets)
+        .fetch_all(pool)
+        .await
+    }
+
     /// Get balance or return zero balance if none exists
     pub async fn get_or_zero(pool: &PgPool, user_id: Uuid, asset: &str) -> Result<Decimal, sqlx::Error> {
         let balance = Self::get(pool, user_id, asset).await?;
@@ -13,6 +13,8 @@ pub enum EntryType {
     Fee,
     Lock,
     Unlock,
+    /// Reversal of a busted trade
+    Bust,
 }
 
 impl std::fmt::Display for EntryType {
@@ -24,6 +26,7 @@ impl std::fmt::Display for EntryType {
             EntryType::Fee => write!(f, "fee"),
             EntryType::Lock => write!(f, "lock"),
             EntryType::Unlock => write!(f, "unlock"),
+            EntryType::Bust => write!(f, "bust"),
         }
     }
 }
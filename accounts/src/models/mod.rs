@@ -14,4 +14,4 @@ pub use balance::Balance;
 pub use ohlcv::{OHLCV, Stats24h};
 pub use ledger::{LedgerEntry, EntryType};
 pub use order::{Order, OrderError, OrderStatus, OrderType, PlaceOrderRequest, PlaceOrderResult, Side};
-pub use trade::{Fill, SettlementError, Trade};
+pub use trade::{BustError, Fill, SettlementError, Trade};
@@ -29,6 +29,9 @@ pub struct Trade {
     pub seller_fee: Decimal,
     pub exchange_fill_id: Option<String>,
     pub settled_at: DateTime<Utc>,
+    /// Set once an operator reversed the trade
+    pub busted_at: Option<DateTime<Utc>>,
+    pub bust_reason: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -44,6 +47,20 @@ pub struct Fill {
     pub timestamp: i64,
 }
 
+#[derive(Debug, thiserror::Error)]
+pub enum BustError {
+    #[error("Trade not found: {0}")]
+    NotFound(String),
+    #[error("Trade already busted: {0}")]
+    AlreadyBusted(Uuid),
+    #[error("Cannot reverse trade {0}: {1}")]
+    Reversal(Uuid, String),
+    #[error("Invalid symbol format: {0}")]
+    InvalidSymbol(String),
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum SettlementError {
     #[error("Order not found: {0}")]
@@ -59,6 +76,11 @@ pub enum SettlementError {
 }
 
 impl Trade {
+    /// Idempotency key of a fill, the matching engine identifies trades the same way
+    pub fn fill_id(buy_order_id: Uuid, sell_order_id: Uuid, timestamp: i64) -> String {
+        format!("{}-{}-{}", buy_order_id, sell_order_id, timestamp)
+    }
+
     /// Check if a trade with this fill ID already exists (idempotency)
     pub async fn exists_by_fill_id(pool: &PgPool, fill_id: &str) -> Result<bool, sqlx::Error> {
         let count: i64 = sqlx::query_scalar(
@@ -91,7 +113,7 @@ impl Trade {
     /// If an order UUID is not found in the database, that side is treated as anonymous.
     pub async fn settle(pool: &PgPool, symbol: &str, fill: &Fill) -> Result<Trade, SettlementError> {
         // Generate idempotency key based on order IDs and timestamp
-        let fill_id = format!("{}-{}-{}", fill.buy_order_id, fill.sell_order_id, fill.timestamp);
+        let fill_id = Self::fill_id(fill.buy_order_id, fill.sell_order_id, fill.timestamp);
 
         // Quick idempotency check outside transaction for performance
         // The actual guarantee comes from the unique constraint inside the transaction
@@ -277,6 +299,102 @@ impl Trade {
         Ok(trade)
     }
 
+    /// Bust a settled trade atomically
+    /// This handles:
+    /// 1. Marking the trade as busted (only once)
+    /// 2. Returning the asset to the seller and the payment to the buyer
+    ///    with compensating ledger entries
+    ///
+    /// Order fill quantities are left alone, the orders are no longer on the book.
+    /// Fails without any change if a counterparty no longer holds what the
+    /// reversal takes back.
+    pub async fn bust(pool: &PgPool, fill_id: &str, reason: &str) -> Result<Trade, BustError> {
+        let mut tx = pool.begin().await?;
+
+        // The row lock makes concurrent busts of the same trade wait for each other
+        let trade = sqlx::query_as::<_, Trade>(
+            "SELECT * FROM trades WHERE exchange_fill_id = $1 FOR UPDATE"
+        )
+        .bind(fill_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| BustError::NotFound(fill_id.to_string()))?;
+
+        if trade.busted_at.is_some() {
+            return Err(BustError::AlreadyBusted(trade.id));
+        }
+
+        let parts: Vec<&str> = trade.symbol.split('/').collect();
+        if parts.len() != 2 {
+            return Err(BustError::InvalidSymbol(trade.symbol.clone()));
+        }
+        let base_asset = parts[0];
+        let quote_asset = parts[1];
+
+        // Same amount the settlement moved
+        let quote_amount = LedgerEntry::round_to_precision(quote_asset, trade.price * trade.quantity);
+
+        // Lock in the same order as settlement to prevent deadlocks
+        let mut user_ids: Vec<Uuid> = trade.buyer_id.into_iter().chain(trade.seller_id).collect();
+        user_ids.sort();
+        user_ids.dedup();
+        for user_id in &user_ids {
+            for asset in [base_asset, quote_asset] {
+                let lock_key = LedgerEntry::compute_lock_key_public(*user_id, asset);
+                sqlx::query("SELECT pg_advisory_xact_lock($1)")
+                    .bind(lock_key)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+        }
+
+        // Debits first, so a counterparty that can't pay it back fails the bust
+        // before anything else was written
+        let mut reversals: Vec<(Uuid, &str, Decimal)> = Vec::new();
+        if let Some(buyer) = trade.buyer_id {
+            reversals.push((buyer, base_asset, -trade.quantity));
+        }
+        if let Some(seller) = trade.seller_id {
+            reversals.push((seller, quote_asset, -quote_amount));
+        }
+        if let Some(buyer) = trade.buyer_id {
+            reversals.push((buyer, quote_asset, quote_amount));
+        }
+        if let Some(seller) = trade.seller_id {
+            reversals.push((seller, base_asset, trade.quantity));
+        }
+
+        for (user_id, asset, amount) in reversals {
+            let action = if amount.is_sign_negative() { "return" } else { "refund" };
+            LedgerEntry::append_in_tx(
+                &mut tx,
+                user_id,
+                asset,
+                amount,
+                EntryType::Bust,
+                Some(trade.id),
+                Some(&format!("Bust of trade {}: {} {} {}", trade.id, action, amount.abs(), asset)),
+            )
+            .await
+            .map_err(|e| match e {
+                sqlx::Error::Protocol(msg) => BustError::Reversal(trade.id, msg),
+                e => BustError::Database(e),
+            })?;
+        }
+
+        let trade = sqlx::query_as::<_, Trade>(
+            "UPDATE trades SET busted_at = NOW(), bust_reason = $2 WHERE id = $1 RETURNING *"
+        )
+        .bind(trade.id)
+        .bind(reason)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(trade)
+    }
+
     /// Get trades for a user (as buyer or seller) with pagination
     pub async fn list_for_user(
         pool: &PgPool,
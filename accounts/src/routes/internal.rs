@@ -8,13 +8,14 @@ use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::models::{EntryType, Fill, LedgerEntry, Order, OrderType, PlaceOrderRequest, Side, Trade};
+use crate::models::{Balance, BustError, EntryType, Fill, LedgerEntry, Order, OrderType, PlaceOrderRequest, Side, Trade};
 use crate::AppState;
 
 /// Internal API routes (called by gateway/matching engine, not end users)
 pub fn internal_routes() -> Router<AppState> {
     Router::new()
         .route("/settle", post(settle_fill))
+        .route("/trades/bust", post(bust_trade))
         .route("/cancel", post(cancel_order_internal))
         .route("/orders", post(place_order_internal))
         .route("/orders/:order_id", delete(cancel_order_by_id_internal))
@@ -43,6 +44,16 @@ pub struct SettleFillResponse {
     pub buyer_id: String,
     pub seller_id: String,
     pub settled: bool,
+    /// Balances of the counterparties in the traded assets after settlement
+    pub positions: Vec<PositionResponse>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PositionResponse {
+    pub user_id: String,
+    pub asset: String,
+    pub available: String,
+    pub locked: String,
 }
 
 #[derive(Debug, Serialize)]
@@ -51,6 +62,27 @@ pub struct SettleErrorResponse {
     pub code: String,
 }
 
+/// Identifies the trade the way the matching engine published it
+#[derive(Debug, Deserialize)]
+pub struct BustTradeRequest {
+    pub buy_order_id: Uuid,
+    pub sell_order_id: Uuid,
+    pub timestamp: i64,
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BustTradeResponse {
+    pub trade_id: Uuid,
+    pub symbol: String,
+    /// None for anonymous/bot orders
+    pub buyer_id: Option<Uuid>,
+    pub seller_id: Option<Uuid>,
+    pub price: Decimal,
+    pub quantity: Decimal,
+    pub busted_at: String,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct CancelOrderInternalRequest {
     pub order_id: Uuid,
@@ -136,11 +168,83 @@ async fn settle_fill(
 
     // OHLCV updates are handled by market_data service via WebSocket
 
+    // Balances are read after the commit, the engine pushes them to the users as position
+    // updates. Failing to read them does not undo the settlement.
+    let user_ids: Vec<Uuid> = trade.buyer_id.into_iter().chain(trade.seller_id).collect();
+    let assets: Vec<&str> = trade.symbol.split('/').collect();
+    let positions = match Balance::get_for_users(&state.pool, &user_ids, &assets).await {
+        Ok(balances) => balances
+            .into_iter()
+            .map(|b| PositionResponse {
+                user_id: b.user_id.to_string(),
+                available: LedgerEntry::round_to_precision(&b.asset, b.available).to_string(),
+                locked: LedgerEntry::round_to_precision(&b.asset, b.locked).to_string(),
+                asset: b.asset,
+            })
+            .collect(),
+        Err(e) => {
+            tracing::warn!("Failed to read positions after settling trade {}: {}", trade.id, e);
+            Vec::new()
+        }
+    };
+
     Ok(Json(SettleFillResponse {
         trade_id: trade.id.to_string(),
         buyer_id: trade.buyer_id.map(|id| id.to_string()).unwrap_or_default(),
         seller_id: trade.seller_id.map(|id| id.to_string()).unwrap_or_default(),
         settled: true,
+        positions,
+    }))
+}
+
+/// Internal endpoint to reverse a settled trade (operator action via the matching engine)
+async fn bust_trade(
+    State(state): State<AppState>,
+    Json(req): Json<BustTradeRequest>,
+) -> Result<Json<BustTradeResponse>, (StatusCode, Json<SettleErrorResponse>)> {
+    let fill_id = Trade::fill_id(req.buy_order_id, req.sell_order_id, req.timestamp);
+    tracing::info!("Internal bust request: fill_id={}, reason={}", fill_id, req.reason);
+
+    let trade = Trade::bust(&state.pool, &fill_id, &req.reason)
+        .await
+        .map_err(|e| {
+            let (status, code, error) = match &e {
+                BustError::NotFound(_) => (StatusCode::NOT_FOUND, "TRADE_NOT_FOUND", e.to_string()),
+                BustError::AlreadyBusted(_) => (StatusCode::CONFLICT, "ALREADY_BUSTED", e.to_string()),
+                BustError::Reversal(_, _) => (StatusCode::CONFLICT, "REVERSAL_FAILED", e.to_string()),
+                BustError::InvalidSymbol(_) => (StatusCode::BAD_REQUEST, "INVALID_SYMBOL", e.to_string()),
+                BustError::Database(err) => {
+                    tracing::error!("Bust database error: {}", err);
+                    (StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Database error".to_string())
+                }
+            };
+            (
+                status,
+                Json(SettleErrorResponse {
+                    error,
+                    code: code.to_string(),
+                }),
+            )
+        })?;
+
+    tracing::info!(
+        "Busted trade {}: {} {} @ {} (buyer={:?}, seller={:?})",
+        trade.id,
+        trade.quantity,
+        trade.symbol,
+        trade.price,
+        trade.buyer_id,
+        trade.seller_id
+    );
+
+    Ok(Json(BustTradeResponse {
+        trade_id: trade.id,
+        symbol: trade.symbol,
+        buyer_id: trade.buyer_id,
+        seller_id: trade.seller_id,
+        price: trade.price,
+        quantity: trade.quantity,
+        busted_at: trade.busted_at.map(|t| t.to_rfc3339()).unwrap_or_default(),
     }))
 }
 
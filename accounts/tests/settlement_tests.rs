@@ -1,6 +1,6 @@
 use accounts::db;
 use accounts::models::{
-    Balance, EntryType, Fill, LedgerEntry, Order, OrderType, PlaceOrderRequest, Side, Trade,
+    Balance, BustError, EntryType, Fill, LedgerEntry, Order, OrderType, PlaceOrderRequest, Side, Trade,
 };
 use rust_decimal::Decimal;
 use serial_test::serial;
@@ -423,6 +423,149 @@ async fn test_settlement_partial_fill() {
     assert_eq!(buyer_kcn, expected_kcn, "Buyer KCN balance mismatch");
 }
 
+// =============================================================================
+// TRADE BUST TESTS
+// =============================================================================
+
+/// Place matching 10 KCN @ 5.00 orders and settle the fill, returns its fill ID
+async fn settle_trade(pool: &PgPool, buyer_id: Uuid, seller_id: Uuid) -> String {
+    let mut order_ids = Vec::new();
+    for (user_id, side) in [(buyer_id, Side::Bid), (seller_id, Side::Ask)] {
+        let order = Order::place(
+            pool,
+            user_id,
+            PlaceOrderRequest {
+                symbol: "KCN/EUR".to_string(),
+                side,
+                order_type: OrderType::Limit,
+                price: Some(Decimal::from_str("5.00").unwrap()),
+                quantity: Decimal::from_str("10.00000000").unwrap(),
+                max_slippage_price: None,
+                quote_amount: None,
+            },
+        )
+        .await
+        .unwrap()
+        .order;
+        order_ids.push(order.id);
+    }
+
+    let fill = Fill {
+        buy_order_id: order_ids[0],
+        sell_order_id: order_ids[1],
+        price: Decimal::from_str("5.00").unwrap(),
+        quantity: Decimal::from_str("10.00000000").unwrap(),
+        timestamp: chrono::Utc::now().timestamp_millis(),
+    };
+    Trade::settle(pool, "KCN/EUR", &fill).await.unwrap();
+    Trade::fill_id(fill.buy_order_id, fill.sell_order_id, fill.timestamp)
+}
+
+#[tokio::test]
+#[serial]
+async fn test_bust_reverses_settlement() {
+    let pool = setup_db().await;
+
+    let buyer_id = create_test_user(&pool, "bust_buyer@test.com").await;
+    let seller_id = create_test_user(&pool, "bust_seller@test.com").await;
+    fund_user(&pool, buyer_id, "1000.00", "0").await;
+    fund_user(&pool, seller_id, "0", "100.00000000").await;
+
+    let fill_id = settle_trade(&pool, buyer_id, seller_id).await;
+    let trade = Trade::bust(&pool, &fill_id, "erroneous price").await.unwrap();
+    assert!(trade.busted_at.is_some());
+    assert_eq!(trade.bust_reason.as_deref(), Some("erroneous price"));
+
+    // Both sides are back where they were before the trade
+    assert_eq!(Balance::get_or_zero(&pool, buyer_id, "EUR").await.unwrap(), Decimal::from_str("1000.00").unwrap());
+    assert_eq!(Balance::get_or_zero(&pool, buyer_id, "KCN").await.unwrap(), Decimal::ZERO);
+    assert_eq!(Balance::get_or_zero(&pool, seller_id, "EUR").await.unwrap(), Decimal::ZERO);
+    assert_eq!(Balance::get_or_zero(&pool, seller_id, "KCN").await.unwrap(), Decimal::from_str("100.00000000").unwrap());
+
+    // Reversals are ledger entries of their own, referencing the trade
+    let history = LedgerEntry::get_history(&pool, buyer_id, "KCN", 10).await.unwrap();
+    assert_eq!(history[0].entry_type, "bust");
+    assert_eq!(history[0].reference_id, Some(trade.id));
+    for user_id in [buyer_id, seller_id] {
+        for asset in ["EUR", "KCN"] {
+            assert!(LedgerEntry::reconcile(&pool, user_id, asset).await.unwrap());
+        }
+    }
+
+    let again = Trade::bust(&pool, &fill_id, "erroneous price").await;
+    assert!(matches!(again, Err(BustError::AlreadyBusted(id)) if id == trade.id));
+    assert!(matches!(
+        Trade::bust(&pool, "no-such-fill", "typo").await,
+        Err(BustError::NotFound(_))
+    ));
+}
+
+#[tokio::test]
+#[serial]
+async fn test_bust_fails_when_asset_is_gone() {
+    let pool = setup_db().await;
+
+    let buyer_id = create_test_user(&pool, "bust_buyer2@test.com").await;
+    let seller_id = create_test_user(&pool, "bust_seller2@test.com").await;
+    fund_user(&pool, buyer_id, "1000.00", "0").await;
+    fund_user(&pool, seller_id, "0", "100.00000000").await;
+
+    let fill_id = settle_trade(&pool, buyer_id, seller_id).await;
+    // The buyer already moved the KCN on
+    LedgerEntry::append(
+        &pool,
+        buyer_id,
+        "KCN",
+        Decimal::from_str("-10.00000000").unwrap(),
+        EntryType::Withdrawal,
+        None,
+        None,
+    )
+    .await
+    .unwrap();
+
+    let result = Trade::bust(&pool, &fill_id, "erroneous price").await;
+    assert!(matches!(result, Err(BustError::Reversal(_, _))));
+
+    // Nothing was reversed and the trade still stands
+    assert_eq!(Balance::get_or_zero(&pool, seller_id, "EUR").await.unwrap(), Decimal::from_str("50.00").unwrap());
+    let trade = Trade::get_by_fill_id(&pool, &fill_id).await.unwrap().unwrap();
+    assert!(trade.busted_at.is_none());
+}
+
+#[tokio::test]
+#[serial]
+async fn test_positions_after_settlement() {
+    let pool = setup_db().await;
+
+    let buyer_id = create_test_user(&pool, "position_buyer@test.com").await;
+    let seller_id = create_test_user(&pool, "position_seller@test.com").await;
+    let bystander_id = create_test_user(&pool, "position_bystander@test.com").await;
+    fund_user(&pool, buyer_id, "1000.00", "0").await;
+    fund_user(&pool, seller_id, "0", "100.00000000").await;
+    fund_user(&pool, bystander_id, "1.00", "1.00000000").await;
+
+    settle_trade(&pool, buyer_id, seller_id).await;
+
+    let positions = Balance::get_for_users(&pool, &[buyer_id, seller_id], &["KCN", "EUR"])
+        .await
+        .unwrap();
+    assert_eq!(positions.len(), 4);
+    assert!(positions.iter().all(|b| b.user_id == buyer_id || b.user_id == seller_id));
+
+    let position = |user_id: Uuid, asset: &str| {
+        positions
+            .iter()
+            .find(|b| b.user_id == user_id && b.asset == asset)
+            .map(|b| b.available)
+            .unwrap()
+    };
+    assert_eq!(position(buyer_id, "EUR"), Decimal::from_str("950.00").unwrap());
+    assert_eq!(position(buyer_id, "KCN"), Decimal::from_str("10.00000000").unwrap());
+    assert_eq!(position(seller_id, "EUR"), Decimal::from_str("50.00").unwrap());
+    assert_eq!(position(seller_id, "KCN"), Decimal::from_str("90.00000000").unwrap());
+}
+
 // =============================================================================
 // EUR PRECISION VALIDATION TESTS
 // =============================================================================
@@ -109,7 +109,7 @@ function App() {
     }
   }, []);
 
-  // Handle order lifecycle events (order_filled, order_cancelled)
+  // Handle order lifecycle events (order_filled, order_cancelled, order_rejected, trade_bust)
   const handleOrderEvent = useCallback((event: OrderEvent) => {
     if (event.type === 'order_filled') {
       // Check if this is one of our orders
@@ -173,6 +173,24 @@ function App() {
         }
         orderFillsRef.current.delete(event.order_id);
       }
+    } else if (event.type === 'order_rejected') {
+      // Only ever sent to the owner, the order never reached the book
+      fetchOrders();
+      fetchBalances();
+      addToast({
+        type: 'error',
+        title: 'Order Rejected',
+        message: event.reason,
+      });
+    } else if (event.type === 'trade_bust' && event.side) {
+      // One of our trades was reversed, balances are back to before it
+      fetchOrders();
+      fetchBalances();
+      addToast({
+        type: 'error',
+        title: 'Trade Busted',
+        message: `Your ${event.side} of ${parseFloat(event.quantity).toFixed(2)} KCN @ ${parseFloat(event.price).toFixed(2)} EUR was reversed: ${event.reason}`,
+      });
     }
   }, [fetchOrders, fetchBalances, addToast]);
 
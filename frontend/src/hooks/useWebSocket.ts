@@ -29,7 +29,27 @@ export interface OrderCancelledEvent {
   filled_quantity: string;
 }
 
-export type OrderEvent = OrderFilledEvent | OrderCancelledEvent;
+export interface OrderRejectedEvent {
+  type: 'order_rejected';
+  order_id: string;
+  reason: string;
+}
+
+// Sent to every client, `side` is only set for the counterparties
+export interface TradeBustEvent {
+  type: 'trade_bust';
+  symbol: string;
+  trade_id: string;
+  buy_order_id: string;
+  sell_order_id: string;
+  price: string;
+  quantity: string;
+  timestamp: number;
+  reason: string;
+  side?: 'buy' | 'sell';
+}
+
+export type OrderEvent = OrderFilledEvent | OrderCancelledEvent | OrderRejectedEvent | TradeBustEvent;
 
 // Server response messages
 export interface AuthResultMessage {
@@ -147,7 +167,7 @@ export function useWebSocket(url: string, options: UseWebSocketOptions) {
             const data = JSON.parse(event.data);
 
             // Check message type
-            if (data.type === 'order_filled' || data.type === 'order_cancelled') {
+            if (data.type === 'order_filled' || data.type === 'order_cancelled' || data.type === 'order_rejected' || data.type === 'trade_bust') {
               if (onOrderEventRef.current) {
                 onOrderEventRef.current(data as OrderEvent);
               }
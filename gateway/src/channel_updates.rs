@@ -1,11 +1,42 @@
 use rust_decimal::Decimal;
 use rust_decimal::prelude::ToPrimitive;
 use std::collections::{BTreeMap, VecDeque};
+use std::time::{Duration, Instant};
 
 use crate::events::{DeltaAction, MarketEvent, Side};
 use crate::websocket::{ChannelNotification, NotificationData, PriceLevelChange, Stats24h, TradeData};
 
 const TWENTY_FOUR_HOURS_MS: u64 = 24 * 60 * 60 * 1000;
+/// Minimum time between snapshot requests while the book is out of sync
+const SNAPSHOT_RETRY: Duration = Duration::from_secs(1);
+
+/// Price levels of one side of a book view, best first
+type Levels = Vec<(Decimal, Decimal)>;
+
+/// Depth tiers offered to subscribers when `BOOK_DEPTH_TIERS` is not set
+pub const DEFAULT_BOOK_DEPTHS: [usize; 3] = [1, 10, 25];
+
+/// A subscription to one depth tier of a book, `book.{SYMBOL}.none.{DEPTH}.{INTERVAL}`.
+/// Updates are pushed as the book changes, the interval is kept for
+/// compatibility with existing clients and otherwise ignored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BookChannel {
+    pub symbol: String,
+    pub depth: usize,
+}
+
+impl BookChannel {
+    pub fn parse(channel: &str) -> Option<Self> {
+        let mut parts = channel.split('.');
+        if parts.next() != Some("book") {
+            return None;
+        }
+        let symbol = parts.next()?.to_string();
+        let _grouping = parts.next()?;
+        let depth = parts.next()?.parse().ok().filter(|d| *d > 0)?;
+        Some(Self { symbol, depth })
+    }
+}
 
 /// A trade record for 24h stats calculation
 #[derive(Clone)]
@@ -19,7 +50,11 @@ pub struct OrderBookState {
     bids: BTreeMap<Decimal, Decimal>,
     asks: BTreeMap<Decimal, Decimal>,
     last_trades: Vec<TradeData>,
+    /// Sequence of the last snapshot or delta applied
     last_sequence: u64,
+    /// No snapshot seen yet, or a delta went missing since the last one
+    stale: bool,
+    last_snapshot_request: Option<Instant>,
     /// Rolling window of trades for 24h stats (ordered by timestamp)
     stats_trades: VecDeque<StatsTradeRecord>,
     last_price: f64,
@@ -32,6 +67,8 @@ impl OrderBookState {
             asks: BTreeMap::new(),
             last_trades: Vec::new(),
             last_sequence: 0,
+            stale: true,
+            last_snapshot_request: None,
             stats_trades: VecDeque::new(),
             last_price: 0.0,
         }
@@ -95,124 +132,76 @@ impl OrderBookState {
         })
     }
 
-    pub fn apply_orderbook_update(&mut self, event: &MarketEvent) -> Option<ChannelNotification> {
-        match event {
-            MarketEvent::OrderBookSnapshot { symbol, sequence, bids, asks } => {
-                let mut bid_changes = Vec::new();
-                let mut ask_changes = Vec::new();
-
-                // Build new state from incoming snapshot
-                let mut new_bids = BTreeMap::new();
-                let mut new_asks = BTreeMap::new();
-
-                for level in bids {
-                    tracing::info!("Snapshot bid: price={}, quantity={}", level.price, level.quantity);
-                    let old_qty = self
-                        .bids
-                        .get(&level.price)
-                        .copied()
-                        .unwrap_or(Decimal::ZERO);
-                    tracing::info!("  old_qty={}, comparison: {} != {} = {}",
-                        old_qty, old_qty, level.quantity, old_qty != level.quantity);
-                    if old_qty != level.quantity {
-                        bid_changes.push(PriceLevelChange {
-                            price: level.price.to_f64().unwrap_or(0.0),
-                            old_quantity: old_qty.to_f64().unwrap_or(0.0),
-                            new_quantity: level.quantity.to_f64().unwrap_or(0.0),
-                        });
-                    }
-                    if !level.quantity.is_zero() {
-                        new_bids.insert(level.price, level.quantity);
-                    }
-                }
-
-                for level in asks {
-                    let old_qty = self
-                        .asks
-                        .get(&level.price)
-                        .copied()
-                        .unwrap_or(Decimal::ZERO);
-                    if old_qty != level.quantity {
-                        ask_changes.push(PriceLevelChange {
-                            price: level.price.to_f64().unwrap_or(0.0),
-                            old_quantity: old_qty.to_f64().unwrap_or(0.0),
-                            new_quantity: level.quantity.to_f64().unwrap_or(0.0),
-                        });
-                    }
-                    if !level.quantity.is_zero() {
-                        new_asks.insert(level.price, level.quantity);
-                    }
-                }
-
-                // Emit removals for levels no longer present
-                for (price, qty) in &self.bids {
-                    if !new_bids.contains_key(price) {
-                        bid_changes.push(PriceLevelChange {
-                            price: price.to_f64().unwrap_or(0.0),
-                            old_quantity: qty.to_f64().unwrap_or(0.0),
-                            new_quantity: 0.0,
-                        });
-                    }
-                }
+    /// Whether a snapshot should be requested from the engine now. True
+    /// while the book is out of sync, at most once per `SNAPSHOT_RETRY`.
+    pub fn snapshot_due(&mut self) -> bool {
+        if !self.stale {
+            return false;
+        }
+        let now = Instant::now();
+        if self
+            .last_snapshot_request
+            .is_some_and(|at| now.duration_since(at) < SNAPSHOT_RETRY)
+        {
+            return false;
+        }
+        self.last_snapshot_request = Some(now);
+        true
+    }
 
-                for (price, qty) in &self.asks {
-                    if !new_asks.contains_key(price) {
-                        ask_changes.push(PriceLevelChange {
-                            price: price.to_f64().unwrap_or(0.0),
-                            old_quantity: qty.to_f64().unwrap_or(0.0),
-                            new_quantity: 0.0,
-                        });
-                    }
-                }
+    /// Apply a book event to the full book and work out what changed for
+    /// each of the subscribed `depths`. Depths without changes are left out.
+    pub fn apply_orderbook_update(&mut self, event: &MarketEvent, depths: &[usize]) -> Vec<(usize, NotificationData)> {
+        let before: Vec<(usize, Levels, Levels)> = depths
+            .iter()
+            .map(|&depth| (depth, self.top_bids(depth), self.top_asks(depth)))
+            .collect();
+        let mut trades = Vec::new();
 
+        match event {
+            MarketEvent::OrderBookSnapshot { symbol, sequence, bids, asks } => {
+                tracing::info!("Applying {} snapshot at sequence {}", symbol, sequence);
                 // Replace state with new snapshot
-                self.bids = new_bids;
-                self.asks = new_asks;
+                self.bids = bids
+                    .iter()
+                    .filter(|level| !level.quantity.is_zero())
+                    .map(|level| (level.price, level.quantity))
+                    .collect();
+                self.asks = asks
+                    .iter()
+                    .filter(|level| !level.quantity.is_zero())
+                    .map(|level| (level.price, level.quantity))
+                    .collect();
                 self.last_sequence = *sequence;
-
-                self.build_notification(symbol, bid_changes, ask_changes, Vec::new())
+                self.stale = false;
+                self.last_snapshot_request = None;
             }
             MarketEvent::OrderBookDelta { symbol, sequence, deltas } => {
-                let mut bid_changes = Vec::new();
-                let mut ask_changes = Vec::new();
-
-                // Apply deltas to current state
+                if *sequence != self.last_sequence + 1 && !self.stale {
+                    tracing::warn!(
+                        "Book delta gap for {}: expected sequence {}, got {}",
+                        symbol,
+                        self.last_sequence + 1,
+                        sequence
+                    );
+                    self.stale = true;
+                }
+                // Keep applying while out of sync, the snapshot will correct the book
                 for delta in deltas {
-                    let (book, changes) = match delta.side {
-                        Side::Bid => (&mut self.bids, &mut bid_changes),
-                        Side::Ask => (&mut self.asks, &mut ask_changes),
+                    let book = match delta.side {
+                        Side::Bid => &mut self.bids,
+                        Side::Ask => &mut self.asks,
                     };
-
-                    let old_qty = book.get(&delta.price).copied().unwrap_or(Decimal::ZERO);
-
                     match delta.action {
                         DeltaAction::Add | DeltaAction::Update => {
                             book.insert(delta.price, delta.quantity);
-                            changes.push(PriceLevelChange {
-                                price: delta.price.to_f64().unwrap_or(0.0),
-                                old_quantity: old_qty.to_f64().unwrap_or(0.0),
-                                new_quantity: delta.quantity.to_f64().unwrap_or(0.0),
-                            });
                         }
                         DeltaAction::Remove => {
                             book.remove(&delta.price);
-                            changes.push(PriceLevelChange {
-                                price: delta.price.to_f64().unwrap_or(0.0),
-                                old_quantity: old_qty.to_f64().unwrap_or(0.0),
-                                new_quantity: 0.0,
-                            });
                         }
                     }
                 }
-
                 self.last_sequence = *sequence;
-
-                // Only send notification if there were actual changes
-                if bid_changes.is_empty() && ask_changes.is_empty() {
-                    return None;
-                }
-
-                self.build_notification(symbol, bid_changes, ask_changes, Vec::new())
             }
             MarketEvent::Fill {
                 buy_order_id,
@@ -220,7 +209,7 @@ impl OrderBookState {
                 price,
                 quantity,
                 timestamp,
-                symbol,
+                ..
             } => {
                 let price_f64 = price.to_f64().unwrap_or(0.0);
                 let quantity_f64 = quantity.to_f64().unwrap_or(0.0);
@@ -242,91 +231,119 @@ impl OrderBookState {
                     self.last_trades.remove(0);
                 }
 
-                // Broadcast the trade with stats
-                let channel_name = format!("book.{}.none.10.100ms", symbol);
-                Some(ChannelNotification {
-                    channel_name,
-                    notification: NotificationData {
-                        trades: vec![trade],
-                        bid_changes: Vec::new(),
-                        ask_changes: Vec::new(),
-                        total_bid_amount: self.bids.values().map(|q| q.to_f64().unwrap_or(0.0)).sum(),
-                        total_ask_amount: self.asks.values().map(|q| q.to_f64().unwrap_or(0.0)).sum(),
-                        time: std::time::SystemTime::now()
-                            .duration_since(std::time::UNIX_EPOCH)
-                            .unwrap()
-                            .as_secs_f64(),
-                        stats_24h: self.get_stats_24h(),
-                    },
-                })
+                // Broadcast the trade with stats to every tier
+                trades.push(trade);
             }
-            _ => None,
+            _ => return Vec::new(),
         }
+
+        before
+            .into_iter()
+            .filter_map(|(depth, old_bids, old_asks)| {
+                let bid_changes = level_changes(&old_bids, &self.top_bids(depth));
+                let ask_changes = level_changes(&old_asks, &self.top_asks(depth));
+                // Only send notification if there were actual changes
+                if bid_changes.is_empty() && ask_changes.is_empty() && trades.is_empty() {
+                    return None;
+                }
+                Some((depth, self.build_notification(depth, bid_changes, ask_changes, trades.clone())))
+            })
+            .collect()
     }
 
     pub fn get_trades_snapshot(&self) -> Vec<TradeData> {
         self.last_trades.clone()
     }
 
-    pub fn get_orderbook_snapshot(&self, symbol: &str) -> ChannelNotification {
-        let bid_changes: Vec<PriceLevelChange> = self
-            .bids
-            .iter()
-            .map(|(price, qty)| PriceLevelChange {
-                price: price.to_f64().unwrap_or(0.0),
-                old_quantity: 0.0,
-                new_quantity: qty.to_f64().unwrap_or(0.0),
-            })
-            .collect();
+    /// Full view of one depth tier, sent to a client when it subscribes
+    pub fn get_orderbook_snapshot(&self, channel_name: &str, depth: usize) -> ChannelNotification {
+        let added = |levels: Levels| -> Vec<PriceLevelChange> {
+            levels
+                .into_iter()
+                .map(|(price, qty)| PriceLevelChange {
+                    price: price.to_f64().unwrap_or(0.0),
+                    old_quantity: 0.0,
+                    new_quantity: qty.to_f64().unwrap_or(0.0),
+                })
+                .collect()
+        };
+
+        ChannelNotification {
+            channel_name: channel_name.to_string(),
+            notification: self.build_notification(
+                depth,
+                added(self.top_bids(depth)),
+                added(self.top_asks(depth)),
+                self.last_trades.clone(),
+            ),
+        }
+    }
 
-        let ask_changes: Vec<PriceLevelChange> = self
-            .asks
-            .iter()
-            .map(|(price, qty)| PriceLevelChange {
-                price: price.to_f64().unwrap_or(0.0),
-                old_quantity: 0.0,
-                new_quantity: qty.to_f64().unwrap_or(0.0),
-            })
-            .collect();
+    /// Best `depth` bids, highest first
+    fn top_bids(&self, depth: usize) -> Levels {
+        self.bids.iter().rev().take(depth).map(|(p, q)| (*p, *q)).collect()
+    }
 
-        self.build_notification(symbol, bid_changes, ask_changes, self.last_trades.clone())
-            .unwrap()
+    /// Best `depth` asks, lowest first
+    fn top_asks(&self, depth: usize) -> Levels {
+        self.asks.iter().take(depth).map(|(p, q)| (*p, *q)).collect()
     }
 
     fn build_notification(
         &self,
-        symbol: &str,
+        depth: usize,
         bid_changes: Vec<PriceLevelChange>,
         ask_changes: Vec<PriceLevelChange>,
         trades: Vec<TradeData>,
-    ) -> Option<ChannelNotification> {
-        let total_bid_amount: f64 = self
-            .bids
-            .values()
-            .map(|q| q.to_f64().unwrap_or(0.0))
-            .sum();
-        let total_ask_amount: f64 = self
-            .asks
-            .values()
-            .map(|q| q.to_f64().unwrap_or(0.0))
-            .sum();
-
-        let channel_name = format!("book.{}.none.10.100ms", symbol);
-        Some(ChannelNotification {
-            channel_name,
-            notification: NotificationData {
-                trades,
-                bid_changes,
-                ask_changes,
-                total_bid_amount,
-                total_ask_amount,
-                time: std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .unwrap()
-                    .as_secs_f64(),
-                stats_24h: self.get_stats_24h(),
-            },
-        })
+    ) -> NotificationData {
+        // Totals cover the levels the subscriber can see
+        let total = |levels: Levels| -> f64 {
+            levels.iter().map(|(_, q)| q.to_f64().unwrap_or(0.0)).sum()
+        };
+
+        NotificationData {
+            trades,
+            bid_changes,
+            ask_changes,
+            total_bid_amount: total(self.top_bids(depth)),
+            total_ask_amount: total(self.top_asks(depth)),
+            time: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_secs_f64(),
+            stats_24h: self.get_stats_24h(),
+        }
     }
 }
 
+/// Level changes between two views of one side of a tier. Levels that
+/// scroll in or out of the tier show up as added or removed.
+fn level_changes(before: &[(Decimal, Decimal)], after: &[(Decimal, Decimal)]) -> Vec<PriceLevelChange> {
+    let old: BTreeMap<Decimal, Decimal> = before.iter().copied().collect();
+    let new: BTreeMap<Decimal, Decimal> = after.iter().copied().collect();
+    let mut changes = Vec::new();
+
+    for (price, qty) in &new {
+        let old_qty = old.get(price).copied().unwrap_or(Decimal::ZERO);
+        if old_qty != *qty {
+            changes.push(PriceLevelChange {
+                price: price.to_f64().unwrap_or(0.0),
+                old_quantity: old_qty.to_f64().unwrap_or(0.0),
+                new_quantity: qty.to_f64().unwrap_or(0.0),
+            });
+        }
+    }
+
+    // Emit removals for levels no longer present
+    for (price, qty) in &old {
+        if !new.contains_key(price) {
+            changes.push(PriceLevelChange {
+                price: price.to_f64().unwrap_or(0.0),
+                old_quantity: qty.to_f64().unwrap_or(0.0),
+                new_quantity: 0.0,
+            });
+        }
+    }
+
+    changes
+}
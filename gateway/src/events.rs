@@ -1,6 +1,7 @@
 use matching_engine::OrderId;
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -26,6 +27,26 @@ pub enum DeltaAction {
     Remove,
 }
 
+/// Trading phase of a market, only an open market matches new orders
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MarketPhase {
+    PreOpen,
+    Open,
+    Auction,
+    Halted,
+    Closed,
+    Delisted,
+}
+
+/// Balance of one asset in a position update
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssetBalance {
+    pub asset: String,
+    pub available: Decimal,
+    pub locked: Decimal,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LevelDelta {
     pub action: DeltaAction,
@@ -85,6 +106,51 @@ pub enum MarketEvent {
         /// Order ID (UUID)
         order_id: OrderId,
     },
+    /// Sent when an order failed a pre-trade risk check and never reached the book
+    #[serde(rename = "order_rejected")]
+    OrderRejected {
+        /// Order ID (UUID)
+        order_id: OrderId,
+        /// Which limit was hit
+        reason: String,
+    },
+    /// Trading phase of a market, with the reason for the last operator action
+    #[serde(rename = "market_status")]
+    MarketStatus {
+        symbol: String,
+        phase: MarketPhase,
+        reason: String,
+        timestamp: u64,
+    },
+    /// A settled trade was reversed by an operator, sent to every client
+    #[serde(rename = "trade_bust")]
+    TradeBust {
+        symbol: String,
+        trade_id: Uuid,
+        buy_order_id: OrderId,
+        sell_order_id: OrderId,
+        /// Routing only, counterparties never learn each other's ID
+        #[serde(skip_serializing)]
+        buyer_id: Option<Uuid>,
+        #[serde(skip_serializing)]
+        seller_id: Option<Uuid>,
+        price: Decimal,
+        quantity: Decimal,
+        /// Timestamp of the original fill
+        timestamp: u64,
+        reason: String,
+    },
+    /// Balances of a user after one of their fills settled, sent only to that
+    /// user on the private `positions` channel
+    #[serde(rename = "position_update")]
+    PositionUpdate {
+        /// Routing only, the client knows who it is
+        #[serde(skip_serializing)]
+        user_id: Uuid,
+        balances: Vec<AssetBalance>,
+        /// Time of the fill, Unix milliseconds
+        timestamp: u64,
+    },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
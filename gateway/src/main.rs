@@ -1,3 +1,4 @@
+use gateway::channel_updates::DEFAULT_BOOK_DEPTHS;
 use gateway::{GatewayServer, UdpOrderSender, UdpTransportConfig};
 use std::net::SocketAddr;
 use std::sync::Arc;
@@ -57,9 +58,13 @@ async fn tokio_main() -> anyhow::Result<()> {
     tracing::info!("Proxying market data requests to: {}", market_data_url);
 
     // Create UDP order sender
+    if udp_config.order_stream_auth.is_none() {
+        tracing::warn!("ORDER_STREAM_KEY not set, sending unsigned orders");
+    }
     let order_sender = match UdpOrderSender::new(
         udp_config.matching_engine_addr,
         udp_config.order_sender_bind,
+        udp_config.order_stream_auth.clone(),
     ) {
         Ok(s) => {
             tracing::info!("UDP order sender created");
@@ -71,12 +76,20 @@ async fn tokio_main() -> anyhow::Result<()> {
         }
     };
 
-    let server = GatewayServer::new(order_sender, accounts_url, market_data_url);
+    // Book depths clients can subscribe to, e.g. "1,10,25"
+    let book_depths: Vec<usize> = match std::env::var("BOOK_DEPTH_TIERS") {
+        Ok(v) => v.split(',').filter_map(|d| d.trim().parse().ok()).filter(|d| *d > 0).collect(),
+        Err(_) => DEFAULT_BOOK_DEPTHS.to_vec(),
+    };
+    tracing::info!("Book depth tiers: {:?}", book_depths);
+
+    let server = GatewayServer::new(order_sender, accounts_url, market_data_url, book_depths);
 
     server.start_event_broadcaster().await;
 
     // Start UDP event receiver
-    if let Err(e) = server.start_udp_event_receiver(udp_config.event_receiver_bind).await {
+    let multicast_group = udp_config.event_multicast_group;
+    if let Err(e) = server.start_udp_event_receiver(udp_config.event_receiver_bind, multicast_group).await {
         tracing::warn!("Failed to start UDP event receiver (continuing without it): {}", e);
     }
 
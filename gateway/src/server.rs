@@ -15,13 +15,15 @@ use tracing::{error, info};
 
 use crate::channel_updates::OrderBookState;
 use crate::events::MarketEvent;
+use crate::websocket::ChannelNotification;
 use crate::udp_transport::{UdpOrderSender, UdpEventReceiver};
 use crate::proxy::{proxy_accounts, proxy_market_data, ProxyState};
 use crate::state::GatewayState;
 use crate::websocket::{BotCommand, BotCommandInner, ChannelManager};
 use std::collections::HashMap;
-use std::net::SocketAddr;
+use std::net::{Ipv4Addr, SocketAddr};
 use std::sync::Arc;
+use uuid::Uuid;
 
 pub struct GatewayServer {
     state: GatewayState,
@@ -40,11 +42,17 @@ type AppState = (
 );
 
 impl GatewayServer {
-    pub fn new(order_sender: Arc<UdpOrderSender>, accounts_url: String, market_data_url: String) -> Self {
+    /// `book_depths` are the depth tiers clients can subscribe to
+    pub fn new(
+        order_sender: Arc<UdpOrderSender>,
+        accounts_url: String,
+        market_data_url: String,
+        book_depths: Vec<usize>,
+    ) -> Self {
         Self {
             state: GatewayState::new(),
             order_sender,
-            channel_manager: Arc::new(tokio::sync::RwLock::new(ChannelManager::new())),
+            channel_manager: Arc::new(tokio::sync::RwLock::new(ChannelManager::new(book_depths))),
             orderbook_states: Arc::new(tokio::sync::RwLock::new(HashMap::new())),
             proxy_state: ProxyState::new(accounts_url, market_data_url),
         }
@@ -159,10 +167,22 @@ impl GatewayServer {
     pub async fn start_udp_event_receiver(
         &self,
         bind_addr: SocketAddr,
+        multicast_group: Option<Ipv4Addr>,
     ) -> anyhow::Result<()> {
         let state = self.state.clone();
 
-        let (_receiver, mut event_rx) = UdpEventReceiver::new(bind_addr)?;
+        // Lost book deltas can't be replayed, rebuild every book we serve
+        let order_sender = self.order_sender.clone();
+        let orderbook_states = self.orderbook_states.clone();
+        let resync = move || {
+            for symbol in orderbook_states.blocking_read().keys() {
+                if let Err(e) = order_sender.request_snapshot(symbol) {
+                    error!("Failed to request snapshot for {}: {}", symbol, e);
+                }
+            }
+        };
+
+        let (_receiver, mut event_rx) = UdpEventReceiver::new(bind_addr, multicast_group, resync)?;
 
         tokio::spawn(async move {
             info!("UDP event receiver started, waiting for market events...");
@@ -180,11 +200,45 @@ impl GatewayServer {
         let mut event_rx = self.state.subscribe_events();
         let channel_manager = self.channel_manager.clone();
         let orderbook_states = self.orderbook_states.clone();
+        let order_sender = self.order_sender.clone();
 
         tokio::spawn(async move {
             while let Ok(event) = event_rx.recv().await {
-                // Note: OrderFilled/OrderCancelled events are now handled per-client in websocket.rs
-                // Here we only handle orderbook updates for channel subscribers
+                // Note: OrderFilled/OrderCancelled/OrderRejected/PositionUpdate events are now handled per-client in websocket.rs
+                // Here we handle orderbook updates for channel subscribers, market status and busts for everyone
+
+                if let MarketEvent::MarketStatus { symbol, phase, reason, .. } = &event {
+                    info!("Event broadcaster: MarketStatus for {} phase={:?} ({})", symbol, phase, reason);
+                    match serde_json::to_string(&event) {
+                        Ok(json) => channel_manager.write().await.broadcast_market_status(symbol, json),
+                        Err(e) => error!("Failed to serialize market status: {}", e),
+                    }
+                    continue;
+                }
+
+                // Everyone learns of a bust, the counterparties also see their side of it
+                if let MarketEvent::TradeBust { symbol, trade_id, buyer_id, seller_id, .. } = &event {
+                    info!("Event broadcaster: TradeBust for {} trade={}", symbol, trade_id);
+                    match serde_json::to_value(&event) {
+                        Ok(public) => {
+                            let personalize = |user_id: Uuid| {
+                                let side = if Some(user_id) == *buyer_id {
+                                    "buy"
+                                } else if Some(user_id) == *seller_id {
+                                    "sell"
+                                } else {
+                                    return None;
+                                };
+                                let mut own = public.clone();
+                                own["side"] = side.into();
+                                Some(own.to_string())
+                            };
+                            channel_manager.read().await.broadcast_to_all(&public.to_string(), personalize);
+                        }
+                        Err(e) => error!("Failed to serialize trade bust: {}", e),
+                    }
+                    continue;
+                }
 
                 let mut ob_states = orderbook_states.write().await;
 
@@ -206,20 +260,45 @@ impl GatewayServer {
                 };
 
                 if let Some(symbol) = symbol {
+                    // Every subscribed tier of this book gets its own view of the change
+                    let subscriptions = channel_manager.read().await.book_subscriptions(&symbol);
+                    let mut depths: Vec<usize> = subscriptions.iter().map(|(_, depth)| *depth).collect();
+                    depths.sort_unstable();
+                    depths.dedup();
+
                     let ob_state = ob_states
                         .entry(symbol.clone())
                         .or_insert_with(crate::channel_updates::OrderBookState::new);
+                    let notifications = ob_state.apply_orderbook_update(&event, &depths);
 
-                    if let Some(notification) = ob_state.apply_orderbook_update(&event) {
-                        let channel_name = notification.channel_name.clone();
-                        let bid_changes_count = notification.notification.bid_changes.len();
-                        let ask_changes_count = notification.notification.ask_changes.len();
-                        let trades_count = notification.notification.trades.len();
+                    // Missed a delta (or never had a snapshot), resync from the engine
+                    if ob_state.snapshot_due() {
+                        info!("Book for {} out of sync, requesting snapshot", symbol);
+                        if let Err(e) = order_sender.request_snapshot(&symbol) {
+                            error!("Failed to request snapshot for {}: {}", symbol, e);
+                        }
+                    }
+                    drop(ob_states);
+
+                    if notifications.is_empty() {
+                        info!("Event broadcaster: no subscribed tier changed for {} event", event_type);
+                        continue;
+                    }
+
+                    let cm = channel_manager.read().await;
+                    for (channel_name, depth) in subscriptions {
+                        let Some((_, notification)) = notifications.iter().find(|(d, _)| *d == depth) else {
+                            continue;
+                        };
 
                         info!("Broadcasting {} to channel {}: {} bid_changes, {} ask_changes, {} trades",
-                            event_type, channel_name, bid_changes_count, ask_changes_count, trades_count);
+                            event_type, channel_name, notification.bid_changes.len(),
+                            notification.ask_changes.len(), notification.trades.len());
 
-                        let json = match serde_json::to_string(&notification) {
+                        let json = match serde_json::to_string(&ChannelNotification {
+                            channel_name: channel_name.clone(),
+                            notification: notification.clone(),
+                        }) {
                             Ok(json) => json,
                             Err(e) => {
                                 error!("Failed to serialize notification: {}", e);
@@ -227,7 +306,6 @@ impl GatewayServer {
                             }
                         };
 
-                        let cm = channel_manager.read().await;
                         let subscribers = cm.get_subscribers(&channel_name);
                         info!("Channel {} has {} subscribers", channel_name, subscribers.len());
                         for client_id in subscribers {
@@ -235,8 +313,6 @@ impl GatewayServer {
                                 let _ = sender.send(json.clone());
                             }
                         }
-                    } else {
-                        info!("Event broadcaster: apply_orderbook_update returned None for {} event", event_type);
                     }
                 }
             }
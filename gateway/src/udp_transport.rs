@@ -1,16 +1,19 @@
-use rust_decimal::Decimal;
-use std::net::{SocketAddr, ToSocketAddrs};
-use std::str::FromStr;
+use std::net::{Ipv4Addr, SocketAddr, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use tokio::sync::mpsc;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
 use udp_proto::{
-    MessageType, ReceiverConfig, ReceivedMessage, SenderConfig, UdpReceiver, UdpSender,
-    binary::{decode_market_event, MarketEvent as BinaryMarketEvent, DeltaAction as BinaryDeltaAction, Side as BinarySide},
+    AsyncUdpReceiver, AuthConfig, AuthMode, MessageType, ProtocolError, ReceiverConfig, ReceiverStatsSnapshot, ReceivedMessage, SenderConfig, SessionEvent, SessionHandler, StreamKey, UdpSender,
+    binary::{
+        decode_market_event, MarketEvent as BinaryMarketEvent, DeltaAction as BinaryDeltaAction, Side as BinarySide,
+        OrderCommand as BinaryOrderCommand, OrderCommandEncoder, OrderType as BinaryOrderType, MarketPhase as BinaryMarketPhase,
+    },
 };
 
-use crate::events::{MarketEvent, OrderCommand, PriceLevel, LevelDelta, DeltaAction, Side};
+use crate::events::{AssetBalance, MarketEvent, OrderCommand, PriceLevel, LevelDelta, DeltaAction, Side, MarketPhase};
 
 /// Resolve a string address (hostname:port or ip:port) to SocketAddr
 /// Retries with exponential backoff for DNS resolution
@@ -49,39 +52,122 @@ fn resolve_addr(addr_str: &str) -> SocketAddr {
     }
 }
 
+/// How often the event stream latency is logged
+const LATENCY_REPORT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How long an order waits for room when the sender's channel is full,
+/// before the client is told it could not be submitted
+const ORDER_SEND_DEADLINE: Duration = Duration::from_millis(5);
+
 // Stream IDs
 const ORDER_STREAM_ID: u32 = 1;
 const EVENT_STREAM_ID: u32 = 2;
 
+/// Key ID of `ORDER_STREAM_KEY` (must match matching engine)
+const ORDER_STREAM_KEY_ID: u32 = 1;
+
 /// UDP sender for order commands (gateway -> matching engine)
 pub struct UdpOrderSender {
-    sender: UdpSender,
+    sender: Arc<UdpSender>,
+    encoder: Mutex<OrderCommandEncoder>,
 }
 
 impl UdpOrderSender {
-    pub fn new(target_addr: SocketAddr, bind_addr: SocketAddr) -> anyhow::Result<Self> {
+    /// With `auth` set, every packet is signed with the order stream key
+    pub fn new(target_addr: SocketAddr, bind_addr: SocketAddr, auth: Option<AuthConfig>) -> anyhow::Result<Self> {
+        let signed = auth.is_some();
         let config = SenderConfig {
             stream_id: ORDER_STREAM_ID,
             target_addr,
             max_batch_delay: Duration::from_micros(100),
             channel_capacity: 10_000,
             enable_heartbeats: true,
+            // Order latency shows in the matching engine's metrics
+            send_timestamps: true,
+            auth,
+            ..Default::default()
         };
 
         let sender = UdpSender::new(config, bind_addr)?;
-        info!("UDP order sender created: {} -> {}", bind_addr, target_addr);
+        info!("UDP order sender created: {} -> {} (signed: {})", bind_addr, target_addr, signed);
 
-        Ok(Self { sender })
+        Ok(Self {
+            sender: Arc::new(sender),
+            encoder: Mutex::new(OrderCommandEncoder::new()),
+        })
     }
 
+    /// Orders are never dropped, they wait for room or fail. Only a full
+    /// channel waits, on the blocking pool rather than a runtime thread.
     pub async fn send_order_command(&self, command: &OrderCommand) -> anyhow::Result<()> {
-        let json = serde_json::to_vec(command)?;
+        let binary_command = Self::convert_to_binary(command)?;
+        let (msg_type, data) = self.encode(&binary_command)?;
+        match self.sender.try_send(msg_type, data) {
+            Err(ProtocolError::ChannelFull) => {}
+            result => return result.map_err(|e| anyhow::anyhow!("UDP send error: {:?}", e)),
+        }
+
+        // The refused payload is gone, encode it again
+        let (msg_type, data) = self.encode(&binary_command)?;
+        let sender = self.sender.clone();
+        tokio::task::spawn_blocking(move || sender.send_timeout(msg_type, data, ORDER_SEND_DEADLINE))
+            .await?
+            .map_err(|e| anyhow::anyhow!("UDP send error: {:?}", e))
+    }
+
+    /// Ask the matching engine to publish a book snapshot on the event stream.
+    /// Refused without waiting when the sender's channel is full.
+    pub fn request_snapshot(&self, symbol: &str) -> anyhow::Result<()> {
+        let (msg_type, data) = self.encode(&BinaryOrderCommand::SnapshotRequest {
+            symbol: symbol.to_string(),
+        })?;
 
         self.sender
-            .try_send(MessageType::OrderNew, json)
-            .map_err(|e| anyhow::anyhow!("UDP send error: {:?}", e))?;
+            .try_send(msg_type, data)
+            .map_err(|e| anyhow::anyhow!("UDP send error: {:?}", e))
+    }
 
-        Ok(())
+    fn encode(&self, binary_command: &BinaryOrderCommand) -> anyhow::Result<(MessageType, Vec<u8>)> {
+        let mut encoder = self
+            .encoder
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Order encoder lock poisoned"))?;
+        Ok((binary_command.message_type(), encoder.encode(binary_command).to_vec()))
+    }
+
+    /// Convert an order command to its wire representation
+    fn convert_to_binary(command: &OrderCommand) -> anyhow::Result<BinaryOrderCommand> {
+        Ok(match command {
+            OrderCommand::PlaceOrder {
+                order_id,
+                side,
+                order_type,
+                price,
+                quantity,
+                user_id,
+            } => {
+                let order_type = match order_type.to_lowercase().as_str() {
+                    "limit" => BinaryOrderType::Limit,
+                    "market" => BinaryOrderType::Market,
+                    other => anyhow::bail!("Unknown order type: {}", other),
+                };
+                BinaryOrderCommand::New {
+                    order_id: *order_id,
+                    user_id: *user_id,
+                    side: match side {
+                        Side::Bid => BinarySide::Bid,
+                        Side::Ask => BinarySide::Ask,
+                    },
+                    order_type,
+                    price: *price,
+                    quantity: *quantity,
+                }
+            }
+            OrderCommand::CancelOrder { order_id, user_id } => BinaryOrderCommand::Cancel {
+                order_id: *order_id,
+                user_id: *user_id,
+            },
+        })
     }
 
     pub fn stats(&self) -> udp_proto::SenderStatsSnapshot {
@@ -95,56 +181,108 @@ pub struct UdpEventReceiver {
 }
 
 impl UdpEventReceiver {
+    /// `on_loss` runs when events were lost for good or the matching engine
+    /// restarted, to resync from snapshots. With `multicast_group` set the
+    /// events are taken from that group.
+    /// Must be called within a tokio runtime.
     pub fn new(
         bind_addr: SocketAddr,
+        multicast_group: Option<Ipv4Addr>,
+        on_loss: impl Fn() + Send + Sync + 'static,
     ) -> anyhow::Result<(Self, mpsc::Receiver<MarketEvent>)> {
+        let engine_restarted = Arc::new(AtomicBool::new(false));
         let config = ReceiverConfig {
             stream_id: EVENT_STREAM_ID,
             channel_capacity: 10_000,
             recv_timeout: Duration::from_millis(10),
             stream_timeout: Duration::from_millis(500),
+            multicast_group,
+            on_session: Some(SessionHandler::new({
+                let engine_restarted = engine_restarted.clone();
+                move |event| {
+                    if let SessionEvent::Restarted { peer, .. } = event {
+                        warn!("Matching engine at {} restarted", peer);
+                        engine_restarted.store(true, Ordering::Relaxed);
+                    }
+                }
+            })),
+            ..Default::default()
         };
 
-        let receiver = UdpReceiver::new(config, bind_addr)?;
-        info!("UDP event receiver created on {}", bind_addr);
+        let receiver = AsyncUdpReceiver::new(config, bind_addr)?;
+        match multicast_group {
+            Some(group) => info!("UDP event receiver created on {}, joined {}", bind_addr, group),
+            None => info!("UDP event receiver created on {}", bind_addr),
+        }
 
         // Create channel for forwarding events
         let (event_tx, event_rx) = mpsc::channel(10_000);
 
         // Spawn receiver task
         let tx = event_tx.clone();
-        std::thread::spawn(move || {
-            Self::receiver_loop(receiver, tx);
-        });
+        tokio::spawn(Self::receiver_loop(receiver, tx, engine_restarted, Arc::new(on_loss)));
 
         Ok((Self { event_tx }, event_rx))
     }
 
-    fn receiver_loop(receiver: UdpReceiver, event_tx: mpsc::Sender<MarketEvent>) {
+    async fn receiver_loop(
+        mut receiver: AsyncUdpReceiver,
+        event_tx: mpsc::Sender<MarketEvent>,
+        engine_restarted: Arc<AtomicBool>,
+        on_loss: Arc<impl Fn() + Send + Sync + 'static>,
+    ) {
         info!("UDP event receiver loop started");
+        let mut loss_check = tokio::time::interval(Duration::from_millis(100));
+        let mut latency_report = tokio::time::interval(LATENCY_REPORT_INTERVAL);
 
         loop {
-            match receiver.recv_timeout(Duration::from_millis(100)) {
-                Ok(Some(msg)) => {
-                    if let Err(e) = Self::handle_message(&msg, &event_tx) {
-                        error!("Failed to handle UDP message: {}", e);
+            tokio::select! {
+                msg = receiver.recv() => {
+                    match Self::handle_message(&msg) {
+                        Ok(event) => {
+                            if event_tx.send(event).await.is_err() {
+                                error!("Market event channel closed");
+                                break;
+                            }
+                        }
+                        Err(e) => error!("Failed to handle UDP message: {}", e),
                     }
                 }
-                Ok(None) => {
-                    // Timeout, continue
-                }
-                Err(e) => {
-                    error!("UDP receiver error: {:?}", e);
-                    break;
+                _ = loss_check.tick() => {}
+                _ = latency_report.tick() => Self::report_latency(&receiver.stats()),
+            }
+
+            // Books the engine rebuilt after a restart differ from ours
+            let restarted = engine_restarted.swap(false, Ordering::Relaxed);
+            if receiver.needs_snapshot() || restarted {
+                if !restarted {
+                    warn!("Market events lost beyond retransmission, resyncing from snapshots");
                 }
+                receiver.clear_gaps();
+                let on_loss = on_loss.clone();
+                tokio::task::spawn_blocking(move || on_loss());
             }
         }
     }
 
-    fn handle_message(
-        msg: &ReceivedMessage,
-        event_tx: &mpsc::Sender<MarketEvent>,
-    ) -> anyhow::Result<()> {
+    /// Log one-way latency of the event stream, timestamped by the engine
+    fn report_latency(stats: &ReceiverStatsSnapshot) {
+        let (latency, jitter) = (&stats.latency, &stats.jitter);
+        if latency.count == 0 {
+            return;
+        }
+        let ms = |d: Option<Duration>| d.unwrap_or_default().as_secs_f64() * 1000.0;
+        info!(
+            "Event stream latency over {} packets: p50 {:.3}ms p99 {:.3}ms max {:.3}ms, jitter p99 {:.3}ms",
+            latency.count,
+            ms(latency.quantile(0.5)),
+            ms(latency.quantile(0.99)),
+            ms(latency.max()),
+            ms(jitter.quantile(0.99)),
+        );
+    }
+
+    fn handle_message(msg: &ReceivedMessage) -> anyhow::Result<MarketEvent> {
         // Decode FlatBuffers payload
         let binary_event = decode_market_event(&msg.payload)
             .map_err(|e| anyhow::anyhow!("FlatBuffer decode error: {}", e))?;
@@ -165,13 +303,20 @@ impl UdpEventReceiver {
             MarketEvent::OrderFilled { order_id } => {
                 info!("Received OrderFilled event via UDP: order_id={}", order_id);
             }
+            MarketEvent::OrderRejected { order_id, reason } => {
+                info!("Received OrderRejected event via UDP: order_id={}, reason={}", order_id, reason);
+            }
+            MarketEvent::TradeBust { trade_id, symbol, reason, .. } => {
+                info!("Received TradeBust event via UDP: trade_id={}, symbol={}, reason={}",
+                    trade_id, symbol, reason);
+            }
+            MarketEvent::PositionUpdate { user_id, balances, .. } => {
+                info!("Received PositionUpdate event via UDP: user_id={}, assets={}", user_id, balances.len());
+            }
             _ => {}
         }
 
-        // Send to channel (blocking in sync context)
-        event_tx.blocking_send(event)?;
-
-        Ok(())
+        Ok(event)
     }
 
     fn convert_from_binary(event: BinaryMarketEvent) -> anyhow::Result<MarketEvent> {
@@ -187,8 +332,8 @@ impl UdpEventReceiver {
                 symbol,
                 buy_order_id,
                 sell_order_id,
-                price: Decimal::from_str(&price.to_string()).unwrap_or_default(),
-                quantity: Decimal::from_str(&quantity.to_string()).unwrap_or_default(),
+                price,
+                quantity,
                 timestamp,
             }),
             BinaryMarketEvent::OrderBookSnapshot {
@@ -202,15 +347,15 @@ impl UdpEventReceiver {
                 bids: bids
                     .into_iter()
                     .map(|l| PriceLevel {
-                        price: Decimal::from_str(&l.price.to_string()).unwrap_or_default(),
-                        quantity: Decimal::from_str(&l.quantity.to_string()).unwrap_or_default(),
+                        price: l.price,
+                        quantity: l.quantity,
                     })
                     .collect(),
                 asks: asks
                     .into_iter()
                     .map(|l| PriceLevel {
-                        price: Decimal::from_str(&l.price.to_string()).unwrap_or_default(),
-                        quantity: Decimal::from_str(&l.quantity.to_string()).unwrap_or_default(),
+                        price: l.price,
+                        quantity: l.quantity,
                     })
                     .collect(),
             }),
@@ -233,8 +378,8 @@ impl UdpEventReceiver {
                             BinarySide::Bid => Side::Bid,
                             BinarySide::Ask => Side::Ask,
                         },
-                        price: Decimal::from_str(&d.price.to_string()).unwrap_or_default(),
-                        quantity: Decimal::from_str(&d.quantity.to_string()).unwrap_or_default(),
+                        price: d.price,
+                        quantity: d.quantity,
                     })
                     .collect(),
             }),
@@ -243,11 +388,72 @@ impl UdpEventReceiver {
                 filled_quantity,
             } => Ok(MarketEvent::OrderCancelled {
                 order_id,
-                filled_quantity: Decimal::from_str(&filled_quantity.to_string()).unwrap_or_default(),
+                filled_quantity,
             }),
             BinaryMarketEvent::OrderFilled { order_id } => Ok(MarketEvent::OrderFilled {
                 order_id,
             }),
+            BinaryMarketEvent::OrderRejected { order_id, reason } => Ok(MarketEvent::OrderRejected {
+                order_id,
+                reason,
+            }),
+            BinaryMarketEvent::TradeBust {
+                symbol,
+                trade_id,
+                buy_order_id,
+                sell_order_id,
+                buyer_id,
+                seller_id,
+                price,
+                quantity,
+                timestamp,
+                reason,
+            } => Ok(MarketEvent::TradeBust {
+                symbol,
+                trade_id,
+                buy_order_id,
+                sell_order_id,
+                buyer_id,
+                seller_id,
+                price,
+                quantity,
+                timestamp,
+                reason,
+            }),
+            BinaryMarketEvent::PositionUpdate {
+                user_id,
+                balances,
+                timestamp,
+            } => Ok(MarketEvent::PositionUpdate {
+                user_id,
+                balances: balances
+                    .into_iter()
+                    .map(|b| AssetBalance {
+                        asset: b.asset,
+                        available: b.available,
+                        locked: b.locked,
+                    })
+                    .collect(),
+                timestamp,
+            }),
+            BinaryMarketEvent::MarketStatus {
+                symbol,
+                phase,
+                reason,
+                timestamp,
+            } => Ok(MarketEvent::MarketStatus {
+                symbol,
+                phase: match phase {
+                    BinaryMarketPhase::PreOpen => MarketPhase::PreOpen,
+                    BinaryMarketPhase::Open => MarketPhase::Open,
+                    BinaryMarketPhase::Auction => MarketPhase::Auction,
+                    BinaryMarketPhase::Halted => MarketPhase::Halted,
+                    BinaryMarketPhase::Closed => MarketPhase::Closed,
+                    BinaryMarketPhase::Delisted => MarketPhase::Delisted,
+                },
+                reason,
+                timestamp,
+            }),
         }
     }
 }
@@ -261,6 +467,11 @@ pub struct UdpTransportConfig {
     pub order_sender_bind: SocketAddr,
     /// Local address to receive market events
     pub event_receiver_bind: SocketAddr,
+    /// Multicast group the matching engine publishes events to, if any
+    pub event_multicast_group: Option<Ipv4Addr>,
+    /// Pre-shared key of the order stream, the matching engine refuses
+    /// unsigned orders once it has one
+    pub order_stream_auth: Option<AuthConfig>,
 }
 
 impl Default for UdpTransportConfig {
@@ -269,6 +480,8 @@ impl Default for UdpTransportConfig {
             matching_engine_addr: "127.0.0.1:9100".parse().unwrap(),
             order_sender_bind: "127.0.0.1:9102".parse().unwrap(),
             event_receiver_bind: "127.0.0.1:9101".parse().unwrap(),
+            event_multicast_group: None,
+            order_stream_auth: None,
         }
     }
 }
@@ -287,10 +500,22 @@ impl UdpTransportConfig {
             .unwrap_or_else(|_| "0.0.0.0:9101".to_string());
         let event_receiver_bind = resolve_addr(&event_receiver_bind_str);
 
+        let event_multicast_group = std::env::var("EVENT_MULTICAST_GROUP")
+            .ok()
+            .filter(|g| !g.is_empty())
+            .map(|g| g.parse().unwrap_or_else(|e| panic!("Invalid EVENT_MULTICAST_GROUP '{}': {}", g, e)));
+
+        let order_stream_auth = std::env::var("ORDER_STREAM_KEY")
+            .ok()
+            .filter(|k| !k.is_empty())
+            .map(|k| AuthConfig::new(AuthMode::Sign, StreamKey::new(ORDER_STREAM_KEY_ID, k.into_bytes())));
+
         Self {
             matching_engine_addr,
             order_sender_bind,
             event_receiver_bind,
+            event_multicast_group,
+            order_stream_auth,
         }
     }
 }
@@ -8,11 +8,13 @@ use tokio::sync::broadcast;
 use tracing::{info, warn, error};
 use uuid::Uuid;
 
-use crate::channel_updates::OrderBookState;
+use crate::channel_updates::{BookChannel, OrderBookState};
 use crate::events::{MarketEvent, OrderCommand, Side};
 use crate::proxy::ProxyState;
 use crate::udp_transport::UdpOrderSender;
 
+/// Private channel carrying the subscriber's own position updates, needs auth
+pub const POSITIONS_CHANNEL: &str = "positions";
 
 /// All possible client message types
 #[derive(Debug, Clone, Deserialize)]
@@ -190,15 +192,19 @@ pub struct ChannelManager {
     authenticated_users: HashMap<u64, Uuid>,
     /// Reverse lookup: user_id -> client_id (for targeted events)
     user_to_client: HashMap<Uuid, u64>,
-    /// Order ownership: order_id -> user_id (for targeted OrderFilled/OrderCancelled events)
+    /// Order ownership: order_id -> user_id (for targeted OrderFilled/OrderCancelled/OrderRejected events)
     order_owners: HashMap<Uuid, Uuid>,
     /// Bot state
     bot_client_id: Option<u64>,
     last_bot_status: Option<Vec<StrategyStatus>>,
+    /// Book depths clients may subscribe to
+    book_depths: Vec<usize>,
+    /// Last `market_status` message per symbol, replayed to new clients
+    market_statuses: HashMap<String, String>,
 }
 
 impl ChannelManager {
-    pub fn new() -> Self {
+    pub fn new(book_depths: Vec<usize>) -> Self {
         Self {
             subscribers: HashMap::new(),
             client_senders: HashMap::new(),
@@ -207,9 +213,26 @@ impl ChannelManager {
             order_owners: HashMap::new(),
             bot_client_id: None,
             last_bot_status: None,
+            book_depths,
+            market_statuses: HashMap::new(),
         }
     }
 
+    pub fn book_depths(&self) -> &[usize] {
+        &self.book_depths
+    }
+
+    /// Subscribed book channels of a symbol with their depth
+    pub fn book_subscriptions(&self, symbol: &str) -> Vec<(String, usize)> {
+        self.subscribers
+            .keys()
+            .filter_map(|channel| {
+                let book = BookChannel::parse(channel)?;
+                (book.symbol == symbol).then(|| (channel.clone(), book.depth))
+            })
+            .collect()
+    }
+
     pub fn register_bot(&mut self, client_id: u64, sender: broadcast::Sender<String>) {
         self.bot_client_id = Some(client_id);
         self.client_senders.insert(client_id, sender);
@@ -312,9 +335,55 @@ impl ChannelManager {
             .unwrap_or_default()
     }
 
+    pub fn is_subscribed(&self, client_id: u64, channel: &str) -> bool {
+        self.subscribers
+            .get(channel)
+            .is_some_and(|subs| subs.contains(&client_id))
+    }
+
+    /// Why `client_id` may not subscribe to `channel`, None when it may.
+    /// Only authenticated clients get the positions channel.
+    pub fn subscribe_refusal(&self, client_id: u64, channel: &str) -> Option<String> {
+        (channel == POSITIONS_CHANNEL && self.get_user_id(client_id).is_none())
+            .then(|| format!("Authentication required for channel {}", POSITIONS_CHANNEL))
+    }
+
+    /// Whether a position update of `user_id` goes to `client_id`: only to
+    /// the user's own connection, once it subscribed to the positions channel
+    pub fn routes_position_update(&self, client_id: u64, user_id: &Uuid) -> bool {
+        self.get_client_for_user(user_id) == Some(client_id) && self.is_subscribed(client_id, POSITIONS_CHANNEL)
+    }
+
     pub fn get_sender(&self, client_id: u64) -> Option<&broadcast::Sender<String>> {
         self.client_senders.get(&client_id)
     }
+
+    /// Send a market status to every connected client, subscribed or not,
+    /// and keep it for clients connecting later
+    pub fn broadcast_market_status(&mut self, symbol: &str, json: String) {
+        for sender in self.client_senders.values() {
+            let _ = sender.send(json.clone());
+        }
+        self.market_statuses.insert(symbol.to_string(), json);
+    }
+
+    /// Send a message to every connected client. A logged-in user gets the
+    /// message `personalize` returns for them instead, if any.
+    pub fn broadcast_to_all(&self, json: &str, personalize: impl Fn(Uuid) -> Option<String>) {
+        for (client_id, sender) in &self.client_senders {
+            let message = self
+                .authenticated_users
+                .get(client_id)
+                .and_then(|user_id| personalize(*user_id))
+                .unwrap_or_else(|| json.to_string());
+            let _ = sender.send(message);
+        }
+    }
+
+    /// Last known status of every market
+    pub fn market_statuses(&self) -> impl Iterator<Item = &String> {
+        self.market_statuses.values()
+    }
 }
 
 pub async fn handle_websocket_connection(
@@ -332,6 +401,10 @@ pub async fn handle_websocket_connection(
     {
         let mut cm = channel_manager.write().await;
         cm.client_senders.insert(client_id, tx.clone());
+        // Tell the new client which markets are open before anything else
+        for status in cm.market_statuses() {
+            let _ = tx.send(status.clone());
+        }
     }
 
     let channel_manager_clone = channel_manager.clone();
@@ -341,14 +414,17 @@ pub async fn handle_websocket_connection(
     let client_id_clone = client_id;
     let tx_for_recv = tx.clone();
 
-    // Forward targeted events (OrderFilled, OrderCancelled) only to the order owner
+    // Forward targeted events (OrderFilled, OrderCancelled, OrderRejected) only to the order owner,
+    // position updates only to the user's own connection
     let tx_for_events = tx.clone();
     tokio::spawn(async move {
         while let Ok(event) = event_rx.recv().await {
             let cm = channel_manager_for_events.read().await;
 
             match &event {
-                MarketEvent::OrderFilled { order_id } | MarketEvent::OrderCancelled { order_id, .. } => {
+                MarketEvent::OrderFilled { order_id }
+                | MarketEvent::OrderCancelled { order_id, .. }
+                | MarketEvent::OrderRejected { order_id, .. } => {
                     // Only send to the order owner
                     if let Some(user_id) = cm.get_order_owner(order_id) {
                         if let Some(owner_client_id) = cm.get_client_for_user(&user_id) {
@@ -360,7 +436,12 @@ pub async fn handle_websocket_connection(
                         }
                     }
                 }
-                // Other events (Fill, OrderBookSnapshot, OrderBookDelta) are handled by the broadcaster
+                MarketEvent::PositionUpdate { user_id, .. } if cm.routes_position_update(client_id, user_id) => {
+                    if let Ok(json) = serde_json::to_string(&event) {
+                        let _ = tx_for_events.send(json);
+                    }
+                }
+                // Other events (Fill, OrderBookSnapshot, OrderBookDelta, MarketStatus, TradeBust) are handled by the broadcaster
                 _ => {}
             }
         }
@@ -409,20 +490,43 @@ pub async fn handle_websocket_connection(
                 }
 
                 Ok(ClientMessage::Subscribe { channel }) => {
-                    let symbol = channel
-                        .strip_prefix("book.")
-                        .and_then(|s| s.split('.').next())
-                        .unwrap_or("KCN/EUR");
-
-                    let ob_states = orderbook_states_clone.read().await;
-                    if let Some(ob_state) = ob_states.get(symbol) {
-                        let snapshot = ob_state.get_orderbook_snapshot(symbol);
+                    let refusal = channel_manager_clone.read().await.subscribe_refusal(client_id_clone, &channel);
+                    if let Some(message) = refusal {
+                        let response = ServerMessage::Error { message };
+                        if let Ok(json) = serde_json::to_string(&response) {
+                            let _ = tx_for_recv.send(json);
+                        }
+                        continue;
+                    }
+
+                    if let Some(book) = BookChannel::parse(&channel) {
+                        let book_depths = channel_manager_clone.read().await.book_depths().to_vec();
+                        if !book_depths.contains(&book.depth) {
+                            let response = ServerMessage::Error {
+                                message: format!("Unsupported book depth {}, available: {:?}", book.depth, book_depths),
+                            };
+                            if let Ok(json) = serde_json::to_string(&response) {
+                                let _ = tx_for_recv.send(json);
+                            }
+                            continue;
+                        }
+
+                        let mut ob_states = orderbook_states_clone.write().await;
+                        let ob_state = ob_states
+                            .entry(book.symbol.clone())
+                            .or_insert_with(OrderBookState::new);
+                        // First subscriber or a book out of sync, ask the engine for the full picture
+                        if ob_state.snapshot_due() {
+                            if let Err(e) = order_sender_clone.request_snapshot(&book.symbol) {
+                                warn!("Failed to request snapshot for {}: {}", book.symbol, e);
+                            }
+                        }
+                        let snapshot = ob_state.get_orderbook_snapshot(&channel, book.depth);
                         if let Ok(json) = serde_json::to_string(&snapshot) {
                             let _ = tx_for_recv.send(json);
-                            info!("Sent orderbook snapshot to client {} for {}", client_id_clone, symbol);
+                            info!("Sent orderbook snapshot to client {} for {}", client_id_clone, book.symbol);
                         }
                     }
-                    drop(ob_states);
 
                     let mut cm = channel_manager_clone.write().await;
                     cm.subscribe(client_id_clone, channel, tx_for_recv.clone());
@@ -733,3 +837,39 @@ async fn cancel_order_in_accounts(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn client_sender() -> broadcast::Sender<String> {
+        broadcast::channel(1).0
+    }
+
+    #[test]
+    fn test_anonymous_positions_subscribe_refused() {
+        let mut cm = ChannelManager::new(vec![10]);
+        assert!(cm.subscribe_refusal(1, POSITIONS_CHANNEL).is_some());
+        assert!(cm.subscribe_refusal(1, "book.KCN/EUR.none.10.500ms").is_none());
+
+        cm.authenticate_client(1, Uuid::new_v4());
+        assert!(cm.subscribe_refusal(1, POSITIONS_CHANNEL).is_none());
+    }
+
+    #[test]
+    fn test_position_update_reaches_owner_only() {
+        let mut cm = ChannelManager::new(vec![10]);
+        let (owner, other) = (Uuid::new_v4(), Uuid::new_v4());
+        cm.authenticate_client(1, owner);
+        cm.authenticate_client(2, other);
+
+        // Not before the owner subscribed
+        assert!(!cm.routes_position_update(1, &owner));
+
+        cm.subscribe(1, POSITIONS_CHANNEL.to_string(), client_sender());
+        cm.subscribe(2, POSITIONS_CHANNEL.to_string(), client_sender());
+        assert!(cm.routes_position_update(1, &owner));
+        assert!(!cm.routes_position_update(2, &owner));
+        assert!(cm.routes_position_update(2, &other));
+    }
+}
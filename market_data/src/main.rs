@@ -228,6 +228,19 @@ struct TradeData {
     #[allow(dead_code)]
     side: String,
     timestamp: u64,
+    buy_order_id: Option<String>,
+    sell_order_id: Option<String>,
+}
+
+/// An operator reversed a trade, its bars need rebuilding
+#[derive(Debug, Deserialize)]
+struct TradeBustNotification {
+    #[serde(rename = "type")]
+    kind: String,
+    symbol: String,
+    buy_order_id: String,
+    sell_order_id: String,
+    timestamp: u64,
 }
 
 async fn start_websocket_consumer(gateway_ws_url: &str, aggregator: Arc<OhlcvAggregator>) {
@@ -273,10 +286,17 @@ async fn start_websocket_consumer(gateway_ws_url: &str, aggregator: Arc<OhlcvAgg
                                         let quantity =
                                             rust_decimal::Decimal::try_from(trade.quantity)
                                                 .unwrap_or_default();
+                                        let fill_id = match (&trade.buy_order_id, &trade.sell_order_id) {
+                                            (Some(buy), Some(sell)) => {
+                                                Some(ohlcv::fill_id(buy, sell, trade.timestamp))
+                                            }
+                                            _ => None,
+                                        };
 
                                         if let Err(e) = aggregator
                                             .process_trade(
                                                 "KCN/EUR",
+                                                fill_id.as_deref(),
                                                 price,
                                                 quantity,
                                                 trade.timestamp,
@@ -286,6 +306,22 @@ async fn start_websocket_consumer(gateway_ws_url: &str, aggregator: Arc<OhlcvAgg
                                             error!("Failed to process trade for OHLCV: {}", e);
                                         }
                                     }
+                                } else if let Ok(bust) =
+                                    serde_json::from_str::<TradeBustNotification>(&text)
+                                {
+                                    if bust.kind == "trade_bust" {
+                                        let fill_id = ohlcv::fill_id(
+                                            &bust.buy_order_id,
+                                            &bust.sell_order_id,
+                                            bust.timestamp,
+                                        );
+                                        if let Err(e) = aggregator
+                                            .process_bust(&bust.symbol, &fill_id, bust.timestamp)
+                                            .await
+                                        {
+                                            error!("Failed to rebuild OHLCV after bust: {}", e);
+                                        }
+                                    }
                                 }
                                 // Ignore other message types (snapshots, etc.)
                             }
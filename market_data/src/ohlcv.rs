@@ -1,7 +1,7 @@
 use chrono::{DateTime, Utc};
 use rust_decimal::Decimal;
 use sqlx::PgPool;
-use tracing::error;
+use tracing::{error, info, warn};
 
 const INTERVALS: &[(&str, i64)] = &[
     ("1m", 60),
@@ -12,6 +12,11 @@ const INTERVALS: &[(&str, i64)] = &[
     ("1d", 86400),
 ];
 
+/// Same key the accounts service settles a fill under
+pub fn fill_id(buy_order_id: &str, sell_order_id: &str, timestamp_ms: u64) -> String {
+    format!("{}-{}-{}", buy_order_id, sell_order_id, timestamp_ms)
+}
+
 pub struct OhlcvAggregator {
     pool: PgPool,
 }
@@ -21,9 +26,12 @@ impl OhlcvAggregator {
         Self { pool }
     }
 
+    /// Add a trade to the bars. Trades carrying a fill ID are recorded so a
+    /// later bust can rebuild the bars, and are counted only once.
     pub async fn process_trade(
         &self,
         symbol: &str,
+        fill_id: Option<&str>,
         price: Decimal,
         quantity: Decimal,
         timestamp_ms: u64,
@@ -31,6 +39,28 @@ impl OhlcvAggregator {
         let timestamp = DateTime::from_timestamp_millis(timestamp_ms as i64)
             .unwrap_or_else(Utc::now);
 
+        if let Some(fill_id) = fill_id {
+            let recorded = sqlx::query(
+                r#"
+                INSERT INTO market_trades (fill_id, symbol, price, quantity, traded_at)
+                VALUES ($1, $2, $3, $4, $5)
+                ON CONFLICT (fill_id) DO NOTHING
+                "#,
+            )
+            .bind(fill_id)
+            .bind(symbol)
+            .bind(price)
+            .bind(quantity)
+            .bind(timestamp)
+            .execute(&self.pool)
+            .await?;
+
+            if recorded.rows_affected() == 0 {
+                // Seen before, e.g. replayed after a reconnect
+                return Ok(());
+            }
+        }
+
         for (interval_name, interval_secs) in INTERVALS {
             let open_time = Self::truncate_to_interval(timestamp, *interval_secs);
 
@@ -48,6 +78,85 @@ impl OhlcvAggregator {
         Ok(())
     }
 
+    /// Take a busted trade out of the bars it was counted in. Each bar is
+    /// rebuilt from the recorded trades left in it, or removed when none are.
+    pub async fn process_bust(&self, symbol: &str, fill_id: &str, timestamp_ms: u64) -> anyhow::Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        let busted = sqlx::query("UPDATE market_trades SET busted = TRUE WHERE fill_id = $1 AND NOT busted")
+            .bind(fill_id)
+            .execute(&mut *tx)
+            .await?;
+        if busted.rows_affected() == 0 {
+            warn!("Bust of unknown or already busted trade {}, bars left as they are", fill_id);
+            return Ok(());
+        }
+
+        let timestamp = DateTime::from_timestamp_millis(timestamp_ms as i64)
+            .unwrap_or_else(Utc::now);
+
+        for (interval_name, interval_secs) in INTERVALS {
+            let open_time = Self::truncate_to_interval(timestamp, *interval_secs);
+            let close_time = open_time + chrono::Duration::seconds(*interval_secs);
+
+            let trades: Vec<(Decimal, Decimal)> = sqlx::query_as(
+                r#"
+                SELECT price, quantity FROM market_trades
+                WHERE symbol = $1 AND NOT busted AND traded_at >= $2 AND traded_at < $3
+                ORDER BY traded_at, id
+                "#,
+            )
+            .bind(symbol)
+            .bind(open_time)
+            .bind(close_time)
+            .fetch_all(&mut *tx)
+            .await?;
+
+            let Some(&(open, _)) = trades.first() else {
+                sqlx::query("DELETE FROM ohlcv WHERE symbol = $1 AND interval = $2 AND open_time = $3")
+                    .bind(symbol)
+                    .bind(interval_name)
+                    .bind(open_time)
+                    .execute(&mut *tx)
+                    .await?;
+                continue;
+            };
+            let close = trades[trades.len() - 1].0;
+            let high = trades.iter().map(|(price, _)| *price).max().unwrap_or(open);
+            let low = trades.iter().map(|(price, _)| *price).min().unwrap_or(open);
+            let volume: Decimal = trades.iter().map(|(_, quantity)| *quantity).sum();
+
+            sqlx::query(
+                r#"
+                INSERT INTO ohlcv (symbol, interval, open_time, open, high, low, close, volume, trade_count)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+                ON CONFLICT (symbol, interval, open_time) DO UPDATE SET
+                    open = EXCLUDED.open,
+                    high = EXCLUDED.high,
+                    low = EXCLUDED.low,
+                    close = EXCLUDED.close,
+                    volume = EXCLUDED.volume,
+                    trade_count = EXCLUDED.trade_count
+                "#,
+            )
+            .bind(symbol)
+            .bind(interval_name)
+            .bind(open_time)
+            .bind(open)
+            .bind(high)
+            .bind(low)
+            .bind(close)
+            .bind(volume)
+            .bind(trades.len() as i32)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        info!("Rebuilt {} bars after bust of trade {}", symbol, fill_id);
+        Ok(())
+    }
+
     fn truncate_to_interval(timestamp: DateTime<Utc>, interval_secs: i64) -> DateTime<Utc> {
         let ts_secs = timestamp.timestamp();
         let truncated_secs = (ts_secs / interval_secs) * interval_secs;
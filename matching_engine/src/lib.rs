@@ -231,6 +231,73 @@ impl OrderBook {
             .collect()
     }
 
+    /// Returns resting bid orders grouped by price level (best bid first),
+    /// in time priority within each level
+    pub fn get_bid_orders(&self, max_levels: usize) -> Vec<(Price, Vec<Order>)> {
+        self.bids
+            .iter()
+            .rev()
+            .take(max_levels)
+            .map(|(price, level)| (*price, level.orders.iter().cloned().collect()))
+            .collect()
+    }
+
+    /// Returns resting ask orders grouped by price level (best ask first),
+    /// in time priority within each level
+    pub fn get_ask_orders(&self, max_levels: usize) -> Vec<(Price, Vec<Order>)> {
+        self.asks
+            .iter()
+            .take(max_levels)
+            .map(|(price, level)| (*price, level.orders.iter().cloned().collect()))
+            .collect()
+    }
+
+    /// Number of orders resting on the book
+    pub fn order_count(&self) -> usize {
+        self.orders.len()
+    }
+
+    /// Number of price levels on one side of the book
+    pub fn level_count(&self, side: Side) -> usize {
+        match side {
+            Side::Bid => self.bids.len(),
+            Side::Ask => self.asks.len(),
+        }
+    }
+
+    /// Hash of every resting order in priority order (id, side, price,
+    /// remaining quantity). Two books that went through the same commands
+    /// hash the same on any machine, so replicas can compare their state.
+    pub fn state_hash(&self) -> u64 {
+        // FNV-1a, std's hashers are not guaranteed stable across builds
+        const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+        const PRIME: u64 = 0x0000_0100_0000_01b3;
+        let mut hash = OFFSET;
+        let mut write = |bytes: &[u8]| {
+            for byte in bytes {
+                hash ^= u64::from(*byte);
+                hash = hash.wrapping_mul(PRIME);
+            }
+        };
+
+        let levels = self
+            .bids
+            .iter()
+            .rev()
+            .map(|level| (Side::Bid, level))
+            .chain(self.asks.iter().map(|level| (Side::Ask, level)));
+        for (side, (price, level)) in levels {
+            for order in &level.orders {
+                write(&[side as u8]);
+                write(order.id.as_bytes());
+                // 1.0 and 1.00 are the same price
+                write(&price.normalize().serialize());
+                write(&order.remaining_quantity.normalize().serialize());
+            }
+        }
+        hash
+    }
+
     fn match_order(&mut self, order: &mut Order, fills: &mut Vec<Fill>, completed_orders: &mut Vec<OrderId>) {
         let opposite_book = match order.side {
             Side::Bid => &mut self.asks,
@@ -895,4 +962,58 @@ mod tests {
         assert_eq!(ob.best_bid(), Some(Decimal::from(100)));
         assert_eq!(ob.quantity_at_price(Side::Bid, Decimal::from(100)), Decimal::from(10));
     }
+
+    #[test]
+    fn test_get_level_orders() {
+        let mut ob = OrderBook::new();
+
+        let first = new_id();
+        let second = new_id();
+        let deeper = new_id();
+        ob.add_limit_order(first, Side::Bid, Decimal::from(100), Decimal::from(10));
+        ob.add_limit_order(second, Side::Bid, Decimal::from(100), Decimal::from(5));
+        ob.add_limit_order(deeper, Side::Bid, Decimal::from(99), Decimal::from(1));
+        ob.add_limit_order(new_id(), Side::Ask, Decimal::from(101), Decimal::from(2));
+
+        // Partially fill the first order in the queue
+        ob.add_limit_order(new_id(), Side::Ask, Decimal::from(100), Decimal::from(4));
+
+        let bids = ob.get_bid_orders(10);
+        assert_eq!(bids.len(), 2);
+        assert_eq!(bids[0].0, Decimal::from(100));
+        let ids: Vec<OrderId> = bids[0].1.iter().map(|o| o.id).collect();
+        assert_eq!(ids, vec![first, second]);
+        assert_eq!(bids[0].1[0].remaining_quantity, Decimal::from(6));
+        assert_eq!(bids[1].1[0].id, deeper);
+
+        assert_eq!(ob.get_bid_orders(1).len(), 1);
+        assert_eq!(ob.get_ask_orders(10).len(), 1);
+        assert_eq!(ob.order_count(), 4);
+        assert_eq!(ob.level_count(Side::Bid), 2);
+        assert_eq!(ob.level_count(Side::Ask), 1);
+    }
+
+    #[test]
+    fn test_state_hash_follows_book_state() {
+        let (a, b) = (new_id(), new_id());
+        let mut ob = OrderBook::new();
+        let mut replica = OrderBook::new();
+        assert_eq!(ob.state_hash(), replica.state_hash());
+
+        for book in [&mut ob, &mut replica] {
+            book.add_limit_order(a, Side::Bid, Decimal::from(100), Decimal::from(10));
+            book.add_limit_order(b, Side::Bid, Decimal::from(100), Decimal::from(5));
+        }
+        assert_eq!(ob.state_hash(), replica.state_hash());
+
+        // A partial fill changes the remaining quantity
+        ob.add_limit_order(new_id(), Side::Ask, Decimal::from(100), Decimal::from(1));
+        assert_ne!(ob.state_hash(), replica.state_hash());
+
+        // Same orders in a different time priority
+        let mut reordered = OrderBook::new();
+        reordered.add_limit_order(b, Side::Bid, Decimal::from(100), Decimal::from(5));
+        reordered.add_limit_order(a, Side::Bid, Decimal::from(100), Decimal::from(10));
+        assert_ne!(reordered.state_hash(), replica.state_hash());
+    }
 }
@@ -0,0 +1,241 @@
+//! End-to-end latency of the order path: a command handed over by the UDP
+//! receiver thread until its result reaches the task that publishes events.
+//!
+//! Compares the previous design (tokio mpsc into an async task that locks a
+//! `tokio::sync::RwLock<OrderBook>`, with the 100 ms publisher contending for
+//! the same lock) against the pinned matching core fed by SPSC rings.
+//! Both pipelines are rebuilt here from the same pieces the service uses,
+//! since benches cannot reach into a binary crate. UDP and settlement are
+//! left out, they cost the same in both designs.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use matching_engine::{OrderBook, OrderResult, Side};
+use rtrb::{Producer, PushError, RingBuffer};
+use rust_decimal::Decimal;
+use std::sync::mpsc as std_mpsc;
+use std::sync::Arc;
+use std::thread::{self, Thread};
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, Notify, RwLock};
+use uuid::Uuid;
+
+const RING_CAPACITY: usize = 16_384;
+const SPIN_LIMIT: u32 = 10_000;
+const PUBLISH_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Alternate sides at one price so every other order fills and the book stays small
+fn command(i: u64) -> (Uuid, Side) {
+    let side = if i.is_multiple_of(2) { Side::Bid } else { Side::Ask };
+    (Uuid::new_v4(), side)
+}
+
+fn execute(ob: &mut OrderBook, (order_id, side): (Uuid, Side)) -> OrderResult {
+    ob.add_limit_order(order_id, side, Decimal::from(100), Decimal::ONE)
+}
+
+trait Pipeline {
+    fn send(&mut self, command: (Uuid, Side));
+    fn recv(&self) -> OrderResult;
+}
+
+/// Previous design: async task behind a tokio mpsc, book behind a RwLock
+struct LockedPipeline {
+    commands: mpsc::Sender<(Uuid, Side)>,
+    results: std_mpsc::Receiver<OrderResult>,
+    _runtime: tokio::runtime::Runtime,
+}
+
+impl LockedPipeline {
+    fn new() -> Self {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let orderbook = Arc::new(RwLock::new(OrderBook::new()));
+        let (commands, mut command_rx) = mpsc::channel::<(Uuid, Side)>(10_000);
+        let (result_tx, results) = std_mpsc::channel();
+
+        let ob = orderbook.clone();
+        runtime.spawn(async move {
+            while let Some(command) = command_rx.recv().await {
+                let result = execute(&mut *ob.write().await, command);
+                if result_tx.send(result).is_err() {
+                    break;
+                }
+            }
+        });
+        runtime.spawn(async move {
+            let mut interval = tokio::time::interval(PUBLISH_INTERVAL);
+            loop {
+                interval.tick().await;
+                let ob = orderbook.read().await;
+                std::hint::black_box((ob.get_bids(10), ob.get_asks(10)));
+            }
+        });
+
+        Self {
+            commands,
+            results,
+            _runtime: runtime,
+        }
+    }
+}
+
+impl Pipeline for LockedPipeline {
+    fn send(&mut self, command: (Uuid, Side)) {
+        self.commands.blocking_send(command).unwrap();
+    }
+
+    fn recv(&self) -> OrderResult {
+        self.results.recv().unwrap()
+    }
+}
+
+/// Current design: pinned core thread between two SPSC rings
+struct RingPipeline {
+    commands: Producer<(Uuid, Side)>,
+    core: Thread,
+    results: std_mpsc::Receiver<OrderResult>,
+    _runtime: tokio::runtime::Runtime,
+}
+
+impl RingPipeline {
+    fn new() -> Self {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let (commands, mut command_rx) = RingBuffer::<(Uuid, Side)>::new(RING_CAPACITY);
+        let (mut output_tx, mut output_rx) = RingBuffer::<OrderResult>::new(RING_CAPACITY);
+        let ready = Arc::new(Notify::new());
+        let (result_tx, results) = std_mpsc::channel();
+
+        let core_ready = ready.clone();
+        let core = thread::spawn(move || {
+            // Same policy as the service: pin and spin only with a CPU to spare
+            let spin_limit = match core_affinity::get_core_ids() {
+                Some(ids) if ids.len() > 1 && core_affinity::set_for_current(*ids.last().unwrap()) => SPIN_LIMIT,
+                _ => 0,
+            };
+            let mut ob = OrderBook::new();
+            let mut next_book = Instant::now();
+            let mut idle_polls = 0u32;
+            loop {
+                let mut busy = false;
+                while let Ok(command) = command_rx.pop() {
+                    busy = true;
+                    let mut result = execute(&mut ob, command);
+                    while let Err(PushError::Full(rejected)) = output_tx.push(result) {
+                        result = rejected;
+                        thread::yield_now();
+                    }
+                }
+                if busy {
+                    core_ready.notify_one();
+                }
+                let now = Instant::now();
+                if now >= next_book {
+                    std::hint::black_box((ob.get_bids(10), ob.get_asks(10)));
+                    next_book = now + PUBLISH_INTERVAL;
+                }
+                if busy {
+                    idle_polls = 0;
+                    continue;
+                }
+                if command_rx.is_abandoned() {
+                    break;
+                }
+                if idle_polls < spin_limit {
+                    idle_polls += 1;
+                    std::hint::spin_loop();
+                } else {
+                    thread::park_timeout(next_book.saturating_duration_since(Instant::now()));
+                }
+            }
+        })
+        .thread()
+        .clone();
+
+        runtime.spawn(async move {
+            loop {
+                while let Ok(result) = output_rx.pop() {
+                    if result_tx.send(result).is_err() {
+                        return;
+                    }
+                }
+                if output_rx.is_abandoned() {
+                    return;
+                }
+                ready.notified().await;
+            }
+        });
+
+        Self {
+            commands,
+            core,
+            results,
+            _runtime: runtime,
+        }
+    }
+}
+
+impl Pipeline for RingPipeline {
+    fn send(&mut self, mut command: (Uuid, Side)) {
+        while let Err(PushError::Full(rejected)) = self.commands.push(command) {
+            command = rejected;
+            thread::yield_now();
+        }
+        self.core.unpark();
+    }
+
+    fn recv(&self) -> OrderResult {
+        self.results.recv().unwrap()
+    }
+}
+
+/// Send `batch` commands, then wait for all results
+fn run_batch(pipeline: &mut impl Pipeline, batch: u64, iters: u64) -> Duration {
+    let start = Instant::now();
+    for i in 0..iters * batch {
+        pipeline.send(command(i));
+        if (i + 1) % batch == 0 {
+            for _ in 0..batch {
+                std::hint::black_box(pipeline.recv());
+            }
+        }
+    }
+    start.elapsed()
+}
+
+fn bench_round_trip(c: &mut Criterion) {
+    let mut group = c.benchmark_group("order_path_round_trip");
+
+    let mut locked = LockedPipeline::new();
+    group.bench_function("rwlock_async_task", |b| {
+        b.iter_custom(|iters| run_batch(&mut locked, 1, iters))
+    });
+
+    let mut ring = RingPipeline::new();
+    group.bench_function("pinned_core_spsc", |b| {
+        b.iter_custom(|iters| run_batch(&mut ring, 1, iters))
+    });
+
+    group.finish();
+}
+
+fn bench_burst(c: &mut Criterion) {
+    let mut group = c.benchmark_group("order_path_burst");
+
+    for batch in [100u64, 1000] {
+        group.throughput(Throughput::Elements(batch));
+
+        let mut locked = LockedPipeline::new();
+        group.bench_with_input(BenchmarkId::new("rwlock_async_task", batch), &batch, |b, &batch| {
+            b.iter_custom(|iters| run_batch(&mut locked, batch, iters))
+        });
+
+        let mut ring = RingPipeline::new();
+        group.bench_with_input(BenchmarkId::new("pinned_core_spsc", batch), &batch, |b, &batch| {
+            b.iter_custom(|iters| run_batch(&mut ring, batch, iters))
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_round_trip, bench_burst);
+criterion_main!(benches);
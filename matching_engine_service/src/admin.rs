@@ -0,0 +1,1059 @@
+//! Authenticated admin API for operators.
+//!
+//! Halts and resumes trading or moves the market to any other phase, cancels
+//! resting orders for the market or for a single owner, dumps the book (L2
+//! levels or L3 orders) and forces a snapshot publish. Every market action is
+//! broadcast to clients as a `MarketStatus` event. Settled trades can be
+//! busted, which reverses them in accounts and broadcasts a `TradeBust`
+//! event. Per owner, operators read
+//! and override risk limits and trip or lift the kill switch. Replicas report
+//! their role and a follower can be promoted to primary.
+//!
+//! Requests must carry `Authorization: Bearer <ADMIN_TOKEN>`. The symbol is
+//! part of the path and must be URL-encoded (`KCN%2FEUR`).
+
+use axum::{
+    extract::{Path, Query, Request, State},
+    http::{header, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::{get, post, put},
+    Json, Router,
+};
+use matching_engine::OrderId;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::control::now_millis;
+use crate::engine::DumpLevel;
+use crate::events::{MarketEvent, MarketPhase, PriceLevel};
+use crate::replication::Role;
+use crate::risk::{OwnerRisk, RiskLimits};
+use crate::settlement::BustResult;
+use crate::AppState;
+
+pub fn router(state: AppState) -> Router<AppState> {
+    Router::new()
+        .route("/admin/markets/:symbol/halt", post(halt))
+        .route("/admin/markets/:symbol/resume", post(resume))
+        .route("/admin/markets/:symbol/phase", post(change_phase))
+        .route("/admin/markets/:symbol/cancel-all", post(cancel_all))
+        .route("/admin/markets/:symbol/book", get(book))
+        .route("/admin/markets/:symbol/snapshot", post(snapshot))
+        .route("/admin/markets/:symbol/trades/bust", post(bust_trade))
+        .route("/admin/owners/:owner/risk", get(owner_risk))
+        .route("/admin/owners/:owner/limits", put(set_limits).delete(clear_limits))
+        .route("/admin/owners/:owner/kill", post(kill))
+        .route("/admin/owners/:owner/revive", post(revive))
+        .route("/admin/replication", get(replication))
+        .route("/admin/replication/promote", post(promote))
+        .route_layer(middleware::from_fn_with_state(state, require_admin))
+}
+
+#[derive(Debug, Serialize)]
+struct ErrorResponse {
+    error: String,
+}
+
+type AdminError = (StatusCode, Json<ErrorResponse>);
+
+fn error(status: StatusCode, message: impl Into<String>) -> AdminError {
+    (status, Json(ErrorResponse { error: message.into() }))
+}
+
+fn core_unavailable(e: anyhow::Error) -> AdminError {
+    warn!("Admin request failed: {}", e);
+    error(StatusCode::SERVICE_UNAVAILABLE, e.to_string())
+}
+
+async fn require_admin(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let Some(expected) = state.admin_token.as_deref() else {
+        return error(StatusCode::SERVICE_UNAVAILABLE, "Admin API disabled").into_response();
+    };
+
+    let provided = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+
+    match provided {
+        Some(token) if constant_time_eq(token.as_bytes(), expected.as_bytes()) => {
+            next.run(request).await
+        }
+        _ => {
+            warn!("Rejected unauthenticated admin request to {}", request.uri().path());
+            error(StatusCode::UNAUTHORIZED, "Unauthorized").into_response()
+        }
+    }
+}
+
+/// Compare tokens without leaking the matching prefix length through timing
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// This service runs a single market
+fn check_symbol(state: &AppState, symbol: &str) -> Result<(), AdminError> {
+    if symbol == state.symbol {
+        Ok(())
+    } else {
+        Err(error(StatusCode::NOT_FOUND, format!("Unknown symbol: {}", symbol)))
+    }
+}
+
+/// The book and the phase follow the primary until this replica is promoted
+fn check_primary(state: &AppState) -> Result<(), AdminError> {
+    match state.replica.role() {
+        Role::Primary => Ok(()),
+        Role::Follower => Err(error(
+            StatusCode::CONFLICT,
+            "Replica is following, send this to the primary",
+        )),
+    }
+}
+
+/// Broadcast the current phase with the reason for the action.
+/// A missing event link must not fail the operator action itself.
+async fn broadcast_status(state: &AppState, reason: String) {
+    let event = MarketEvent::MarketStatus {
+        symbol: state.symbol.clone(),
+        phase: state.control.phase(),
+        reason,
+        timestamp: now_millis(),
+    };
+    if let Err(e) = state.event_sender.send_event(&event).await {
+        warn!("Failed to broadcast market status: {}", e);
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct PhaseRequest {
+    reason: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct PhaseResponse {
+    symbol: String,
+    phase: MarketPhase,
+    previous_phase: MarketPhase,
+    reason: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct PhaseChangeRequest {
+    phase: MarketPhase,
+    reason: Option<String>,
+}
+
+async fn halt(
+    State(state): State<AppState>,
+    Path(symbol): Path<String>,
+    body: Option<Json<PhaseRequest>>,
+) -> Result<Json<PhaseResponse>, AdminError> {
+    let reason = body.and_then(|Json(b)| b.reason);
+    set_phase(state, symbol, MarketPhase::Halted, reason).await
+}
+
+async fn resume(
+    State(state): State<AppState>,
+    Path(symbol): Path<String>,
+    body: Option<Json<PhaseRequest>>,
+) -> Result<Json<PhaseResponse>, AdminError> {
+    let reason = body.and_then(|Json(b)| b.reason);
+    set_phase(state, symbol, MarketPhase::Open, reason).await
+}
+
+async fn change_phase(
+    State(state): State<AppState>,
+    Path(symbol): Path<String>,
+    Json(body): Json<PhaseChangeRequest>,
+) -> Result<Json<PhaseResponse>, AdminError> {
+    set_phase(state, symbol, body.phase, body.reason).await
+}
+
+async fn set_phase(
+    state: AppState,
+    symbol: String,
+    phase: MarketPhase,
+    detail: Option<String>,
+) -> Result<Json<PhaseResponse>, AdminError> {
+    check_symbol(&state, &symbol)?;
+    check_primary(&state)?;
+
+    let previous_phase = state.control.transition(phase).map_err(|current| {
+        error(
+            StatusCode::CONFLICT,
+            format!("Market is {}, it can't change phase", current.as_str()),
+        )
+    })?;
+    let action = match (previous_phase, phase) {
+        (MarketPhase::Halted, MarketPhase::Open) => "resumed",
+        (_, MarketPhase::Open) => "opened",
+        (_, MarketPhase::PreOpen) => "moved to pre-open",
+        (_, MarketPhase::Auction) => "moved to auction",
+        (_, MarketPhase::Halted) => "halted",
+        (_, MarketPhase::Closed) => "closed",
+        (_, MarketPhase::Delisted) => "delisted",
+    };
+    let reason = match detail {
+        Some(detail) => format!("{} by operator: {}", action, detail),
+        None => format!("{} by operator", action),
+    };
+    info!("Market {} {:?} -> {:?} ({})", symbol, previous_phase, phase, reason);
+
+    broadcast_status(&state, reason.clone()).await;
+
+    Ok(Json(PhaseResponse {
+        symbol,
+        phase,
+        previous_phase,
+        reason,
+    }))
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct CancelAllRequest {
+    /// Only cancel orders of this user, all orders when absent
+    owner: Option<Uuid>,
+    reason: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct CancelAllResponse {
+    symbol: String,
+    owner: Option<Uuid>,
+    cancelled: Vec<OrderId>,
+}
+
+async fn cancel_all(
+    State(state): State<AppState>,
+    Path(symbol): Path<String>,
+    body: Option<Json<CancelAllRequest>>,
+) -> Result<Json<CancelAllResponse>, AdminError> {
+    check_symbol(&state, &symbol)?;
+    check_primary(&state)?;
+    let request = body.map(|Json(b)| b).unwrap_or_default();
+
+    // The core releases funds and notifies owners through its post-trade output
+    let cancelled = state
+        .core
+        .cancel_all(request.owner)
+        .await
+        .map_err(core_unavailable)?;
+
+    let scope = match request.owner {
+        Some(owner) => format!("owner {}", owner),
+        None => "all owners".to_string(),
+    };
+    let mut reason = format!("cancelled {} orders of {} by operator", cancelled.len(), scope);
+    if let Some(detail) = request.reason {
+        reason = format!("{}: {}", reason, detail);
+    }
+    info!("Market {}: {}", symbol, reason);
+    broadcast_status(&state, reason).await;
+
+    Ok(Json(CancelAllResponse {
+        symbol,
+        owner: request.owner,
+        cancelled,
+    }))
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum BookLevel {
+    /// Aggregated price levels
+    #[default]
+    L2,
+    /// Individual orders per level
+    L3,
+}
+
+#[derive(Debug, Deserialize)]
+struct BookQuery {
+    #[serde(default)]
+    level: BookLevel,
+    /// Number of price levels per side, the full book when absent
+    depth: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+struct L3Order {
+    order_id: OrderId,
+    owner: Option<Uuid>,
+    quantity: Decimal,
+    remaining_quantity: Decimal,
+}
+
+#[derive(Debug, Serialize)]
+struct L3Level {
+    price: Decimal,
+    orders: Vec<L3Order>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+enum BookSide {
+    L2(Vec<PriceLevel>),
+    L3(Vec<L3Level>),
+}
+
+#[derive(Debug, Serialize)]
+struct BookResponse {
+    symbol: String,
+    phase: MarketPhase,
+    level: BookLevel,
+    order_count: usize,
+    bids: BookSide,
+    asks: BookSide,
+}
+
+async fn book(
+    State(state): State<AppState>,
+    Path(symbol): Path<String>,
+    Query(query): Query<BookQuery>,
+) -> Result<Json<BookResponse>, AdminError> {
+    check_symbol(&state, &symbol)?;
+    let depth = query.depth.unwrap_or(usize::MAX);
+
+    let dump = state.core.book(depth).await.map_err(core_unavailable)?;
+    let (bids, asks) = match query.level {
+        BookLevel::L2 => (l2(&dump.bids), l2(&dump.asks)),
+        BookLevel::L3 => (l3(dump.bids), l3(dump.asks)),
+    };
+    let response = BookResponse {
+        symbol: symbol.clone(),
+        phase: state.control.phase(),
+        level: query.level,
+        order_count: dump.order_count,
+        bids,
+        asks,
+    };
+
+    let level = match query.level {
+        BookLevel::L2 => "L2",
+        BookLevel::L3 => "L3",
+    };
+    broadcast_status(&state, format!("{} book dump requested by operator", level)).await;
+
+    Ok(Json(response))
+}
+
+fn l2(levels: &[DumpLevel]) -> BookSide {
+    BookSide::L2(
+        levels
+            .iter()
+            .map(|level| PriceLevel {
+                price: level.price,
+                quantity: level.orders.iter().map(|(o, _)| o.remaining_quantity).sum(),
+            })
+            .collect(),
+    )
+}
+
+fn l3(levels: Vec<DumpLevel>) -> BookSide {
+    BookSide::L3(
+        levels
+            .into_iter()
+            .map(|level| L3Level {
+                price: level.price,
+                orders: level
+                    .orders
+                    .into_iter()
+                    .map(|(o, owner)| L3Order {
+                        order_id: o.id,
+                        owner,
+                        quantity: o.quantity,
+                        remaining_quantity: o.remaining_quantity,
+                    })
+                    .collect(),
+            })
+            .collect(),
+    )
+}
+
+#[derive(Debug, Serialize)]
+struct SnapshotResponse {
+    symbol: String,
+    /// Sequence of the published snapshot, the next delta carries sequence + 1
+    sequence: u64,
+}
+
+async fn snapshot(
+    State(state): State<AppState>,
+    Path(symbol): Path<String>,
+) -> Result<Json<SnapshotResponse>, AdminError> {
+    check_symbol(&state, &symbol)?;
+
+    let sequence = state.core.snapshot().await.map_err(core_unavailable)?;
+    broadcast_status(&state, "snapshot publish forced by operator".to_string()).await;
+
+    Ok(Json(SnapshotResponse { symbol, sequence }))
+}
+
+#[derive(Debug, Deserialize)]
+struct BustRequest {
+    buy_order_id: OrderId,
+    sell_order_id: OrderId,
+    /// Timestamp of the fill as published, in milliseconds
+    timestamp: u64,
+    reason: String,
+}
+
+#[derive(Debug, Serialize)]
+struct BustResponse {
+    trade_id: Uuid,
+    symbol: String,
+    buy_order_id: OrderId,
+    sell_order_id: OrderId,
+    price: Decimal,
+    quantity: Decimal,
+}
+
+/// Reverse a settled trade. Accounts undoes the ledger entries of both
+/// counterparties, then the bust is broadcast so market data can correct its
+/// candles and the counterparties are told. The book is not touched.
+async fn bust_trade(
+    State(state): State<AppState>,
+    Path(symbol): Path<String>,
+    Json(request): Json<BustRequest>,
+) -> Result<Json<BustResponse>, AdminError> {
+    check_symbol(&state, &symbol)?;
+    check_primary(&state)?;
+
+    let trade = match state
+        .settlement_client
+        .bust_trade(
+            request.buy_order_id,
+            request.sell_order_id,
+            request.timestamp,
+            &request.reason,
+        )
+        .await
+    {
+        BustResult::Busted(trade) => trade,
+        BustResult::Refused { status, error: message } => {
+            let status = StatusCode::from_u16(status).unwrap_or(StatusCode::BAD_GATEWAY);
+            return Err(error(status, message));
+        }
+        BustResult::Failed(message) => return Err(error(StatusCode::BAD_GATEWAY, message)),
+    };
+    warn!(
+        "Trade {} ({} {} @ {}) busted by operator: {}",
+        trade.trade_id, trade.quantity, trade.symbol, trade.price, request.reason
+    );
+
+    let event = MarketEvent::TradeBust {
+        symbol: trade.symbol.clone(),
+        trade_id: trade.trade_id,
+        buy_order_id: request.buy_order_id,
+        sell_order_id: request.sell_order_id,
+        buyer_id: trade.buyer_id,
+        seller_id: trade.seller_id,
+        price: trade.price,
+        quantity: trade.quantity,
+        timestamp: request.timestamp,
+        reason: request.reason,
+    };
+    // The ledger is already reversed, a lost event must not fail the bust
+    if let Err(e) = state.event_sender.send_event(&event).await {
+        warn!("Failed to broadcast trade bust: {}", e);
+    }
+
+    Ok(Json(BustResponse {
+        trade_id: trade.trade_id,
+        symbol: trade.symbol,
+        buy_order_id: request.buy_order_id,
+        sell_order_id: request.sell_order_id,
+        price: trade.price,
+        quantity: trade.quantity,
+    }))
+}
+
+async fn owner_risk(State(state): State<AppState>, Path(owner): Path<Uuid>) -> Result<Json<OwnerRisk>, AdminError> {
+    let risk = state.core.risk(owner).await.map_err(core_unavailable)?;
+    Ok(Json(risk))
+}
+
+/// Replace the owner's limits as a whole, limits left out are not enforced
+async fn set_limits(
+    State(state): State<AppState>,
+    Path(owner): Path<Uuid>,
+    Json(limits): Json<RiskLimits>,
+) -> Result<Json<OwnerRisk>, AdminError> {
+    check_primary(&state)?;
+
+    info!("Risk limits of owner {} set by operator: {:?}", owner, limits);
+    let risk = state
+        .core
+        .set_limits(owner, Some(limits))
+        .await
+        .map_err(core_unavailable)?;
+    Ok(Json(risk))
+}
+
+/// Go back to the default limits
+async fn clear_limits(State(state): State<AppState>, Path(owner): Path<Uuid>) -> Result<Json<OwnerRisk>, AdminError> {
+    check_primary(&state)?;
+
+    info!("Risk limits of owner {} reset to defaults by operator", owner);
+    let risk = state.core.set_limits(owner, None).await.map_err(core_unavailable)?;
+    Ok(Json(risk))
+}
+
+#[derive(Debug, Serialize)]
+struct KillResponse {
+    owner: Uuid,
+    cancelled: Vec<OrderId>,
+}
+
+/// Block the owner's new orders and cancel the resting ones. The core
+/// releases funds and notifies the owner of every cancel.
+async fn kill(
+    State(state): State<AppState>,
+    Path(owner): Path<Uuid>,
+    body: Option<Json<PhaseRequest>>,
+) -> Result<Json<KillResponse>, AdminError> {
+    check_primary(&state)?;
+
+    let cancelled = state.core.kill(owner).await.map_err(core_unavailable)?;
+    let mut message = format!(
+        "Kill switch tripped for owner {} by operator, {} orders cancelled",
+        owner,
+        cancelled.len()
+    );
+    if let Some(detail) = body.and_then(|Json(b)| b.reason) {
+        message = format!("{}: {}", message, detail);
+    }
+    warn!("{}", message);
+    Ok(Json(KillResponse { owner, cancelled }))
+}
+
+async fn revive(State(state): State<AppState>, Path(owner): Path<Uuid>) -> Result<Json<OwnerRisk>, AdminError> {
+    check_primary(&state)?;
+
+    if !state.core.revive(owner).await.map_err(core_unavailable)? {
+        return Err(error(StatusCode::CONFLICT, "Kill switch is not set for this owner"));
+    }
+    info!("Kill switch lifted for owner {} by operator", owner);
+    let risk = state.core.risk(owner).await.map_err(core_unavailable)?;
+    Ok(Json(risk))
+}
+
+#[derive(Debug, Serialize)]
+struct ReplicationResponse {
+    role: Role,
+}
+
+async fn replication(State(state): State<AppState>) -> Json<ReplicationResponse> {
+    Json(ReplicationResponse {
+        role: state.replica.role(),
+    })
+}
+
+/// Take over from the primary. Make sure it is gone first, nothing stops two
+/// primaries from matching orders side by side.
+async fn promote(State(state): State<AppState>) -> Result<(StatusCode, Json<ReplicationResponse>), AdminError> {
+    if !state.replica.request_promotion() {
+        return Err(error(StatusCode::CONFLICT, "Replica already is primary"));
+    }
+    info!("Promotion to primary requested by operator");
+
+    // The journal receiver hands over to the order stream shortly after
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(ReplicationResponse {
+            role: state.replica.role(),
+        }),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::control::MarketControl;
+    use crate::dedup::CommandDeduplicator;
+    use crate::engine::{self, CommandQueue, MatchingCore};
+    use crate::events::{OrderCommand, Side};
+    use crate::metrics::ServiceMetrics;
+    use crate::replication::ReplicaControl;
+    use crate::settlement::SettlementClient;
+    use crate::udp_transport::UdpEventSender;
+    use serde_json::{json, Value};
+    use std::net::{SocketAddr, UdpSocket};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use udp_proto::binary::{decode_market_event, MarketEvent as BinaryMarketEvent, MarketPhase as BinaryMarketPhase};
+    use udp_proto::{ReceiverConfig, UdpReceiver};
+
+    const SYMBOL: &str = "KCN/EUR";
+    const TOKEN: &str = "test-token";
+
+    fn free_addr() -> SocketAddr {
+        UdpSocket::bind("127.0.0.1:0").unwrap().local_addr().unwrap()
+    }
+
+    /// Serve the admin API on an ephemeral port, backed by a running matching
+    /// core, with a UDP receiver standing in for the gateway's event stream
+    async fn start(admin_token: Option<&str>) -> (String, AppState, UdpReceiver, CommandQueue) {
+        start_with_accounts(admin_token, "http://127.0.0.1:1").await
+    }
+
+    async fn start_with_accounts(
+        admin_token: Option<&str>,
+        accounts_url: &str,
+    ) -> (String, AppState, UdpReceiver, CommandQueue) {
+        let event_addr = free_addr();
+        let events = UdpReceiver::new(
+            ReceiverConfig {
+                stream_id: 2,
+                ..Default::default()
+            },
+            event_addr,
+        )
+        .unwrap();
+
+        let control = Arc::new(MarketControl::new());
+        let metrics = Arc::new(ServiceMetrics::new());
+        let core = MatchingCore::new(
+            CommandDeduplicator::new(16),
+            control.clone(),
+            metrics.clone(),
+            SYMBOL.to_string(),
+        );
+        let (commands, core, outputs) = engine::spawn(core, None).unwrap();
+        let event_sender = Arc::new(UdpEventSender::new(event_addr.to_string(), free_addr()).unwrap());
+        let settlement_client = Arc::new(SettlementClient::new(accounts_url.to_string()));
+        tokio::spawn(crate::run_post_trade(
+            outputs.outputs,
+            outputs.ready,
+            true,
+            event_sender.clone(),
+            settlement_client.clone(),
+            metrics.clone(),
+            SYMBOL.to_string(),
+        ));
+
+        let state = AppState {
+            core,
+            event_sender,
+            settlement_client,
+            control,
+            replica: Arc::new(ReplicaControl::new(Role::Primary)),
+            metrics,
+            symbol: SYMBOL.to_string(),
+            admin_token: admin_token.map(Into::into),
+        };
+
+        let app = router(state.clone()).with_state(state.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}/admin/markets/KCN%2FEUR", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        (base, state, events, commands)
+    }
+
+    fn next_status(events: &UdpReceiver) -> (BinaryMarketPhase, String) {
+        loop {
+            let msg = events
+                .recv_timeout(Duration::from_secs(2))
+                .unwrap()
+                .expect("no market status event");
+            if let Ok(BinaryMarketEvent::MarketStatus { phase, reason, .. }) = decode_market_event(&msg.payload) {
+                return (phase, reason);
+            }
+        }
+    }
+
+    fn next_rejection(events: &UdpReceiver) -> (OrderId, String) {
+        loop {
+            let msg = events
+                .recv_timeout(Duration::from_secs(2))
+                .unwrap()
+                .expect("no order rejected event");
+            if let Ok(BinaryMarketEvent::OrderRejected { order_id, reason }) = decode_market_event(&msg.payload) {
+                return (order_id, reason);
+            }
+        }
+    }
+
+    fn next_bust(events: &UdpReceiver) -> BinaryMarketEvent {
+        loop {
+            let msg = events
+                .recv_timeout(Duration::from_secs(2))
+                .unwrap()
+                .expect("no trade bust event");
+            if let Ok(event @ BinaryMarketEvent::TradeBust { .. }) = decode_market_event(&msg.payload) {
+                return event;
+            }
+        }
+    }
+
+    /// Accounts stand-in that knows a single settled trade, and busts it once
+    async fn fake_accounts(trade_id: Uuid, buyer: Uuid, buy_order_id: OrderId) -> String {
+        let busted = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let app = Router::new().route(
+            "/internal/trades/bust",
+            post(move |Json(request): Json<Value>| async move {
+                if request["buy_order_id"] != json!(buy_order_id) {
+                    let body = json!({ "error": "Trade not found", "code": "TRADE_NOT_FOUND" });
+                    return (StatusCode::NOT_FOUND, Json(body));
+                }
+                if busted.swap(true, std::sync::atomic::Ordering::SeqCst) {
+                    let body = json!({ "error": "Trade already busted", "code": "ALREADY_BUSTED" });
+                    return (StatusCode::CONFLICT, Json(body));
+                }
+                let body = json!({
+                    "trade_id": trade_id,
+                    "symbol": SYMBOL,
+                    "buyer_id": buyer,
+                    "seller_id": null,
+                    "price": "100.5",
+                    "quantity": "2",
+                    "busted_at": "2026-01-01T00:00:00Z",
+                });
+                (StatusCode::OK, Json(body))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        url
+    }
+
+    fn place(commands: &mut CommandQueue, owner: Uuid, side: Side, price: i64) -> OrderId {
+        let order_id = Uuid::new_v4();
+        commands.push(OrderCommand::PlaceOrder {
+            order_id,
+            side,
+            order_type: "limit".to_string(),
+            price: Some(Decimal::from(price)),
+            quantity: Decimal::from(1),
+            user_id: Some(owner),
+        });
+        order_id
+    }
+
+    /// Book requests see every command enqueued before them
+    async fn is_resting(state: &AppState, order_id: OrderId) -> bool {
+        let dump = state.core.book(usize::MAX).await.unwrap();
+        dump.bids
+            .iter()
+            .chain(&dump.asks)
+            .any(|level| level.orders.iter().any(|(o, _)| o.id == order_id))
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_admin_requires_token() {
+        let client = reqwest::Client::new();
+
+        let (base, _state, _events, _commands) = start(Some(TOKEN)).await;
+        let response = client.post(format!("{}/halt", base)).send().await.unwrap();
+        assert_eq!(response.status(), 401);
+        let response = client
+            .post(format!("{}/halt", base))
+            .bearer_auth("wrong-token")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 401);
+
+        let (base, state, _events, _commands) = start(None).await;
+        let response = client
+            .post(format!("{}/halt", base))
+            .bearer_auth(TOKEN)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 503);
+        assert_eq!(state.control.phase(), MarketPhase::Open);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_halt_rejects_orders_until_resume() {
+        let (base, state, events, mut commands) = start(Some(TOKEN)).await;
+        let client = reqwest::Client::new();
+
+        let response: Value = client
+            .post(format!("{}/halt", base))
+            .bearer_auth(TOKEN)
+            .json(&json!({ "reason": "fat finger" }))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(response["phase"], "halted");
+        assert_eq!(response["previous_phase"], "open");
+        assert_eq!(
+            next_status(&events),
+            (BinaryMarketPhase::Halted, "halted by operator: fat finger".to_string())
+        );
+
+        let order_id = place(&mut commands, Uuid::new_v4(), Side::Bid, 100);
+        assert!(!is_resting(&state, order_id).await);
+
+        let response = client
+            .post(format!("{}/resume", base))
+            .bearer_auth(TOKEN)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(next_status(&events).0, BinaryMarketPhase::Open);
+
+        let order_id = place(&mut commands, Uuid::new_v4(), Side::Bid, 100);
+        assert!(is_resting(&state, order_id).await);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_phase_changes_until_delisted() {
+        let (base, state, events, mut commands) = start(Some(TOKEN)).await;
+        let client = reqwest::Client::new();
+        let change = |phase: &str| {
+            client
+                .post(format!("{}/phase", base))
+                .bearer_auth(TOKEN)
+                .json(&json!({ "phase": phase, "reason": "schedule" }))
+                .send()
+        };
+
+        let response = change("auction").await.unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(
+            next_status(&events),
+            (BinaryMarketPhase::Auction, "moved to auction by operator: schedule".to_string())
+        );
+        let order_id = place(&mut commands, Uuid::new_v4(), Side::Bid, 100);
+        assert!(!is_resting(&state, order_id).await);
+
+        let response: Value = change("open").await.unwrap().json().await.unwrap();
+        assert_eq!(response["previous_phase"], "auction");
+        assert_eq!(next_status(&events), (BinaryMarketPhase::Open, "opened by operator: schedule".to_string()));
+
+        change("delisted").await.unwrap();
+        assert_eq!(next_status(&events).0, BinaryMarketPhase::Delisted);
+        let response = change("pre_open").await.unwrap();
+        assert_eq!(response.status(), 409);
+        assert_eq!(state.control.phase(), MarketPhase::Delisted);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_cancel_all_for_owner_and_book_dump() {
+        let (base, _state, events, mut commands) = start(Some(TOKEN)).await;
+        let client = reqwest::Client::new();
+
+        let alice = Uuid::new_v4();
+        let bob = Uuid::new_v4();
+        let alice_bid = place(&mut commands, alice, Side::Bid, 99);
+        let alice_ask = place(&mut commands, alice, Side::Ask, 101);
+        let bob_bid = place(&mut commands, bob, Side::Bid, 99);
+
+        let book: Value = client
+            .get(format!("{}/book?level=l3", base))
+            .bearer_auth(TOKEN)
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(book["order_count"], 3);
+        let queue = book["bids"][0]["orders"].as_array().unwrap();
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue[0]["order_id"], alice_bid.to_string());
+        assert_eq!(queue[0]["owner"], alice.to_string());
+        assert_eq!(queue[1]["order_id"], bob_bid.to_string());
+        assert_eq!(next_status(&events).1, "L3 book dump requested by operator");
+
+        let response: Value = client
+            .post(format!("{}/cancel-all", base))
+            .bearer_auth(TOKEN)
+            .json(&json!({ "owner": alice }))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        let mut cancelled: Vec<String> = response["cancelled"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|v| v.as_str().unwrap().to_string())
+            .collect();
+        cancelled.sort();
+        let mut expected = vec![alice_bid.to_string(), alice_ask.to_string()];
+        expected.sort();
+        assert_eq!(cancelled, expected);
+        assert!(next_status(&events).1.starts_with("cancelled 2 orders of owner"));
+
+        let book: Value = client
+            .get(format!("{}/book", base))
+            .bearer_auth(TOKEN)
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(book["level"], "l2");
+        assert_eq!(book["order_count"], 1);
+        assert_eq!(book["bids"][0]["quantity"], "1");
+        assert!(book["asks"].as_array().unwrap().is_empty());
+
+        let response = client
+            .get(format!("{}/book", base.replace("KCN%2FEUR", "BTC%2FEUR")))
+            .bearer_auth(TOKEN)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 404);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_owner_limits_and_kill_switch() {
+        let (base, state, events, mut commands) = start(Some(TOKEN)).await;
+        let owners = base.replace("markets/KCN%2FEUR", "owners");
+        let client = reqwest::Client::new();
+        let alice = Uuid::new_v4();
+
+        let response: Value = client
+            .put(format!("{}/{}/limits", owners, alice))
+            .bearer_auth(TOKEN)
+            .json(&json!({ "max_open_orders": 1 }))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(response["limits"]["max_open_orders"], 1);
+        assert_eq!(response["overridden"], true);
+
+        let resting = place(&mut commands, alice, Side::Bid, 99);
+        let refused = place(&mut commands, alice, Side::Bid, 98);
+        assert!(is_resting(&state, resting).await);
+        assert!(!is_resting(&state, refused).await);
+        assert_eq!(next_rejection(&events), (refused, "Open order limit of 1 reached".to_string()));
+        // Other owners keep the defaults
+        let bob_bid = place(&mut commands, Uuid::new_v4(), Side::Bid, 98);
+        assert!(is_resting(&state, bob_bid).await);
+
+        let response: Value = client
+            .post(format!("{}/{}/kill", owners, alice))
+            .bearer_auth(TOKEN)
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(response["cancelled"], json!([resting]));
+        assert!(!is_resting(&state, resting).await);
+
+        let refused = place(&mut commands, alice, Side::Bid, 99);
+        assert!(!is_resting(&state, refused).await);
+        assert_eq!(next_rejection(&events), (refused, "Trading disabled by kill switch".to_string()));
+        let risk: Value = client
+            .get(format!("{}/{}/risk", owners, alice))
+            .bearer_auth(TOKEN)
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(risk["killed"], true);
+        assert_eq!(risk["open_orders"], 0);
+
+        let response = client
+            .post(format!("{}/{}/revive", owners, alice))
+            .bearer_auth(TOKEN)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+        let response = client
+            .delete(format!("{}/{}/limits", owners, alice))
+            .bearer_auth(TOKEN)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+        let first = place(&mut commands, alice, Side::Bid, 99);
+        let second = place(&mut commands, alice, Side::Bid, 98);
+        assert!(is_resting(&state, first).await);
+        assert!(is_resting(&state, second).await);
+
+        let response = client
+            .post(format!("{}/{}/revive", owners, alice))
+            .bearer_auth(TOKEN)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 409);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_bust_trade_is_broadcast() {
+        let (trade_id, buyer) = (Uuid::new_v4(), Uuid::new_v4());
+        let (buy_order_id, sell_order_id) = (Uuid::new_v4(), Uuid::new_v4());
+        let accounts = fake_accounts(trade_id, buyer, buy_order_id).await;
+        let (base, _state, events, _commands) = start_with_accounts(Some(TOKEN), &accounts).await;
+        let client = reqwest::Client::new();
+        let bust = |buy_order_id: OrderId| {
+            client
+                .post(format!("{}/trades/bust", base))
+                .bearer_auth(TOKEN)
+                .json(&json!({
+                    "buy_order_id": buy_order_id,
+                    "sell_order_id": sell_order_id,
+                    "timestamp": 1_700_000_000_000u64,
+                    "reason": "erroneous price",
+                }))
+                .send()
+        };
+
+        let response = bust(buy_order_id).await.unwrap();
+        assert_eq!(response.status(), 200);
+        let response: Value = response.json().await.unwrap();
+        assert_eq!(response["trade_id"], json!(trade_id));
+        assert_eq!(response["quantity"], "2");
+
+        match next_bust(&events) {
+            BinaryMarketEvent::TradeBust {
+                trade_id: busted,
+                buyer_id,
+                seller_id,
+                price,
+                timestamp,
+                reason,
+                ..
+            } => {
+                assert_eq!(busted, trade_id);
+                assert_eq!(buyer_id, Some(buyer));
+                assert_eq!(seller_id, None);
+                assert_eq!(price, Decimal::new(1005, 1));
+                assert_eq!(timestamp, 1_700_000_000_000);
+                assert_eq!(reason, "erroneous price");
+            }
+            _ => unreachable!(),
+        }
+
+        // Accounts decides, its refusals reach the operator as they are
+        assert_eq!(bust(buy_order_id).await.unwrap().status(), 409);
+        assert_eq!(bust(Uuid::new_v4()).await.unwrap().status(), 404);
+    }
+}
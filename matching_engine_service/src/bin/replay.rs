@@ -0,0 +1,314 @@
+//! Deterministic replay of a journal file (see `JOURNAL_FILE`).
+//!
+//! Runs the recorded commands through a fresh `OrderBook` at full speed, or
+//! up to a given sequence, prints the fills and the resulting book, and
+//! compares them with the fills and book checksums the engine recorded. Any
+//! difference means this build of the engine does not match the one that
+//! wrote the file, and the tool exits with status 1.
+
+use clap::Parser;
+use matching_engine::{Fill, OrderBook, Side};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use udp_proto::binary::{
+    JournalEntry, JournalRecord, MarketPhase, OrderCommand, OrderType, Side as BinarySide,
+};
+use udp_proto::journal_file::JournalReader;
+
+#[derive(Parser, Debug)]
+#[command(name = "replay")]
+#[command(about = "Replay a matching engine journal file and check it against the recording")]
+struct Args {
+    /// Journal file written by the engine
+    journal: PathBuf,
+
+    /// Stop after the command with this sequence, in the first run of the file
+    #[arg(long)]
+    until: Option<u64>,
+
+    /// Print every fill as it is replayed
+    #[arg(long, default_value_t = false)]
+    fills: bool,
+
+    /// Price levels per side in the printed book
+    #[arg(long, default_value_t = 10)]
+    depth: usize,
+
+    /// Print individual orders instead of aggregated levels
+    #[arg(long, default_value_t = false)]
+    l3: bool,
+}
+
+/// A command whose recorded fills are still being read
+struct Pending {
+    sequence: u64,
+    replayed: Vec<Fill>,
+    recorded: Vec<Fill>,
+}
+
+#[derive(Default)]
+struct Summary {
+    /// Engine runs found in the file, each starts from sequence 1
+    runs: u64,
+    commands: u64,
+    fills: u64,
+    gaps: u64,
+    checksums_verified: u64,
+    divergences: u64,
+    phase: Option<MarketPhase>,
+}
+
+struct Replay {
+    book: OrderBook,
+    sequence: u64,
+    pending: Option<Pending>,
+    print_fills: bool,
+    summary: Summary,
+}
+
+impl Replay {
+    fn new(print_fills: bool) -> Self {
+        Self {
+            book: OrderBook::new(),
+            sequence: 0,
+            pending: None,
+            print_fills,
+            summary: Summary::default(),
+        }
+    }
+
+    /// Apply one entry, returns false once past `until`
+    fn apply(&mut self, entry: JournalEntry, until: Option<u64>) -> bool {
+        match entry.record {
+            JournalRecord::Command(command) => {
+                self.settle();
+                if entry.sequence == 1 && self.sequence > 0 {
+                    if until.is_some() {
+                        // The run we were asked about is complete
+                        return false;
+                    }
+                    println!("Engine restarted after sequence {}, starting over with an empty book", self.sequence);
+                    self.book = OrderBook::new();
+                    self.sequence = 0;
+                }
+                if until.is_some_and(|until| entry.sequence > until) {
+                    return false;
+                }
+                self.command(entry.sequence, command);
+            }
+            JournalRecord::Fill {
+                buy_order_id,
+                sell_order_id,
+                price,
+                quantity,
+            } => match &mut self.pending {
+                Some(pending) if pending.sequence == entry.sequence => pending.recorded.push(Fill {
+                    buy_order_id,
+                    sell_order_id,
+                    price,
+                    quantity,
+                }),
+                _ => {
+                    println!("DIVERGENCE at {}: recorded fill without its command", entry.sequence);
+                    self.summary.divergences += 1;
+                }
+            },
+            // Only the follower gets rejections and risk controls, neither changes the book
+            JournalRecord::Rejected { .. } | JournalRecord::RiskControl(_) => {}
+            JournalRecord::Checksum {
+                hash,
+                order_count,
+                phase,
+            } => {
+                self.settle();
+                // Checksums of commands past `until` or skipped by a gap tell nothing
+                if entry.sequence != self.sequence {
+                    return true;
+                }
+                self.summary.phase = Some(phase);
+                if hash == self.book.state_hash() && order_count == self.book.order_count() as u64 {
+                    self.summary.checksums_verified += 1;
+                } else {
+                    println!(
+                        "DIVERGENCE at {}: recorded book has {} orders (hash {:016x}), replayed has {} (hash {:016x})",
+                        entry.sequence,
+                        order_count,
+                        hash,
+                        self.book.order_count(),
+                        self.book.state_hash()
+                    );
+                    self.summary.divergences += 1;
+                }
+            }
+        }
+        true
+    }
+
+    fn command(&mut self, sequence: u64, command: OrderCommand) {
+        if sequence <= self.sequence {
+            println!("Skipping entry {}, already at {}", sequence, self.sequence);
+            return;
+        }
+        if self.sequence == 0 {
+            self.summary.runs += 1;
+        }
+        if sequence != self.sequence + 1 {
+            println!("GAP: entries {} to {} are missing from the file", self.sequence + 1, sequence - 1);
+            self.summary.gaps += 1;
+        }
+        self.sequence = sequence;
+        self.summary.commands += 1;
+
+        let fills = match command {
+            OrderCommand::New {
+                order_id,
+                side,
+                order_type,
+                price,
+                quantity,
+                ..
+            } => {
+                let side = match side {
+                    BinarySide::Bid => Side::Bid,
+                    BinarySide::Ask => Side::Ask,
+                };
+                let result = match (order_type, price) {
+                    (OrderType::Market, _) => self.book.add_market_order(order_id, side, quantity),
+                    (OrderType::Limit, Some(price)) => self.book.add_limit_order(order_id, side, price, quantity),
+                    (OrderType::Limit, None) => {
+                        println!("DIVERGENCE at {}: limit order {} without a price was journaled", sequence, order_id);
+                        self.summary.divergences += 1;
+                        return;
+                    }
+                };
+                result.fills
+            }
+            OrderCommand::Cancel { order_id, .. } => {
+                self.book.cancel_order(order_id);
+                Vec::new()
+            }
+            other => {
+                println!("Skipping entry {}, not a book command: {:?}", sequence, other);
+                return;
+            }
+        };
+
+        if self.print_fills {
+            for fill in &fills {
+                println!(
+                    "#{} fill {} @ {} buy={} sell={}",
+                    sequence, fill.quantity, fill.price, fill.buy_order_id, fill.sell_order_id
+                );
+            }
+        }
+        self.summary.fills += fills.len() as u64;
+        self.pending = Some(Pending {
+            sequence,
+            replayed: fills,
+            recorded: Vec::new(),
+        });
+    }
+
+    /// Compare the fills of the last command with the recorded ones
+    fn settle(&mut self) {
+        let Some(pending) = self.pending.take() else {
+            return;
+        };
+        if pending.replayed == pending.recorded {
+            return;
+        }
+        println!(
+            "DIVERGENCE at {}: recorded {} fills, replayed {}",
+            pending.sequence,
+            pending.recorded.len(),
+            pending.replayed.len()
+        );
+        let describe = |fill: Option<&Fill>| match fill {
+            Some(f) => format!("{} @ {} buy={} sell={}", f.quantity, f.price, f.buy_order_id, f.sell_order_id),
+            None => "-".to_string(),
+        };
+        for i in 0..pending.recorded.len().max(pending.replayed.len()) {
+            let (recorded, replayed) = (pending.recorded.get(i), pending.replayed.get(i));
+            if recorded != replayed {
+                println!("  recorded: {}", describe(recorded));
+                println!("  replayed: {}", describe(replayed));
+            }
+        }
+        self.summary.divergences += 1;
+    }
+
+    fn print_book(&self, depth: usize, l3: bool) {
+        println!();
+        println!("Book after sequence {} ({} resting orders)", self.sequence, self.book.order_count());
+        for (name, side) in [("Asks", Side::Ask), ("Bids", Side::Bid)] {
+            println!("{}:", name);
+            if l3 {
+                let levels = match side {
+                    Side::Bid => self.book.get_bid_orders(depth),
+                    Side::Ask => self.book.get_ask_orders(depth),
+                };
+                for (price, orders) in levels {
+                    for order in orders {
+                        println!("  {:>16} {:>16}  {}", price, order.remaining_quantity, order.id);
+                    }
+                }
+            } else {
+                let levels = match side {
+                    Side::Bid => self.book.get_bids(depth),
+                    Side::Ask => self.book.get_asks(depth),
+                };
+                for (price, quantity) in levels {
+                    println!("  {:>16} {:>16}", price, quantity);
+                }
+            }
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+
+    let file = match File::open(&args.journal) {
+        Ok(file) => file,
+        Err(e) => {
+            eprintln!("Failed to open {}: {}", args.journal.display(), e);
+            return ExitCode::from(2);
+        }
+    };
+
+    let mut replay = Replay::new(args.fills);
+    for entry in JournalReader::new(BufReader::new(file)) {
+        match entry {
+            Ok(entry) => {
+                if !replay.apply(entry, args.until) {
+                    break;
+                }
+            }
+            // The engine died mid-write, or the file is corrupt from here on
+            Err(e) => {
+                println!("Stopped reading the journal: {}", e);
+                break;
+            }
+        }
+    }
+    replay.settle();
+    replay.print_book(args.depth, args.l3);
+
+    let summary = &replay.summary;
+    println!();
+    println!(
+        "Replayed {} commands in {} runs: {} fills, {} checksums verified, {} gaps, {} divergences",
+        summary.commands, summary.runs, summary.fills, summary.checksums_verified, summary.gaps, summary.divergences
+    );
+    if let Some(phase) = summary.phase {
+        println!("Market phase at the last checksum: {:?}", phase);
+    }
+
+    if summary.divergences > 0 {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
@@ -0,0 +1,225 @@
+//! Market data feed generated by the matching core.
+//!
+//! Every command that changes the book reports the price levels it touched,
+//! and once the command is done the feed emits their new quantities as a
+//! sequenced `OrderBookDelta`. Nothing is sampled or diffed on a timer, so
+//! every change is published at any depth. Snapshots are only built when
+//! someone asks for one and go through the same output stream as the
+//! deltas, so a snapshot with sequence N is followed by delta N + 1. They
+//! hold the whole book: a level that never changes is never sent as a delta,
+//! so a consumer only learns about it from a snapshot.
+
+use matching_engine::{OrderBook, Price, Quantity, Side as MatchingSide};
+
+use crate::engine::CoreOutput;
+use crate::events::{DeltaAction, LevelDelta, MarketEvent, PriceLevel, Side};
+
+/// Level changes per delta event, larger changes are split over several
+/// events with consecutive sequence numbers. Bounded by one datagram, so a
+/// lost packet never costs more than one delta.
+pub const MAX_DELTAS_PER_EVENT: usize = 20;
+
+pub struct BookFeed {
+    symbol: String,
+    /// Sequence of the last published delta
+    sequence: u64,
+    /// Levels changed by the current command, and whether each existed before it
+    touched: Vec<(MatchingSide, Price, bool)>,
+}
+
+impl BookFeed {
+    pub fn new(symbol: String) -> Self {
+        Self {
+            symbol,
+            sequence: 0,
+            touched: Vec::new(),
+        }
+    }
+
+    pub fn sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    /// Note a level about to change. `existed` is whether the level was on
+    /// the book before the current command.
+    pub fn touch(&mut self, side: MatchingSide, price: Price, existed: bool) {
+        self.touched.push((side, price, existed));
+    }
+
+    /// Publish the current quantity of every level touched since the last flush
+    pub fn flush(&mut self, orderbook: &OrderBook, out: &mut impl FnMut(CoreOutput)) {
+        if self.touched.is_empty() {
+            return;
+        }
+
+        // The first touch of a level saw it before the command, the stable
+        // sort keeps that one in front of later touches
+        let mut touched = std::mem::take(&mut self.touched);
+        touched.sort_by_key(|&(side, price, _)| (side == MatchingSide::Bid, price));
+        touched.dedup_by_key(|&mut (side, price, _)| (side, price));
+
+        let deltas: Vec<LevelDelta> = touched
+            .iter()
+            .filter_map(|&(side, price, existed)| {
+                let quantity = orderbook.quantity_at_price(side, price);
+                let action = match (existed, quantity.is_zero()) {
+                    (true, true) => DeltaAction::Remove,
+                    (true, false) => DeltaAction::Update,
+                    (false, false) => DeltaAction::Add,
+                    // Added and removed within the command (e.g. rested, then cancelled)
+                    (false, true) => return None,
+                };
+                Some(LevelDelta {
+                    action,
+                    side: side_of(side),
+                    price,
+                    quantity,
+                })
+            })
+            .collect();
+
+        for chunk in deltas.chunks(MAX_DELTAS_PER_EVENT) {
+            self.sequence += 1;
+            out(CoreOutput::Event(MarketEvent::OrderBookDelta {
+                symbol: self.symbol.clone(),
+                sequence: self.sequence,
+                deltas: chunk.to_vec(),
+            }));
+        }
+
+        // Keep the allocation for the next command
+        touched.clear();
+        self.touched = touched;
+    }
+
+    /// Every level of the book, tagged with the sequence of the last delta.
+    /// Snapshots are rare, the transport fragments them over several datagrams.
+    pub fn snapshot(&self, orderbook: &OrderBook) -> MarketEvent {
+        let levels = |levels: Vec<(Price, Quantity)>| {
+            levels
+                .into_iter()
+                .map(|(price, quantity)| PriceLevel { price, quantity })
+                .collect()
+        };
+
+        MarketEvent::OrderBookSnapshot {
+            symbol: self.symbol.clone(),
+            sequence: self.sequence,
+            bids: levels(orderbook.get_bids(usize::MAX)),
+            asks: levels(orderbook.get_asks(usize::MAX)),
+        }
+    }
+}
+
+fn side_of(side: MatchingSide) -> Side {
+    match side {
+        MatchingSide::Bid => Side::Bid,
+        MatchingSide::Ask => Side::Ask,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal::Decimal;
+    use uuid::Uuid;
+
+    fn deltas(outputs: &[CoreOutput]) -> Vec<(u64, Vec<LevelDelta>)> {
+        outputs
+            .iter()
+            .map(|o| match o {
+                CoreOutput::Event(MarketEvent::OrderBookDelta { sequence, deltas, .. }) => {
+                    (*sequence, deltas.clone())
+                }
+                other => panic!("expected delta, got {:?}", other),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_flush_reports_levels_once() {
+        let mut ob = OrderBook::new();
+        let mut feed = BookFeed::new("KCN/EUR".to_string());
+        let mut outputs = Vec::new();
+        let price = Decimal::from(100);
+
+        feed.touch(MatchingSide::Bid, price, false);
+        ob.add_limit_order(Uuid::new_v4(), MatchingSide::Bid, price, Decimal::ONE);
+        feed.touch(MatchingSide::Bid, price, true);
+        ob.add_limit_order(Uuid::new_v4(), MatchingSide::Bid, price, Decimal::ONE);
+        feed.flush(&ob, &mut |o| outputs.push(o));
+        // Nothing touched since, nothing to send
+        feed.flush(&ob, &mut |o| outputs.push(o));
+
+        let deltas = deltas(&outputs);
+        assert_eq!(deltas.len(), 1);
+        let (sequence, deltas) = &deltas[0];
+        assert_eq!(*sequence, 1);
+        assert_eq!(deltas.len(), 1);
+        assert_eq!(deltas[0].action, DeltaAction::Add);
+        assert_eq!(deltas[0].quantity, Decimal::from(2));
+    }
+
+    #[test]
+    fn test_large_changes_split_over_sequences() {
+        let mut ob = OrderBook::new();
+        let mut feed = BookFeed::new("KCN/EUR".to_string());
+        let mut outputs = Vec::new();
+
+        for price in 1..=(MAX_DELTAS_PER_EVENT as i64 + 2) {
+            feed.touch(MatchingSide::Ask, Decimal::from(price), false);
+            ob.add_limit_order(Uuid::new_v4(), MatchingSide::Ask, Decimal::from(price), Decimal::ONE);
+        }
+        feed.flush(&ob, &mut |o| outputs.push(o));
+
+        let deltas = deltas(&outputs);
+        assert_eq!(deltas.iter().map(|(s, _)| *s).collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(deltas[0].1.len(), MAX_DELTAS_PER_EVENT);
+        assert_eq!(deltas[1].1.len(), 2);
+
+        let MarketEvent::OrderBookSnapshot { sequence, asks, .. } = feed.snapshot(&ob) else {
+            panic!("expected snapshot");
+        };
+        assert_eq!(sequence, 2);
+        assert_eq!(asks.len(), MAX_DELTAS_PER_EVENT + 2);
+    }
+
+    #[test]
+    fn test_largest_events_fit_the_transport() {
+        let symbol = "KCN/EUR".to_string();
+        let level = |i: usize| PriceLevel {
+            price: Decimal::from(i),
+            quantity: Decimal::ONE,
+        };
+        // Deeper books than this exceed the largest message the transport takes
+        let snapshot_levels = 10_000;
+        let snapshot = MarketEvent::OrderBookSnapshot {
+            symbol: symbol.clone(),
+            sequence: u64::MAX,
+            bids: (0..snapshot_levels).map(level).collect(),
+            asks: (0..snapshot_levels).map(level).collect(),
+        };
+        let delta = MarketEvent::OrderBookDelta {
+            symbol,
+            sequence: u64::MAX,
+            deltas: (0..MAX_DELTAS_PER_EVENT)
+                .map(|i| LevelDelta {
+                    action: DeltaAction::Update,
+                    side: Side::Bid,
+                    price: Decimal::from(i),
+                    quantity: Decimal::ONE,
+                })
+                .collect(),
+        };
+
+        let mut encoder = udp_proto::binary::MarketEventEncoder::new();
+        for (event, max) in [
+            (snapshot, udp_proto::DEFAULT_MAX_MESSAGE_SIZE),
+            (delta, udp_proto::MAX_MESSAGE_PAYLOAD),
+        ] {
+            let binary = crate::udp_transport::UdpEventSender::convert_to_binary(&event);
+            let size = encoder.encode(&binary).len();
+            assert!(size <= max, "{:?} encodes to {} bytes", event, size);
+        }
+    }
+}
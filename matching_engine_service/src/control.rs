@@ -0,0 +1,58 @@
+//! Operator controls shared by the matching core and the admin API.
+//!
+//! Holds the trading phase of the market.
+
+use parking_lot::RwLock;
+
+use crate::events::MarketPhase;
+
+pub struct MarketControl {
+    phase: RwLock<MarketPhase>,
+}
+
+impl MarketControl {
+    pub fn new() -> Self {
+        Self {
+            phase: RwLock::new(MarketPhase::Open),
+        }
+    }
+
+    pub fn phase(&self) -> MarketPhase {
+        *self.phase.read()
+    }
+
+    /// New orders are only matched while the market is open.
+    /// Cancels are always accepted.
+    pub fn accepts_orders(&self) -> bool {
+        self.phase() == MarketPhase::Open
+    }
+
+    /// Switch the trading phase, returning the previous one
+    pub fn set_phase(&self, phase: MarketPhase) -> MarketPhase {
+        std::mem::replace(&mut *self.phase.write(), phase)
+    }
+
+    /// Switch the trading phase unless the market is delisted.
+    /// Returns the previous phase, or the current one if it can't be left.
+    pub fn transition(&self, phase: MarketPhase) -> Result<MarketPhase, MarketPhase> {
+        let mut current = self.phase.write();
+        if *current == MarketPhase::Delisted && phase != MarketPhase::Delisted {
+            return Err(*current);
+        }
+        Ok(std::mem::replace(&mut *current, phase))
+    }
+}
+
+impl Default for MarketControl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Current wall clock time in milliseconds since the Unix epoch
+pub fn now_millis() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
@@ -0,0 +1,315 @@
+//! Bounded deduplication window for order commands.
+//!
+//! UDP retries or a gateway resend can deliver the same command twice. The
+//! engine remembers the outcome of the last N commands it executed so that a
+//! duplicate can be acknowledged with the original result instead of being
+//! matched again.
+
+use matching_engine::OrderId;
+use rust_decimal::Decimal;
+use std::collections::{HashMap, VecDeque};
+
+use crate::events::OrderCommand;
+
+/// Default number of commands remembered by the window
+pub const DEFAULT_DEDUP_WINDOW: usize = 100_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CommandKind {
+    Place,
+    Cancel,
+}
+
+/// Identifies a command independent of the packet it arrived in.
+/// Place and cancel share the order ID, so the kind is part of the key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CommandKey {
+    pub kind: CommandKind,
+    pub order_id: OrderId,
+}
+
+impl CommandKey {
+    pub fn of(command: &OrderCommand) -> Self {
+        match command {
+            OrderCommand::PlaceOrder { order_id, .. } => Self {
+                kind: CommandKind::Place,
+                order_id: *order_id,
+            },
+            OrderCommand::CancelOrder { order_id, .. } => Self {
+                kind: CommandKind::Cancel,
+                order_id: *order_id,
+            },
+        }
+    }
+}
+
+/// Result of executing a command, kept so duplicates can be answered
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommandOutcome {
+    /// Order was accepted and matched
+    Placed {
+        /// Quantity filled while matching
+        filled_quantity: Decimal,
+        /// Order was 100% filled
+        completed: bool,
+        /// Unfilled remainder was cancelled (market orders)
+        cancelled: bool,
+    },
+    /// Cancel was processed; `cancelled` is false if the order was not on the book
+    Cancelled { cancelled: bool },
+    /// Command was not executed
+    Rejected { reason: String },
+}
+
+/// Fixed-size window of recently executed commands.
+/// Oldest entries are evicted first once the capacity is reached.
+pub struct CommandDeduplicator {
+    capacity: usize,
+    outcomes: HashMap<CommandKey, CommandOutcome>,
+    order: VecDeque<CommandKey>,
+    duplicates: u64,
+}
+
+impl CommandDeduplicator {
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        Self {
+            capacity,
+            outcomes: HashMap::with_capacity(capacity),
+            order: VecDeque::with_capacity(capacity),
+            duplicates: 0,
+        }
+    }
+
+    /// Look up the original outcome of a command seen before.
+    /// Counts the hit as a duplicate.
+    pub fn check(&mut self, command: &OrderCommand) -> Option<CommandOutcome> {
+        let outcome = self.outcomes.get(&CommandKey::of(command)).cloned();
+        if outcome.is_some() {
+            self.duplicates += 1;
+        }
+        outcome
+    }
+
+    /// Remember the outcome of an executed command
+    pub fn record(&mut self, command: &OrderCommand, outcome: CommandOutcome) {
+        self.record_key(CommandKey::of(command), outcome);
+    }
+
+    /// Remember an outcome by key, for commands known only by their journal record
+    pub fn record_key(&mut self, key: CommandKey, outcome: CommandOutcome) {
+        if self.outcomes.insert(key, outcome).is_some() {
+            // Outcome updated in place, keep its position in the window
+            return;
+        }
+
+        self.order.push_back(key);
+        while self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.outcomes.remove(&oldest);
+            }
+        }
+    }
+
+    /// Number of duplicate commands seen since startup
+    pub fn duplicates(&self) -> u64 {
+        self.duplicates
+    }
+}
+
+impl Default for CommandDeduplicator {
+    fn default() -> Self {
+        Self::new(DEFAULT_DEDUP_WINDOW)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::Side;
+    use crate::control::MarketControl;
+    use crate::engine::{CommandQueue, CoreCommand, CoreOutput, MatchingCore, QueuedCommand};
+    use crate::metrics::ServiceMetrics;
+    use crate::udp_transport::UdpOrderReceiver;
+    use std::net::{SocketAddr, UdpSocket};
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+    use udp_proto::binary::{OrderCommand as BinaryOrderCommand, OrderCommandEncoder, OrderType, Side as BinarySide};
+    use udp_proto::PacketBuilder;
+    use uuid::Uuid;
+
+    fn place(order_id: OrderId) -> OrderCommand {
+        OrderCommand::PlaceOrder {
+            order_id,
+            side: Side::Bid,
+            order_type: "limit".to_string(),
+            price: Some(Decimal::from(100)),
+            quantity: Decimal::from(1),
+            user_id: None,
+        }
+    }
+
+    fn cancel(order_id: OrderId) -> OrderCommand {
+        OrderCommand::CancelOrder {
+            order_id,
+            user_id: None,
+        }
+    }
+
+    fn free_addr() -> SocketAddr {
+        UdpSocket::bind("127.0.0.1:0").unwrap().local_addr().unwrap()
+    }
+
+    #[test]
+    fn test_duplicate_returns_original_outcome() {
+        let mut dedup = CommandDeduplicator::new(10);
+        let command = place(Uuid::new_v4());
+        let outcome = CommandOutcome::Placed {
+            filled_quantity: Decimal::ZERO,
+            completed: false,
+            cancelled: false,
+        };
+
+        assert_eq!(dedup.check(&command), None);
+        dedup.record(&command, outcome.clone());
+
+        assert_eq!(dedup.check(&command), Some(outcome));
+        assert_eq!(dedup.duplicates(), 1);
+    }
+
+    #[test]
+    fn test_place_and_cancel_are_distinct() {
+        let mut dedup = CommandDeduplicator::new(10);
+        let order_id = Uuid::new_v4();
+
+        dedup.record(
+            &place(order_id),
+            CommandOutcome::Placed {
+                filled_quantity: Decimal::ZERO,
+                completed: false,
+                cancelled: false,
+            },
+        );
+
+        assert_eq!(dedup.check(&cancel(order_id)), None);
+    }
+
+    #[test]
+    fn test_window_evicts_oldest() {
+        let mut dedup = CommandDeduplicator::new(2);
+        let first = cancel(Uuid::new_v4());
+        let second = cancel(Uuid::new_v4());
+        let third = cancel(Uuid::new_v4());
+
+        for command in [&first, &second, &third] {
+            dedup.record(command, CommandOutcome::Cancelled { cancelled: true });
+        }
+
+        assert_eq!(dedup.check(&first), None);
+        assert!(dedup.check(&second).is_some());
+        assert!(dedup.check(&third).is_some());
+    }
+
+    /// Gateway resends travel in fresh packets (new packet_seq), so the
+    /// transport passes them through and the window has to catch them.
+    /// Exact packet replays are already dropped by the receiver.
+    #[test]
+    fn test_replayed_udp_commands_execute_once() {
+        // The test thread plays the matching core, popping the ring itself
+        let order_addr = free_addr();
+        let (producer, mut ring) = rtrb::RingBuffer::new(16);
+        let _receiver =
+            UdpOrderReceiver::new(order_addr, CommandQueue::new(producer, std::thread::current()), None).unwrap();
+
+        let mut core = MatchingCore::new(
+            CommandDeduplicator::new(16),
+            Arc::new(MarketControl::new()),
+            Arc::new(ServiceMetrics::new()),
+            "KCN/EUR".to_string(),
+        );
+
+        let order_id = Uuid::new_v4();
+        let commands = [
+            BinaryOrderCommand::New {
+                order_id,
+                user_id: None,
+                side: BinarySide::Bid,
+                order_type: OrderType::Limit,
+                price: Some(Decimal::from(100)),
+                quantity: Decimal::from(1),
+            },
+            BinaryOrderCommand::Cancel {
+                order_id,
+                user_id: None,
+            },
+        ];
+
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let mut encoder = OrderCommandEncoder::new();
+        let mut packet_seq = 0;
+        let mut msg_seq = 0;
+        for command in &commands {
+            let payload = encoder.encode(command).to_vec();
+            let mut builder = PacketBuilder::new(1, packet_seq, msg_seq);
+            assert!(builder.try_add_message(command.message_type(), &payload));
+            let packet = builder.finish();
+
+            // Original, exact replay, then a resend in a new packet
+            socket.send_to(&packet, order_addr).unwrap();
+            socket.send_to(&packet, order_addr).unwrap();
+
+            let mut resend = PacketBuilder::new(1, packet_seq + 1, msg_seq + 1);
+            assert!(resend.try_add_message(command.message_type(), &payload));
+            socket.send_to(&resend.finish(), order_addr).unwrap();
+
+            packet_seq += 2;
+            msg_seq += 2;
+        }
+
+        let mut outcomes = Vec::new();
+        let mut placed_outputs = 0;
+        for _ in 0..4 {
+            let queued = next_command(&mut ring, Duration::from_secs(2)).expect("timed out waiting for command");
+            let CoreCommand::Order(command) = queued.command else {
+                panic!("expected an order command, got {:?}", queued.command);
+            };
+            let outcome = core.process(command, &mut |output| {
+                if matches!(output, CoreOutput::Placed { .. }) {
+                    placed_outputs += 1;
+                }
+            });
+            outcomes.push(outcome);
+
+            if outcomes.len() == 2 {
+                // Resend of the place must not add a second resting order
+                assert_eq!(core.dump(usize::MAX).order_count, 1);
+            }
+        }
+
+        // Exact replays were filtered by the transport
+        assert!(next_command(&mut ring, Duration::from_millis(100)).is_none());
+
+        let placed = CommandOutcome::Placed {
+            filled_quantity: Decimal::ZERO,
+            completed: false,
+            cancelled: false,
+        };
+        let cancelled = CommandOutcome::Cancelled { cancelled: true };
+        assert_eq!(outcomes, vec![placed.clone(), placed, cancelled.clone(), cancelled]);
+        assert_eq!(placed_outputs, 1);
+        assert_eq!(core.dump(usize::MAX).order_count, 0);
+    }
+
+    fn next_command(ring: &mut rtrb::Consumer<QueuedCommand>, timeout: Duration) -> Option<QueuedCommand> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if let Ok(queued) = ring.pop() {
+                return Some(queued);
+            }
+            if Instant::now() >= deadline {
+                return None;
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+}
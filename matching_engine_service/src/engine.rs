@@ -0,0 +1,1376 @@
+//! Single-threaded matching core.
+//!
+//! The order book is owned by one dedicated thread, pinned to a spare CPU. The UDP
+//! receiver feeds it through a bounded SPSC command ring and the core writes
+//! its results to an SPSC output ring, which the async post-trade task drains
+//! to settle fills and publish events. Book deltas are generated by the core
+//! as it mutates the book and travel through the same ring, so nothing ever
+//! takes a lock on the book.
+//!
+//! Admin requests (cancel-all, book dumps, snapshots, risk limits) are rare
+//! and come from many HTTP handlers, so they use a separate channel the core
+//! polls between commands.
+//!
+//! New orders pass the pre-trade risk checks (see `risk`) on the core,
+//! right before matching, where the open orders and the last trade price are
+//! known without any locking.
+//!
+//! Every command that reaches the book gets the next journal sequence. A
+//! primary sends it to its follower, a follower's core is fed the primary's
+//! journal instead of the order stream (see `replication`). Either can also
+//! record it to a file (see `recorder`).
+
+use crossbeam_channel::{Receiver, Sender};
+use matching_engine::{Fill, Order, OrderBook, OrderId, Price, Side as MatchingSide};
+use rtrb::{Consumer, Producer, PushError, RingBuffer};
+use rust_decimal::Decimal;
+use std::sync::Arc;
+use std::thread::{self, Thread};
+use std::time::{Duration, Instant};
+use tokio::sync::{oneshot, Notify};
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::book_feed::BookFeed;
+use crate::control::MarketControl;
+use crate::dedup::{CommandDeduplicator, CommandKey, CommandKind, CommandOutcome};
+use crate::events::{MarketEvent, MarketPhase, OrderCommand, Side};
+use crate::metrics::ServiceMetrics;
+use crate::recorder::JournalRecorder;
+use crate::replication::{JournalSender, CHECKSUM_INTERVAL};
+use crate::risk::{OrderOwners, OrderRisk, OwnerRisk, RiskControl, RiskEngine, RiskLimits, RiskViolation};
+
+const COMMAND_RING_CAPACITY: usize = 16_384;
+const OUTPUT_RING_CAPACITY: usize = 65_536;
+const ADMIN_CHANNEL_CAPACITY: usize = 64;
+
+/// How often the core samples the book gauges
+const GAUGE_INTERVAL: Duration = Duration::from_millis(100);
+/// Empty polls before a pinned core parks until a producer wakes it.
+/// Unpinned cores park right away, spinning only pays off on a CPU of its own.
+const SPIN_LIMIT: u32 = 10_000;
+
+/// Work arriving on the order stream
+#[derive(Debug)]
+pub enum CoreCommand {
+    Order(OrderCommand),
+    /// Publish a book snapshot, in order with the deltas around it
+    Snapshot { symbol: String },
+    /// A command from the primary's journal, already accepted there
+    Replicate { sequence: u64, command: OrderCommand },
+    /// A new order the primary refused, for the dedup window only
+    Rejected { order_id: OrderId, reason: String },
+    /// An operator's risk control change on the primary
+    RiskControl(RiskControl),
+    /// Compare the book with the primary's checksum at `sequence`, and
+    /// follow its trading phase
+    Verify {
+        sequence: u64,
+        hash: u64,
+        order_count: u64,
+        phase: MarketPhase,
+    },
+    /// Stop following and take over as primary
+    Promote,
+}
+
+/// A command with the time it was taken off the wire
+pub struct QueuedCommand {
+    pub command: CoreCommand,
+    pub received: Instant,
+}
+
+/// Producer side of the command ring, owned by the UDP receiver thread
+pub struct CommandQueue {
+    producer: Producer<QueuedCommand>,
+    core: Thread,
+}
+
+impl CommandQueue {
+    /// `core` is the thread consuming the ring, it is unparked on every push
+    pub fn new(producer: Producer<QueuedCommand>, core: Thread) -> Self {
+        Self { producer, core }
+    }
+
+    /// Enqueue an order command, waiting for space if the core falls behind
+    pub fn push(&mut self, command: OrderCommand) {
+        self.push_command(CoreCommand::Order(command));
+    }
+
+    /// Ask the core for a book snapshot
+    pub fn request_snapshot(&mut self, symbol: String) {
+        self.push_command(CoreCommand::Snapshot { symbol });
+    }
+
+    /// Apply the primary's journal entry `sequence`
+    pub fn replicate(&mut self, sequence: u64, command: OrderCommand) {
+        self.push_command(CoreCommand::Replicate { sequence, command });
+    }
+
+    /// Remember an order the primary refused
+    pub fn rejected(&mut self, order_id: OrderId, reason: String) {
+        self.push_command(CoreCommand::Rejected { order_id, reason });
+    }
+
+    /// Apply an operator's risk control change made on the primary
+    pub fn risk_control(&mut self, control: RiskControl) {
+        self.push_command(CoreCommand::RiskControl(control));
+    }
+
+    /// Check the book against the primary's checksum
+    pub fn verify(&mut self, sequence: u64, hash: u64, order_count: u64, phase: MarketPhase) {
+        self.push_command(CoreCommand::Verify {
+            sequence,
+            hash,
+            order_count,
+            phase,
+        });
+    }
+
+    /// Make the core primary, after everything queued so far
+    pub fn promote(&mut self) {
+        self.push_command(CoreCommand::Promote);
+    }
+
+    fn push_command(&mut self, command: CoreCommand) {
+        let mut queued = QueuedCommand {
+            command,
+            received: Instant::now(),
+        };
+        loop {
+            match self.producer.push(queued) {
+                Ok(()) => break,
+                Err(PushError::Full(rejected)) => {
+                    queued = rejected;
+                    self.core.unpark();
+                    thread::yield_now();
+                }
+            }
+        }
+        self.core.unpark();
+    }
+}
+
+/// Work the core hands to the post-trade task, in the order it happened
+#[derive(Debug)]
+pub enum CoreOutput {
+    /// Publish as is
+    Event(MarketEvent),
+    /// An order left the book before filling completely. Release what is
+    /// still reserved for it, then tell the owner.
+    Release {
+        order_id: OrderId,
+        filled_quantity: Decimal,
+    },
+    /// A new order went through matching, its fills are settled before
+    /// anything is published
+    Placed {
+        order_id: OrderId,
+        side: Side,
+        order_type: String,
+        price: Option<Decimal>,
+        quantity: Decimal,
+        fills: Vec<Fill>,
+        completed_orders: Vec<OrderId>,
+    },
+    /// A follower took over. Outputs before this one were the primary's to
+    /// settle and publish, everything after is ours.
+    Promoted,
+    /// A new order failed a risk check or reached a market that takes no
+    /// orders. Release its reservation and tell the owner why.
+    Reject { order_id: OrderId, reason: String },
+}
+
+/// Resting orders at one price, in time priority, with their owners
+#[derive(Debug, Clone)]
+pub struct DumpLevel {
+    pub price: Price,
+    pub orders: Vec<(Order, Option<Uuid>)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct BookDump {
+    pub order_count: usize,
+    pub bids: Vec<DumpLevel>,
+    pub asks: Vec<DumpLevel>,
+}
+
+pub enum AdminRequest {
+    /// Cancel all resting orders, or only those of one owner
+    CancelAll {
+        owner: Option<Uuid>,
+        reply: oneshot::Sender<Vec<OrderId>>,
+    },
+    /// Dump up to `depth` levels per side
+    Book {
+        depth: usize,
+        reply: oneshot::Sender<BookDump>,
+    },
+    /// Publish a book snapshot, replies with its sequence
+    Snapshot { reply: oneshot::Sender<u64> },
+    /// Limits and kill switch state of one owner
+    Risk {
+        owner: Uuid,
+        reply: oneshot::Sender<OwnerRisk>,
+    },
+    /// Override the limits of one owner, `None` goes back to the defaults
+    SetLimits {
+        owner: Uuid,
+        limits: Option<RiskLimits>,
+        reply: oneshot::Sender<OwnerRisk>,
+    },
+    /// Block new orders of an owner and cancel the resting ones
+    Kill {
+        owner: Uuid,
+        reply: oneshot::Sender<Vec<OrderId>>,
+    },
+    /// Lift the kill switch, replies whether it was set
+    Revive {
+        owner: Uuid,
+        reply: oneshot::Sender<bool>,
+    },
+}
+
+/// Order book plus everything needed to execute commands against it.
+/// Only ever touched by the core thread.
+pub struct MatchingCore {
+    orderbook: OrderBook,
+    dedup: CommandDeduplicator,
+    /// Owner of each resting order, the matching engine itself is anonymous
+    owners: OrderOwners,
+    risk: RiskEngine,
+    feed: BookFeed,
+    /// Journal sequence of the last command that reached the book
+    sequence: u64,
+    /// Sent to the follower while this core is primary
+    journal: Option<JournalSender>,
+    /// Journal file, written as primary and as follower
+    recorder: Option<JournalRecorder>,
+    /// Fills of the command being executed, for the recorder
+    recorded_fills: Vec<Fill>,
+    /// Fed by the primary's journal rather than the order stream
+    following: bool,
+    control: Arc<MarketControl>,
+    metrics: Arc<ServiceMetrics>,
+    symbol: String,
+}
+
+impl MatchingCore {
+    pub fn new(
+        dedup: CommandDeduplicator,
+        control: Arc<MarketControl>,
+        metrics: Arc<ServiceMetrics>,
+        symbol: String,
+    ) -> Self {
+        Self {
+            orderbook: OrderBook::new(),
+            dedup,
+            owners: OrderOwners::default(),
+            risk: RiskEngine::new(RiskLimits::default()),
+            feed: BookFeed::new(symbol.clone()),
+            sequence: 0,
+            journal: None,
+            recorder: None,
+            recorded_fills: Vec::new(),
+            following: false,
+            control,
+            metrics,
+            symbol,
+        }
+    }
+
+    /// Send executed commands and book checksums to a follower
+    pub fn with_journal(mut self, journal: JournalSender) -> Self {
+        self.journal = Some(journal);
+        self
+    }
+
+    /// Record executed commands, their fills and book checksums to a file
+    pub fn with_recorder(mut self, recorder: JournalRecorder) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
+    /// Check new orders against these limits unless overridden per owner
+    pub fn with_risk_limits(mut self, limits: RiskLimits) -> Self {
+        self.risk = RiskEngine::new(limits);
+        self
+    }
+
+    /// Start as a follower, until promoted
+    pub fn following(mut self) -> Self {
+        self.following = true;
+        self
+    }
+
+    /// Execute one command. Everything that must happen afterwards
+    /// (settlement, events) is handed to `out`.
+    pub fn process(&mut self, command: OrderCommand, out: &mut impl FnMut(CoreOutput)) -> CommandOutcome {
+        // Duplicates (UDP retries, gateway resends) are acked, never re-executed
+        if let Some(outcome) = self.dedup.check(&command) {
+            warn!(
+                "Duplicate order command {:?}, replying with original outcome {:?} ({} duplicates so far)",
+                command, outcome, self.dedup.duplicates()
+            );
+            self.ack_duplicate(&command, &outcome, out);
+            return outcome;
+        }
+
+        let outcome = match &command {
+            OrderCommand::PlaceOrder { order_id, .. } if !self.control.accepts_orders() => {
+                // Funds were reserved by the gateway, release them and tell the
+                // owner, the same answer a resend gets
+                warn!("Rejecting order {}: market {} is {:?}", order_id, self.symbol, self.control.phase());
+                let reason = format!("Market {}", self.control.phase().as_str());
+                out(CoreOutput::Reject {
+                    order_id: *order_id,
+                    reason: reason.clone(),
+                });
+                CommandOutcome::Rejected { reason }
+            }
+            OrderCommand::PlaceOrder {
+                order_id,
+                order_type,
+                price,
+                quantity,
+                user_id,
+                ..
+            } => {
+                let now = Instant::now();
+                let order = OrderRisk {
+                    owner: *user_id,
+                    price: price.filter(|_| !order_type.eq_ignore_ascii_case("market")),
+                    quantity: *quantity,
+                    open_orders: user_id.map_or(0, |owner| self.owners.open_orders(&owner)),
+                };
+                match self.risk.check(&order, now) {
+                    Ok(notional) => {
+                        let outcome = self.execute(&command, out);
+                        if let (CommandOutcome::Placed { .. }, Some(owner)) = (&outcome, user_id) {
+                            self.risk.record(*owner, notional, now);
+                        }
+                        outcome
+                    }
+                    Err(violation) => self.reject(*order_id, *user_id, violation, out),
+                }
+            }
+            _ => self.execute(&command, out),
+        };
+
+        self.dedup.record(&command, outcome.clone());
+        match &outcome {
+            // Off the book, but the follower must answer a resend the same way
+            CommandOutcome::Rejected { reason } => self.journal_rejection(&command, reason),
+            _ => {
+                self.sequence += 1;
+                self.append_journal(&command);
+            }
+        }
+        outcome
+    }
+
+    fn reject(
+        &mut self,
+        order_id: OrderId,
+        owner: Option<Uuid>,
+        violation: RiskViolation,
+        out: &mut impl FnMut(CoreOutput),
+    ) -> CommandOutcome {
+        match owner {
+            Some(owner) => warn!("Rejecting order {} of {}: {}", order_id, owner, violation),
+            None => warn!("Rejecting order {}: {}", order_id, violation),
+        }
+        self.metrics.record_risk_rejection(violation.check());
+        let reason = violation.to_string();
+        out(CoreOutput::Reject {
+            order_id,
+            reason: reason.clone(),
+        });
+        CommandOutcome::Rejected { reason }
+    }
+
+    /// Apply a command from the primary's journal. It was accepted there, so
+    /// the market phase and the dedup window are not consulted again.
+    pub fn replicate(&mut self, sequence: u64, command: OrderCommand, out: &mut impl FnMut(CoreOutput)) {
+        if sequence <= self.sequence {
+            warn!("Ignoring journal entry {}, already at {}", sequence, self.sequence);
+            return;
+        }
+        if sequence != self.sequence + 1 {
+            // Nothing to recover from without a state transfer, the next
+            // checksum will fail as well
+            error!(
+                "Journal gap: expected entry {}, got {}, replica diverged from primary",
+                self.sequence + 1,
+                sequence
+            );
+            self.metrics.record_journal_gap(sequence - self.sequence - 1);
+        }
+
+        let outcome = self.execute(&command, out);
+        self.dedup.record(&command, outcome);
+        self.sequence = sequence;
+        self.append_journal(&command);
+    }
+
+    /// Remember a new order the primary refused, so a resend reaching this
+    /// replica after promotion is rejected again rather than placed
+    pub fn replicate_rejection(&mut self, order_id: OrderId, reason: String) {
+        let key = CommandKey {
+            kind: CommandKind::Place,
+            order_id,
+        };
+        self.dedup.record_key(key, CommandOutcome::Rejected { reason });
+    }
+
+    /// Apply the primary's risk control change. The cancels of a kill arrive
+    /// as journal entries of their own.
+    pub fn replicate_risk_control(&mut self, control: RiskControl) {
+        info!("Primary changed risk controls: {:?}", control);
+        self.risk.apply(&control);
+    }
+
+    /// Compare the book against the primary's checksum for the same sequence.
+    /// The phase is taken over as is, a promoted follower keeps a halt in place.
+    pub fn verify(&self, sequence: u64, hash: u64, order_count: u64, phase: MarketPhase) -> bool {
+        if self.control.phase() != phase {
+            info!("Primary switched market {} to {:?}", self.symbol, phase);
+            self.control.set_phase(phase);
+        }
+
+        let matches = sequence == self.sequence
+            && order_count == self.orderbook.order_count() as u64
+            && hash == self.orderbook.state_hash();
+        if matches {
+            self.metrics.record_checksum(true);
+        } else {
+            error!(
+                "Book checksum mismatch: primary at {} has {} orders (hash {:016x}), replica at {} has {} (hash {:016x})",
+                sequence,
+                order_count,
+                hash,
+                self.sequence,
+                self.orderbook.order_count(),
+                self.orderbook.state_hash()
+            );
+            self.metrics.record_checksum(false);
+        }
+        matches
+    }
+
+    /// Take over as primary: publish from here on, starting with a snapshot
+    /// so subscribers resync with this replica's book
+    pub fn promote(&mut self, out: &mut impl FnMut(CoreOutput)) {
+        if !self.following {
+            return;
+        }
+        info!("Matching core promoted to primary at journal sequence {}", self.sequence);
+        self.following = false;
+        self.metrics.set_primary(true);
+        out(CoreOutput::Promoted);
+        self.snapshot(out);
+    }
+
+    /// Journal the command that just got `self.sequence`
+    fn append_journal(&mut self, command: &OrderCommand) {
+        if let Some(recorder) = &mut self.recorder {
+            recorder.command(self.sequence, command, &self.recorded_fills);
+            self.recorded_fills.clear();
+        }
+        if self.following {
+            return;
+        }
+        if let Some(journal) = &mut self.journal {
+            journal.append(self.sequence, command);
+        }
+    }
+
+    /// Tell the follower about a refused command. It takes no sequence, only
+    /// the dedup window changed.
+    fn journal_rejection(&mut self, command: &OrderCommand, reason: &str) {
+        if self.following {
+            return;
+        }
+        if let Some(journal) = &mut self.journal {
+            journal.rejected(self.sequence, CommandKey::of(command).order_id, reason);
+        }
+    }
+
+    /// Tell the follower and the journal file what the book should look like by now
+    fn send_checksum(&mut self) {
+        let sending = !self.following && self.journal.is_some();
+        if !sending && self.recorder.is_none() {
+            return;
+        }
+        // Walks every resting order, once per CHECKSUM_INTERVAL
+        let hash = self.orderbook.state_hash();
+        let order_count = self.orderbook.order_count() as u64;
+        let phase = self.control.phase();
+
+        if let Some(recorder) = &mut self.recorder {
+            recorder.checksum(self.sequence, hash, order_count, phase);
+        }
+        if !sending {
+            return;
+        }
+        if let Some(journal) = &mut self.journal {
+            journal.checksum(self.sequence, hash, order_count, phase);
+        }
+    }
+
+    /// Run a command against the book
+    fn execute(&mut self, command: &OrderCommand, out: &mut impl FnMut(CoreOutput)) -> CommandOutcome {
+        let outcome = match command {
+            OrderCommand::PlaceOrder {
+                order_id,
+                side,
+                order_type,
+                price,
+                quantity,
+                user_id,
+            } => self.place(*order_id, *side, order_type, *price, *quantity, *user_id, out),
+            OrderCommand::CancelOrder { order_id, .. } => {
+                let order_id = *order_id;
+                let cancelled = self.cancel_resting(order_id).is_some();
+                self.owners.remove(&order_id);
+
+                if cancelled {
+                    out(CoreOutput::Event(MarketEvent::OrderCancelled {
+                        order_id,
+                        // For manual cancellations, we don't track filled quantity here
+                        // The accounts service has this info from partial fill updates
+                        filled_quantity: Decimal::ZERO,
+                    }));
+                }
+
+                CommandOutcome::Cancelled { cancelled }
+            }
+        };
+
+        self.feed.flush(&self.orderbook, out);
+        outcome
+    }
+
+    /// Publish a snapshot of the book, returns its sequence
+    pub fn snapshot(&self, out: &mut impl FnMut(CoreOutput)) -> u64 {
+        out(CoreOutput::Event(self.feed.snapshot(&self.orderbook)));
+        self.feed.sequence()
+    }
+
+    /// Remove a resting order, recording the level it leaves for the feed
+    fn cancel_resting(&mut self, order_id: OrderId) -> Option<Order> {
+        let order = self.orderbook.get_order(order_id)?.clone();
+        if let Some(price) = order.price {
+            self.feed.touch(order.side, price, true);
+        }
+        self.orderbook.cancel_order(order_id).then_some(order)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn place(
+        &mut self,
+        order_id: OrderId,
+        side: Side,
+        order_type: &str,
+        price: Option<Decimal>,
+        quantity: Decimal,
+        user_id: Option<Uuid>,
+        out: &mut impl FnMut(CoreOutput),
+    ) -> CommandOutcome {
+        if self.orderbook.order_exists(order_id) {
+            // Already resting but evicted from the dedup window
+            warn!("Rejecting order {}: order ID already on the book", order_id);
+            return CommandOutcome::Rejected {
+                reason: "Duplicate order ID".to_string(),
+            };
+        }
+
+        let matching_side = match side {
+            Side::Bid => MatchingSide::Bid,
+            Side::Ask => MatchingSide::Ask,
+        };
+        let is_market = order_type.eq_ignore_ascii_case("market");
+        let result = if is_market {
+            self.orderbook.add_market_order(order_id, matching_side, quantity)
+        } else {
+            let Some(price) = price else {
+                warn!("Rejecting order {}: limit order requires price", order_id);
+                return CommandOutcome::Rejected {
+                    reason: "Limit order requires price".to_string(),
+                };
+            };
+            let existed = !self.orderbook.quantity_at_price(matching_side, price).is_zero();
+            let result = self.orderbook.add_limit_order(order_id, matching_side, price, quantity);
+            if self.orderbook.order_exists(order_id) {
+                self.feed.touch(matching_side, price, existed);
+            }
+            result
+        };
+
+        // Every fill took quantity from a resting level on the other side
+        let resting_side = match matching_side {
+            MatchingSide::Bid => MatchingSide::Ask,
+            MatchingSide::Ask => MatchingSide::Bid,
+        };
+        for fill in &result.fills {
+            self.feed.touch(resting_side, fill.price, true);
+        }
+        if let Some(last) = result.fills.last() {
+            self.risk.observe_trade(last.price);
+        }
+        if self.recorder.is_some() {
+            self.recorded_fills.extend_from_slice(&result.fills);
+        }
+
+        if let Some(owner) = user_id {
+            if self.orderbook.order_exists(order_id) {
+                self.owners.insert(order_id, owner);
+            }
+        }
+        for completed_order_id in &result.completed_orders {
+            self.owners.remove(completed_order_id);
+        }
+
+        // Settlement happens after the core, duplicates are acked with the matched quantity
+        let filled_quantity: Decimal = result.fills.iter().map(|f| f.quantity).sum();
+        let completed = result.completed_orders.contains(&order_id);
+        let cancelled = is_market && filled_quantity < quantity;
+
+        out(CoreOutput::Placed {
+            order_id,
+            side,
+            order_type: order_type.to_string(),
+            price,
+            quantity,
+            fills: result.fills,
+            completed_orders: result.completed_orders,
+        });
+
+        CommandOutcome::Placed {
+            filled_quantity,
+            completed,
+            cancelled,
+        }
+    }
+
+    pub fn handle_admin(&mut self, request: AdminRequest, out: &mut impl FnMut(CoreOutput)) {
+        match request {
+            AdminRequest::CancelAll { owner, reply } => {
+                let cancelled = self.cancel_all(owner, out);
+                // The HTTP handler may have given up waiting, the cancels stand regardless
+                let _ = reply.send(cancelled);
+            }
+            AdminRequest::Book { depth, reply } => {
+                let _ = reply.send(self.dump(depth));
+            }
+            AdminRequest::Snapshot { reply } => {
+                let _ = reply.send(self.snapshot(out));
+            }
+            AdminRequest::Risk { owner, reply } => {
+                let _ = reply.send(self.owner_risk(owner));
+            }
+            AdminRequest::SetLimits { owner, limits, reply } => {
+                self.control_risk(RiskControl::SetLimits { owner, limits });
+                let _ = reply.send(self.owner_risk(owner));
+            }
+            AdminRequest::Kill { owner, reply } => {
+                // Blocked first, nothing gets in between the cancels
+                self.control_risk(RiskControl::Kill { owner });
+                let cancelled = self.cancel_all(Some(owner), out);
+                let _ = reply.send(cancelled);
+            }
+            AdminRequest::Revive { owner, reply } => {
+                let _ = reply.send(self.control_risk(RiskControl::Revive { owner }));
+            }
+        }
+    }
+
+    /// Apply an operator's risk control change and journal it, so a promoted
+    /// follower enforces the same. Returns false if it changed nothing.
+    fn control_risk(&mut self, control: RiskControl) -> bool {
+        let changed = self.risk.apply(&control);
+        if !self.following {
+            if let Some(journal) = &mut self.journal {
+                journal.risk_control(self.sequence, &control);
+            }
+        }
+        changed
+    }
+
+    /// Cancel resting orders, of one owner or all of them
+    fn cancel_all(&mut self, owner: Option<Uuid>, out: &mut impl FnMut(CoreOutput)) -> Vec<OrderId> {
+        let candidates: Vec<OrderId> = match owner {
+            Some(owner) => self.owners.orders_of(&owner),
+            None => self
+                .orderbook
+                .get_bid_orders(usize::MAX)
+                .into_iter()
+                .chain(self.orderbook.get_ask_orders(usize::MAX))
+                .flat_map(|(_, orders)| orders.into_iter().map(|o| o.id))
+                .collect(),
+        };
+
+        let mut cancelled = Vec::with_capacity(candidates.len());
+        for order_id in candidates {
+            if let Some(order) = self.cancel_resting(order_id) {
+                self.owners.remove(&order_id);
+                out(CoreOutput::Release {
+                    order_id,
+                    filled_quantity: order.quantity - order.remaining_quantity,
+                });
+                cancelled.push(order_id);
+                // The follower replays it as an ordinary cancel
+                self.sequence += 1;
+                self.append_journal(&OrderCommand::CancelOrder { order_id, user_id: None });
+            }
+        }
+        self.feed.flush(&self.orderbook, out);
+        cancelled
+    }
+
+    fn owner_risk(&self, owner: Uuid) -> OwnerRisk {
+        OwnerRisk {
+            owner,
+            limits: self.risk.limits(&owner).clone(),
+            overridden: self.risk.has_override(&owner),
+            killed: self.risk.is_killed(&owner),
+            open_orders: self.owners.open_orders(&owner),
+        }
+    }
+
+    pub fn dump(&self, depth: usize) -> BookDump {
+        let with_owners = |levels: Vec<(Price, Vec<Order>)>| {
+            levels
+                .into_iter()
+                .map(|(price, orders)| DumpLevel {
+                    price,
+                    orders: orders
+                        .into_iter()
+                        .map(|o| {
+                            let owner = self.owners.get(&o.id);
+                            (o, owner)
+                        })
+                        .collect(),
+                })
+                .collect()
+        };
+
+        BookDump {
+            order_count: self.orderbook.order_count(),
+            bids: with_owners(self.orderbook.get_bid_orders(depth)),
+            asks: with_owners(self.orderbook.get_ask_orders(depth)),
+        }
+    }
+
+    /// Sample book gauges, scrapes never reach into the core
+    fn observe_book(&self) {
+        self.metrics.observe_book(&self.symbol, &self.orderbook);
+        self.metrics.observe_journal(self.sequence);
+    }
+
+    /// Re-send the order status events of a command that was already executed.
+    /// Fills are never repeated - only the idempotent per-order status events.
+    fn ack_duplicate(&self, command: &OrderCommand, outcome: &CommandOutcome, out: &mut impl FnMut(CoreOutput)) {
+        match (command, outcome) {
+            (
+                OrderCommand::PlaceOrder { order_id, side, order_type, price, quantity, .. },
+                CommandOutcome::Placed { filled_quantity, completed, cancelled },
+            ) => {
+                out(CoreOutput::Event(MarketEvent::OrderAccepted {
+                    order_id: *order_id,
+                    side: *side,
+                    order_type: order_type.clone(),
+                    price: *price,
+                    quantity: *quantity,
+                }));
+                if *completed {
+                    out(CoreOutput::Event(MarketEvent::OrderFilled { order_id: *order_id }));
+                } else if *cancelled {
+                    out(CoreOutput::Event(MarketEvent::OrderCancelled {
+                        order_id: *order_id,
+                        filled_quantity: *filled_quantity,
+                    }));
+                }
+            }
+            (OrderCommand::CancelOrder { order_id, .. }, CommandOutcome::Cancelled { cancelled: true }) => {
+                out(CoreOutput::Event(MarketEvent::OrderCancelled {
+                    order_id: *order_id,
+                    filled_quantity: Decimal::ZERO,
+                }));
+            }
+            (OrderCommand::PlaceOrder { order_id, .. }, CommandOutcome::Rejected { reason })
+                // A resend of an order still resting was refused as a duplicate ID, the order stands
+                if !self.orderbook.order_exists(*order_id) =>
+            {
+                // The reservation was released the first time, only the owner is told again
+                out(CoreOutput::Event(MarketEvent::OrderRejected {
+                    order_id: *order_id,
+                    reason: reason.clone(),
+                }));
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Admin side of the core, cheap to clone into HTTP handlers
+#[derive(Clone)]
+pub struct CoreHandle {
+    admin: Sender<AdminRequest>,
+    core: Thread,
+}
+
+impl CoreHandle {
+    async fn request<T>(&self, make: impl FnOnce(oneshot::Sender<T>) -> AdminRequest) -> anyhow::Result<T> {
+        let (reply, response) = oneshot::channel();
+        self.admin
+            .try_send(make(reply))
+            .map_err(|e| anyhow::anyhow!("Matching core not accepting admin requests: {}", e))?;
+        self.core.unpark();
+        response
+            .await
+            .map_err(|_| anyhow::anyhow!("Matching core stopped"))
+    }
+
+    /// Cancel resting orders, returns the IDs that were removed
+    pub async fn cancel_all(&self, owner: Option<Uuid>) -> anyhow::Result<Vec<OrderId>> {
+        self.request(|reply| AdminRequest::CancelAll { owner, reply }).await
+    }
+
+    pub async fn book(&self, depth: usize) -> anyhow::Result<BookDump> {
+        self.request(|reply| AdminRequest::Book { depth, reply }).await
+    }
+
+    /// Publish a book snapshot, returns its sequence
+    pub async fn snapshot(&self) -> anyhow::Result<u64> {
+        self.request(|reply| AdminRequest::Snapshot { reply }).await
+    }
+
+    pub async fn risk(&self, owner: Uuid) -> anyhow::Result<OwnerRisk> {
+        self.request(|reply| AdminRequest::Risk { owner, reply }).await
+    }
+
+    pub async fn set_limits(&self, owner: Uuid, limits: Option<RiskLimits>) -> anyhow::Result<OwnerRisk> {
+        self.request(|reply| AdminRequest::SetLimits { owner, limits, reply }).await
+    }
+
+    /// Trip the kill switch, returns the IDs of the cancelled orders
+    pub async fn kill(&self, owner: Uuid) -> anyhow::Result<Vec<OrderId>> {
+        self.request(|reply| AdminRequest::Kill { owner, reply }).await
+    }
+
+    /// Lift the kill switch, returns false if it was not set
+    pub async fn revive(&self, owner: Uuid) -> anyhow::Result<bool> {
+        self.request(|reply| AdminRequest::Revive { owner, reply }).await
+    }
+}
+
+/// Consumer side of the core's output ring
+pub struct CoreOutputs {
+    pub outputs: Consumer<CoreOutput>,
+    /// Notified whenever new outputs were pushed
+    pub ready: Arc<Notify>,
+}
+
+/// Start the core thread, optionally pinned to `cpu`
+pub fn spawn(core: MatchingCore, cpu: Option<usize>) -> anyhow::Result<(CommandQueue, CoreHandle, CoreOutputs)> {
+    let (command_tx, command_rx) = RingBuffer::new(COMMAND_RING_CAPACITY);
+    let (output_tx, output_rx) = RingBuffer::new(OUTPUT_RING_CAPACITY);
+    let (admin_tx, admin_rx) = crossbeam_channel::bounded(ADMIN_CHANNEL_CAPACITY);
+    let ready = Arc::new(Notify::new());
+
+    let runner = CoreRunner {
+        core,
+        commands: command_rx,
+        admin: admin_rx,
+        outputs: output_tx,
+        ready: ready.clone(),
+    };
+    let handle = thread::Builder::new()
+        .name("matching-core".to_string())
+        .spawn(move || {
+            let spin_limit = if pin_to_cpu(cpu) { SPIN_LIMIT } else { 0 };
+            runner.run(spin_limit);
+        })?;
+    let thread = handle.thread().clone();
+
+    Ok((
+        CommandQueue::new(command_tx, thread.clone()),
+        CoreHandle {
+            admin: admin_tx,
+            core: thread,
+        },
+        CoreOutputs {
+            outputs: output_rx,
+            ready,
+        },
+    ))
+}
+
+/// Last CPU of the machine, keeping CPU 0 for the runtime and the kernel.
+/// None on single CPU hosts, where a spinning core would starve everything else.
+pub fn default_cpu() -> Option<usize> {
+    let ids = core_affinity::get_core_ids()?;
+    if ids.len() < 2 {
+        return None;
+    }
+    ids.last().map(|c| c.id)
+}
+
+/// Pin the calling thread, returns whether it worked
+fn pin_to_cpu(cpu: Option<usize>) -> bool {
+    let Some(cpu) = cpu else {
+        info!("Matching core not pinned");
+        return false;
+    };
+    let core_id = core_affinity::get_core_ids().and_then(|ids| ids.into_iter().find(|c| c.id == cpu));
+    match core_id {
+        Some(core_id) if core_affinity::set_for_current(core_id) => {
+            info!("Matching core pinned to CPU {}", cpu);
+            true
+        }
+        _ => {
+            warn!("Failed to pin matching core to CPU {}, running unpinned", cpu);
+            false
+        }
+    }
+}
+
+struct CoreRunner {
+    core: MatchingCore,
+    commands: Consumer<QueuedCommand>,
+    admin: Receiver<AdminRequest>,
+    outputs: Producer<CoreOutput>,
+    ready: Arc<Notify>,
+}
+
+impl CoreRunner {
+    fn run(mut self, spin_limit: u32) {
+        info!("Matching core started");
+        // Subscribers that were up before the engine start from the empty book
+        let (outputs, ready) = (&mut self.outputs, &*self.ready);
+        self.core.snapshot(&mut |output| push_output(outputs, ready, output));
+        self.ready.notify_one();
+
+        let mut next_gauges = Instant::now();
+        let mut next_checksum = Instant::now() + CHECKSUM_INTERVAL;
+        let mut idle_polls = 0u32;
+
+        loop {
+            let mut busy = self.drain_commands();
+            if let Ok(request) = self.admin.try_recv() {
+                // Commands enqueued before the request must be visible to it
+                self.drain_commands();
+                let (outputs, ready) = (&mut self.outputs, &*self.ready);
+                self.core.handle_admin(request, &mut |output| push_output(outputs, ready, output));
+                busy = true;
+            }
+            if busy {
+                self.ready.notify_one();
+            }
+
+            let now = Instant::now();
+            if now >= next_gauges {
+                self.core.observe_book();
+                next_gauges = now + GAUGE_INTERVAL;
+            }
+            if now >= next_checksum {
+                self.core.send_checksum();
+                next_checksum = now + CHECKSUM_INTERVAL;
+            }
+
+            if busy {
+                idle_polls = 0;
+                continue;
+            }
+            if self.commands.is_abandoned() {
+                info!("Command ring closed, matching core stopping");
+                break;
+            }
+            if idle_polls < spin_limit {
+                idle_polls += 1;
+                std::hint::spin_loop();
+            } else {
+                thread::park_timeout(next_gauges.saturating_duration_since(Instant::now()));
+            }
+        }
+    }
+
+    fn drain_commands(&mut self) -> bool {
+        let mut any = false;
+        while let Ok(queued) = self.commands.pop() {
+            any = true;
+            let (outputs, ready) = (&mut self.outputs, &*self.ready);
+            let mut out = |output| push_output(outputs, ready, output);
+            let kind = match queued.command {
+                CoreCommand::Order(command) => {
+                    let kind = match command {
+                        OrderCommand::PlaceOrder { .. } => "place",
+                        OrderCommand::CancelOrder { .. } => "cancel",
+                    };
+                    self.core.process(command, &mut out);
+                    kind
+                }
+                CoreCommand::Snapshot { symbol } => {
+                    if symbol == self.core.symbol {
+                        self.core.snapshot(&mut out);
+                    } else {
+                        warn!("Ignoring snapshot request for unknown symbol {}", symbol);
+                    }
+                    "snapshot"
+                }
+                CoreCommand::Replicate { sequence, command } => {
+                    self.core.replicate(sequence, command, &mut out);
+                    "replicate"
+                }
+                CoreCommand::Rejected { order_id, reason } => {
+                    self.core.replicate_rejection(order_id, reason);
+                    "replicate"
+                }
+                CoreCommand::RiskControl(control) => {
+                    self.core.replicate_risk_control(control);
+                    "replicate"
+                }
+                CoreCommand::Verify {
+                    sequence,
+                    hash,
+                    order_count,
+                    phase,
+                } => {
+                    self.core.verify(sequence, hash, order_count, phase);
+                    "verify"
+                }
+                CoreCommand::Promote => {
+                    self.core.promote(&mut out);
+                    "promote"
+                }
+            };
+            self.core
+                .metrics
+                .observe_command(kind, queued.received.elapsed().as_secs_f64());
+        }
+        any
+    }
+}
+
+/// Results are never dropped, wait for the post-trade task to make room
+fn push_output(outputs: &mut Producer<CoreOutput>, ready: &Notify, mut output: CoreOutput) {
+    loop {
+        match outputs.push(output) {
+            Ok(()) => return,
+            Err(PushError::Full(rejected)) => {
+                if outputs.is_abandoned() {
+                    error!("Post-trade task gone, dropping {:?}", rejected);
+                    return;
+                }
+                output = rejected;
+                ready.notify_one();
+                thread::yield_now();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::DeltaAction;
+
+    fn core() -> MatchingCore {
+        MatchingCore::new(
+            CommandDeduplicator::new(16),
+            Arc::new(MarketControl::new()),
+            Arc::new(ServiceMetrics::new()),
+            "KCN/EUR".to_string(),
+        )
+    }
+
+    fn limit(side: Side, price: i64, owner: Uuid) -> OrderCommand {
+        OrderCommand::PlaceOrder {
+            order_id: Uuid::new_v4(),
+            side,
+            order_type: "limit".to_string(),
+            price: Some(Decimal::from(price)),
+            quantity: Decimal::from(1),
+            user_id: Some(owner),
+        }
+    }
+
+    #[test]
+    fn test_place_hands_fills_to_post_trade() {
+        let mut core = core();
+        let mut outputs = Vec::new();
+        let mut out = |o| outputs.push(o);
+
+        let ask = limit(Side::Ask, 100, Uuid::new_v4());
+        core.process(ask, &mut out);
+        let outcome = core.process(limit(Side::Bid, 100, Uuid::new_v4()), &mut out);
+
+        assert_eq!(
+            outcome,
+            CommandOutcome::Placed {
+                filled_quantity: Decimal::from(1),
+                completed: true,
+                cancelled: false,
+            }
+        );
+        // Book deltas are interleaved with the placements
+        let placed: Vec<&CoreOutput> = outputs.iter().filter(|o| matches!(o, CoreOutput::Placed { .. })).collect();
+        let CoreOutput::Placed { fills, completed_orders, .. } = placed[1] else {
+            panic!("expected placed output, got {:?}", placed[1]);
+        };
+        assert_eq!(fills.len(), 1);
+        assert_eq!(completed_orders.len(), 2);
+        assert!(core.owners.is_empty());
+    }
+
+    #[test]
+    fn test_risk_check_rejects_before_matching() {
+        let mut core = core().with_risk_limits(RiskLimits {
+            max_order_quantity: Some(Decimal::from(1)),
+            price_band_percent: Some(Decimal::from(5)),
+            ..Default::default()
+        });
+        let owner = Uuid::new_v4();
+        let mut outputs = Vec::new();
+        let mut out = |o| outputs.push(o);
+
+        core.process(limit(Side::Ask, 100, owner), &mut out);
+        core.process(limit(Side::Bid, 100, Uuid::new_v4()), &mut out);
+        // The trade at 100 anchors the price band
+        let far = limit(Side::Bid, 106, owner);
+        let OrderCommand::PlaceOrder { order_id, .. } = far else { unreachable!() };
+        let outcome = core.process(far, &mut out);
+
+        assert!(matches!(outcome, CommandOutcome::Rejected { .. }));
+        assert!(matches!(
+            outputs.last(),
+            Some(CoreOutput::Reject { order_id: rejected, reason }) if *rejected == order_id && reason.contains("5%")
+        ));
+        assert_eq!(core.orderbook.order_count(), 0);
+        // Rejections never reach the journal
+        assert_eq!(core.sequence, 2);
+    }
+
+    #[test]
+    fn test_halted_core_releases_reservation() {
+        let mut core = core();
+        core.control.set_phase(crate::events::MarketPhase::Halted);
+        let mut outputs = Vec::new();
+
+        let outcome = core.process(limit(Side::Bid, 100, Uuid::new_v4()), &mut |o| outputs.push(o));
+
+        assert!(matches!(outcome, CommandOutcome::Rejected { .. }));
+        assert!(matches!(
+            outputs.as_slice(),
+            [CoreOutput::Reject { reason, .. }] if reason == "Market halted"
+        ));
+        assert_eq!(core.orderbook.order_count(), 0);
+    }
+
+    #[test]
+    fn test_duplicate_of_rejected_order_rejected_again() {
+        let mut core = core();
+        core.control.set_phase(crate::events::MarketPhase::Halted);
+        let order = limit(Side::Bid, 100, Uuid::new_v4());
+        let OrderCommand::PlaceOrder { order_id, .. } = order else { unreachable!() };
+        core.process(order.clone(), &mut |_| {});
+
+        // The first reply got lost, the retry is answered without releasing twice
+        let mut outputs = Vec::new();
+        let outcome = core.process(order, &mut |o| outputs.push(o));
+
+        assert!(matches!(outcome, CommandOutcome::Rejected { .. }));
+        assert!(matches!(
+            outputs.as_slice(),
+            [CoreOutput::Event(MarketEvent::OrderRejected { order_id: rejected, reason })]
+                if *rejected == order_id && reason == "Market halted"
+        ));
+    }
+
+    #[test]
+    fn test_replicated_rejection_refuses_resend_after_promotion() {
+        let mut core = core().following();
+        let order = limit(Side::Bid, 100, Uuid::new_v4());
+        let OrderCommand::PlaceOrder { order_id, .. } = order else { unreachable!() };
+        core.replicate_rejection(order_id, "Market halted".to_string());
+        core.promote(&mut |_| {});
+
+        // The primary refused it while halted, the market here is open
+        let mut outputs = Vec::new();
+        let outcome = core.process(order, &mut |o| outputs.push(o));
+
+        assert_eq!(
+            outcome,
+            CommandOutcome::Rejected {
+                reason: "Market halted".to_string()
+            }
+        );
+        assert_eq!(core.orderbook.order_count(), 0);
+        assert!(matches!(
+            outputs.as_slice(),
+            [CoreOutput::Event(MarketEvent::OrderRejected { order_id: rejected, .. })] if *rejected == order_id
+        ));
+    }
+
+    #[test]
+    fn test_order_without_owner_checked() {
+        let mut core = core().with_risk_limits(RiskLimits {
+            max_order_quantity: Some(Decimal::from(1)),
+            ..Default::default()
+        });
+        let mut order = limit(Side::Bid, 100, Uuid::new_v4());
+        if let OrderCommand::PlaceOrder { user_id, quantity, .. } = &mut order {
+            *user_id = None;
+            *quantity = Decimal::from(2);
+        }
+        let mut outputs = Vec::new();
+
+        let outcome = core.process(order, &mut |o| outputs.push(o));
+
+        assert!(matches!(outcome, CommandOutcome::Rejected { .. }));
+        assert!(matches!(outputs.as_slice(), [CoreOutput::Reject { .. }]));
+        assert_eq!(core.orderbook.order_count(), 0);
+    }
+
+    #[test]
+    fn test_replicated_kill_switch_survives_promotion() {
+        let mut core = core().following();
+        let owner = Uuid::new_v4();
+        core.replicate_risk_control(RiskControl::Kill { owner });
+        core.promote(&mut |_| {});
+
+        let outcome = core.process(limit(Side::Bid, 100, owner), &mut |_| {});
+
+        assert_eq!(
+            outcome,
+            CommandOutcome::Rejected {
+                reason: RiskViolation::KillSwitch.to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_book_changes_published_as_deltas() {
+        let mut core = core();
+        let mut outputs = Vec::new();
+        let mut out = |o| outputs.push(o);
+
+        for price in [101, 102] {
+            core.process(limit(Side::Ask, price, Uuid::new_v4()), &mut out);
+        }
+        // Sweeps 101 and rests the remainder at 102
+        core.process(
+            OrderCommand::PlaceOrder {
+                order_id: Uuid::new_v4(),
+                side: Side::Bid,
+                order_type: "limit".to_string(),
+                price: Some(Decimal::from(102)),
+                quantity: Decimal::from(3),
+                user_id: None,
+            },
+            &mut out,
+        );
+        let sequence = core.snapshot(&mut out);
+
+        type Change = (DeltaAction, Side, Decimal, Decimal);
+        let deltas: Vec<(u64, Vec<Change>)> = outputs
+            .iter()
+            .filter_map(|o| match o {
+                CoreOutput::Event(MarketEvent::OrderBookDelta { sequence, deltas, .. }) => Some((
+                    *sequence,
+                    deltas.iter().map(|d| (d.action, d.side, d.price, d.quantity)).collect(),
+                )),
+                _ => None,
+            })
+            .collect();
+        let (p101, p102) = (Decimal::from(101), Decimal::from(102));
+        assert_eq!(
+            deltas,
+            vec![
+                (1, vec![(DeltaAction::Add, Side::Ask, p101, Decimal::ONE)]),
+                (2, vec![(DeltaAction::Add, Side::Ask, p102, Decimal::ONE)]),
+                (
+                    3,
+                    vec![
+                        (DeltaAction::Remove, Side::Ask, p101, Decimal::ZERO),
+                        (DeltaAction::Remove, Side::Ask, p102, Decimal::ZERO),
+                        (DeltaAction::Add, Side::Bid, p102, Decimal::ONE),
+                    ]
+                ),
+            ]
+        );
+
+        assert_eq!(sequence, 3);
+        let Some(CoreOutput::Event(MarketEvent::OrderBookSnapshot { bids, asks, .. })) = outputs.last() else {
+            panic!("expected snapshot, got {:?}", outputs.last());
+        };
+        assert_eq!(bids.len(), 1);
+        assert!(asks.is_empty());
+    }
+
+    #[test]
+    fn test_replica_checksum_detects_divergence() {
+        let mut primary = core();
+        let mut replica = core().following();
+        let mut discard = |_| {};
+
+        let commands: Vec<OrderCommand> = [(Side::Ask, 101), (Side::Ask, 102), (Side::Bid, 101)]
+            .into_iter()
+            .map(|(side, price)| limit(side, price, Uuid::new_v4()))
+            .collect();
+        for command in &commands {
+            primary.process(command.clone(), &mut discard);
+        }
+        let (hash, order_count) = (primary.orderbook.state_hash(), primary.orderbook.order_count() as u64);
+
+        // Replaying the journal in order ends in the same book
+        for (sequence, command) in (1..).zip(&commands) {
+            replica.replicate(sequence, command.clone(), &mut discard);
+        }
+        assert!(replica.verify(3, hash, order_count, MarketPhase::Open));
+
+        // A lost entry shows up in the next checksum
+        let mut lossy = core().following();
+        lossy.replicate(1, commands[0].clone(), &mut discard);
+        lossy.replicate(3, commands[2].clone(), &mut discard);
+        assert!(!lossy.verify(3, hash, order_count, MarketPhase::Open));
+        let text = lossy.metrics.render();
+        assert!(text.contains("replication_journal_gap_entries_total 1"));
+        assert!(text.contains("replication_checksum_mismatches_total 1"));
+
+        // The primary's phase is taken over with its checksum
+        replica.verify(3, hash, order_count, MarketPhase::Halted);
+        assert!(!replica.control.accepts_orders());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_core_thread_round_trip() {
+        let (mut commands, handle, mut outputs) = spawn(core(), None).unwrap();
+
+        let owner = Uuid::new_v4();
+        for price in [99, 98] {
+            commands.push(limit(Side::Bid, price, owner));
+        }
+
+        // Admin requests see every command enqueued before them
+        let dump = handle.book(1).await.unwrap();
+        assert_eq!(dump.order_count, 2);
+        assert_eq!(dump.bids.len(), 1);
+        assert_eq!(dump.bids[0].orders[0].1, Some(owner));
+
+        let cancelled = handle.cancel_all(Some(owner)).await.unwrap();
+        assert_eq!(cancelled.len(), 2);
+
+        let mut placed = 0;
+        let mut released = 0;
+        while let Ok(output) = outputs.outputs.pop() {
+            match output {
+                CoreOutput::Placed { .. } => placed += 1,
+                CoreOutput::Release { .. } => released += 1,
+                CoreOutput::Event(_) | CoreOutput::Promoted | CoreOutput::Reject { .. } => {}
+            }
+        }
+        assert_eq!((placed, released), (2, 2));
+    }
+}
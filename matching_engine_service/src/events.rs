@@ -1,6 +1,7 @@
 use matching_engine::OrderId;
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -17,6 +18,45 @@ pub enum DeltaAction {
     Remove,
 }
 
+/// Trading phase of a market. Only an open market matches new orders,
+/// cancels are accepted in every phase.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MarketPhase {
+    /// Listed but not trading yet
+    PreOpen,
+    Open,
+    /// Collecting interest before the market (re)opens
+    Auction,
+    /// Stopped by an operator or a safeguard
+    Halted,
+    /// Done trading for the session
+    Closed,
+    /// Removed for good, no phase follows
+    Delisted,
+}
+
+impl MarketPhase {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MarketPhase::PreOpen => "pre_open",
+            MarketPhase::Open => "open",
+            MarketPhase::Auction => "auction",
+            MarketPhase::Halted => "halted",
+            MarketPhase::Closed => "closed",
+            MarketPhase::Delisted => "delisted",
+        }
+    }
+}
+
+/// Balance of one asset in a position update
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AssetBalance {
+    pub asset: String,
+    pub available: Decimal,
+    pub locked: Decimal,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LevelDelta {
     pub action: DeltaAction,
@@ -76,6 +116,44 @@ pub enum MarketEvent {
         /// Order ID (UUID)
         order_id: OrderId,
     },
+    /// Market phase change or operator action
+    #[serde(rename = "market_status")]
+    MarketStatus {
+        symbol: String,
+        phase: MarketPhase,
+        reason: String,
+        timestamp: u64,
+    },
+    /// Sent when an order is refused before matching, e.g. by a risk limit
+    #[serde(rename = "order_rejected")]
+    OrderRejected {
+        /// Order ID (UUID)
+        order_id: OrderId,
+        reason: String,
+    },
+    /// A settled trade was reversed by an operator
+    #[serde(rename = "trade_bust")]
+    TradeBust {
+        symbol: String,
+        trade_id: Uuid,
+        buy_order_id: OrderId,
+        sell_order_id: OrderId,
+        /// Counterparties, None for anonymous orders
+        buyer_id: Option<Uuid>,
+        seller_id: Option<Uuid>,
+        price: Decimal,
+        quantity: Decimal,
+        /// Timestamp of the original fill
+        timestamp: u64,
+        reason: String,
+    },
+    /// Balances of a user after one of their fills settled, only for that user
+    #[serde(rename = "position_update")]
+    PositionUpdate {
+        user_id: Uuid,
+        balances: Vec<AssetBalance>,
+        timestamp: u64,
+    },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -2,27 +2,51 @@ use axum::{
     routing::get,
     Router,
 };
-use matching_engine::{OrderBook, OrderId, OrderResult, Side as MatchingSide};
+use matching_engine::OrderId;
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
-use std::collections::BTreeMap;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::Notify;
 use tracing::{error, info, warn};
 
+mod admin;
+mod book_feed;
+mod control;
+mod dedup;
+mod engine;
 mod events;
+mod metrics;
+mod recorder;
+mod replication;
+mod risk;
 mod settlement;
 mod udp_transport;
 
-use events::{DeltaAction, LevelDelta, MarketEvent, OrderCommand, PriceLevel, Side};
+use control::MarketControl;
+use dedup::{CommandDeduplicator, DEFAULT_DEDUP_WINDOW};
+use engine::{CoreHandle, CoreOutput, MatchingCore};
+use events::{MarketEvent, Side};
+use metrics::ServiceMetrics;
+use recorder::JournalRecorder;
+use replication::{JournalSender, ReplicaControl, ReplicationConfig, Role};
+use risk::RiskLimits;
 use settlement::{SettlementClient, SettlementResult};
 use udp_transport::{UdpEventSender, UdpOrderReceiver, UdpTransportConfig};
 
 #[derive(Clone)]
 struct AppState {
-    orderbook: Arc<RwLock<OrderBook>>,
+    /// Admin access to the book, which is owned by the matching core thread
+    core: CoreHandle,
     event_sender: Arc<UdpEventSender>,
+    /// Accounts service, for operator trade busts
+    settlement_client: Arc<SettlementClient>,
+    control: Arc<MarketControl>,
+    /// Whether this replica is primary or following one
+    replica: Arc<ReplicaControl>,
+    metrics: Arc<ServiceMetrics>,
     symbol: String,
+    /// Bearer token for the admin API, which is disabled when unset
+    admin_token: Option<Arc<str>>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -109,6 +133,17 @@ async fn tokio_main() -> anyhow::Result<()> {
 
     let symbol = std::env::var("SYMBOL").unwrap_or_else(|_| "KCN/EUR".to_string());
     let bind_addr = std::env::var("BIND_ADDR").unwrap_or_else(|_| "0.0.0.0:8080".to_string());
+    let dedup_window = std::env::var("DEDUP_WINDOW")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_DEDUP_WINDOW);
+    let admin_token: Option<Arc<str>> = std::env::var("ADMIN_TOKEN")
+        .ok()
+        .filter(|t| !t.is_empty())
+        .map(Into::into);
+    if admin_token.is_none() {
+        warn!("ADMIN_TOKEN not set, admin API disabled");
+    }
 
     // UDP transport configuration
     let udp_config = UdpTransportConfig::from_env();
@@ -137,44 +172,93 @@ async fn tokio_main() -> anyhow::Result<()> {
     info!("Settlement client configured for: {}", accounts_url);
     let settlement_client = Arc::new(SettlementClient::new(accounts_url));
 
-    let orderbook = Arc::new(RwLock::new(OrderBook::new()));
-    let _state = AppState {
-        orderbook: orderbook.clone(),
-        event_sender: event_sender.clone(),
-        symbol: symbol.clone(),
-    };
+    let control = Arc::new(MarketControl::new());
+    let metrics = Arc::new(ServiceMetrics::new());
 
-    // Start UDP order receiver
-    if let Err(e) = start_order_receiver(
-        udp_config.order_receiver_bind,
-        orderbook.clone(),
-        event_sender.clone(),
-        settlement_client.clone(),
+    let replication = ReplicationConfig::from_env();
+    let replica = Arc::new(ReplicaControl::new(replication.role));
+    metrics.set_primary(replication.role == Role::Primary);
+
+    // Start the matching core, pinned to MATCHING_CORE_CPU (default: the last CPU)
+    let core_cpu = match std::env::var("MATCHING_CORE_CPU") {
+        Ok(v) => v.parse().ok(),
+        Err(_) => engine::default_cpu(),
+    };
+    let mut core = MatchingCore::new(
+        CommandDeduplicator::new(dedup_window),
+        control.clone(),
+        metrics.clone(),
         symbol.clone(),
-    ).await {
-        error!("Failed to start UDP order receiver: {}", e);
-        return Err(e);
+    )
+    .with_risk_limits(RiskLimits::from_env());
+    if let Some(target) = &replication.journal_target {
+        core = core.with_journal(JournalSender::new(target, replication.journal_sender_bind)?);
+    }
+    if let Ok(path) = std::env::var("JOURNAL_FILE") {
+        core = core.with_recorder(JournalRecorder::open(path.as_ref())?);
+    }
+    if replication.role == Role::Follower {
+        core = core.following();
     }
+    let (commands, core, outputs) = engine::spawn(core, core_cpu)?;
 
-    // Spawn orderbook update task with delta publishing
-    let ob_clone = orderbook.clone();
-    let sender_clone = event_sender.clone();
-    let symbol_clone = symbol.clone();
-    tokio::spawn(async move {
-        let mut publisher = OrderBookPublisher::new();
-        let mut interval = tokio::time::interval(tokio::time::Duration::from_millis(100));
-        loop {
-            interval.tick().await;
-            let ob = ob_clone.read().await;
-            if let Err(e) = publisher.publish(&ob, &sender_clone, &symbol_clone).await {
-                error!("Failed to publish orderbook update: {}", e);
-            }
+    let order_bind = udp_config.order_receiver_bind;
+    let order_auth = udp_config.order_stream_auth.clone();
+    if order_auth.is_none() {
+        warn!("ORDER_STREAM_KEY not set, accepting unsigned orders");
+    }
+    match replication.role {
+        Role::Primary => {
+            start_order_receiver(order_bind, order_auth, commands, &event_sender, &metrics)?;
+        }
+        Role::Follower => {
+            // The order stream belongs to the primary until this replica takes over
+            let on_promotion = replication::follow(
+                replication.journal_bind,
+                replication.failover_timeout,
+                commands,
+                replica.clone(),
+            )?;
+            let (event_sender, metrics) = (event_sender.clone(), metrics.clone());
+            tokio::spawn(async move {
+                let Ok(commands) = on_promotion.await else {
+                    error!("Journal receiver stopped without promotion");
+                    return;
+                };
+                if let Err(e) = start_order_receiver(order_bind, order_auth, commands, &event_sender, &metrics) {
+                    error!("Promoted, but the order stream is unavailable: {}", e);
+                }
+            });
         }
-    });
+    }
+
+    // Settle and publish what the core produced, book deltas included
+    tokio::spawn(run_post_trade(
+        outputs.outputs,
+        outputs.ready,
+        replication.role == Role::Primary,
+        event_sender.clone(),
+        settlement_client.clone(),
+        metrics.clone(),
+        symbol.clone(),
+    ));
+
+    let state = AppState {
+        core,
+        event_sender,
+        settlement_client,
+        control,
+        replica,
+        metrics,
+        symbol,
+        admin_token,
+    };
 
     let app = Router::new()
         .route("/health", get(health))
-        .with_state(());
+        .route("/metrics", get(metrics::metrics_handler))
+        .merge(admin::router(state.clone()))
+        .with_state(state);
 
     info!("Matching engine service listening on {} (orders via UDP)", bind_addr);
     let listener = tokio::net::TcpListener::bind(bind_addr).await?;
@@ -188,79 +272,107 @@ async fn health() -> &'static str {
     "ok"
 }
 
-async fn start_order_receiver(
+/// Start the UDP order receiver, it feeds the core's command ring
+fn start_order_receiver(
     bind_addr: std::net::SocketAddr,
-    orderbook: Arc<RwLock<OrderBook>>,
+    auth: Option<udp_proto::AuthConfig>,
+    commands: engine::CommandQueue,
+    event_sender: &Arc<UdpEventSender>,
+    metrics: &ServiceMetrics,
+) -> anyhow::Result<()> {
+    let order_receiver = match UdpOrderReceiver::new(bind_addr, commands, auth) {
+        Ok(r) => Arc::new(r),
+        Err(e) => {
+            error!("Failed to start UDP order receiver: {}", e);
+            return Err(e);
+        }
+    };
+    metrics.register_udp(event_sender.clone(), order_receiver);
+    Ok(())
+}
+
+/// Drain the core's output ring in order: settle fills, release
+/// reservations and publish events. Settlement is async, so it lives here
+/// rather than on the core thread. A follower drops everything until the
+/// core reports its promotion, the primary settled and published it already.
+async fn run_post_trade(
+    mut outputs: rtrb::Consumer<CoreOutput>,
+    ready: Arc<Notify>,
+    mut publishing: bool,
     event_sender: Arc<UdpEventSender>,
     settlement_client: Arc<SettlementClient>,
+    metrics: Arc<ServiceMetrics>,
     symbol: String,
-) -> anyhow::Result<()> {
-    let (_receiver, mut order_rx) = UdpOrderReceiver::new(bind_addr)?;
-
-    tokio::spawn(async move {
-        info!("UDP order receiver started, waiting for orders...");
-        while let Some(command) = order_rx.recv().await {
-            info!("Processing order command via UDP: {:?}", command);
-            if let Err(e) = process_order_command(
-                &orderbook,
-                &event_sender,
-                &settlement_client,
-                command,
-                &symbol,
-            ).await {
-                error!("Failed to process order command: {}", e);
+) {
+    info!("Post-trade task started, waiting for matching core output...");
+    loop {
+        while let Ok(output) = outputs.pop() {
+            if matches!(output, CoreOutput::Promoted) {
+                info!("Promoted to primary, settling and publishing from here on");
+                publishing = true;
+                continue;
+            }
+            if !publishing {
+                continue;
+            }
+            if let Err(e) = handle_output(output, &event_sender, &settlement_client, &metrics, &symbol).await {
+                error!("Failed to handle matching core output: {}", e);
             }
         }
-        error!("UDP order receiver channel closed");
-    });
-
-    Ok(())
+        if outputs.is_abandoned() {
+            error!("Matching core stopped");
+            break;
+        }
+        ready.notified().await;
+    }
 }
 
-async fn process_order_command(
-    orderbook: &Arc<RwLock<OrderBook>>,
-    event_sender: &Arc<UdpEventSender>,
-    settlement_client: &Arc<SettlementClient>,
-    command: OrderCommand,
+async fn handle_output(
+    output: CoreOutput,
+    event_sender: &UdpEventSender,
+    settlement_client: &SettlementClient,
+    metrics: &ServiceMetrics,
     symbol: &str,
 ) -> anyhow::Result<()> {
-    match command {
-        OrderCommand::PlaceOrder {
+    match output {
+        CoreOutput::Event(event) => event_sender.send_event(&event).await,
+        // Handled by the post-trade loop
+        CoreOutput::Promoted => Ok(()),
+        CoreOutput::Reject { order_id, reason } => {
+            settlement_client.cancel_order(order_id, Decimal::ZERO).await;
+            event_sender
+                .send_event(&MarketEvent::OrderRejected { order_id, reason })
+                .await
+        }
+        CoreOutput::Release { order_id, filled_quantity } => {
+            settlement_client.cancel_order(order_id, filled_quantity).await;
+            event_sender
+                .send_event(&MarketEvent::OrderCancelled {
+                    order_id,
+                    filled_quantity,
+                })
+                .await
+        }
+        CoreOutput::Placed {
             order_id,
             side,
             order_type,
             price,
             quantity,
-            ..
+            fills,
+            completed_orders,
         } => {
-            let matching_side = match side {
-                Side::Bid => MatchingSide::Bid,
-                Side::Ask => MatchingSide::Ask,
-            };
-
-            // Execute matching
-            let mut ob = orderbook.write().await;
-            let result: OrderResult = if order_type.to_lowercase() == "market" {
-                ob.add_market_order(order_id, matching_side, quantity)
-            } else {
-                let p = price.ok_or_else(|| anyhow::anyhow!("Limit order requires price"))?;
-                ob.add_limit_order(order_id, matching_side, p, quantity)
-            };
-            drop(ob);
-
-            if !result.fills.is_empty() {
-                info!("Order {} produced {} fills, settling...", result.order_id, result.fills.len());
+            if !fills.is_empty() {
+                info!("Order {} produced {} fills, settling...", order_id, fills.len());
             }
 
-            // Settle each fill synchronously BEFORE publishing events
+            // Settle each fill BEFORE publishing events
             let mut settled_fills = Vec::new();
             let mut settlement_failed = false;
 
-            for fill in &result.fills {
-                let timestamp = std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .map(|d| d.as_millis() as u64)
-                    .unwrap_or(0);
+            for fill in &fills {
+                metrics.record_fill();
+                let timestamp = control::now_millis();
 
                 // Attempt settlement
                 let settlement_result = settlement_client.settle_fill(
@@ -271,11 +383,16 @@ async fn process_order_command(
                     fill.quantity,
                     timestamp,
                 ).await;
+                metrics.record_settlement(&settlement_result);
 
                 match settlement_result {
-                    SettlementResult::Success(_) | SettlementResult::Skipped => {
-                        // Settlement succeeded or was skipped (anonymous orders)
-                        settled_fills.push((fill.clone(), timestamp));
+                    SettlementResult::Success(response) => {
+                        let position_updates = response.position_updates(timestamp);
+                        settled_fills.push((fill.clone(), timestamp, position_updates));
+                    }
+                    SettlementResult::Skipped => {
+                        // Both sides anonymous, nothing to settle or report
+                        settled_fills.push((fill.clone(), timestamp, Vec::new()));
                     }
                     SettlementResult::Failed(reason) => {
                         // Settlement failed - this is a critical issue
@@ -293,7 +410,7 @@ async fn process_order_command(
             }
 
             // Only publish events for successfully settled fills
-            for (fill, timestamp) in &settled_fills {
+            for (fill, timestamp, position_updates) in &settled_fills {
                 let event = MarketEvent::Fill {
                     symbol: symbol.to_string(),
                     buy_order_id: fill.buy_order_id,
@@ -303,35 +420,38 @@ async fn process_order_command(
                     timestamp: *timestamp,
                 };
                 event_sender.send_event(&event).await?;
+
+                // Counterparties learn their new balances right after the fill
+                for update in position_updates {
+                    event_sender.send_event(update).await?;
+                }
             }
 
             // Calculate total filled quantity from settled fills only
-            let filled_quantity: Decimal = settled_fills.iter().map(|(f, _)| f.quantity).sum();
+            let filled_quantity: Decimal = settled_fills.iter().map(|(f, _, _)| f.quantity).sum();
 
             // For market orders that weren't fully filled, cancel the order in accounts
             // and send a cancel event
-            if order_type.to_lowercase() == "market" {
-                if filled_quantity < quantity {
-                    info!(
-                        "Market order {} filled {} of {} - cancelling unfilled portion",
-                        result.order_id, filled_quantity, quantity
-                    );
-
-                    // Cancel order in accounts service (updates status, unlocks remaining funds)
-                    settlement_client.cancel_order(result.order_id, filled_quantity).await;
-
-                    // Send cancel event to clients
-                    let cancel_event = MarketEvent::OrderCancelled {
-                        order_id: result.order_id,
-                        filled_quantity,
-                    };
-                    event_sender.send_event(&cancel_event).await?;
-                }
+            if order_type.eq_ignore_ascii_case("market") && filled_quantity < quantity {
+                info!(
+                    "Market order {} filled {} of {} - cancelling unfilled portion",
+                    order_id, filled_quantity, quantity
+                );
+
+                // Cancel order in accounts service (updates status, unlocks remaining funds)
+                settlement_client.cancel_order(order_id, filled_quantity).await;
+
+                // Send cancel event to clients
+                let cancel_event = MarketEvent::OrderCancelled {
+                    order_id,
+                    filled_quantity,
+                };
+                event_sender.send_event(&cancel_event).await?;
             }
 
             // Send OrderAccepted (the order was accepted even if some settlements failed)
             let event = MarketEvent::OrderAccepted {
-                order_id: result.order_id,
+                order_id,
                 side,
                 order_type,
                 price,
@@ -340,11 +460,11 @@ async fn process_order_command(
             event_sender.send_event(&event).await?;
 
             // Send OrderFilled events for all orders that were 100% filled
-            if !result.completed_orders.is_empty() {
+            if !completed_orders.is_empty() {
                 info!("Order {} has {} completed orders: {:?}",
-                    result.order_id, result.completed_orders.len(), result.completed_orders);
+                    order_id, completed_orders.len(), completed_orders);
             }
-            for completed_order_id in &result.completed_orders {
+            for completed_order_id in &completed_orders {
                 info!("Sending OrderFilled for order {}", completed_order_id);
                 let filled_event = MarketEvent::OrderFilled {
                     order_id: *completed_order_id,
@@ -355,192 +475,11 @@ async fn process_order_command(
             if settlement_failed {
                 warn!(
                     "Order {} completed with settlement failures - check logs for CRITICAL errors",
-                    result.order_id
+                    order_id
                 );
             }
-        }
-        OrderCommand::CancelOrder { order_id, .. } => {
-            let mut ob = orderbook.write().await;
-            let cancelled = ob.cancel_order(order_id);
-            drop(ob);
-
-            if cancelled {
-                let event = MarketEvent::OrderCancelled {
-                    order_id,
-                    // For manual cancellations, we don't track filled quantity here
-                    // The accounts service has this info from partial fill updates
-                    filled_quantity: Decimal::ZERO,
-                };
-                event_sender.send_event(&event).await?;
-            }
-        }
-    }
-
-    Ok(())
-}
-
-const MAX_LEVELS: usize = 10;
-const SNAPSHOT_INTERVAL: u64 = 10; // Send snapshot every N updates
-
-/// Tracks orderbook state for delta computation
-struct OrderBookPublisher {
-    prev_bids: BTreeMap<Decimal, Decimal>,
-    prev_asks: BTreeMap<Decimal, Decimal>,
-    sequence: u64,
-    updates_since_snapshot: u64,
-}
-
-impl OrderBookPublisher {
-    fn new() -> Self {
-        Self {
-            prev_bids: BTreeMap::new(),
-            prev_asks: BTreeMap::new(),
-            sequence: 0,
-            updates_since_snapshot: SNAPSHOT_INTERVAL, // Force initial snapshot
-        }
-    }
-
-    fn should_send_snapshot(&self) -> bool {
-        self.updates_since_snapshot >= SNAPSHOT_INTERVAL
-    }
-
-    fn compute_deltas(
-        &self,
-        new_bids: &[(Decimal, Decimal)],
-        new_asks: &[(Decimal, Decimal)],
-    ) -> Vec<LevelDelta> {
-        let mut deltas = Vec::new();
-
-        // Build maps for new state
-        let new_bid_map: BTreeMap<Decimal, Decimal> = new_bids.iter().cloned().collect();
-        let new_ask_map: BTreeMap<Decimal, Decimal> = new_asks.iter().cloned().collect();
-
-        // Check for bid changes
-        for (price, qty) in &new_bid_map {
-            match self.prev_bids.get(price) {
-                Some(old_qty) if old_qty != qty => {
-                    deltas.push(LevelDelta {
-                        action: DeltaAction::Update,
-                        side: Side::Bid,
-                        price: *price,
-                        quantity: *qty,
-                    });
-                }
-                None => {
-                    deltas.push(LevelDelta {
-                        action: DeltaAction::Add,
-                        side: Side::Bid,
-                        price: *price,
-                        quantity: *qty,
-                    });
-                }
-                _ => {}
-            }
-        }
-
-        // Check for removed bids
-        for price in self.prev_bids.keys() {
-            if !new_bid_map.contains_key(price) {
-                deltas.push(LevelDelta {
-                    action: DeltaAction::Remove,
-                    side: Side::Bid,
-                    price: *price,
-                    quantity: Decimal::ZERO,
-                });
-            }
-        }
-
-        // Check for ask changes
-        for (price, qty) in &new_ask_map {
-            match self.prev_asks.get(price) {
-                Some(old_qty) if old_qty != qty => {
-                    deltas.push(LevelDelta {
-                        action: DeltaAction::Update,
-                        side: Side::Ask,
-                        price: *price,
-                        quantity: *qty,
-                    });
-                }
-                None => {
-                    deltas.push(LevelDelta {
-                        action: DeltaAction::Add,
-                        side: Side::Ask,
-                        price: *price,
-                        quantity: *qty,
-                    });
-                }
-                _ => {}
-            }
-        }
-
-        // Check for removed asks
-        for price in self.prev_asks.keys() {
-            if !new_ask_map.contains_key(price) {
-                deltas.push(LevelDelta {
-                    action: DeltaAction::Remove,
-                    side: Side::Ask,
-                    price: *price,
-                    quantity: Decimal::ZERO,
-                });
-            }
-        }
-
-        deltas
-    }
 
-    fn update_state(&mut self, bids: &[(Decimal, Decimal)], asks: &[(Decimal, Decimal)]) {
-        self.prev_bids = bids.iter().cloned().collect();
-        self.prev_asks = asks.iter().cloned().collect();
-        self.sequence += 1;
-    }
-
-    async fn publish(
-        &mut self,
-        orderbook: &OrderBook,
-        event_sender: &UdpEventSender,
-        symbol: &str,
-    ) -> anyhow::Result<()> {
-        let bid_levels = orderbook.get_bids(MAX_LEVELS);
-        let ask_levels = orderbook.get_asks(MAX_LEVELS);
-
-        if self.should_send_snapshot() {
-            // Send full snapshot
-            let bids: Vec<PriceLevel> = bid_levels
-                .iter()
-                .map(|(price, quantity)| PriceLevel { price: *price, quantity: *quantity })
-                .collect();
-            let asks: Vec<PriceLevel> = ask_levels
-                .iter()
-                .map(|(price, quantity)| PriceLevel { price: *price, quantity: *quantity })
-                .collect();
-
-            let event = MarketEvent::OrderBookSnapshot {
-                symbol: symbol.to_string(),
-                sequence: self.sequence,
-                bids,
-                asks,
-            };
-
-            event_sender.send_event(&event).await?;
-            self.updates_since_snapshot = 0;
-        } else {
-            // Compute and send deltas
-            let deltas = self.compute_deltas(&bid_levels, &ask_levels);
-
-            if !deltas.is_empty() {
-                let event = MarketEvent::OrderBookDelta {
-                    symbol: symbol.to_string(),
-                    sequence: self.sequence,
-                    deltas,
-                };
-
-                event_sender.send_event(&event).await?;
-            }
+            Ok(())
         }
-
-        self.update_state(&bid_levels, &ask_levels);
-        self.updates_since_snapshot += 1;
-
-        Ok(())
     }
 }
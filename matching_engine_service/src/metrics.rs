@@ -0,0 +1,406 @@
+//! Prometheus metrics for the matching engine service.
+//!
+//! Command latency, fills, risk rejections and settlement outcomes are
+//! recorded on the order path. Book depth and the journal sequence are sampled by the matching core
+//! on a timer, replication checks are counted as the core runs them, and the
+//! UDP transport counters and latency histograms are read when `/metrics` is
+//! scraped.
+
+use axum::{extract::State, http::header, response::IntoResponse};
+use matching_engine::{OrderBook, Side};
+use prometheus::core::{Collector, Desc};
+use prometheus::proto::{Bucket, Histogram, Metric, MetricFamily, MetricType};
+use prometheus::{
+    exponential_buckets, Encoder, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec,
+    Opts, Registry, TextEncoder,
+};
+use std::sync::Arc;
+use udp_proto::{HistogramSnapshot, LATENCY_BUCKETS};
+
+use crate::settlement::SettlementResult;
+use crate::udp_transport::{UdpEventSender, UdpOrderReceiver};
+use crate::AppState;
+
+pub struct ServiceMetrics {
+    registry: Registry,
+    command_duration: HistogramVec,
+    fills: IntCounter,
+    settlements: IntCounterVec,
+    risk_rejections: IntCounterVec,
+    book_levels: IntGaugeVec,
+    book_orders: IntGaugeVec,
+    primary: IntGauge,
+    journal_sequence: IntGauge,
+    journal_gaps: IntCounter,
+    checksums_verified: IntCounter,
+    checksum_mismatches: IntCounter,
+}
+
+impl ServiceMetrics {
+    pub fn new() -> Self {
+        let registry = Registry::new();
+
+        let command_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "engine_command_duration_seconds",
+                "Time from receiving an order command until the matching core processed it",
+            )
+            // 1us up to ~0.5s, queueing behind a burst dominates the tail
+            .buckets(exponential_buckets(0.000001, 2.0, 20).expect("valid buckets")),
+            &["command"],
+        )
+        .expect("valid metric");
+        let fills = IntCounter::new("engine_fills_total", "Fills produced by the matching engine")
+            .expect("valid metric");
+        let settlements = IntCounterVec::new(
+            Opts::new("engine_settlements_total", "Fill settlements by outcome"),
+            &["result"],
+        )
+        .expect("valid metric");
+        let risk_rejections = IntCounterVec::new(
+            Opts::new("engine_risk_rejections_total", "Orders rejected by a pre-trade risk check"),
+            &["check"],
+        )
+        .expect("valid metric");
+        let book_levels = IntGaugeVec::new(
+            Opts::new("engine_book_depth_levels", "Price levels on one side of the book"),
+            &["symbol", "side"],
+        )
+        .expect("valid metric");
+        let book_orders = IntGaugeVec::new(
+            Opts::new("engine_book_orders", "Orders resting on the book"),
+            &["symbol"],
+        )
+        .expect("valid metric");
+        let primary = IntGauge::new("replication_primary", "1 while this replica is the primary, 0 while following")
+            .expect("valid metric");
+        let journal_sequence = IntGauge::new(
+            "replication_journal_sequence",
+            "Journal sequence of the last command applied to the book",
+        )
+        .expect("valid metric");
+        let journal_gaps = IntCounter::new(
+            "replication_journal_gap_entries_total",
+            "Journal entries a follower never received",
+        )
+        .expect("valid metric");
+        let checksums_verified = IntCounter::new(
+            "replication_checksums_verified_total",
+            "Primary book checksums that matched the follower's book",
+        )
+        .expect("valid metric");
+        let checksum_mismatches = IntCounter::new(
+            "replication_checksum_mismatches_total",
+            "Primary book checksums that did not match the follower's book",
+        )
+        .expect("valid metric");
+
+        registry.register(Box::new(command_duration.clone())).expect("unique metric");
+        registry.register(Box::new(fills.clone())).expect("unique metric");
+        registry.register(Box::new(settlements.clone())).expect("unique metric");
+        registry.register(Box::new(risk_rejections.clone())).expect("unique metric");
+        registry.register(Box::new(book_levels.clone())).expect("unique metric");
+        registry.register(Box::new(book_orders.clone())).expect("unique metric");
+        registry.register(Box::new(primary.clone())).expect("unique metric");
+        registry.register(Box::new(journal_sequence.clone())).expect("unique metric");
+        registry.register(Box::new(journal_gaps.clone())).expect("unique metric");
+        registry.register(Box::new(checksums_verified.clone())).expect("unique metric");
+        registry.register(Box::new(checksum_mismatches.clone())).expect("unique metric");
+
+        Self {
+            registry,
+            command_duration,
+            fills,
+            settlements,
+            risk_rejections,
+            book_levels,
+            book_orders,
+            primary,
+            journal_sequence,
+            journal_gaps,
+            checksums_verified,
+            checksum_mismatches,
+        }
+    }
+
+    /// Export the UDP transport counters on every scrape
+    pub fn register_udp(&self, event_sender: Arc<UdpEventSender>, order_receiver: Arc<UdpOrderReceiver>) {
+        let collector = UdpStatsCollector::new(event_sender, order_receiver);
+        self.registry.register(Box::new(collector)).expect("unique metric");
+    }
+
+    /// Record how long one command took, `command` is "place", "cancel",
+    /// "snapshot", or one of the replication commands ("replicate", "verify", "promote")
+    pub fn observe_command(&self, command: &str, seconds: f64) {
+        self.command_duration.with_label_values(&[command]).observe(seconds);
+    }
+
+    pub fn record_fill(&self) {
+        self.fills.inc();
+    }
+
+    pub fn record_settlement(&self, result: &SettlementResult) {
+        let label = match result {
+            SettlementResult::Success(_) => "success",
+            SettlementResult::Skipped => "skipped",
+            SettlementResult::Failed(_) => "failed",
+        };
+        self.settlements.with_label_values(&[label]).inc();
+    }
+
+    /// Count an order refused by the risk check named `check`
+    pub fn record_risk_rejection(&self, check: &str) {
+        self.risk_rejections.with_label_values(&[check]).inc();
+    }
+
+    /// Sample depth and order count of a book
+    pub fn observe_book(&self, symbol: &str, orderbook: &OrderBook) {
+        self.book_levels
+            .with_label_values(&[symbol, "bid"])
+            .set(orderbook.level_count(Side::Bid) as i64);
+        self.book_levels
+            .with_label_values(&[symbol, "ask"])
+            .set(orderbook.level_count(Side::Ask) as i64);
+        self.book_orders
+            .with_label_values(&[symbol])
+            .set(orderbook.order_count() as i64);
+    }
+
+    pub fn set_primary(&self, primary: bool) {
+        self.primary.set(primary as i64);
+    }
+
+    pub fn observe_journal(&self, sequence: u64) {
+        self.journal_sequence.set(sequence as i64);
+    }
+
+    pub fn record_journal_gap(&self, missed: u64) {
+        self.journal_gaps.inc_by(missed);
+    }
+
+    /// Count the outcome of comparing the book against a primary checksum
+    pub fn record_checksum(&self, matched: bool) {
+        if matched {
+            self.checksums_verified.inc();
+        } else {
+            self.checksum_mismatches.inc();
+        }
+    }
+
+    /// Render all metrics in the Prometheus text format
+    pub fn render(&self) -> String {
+        let mut buffer = Vec::new();
+        if let Err(e) = TextEncoder::new().encode(&self.registry.gather(), &mut buffer) {
+            tracing::error!("Failed to encode metrics: {}", e);
+        }
+        String::from_utf8(buffer).unwrap_or_default()
+    }
+}
+
+impl Default for ServiceMetrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// GET /metrics
+pub async fn metrics_handler(State(state): State<AppState>) -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, prometheus::TEXT_FORMAT)],
+        state.metrics.render(),
+    )
+}
+
+/// Builds counters from `SenderStats`/`ReceiverStats` snapshots at scrape time
+struct UdpStatsCollector {
+    event_sender: Arc<UdpEventSender>,
+    order_receiver: Arc<UdpOrderReceiver>,
+    descs: Vec<Desc>,
+}
+
+const SENDER_COUNTERS: [(&str, &str); 9] = [
+    ("udp_sender_packets_total", "Packets sent on the event stream"),
+    ("udp_sender_messages_total", "Messages sent on the event stream"),
+    ("udp_sender_bytes_total", "Bytes sent on the event stream"),
+    ("udp_sender_heartbeats_total", "Heartbeats sent on the event stream"),
+    ("udp_sender_errors_total", "Send errors on the event stream"),
+    ("udp_sender_channel_full_total", "Events that found the event stream's channel full"),
+    ("udp_sender_rejected_total", "Events refused because the event stream's channel was full"),
+    ("udp_sender_dropped_total", "Book events dropped to make room on the event stream"),
+    ("udp_sender_coalesced_total", "Book events replaced by a newer one of their symbol"),
+];
+
+const RECEIVER_COUNTERS: [(&str, &str); 9] = [
+    ("udp_receiver_packets_total", "Packets received on the order stream"),
+    ("udp_receiver_messages_total", "Messages received on the order stream"),
+    ("udp_receiver_bytes_total", "Bytes received on the order stream"),
+    ("udp_receiver_heartbeats_total", "Heartbeats received on the order stream"),
+    ("udp_receiver_duplicates_total", "Duplicate packets dropped on the order stream"),
+    ("udp_receiver_gaps_total", "Sequence gaps detected on the order stream"),
+    ("udp_receiver_gap_messages_total", "Messages missing in detected gaps"),
+    ("udp_receiver_errors_total", "Receive errors on the order stream"),
+    ("udp_receiver_streams_rejected_total", "Packets of order streams beyond the receiver's limit"),
+];
+
+const RECEIVER_HISTOGRAMS: [(&str, &str); 2] = [
+    ("udp_receiver_latency_seconds", "One-way latency of the order stream beyond its quickest heartbeat"),
+    ("udp_receiver_jitter_seconds", "Change in one-way latency between order stream packets"),
+];
+
+impl UdpStatsCollector {
+    fn new(event_sender: Arc<UdpEventSender>, order_receiver: Arc<UdpOrderReceiver>) -> Self {
+        let descs = SENDER_COUNTERS
+            .iter()
+            .chain(RECEIVER_COUNTERS.iter())
+            .chain(RECEIVER_HISTOGRAMS.iter())
+            .map(|(name, help)| {
+                Desc::new(name.to_string(), help.to_string(), vec![], Default::default())
+                    .expect("valid metric")
+            })
+            .collect();
+        Self { event_sender, order_receiver, descs }
+    }
+
+    fn counter(name: &str, help: &str, value: u64) -> Vec<MetricFamily> {
+        let counter = IntCounter::new(name, help).expect("valid metric");
+        counter.inc_by(value);
+        counter.collect()
+    }
+
+    /// The receiver keeps its own buckets, they are handed over as they are
+    fn histogram(name: &str, help: &str, snapshot: &HistogramSnapshot) -> MetricFamily {
+        let mut histogram = Histogram::default();
+        histogram.set_sample_count(snapshot.count);
+        histogram.set_sample_sum(snapshot.sum_ns as f64 / 1e9);
+        let mut cumulative = 0;
+        let buckets = (0..LATENCY_BUCKETS - 1)
+            .filter_map(|i| {
+                cumulative += snapshot.buckets[i];
+                let mut bucket = Bucket::default();
+                bucket.set_cumulative_count(cumulative);
+                bucket.set_upper_bound(HistogramSnapshot::upper_bound(i)?.as_secs_f64());
+                Some(bucket)
+            })
+            .collect();
+        histogram.set_bucket(buckets);
+
+        let mut metric = Metric::default();
+        metric.set_histogram(histogram);
+        let mut family = MetricFamily::default();
+        family.set_name(name.to_string());
+        family.set_help(help.to_string());
+        family.set_field_type(MetricType::HISTOGRAM);
+        family.set_metric(vec![metric]);
+        family
+    }
+}
+
+impl Collector for UdpStatsCollector {
+    fn desc(&self) -> Vec<&Desc> {
+        self.descs.iter().collect()
+    }
+
+    fn collect(&self) -> Vec<MetricFamily> {
+        let mut families = Vec::new();
+
+        // The event sender is created lazily, there is nothing to export before that
+        if let Some(s) = self.event_sender.stats() {
+            let values = [
+                s.packets_sent,
+                s.messages_sent,
+                s.bytes_sent,
+                s.heartbeats_sent,
+                s.send_errors,
+                s.channel_full,
+                s.messages_rejected,
+                s.messages_dropped,
+                s.messages_coalesced,
+            ];
+            for ((name, help), value) in SENDER_COUNTERS.iter().zip(values) {
+                families.extend(Self::counter(name, help, value));
+            }
+        }
+
+        let r = self.order_receiver.stats();
+        let values = [
+            r.packets_received,
+            r.messages_received,
+            r.bytes_received,
+            r.heartbeats_received,
+            r.duplicates_dropped,
+            r.gaps_detected,
+            r.total_gap_messages,
+            r.recv_errors,
+            r.streams_rejected,
+        ];
+        for ((name, help), value) in RECEIVER_COUNTERS.iter().zip(values) {
+            families.extend(Self::counter(name, help, value));
+        }
+        for ((name, help), snapshot) in RECEIVER_HISTOGRAMS.iter().zip([&r.latency, &r.jitter]) {
+            families.push(Self::histogram(name, help, snapshot));
+        }
+
+        families
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::CommandQueue;
+    use rust_decimal::Decimal;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_render_command_and_settlement_metrics() {
+        let metrics = ServiceMetrics::new();
+        metrics.observe_command("place", 0.002);
+        metrics.record_fill();
+        metrics.record_settlement(&SettlementResult::Skipped);
+        metrics.record_settlement(&SettlementResult::Failed("down".to_string()));
+        metrics.record_settlement(&SettlementResult::Failed("down".to_string()));
+
+        let text = metrics.render();
+        assert!(text.contains("engine_command_duration_seconds_count{command=\"place\"} 1"));
+        assert!(text.contains("engine_fills_total 1"));
+        assert!(text.contains("engine_settlements_total{result=\"skipped\"} 1"));
+        assert!(text.contains("engine_settlements_total{result=\"failed\"} 2"));
+    }
+
+    #[test]
+    fn test_observe_book() {
+        let metrics = ServiceMetrics::new();
+        let mut ob = OrderBook::new();
+        ob.add_limit_order(Uuid::new_v4(), Side::Bid, Decimal::from(99), Decimal::ONE);
+        ob.add_limit_order(Uuid::new_v4(), Side::Bid, Decimal::from(98), Decimal::ONE);
+        ob.add_limit_order(Uuid::new_v4(), Side::Bid, Decimal::from(98), Decimal::ONE);
+        metrics.observe_book("KCN/EUR", &ob);
+
+        let text = metrics.render();
+        assert!(text.contains("engine_book_depth_levels{side=\"bid\",symbol=\"KCN/EUR\"} 2"));
+        assert!(text.contains("engine_book_depth_levels{side=\"ask\",symbol=\"KCN/EUR\"} 0"));
+        assert!(text.contains("engine_book_orders{symbol=\"KCN/EUR\"} 3"));
+    }
+
+    #[test]
+    fn test_udp_stats_exported() {
+        let metrics = ServiceMetrics::new();
+        let event_sender = Arc::new(
+            UdpEventSender::new("127.0.0.1:1".to_string(), "127.0.0.1:0".parse().unwrap()).unwrap(),
+        );
+        let (producer, _ring) = rtrb::RingBuffer::new(1);
+        let order_receiver = UdpOrderReceiver::new(
+            "127.0.0.1:0".parse().unwrap(),
+            CommandQueue::new(producer, std::thread::current()),
+            None,
+        )
+        .unwrap();
+        metrics.register_udp(event_sender, Arc::new(order_receiver));
+
+        let text = metrics.render();
+        assert!(text.contains("udp_sender_messages_total 0"));
+        assert!(text.contains("udp_receiver_gaps_total 0"));
+        assert!(text.contains("udp_receiver_latency_seconds_bucket{le=\"0.000001\"} 0"));
+        assert!(text.contains("udp_receiver_jitter_seconds_count 0"));
+    }
+}
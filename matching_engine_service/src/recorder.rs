@@ -0,0 +1,88 @@
+//! Journal file for audits and offline replays.
+//!
+//! With `JOURNAL_FILE` set, the matching core appends every command that
+//! reached the book to that file, with the fills it produced and a book
+//! checksum whenever the book changed within the last second. The `replay`
+//! binary runs such a file through a fresh order book and compares the
+//! outcome with what was recorded.
+//!
+//! The file is opened for appending, so it keeps the history of earlier runs.
+//! Each run numbers its commands from 1 again. Writes are buffered and
+//! flushed with every checksum, a crash loses at most the last second.
+
+use matching_engine::Fill;
+use std::fs::{File, OpenOptions};
+use std::io::BufWriter;
+use std::path::Path;
+use tracing::{error, info};
+use udp_proto::binary::{JournalEntry, JournalRecord};
+use udp_proto::journal_file::JournalWriter;
+
+use crate::events::{MarketPhase, OrderCommand};
+use crate::replication::to_binary;
+
+/// Journal file writer, owned by the matching core
+pub struct JournalRecorder {
+    writer: JournalWriter<BufWriter<File>>,
+    /// Sequence and phase of the last checksum written
+    last_checksum: Option<(u64, MarketPhase)>,
+}
+
+impl JournalRecorder {
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        info!("Recording journal to {}", path.display());
+
+        Ok(Self {
+            writer: JournalWriter::new(BufWriter::new(file)),
+            last_checksum: None,
+        })
+    }
+
+    /// Record the command executed as `sequence` and its fills
+    pub fn command(&mut self, sequence: u64, command: &OrderCommand, fills: &[Fill]) {
+        self.write(&JournalEntry {
+            sequence,
+            record: JournalRecord::Command(to_binary(command)),
+        });
+        for fill in fills {
+            self.write(&JournalEntry {
+                sequence,
+                record: JournalRecord::Fill {
+                    buy_order_id: fill.buy_order_id,
+                    sell_order_id: fill.sell_order_id,
+                    price: fill.price,
+                    quantity: fill.quantity,
+                },
+            });
+        }
+    }
+
+    /// Record the book state after the command with `sequence`, unless nothing
+    /// changed since the last checksum
+    pub fn checksum(&mut self, sequence: u64, hash: u64, order_count: u64, phase: MarketPhase) {
+        if self.last_checksum == Some((sequence, phase)) {
+            return;
+        }
+        self.last_checksum = Some((sequence, phase));
+
+        self.write(&JournalEntry {
+            sequence,
+            record: JournalRecord::Checksum {
+                hash,
+                order_count,
+                phase: phase.into(),
+            },
+        });
+        if let Err(e) = self.writer.flush() {
+            error!("Failed to flush journal file: {}", e);
+        }
+    }
+
+    fn write(&mut self, entry: &JournalEntry) {
+        // A full disk must not stop matching
+        if let Err(e) = self.writer.write(entry) {
+            error!("Failed to record journal entry {}: {}", entry.sequence, e);
+        }
+    }
+}
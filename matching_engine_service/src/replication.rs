@@ -0,0 +1,476 @@
+//! Hot standby replication.
+//!
+//! The primary's matching core numbers every command it executes and sends it
+//! on the journal stream, with a checksum of its book every second. New orders
+//! it refuses and operator changes to the risk limits and kill switch go out
+//! as well, without a sequence of their own, so a promoted follower refuses
+//! the same orders. A follower feeds that journal into its own core, which
+//! settles and publishes nothing, and compares the checksums at the same
+//! sequence.
+//!
+//! A follower becomes primary when an operator promotes it, or on its own once
+//! the primary has been silent for `FAILOVER_TIMEOUT_MS`. It then binds the
+//! order stream and publishes a fresh snapshot. Followers must be running
+//! before the primary takes orders, there is no state transfer for a follower
+//! that joins late.
+
+use matching_engine::OrderId;
+use serde::Serialize;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::oneshot;
+use tracing::{error, info, warn};
+
+use udp_proto::binary::{
+    decode_journal_entry, JournalEncoder, JournalEntry, JournalRecord,
+    OrderCommand as BinaryOrderCommand, OrderType as BinaryOrderType, RiskControl as BinaryRiskControl,
+    RiskLimits as BinaryRiskLimits, Side as BinarySide,
+};
+use udp_proto::{MessageType, ReceiverConfig, SenderConfig, UdpReceiver, UdpSender};
+
+use crate::engine::CommandQueue;
+use crate::events::{MarketPhase, OrderCommand, Side};
+use crate::risk::{RiskControl, RiskLimits};
+use crate::udp_transport::{resolve_addr, UdpOrderReceiver};
+
+// Stream ID of the journal (orders and events use 1 and 2)
+const JOURNAL_STREAM_ID: u32 = 3;
+
+/// How often the primary sends a book checksum. Also bounds how long a
+/// quiet primary goes without sending anything but heartbeats.
+pub const CHECKSUM_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    Primary,
+    Follower,
+}
+
+#[derive(Debug, Clone)]
+pub struct ReplicationConfig {
+    pub role: Role,
+    /// Where the primary sends its journal, no journal when unset
+    pub journal_target: Option<String>,
+    /// Local address of the primary's journal sender
+    pub journal_sender_bind: SocketAddr,
+    /// Where a follower receives the journal
+    pub journal_bind: SocketAddr,
+    /// Promote a follower once the primary is silent this long, manual only when unset
+    pub failover_timeout: Option<Duration>,
+}
+
+impl ReplicationConfig {
+    pub fn from_env() -> Self {
+        let role = match std::env::var("ROLE").as_deref() {
+            Ok("follower") => Role::Follower,
+            Ok("primary") | Err(_) => Role::Primary,
+            Ok(other) => {
+                warn!("Unknown ROLE '{}', running as primary", other);
+                Role::Primary
+            }
+        };
+        let journal_target = std::env::var("REPLICA_ADDR").ok().filter(|a| !a.is_empty());
+        let journal_sender_bind = resolve_addr(
+            &std::env::var("JOURNAL_SENDER_BIND").unwrap_or_else(|_| "0.0.0.0:9105".to_string()),
+        );
+        let journal_bind =
+            resolve_addr(&std::env::var("JOURNAL_BIND").unwrap_or_else(|_| "0.0.0.0:9104".to_string()));
+        let failover_timeout = std::env::var("FAILOVER_TIMEOUT_MS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .map(Duration::from_millis);
+
+        Self {
+            role,
+            journal_target,
+            journal_sender_bind,
+            journal_bind,
+            failover_timeout,
+        }
+    }
+}
+
+/// Role of this replica, shared with the admin API
+pub struct ReplicaControl {
+    primary: AtomicBool,
+    promotion_requested: AtomicBool,
+}
+
+impl ReplicaControl {
+    pub fn new(role: Role) -> Self {
+        Self {
+            primary: AtomicBool::new(role == Role::Primary),
+            promotion_requested: AtomicBool::new(false),
+        }
+    }
+
+    pub fn role(&self) -> Role {
+        if self.primary.load(Ordering::Acquire) {
+            Role::Primary
+        } else {
+            Role::Follower
+        }
+    }
+
+    /// Ask a follower to take over, returns false if this replica already is primary
+    pub fn request_promotion(&self) -> bool {
+        if self.role() == Role::Primary {
+            return false;
+        }
+        self.promotion_requested.store(true, Ordering::Release);
+        true
+    }
+}
+
+/// Journal writer, owned by the primary's matching core
+pub struct JournalSender {
+    sender: UdpSender,
+    encoder: JournalEncoder,
+}
+
+impl JournalSender {
+    pub fn new(target: &str, bind_addr: SocketAddr) -> anyhow::Result<Self> {
+        let config = SenderConfig {
+            stream_id: JOURNAL_STREAM_ID,
+            target_addr: resolve_addr(target),
+            max_batch_delay: Duration::from_micros(100),
+            channel_capacity: 65_536,
+            enable_heartbeats: true,
+            ..Default::default()
+        };
+        let sender = UdpSender::new(config, bind_addr)?;
+        info!("Journal sender created: {} -> {}", bind_addr, target);
+
+        Ok(Self {
+            sender,
+            encoder: JournalEncoder::new(),
+        })
+    }
+
+    /// Send the command executed as `sequence`
+    pub fn append(&mut self, sequence: u64, command: &OrderCommand) {
+        self.send(&JournalEntry {
+            sequence,
+            record: JournalRecord::Command(to_binary(command)),
+        });
+    }
+
+    /// Send a new order refused after the command with `sequence`
+    pub fn rejected(&mut self, sequence: u64, order_id: OrderId, reason: &str) {
+        self.send(&JournalEntry {
+            sequence,
+            record: JournalRecord::Rejected {
+                order_id,
+                reason: reason.to_string(),
+            },
+        });
+    }
+
+    /// Send an operator's risk control change made after the command with `sequence`
+    pub fn risk_control(&mut self, sequence: u64, control: &RiskControl) {
+        self.send(&JournalEntry {
+            sequence,
+            record: JournalRecord::RiskControl(risk_control_to_binary(control)),
+        });
+    }
+
+    /// Send the book state after the command with `sequence`
+    pub fn checksum(&mut self, sequence: u64, hash: u64, order_count: u64, phase: MarketPhase) {
+        self.send(&JournalEntry {
+            sequence,
+            record: JournalRecord::Checksum {
+                hash,
+                order_count,
+                phase: phase.into(),
+            },
+        });
+    }
+
+    fn send(&mut self, entry: &JournalEntry) {
+        let result = match self.encoder.encode(entry) {
+            Ok(data) => self
+                .sender
+                .try_send(MessageType::Journal, data.to_vec())
+                .map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+        // The follower detects the gap, the primary never waits for it
+        if let Err(e) = result {
+            error!("Failed to send journal entry {}: {}", entry.sequence, e);
+        }
+    }
+}
+
+pub(crate) fn to_binary(command: &OrderCommand) -> BinaryOrderCommand {
+    match command {
+        OrderCommand::PlaceOrder {
+            order_id,
+            side,
+            order_type,
+            price,
+            quantity,
+            user_id,
+        } => BinaryOrderCommand::New {
+            order_id: *order_id,
+            user_id: *user_id,
+            side: match side {
+                Side::Bid => BinarySide::Bid,
+                Side::Ask => BinarySide::Ask,
+            },
+            order_type: if order_type.eq_ignore_ascii_case("market") {
+                BinaryOrderType::Market
+            } else {
+                BinaryOrderType::Limit
+            },
+            price: *price,
+            quantity: *quantity,
+        },
+        OrderCommand::CancelOrder { order_id, user_id } => BinaryOrderCommand::Cancel {
+            order_id: *order_id,
+            user_id: *user_id,
+        },
+    }
+}
+
+fn risk_control_to_binary(control: &RiskControl) -> BinaryRiskControl {
+    match control {
+        RiskControl::SetLimits { owner, limits } => BinaryRiskControl::SetLimits {
+            owner: *owner,
+            limits: limits.as_ref().map(|limits| BinaryRiskLimits {
+                max_order_quantity: limits.max_order_quantity,
+                max_open_orders: limits.max_open_orders.map(|n| n as u64),
+                max_notional_per_second: limits.max_notional_per_second,
+                price_band_percent: limits.price_band_percent,
+            }),
+        },
+        RiskControl::Kill { owner } => BinaryRiskControl::Kill { owner: *owner },
+        RiskControl::Revive { owner } => BinaryRiskControl::Revive { owner: *owner },
+    }
+}
+
+fn risk_control_from_binary(control: BinaryRiskControl) -> RiskControl {
+    match control {
+        BinaryRiskControl::SetLimits { owner, limits } => RiskControl::SetLimits {
+            owner,
+            limits: limits.map(|limits| RiskLimits {
+                max_order_quantity: limits.max_order_quantity,
+                max_open_orders: limits.max_open_orders.map(|n| usize::try_from(n).unwrap_or(usize::MAX)),
+                max_notional_per_second: limits.max_notional_per_second,
+                price_band_percent: limits.price_band_percent,
+            }),
+        },
+        BinaryRiskControl::Kill { owner } => RiskControl::Kill { owner },
+        BinaryRiskControl::Revive { owner } => RiskControl::Revive { owner },
+    }
+}
+
+/// Feed the primary's journal into the core until this replica is promoted.
+/// The command queue is handed back on promotion, for the order receiver.
+pub fn follow(
+    bind_addr: SocketAddr,
+    failover_timeout: Option<Duration>,
+    commands: CommandQueue,
+    replica: Arc<ReplicaControl>,
+) -> anyhow::Result<oneshot::Receiver<CommandQueue>> {
+    let config = ReceiverConfig {
+        stream_id: JOURNAL_STREAM_ID,
+        channel_capacity: 65_536,
+        recv_timeout: Duration::from_millis(10),
+        stream_timeout: Duration::from_millis(500),
+        ..Default::default()
+    };
+    let receiver = UdpReceiver::new(config, bind_addr)?;
+    info!("Following primary, journal on {}", bind_addr);
+
+    let (promoted, on_promotion) = oneshot::channel();
+    std::thread::Builder::new()
+        .name("journal-receiver".to_string())
+        .spawn(move || {
+            let commands = journal_loop(&receiver, failover_timeout, commands, &replica);
+            let _ = promoted.send(commands);
+        })?;
+
+    Ok(on_promotion)
+}
+
+fn journal_loop(
+    receiver: &UdpReceiver,
+    failover_timeout: Option<Duration>,
+    mut commands: CommandQueue,
+    replica: &ReplicaControl,
+) -> CommandQueue {
+    // Heartbeats count as signs of life too, the failover timer only runs
+    // once the primary was heard from
+    let mut packets = 0;
+    let mut last_heard: Option<Instant> = None;
+
+    loop {
+        match receiver.recv_timeout(Duration::from_millis(100)) {
+            Ok(Some(msg)) if msg.msg_type == MessageType::Journal => match decode_journal_entry(&msg.payload) {
+                Ok(entry) => apply(entry, &mut commands),
+                Err(e) => error!("Failed to decode journal entry: {}", e),
+            },
+            Ok(_) => {}
+            Err(e) => {
+                error!("Journal receiver error: {:?}, waiting for promotion", e);
+                std::thread::sleep(Duration::from_millis(100));
+            }
+        }
+
+        let received = receiver.stats().packets_received;
+        if received != packets {
+            packets = received;
+            last_heard = Some(Instant::now());
+        }
+
+        if replica.promotion_requested.load(Ordering::Acquire) {
+            info!("Promoted to primary by operator");
+            break;
+        }
+        if let (Some(timeout), Some(heard)) = (failover_timeout, last_heard) {
+            if heard.elapsed() >= timeout {
+                warn!("Primary silent for {:?}, taking over", heard.elapsed());
+                break;
+            }
+        }
+    }
+
+    // Everything already journaled is applied before the first own order
+    commands.promote();
+    replica.primary.store(true, Ordering::Release);
+    commands
+}
+
+fn apply(entry: JournalEntry, commands: &mut CommandQueue) {
+    match entry.record {
+        JournalRecord::Command(command) => match UdpOrderReceiver::convert_from_binary(command) {
+            Some(command) => commands.replicate(entry.sequence, command),
+            None => warn!("Ignoring journal entry {}, not a book command", entry.sequence),
+        },
+        JournalRecord::Checksum {
+            hash,
+            order_count,
+            phase,
+        } => {
+            commands.verify(entry.sequence, hash, order_count, phase.into());
+        }
+        JournalRecord::Rejected { order_id, reason } => commands.rejected(order_id, reason),
+        JournalRecord::RiskControl(control) => commands.risk_control(risk_control_from_binary(control)),
+        // Only journal files carry fills, the follower matches on its own
+        JournalRecord::Fill { .. } => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{self, CoreOutput, MatchingCore};
+    use crate::control::MarketControl;
+    use crate::dedup::CommandDeduplicator;
+    use crate::metrics::ServiceMetrics;
+    use rust_decimal::Decimal;
+    use std::net::UdpSocket;
+    use uuid::Uuid;
+
+    fn free_addr() -> SocketAddr {
+        UdpSocket::bind("127.0.0.1:0").unwrap().local_addr().unwrap()
+    }
+
+    /// Current value of a counter in the Prometheus text output
+    fn counter(metrics: &ServiceMetrics, name: &str) -> u64 {
+        metrics
+            .render()
+            .lines()
+            .find_map(|line| line.strip_prefix(name)?.trim().parse().ok())
+            .unwrap_or(0)
+    }
+
+    fn core(metrics: Arc<ServiceMetrics>) -> MatchingCore {
+        MatchingCore::new(
+            CommandDeduplicator::new(16),
+            Arc::new(MarketControl::new()),
+            metrics,
+            "KCN/EUR".to_string(),
+        )
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_follower_replays_journal_and_takes_over() {
+        let journal_addr = free_addr();
+        let metrics = Arc::new(ServiceMetrics::new());
+        let follower_metrics = Arc::new(ServiceMetrics::new());
+
+        let primary = core(metrics).with_journal(JournalSender::new(&journal_addr.to_string(), free_addr()).unwrap());
+        let (mut orders, primary, _primary_outputs) = engine::spawn(primary, None).unwrap();
+        let (commands, follower, mut follower_outputs) =
+            engine::spawn(core(follower_metrics.clone()).following(), None).unwrap();
+        let replica = Arc::new(ReplicaControl::new(Role::Follower));
+        let on_promotion =
+            follow(journal_addr, Some(Duration::from_secs(30)), commands, replica.clone()).unwrap();
+
+        for (side, price, quantity) in [(Side::Ask, 101, 2), (Side::Ask, 102, 1), (Side::Bid, 101, 1)] {
+            orders.push(OrderCommand::PlaceOrder {
+                order_id: Uuid::new_v4(),
+                side,
+                order_type: "limit".to_string(),
+                price: Some(Decimal::from(price)),
+                quantity: Decimal::from(quantity),
+                user_id: None,
+            });
+        }
+        primary.cancel_all(None).await.unwrap();
+        let (killed, limited) = (Uuid::new_v4(), Uuid::new_v4());
+        let limits = RiskLimits {
+            max_open_orders: Some(3),
+            ..Default::default()
+        };
+        primary.kill(killed).await.unwrap();
+        primary.set_limits(limited, Some(limits.clone())).await.unwrap();
+        orders.push(OrderCommand::PlaceOrder {
+            order_id: Uuid::new_v4(),
+            side: Side::Bid,
+            order_type: "limit".to_string(),
+            price: Some(Decimal::from(100)),
+            quantity: Decimal::ONE,
+            user_id: None,
+        });
+        let expected = primary.book(usize::MAX).await.unwrap();
+
+        // The primary sends a checksum every second
+        let deadline = Instant::now() + Duration::from_secs(5);
+        loop {
+            let replayed = follower.book(usize::MAX).await.unwrap();
+            let caught_up = replayed.order_count == 1 && replayed.bids[0].orders[0].0 == expected.bids[0].orders[0].0;
+            if caught_up && counter(&follower_metrics, "replication_checksums_verified_total") > 0 {
+                break;
+            }
+            assert!(Instant::now() < deadline, "follower did not catch up:\n{}", follower_metrics.render());
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        assert_eq!(counter(&follower_metrics, "replication_checksum_mismatches_total"), 0);
+        assert_eq!(counter(&follower_metrics, "replication_journal_gap_entries_total"), 0);
+        // Risk controls ride the journal ahead of the last order
+        assert!(follower.risk(killed).await.unwrap().killed);
+        assert_eq!(follower.risk(limited).await.unwrap().limits, limits);
+
+        assert!(replica.request_promotion());
+        let _commands = tokio::time::timeout(Duration::from_secs(5), on_promotion)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(replica.role(), Role::Primary);
+        assert!(!replica.request_promotion());
+
+        // Promotion is ordered after every replayed command, admin requests
+        // see everything queued before them
+        follower.book(0).await.unwrap();
+        let mut promoted = false;
+        while let Ok(output) = follower_outputs.outputs.pop() {
+            promoted |= matches!(output, CoreOutput::Promoted);
+        }
+        assert!(promoted);
+    }
+}
@@ -0,0 +1,457 @@
+//! Pre-trade risk checks.
+//!
+//! Every new order is checked on the matching core before it reaches the
+//! book: order size, open order count, notional accepted per second and
+//! distance of the limit price from the last trade. Limits come from the
+//! environment and can be overridden per owner through the admin API. The
+//! kill switch cancels an owner's resting orders and blocks new ones until it
+//! is lifted.
+//!
+//! Orders without an owner are held to the default order size and price band,
+//! the other checks need an owner to count against. Overrides and the kill
+//! switch are journaled, a promoted follower enforces the same. Only the
+//! notional windows start over on promotion.
+
+use matching_engine::OrderId;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// Window of the notional limit
+const NOTIONAL_WINDOW: Duration = Duration::from_secs(1);
+
+/// Limits of one owner, a missing limit is not enforced
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RiskLimits {
+    /// Largest quantity of a single order
+    pub max_order_quantity: Option<Decimal>,
+    /// Most orders resting on the book at once
+    pub max_open_orders: Option<usize>,
+    /// Most notional (price x quantity) accepted within one second. Market
+    /// orders are valued at the last trade price.
+    pub max_notional_per_second: Option<Decimal>,
+    /// Furthest a limit price may be from the last trade price, in percent
+    pub price_band_percent: Option<Decimal>,
+}
+
+impl RiskLimits {
+    /// Defaults for every owner, from `RISK_MAX_ORDER_QUANTITY`,
+    /// `RISK_MAX_OPEN_ORDERS`, `RISK_MAX_NOTIONAL_PER_SECOND` and
+    /// `RISK_PRICE_BAND_PERCENT`
+    pub fn from_env() -> Self {
+        fn var<T: FromStr>(name: &str) -> Option<T> {
+            std::env::var(name).ok().and_then(|v| v.parse().ok())
+        }
+
+        Self {
+            max_order_quantity: var("RISK_MAX_ORDER_QUANTITY"),
+            max_open_orders: var("RISK_MAX_OPEN_ORDERS"),
+            max_notional_per_second: var("RISK_MAX_NOTIONAL_PER_SECOND"),
+            price_band_percent: var("RISK_PRICE_BAND_PERCENT"),
+        }
+    }
+}
+
+/// Why an order was refused
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RiskViolation {
+    KillSwitch,
+    OrderSize { quantity: Decimal, limit: Decimal },
+    OpenOrders { limit: usize },
+    Notional { limit: Decimal },
+    PriceBand { price: Decimal, last_price: Decimal, percent: Decimal },
+}
+
+impl RiskViolation {
+    /// Metric label of the check that failed
+    pub fn check(&self) -> &'static str {
+        match self {
+            RiskViolation::KillSwitch => "kill_switch",
+            RiskViolation::OrderSize { .. } => "order_size",
+            RiskViolation::OpenOrders { .. } => "open_orders",
+            RiskViolation::Notional { .. } => "notional",
+            RiskViolation::PriceBand { .. } => "price_band",
+        }
+    }
+}
+
+impl fmt::Display for RiskViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RiskViolation::KillSwitch => write!(f, "Trading disabled by kill switch"),
+            RiskViolation::OrderSize { quantity, limit } => {
+                write!(f, "Order quantity {} exceeds the limit of {}", quantity, limit)
+            }
+            RiskViolation::OpenOrders { limit } => write!(f, "Open order limit of {} reached", limit),
+            RiskViolation::Notional { limit } => write!(f, "Notional limit of {} per second exceeded", limit),
+            RiskViolation::PriceBand {
+                price,
+                last_price,
+                percent,
+            } => write!(
+                f,
+                "Price {} is more than {}% away from the last trade at {}",
+                price, percent, last_price
+            ),
+        }
+    }
+}
+
+/// Owner of each resting order, counted per owner for the open order limit
+#[derive(Debug, Default)]
+pub struct OrderOwners {
+    by_order: HashMap<OrderId, Uuid>,
+    open: HashMap<Uuid, usize>,
+}
+
+impl OrderOwners {
+    pub fn insert(&mut self, order_id: OrderId, owner: Uuid) {
+        if let Some(previous) = self.by_order.insert(order_id, owner) {
+            self.release(previous);
+        }
+        *self.open.entry(owner).or_default() += 1;
+    }
+
+    pub fn remove(&mut self, order_id: &OrderId) -> Option<Uuid> {
+        let owner = self.by_order.remove(order_id)?;
+        self.release(owner);
+        Some(owner)
+    }
+
+    fn release(&mut self, owner: Uuid) {
+        if let Some(count) = self.open.get_mut(&owner) {
+            *count -= 1;
+            if *count == 0 {
+                self.open.remove(&owner);
+            }
+        }
+    }
+
+    pub fn get(&self, order_id: &OrderId) -> Option<Uuid> {
+        self.by_order.get(order_id).copied()
+    }
+
+    pub fn open_orders(&self, owner: &Uuid) -> usize {
+        self.open.get(owner).copied().unwrap_or(0)
+    }
+
+    /// Resting orders of one owner, in no particular order
+    pub fn orders_of(&self, owner: &Uuid) -> Vec<OrderId> {
+        self.by_order
+            .iter()
+            .filter(|(_, o)| *o == owner)
+            .map(|(id, _)| *id)
+            .collect()
+    }
+
+    #[cfg(test)]
+    pub fn is_empty(&self) -> bool {
+        self.by_order.is_empty()
+    }
+}
+
+/// Risk state of one owner, as reported to operators
+#[derive(Debug, Clone, Serialize)]
+pub struct OwnerRisk {
+    pub owner: Uuid,
+    pub limits: RiskLimits,
+    /// Limits were set for this owner rather than the defaults
+    pub overridden: bool,
+    pub killed: bool,
+    pub open_orders: usize,
+}
+
+/// An operator's change to the risk controls of one owner
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RiskControl {
+    /// Override the limits, `None` goes back to the defaults
+    SetLimits { owner: Uuid, limits: Option<RiskLimits> },
+    /// Block new orders, the resting ones are cancelled separately
+    Kill { owner: Uuid },
+    Revive { owner: Uuid },
+}
+
+/// An order as far as the risk checks are concerned
+pub struct OrderRisk {
+    pub owner: Option<Uuid>,
+    /// `None` for market orders
+    pub price: Option<Decimal>,
+    pub quantity: Decimal,
+    pub open_orders: usize,
+}
+
+/// Limits, kill switch and the state they are checked against.
+/// Only ever touched by the core thread.
+pub struct RiskEngine {
+    defaults: RiskLimits,
+    overrides: HashMap<Uuid, RiskLimits>,
+    killed: HashSet<Uuid>,
+    /// Notional accepted per owner within the last second, oldest first
+    notional: HashMap<Uuid, VecDeque<(Instant, Decimal)>>,
+    last_price: Option<Decimal>,
+}
+
+impl RiskEngine {
+    pub fn new(defaults: RiskLimits) -> Self {
+        Self {
+            defaults,
+            overrides: HashMap::new(),
+            killed: HashSet::new(),
+            notional: HashMap::new(),
+            last_price: None,
+        }
+    }
+
+    /// Limits in force for an owner
+    pub fn limits(&self, owner: &Uuid) -> &RiskLimits {
+        self.overrides.get(owner).unwrap_or(&self.defaults)
+    }
+
+    pub fn has_override(&self, owner: &Uuid) -> bool {
+        self.overrides.contains_key(owner)
+    }
+
+    /// Replace the limits of one owner, `None` goes back to the defaults
+    pub fn set_limits(&mut self, owner: Uuid, limits: Option<RiskLimits>) {
+        match limits {
+            Some(limits) => self.overrides.insert(owner, limits),
+            None => self.overrides.remove(&owner),
+        };
+    }
+
+    /// Block new orders of an owner, returns false if already blocked
+    pub fn kill(&mut self, owner: Uuid) -> bool {
+        self.killed.insert(owner)
+    }
+
+    /// Lift the kill switch, returns false if the owner was not blocked
+    pub fn revive(&mut self, owner: &Uuid) -> bool {
+        self.killed.remove(owner)
+    }
+
+    pub fn is_killed(&self, owner: &Uuid) -> bool {
+        self.killed.contains(owner)
+    }
+
+    /// Apply an operator's change, returns false if it changed nothing
+    pub fn apply(&mut self, control: &RiskControl) -> bool {
+        match control {
+            RiskControl::SetLimits { owner, limits } => {
+                self.set_limits(*owner, limits.clone());
+                true
+            }
+            RiskControl::Kill { owner } => self.kill(*owner),
+            RiskControl::Revive { owner } => self.revive(owner),
+        }
+    }
+
+    /// Reference for the price band and for valuing market orders
+    pub fn observe_trade(&mut self, price: Decimal) {
+        self.last_price = Some(price);
+    }
+
+    /// Check an order against its owner's limits, or the defaults without an
+    /// owner. Returns the notional to `record` once the order went through.
+    pub fn check(&mut self, order: &OrderRisk, now: Instant) -> Result<Decimal, RiskViolation> {
+        if order.owner.is_some_and(|owner| self.is_killed(&owner)) {
+            return Err(RiskViolation::KillSwitch);
+        }
+        let limits = order
+            .owner
+            .and_then(|owner| self.overrides.get(&owner))
+            .unwrap_or(&self.defaults);
+
+        if let Some(limit) = limits.max_order_quantity {
+            if order.quantity > limit {
+                return Err(RiskViolation::OrderSize {
+                    quantity: order.quantity,
+                    limit,
+                });
+            }
+        }
+
+        // Only a limit order adds to the resting orders
+        if let (Some(limit), Some(_), Some(_)) = (limits.max_open_orders, order.price, order.owner) {
+            if order.open_orders >= limit {
+                return Err(RiskViolation::OpenOrders { limit });
+            }
+        }
+
+        if let (Some(percent), Some(price), Some(last_price)) =
+            (limits.price_band_percent, order.price, self.last_price)
+        {
+            let band = last_price * percent / Decimal::ONE_HUNDRED;
+            if (price - last_price).abs() > band {
+                return Err(RiskViolation::PriceBand {
+                    price,
+                    last_price,
+                    percent,
+                });
+            }
+        }
+
+        let notional = order.price.or(self.last_price).unwrap_or_default() * order.quantity;
+        if let (Some(limit), Some(owner)) = (limits.max_notional_per_second, order.owner) {
+            let window = self.notional.entry(owner).or_default();
+            while window.front().is_some_and(|(at, _)| now.duration_since(*at) >= NOTIONAL_WINDOW) {
+                window.pop_front();
+            }
+            let recent: Decimal = window.iter().map(|(_, n)| *n).sum();
+            if window.is_empty() {
+                self.notional.remove(&owner);
+            }
+            if recent + notional > limit {
+                return Err(RiskViolation::Notional { limit });
+            }
+        }
+
+        Ok(notional)
+    }
+
+    /// Count an accepted order towards the notional limit
+    pub fn record(&mut self, owner: Uuid, notional: Decimal, now: Instant) {
+        if self.limits(&owner).max_notional_per_second.is_none() {
+            return;
+        }
+        self.notional.entry(owner).or_default().push_back((now, notional));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn order(owner: Uuid, price: Option<i64>, quantity: i64) -> OrderRisk {
+        OrderRisk {
+            owner: Some(owner),
+            price: price.map(Decimal::from),
+            quantity: Decimal::from(quantity),
+            open_orders: 0,
+        }
+    }
+
+    #[test]
+    fn test_limits_per_owner() {
+        let owner = Uuid::new_v4();
+        let mut risk = RiskEngine::new(RiskLimits {
+            max_order_quantity: Some(Decimal::from(10)),
+            ..Default::default()
+        });
+        let now = Instant::now();
+
+        assert!(risk.check(&order(owner, Some(100), 10), now).is_ok());
+        assert_eq!(
+            risk.check(&order(owner, Some(100), 11), now),
+            Err(RiskViolation::OrderSize {
+                quantity: Decimal::from(11),
+                limit: Decimal::from(10),
+            })
+        );
+
+        risk.set_limits(
+            owner,
+            Some(RiskLimits {
+                max_open_orders: Some(2),
+                ..Default::default()
+            }),
+        );
+        assert!(risk.check(&order(owner, Some(100), 11), now).is_ok());
+        let mut resting = order(owner, Some(100), 1);
+        resting.open_orders = 2;
+        assert_eq!(risk.check(&resting, now), Err(RiskViolation::OpenOrders { limit: 2 }));
+        // A market order never rests
+        assert!(risk.check(&order(owner, None, 1), now).is_ok());
+
+        // Others keep the defaults
+        assert!(risk.check(&order(Uuid::new_v4(), Some(100), 11), now).is_err());
+    }
+
+    #[test]
+    fn test_notional_window_and_price_band() {
+        let owner = Uuid::new_v4();
+        let mut risk = RiskEngine::new(RiskLimits {
+            max_notional_per_second: Some(Decimal::from(1_000)),
+            price_band_percent: Some(Decimal::from(10)),
+            ..Default::default()
+        });
+        let start = Instant::now();
+
+        let notional = risk.check(&order(owner, Some(100), 6), start).unwrap();
+        risk.record(owner, notional, start);
+        assert_eq!(
+            risk.check(&order(owner, Some(100), 5), start),
+            Err(RiskViolation::Notional {
+                limit: Decimal::from(1_000)
+            })
+        );
+        assert!(risk.check(&order(owner, Some(100), 5), start + NOTIONAL_WINDOW).is_ok());
+
+        // No band until something traded
+        assert!(risk.check(&order(owner, Some(150), 1), start).is_ok());
+        risk.observe_trade(Decimal::from(100));
+        assert!(risk.check(&order(owner, Some(110), 1), start).is_ok());
+        assert!(matches!(
+            risk.check(&order(owner, Some(111), 1), start),
+            Err(RiskViolation::PriceBand { .. })
+        ));
+        assert!(matches!(
+            risk.check(&order(owner, Some(89), 1), start),
+            Err(RiskViolation::PriceBand { .. })
+        ));
+    }
+
+    #[test]
+    fn test_orders_without_owner_checked_against_defaults() {
+        let mut risk = RiskEngine::new(RiskLimits {
+            max_order_quantity: Some(Decimal::from(10)),
+            max_open_orders: Some(0),
+            max_notional_per_second: Some(Decimal::ONE),
+            price_band_percent: Some(Decimal::from(10)),
+        });
+        risk.observe_trade(Decimal::from(100));
+        let anonymous = |price, quantity| OrderRisk {
+            owner: None,
+            ..order(Uuid::nil(), Some(price), quantity)
+        };
+        let now = Instant::now();
+
+        // Open orders and notional are counted per owner
+        assert!(risk.check(&anonymous(100, 10), now).is_ok());
+        assert!(matches!(
+            risk.check(&anonymous(100, 11), now),
+            Err(RiskViolation::OrderSize { .. })
+        ));
+        assert!(matches!(
+            risk.check(&anonymous(111, 1), now),
+            Err(RiskViolation::PriceBand { .. })
+        ));
+    }
+
+    #[test]
+    fn test_kill_switch_and_owner_counts() {
+        let owner = Uuid::new_v4();
+        let mut risk = RiskEngine::new(RiskLimits::default());
+        assert!(risk.kill(owner));
+        assert!(!risk.kill(owner));
+        assert_eq!(risk.check(&order(owner, Some(1), 1), Instant::now()), Err(RiskViolation::KillSwitch));
+        assert!(risk.revive(&owner));
+        assert!(risk.check(&order(owner, Some(1), 1), Instant::now()).is_ok());
+
+        let mut owners = OrderOwners::default();
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        owners.insert(a, owner);
+        owners.insert(b, owner);
+        assert_eq!(owners.open_orders(&owner), 2);
+        assert_eq!(owners.remove(&a), Some(owner));
+        assert_eq!(owners.remove(&a), None);
+        assert_eq!(owners.orders_of(&owner), vec![b]);
+        owners.remove(&b);
+        assert_eq!(owners.open_orders(&owner), 0);
+        assert!(owners.is_empty());
+    }
+}
@@ -10,6 +10,8 @@ use std::time::Duration;
 use tracing::{error, info, warn};
 use uuid::Uuid;
 
+use crate::events::{AssetBalance, MarketEvent};
+
 /// Maximum number of retry attempts for settlement
 const MAX_RETRIES: u32 = 3;
 /// Base delay for exponential backoff (doubles each retry)
@@ -40,6 +42,18 @@ pub struct SettleFillResponse {
     pub buyer_id: String,
     pub seller_id: String,
     pub settled: bool,
+    /// Balances of the counterparties in the traded assets after settlement
+    #[serde(default)]
+    pub positions: Vec<Position>,
+}
+
+/// Balance of one user in one asset, as reported by the accounts service
+#[derive(Debug, Clone, Deserialize)]
+pub struct Position {
+    pub user_id: Uuid,
+    pub asset: String,
+    pub available: Decimal,
+    pub locked: Decimal,
 }
 
 #[derive(Debug, Deserialize)]
@@ -59,6 +73,36 @@ pub enum SettlementResult {
     Failed(String),
 }
 
+#[derive(Debug, Serialize)]
+struct BustTradeRequest<'a> {
+    buy_order_id: Uuid,
+    sell_order_id: Uuid,
+    timestamp: i64,
+    reason: &'a str,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BustTradeResponse {
+    pub trade_id: Uuid,
+    pub symbol: String,
+    /// None for anonymous/bot orders
+    pub buyer_id: Option<Uuid>,
+    pub seller_id: Option<Uuid>,
+    pub price: Decimal,
+    pub quantity: Decimal,
+}
+
+/// Result of a bust attempt
+#[derive(Debug)]
+pub enum BustResult {
+    /// The ledger entries were reversed
+    Busted(BustTradeResponse),
+    /// The accounts service refused the bust (unknown trade, already busted, ...)
+    Refused { status: u16, error: String },
+    /// The accounts service could not be reached or answered garbage
+    Failed(String),
+}
+
 #[derive(Debug, Serialize)]
 struct CancelOrderRequest {
     order_id: Uuid,
@@ -72,6 +116,33 @@ pub struct CancelOrderResponse {
     pub status: String,
 }
 
+impl SettleFillResponse {
+    /// One position update per counterparty, each carrying only that user's balances
+    pub fn position_updates(&self, timestamp: u64) -> Vec<MarketEvent> {
+        let mut updates: Vec<MarketEvent> = Vec::new();
+        for position in &self.positions {
+            let balance = AssetBalance {
+                asset: position.asset.clone(),
+                available: position.available,
+                locked: position.locked,
+            };
+            let existing = updates.iter_mut().find_map(|update| match update {
+                MarketEvent::PositionUpdate { user_id, balances, .. } if *user_id == position.user_id => Some(balances),
+                _ => None,
+            });
+            match existing {
+                Some(balances) => balances.push(balance),
+                None => updates.push(MarketEvent::PositionUpdate {
+                    user_id: position.user_id,
+                    balances: vec![balance],
+                    timestamp,
+                }),
+            }
+        }
+        updates
+    }
+}
+
 impl SettlementClient {
     pub fn new(accounts_url: String) -> Self {
         let client = Client::builder()
@@ -174,6 +245,7 @@ impl SettlementClient {
                                         buyer_id: String::new(),
                                         seller_id: String::new(),
                                         settled: true,
+                                        positions: Vec::new(),
                                     });
                                 } else if status.is_server_error() && attempts < MAX_RETRIES {
                                     // Server error - retry
@@ -294,4 +366,121 @@ impl SettlementClient {
             }
         }
     }
+
+    /// Reverse a settled trade in the accounts service.
+    /// The trade is identified the way it was settled: both order IDs and the
+    /// fill timestamp. Not retried, the operator sees the outcome right away.
+    pub async fn bust_trade(
+        &self,
+        buy_order_id: Uuid,
+        sell_order_id: Uuid,
+        timestamp: u64,
+        reason: &str,
+    ) -> BustResult {
+        let url = format!("{}/internal/trades/bust", self.accounts_url);
+
+        let request = BustTradeRequest {
+            buy_order_id,
+            sell_order_id,
+            timestamp: timestamp.min(i64::MAX as u64) as i64,
+            reason,
+        };
+
+        let response = match self.client.post(&url).json(&request).send().await {
+            Ok(response) => response,
+            Err(e) => {
+                warn!("Bust network error for buy={}, sell={}: {}", buy_order_id, sell_order_id, e);
+                return BustResult::Failed(format!("Network error: {}", e));
+            }
+        };
+
+        let status = response.status();
+        if status.is_success() {
+            return match response.json::<BustTradeResponse>().await {
+                Ok(result) => {
+                    info!(
+                        "Busted trade {}: {} {} @ {}",
+                        result.trade_id, result.quantity, result.symbol, result.price
+                    );
+                    BustResult::Busted(result)
+                }
+                Err(e) => {
+                    warn!("Failed to parse bust response: {}", e);
+                    BustResult::Failed(format!("Parse error: {}", e))
+                }
+            };
+        }
+
+        match response.json::<SettleErrorResponse>().await {
+            Ok(err) if !status.is_server_error() => {
+                warn!(
+                    "Bust refused for buy={}, sell={}: {} ({})",
+                    buy_order_id, sell_order_id, err.error, err.code
+                );
+                BustResult::Refused {
+                    status: status.as_u16(),
+                    error: err.error,
+                }
+            }
+            Ok(err) => {
+                error!(
+                    "Bust failed for buy={}, sell={}: {} ({})",
+                    buy_order_id, sell_order_id, err.error, err.code
+                );
+                BustResult::Failed(format!("{}: {}", err.code, err.error))
+            }
+            Err(_) => {
+                error!("Bust failed for buy={}, sell={}: status {}", buy_order_id, sell_order_id, status);
+                BustResult::Failed(format!("HTTP {}", status))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_position_updates_grouped_by_user() {
+        let buyer = Uuid::new_v4();
+        let seller = Uuid::new_v4();
+        let position = |user_id, asset: &str, available| Position {
+            user_id,
+            asset: asset.to_string(),
+            available: Decimal::new(available, 0),
+            locked: Decimal::ZERO,
+        };
+        let response = SettleFillResponse {
+            trade_id: Uuid::new_v4().to_string(),
+            buyer_id: buyer.to_string(),
+            seller_id: seller.to_string(),
+            settled: true,
+            positions: vec![
+                position(buyer, "EUR", 950),
+                position(buyer, "KCN", 10),
+                position(seller, "EUR", 50),
+                position(seller, "KCN", 90),
+            ],
+        };
+
+        let updates = response.position_updates(1_700_000_000_000);
+        assert_eq!(updates.len(), 2);
+        for (update, user) in updates.iter().zip([buyer, seller]) {
+            match update {
+                MarketEvent::PositionUpdate { user_id, balances, timestamp } => {
+                    assert_eq!(*user_id, user);
+                    assert_eq!(*timestamp, 1_700_000_000_000);
+                    let assets: Vec<&str> = balances.iter().map(|b| b.asset.as_str()).collect();
+                    assert_eq!(assets, ["EUR", "KCN"]);
+                }
+                other => panic!("Unexpected event {:?}", other),
+            }
+        }
+
+        // Older accounts services do not report positions
+        let legacy: SettleFillResponse =
+            serde_json::from_str(r#"{"trade_id":"t","buyer_id":"","seller_id":"","settled":true}"#).unwrap();
+        assert!(legacy.position_updates(0).is_empty());
+    }
 }
@@ -1,19 +1,47 @@
 use parking_lot;
 use std::net::{SocketAddr, ToSocketAddrs};
+use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::mpsc;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
 use udp_proto::{
-    MessageType, ReceiverConfig, ReceivedMessage, SenderConfig, UdpReceiver, UdpSender,
-    binary::{MarketEventEncoder, MarketEvent as BinaryMarketEvent, PriceLevel, LevelDelta, Side as BinarySide, DeltaAction as BinaryDeltaAction},
+    AuthConfig, AuthMode, OverflowPolicy, ReceiverConfig, ReceivedMessage, SenderConfig, StreamKey, UdpReceiver, UdpSender,
+    binary::{MarketEventEncoder, MarketEvent as BinaryMarketEvent, AssetBalance as BinaryAssetBalance, PriceLevel, LevelDelta, Side as BinarySide, DeltaAction as BinaryDeltaAction},
+    binary::{decode_order_command, OrderCommand as BinaryOrderCommand, OrderType as BinaryOrderType, MarketPhase as BinaryMarketPhase},
 };
 
-use crate::events::{MarketEvent, OrderCommand, Side, DeltaAction};
+use crate::engine::CommandQueue;
+use crate::events::{MarketEvent, MarketPhase, OrderCommand, Side, DeltaAction};
+
+impl From<MarketPhase> for BinaryMarketPhase {
+    fn from(phase: MarketPhase) -> Self {
+        match phase {
+            MarketPhase::PreOpen => BinaryMarketPhase::PreOpen,
+            MarketPhase::Open => BinaryMarketPhase::Open,
+            MarketPhase::Auction => BinaryMarketPhase::Auction,
+            MarketPhase::Halted => BinaryMarketPhase::Halted,
+            MarketPhase::Closed => BinaryMarketPhase::Closed,
+            MarketPhase::Delisted => BinaryMarketPhase::Delisted,
+        }
+    }
+}
+
+impl From<BinaryMarketPhase> for MarketPhase {
+    fn from(phase: BinaryMarketPhase) -> Self {
+        match phase {
+            BinaryMarketPhase::PreOpen => MarketPhase::PreOpen,
+            BinaryMarketPhase::Open => MarketPhase::Open,
+            BinaryMarketPhase::Auction => MarketPhase::Auction,
+            BinaryMarketPhase::Halted => MarketPhase::Halted,
+            BinaryMarketPhase::Closed => MarketPhase::Closed,
+            BinaryMarketPhase::Delisted => MarketPhase::Delisted,
+        }
+    }
+}
 
 /// Resolve a string address (hostname:port or ip:port) to SocketAddr
 /// Retries with exponential backoff for DNS resolution
-fn resolve_addr(addr_str: &str) -> SocketAddr {
+pub(crate) fn resolve_addr(addr_str: &str) -> SocketAddr {
     let mut attempts = 0;
     let max_attempts = 10;
     let mut delay = Duration::from_millis(100);
@@ -52,6 +80,9 @@ fn resolve_addr(addr_str: &str) -> SocketAddr {
 const ORDER_STREAM_ID: u32 = 1;
 const EVENT_STREAM_ID: u32 = 2;
 
+/// Key ID of `ORDER_STREAM_KEY` (must match gateway)
+const ORDER_STREAM_KEY_ID: u32 = 1;
+
 /// UDP sender for market events (matching engine -> gateway)
 /// Uses lazy DNS resolution for the target address
 pub struct UdpEventSender {
@@ -88,7 +119,13 @@ impl UdpEventSender {
                         target_addr,
                         max_batch_delay: Duration::from_micros(100),
                         channel_capacity: 10_000,
+                        // Under load only the latest book of a symbol is worth sending,
+                        // the gateway resyncs it from a snapshot. Fills are never dropped.
+                        overflow: OverflowPolicy::Coalesce,
                         enable_heartbeats: true,
+                        // The gateway measures how long events take to reach it
+                        send_timestamps: true,
+                        ..Default::default()
                     };
                     match UdpSender::new(config, bind_addr) {
                         Ok(sender) => {
@@ -154,6 +191,20 @@ impl UdpEventSender {
                 info!("Sending Fill event via UDP: buy_order={}, sell_order={}, price={}, qty={}",
                     buy_order_id, sell_order_id, price, quantity);
             }
+            MarketEvent::MarketStatus { symbol, phase, reason, .. } => {
+                info!("Sending MarketStatus event via UDP: symbol={}, phase={:?}, reason={}",
+                    symbol, phase, reason);
+            }
+            MarketEvent::OrderRejected { order_id, reason } => {
+                info!("Sending OrderRejected event via UDP: order_id={}, reason={}", order_id, reason);
+            }
+            MarketEvent::TradeBust { trade_id, symbol, reason, .. } => {
+                info!("Sending TradeBust event via UDP: trade_id={}, symbol={}, reason={}",
+                    trade_id, symbol, reason);
+            }
+            MarketEvent::PositionUpdate { user_id, balances, .. } => {
+                info!("Sending PositionUpdate event via UDP: user_id={}, assets={}", user_id, balances.len());
+            }
             _ => {}
         }
 
@@ -177,14 +228,14 @@ impl UdpEventSender {
         let guard = self.sender.read();
         if let Some(sender) = guard.as_ref() {
             sender
-                .try_send(MessageType::MatchEvent, data)
+                .try_send(binary_event.message_type(), data)
                 .map_err(|e| anyhow::anyhow!("UDP send error: {:?}", e))?;
         }
 
         Ok(())
     }
 
-    fn convert_to_binary(event: &MarketEvent) -> BinaryMarketEvent {
+    pub(crate) fn convert_to_binary(event: &MarketEvent) -> BinaryMarketEvent {
         match event {
             MarketEvent::Fill {
                 buy_order_id,
@@ -197,8 +248,8 @@ impl UdpEventSender {
                 symbol: symbol.clone(),
                 buy_order_id: *buy_order_id,
                 sell_order_id: *sell_order_id,
-                price: price.to_string().parse().unwrap_or(0.0),
-                quantity: quantity.to_string().parse().unwrap_or(0.0),
+                price: *price,
+                quantity: *quantity,
                 timestamp: *timestamp,
             },
             MarketEvent::OrderBookSnapshot {
@@ -212,15 +263,15 @@ impl UdpEventSender {
                 bids: bids
                     .iter()
                     .map(|l| PriceLevel {
-                        price: l.price.to_string().parse().unwrap_or(0.0),
-                        quantity: l.quantity.to_string().parse().unwrap_or(0.0),
+                        price: l.price,
+                        quantity: l.quantity,
                     })
                     .collect(),
                 asks: asks
                     .iter()
                     .map(|l| PriceLevel {
-                        price: l.price.to_string().parse().unwrap_or(0.0),
-                        quantity: l.quantity.to_string().parse().unwrap_or(0.0),
+                        price: l.price,
+                        quantity: l.quantity,
                     })
                     .collect(),
             },
@@ -243,8 +294,8 @@ impl UdpEventSender {
                             Side::Bid => BinarySide::Bid,
                             Side::Ask => BinarySide::Ask,
                         },
-                        price: d.price.to_string().parse().unwrap_or(0.0),
-                        quantity: d.quantity.to_string().parse().unwrap_or(0.0),
+                        price: d.price,
+                        quantity: d.quantity,
                     })
                     .collect(),
             },
@@ -255,7 +306,7 @@ impl UdpEventSender {
             MarketEvent::OrderCancelled { order_id, filled_quantity } => {
                 BinaryMarketEvent::OrderCancelled {
                     order_id: *order_id,
-                    filled_quantity: filled_quantity.to_string().parse().unwrap_or(0.0),
+                    filled_quantity: *filled_quantity,
                 }
             }
             MarketEvent::OrderFilled { order_id } => {
@@ -263,6 +314,53 @@ impl UdpEventSender {
                     order_id: *order_id,
                 }
             }
+            MarketEvent::MarketStatus { symbol, phase, reason, timestamp } => {
+                BinaryMarketEvent::MarketStatus {
+                    symbol: symbol.clone(),
+                    phase: (*phase).into(),
+                    reason: reason.clone(),
+                    timestamp: *timestamp,
+                }
+            }
+            MarketEvent::OrderRejected { order_id, reason } => BinaryMarketEvent::OrderRejected {
+                order_id: *order_id,
+                reason: reason.clone(),
+            },
+            MarketEvent::TradeBust {
+                symbol,
+                trade_id,
+                buy_order_id,
+                sell_order_id,
+                buyer_id,
+                seller_id,
+                price,
+                quantity,
+                timestamp,
+                reason,
+            } => BinaryMarketEvent::TradeBust {
+                symbol: symbol.clone(),
+                trade_id: *trade_id,
+                buy_order_id: *buy_order_id,
+                sell_order_id: *sell_order_id,
+                buyer_id: *buyer_id,
+                seller_id: *seller_id,
+                price: *price,
+                quantity: *quantity,
+                timestamp: *timestamp,
+                reason: reason.clone(),
+            },
+            MarketEvent::PositionUpdate { user_id, balances, timestamp } => BinaryMarketEvent::PositionUpdate {
+                user_id: *user_id,
+                balances: balances
+                    .iter()
+                    .map(|b| BinaryAssetBalance {
+                        asset: b.asset.clone(),
+                        available: b.available,
+                        locked: b.locked,
+                    })
+                    .collect(),
+                timestamp: *timestamp,
+            },
         }
     }
 
@@ -273,56 +371,47 @@ impl UdpEventSender {
 
 /// UDP receiver for order commands (gateway -> matching engine)
 pub struct UdpOrderReceiver {
-    _receiver: UdpReceiver,
+    receiver: Arc<UdpReceiver>,
 }
 
 impl UdpOrderReceiver {
-    pub fn new(
-        bind_addr: SocketAddr,
-    ) -> anyhow::Result<(Self, mpsc::Receiver<OrderCommand>)> {
+    /// With `auth` set, packets without a valid tag are dropped
+    pub fn new(bind_addr: SocketAddr, commands: CommandQueue, auth: Option<AuthConfig>) -> anyhow::Result<Self> {
+        let signed = auth.is_some();
         let config = ReceiverConfig {
             stream_id: ORDER_STREAM_ID,
             channel_capacity: 10_000,
             recv_timeout: Duration::from_millis(10),
             stream_timeout: Duration::from_millis(500),
+            auth,
+            ..Default::default()
         };
 
-        let receiver = UdpReceiver::new(config, bind_addr)?;
-        info!("UDP order receiver created on {}", bind_addr);
-
-        // Create channel for forwarding orders
-        let (order_tx, order_rx) = mpsc::channel(10_000);
+        let receiver = Arc::new(UdpReceiver::new(config, bind_addr)?);
+        info!("UDP order receiver created on {} (signed: {})", bind_addr, signed);
 
-        // Clone for the spawned thread
-        let rx_clone = receiver;
+        // The loop thread shares the receiver, stats stay readable from here
+        let rx_clone = receiver.clone();
 
-        // Spawn receiver task
+        // Spawn receiver task, the only producer of the command ring
         std::thread::spawn(move || {
-            Self::receiver_loop(rx_clone, order_tx);
+            Self::receiver_loop(&rx_clone, commands);
         });
 
-        Ok((
-            Self {
-                _receiver: UdpReceiver::new(
-                    ReceiverConfig {
-                        stream_id: 0, // Dummy, not used
-                        ..Default::default()
-                    },
-                    "127.0.0.1:0".parse().unwrap(), // Dummy address
-                )
-                .unwrap_or_else(|_| panic!("Failed to create dummy receiver")),
-            },
-            order_rx,
-        ))
+        Ok(Self { receiver })
     }
 
-    fn receiver_loop(receiver: UdpReceiver, order_tx: mpsc::Sender<OrderCommand>) {
+    pub fn stats(&self) -> udp_proto::ReceiverStatsSnapshot {
+        self.receiver.stats()
+    }
+
+    fn receiver_loop(receiver: &UdpReceiver, mut commands: CommandQueue) {
         info!("UDP order receiver loop started");
 
         loop {
             match receiver.recv_timeout(Duration::from_millis(100)) {
                 Ok(Some(msg)) => {
-                    if let Err(e) = Self::handle_message(&msg, &order_tx) {
+                    if let Err(e) = Self::handle_message(&msg, &mut commands) {
                         error!("Failed to handle UDP message: {}", e);
                     }
                 }
@@ -339,17 +428,64 @@ impl UdpOrderReceiver {
 
     fn handle_message(
         msg: &ReceivedMessage,
-        order_tx: &mpsc::Sender<OrderCommand>,
+        commands: &mut CommandQueue,
     ) -> anyhow::Result<()> {
-        // Parse the JSON payload
-        let command: OrderCommand = serde_json::from_slice(&msg.payload)?;
+        let binary_command = decode_order_command(msg.msg_type, &msg.payload)
+            .map_err(|e| anyhow::anyhow!("Failed to decode order command: {}", e))?;
+
+        if let BinaryOrderCommand::SnapshotRequest { symbol } = binary_command {
+            info!("Received snapshot request via UDP for {}", symbol);
+            commands.request_snapshot(symbol);
+            return Ok(());
+        }
+
+        let Some(command) = Self::convert_from_binary(binary_command) else {
+            return Ok(());
+        };
         info!("Received order command via UDP: {:?}", command);
 
-        // Send to channel (blocking in sync context)
-        order_tx.blocking_send(command)?;
+        commands.push(command);
 
         Ok(())
     }
+
+    /// Convert a decoded wire command to the engine's command type.
+    /// Returns None for commands the engine does not support.
+    pub(crate) fn convert_from_binary(command: BinaryOrderCommand) -> Option<OrderCommand> {
+        match command {
+            BinaryOrderCommand::New {
+                order_id,
+                user_id,
+                side,
+                order_type,
+                price,
+                quantity,
+            } => Some(OrderCommand::PlaceOrder {
+                order_id,
+                side: match side {
+                    BinarySide::Bid => Side::Bid,
+                    BinarySide::Ask => Side::Ask,
+                },
+                order_type: match order_type {
+                    BinaryOrderType::Limit => "limit".to_string(),
+                    BinaryOrderType::Market => "market".to_string(),
+                },
+                price,
+                quantity,
+                user_id,
+            }),
+            BinaryOrderCommand::Cancel { order_id, user_id } => {
+                Some(OrderCommand::CancelOrder { order_id, user_id })
+            }
+            BinaryOrderCommand::Replace { order_id, .. } => {
+                // Settlement has no notion of amending a reservation yet
+                warn!("Order replace not supported, ignoring command for order {}", order_id);
+                None
+            }
+            // Not an order, handled before conversion
+            BinaryOrderCommand::SnapshotRequest { .. } => None,
+        }
+    }
 }
 
 /// Configuration for UDP transport
@@ -361,6 +497,8 @@ pub struct UdpTransportConfig {
     pub gateway_event_addr: String,
     /// Local address to bind event sender
     pub event_sender_bind: SocketAddr,
+    /// Pre-shared key of the order stream, unsigned orders are refused once set
+    pub order_stream_auth: Option<AuthConfig>,
 }
 
 impl Default for UdpTransportConfig {
@@ -369,6 +507,7 @@ impl Default for UdpTransportConfig {
             order_receiver_bind: "127.0.0.1:9100".parse().unwrap(),
             gateway_event_addr: "127.0.0.1:9101".to_string(),
             event_sender_bind: "127.0.0.1:9103".parse().unwrap(),
+            order_stream_auth: None,
         }
     }
 }
@@ -388,10 +527,71 @@ impl UdpTransportConfig {
             .unwrap_or_else(|_| "0.0.0.0:9103".to_string());
         let event_sender_bind = resolve_addr(&event_sender_bind_str);
 
+        let order_stream_auth = std::env::var("ORDER_STREAM_KEY")
+            .ok()
+            .filter(|k| !k.is_empty())
+            .map(|k| AuthConfig::new(AuthMode::Sign, StreamKey::new(ORDER_STREAM_KEY_ID, k.into_bytes())));
+
         Self {
             order_receiver_bind,
             gateway_event_addr,
             event_sender_bind,
+            order_stream_auth,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::CoreCommand;
+    use std::time::Instant;
+    use udp_proto::binary::OrderCommandEncoder;
+    use udp_proto::MessageType;
+
+    fn sender(target_addr: SocketAddr, auth: Option<AuthConfig>) -> UdpSender {
+        let config = SenderConfig {
+            stream_id: ORDER_STREAM_ID,
+            target_addr,
+            auth,
+            ..Default::default()
+        };
+        UdpSender::new(config, "127.0.0.1:0".parse().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_keyed_order_stream_refuses_unsigned_orders() {
+        let auth = AuthConfig::new(AuthMode::Sign, StreamKey::new(ORDER_STREAM_KEY_ID, b"order stream key".to_vec()));
+        let order_addr = std::net::UdpSocket::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let (producer, mut ring) = rtrb::RingBuffer::new(16);
+        let receiver =
+            UdpOrderReceiver::new(order_addr, CommandQueue::new(producer, std::thread::current()), Some(auth.clone()))
+                .unwrap();
+        let request = OrderCommandEncoder::new()
+            .encode(&BinaryOrderCommand::SnapshotRequest {
+                symbol: "KCN/EUR".to_string(),
+            })
+            .to_vec();
+
+        let plain = sender(order_addr, None);
+        plain.send(MessageType::SnapshotRequest, request.clone()).unwrap();
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while receiver.stats().auth_failures == 0 {
+            assert!(Instant::now() < deadline, "unsigned packet never arrived");
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        assert!(ring.pop().is_err());
+
+        let signed = sender(order_addr, Some(auth));
+        signed.send(MessageType::SnapshotRequest, request).unwrap();
+        let deadline = Instant::now() + Duration::from_secs(2);
+        let queued = loop {
+            if let Ok(queued) = ring.pop() {
+                break queued;
+            }
+            assert!(Instant::now() < deadline, "signed order was not delivered");
+            std::thread::sleep(Duration::from_millis(5));
+        };
+        assert!(matches!(queued.command, CoreCommand::Snapshot { symbol } if symbol == "KCN/EUR"));
+    }
+}
@@ -0,0 +1,125 @@
+//! Helpers for tests that run the engine binary
+
+#![allow(dead_code)]
+
+use rust_decimal::Decimal;
+use serde_json::Value;
+use std::net::{SocketAddr, TcpListener, UdpSocket};
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+use udp_proto::binary::{OrderCommand, OrderCommandEncoder, OrderType, Side};
+use udp_proto::{SenderConfig, UdpSender};
+use uuid::Uuid;
+
+pub const TOKEN: &str = "engine-test-token";
+pub const BOOK: &str = "admin/markets/KCN%2FEUR/book?level=l3";
+
+pub fn free_udp() -> SocketAddr {
+    UdpSocket::bind("127.0.0.1:0").unwrap().local_addr().unwrap()
+}
+
+pub fn free_tcp() -> SocketAddr {
+    TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap()
+}
+
+/// One engine process, killed when dropped
+pub struct Engine {
+    process: Child,
+    pub http: String,
+    pub orders: SocketAddr,
+}
+
+impl Engine {
+    /// Start the engine with `env` on top of loopback defaults
+    pub fn spawn(env: &[(&str, String)]) -> Self {
+        let (http, orders) = (free_tcp(), free_udp());
+        let process = Command::new(env!("CARGO_BIN_EXE_matching_engine_service"))
+            .env("BIND_ADDR", http.to_string())
+            .env("ORDER_RECEIVER_BIND", orders.to_string())
+            .env("GATEWAY_EVENT_ADDR", free_udp().to_string())
+            .env("EVENT_SENDER_BIND", "127.0.0.1:0")
+            .env("ACCOUNTS_URL", "http://127.0.0.1:1")
+            .env("ADMIN_TOKEN", TOKEN)
+            // Two spinning cores on one CPU would starve each other
+            .env("MATCHING_CORE_CPU", "none")
+            .env("RUST_LOG", "matching_engine_service=warn")
+            .envs(env.iter().map(|(k, v)| (*k, v.as_str())))
+            .stdout(Stdio::null())
+            .spawn()
+            .expect("engine binary");
+
+        Self {
+            process,
+            http: format!("http://{}", http),
+            orders,
+        }
+    }
+
+    pub async fn get(&self, client: &reqwest::Client, path: &str) -> Option<Value> {
+        let response = client
+            .get(format!("{}/{}", self.http, path))
+            .bearer_auth(TOKEN)
+            .send()
+            .await
+            .ok()?;
+        response.json().await.ok()
+    }
+
+    pub async fn metric(&self, client: &reqwest::Client, name: &str) -> u64 {
+        let Ok(response) = client.get(format!("{}/metrics", self.http)).send().await else {
+            return 0;
+        };
+        response
+            .text()
+            .await
+            .unwrap_or_default()
+            .lines()
+            .find_map(|line| line.strip_prefix(name)?.trim().parse().ok())
+            .unwrap_or(0)
+    }
+}
+
+impl Drop for Engine {
+    fn drop(&mut self) {
+        let _ = self.process.kill();
+        let _ = self.process.wait();
+    }
+}
+
+pub fn order_sender(target: SocketAddr) -> UdpSender {
+    UdpSender::new(
+        SenderConfig {
+            stream_id: 1,
+            target_addr: target,
+            ..Default::default()
+        },
+        "127.0.0.1:0".parse().unwrap(),
+    )
+    .unwrap()
+}
+
+pub fn place(sender: &UdpSender, side: Side, price: i64, quantity: i64) {
+    let command = OrderCommand::New {
+        order_id: Uuid::new_v4(),
+        user_id: None,
+        side,
+        order_type: OrderType::Limit,
+        price: Some(Decimal::from(price)),
+        quantity: Decimal::from(quantity),
+    };
+    let data = OrderCommandEncoder::new().encode(&command).to_vec();
+    sender.try_send(command.message_type(), data).unwrap();
+}
+
+/// Poll until `check` holds, failing the test after `timeout`
+pub async fn wait_for<F, Fut>(what: &str, timeout: Duration, mut check: F)
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = bool>,
+{
+    let deadline = Instant::now() + timeout;
+    while !check().await {
+        assert!(Instant::now() < deadline, "timed out waiting for {}", what);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+}
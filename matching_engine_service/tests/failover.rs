@@ -0,0 +1,93 @@
+//! Failover between two engine processes on loopback: the follower replays the
+//! primary's journal, and takes over the order stream once the primary dies.
+
+mod common;
+
+use common::{free_udp, order_sender, place, wait_for, Engine, BOOK, TOKEN};
+use std::time::Duration;
+use udp_proto::binary::Side;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_follower_takes_over_from_dead_primary() {
+    let client = reqwest::Client::new();
+    let journal = free_udp();
+
+    let follower = Engine::spawn(&[
+        ("ROLE", "follower".to_string()),
+        ("JOURNAL_BIND", journal.to_string()),
+        ("FAILOVER_TIMEOUT_MS", "1500".to_string()),
+    ]);
+    wait_for("follower to start", Duration::from_secs(10), || async {
+        follower.get(&client, "admin/replication").await.is_some()
+    })
+    .await;
+    let primary = Engine::spawn(&[
+        ("REPLICA_ADDR", journal.to_string()),
+        ("JOURNAL_SENDER_BIND", "127.0.0.1:0".to_string()),
+    ]);
+    wait_for("primary to start", Duration::from_secs(10), || async {
+        primary.get(&client, "admin/replication").await.is_some()
+    })
+    .await;
+
+    let role = follower.get(&client, "admin/replication").await.unwrap();
+    assert_eq!(role["role"], "follower");
+
+    let orders = order_sender(primary.orders);
+    place(&orders, Side::Ask, 101, 2);
+    place(&orders, Side::Ask, 102, 1);
+    place(&orders, Side::Bid, 101, 1);
+
+    wait_for("primary to match", Duration::from_secs(5), || async {
+        primary.get(&client, BOOK).await.is_some_and(|b| b["order_count"] == 2)
+    })
+    .await;
+    let expected = primary.get(&client, BOOK).await.unwrap();
+    wait_for("follower to verify a checksum", Duration::from_secs(5), || async {
+        follower.metric(&client, "replication_checksums_verified_total").await > 0
+            && follower.get(&client, BOOK).await.is_some_and(|b| b["order_count"] == 2)
+    })
+    .await;
+    let replayed = follower.get(&client, BOOK).await.unwrap();
+    assert_eq!(replayed["bids"], expected["bids"]);
+    assert_eq!(replayed["asks"], expected["asks"]);
+    assert_eq!(follower.metric(&client, "replication_checksum_mismatches_total").await, 0);
+
+    // Followers leave the book to the primary
+    let response = client
+        .post(format!("{}/admin/markets/KCN%2FEUR/cancel-all", follower.http))
+        .bearer_auth(TOKEN)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 409);
+
+    drop(primary);
+    wait_for("follower to take over", Duration::from_secs(10), || async {
+        follower
+            .get(&client, "admin/replication")
+            .await
+            .is_some_and(|r| r["role"] == "primary")
+    })
+    .await;
+
+    // The order receiver registers its metrics once it is bound
+    wait_for("promoted follower to bind the order stream", Duration::from_secs(5), || async {
+        let Ok(response) = client.get(format!("{}/metrics", follower.http)).send().await else {
+            return false;
+        };
+        response.text().await.is_ok_and(|t| t.contains("udp_receiver_packets_total"))
+    })
+    .await;
+
+    // The promoted replica matches against the book it replicated
+    let orders = order_sender(follower.orders);
+    place(&orders, Side::Bid, 102, 1);
+    wait_for("promoted follower to match", Duration::from_secs(5), || async {
+        follower.get(&client, BOOK).await.is_some_and(|b| b["order_count"] == 1)
+    })
+    .await;
+    let book = follower.get(&client, BOOK).await.unwrap();
+    assert_eq!(book["asks"][0]["price"], "102");
+    assert!(book["bids"].as_array().unwrap().is_empty());
+}
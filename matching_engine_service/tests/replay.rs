@@ -0,0 +1,94 @@
+//! Record a journal file with a running engine, then replay it offline and
+//! check that a doctored recording is caught.
+
+mod common;
+
+use common::{order_sender, place, wait_for, Engine, BOOK};
+use rust_decimal::Decimal;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use std::time::Duration;
+use udp_proto::binary::{JournalEntry, JournalRecord, Side};
+use udp_proto::journal_file::{JournalReader, JournalWriter};
+use uuid::Uuid;
+
+fn read_journal(path: &Path) -> Vec<JournalEntry> {
+    let file = File::open(path).unwrap();
+    JournalReader::new(BufReader::new(file))
+        .collect::<std::io::Result<_>>()
+        .unwrap()
+}
+
+fn replay(path: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_replay"))
+        .arg(path)
+        .args(args)
+        .output()
+        .expect("replay binary")
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_replay_matches_recorded_journal() {
+    let client = reqwest::Client::new();
+    let dir = std::env::temp_dir().join(format!("journal-{}", Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let journal: PathBuf = dir.join("engine.journal");
+
+    let engine = Engine::spawn(&[("JOURNAL_FILE", journal.display().to_string())]);
+    wait_for("engine to start", Duration::from_secs(10), || async {
+        engine.get(&client, "admin/replication").await.is_some()
+    })
+    .await;
+
+    let orders = order_sender(engine.orders);
+    place(&orders, Side::Ask, 101, 2);
+    place(&orders, Side::Ask, 102, 1);
+    place(&orders, Side::Bid, 101, 1);
+    wait_for("engine to match", Duration::from_secs(5), || async {
+        engine.get(&client, BOOK).await.is_some_and(|b| b["order_count"] == 2)
+    })
+    .await;
+
+    // The file is flushed with the checksum that follows the last command
+    wait_for("checksum to be recorded", Duration::from_secs(5), || async {
+        journal.exists()
+            && read_journal(&journal)
+                .iter()
+                .any(|e| e.sequence == 3 && matches!(e.record, JournalRecord::Checksum { .. }))
+    })
+    .await;
+    drop(engine);
+
+    let output = replay(&journal, &["--fills"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{}", stdout);
+    assert!(stdout.contains("#3 fill 1 @ 101"), "{}", stdout);
+    assert!(stdout.contains("Replayed 3 commands in 1 runs: 1 fills"), "{}", stdout);
+    assert!(stdout.contains("0 divergences"), "{}", stdout);
+
+    // Up to the second command nothing has matched yet
+    let output = replay(&journal, &["--until", "2"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{}", stdout);
+    assert!(stdout.contains("Book after sequence 2 (2 resting orders)"), "{}", stdout);
+
+    // A recording that disagrees with the engine is reported
+    let doctored = dir.join("doctored.journal");
+    let mut writer = JournalWriter::new(File::create(&doctored).unwrap());
+    for mut entry in read_journal(&journal) {
+        if let JournalRecord::Fill { quantity, .. } = &mut entry.record {
+            *quantity = Decimal::TWO;
+        }
+        writer.write(&entry).unwrap();
+    }
+    drop(writer);
+
+    let output = replay(&doctored, &[]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(1), "{}", stdout);
+    assert!(stdout.contains("DIVERGENCE at 3"), "{}", stdout);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -1,4 +1,4 @@
-use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput, BenchmarkId};
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion, Throughput, BenchmarkId};
 use std::net::SocketAddr;
 use std::sync::atomic::{AtomicU16, Ordering};
 use std::time::Duration;
@@ -147,6 +147,57 @@ fn bench_packet_parse(c: &mut Criterion) {
     group.finish();
 }
 
+fn bench_packet_auth(c: &mut Criterion) {
+    let mut group = c.benchmark_group("packet_auth");
+
+    let payload_sizes = [64, 1024];
+    let key = StreamKey::new(1, b"benchmark stream key".to_vec());
+
+    for mode in [AuthMode::Sign, AuthMode::Encrypt] {
+        let config = AuthConfig::new(mode, key.clone());
+        let name = format!("{:?}", mode).to_lowercase();
+
+        for size in payload_sizes {
+            let mut builder = PacketBuilder::new(1, 0, 0);
+            builder.try_add_message(MessageType::OrderNew, &vec![0xABu8; size]);
+            let packet = builder.finish();
+            let mut sealer = Sealer::new(&config, Role::Sender).unwrap();
+            let mut opener = Opener::new(&config);
+
+            group.throughput(Throughput::Bytes(packet.len() as u64));
+            group.bench_with_input(BenchmarkId::new(format!("{}_seal", name), size), &packet, |b, packet| {
+                b.iter_batched(
+                    || packet.clone(),
+                    |mut packet| {
+                        sealer.seal(black_box(&mut packet));
+                        packet
+                    },
+                    BatchSize::SmallInput,
+                )
+            });
+
+            // Every packet opened needs a fresh packet_seq to pass the replay check
+            let mut packet_seq = 0u64;
+            let from: SocketAddr = "127.0.0.1:9000".parse().unwrap();
+            group.bench_with_input(BenchmarkId::new(format!("{}_open", name), size), &packet, |b, packet| {
+                b.iter_batched(
+                    || {
+                        let mut packet = packet.clone();
+                        packet_seq += 1;
+                        packet[8..16].copy_from_slice(&packet_seq.to_le_bytes());
+                        sealer.seal(&mut packet);
+                        packet
+                    },
+                    |mut packet| opener.open(black_box(&mut packet), from).unwrap(),
+                    BatchSize::SmallInput,
+                )
+            });
+        }
+    }
+
+    group.finish();
+}
+
 fn bench_end_to_end_throughput(c: &mut Criterion) {
     let mut group = c.benchmark_group("end_to_end");
     group.measurement_time(Duration::from_secs(10));
@@ -191,6 +242,62 @@ fn bench_end_to_end_throughput(c: &mut Criterion) {
     group.finish();
 }
 
+/// Bursts of messages through a sender and a receiver on loopback, one
+/// message per packet for 1024 bytes and fragments for 64KB, so the batched
+/// syscalls of the worker threads carry most of the cost
+fn bench_stream_throughput(c: &mut Criterion) {
+    let mut group = c.benchmark_group("stream_throughput");
+    group.measurement_time(Duration::from_secs(10));
+    group.sample_size(30);
+
+    // Bursts fit the default socket buffers, larger ones overflow them and
+    // measure NAK recovery instead
+    for (size, message_count) in [(64usize, 1000u64), (1024, 64), (65536, 1)] {
+        let recv_addr: SocketAddr = format!("127.0.0.1:{}", get_free_port()).parse().unwrap();
+        let receiver = UdpReceiver::new(
+            ReceiverConfig {
+                stream_id: 1,
+                recv_timeout: Duration::from_micros(100),
+                ..Default::default()
+            },
+            recv_addr,
+        )
+        .unwrap();
+        let sender = UdpSender::new(
+            SenderConfig {
+                stream_id: 1,
+                target_addr: recv_addr,
+                enable_heartbeats: false,
+                max_batch_delay: Duration::from_micros(50),
+                ..Default::default()
+            },
+            "127.0.0.1:0".parse().unwrap(),
+        )
+        .unwrap();
+        let payload = vec![0xABu8; size];
+
+        group.throughput(Throughput::Bytes(message_count * size as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &payload, |b, payload| {
+            b.iter(|| {
+                for _ in 0..message_count {
+                    sender.send(MessageType::OrderNew, payload.clone()).unwrap();
+                }
+                // Messages lost for good end the wait
+                let mut received = 0;
+                while received < message_count {
+                    match receiver.recv_timeout(Duration::from_millis(100)) {
+                        Ok(Some(_)) => received += 1,
+                        _ => break,
+                    }
+                }
+                received
+            })
+        });
+    }
+
+    group.finish();
+}
+
 fn bench_serialization_throughput(c: &mut Criterion) {
     let mut group = c.benchmark_group("serialization_throughput");
 
@@ -267,8 +374,10 @@ criterion_group!(
     bench_packet_builder,
     bench_packet_builder_batch,
     bench_packet_parse,
+    bench_packet_auth,
     bench_serialization_throughput,
     bench_end_to_end_throughput,
+    bench_stream_throughput,
     bench_high_throughput_scenario,
 );
 criterion_main!(benches);
@@ -0,0 +1,351 @@
+//! Tokio variants of the sender and receiver.
+//!
+//! `AsyncUdpSender` is a `Sink` of messages and `AsyncUdpReceiver` a
+//! `Stream` of them, both on a `tokio::net::UdpSocket`. Batching,
+//! sequencing, NAK recovery, reassembly and authentication are the ones of
+//! `UdpSender` and `UdpReceiver`, only the I/O differs, so both kinds talk
+//! to each other.
+
+use std::collections::VecDeque;
+use std::future::poll_fn;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::task::{Context, Poll, Waker};
+use std::time::Duration;
+
+use futures_core::Stream;
+use futures_sink::Sink;
+use parking_lot::Mutex;
+use tokio::io::ReadBuf;
+use tokio::net::UdpSocket;
+use tokio::task::JoinHandle;
+use tokio::time::{self, Interval, MissedTickBehavior};
+
+use crate::auth::StreamKey;
+use crate::error::{ProtocolError, Result};
+use crate::protocol::MAX_MTU;
+use crate::receiver::{
+    GapInfo, ReceivedMessage, ReceiverConfig, ReceiverStatsSnapshot, StreamSource, StreamState,
+    StreamStatus, StreamTracker,
+};
+use crate::sender::{
+    Batcher, OutgoingMessage, OutgoingPacket, SenderConfig, SenderState, SenderStatsSnapshot,
+};
+
+/// Finest timer the batching and the receiver ticks run on
+const MIN_TICK: Duration = Duration::from_millis(1);
+
+/// Async UDP sender. Messages fed to the sink go out in batches like with
+/// `UdpSender`: once a packet is full, after `max_batch_delay` or when the
+/// sink is flushed. Heartbeats, NAKs and the session are handled by a
+/// background task. Close the sink to send what is still batched and log
+/// out before dropping it.
+pub struct AsyncUdpSender {
+    shared: Arc<SenderShared>,
+    task: JoinHandle<()>,
+}
+
+struct SenderShared {
+    socket: UdpSocket,
+    state: SenderState,
+    batch: Mutex<Batch>,
+}
+
+struct Batch {
+    batcher: Batcher,
+    out: VecDeque<OutgoingPacket>,
+    /// The socket only wakes the last task waiting to send, the other one
+    /// is woken once the queue is drained
+    waiting: Option<Waker>,
+    logged_out: bool,
+}
+
+impl AsyncUdpSender {
+    /// Create a new sender bound to the specified local address. Needs a
+    /// tokio runtime.
+    pub fn new(config: SenderConfig, bind_addr: SocketAddr) -> Result<Self> {
+        let state = SenderState::new(config)?;
+        let socket = state.bind(bind_addr)?;
+        socket.set_nonblocking(true)?;
+
+        let mut batcher = Batcher::new(state.clone());
+        let mut out = VecDeque::new();
+        batcher.hello(&mut out);
+
+        let shared = Arc::new(SenderShared {
+            socket: UdpSocket::from_std(socket)?,
+            batch: Mutex::new(Batch {
+                batcher,
+                out,
+                waiting: None,
+                logged_out: false,
+            }),
+            state,
+        });
+        let task = tokio::spawn(shared.clone().run());
+
+        Ok(Self { shared, task })
+    }
+
+    /// Get current statistics
+    pub fn stats(&self) -> SenderStatsSnapshot {
+        self.shared.state.stats.snapshot()
+    }
+
+    /// Sign packets with `key` from now on and tell receivers to retire the
+    /// older keys. Receivers need the key in their ring before this.
+    pub async fn rotate_key(&self, key: StreamKey) -> Result<()> {
+        let announcement = self.shared.state.rotate_key(key)?;
+        let result = self
+            .shared
+            .socket
+            .send_to(&announcement.data, announcement.dest)
+            .await;
+        self.shared.state.stats.record(&announcement, &result);
+        result?;
+        Ok(())
+    }
+
+    /// Number the messages fed after this call from `next_seq`. Receivers
+    /// are told, they don't see a gap.
+    pub async fn reset_sequence(&self, next_seq: u64) {
+        self.batch(|batcher, out| batcher.reset_sequence(next_seq, out));
+        poll_fn(|cx| self.shared.poll_send_queued(cx)).await;
+    }
+
+    /// ID of this sender's session, new every time it starts
+    pub fn session_id(&self) -> u64 {
+        self.shared.state.session_id()
+    }
+
+    fn batch(&self, f: impl FnOnce(&mut Batcher, &mut VecDeque<OutgoingPacket>)) {
+        let mut batch = self.shared.batch.lock();
+        let Batch { batcher, out, .. } = &mut *batch;
+        f(batcher, out);
+    }
+}
+
+impl SenderShared {
+    /// Send the queued packets, pending while the socket can't take more
+    fn poll_send_queued(&self, cx: &mut Context<'_>) -> Poll<()> {
+        let mut batch = self.batch.lock();
+        while let Some(packet) = batch.out.front() {
+            let Poll::Ready(result) = self.socket.poll_send_to(cx, &packet.data, packet.dest) else {
+                if let Some(waker) = batch.waiting.replace(cx.waker().clone()) {
+                    if !waker.will_wake(cx.waker()) {
+                        waker.wake();
+                    }
+                }
+                return Poll::Pending;
+            };
+            self.state.stats.record(packet, &result);
+            batch.out.pop_front();
+        }
+        if let Some(waker) = batch.waiting.take() {
+            waker.wake();
+        }
+        Poll::Ready(())
+    }
+
+    /// Partial batches, heartbeats and NAKs
+    async fn run(self: Arc<Self>) {
+        let mut buf = vec![0u8; MAX_MTU];
+        let mut replies = VecDeque::new();
+        let mut batch_timer = time::interval(self.state.config.max_batch_delay.max(MIN_TICK));
+        batch_timer.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            tokio::select! {
+                received = self.socket.recv_from(&mut buf) => {
+                    let Ok((len, addr)) = received else {
+                        continue;
+                    };
+                    self.state.handle_control(&mut buf[..len], addr, &mut replies);
+                    for packet in replies.drain(..) {
+                        let result = self.socket.send_to(&packet.data, packet.dest).await;
+                        self.state.stats.record(&packet, &result);
+                    }
+                }
+                _ = batch_timer.tick() => {
+                    {
+                        let mut batch = self.batch.lock();
+                        let Batch { batcher, out, .. } = &mut *batch;
+                        batcher.idle(out);
+                    }
+                    poll_fn(|cx| self.poll_send_queued(cx)).await;
+                }
+            }
+        }
+    }
+}
+
+impl Sink<OutgoingMessage> for AsyncUdpSender {
+    type Error = ProtocolError;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        self.shared.poll_send_queued(cx).map(Ok)
+    }
+
+    fn start_send(self: Pin<&mut Self>, msg: OutgoingMessage) -> Result<()> {
+        self.shared.state.check_size(&msg.payload)?;
+        self.batch(|batcher, out| batcher.push(&msg, out));
+        Ok(())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        self.batch(|batcher, out| batcher.flush(out));
+        self.shared.poll_send_queued(cx).map(Ok)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        {
+            let mut batch = self.shared.batch.lock();
+            if !batch.logged_out {
+                batch.logged_out = true;
+                let Batch { batcher, out, .. } = &mut *batch;
+                batcher.logout(out);
+            }
+        }
+        self.shared.poll_send_queued(cx).map(Ok)
+    }
+}
+
+impl Drop for AsyncUdpSender {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Async UDP receiver, a stream of the messages of `UdpReceiver`: in order,
+/// with lost messages NAKed and fragmented ones reassembled. The stream
+/// never ends. `recv` is cancel safe, so it can sit in a `select!`. Messages
+/// of every stream come through it, `stream_id` and `source` tell them
+/// apart.
+pub struct AsyncUdpReceiver {
+    socket: UdpSocket,
+    tracker: StreamTracker,
+    /// Drives NAK retries, timeouts and reassembly expiry
+    ticks: Interval,
+    buf: Vec<u8>,
+}
+
+impl AsyncUdpReceiver {
+    /// Create a new receiver bound to the specified address, joining
+    /// `multicast_group` when set. Needs a tokio runtime.
+    pub fn new(config: ReceiverConfig, bind_addr: SocketAddr) -> Result<Self> {
+        let mut ticks = time::interval(config.recv_timeout.max(MIN_TICK));
+        ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let tracker = StreamTracker::new(config)?;
+        let socket = tracker.bind(bind_addr)?;
+        socket.set_nonblocking(true)?;
+
+        Ok(Self {
+            socket: UdpSocket::from_std(socket)?,
+            tracker,
+            ticks,
+            buf: vec![0u8; MAX_MTU],
+        })
+    }
+
+    /// Receive the next message
+    pub async fn recv(&mut self) -> ReceivedMessage {
+        poll_fn(|cx| self.poll_recv(cx)).await
+    }
+
+    fn poll_recv(&mut self, cx: &mut Context<'_>) -> Poll<ReceivedMessage> {
+        loop {
+            self.send_replies();
+            if let Some(msg) = self.tracker.delivered.pop_front() {
+                return Poll::Ready(msg);
+            }
+            if self.ticks.poll_tick(cx).is_ready() {
+                self.tracker.tick();
+                continue;
+            }
+
+            let mut buf = ReadBuf::new(&mut self.buf);
+            let Poll::Ready(received) = self.socket.poll_recv_from(cx, &mut buf) else {
+                return Poll::Pending;
+            };
+            match received {
+                Ok(addr) => {
+                    let len = buf.filled().len();
+                    self.tracker.on_packet(&mut self.buf[..len], addr);
+                }
+                Err(_) => {
+                    self.tracker.handles.stats.recv_errors.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+        }
+    }
+
+    fn send_replies(&mut self) {
+        while let Some((packet, addr)) = self.tracker.replies.pop_front() {
+            let result = self.socket.try_send_to(&packet, addr);
+            self.tracker.handles.stats.record_reply(&result);
+        }
+    }
+
+    /// Get current stream state, the worst of all streams
+    pub fn state(&self) -> StreamState {
+        self.tracker.handles.state()
+    }
+
+    /// State of every stream, by stream ID
+    pub fn streams(&self) -> Vec<StreamStatus> {
+        self.tracker.handles.streams()
+    }
+
+    /// Get current statistics
+    pub fn stats(&self) -> ReceiverStatsSnapshot {
+        self.tracker.handles.stats.snapshot()
+    }
+
+    /// Get detected gaps
+    pub fn gaps(&self) -> Vec<GapInfo> {
+        self.tracker.handles.gaps()
+    }
+
+    /// Whether messages were lost for good, so the consumer has to resync
+    /// from a snapshot. Stays set until `clear_gaps`.
+    pub fn needs_snapshot(&self) -> bool {
+        self.tracker.handles.needs_snapshot()
+    }
+
+    /// Clear detected gaps (after recovery)
+    pub fn clear_gaps(&self) {
+        self.tracker.handles.clear_gaps()
+    }
+
+    /// Clear the gaps of one stream, after it recovered from a snapshot
+    pub fn clear_stream_gaps(&self, source: StreamSource) {
+        self.tracker.handles.clear_stream_gaps(source)
+    }
+
+    /// Accept packets sealed with `key`, see `UdpReceiver::add_key`
+    pub fn add_key(&self, key: StreamKey) -> Result<()> {
+        self.tracker.handles.add_key(key)
+    }
+
+    /// ID of this receiver's session, new every time it starts
+    pub fn session_id(&self) -> u64 {
+        self.tracker.handles.session_id
+    }
+}
+
+impl Drop for AsyncUdpReceiver {
+    fn drop(&mut self) {
+        self.tracker.logout();
+        self.send_replies();
+    }
+}
+
+impl Stream for AsyncUdpReceiver {
+    type Item = ReceivedMessage;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<ReceivedMessage>> {
+        self.get_mut().poll_recv(cx).map(Some)
+    }
+}
@@ -0,0 +1,455 @@
+//! Packet authentication with pre-shared stream keys.
+//!
+//! An authenticated packet carries a trailer after its messages:
+//! ```text
+//! 0  - 4  : key_id  (u32)
+//! 4  - 12 : epoch   (u64)
+//! 12 - 28 : tag     (16 bytes)
+//! ```
+//! With `AuthMode::Sign` the tag is a truncated HMAC-SHA256 over the rest of
+//! the packet. With `AuthMode::Encrypt` the messages are encrypted with
+//! ChaCha20-Poly1305 under a key derived from the stream key and the epoch,
+//! the header and the rest of the trailer are authenticated in clear.
+//!
+//! The epoch is picked by each peer when it starts and grows across restarts.
+//! Receivers keep a window of `packet_seq`s per stream and epoch, replayed or
+//! stale packets and packets from an older epoch are rejected. NAKs are
+//! authenticated but not sequenced, a replayed NAK only costs a resend the
+//! receiver drops as a duplicate.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::net::SocketAddr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use ring::aead::{self, Aad, LessSafeKey, Nonce, UnboundKey, CHACHA20_POLY1305};
+use ring::hkdf;
+use ring::hmac;
+use ring::rand::{SecureRandom, SystemRandom};
+
+use crate::error::{ProtocolError, Result};
+use crate::protocol::{PacketHeader, AUTH_TRAILER_LEN, MAX_PACKET_HEADER_LEN, PACKET_HEADER_LEN};
+use crate::receiver::StreamSource;
+
+const TAG_LEN: usize = 16;
+/// Packets a receiver still accepts behind the newest one, matches the
+/// default retransmission buffer
+const REPLAY_WINDOW: u64 = 1024;
+
+/// How packets of a stream are protected
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthMode {
+    /// HMAC-SHA256 tag, messages in clear
+    Sign,
+    /// ChaCha20-Poly1305, messages encrypted
+    Encrypt,
+}
+
+/// A pre-shared stream key. Key IDs only grow, rotating to a key retires
+/// every key with a lower ID.
+#[derive(Clone)]
+pub struct StreamKey {
+    pub id: u32,
+    secret: Vec<u8>,
+}
+
+impl StreamKey {
+    pub fn new(id: u32, secret: impl Into<Vec<u8>>) -> Self {
+        Self {
+            id,
+            secret: secret.into(),
+        }
+    }
+}
+
+impl fmt::Debug for StreamKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StreamKey")
+            .field("id", &self.id)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Authentication settings shared by the peers of a stream
+#[derive(Debug, Clone)]
+pub struct AuthConfig {
+    pub mode: AuthMode,
+    /// Keys accepted from peers. Senders sign with the one with the highest ID.
+    pub keys: Vec<StreamKey>,
+}
+
+impl AuthConfig {
+    pub fn new(mode: AuthMode, key: StreamKey) -> Self {
+        Self {
+            mode,
+            keys: vec![key],
+        }
+    }
+}
+
+/// Which side of a stream sealed a packet. Part of the derived encryption
+/// key, so data and NAKs never share a nonce.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Sender = 1,
+    Receiver = 2,
+}
+
+struct KeyMaterial {
+    id: u32,
+    hmac: hmac::Key,
+    hkdf: hkdf::Prk,
+}
+
+impl KeyMaterial {
+    fn new(key: &StreamKey) -> Self {
+        Self {
+            id: key.id,
+            hmac: hmac::Key::new(hmac::HMAC_SHA256, &key.secret),
+            hkdf: hkdf::Salt::new(hkdf::HKDF_SHA256, b"udp_proto").extract(&key.secret),
+        }
+    }
+
+    fn aead_key(&self, role: Role, stream_id: u32, epoch: u64) -> LessSafeKey {
+        let mut info = [0u8; 13];
+        info[0] = role as u8;
+        info[1..5].copy_from_slice(&stream_id.to_le_bytes());
+        info[5..13].copy_from_slice(&epoch.to_le_bytes());
+        let info = [&info[..]];
+        let okm = self
+            .hkdf
+            .expand(&info, &CHACHA20_POLY1305)
+            .expect("ChaCha20 key length is valid for HKDF");
+        LessSafeKey::new(UnboundKey::from(okm))
+    }
+}
+
+fn nonce(packet_seq: u64) -> Nonce {
+    let mut nonce = [0u8; aead::NONCE_LEN];
+    nonce[4..].copy_from_slice(&packet_seq.to_le_bytes());
+    Nonce::assume_unique_for_key(nonce)
+}
+
+/// Header and the unencrypted start of the trailer, authenticated but not
+/// encrypted
+struct HeaderAad {
+    buf: [u8; MAX_PACKET_HEADER_LEN + 12],
+    len: usize,
+}
+
+impl AsRef<[u8]> for HeaderAad {
+    fn as_ref(&self) -> &[u8] {
+        &self.buf[..self.len]
+    }
+}
+
+fn aad(header: &[u8], trailer: &[u8]) -> Aad<HeaderAad> {
+    let len = header.len() + trailer.len();
+    let mut buf = [0u8; MAX_PACKET_HEADER_LEN + 12];
+    buf[..header.len()].copy_from_slice(header);
+    buf[header.len()..len].copy_from_slice(trailer);
+    Aad::from(HeaderAad { buf, len })
+}
+
+fn truncated_hmac(key: &hmac::Key, data: &[u8]) -> [u8; TAG_LEN] {
+    let mut tag = [0u8; TAG_LEN];
+    tag.copy_from_slice(&hmac::sign(key, data).as_ref()[..TAG_LEN]);
+    tag
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Milliseconds since the Unix epoch in the high bits, so a restarted peer
+/// starts a newer epoch, and random low bits to keep peers apart
+fn new_epoch() -> u64 {
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default();
+    let mut random = [0u8; 4];
+    SystemRandom::new()
+        .fill(&mut random)
+        .expect("system random source available");
+    (millis << 22) | (u32::from_le_bytes(random) as u64 & 0x3f_ffff)
+}
+
+/// Seals outgoing packets with the current key
+pub struct Sealer {
+    mode: AuthMode,
+    role: Role,
+    epoch: u64,
+    keys: BTreeMap<u32, KeyMaterial>,
+    key_id: u32,
+    /// Derived for the current key and epoch, `Encrypt` only
+    aead: Option<LessSafeKey>,
+}
+
+impl Sealer {
+    /// Seals with the key with the highest ID
+    pub fn new(config: &AuthConfig, role: Role) -> Result<Self> {
+        let keys: BTreeMap<_, _> = config.keys.iter().map(|k| (k.id, KeyMaterial::new(k))).collect();
+        let key_id = *keys.keys().next_back().ok_or(ProtocolError::NoStreamKey)?;
+        Ok(Self {
+            mode: config.mode,
+            role,
+            epoch: new_epoch(),
+            keys,
+            key_id,
+            aead: None,
+        })
+    }
+
+    pub fn key_id(&self) -> u32 {
+        self.key_id
+    }
+
+    pub fn add_key(&mut self, key: &StreamKey) {
+        self.keys.insert(key.id, KeyMaterial::new(key));
+    }
+
+    /// Seal with another key of the ring, false if it is unknown
+    pub fn select_key(&mut self, key_id: u32) -> bool {
+        if !self.keys.contains_key(&key_id) {
+            return false;
+        }
+        if key_id != self.key_id {
+            self.key_id = key_id;
+            self.aead = None;
+        }
+        true
+    }
+
+    /// Append the trailer, encrypting the messages in `Encrypt` mode. The
+    /// packet needs `AUTH_TRAILER_LEN` bytes of room below the MTU.
+    pub fn seal(&mut self, packet: &mut Vec<u8>) {
+        let Ok(header) = PacketHeader::read_from(packet) else {
+            return;
+        };
+        let key = &self.keys[&self.key_id];
+        packet.extend_from_slice(&key.id.to_le_bytes());
+        packet.extend_from_slice(&self.epoch.to_le_bytes());
+
+        match self.mode {
+            AuthMode::Sign => {
+                let tag = truncated_hmac(&key.hmac, packet);
+                packet.extend_from_slice(&tag);
+            }
+            AuthMode::Encrypt => {
+                let (role, epoch) = (self.role, self.epoch);
+                let aead_key = self
+                    .aead
+                    .get_or_insert_with(|| key.aead_key(role, header.stream_id, epoch));
+                let body_end = packet.len() - 12;
+                let header_len = header.encoded_len();
+                let (head, rest) = packet.split_at_mut(header_len);
+                let (body, trailer) = rest.split_at_mut(body_end - header_len);
+                let aad = aad(head, trailer);
+                let tag = aead_key
+                    .seal_in_place_separate_tag(nonce(header.packet_seq), aad, body)
+                    .expect("packet fits ChaCha20-Poly1305 limits");
+                packet.extend_from_slice(tag.as_ref());
+            }
+        }
+    }
+}
+
+/// Replay window of one stream
+struct ReplayWindow {
+    epoch: u64,
+    highest: u64,
+    /// Bit `i` is `highest - i`
+    seen: [u64; (REPLAY_WINDOW / 64) as usize],
+}
+
+impl ReplayWindow {
+    fn new(epoch: u64, packet_seq: u64) -> Self {
+        let mut window = Self {
+            epoch,
+            highest: packet_seq,
+            seen: [0; (REPLAY_WINDOW / 64) as usize],
+        };
+        window.seen[0] = 1;
+        window
+    }
+
+    fn is_fresh(&self, epoch: u64, packet_seq: u64) -> bool {
+        if epoch != self.epoch {
+            return epoch > self.epoch;
+        }
+        if packet_seq > self.highest {
+            return true;
+        }
+        let behind = self.highest - packet_seq;
+        behind < REPLAY_WINDOW && self.seen[(behind / 64) as usize] & (1 << (behind % 64)) == 0
+    }
+
+    /// Mark a fresh packet seen
+    fn record(&mut self, epoch: u64, packet_seq: u64) {
+        if epoch > self.epoch {
+            *self = Self::new(epoch, packet_seq);
+            return;
+        }
+        if packet_seq > self.highest {
+            let shift = packet_seq - self.highest;
+            self.highest = packet_seq;
+            self.shift(shift);
+        }
+        let behind = self.highest - packet_seq;
+        self.seen[(behind / 64) as usize] |= 1 << (behind % 64);
+    }
+
+    fn shift(&mut self, by: u64) {
+        if by >= REPLAY_WINDOW {
+            self.seen = [0; (REPLAY_WINDOW / 64) as usize];
+            return;
+        }
+        let (words, bits) = ((by / 64) as usize, by % 64);
+        for i in (0..self.seen.len()).rev() {
+            let mut word = if i >= words { self.seen[i - words] << bits } else { 0 };
+            if bits > 0 && i > words {
+                word |= self.seen[i - words - 1] >> (64 - bits);
+            }
+            self.seen[i] = word;
+        }
+    }
+}
+
+/// Per stream state of an `Opener`, each sender of a stream ID has its
+/// own epoch and key rotations
+#[derive(Default)]
+struct StreamAuth {
+    window: Option<ReplayWindow>,
+    /// Keys below this ID are retired for packets after `retired_at`
+    min_key_id: u32,
+    /// (epoch, packet_seq) of the rotation
+    retired_at: (u64, u64),
+    /// (key_id, epoch, derived key) of the last packet, `Encrypt` only
+    aead: Option<(u32, u64, LessSafeKey)>,
+}
+
+/// Verifies incoming packets against the key ring
+pub struct Opener {
+    mode: AuthMode,
+    keys: BTreeMap<u32, KeyMaterial>,
+    streams: HashMap<StreamSource, StreamAuth>,
+}
+
+/// What an opened packet was sealed with
+#[derive(Debug, Clone, Copy)]
+pub struct Opened {
+    /// Packet length without the trailer
+    pub len: usize,
+    pub key_id: u32,
+    pub epoch: u64,
+}
+
+impl Opener {
+    pub fn new(config: &AuthConfig) -> Self {
+        Self {
+            mode: config.mode,
+            keys: config.keys.iter().map(|k| (k.id, KeyMaterial::new(k))).collect(),
+            streams: HashMap::new(),
+        }
+    }
+
+    pub fn add_key(&mut self, key: &StreamKey) {
+        self.keys.insert(key.id, KeyMaterial::new(key));
+    }
+
+    /// Stop accepting keys below `key_id` on the stream, for packets sent
+    /// after the rotation announced by `opened` at `packet_seq`
+    pub fn retire_keys(&mut self, source: StreamSource, key_id: u32, opened: Opened, packet_seq: u64) {
+        let stream = self.streams.entry(source).or_default();
+        if key_id > stream.min_key_id {
+            stream.min_key_id = key_id;
+            stream.retired_at = (opened.epoch, packet_seq);
+        }
+    }
+
+    /// Verify a packet from the sender at `from`, decrypting it in place.
+    /// Replays and retired keys are rejected.
+    pub fn open(&mut self, packet: &mut [u8], from: SocketAddr) -> Result<Opened> {
+        self.open_as(packet, from, Role::Sender)
+    }
+
+    /// Verify a NAK from the receiver at `from`, decrypting it in place
+    pub fn open_nak(&mut self, packet: &mut [u8], from: SocketAddr) -> Result<Opened> {
+        self.open_as(packet, from, Role::Receiver)
+    }
+
+    fn open_as(&mut self, packet: &mut [u8], from: SocketAddr, role: Role) -> Result<Opened> {
+        if packet.len() < PACKET_HEADER_LEN + AUTH_TRAILER_LEN {
+            return Err(ProtocolError::AuthenticationFailed);
+        }
+        let header = PacketHeader::read_from(packet)?;
+        if packet.len() < header.encoded_len() + AUTH_TRAILER_LEN {
+            return Err(ProtocolError::AuthenticationFailed);
+        }
+        let len = packet.len() - AUTH_TRAILER_LEN;
+        let key_id = u32::from_le_bytes(packet[len..len + 4].try_into().unwrap());
+        let epoch = u64::from_le_bytes(packet[len + 4..len + 12].try_into().unwrap());
+        let key = self.keys.get(&key_id).ok_or(ProtocolError::UnknownKey(key_id))?;
+        let source = StreamSource {
+            addr: from,
+            stream_id: header.stream_id,
+        };
+
+        // Cheap checks first, nothing is recorded before the tag is verified
+        let stream = self.streams.get(&source);
+        if let (Role::Sender, Some(stream)) = (role, stream) {
+            if key_id < stream.min_key_id && (epoch, header.packet_seq) > stream.retired_at {
+                return Err(ProtocolError::UnknownKey(key_id));
+            }
+            if let Some(window) = &stream.window {
+                if !window.is_fresh(epoch, header.packet_seq) {
+                    return Err(ProtocolError::Replayed {
+                        packet_seq: header.packet_seq,
+                    });
+                }
+            }
+        }
+
+        let mut derived = None;
+        let authentic = match self.mode {
+            AuthMode::Sign => {
+                let expected = truncated_hmac(&key.hmac, &packet[..len + 12]);
+                constant_time_eq(&expected, &packet[len + 12..])
+            }
+            AuthMode::Encrypt => {
+                let aead_key = match stream.and_then(|s| s.aead.as_ref()) {
+                    Some((id, e, k)) if *id == key_id && *e == epoch => k,
+                    _ => derived.insert(key.aead_key(role, header.stream_id, epoch)),
+                };
+                let header_len = header.encoded_len();
+                let aad = aad(&packet[..header_len], &packet[len..len + 12]);
+                let tag: [u8; TAG_LEN] = packet[len + 12..].try_into().unwrap();
+                aead_key
+                    .open_in_place_separate_tag(
+                        nonce(header.packet_seq),
+                        aad,
+                        aead::Tag::from(tag),
+                        &mut packet[header_len..len],
+                        0..,
+                    )
+                    .is_ok()
+            }
+        };
+        if !authentic {
+            return Err(ProtocolError::AuthenticationFailed);
+        }
+
+        let stream = self.streams.entry(source).or_default();
+        if let Some(aead_key) = derived {
+            stream.aead = Some((key_id, epoch, aead_key));
+        }
+        if role == Role::Sender {
+            match &mut stream.window {
+                Some(window) => window.record(epoch, header.packet_seq),
+                None => stream.window = Some(ReplayWindow::new(epoch, header.packet_seq)),
+            }
+        }
+        Ok(Opened { len, key_id, epoch })
+    }
+}
@@ -0,0 +1,188 @@
+//! Several datagrams per syscall for the worker threads.
+//!
+//! On Linux packets go out with `sendmmsg` and come in with `recvmmsg`,
+//! elsewhere this falls back to one `send_to`/`recv_from` per datagram.
+//! GSO is not used: its segments must all have the same length, which the
+//! packets of a batch rarely have.
+
+use std::collections::VecDeque;
+use std::io;
+use std::net::{SocketAddr, UdpSocket};
+
+use crate::protocol::MAX_MTU;
+use crate::sender::{OutgoingPacket, SenderStats};
+
+/// Most datagrams moved by one syscall
+pub(crate) const MAX_BATCH: usize = 32;
+
+/// Send the queued packets, recording each in `stats`. A packet that fails
+/// to send is dropped, the ones after it are still sent.
+pub(crate) fn send_packets(
+    socket: &UdpSocket,
+    queue: &mut VecDeque<OutgoingPacket>,
+    stats: &SenderStats,
+) {
+    let mut lens = [0usize; MAX_BATCH];
+    while !queue.is_empty() {
+        let count = queue.len().min(MAX_BATCH);
+        match send_batch(socket, &queue.make_contiguous()[..count], &mut lens) {
+            Ok(sent) => {
+                for (packet, len) in queue.drain(..sent).zip(lens) {
+                    stats.record(&packet, &Ok(len));
+                }
+            }
+            Err(e) => {
+                if let Some(packet) = queue.pop_front() {
+                    stats.record(&packet, &Err(e));
+                }
+            }
+        }
+    }
+}
+
+/// Send up to `MAX_BATCH` packets from the front of `packets`, at least one
+/// unless it fails. The bytes sent of each land in `lens`.
+#[cfg(target_os = "linux")]
+fn send_batch(
+    socket: &UdpSocket,
+    packets: &[OutgoingPacket],
+    lens: &mut [usize; MAX_BATCH],
+) -> io::Result<usize> {
+    use std::mem;
+    use std::os::fd::AsRawFd;
+
+    use socket2::SockAddr;
+
+    let count = packets.len().min(MAX_BATCH);
+    // SAFETY: plain C structs, all zeroes is a valid value
+    let mut addrs: [libc::sockaddr_storage; MAX_BATCH] = unsafe { mem::zeroed() };
+    let mut iovecs: [libc::iovec; MAX_BATCH] = unsafe { mem::zeroed() };
+    let mut msgs: [libc::mmsghdr; MAX_BATCH] = unsafe { mem::zeroed() };
+
+    for (i, packet) in packets[..count].iter().enumerate() {
+        let addr = SockAddr::from(packet.dest);
+        msgs[i].msg_hdr.msg_namelen = addr.len();
+        addrs[i] = addr.as_storage();
+        iovecs[i] = libc::iovec {
+            iov_base: packet.data.as_ptr() as *mut libc::c_void,
+            iov_len: packet.data.len(),
+        };
+        msgs[i].msg_hdr.msg_name = &mut addrs[i] as *mut _ as *mut libc::c_void;
+        msgs[i].msg_hdr.msg_iov = &mut iovecs[i];
+        msgs[i].msg_hdr.msg_iovlen = 1;
+    }
+
+    // SAFETY: every header points into `addrs`, `iovecs` and the packets,
+    // which outlive the call
+    let sent = unsafe { libc::sendmmsg(socket.as_raw_fd(), msgs.as_mut_ptr(), count as _, 0) };
+    if sent < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    if sent == 0 {
+        return Err(io::ErrorKind::WriteZero.into());
+    }
+
+    let sent = sent as usize;
+    for (len, msg) in lens.iter_mut().zip(&msgs[..sent]) {
+        *len = msg.msg_len as usize;
+    }
+    Ok(sent)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn send_batch(
+    socket: &UdpSocket,
+    packets: &[OutgoingPacket],
+    lens: &mut [usize; MAX_BATCH],
+) -> io::Result<usize> {
+    lens[0] = socket.send_to(&packets[0].data, packets[0].dest)?;
+    Ok(1)
+}
+
+/// Buffers for receiving up to `MAX_BATCH` datagrams at once
+pub(crate) struct RecvBatch {
+    bufs: Vec<u8>,
+    /// Length and source of each datagram of the last `recv`
+    received: Vec<(usize, SocketAddr)>,
+}
+
+impl RecvBatch {
+    pub fn new() -> Self {
+        Self {
+            bufs: vec![0u8; MAX_BATCH * MAX_MTU],
+            received: Vec::with_capacity(MAX_BATCH),
+        }
+    }
+
+    /// Receive what is queued on the socket, waiting for the first
+    /// datagram only (up to the socket read timeout). Returns how many
+    /// datagrams came in.
+    pub fn recv(&mut self, socket: &UdpSocket) -> io::Result<usize> {
+        self.received.clear();
+        self.recv_batch(socket)?;
+        Ok(self.received.len())
+    }
+
+    /// The datagrams of the last `recv` with their sources
+    pub fn packets(&mut self) -> impl Iterator<Item = (&mut [u8], SocketAddr)> {
+        self.bufs
+            .chunks_mut(MAX_MTU)
+            .zip(&self.received)
+            .map(|(buf, &(len, addr))| (&mut buf[..len], addr))
+    }
+
+    #[cfg(target_os = "linux")]
+    fn recv_batch(&mut self, socket: &UdpSocket) -> io::Result<()> {
+        use std::os::fd::AsRawFd;
+        use std::{mem, ptr};
+
+        use socket2::SockAddr;
+
+        // SAFETY: plain C structs, all zeroes is a valid value
+        let mut addrs: [libc::sockaddr_storage; MAX_BATCH] = unsafe { mem::zeroed() };
+        let mut iovecs: [libc::iovec; MAX_BATCH] = unsafe { mem::zeroed() };
+        let mut msgs: [libc::mmsghdr; MAX_BATCH] = unsafe { mem::zeroed() };
+
+        for (i, buf) in self.bufs.chunks_mut(MAX_MTU).enumerate() {
+            iovecs[i] = libc::iovec {
+                iov_base: buf.as_mut_ptr() as *mut libc::c_void,
+                iov_len: buf.len(),
+            };
+            msgs[i].msg_hdr.msg_name = &mut addrs[i] as *mut _ as *mut libc::c_void;
+            msgs[i].msg_hdr.msg_namelen = mem::size_of::<libc::sockaddr_storage>() as _;
+            msgs[i].msg_hdr.msg_iov = &mut iovecs[i];
+            msgs[i].msg_hdr.msg_iovlen = 1;
+        }
+
+        // SAFETY: every header points into `addrs`, `iovecs` and `bufs`,
+        // which outlive the call
+        let received = unsafe {
+            libc::recvmmsg(
+                socket.as_raw_fd(),
+                msgs.as_mut_ptr(),
+                MAX_BATCH as _,
+                libc::MSG_WAITFORONE as _,
+                ptr::null_mut(),
+            )
+        };
+        if received < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        for (msg, addr) in msgs.iter().zip(addrs).take(received as usize) {
+            // SAFETY: the kernel filled `addr` and its length
+            let addr = unsafe { SockAddr::new(addr, msg.msg_hdr.msg_namelen) };
+            if let Some(addr) = addr.as_socket() {
+                self.received.push((msg.msg_len as usize, addr));
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn recv_batch(&mut self, socket: &UdpSocket) -> io::Result<()> {
+        let (len, addr) = socket.recv_from(&mut self.bufs[..MAX_MTU])?;
+        self.received.push((len, addr));
+        Ok(())
+    }
+}
@@ -0,0 +1,719 @@
+//! Records udp_proto traffic and prints it decoded (see `capture_file`).
+//!
+//! `listen` receives datagrams on a port or multicast group, prints them and
+//! records them with `--record`. `decode` reads a capture back, ours or one
+//! taken with tcpdump. Every datagram is printed as a JSON object with its
+//! packet header and messages, each message with the `MarketEvent`, order
+//! command, journal entry or control message it carries. Fragmented
+//! messages are decoded once their last fragment is in. Messages of
+//! encrypted streams can't be decoded, their packet headers still are.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Write};
+use std::net::{Ipv4Addr, SocketAddr, UdpSocket};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use clap::{Parser, Subcommand};
+use serde_json::{json, Value};
+use socket2::{Domain, Protocol, Socket, Type};
+use udp_proto::binary::{
+    decode_journal_entry, decode_market_event, decode_order_command, JournalEntry, JournalRecord,
+    MarketEvent, OrderCommand, RiskControl,
+};
+use udp_proto::capture_file::{CaptureReader, CaptureWriter, CapturedDatagram};
+use udp_proto::{
+    ControlMessage, FragmentHeader, Message, MessageFlags, MessageType, Packet, PacketHeader,
+    AUTH_TRAILER_LEN, FRAGMENT_HEADER_LEN, MESSAGE_HEADER_LEN,
+};
+
+/// Partial messages kept at once, more means fragments that never complete
+const MAX_PARTIAL_MESSAGES: usize = 1024;
+
+#[derive(Parser, Debug)]
+#[command(name = "udp_capture")]
+#[command(about = "Record udp_proto datagrams and print them decoded as JSON")]
+struct Args {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Receive datagrams on a port and print them
+    Listen {
+        /// Address to bind, the port the stream is sent to
+        bind: SocketAddr,
+
+        /// Multicast group to join
+        #[arg(long)]
+        group: Option<Ipv4Addr>,
+
+        /// Also record the datagrams to this capture file
+        #[arg(long)]
+        record: Option<PathBuf>,
+
+        /// Stop after this many datagrams
+        #[arg(long)]
+        count: Option<u64>,
+
+        /// Print nothing, only record
+        #[arg(long, default_value_t = false)]
+        quiet: bool,
+
+        #[command(flatten)]
+        filter: Filter,
+    },
+    /// Print the datagrams of a capture file
+    Decode {
+        /// Capture written by `listen --record` or `tcpdump -w`
+        capture: PathBuf,
+
+        #[command(flatten)]
+        filter: Filter,
+    },
+}
+
+#[derive(clap::Args, Debug)]
+struct Filter {
+    /// Only packets of this stream
+    #[arg(long)]
+    stream: Option<u32>,
+
+    /// Only messages from this sequence on
+    #[arg(long)]
+    from_seq: Option<u64>,
+
+    /// Only messages up to this sequence, inclusive
+    #[arg(long)]
+    to_seq: Option<u64>,
+
+    /// Only datagrams from or to this port, for captures of a busy link
+    #[arg(long)]
+    port: Option<u16>,
+
+    /// Print each datagram over several lines
+    #[arg(long, default_value_t = false)]
+    pretty: bool,
+}
+
+impl Filter {
+    fn seq_range(&self) -> Option<(u64, u64)> {
+        if self.from_seq.is_none() && self.to_seq.is_none() {
+            return None;
+        }
+        Some((self.from_seq.unwrap_or(0), self.to_seq.unwrap_or(u64::MAX)))
+    }
+}
+
+/// A message whose fragments are still coming in
+struct Partial {
+    msg_type: u8,
+    count: u16,
+    fragments: BTreeMap<u16, Vec<u8>>,
+}
+
+struct Decoder {
+    filter: Filter,
+    /// By sender, stream and sequence of the first fragment
+    partial: HashMap<(SocketAddr, u32, u64), Partial>,
+}
+
+impl Decoder {
+    fn new(filter: Filter) -> Self {
+        Self {
+            filter,
+            partial: HashMap::new(),
+        }
+    }
+
+    /// The datagram as JSON, `None` when filtered out
+    fn decode(&mut self, datagram: &CapturedDatagram) -> Option<Value> {
+        if let Some(port) = self.filter.port {
+            if datagram.src.port() != port && datagram.dst.port() != port {
+                return None;
+            }
+        }
+
+        let mut out = json!({
+            "timestamp_ns": datagram.timestamp_ns,
+            "src": datagram.src.to_string(),
+            "dst": datagram.dst.to_string(),
+            "len": datagram.payload.len(),
+        });
+        let header = match PacketHeader::read_from(&datagram.payload) {
+            Ok(header) => header,
+            Err(e) => {
+                // Not ours, or from a peer of another version
+                if self.filter.stream.is_some() || self.filter.seq_range().is_some() {
+                    return None;
+                }
+                out["error"] = json!(e.to_string());
+                return Some(out);
+            }
+        };
+        if self
+            .filter
+            .stream
+            .is_some_and(|stream| stream != header.stream_id)
+        {
+            return None;
+        }
+        out["header"] = json!({
+            "version": header.version,
+            "stream_id": header.stream_id,
+            "packet_seq": header.packet_seq,
+            "first_msg_seq": header.first_msg_seq,
+            "msg_count": header.msg_count,
+        });
+        if let Some(send_time_ns) = header.send_time_ns {
+            out["header"]["send_time_ns"] = json!(send_time_ns);
+        }
+
+        let packet = match Packet::parse(&datagram.payload) {
+            Ok(packet) => packet,
+            Err(e) => {
+                out["kind"] = json!("data");
+                out["error"] = json!(e.to_string());
+                return self
+                    .in_range(header.first_msg_seq, header.msg_count as u64)
+                    .then_some(out);
+            }
+        };
+
+        let kind = if header.is_heartbeat() {
+            "heartbeat"
+        } else if packet.is_control() {
+            "control"
+        } else {
+            "data"
+        };
+        out["kind"] = json!(kind);
+
+        let parsed_len: usize = header.encoded_len()
+            + packet
+                .messages
+                .iter()
+                .map(|m| MESSAGE_HEADER_LEN + m.payload.len())
+                .sum::<usize>();
+        if datagram.payload.len() == parsed_len + AUTH_TRAILER_LEN {
+            let trailer = &datagram.payload[parsed_len..];
+            out["auth"] = json!({
+                "key_id": u32::from_le_bytes(trailer[0..4].try_into().unwrap()),
+                "epoch": u64::from_le_bytes(trailer[4..12].try_into().unwrap()),
+            });
+        }
+
+        // Fragments outside the range still go to the reassembler, the
+        // message they complete may be in it
+        let messages: Vec<Value> = packet
+            .messages
+            .iter()
+            .filter_map(|message| {
+                let decoded = self.message(datagram.src, header.stream_id, message);
+                (kind == "control" || self.in_range(message.seq, 1)).then_some(decoded)
+            })
+            .collect();
+        // Control packets take no sequence numbers, they are always shown
+        if kind != "control" && !self.in_range(header.first_msg_seq, header.msg_count.max(1) as u64)
+        {
+            return None;
+        }
+        if !messages.is_empty() {
+            out["messages"] = Value::Array(messages);
+        }
+        Some(out)
+    }
+
+    /// Whether `count` messages from `seq` on overlap the requested range
+    fn in_range(&self, seq: u64, count: u64) -> bool {
+        match self.filter.seq_range() {
+            Some((from, to)) => seq <= to && seq.saturating_add(count) > from,
+            None => true,
+        }
+    }
+
+    fn message(&mut self, src: SocketAddr, stream_id: u32, message: &Message) -> Value {
+        let msg_type = type_name(message.header.msg_type);
+        let mut out = if message.message_type() == Some(MessageType::Control) {
+            // Control messages take no sequence numbers
+            json!({"type": msg_type, "len": message.payload.len()})
+        } else {
+            json!({"seq": message.seq, "type": msg_type, "len": message.payload.len()})
+        };
+        let flags = flag_names(MessageFlags(message.header.flags));
+        if !flags.is_empty() {
+            out["flags"] = json!(flags);
+        }
+        if !message.is_fragment() {
+            decode_payload(&mut out, message.header.msg_type, &message.payload);
+            return out;
+        }
+
+        let fragment = match FragmentHeader::read_from(&message.payload) {
+            Ok(fragment) => fragment,
+            Err(e) => {
+                out["error"] = json!(e.to_string());
+                return out;
+            }
+        };
+        out["fragment"] = json!({
+            "index": fragment.index,
+            "count": fragment.count,
+            "total_len": fragment.total_len,
+        });
+
+        let first_seq = message.seq.saturating_sub(fragment.index as u64);
+        let key = (src, stream_id, first_seq);
+        if self.partial.len() >= MAX_PARTIAL_MESSAGES && !self.partial.contains_key(&key) {
+            self.partial.clear();
+        }
+        let partial = self.partial.entry(key).or_insert_with(|| Partial {
+            msg_type: message.header.msg_type,
+            count: fragment.count,
+            fragments: BTreeMap::new(),
+        });
+        partial.fragments.insert(
+            fragment.index,
+            message.payload[FRAGMENT_HEADER_LEN..].to_vec(),
+        );
+        if partial.fragments.len() == partial.count as usize {
+            let partial = self.partial.remove(&key).unwrap();
+            let payload: Vec<u8> = partial.fragments.into_values().flatten().collect();
+            out["message_seq"] = json!(first_seq);
+            out["message_len"] = json!(payload.len());
+            decode_payload(&mut out, partial.msg_type, &payload);
+        }
+        out
+    }
+}
+
+fn type_name(msg_type: u8) -> String {
+    match MessageType::from_u8(msg_type) {
+        Some(msg_type) => format!("{:?}", msg_type),
+        None => format!("0x{:02x}", msg_type),
+    }
+}
+
+fn flag_names(flags: MessageFlags) -> Vec<&'static str> {
+    [
+        (MessageFlags::LAST_IN_BATCH, "LAST_IN_BATCH"),
+        (MessageFlags::URGENT, "URGENT"),
+        (MessageFlags::FRAGMENT, "FRAGMENT"),
+    ]
+    .into_iter()
+    .filter(|(flag, _)| flags.contains(*flag))
+    .map(|(_, name)| name)
+    .collect()
+}
+
+/// Add what a whole message carries to its JSON
+fn decode_payload(out: &mut Value, msg_type: u8, payload: &[u8]) {
+    let (field, decoded) = match MessageType::from_u8(msg_type) {
+        Some(MessageType::Control) => (
+            "control",
+            ControlMessage::read_from(payload)
+                .map(|control| control_json(&control))
+                .map_err(|e| e.to_string()),
+        ),
+        Some(
+            msg_type @ (MessageType::OrderNew
+            | MessageType::OrderCancel
+            | MessageType::OrderReplace
+            | MessageType::SnapshotRequest),
+        ) => (
+            "command",
+            decode_order_command(msg_type, payload)
+                .map(|command| command_json(&command))
+                .map_err(str::to_string),
+        ),
+        Some(
+            MessageType::MatchEvent
+            | MessageType::BookSnapshot
+            | MessageType::BookUpdate
+            | MessageType::PositionUpdate,
+        ) => (
+            "event",
+            decode_market_event(payload)
+                .map(|event| event_json(&event))
+                .map_err(str::to_string),
+        ),
+        Some(MessageType::Journal) => (
+            "journal",
+            decode_journal_entry(payload)
+                .map(|entry| journal_json(&entry))
+                .map_err(str::to_string),
+        ),
+        Some(MessageType::Heartbeat) | None => return,
+    };
+    match decoded {
+        Ok(decoded) => out[field] = decoded,
+        Err(e) => out["error"] = json!(e),
+    }
+}
+
+fn control_json(control: &ControlMessage) -> Value {
+    match *control {
+        ControlMessage::Nak { from_seq, to_seq } => {
+            json!({"type": "Nak", "from_seq": from_seq, "to_seq": to_seq})
+        }
+        ControlMessage::Unavailable { from_seq, to_seq } => {
+            json!({"type": "Unavailable", "from_seq": from_seq, "to_seq": to_seq})
+        }
+        ControlMessage::KeyRotation { key_id } => json!({"type": "KeyRotation", "key_id": key_id}),
+        ControlMessage::Hello {
+            session_id,
+            version,
+        } => {
+            json!({"type": "Hello", "session_id": session_id, "version": version})
+        }
+        ControlMessage::Accept {
+            session_id,
+            version,
+        } => {
+            json!({"type": "Accept", "session_id": session_id, "version": version})
+        }
+        ControlMessage::SequenceReset { next_seq } => {
+            json!({"type": "SequenceReset", "next_seq": next_seq})
+        }
+        ControlMessage::Logout { session_id } => {
+            json!({"type": "Logout", "session_id": session_id})
+        }
+    }
+}
+
+fn command_json(command: &OrderCommand) -> Value {
+    match command {
+        OrderCommand::New {
+            order_id,
+            user_id,
+            side,
+            order_type,
+            price,
+            quantity,
+        } => json!({
+            "type": "New",
+            "order_id": order_id.to_string(),
+            "user_id": user_id.map(|id| id.to_string()),
+            "side": format!("{:?}", side),
+            "order_type": format!("{:?}", order_type),
+            "price": price.map(|price| price.to_string()),
+            "quantity": quantity.to_string(),
+        }),
+        OrderCommand::Cancel { order_id, user_id } => json!({
+            "type": "Cancel",
+            "order_id": order_id.to_string(),
+            "user_id": user_id.map(|id| id.to_string()),
+        }),
+        OrderCommand::Replace {
+            order_id,
+            user_id,
+            price,
+            quantity,
+        } => json!({
+            "type": "Replace",
+            "order_id": order_id.to_string(),
+            "user_id": user_id.map(|id| id.to_string()),
+            "price": price.map(|price| price.to_string()),
+            "quantity": quantity.to_string(),
+        }),
+        OrderCommand::SnapshotRequest { symbol } => json!({
+            "type": "SnapshotRequest",
+            "symbol": symbol,
+        }),
+    }
+}
+
+fn event_json(event: &MarketEvent) -> Value {
+    match event {
+        MarketEvent::Fill {
+            symbol,
+            buy_order_id,
+            sell_order_id,
+            price,
+            quantity,
+            timestamp,
+        } => json!({
+            "type": "Fill",
+            "symbol": symbol,
+            "buy_order_id": buy_order_id.to_string(),
+            "sell_order_id": sell_order_id.to_string(),
+            "price": price.to_string(),
+            "quantity": quantity.to_string(),
+            "timestamp": timestamp,
+        }),
+        MarketEvent::OrderBookSnapshot {
+            symbol,
+            sequence,
+            bids,
+            asks,
+        } => {
+            let levels = |levels: &[udp_proto::binary::PriceLevel]| -> Vec<Value> {
+                levels
+                    .iter()
+                    .map(|level| json!([level.price.to_string(), level.quantity.to_string()]))
+                    .collect()
+            };
+            json!({
+                "type": "OrderBookSnapshot",
+                "symbol": symbol,
+                "sequence": sequence,
+                "bids": levels(bids),
+                "asks": levels(asks),
+            })
+        }
+        MarketEvent::OrderBookDelta {
+            symbol,
+            sequence,
+            deltas,
+        } => json!({
+            "type": "OrderBookDelta",
+            "symbol": symbol,
+            "sequence": sequence,
+            "deltas": deltas
+                .iter()
+                .map(|delta| json!({
+                    "action": format!("{:?}", delta.action),
+                    "side": format!("{:?}", delta.side),
+                    "price": delta.price.to_string(),
+                    "quantity": delta.quantity.to_string(),
+                }))
+                .collect::<Vec<_>>(),
+        }),
+        MarketEvent::OrderCancelled {
+            order_id,
+            filled_quantity,
+        } => json!({
+            "type": "OrderCancelled",
+            "order_id": order_id.to_string(),
+            "filled_quantity": filled_quantity.to_string(),
+        }),
+        MarketEvent::OrderFilled { order_id } => json!({
+            "type": "OrderFilled",
+            "order_id": order_id.to_string(),
+        }),
+        MarketEvent::MarketStatus {
+            symbol,
+            phase,
+            reason,
+            timestamp,
+        } => json!({
+            "type": "MarketStatus",
+            "symbol": symbol,
+            "phase": format!("{:?}", phase),
+            "reason": reason,
+            "timestamp": timestamp,
+        }),
+        MarketEvent::OrderRejected { order_id, reason } => json!({
+            "type": "OrderRejected",
+            "order_id": order_id.to_string(),
+            "reason": reason,
+        }),
+        MarketEvent::TradeBust {
+            symbol,
+            trade_id,
+            buy_order_id,
+            sell_order_id,
+            buyer_id,
+            seller_id,
+            price,
+            quantity,
+            timestamp,
+            reason,
+        } => json!({
+            "type": "TradeBust",
+            "symbol": symbol,
+            "trade_id": trade_id.to_string(),
+            "buy_order_id": buy_order_id.to_string(),
+            "sell_order_id": sell_order_id.to_string(),
+            "buyer_id": buyer_id.map(|id| id.to_string()),
+            "seller_id": seller_id.map(|id| id.to_string()),
+            "price": price.to_string(),
+            "quantity": quantity.to_string(),
+            "timestamp": timestamp,
+            "reason": reason,
+        }),
+        MarketEvent::PositionUpdate {
+            user_id,
+            balances,
+            timestamp,
+        } => json!({
+            "type": "PositionUpdate",
+            "user_id": user_id.to_string(),
+            "balances": balances
+                .iter()
+                .map(|b| {
+                    json!({
+                        "asset": b.asset,
+                        "available": b.available.to_string(),
+                        "locked": b.locked.to_string(),
+                    })
+                })
+                .collect::<Vec<_>>(),
+            "timestamp": timestamp,
+        }),
+    }
+}
+
+fn journal_json(entry: &JournalEntry) -> Value {
+    let record = match &entry.record {
+        JournalRecord::Command(command) => {
+            json!({"type": "Command", "command": command_json(command)})
+        }
+        JournalRecord::Checksum {
+            hash,
+            order_count,
+            phase,
+        } => json!({
+            "type": "Checksum",
+            "hash": format!("{:016x}", hash),
+            "order_count": order_count,
+            "phase": format!("{:?}", phase),
+        }),
+        JournalRecord::Fill {
+            buy_order_id,
+            sell_order_id,
+            price,
+            quantity,
+        } => json!({
+            "type": "Fill",
+            "buy_order_id": buy_order_id.to_string(),
+            "sell_order_id": sell_order_id.to_string(),
+            "price": price.to_string(),
+            "quantity": quantity.to_string(),
+        }),
+        JournalRecord::Rejected { order_id, reason } => json!({
+            "type": "Rejected",
+            "order_id": order_id.to_string(),
+            "reason": reason,
+        }),
+        JournalRecord::RiskControl(RiskControl::SetLimits { owner, limits }) => json!({
+            "type": "SetLimits",
+            "owner": owner.to_string(),
+            "limits": limits.as_ref().map(|limits| json!({
+                "max_order_quantity": limits.max_order_quantity.map(|d| d.to_string()),
+                "max_open_orders": limits.max_open_orders,
+                "max_notional_per_second": limits.max_notional_per_second.map(|d| d.to_string()),
+                "price_band_percent": limits.price_band_percent.map(|d| d.to_string()),
+            })),
+        }),
+        JournalRecord::RiskControl(RiskControl::Kill { owner }) => {
+            json!({"type": "Kill", "owner": owner.to_string()})
+        }
+        JournalRecord::RiskControl(RiskControl::Revive { owner }) => {
+            json!({"type": "Revive", "owner": owner.to_string()})
+        }
+    };
+    json!({"sequence": entry.sequence, "record": record})
+}
+
+/// Print one datagram, false once stdout is closed
+fn print(out: &mut impl Write, value: &Value, pretty: bool) -> bool {
+    let text = if pretty {
+        serde_json::to_string_pretty(value)
+    } else {
+        serde_json::to_string(value)
+    };
+    writeln!(out, "{}", text.expect("JSON values serialize")).is_ok()
+}
+
+fn bind(addr: SocketAddr, group: Option<Ipv4Addr>) -> io::Result<UdpSocket> {
+    let socket = Socket::new(Domain::for_address(addr), Type::DGRAM, Some(Protocol::UDP))?;
+    // Receivers of a multicast group share its port
+    socket.set_reuse_address(true)?;
+    socket.bind(&addr.into())?;
+    if let Some(group) = group {
+        socket.join_multicast_v4(&group, &Ipv4Addr::UNSPECIFIED)?;
+    }
+    Ok(socket.into())
+}
+
+fn listen(
+    bind_addr: SocketAddr,
+    group: Option<Ipv4Addr>,
+    record: Option<PathBuf>,
+    count: Option<u64>,
+    quiet: bool,
+    filter: Filter,
+) -> io::Result<()> {
+    let socket = bind(bind_addr, group)?;
+    let dst = socket.local_addr()?;
+    let mut writer = match record {
+        Some(path) => Some(CaptureWriter::new(BufWriter::new(File::create(path)?))?),
+        None => None,
+    };
+
+    let pretty = filter.pretty;
+    let mut decoder = Decoder::new(filter);
+    let mut stdout = io::stdout().lock();
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut received = 0;
+    while count != Some(received) {
+        let (len, src) = socket.recv_from(&mut buf)?;
+        let timestamp_ns = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_nanos() as u64)
+            .unwrap_or(0);
+        let datagram = CapturedDatagram {
+            timestamp_ns,
+            src,
+            dst,
+            payload: buf[..len].to_vec(),
+        };
+        received += 1;
+
+        // Flushed every time, the tool usually ends with Ctrl-C
+        if let Some(writer) = &mut writer {
+            writer.write(&datagram)?;
+            writer.flush()?;
+        }
+        if !quiet {
+            if let Some(value) = decoder.decode(&datagram) {
+                if !print(&mut stdout, &value, pretty) {
+                    break;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn decode(capture: PathBuf, filter: Filter) -> io::Result<()> {
+    let reader = CaptureReader::new(BufReader::new(File::open(capture)?))?;
+    let pretty = filter.pretty;
+    let mut decoder = Decoder::new(filter);
+    let mut stdout = io::stdout().lock();
+    for datagram in reader {
+        if let Some(value) = decoder.decode(&datagram?) {
+            if !print(&mut stdout, &value, pretty) {
+                break;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+    let result = match args.command {
+        Command::Listen {
+            bind,
+            group,
+            record,
+            count,
+            quiet,
+            filter,
+        } => listen(bind, group, record, count, quiet, filter),
+        Command::Decode { capture, filter } => decode(capture, filter),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("udp_capture: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -1,9 +1,11 @@
-//! Binary encoding/decoding for market events using FlatBuffers.
+//! Binary encoding/decoding for market events and order commands using FlatBuffers.
 //!
 //! Provides a clean API for encoding/decoding market events with zero-copy deserialization.
 
 use crate::fb;
+use crate::MessageType;
 use flatbuffers::FlatBufferBuilder;
+use rust_decimal::Decimal;
 use uuid::Uuid;
 
 /// Side of an order (Bid or Ask)
@@ -77,14 +79,79 @@ impl TryFrom<fb::DeltaAction> for DeltaAction {
     }
 }
 
+/// Order type (Limit or Market)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderType {
+    Limit,
+    Market,
+}
+
+impl From<OrderType> for fb::OrderType {
+    fn from(t: OrderType) -> Self {
+        match t {
+            OrderType::Limit => fb::OrderType::Limit,
+            OrderType::Market => fb::OrderType::Market,
+        }
+    }
+}
+
+impl TryFrom<fb::OrderType> for OrderType {
+    type Error = InvalidEnumValue;
+
+    fn try_from(t: fb::OrderType) -> Result<Self, Self::Error> {
+        match t {
+            fb::OrderType::Limit => Ok(OrderType::Limit),
+            fb::OrderType::Market => Ok(OrderType::Market),
+            _ => Err(InvalidEnumValue(t.0)),
+        }
+    }
+}
+
+/// Trading phase of a market
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarketPhase {
+    Open,
+    Halted,
+    PreOpen,
+    Auction,
+    Closed,
+    Delisted,
+}
+
+impl From<MarketPhase> for fb::MarketPhase {
+    fn from(p: MarketPhase) -> Self {
+        match p {
+            MarketPhase::Open => fb::MarketPhase::Open,
+            MarketPhase::Halted => fb::MarketPhase::Halted,
+            MarketPhase::PreOpen => fb::MarketPhase::PreOpen,
+            MarketPhase::Auction => fb::MarketPhase::Auction,
+            MarketPhase::Closed => fb::MarketPhase::Closed,
+            MarketPhase::Delisted => fb::MarketPhase::Delisted,
+        }
+    }
+}
+
+impl TryFrom<fb::MarketPhase> for MarketPhase {
+    type Error = InvalidEnumValue;
+
+    fn try_from(p: fb::MarketPhase) -> Result<Self, Self::Error> {
+        match p {
+            fb::MarketPhase::Open => Ok(MarketPhase::Open),
+            fb::MarketPhase::Halted => Ok(MarketPhase::Halted),
+            fb::MarketPhase::PreOpen => Ok(MarketPhase::PreOpen),
+            fb::MarketPhase::Auction => Ok(MarketPhase::Auction),
+            fb::MarketPhase::Closed => Ok(MarketPhase::Closed),
+            fb::MarketPhase::Delisted => Ok(MarketPhase::Delisted),
+            _ => Err(InvalidEnumValue(p.0)),
+        }
+    }
+}
+
 /// A price level in the orderbook
-/// Note: Uses f64 for wire format efficiency. The matching engine uses rust_decimal
-/// internally for precise calculations. f64 is acceptable here since this is only
-/// used for broadcasting market data to clients, not for order matching.
 #[derive(Debug, Clone, Copy)]
 pub struct PriceLevel {
-    pub price: f64,
-    pub quantity: f64,
+    pub price: Decimal,
+    pub quantity: Decimal,
 }
 
 /// A delta change to a price level
@@ -92,8 +159,16 @@ pub struct PriceLevel {
 pub struct LevelDelta {
     pub action: DeltaAction,
     pub side: Side,
-    pub price: f64,
-    pub quantity: f64,
+    pub price: Decimal,
+    pub quantity: Decimal,
+}
+
+/// Balance of one asset in a position update
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssetBalance {
+    pub asset: String,
+    pub available: Decimal,
+    pub locked: Decimal,
 }
 
 /// Market event types
@@ -105,8 +180,8 @@ pub enum MarketEvent {
         buy_order_id: Uuid,
         /// Order ID (UUID) for the sell side
         sell_order_id: Uuid,
-        price: f64,
-        quantity: f64,
+        price: Decimal,
+        quantity: Decimal,
         timestamp: u64,
     },
     OrderBookSnapshot {
@@ -125,13 +200,60 @@ pub enum MarketEvent {
         /// Order ID (UUID)
         order_id: Uuid,
         /// How much was filled before cancellation
-        filled_quantity: f64,
+        filled_quantity: Decimal,
     },
     /// Order was 100% filled
     OrderFilled {
         /// Order ID (UUID)
         order_id: Uuid,
     },
+    /// Market phase change or operator action
+    MarketStatus {
+        symbol: String,
+        phase: MarketPhase,
+        reason: String,
+        timestamp: u64,
+    },
+    /// Order was refused before matching, nothing of it was filled
+    OrderRejected {
+        /// Order ID (UUID)
+        order_id: Uuid,
+        reason: String,
+    },
+    /// A settled trade was reversed by an operator
+    TradeBust {
+        symbol: String,
+        trade_id: Uuid,
+        buy_order_id: Uuid,
+        sell_order_id: Uuid,
+        /// Counterparties, `None` for anonymous orders
+        buyer_id: Option<Uuid>,
+        seller_id: Option<Uuid>,
+        price: Decimal,
+        quantity: Decimal,
+        /// Timestamp of the original fill
+        timestamp: u64,
+        reason: String,
+    },
+    /// Balances of a user after a fill settled, private to that user
+    PositionUpdate {
+        user_id: Uuid,
+        balances: Vec<AssetBalance>,
+        timestamp: u64,
+    },
+}
+
+impl MarketEvent {
+    /// UDP message type this event is sent with, book events can be
+    /// conflated by the sender
+    pub fn message_type(&self) -> MessageType {
+        match self {
+            MarketEvent::OrderBookSnapshot { .. } => MessageType::BookSnapshot,
+            MarketEvent::OrderBookDelta { .. } => MessageType::BookUpdate,
+            MarketEvent::PositionUpdate { .. } => MessageType::PositionUpdate,
+            _ => MessageType::MatchEvent,
+        }
+    }
 }
 
 /// Helper to convert Uuid to FlatBuffer Uuid struct
@@ -190,6 +312,8 @@ impl MarketEventEncoder {
                 // Convert UUIDs to FlatBuffer format
                 let buy_uuid = uuid_to_fb(buy_order_id);
                 let sell_uuid = uuid_to_fb(sell_order_id);
+                let price = decimal_to_fb(price);
+                let quantity = decimal_to_fb(quantity);
 
                 let fill = fb::Fill::create(
                     &mut self.builder,
@@ -197,8 +321,8 @@ impl MarketEventEncoder {
                         symbol: Some(symbol_offset),
                         buy_order_id: Some(&buy_uuid),
                         sell_order_id: Some(&sell_uuid),
-                        price: *price,
-                        quantity: *quantity,
+                        price: Some(&price),
+                        quantity: Some(&quantity),
                         timestamp: *timestamp,
                     },
                 );
@@ -216,14 +340,14 @@ impl MarketEventEncoder {
                 // Create bid levels
                 let bids_vec: Vec<fb::PriceLevel> = bids
                     .iter()
-                    .map(|l| fb::PriceLevel::new(l.price, l.quantity))
+                    .map(|l| fb::PriceLevel::new(&decimal_to_fb(&l.price), &decimal_to_fb(&l.quantity)))
                     .collect();
                 let bids_offset = self.builder.create_vector(&bids_vec);
 
                 // Create ask levels
                 let asks_vec: Vec<fb::PriceLevel> = asks
                     .iter()
-                    .map(|l| fb::PriceLevel::new(l.price, l.quantity))
+                    .map(|l| fb::PriceLevel::new(&decimal_to_fb(&l.price), &decimal_to_fb(&l.quantity)))
                     .collect();
                 let asks_offset = self.builder.create_vector(&asks_vec);
 
@@ -249,7 +373,14 @@ impl MarketEventEncoder {
                 // Create delta levels
                 let deltas_vec: Vec<fb::LevelDelta> = deltas
                     .iter()
-                    .map(|d| fb::LevelDelta::new(d.action.into(), d.side.into(), d.price, d.quantity))
+                    .map(|d| {
+                        fb::LevelDelta::new(
+                            d.action.into(),
+                            d.side.into(),
+                            &decimal_to_fb(&d.price),
+                            &decimal_to_fb(&d.quantity),
+                        )
+                    })
                     .collect();
                 let deltas_offset = self.builder.create_vector(&deltas_vec);
 
@@ -269,12 +400,13 @@ impl MarketEventEncoder {
                 filled_quantity,
             } => {
                 let order_uuid = uuid_to_fb(order_id);
+                let filled_quantity = decimal_to_fb(filled_quantity);
 
                 let cancelled = fb::OrderCancelled::create(
                     &mut self.builder,
                     &fb::OrderCancelledArgs {
                         order_id: Some(&order_uuid),
-                        filled_quantity: *filled_quantity,
+                        filled_quantity: Some(&filled_quantity),
                     },
                 );
                 payload_type = fb::EventPayload::OrderCancelled;
@@ -292,6 +424,117 @@ impl MarketEventEncoder {
                 payload_type = fb::EventPayload::OrderFilled;
                 payload_offset = filled.as_union_value();
             }
+            MarketEvent::MarketStatus {
+                symbol,
+                phase,
+                reason,
+                timestamp,
+            } => {
+                let symbol_offset = self.builder.create_string(symbol);
+                let reason_offset = self.builder.create_string(reason);
+
+                let status = fb::MarketStatus::create(
+                    &mut self.builder,
+                    &fb::MarketStatusArgs {
+                        symbol: Some(symbol_offset),
+                        phase: (*phase).into(),
+                        reason: Some(reason_offset),
+                        timestamp: *timestamp,
+                    },
+                );
+                payload_type = fb::EventPayload::MarketStatus;
+                payload_offset = status.as_union_value();
+            }
+            MarketEvent::OrderRejected { order_id, reason } => {
+                let order_uuid = uuid_to_fb(order_id);
+                let reason_offset = self.builder.create_string(reason);
+
+                let rejected = fb::OrderRejected::create(
+                    &mut self.builder,
+                    &fb::OrderRejectedArgs {
+                        order_id: Some(&order_uuid),
+                        reason: Some(reason_offset),
+                    },
+                );
+                payload_type = fb::EventPayload::OrderRejected;
+                payload_offset = rejected.as_union_value();
+            }
+            MarketEvent::TradeBust {
+                symbol,
+                trade_id,
+                buy_order_id,
+                sell_order_id,
+                buyer_id,
+                seller_id,
+                price,
+                quantity,
+                timestamp,
+                reason,
+            } => {
+                let symbol_offset = self.builder.create_string(symbol);
+                let reason_offset = self.builder.create_string(reason);
+                let trade_uuid = uuid_to_fb(trade_id);
+                let buy_uuid = uuid_to_fb(buy_order_id);
+                let sell_uuid = uuid_to_fb(sell_order_id);
+                let buyer_uuid = buyer_id.as_ref().map(uuid_to_fb);
+                let seller_uuid = seller_id.as_ref().map(uuid_to_fb);
+                let price = decimal_to_fb(price);
+                let quantity = decimal_to_fb(quantity);
+
+                let bust = fb::TradeBust::create(
+                    &mut self.builder,
+                    &fb::TradeBustArgs {
+                        symbol: Some(symbol_offset),
+                        trade_id: Some(&trade_uuid),
+                        buy_order_id: Some(&buy_uuid),
+                        sell_order_id: Some(&sell_uuid),
+                        buyer_id: buyer_uuid.as_ref(),
+                        seller_id: seller_uuid.as_ref(),
+                        price: Some(&price),
+                        quantity: Some(&quantity),
+                        timestamp: *timestamp,
+                        reason: Some(reason_offset),
+                    },
+                );
+                payload_type = fb::EventPayload::TradeBust;
+                payload_offset = bust.as_union_value();
+            }
+            MarketEvent::PositionUpdate {
+                user_id,
+                balances,
+                timestamp,
+            } => {
+                let user_uuid = uuid_to_fb(user_id);
+
+                let balances_vec: Vec<_> = balances
+                    .iter()
+                    .map(|b| {
+                        let asset = self.builder.create_string(&b.asset);
+                        let available = decimal_to_fb(&b.available);
+                        let locked = decimal_to_fb(&b.locked);
+                        fb::AssetBalance::create(
+                            &mut self.builder,
+                            &fb::AssetBalanceArgs {
+                                asset: Some(asset),
+                                available: Some(&available),
+                                locked: Some(&locked),
+                            },
+                        )
+                    })
+                    .collect();
+                let balances_offset = self.builder.create_vector(&balances_vec);
+
+                let update = fb::PositionUpdate::create(
+                    &mut self.builder,
+                    &fb::PositionUpdateArgs {
+                        user_id: Some(&user_uuid),
+                        balances: Some(balances_offset),
+                        timestamp: *timestamp,
+                    },
+                );
+                payload_type = fb::EventPayload::PositionUpdate;
+                payload_offset = update.as_union_value();
+            }
         }
 
         let market_event = fb::MarketEvent::create(
@@ -318,6 +561,16 @@ impl Default for MarketEventEncoder {
     }
 }
 
+/// Symbol of an encoded book snapshot or delta, without decoding the rest
+pub fn book_symbol(data: &[u8]) -> Option<&str> {
+    let event = fb::root_as_market_event(data).ok()?;
+    match event.payload_type() {
+        fb::EventPayload::OrderBookSnapshot => event.payload_as_order_book_snapshot()?.symbol(),
+        fb::EventPayload::OrderBookDelta => event.payload_as_order_book_delta()?.symbol(),
+        _ => None,
+    }
+}
+
 /// Decode a market event from binary data (zero-copy)
 pub fn decode_market_event(data: &[u8]) -> Result<MarketEvent, &'static str> {
     let event = fb::root_as_market_event(data).map_err(|_| "Invalid FlatBuffer")?;
@@ -346,8 +599,8 @@ pub fn decode_market_event(data: &[u8]) -> Result<MarketEvent, &'static str> {
                 symbol: symbol.to_string(),
                 buy_order_id,
                 sell_order_id,
-                price: fill.price(),
-                quantity: fill.quantity(),
+                price: fb_to_decimal(fill.price().ok_or("Missing price in Fill")?)?,
+                quantity: fb_to_decimal(fill.quantity().ok_or("Missing quantity in Fill")?)?,
                 timestamp: fill.timestamp(),
             })
         }
@@ -362,35 +615,26 @@ pub fn decode_market_event(data: &[u8]) -> Result<MarketEvent, &'static str> {
                 return Err("Empty symbol in OrderBookSnapshot");
             }
 
-            let bids = snapshot
-                .bids()
-                .map(|v| {
-                    v.iter()
-                        .map(|l| PriceLevel {
-                            price: l.price(),
-                            quantity: l.quantity(),
-                        })
-                        .collect()
-                })
-                .unwrap_or_default();
-
-            let asks = snapshot
-                .asks()
-                .map(|v| {
-                    v.iter()
-                        .map(|l| PriceLevel {
-                            price: l.price(),
-                            quantity: l.quantity(),
-                        })
-                        .collect()
-                })
-                .unwrap_or_default();
+            let levels = |levels: Option<flatbuffers::Vector<'_, fb::PriceLevel>>| -> Result<Vec<PriceLevel>, &'static str> {
+                levels
+                    .map(|v| {
+                        v.iter()
+                            .map(|l| {
+                                Ok(PriceLevel {
+                                    price: fb_to_decimal(l.price())?,
+                                    quantity: fb_to_decimal(l.quantity())?,
+                                })
+                            })
+                            .collect()
+                    })
+                    .unwrap_or_else(|| Ok(Vec::new()))
+            };
 
             Ok(MarketEvent::OrderBookSnapshot {
                 symbol: symbol.to_string(),
                 sequence: snapshot.sequence(),
-                bids,
-                asks,
+                bids: levels(snapshot.bids())?,
+                asks: levels(snapshot.asks())?,
             })
         }
         fb::EventPayload::OrderBookDelta => {
@@ -404,7 +648,7 @@ pub fn decode_market_event(data: &[u8]) -> Result<MarketEvent, &'static str> {
                 return Err("Empty symbol in OrderBookDelta");
             }
 
-            let deltas_result: Result<Vec<LevelDelta>, _> = delta_msg
+            let deltas_result: Result<Vec<LevelDelta>, &'static str> = delta_msg
                 .deltas()
                 .map(|v| {
                     v.iter()
@@ -412,8 +656,8 @@ pub fn decode_market_event(data: &[u8]) -> Result<MarketEvent, &'static str> {
                             Ok(LevelDelta {
                                 action: d.action().try_into().map_err(|_| "Invalid delta action")?,
                                 side: d.side().try_into().map_err(|_| "Invalid side")?,
-                                price: d.price(),
-                                quantity: d.quantity(),
+                                price: fb_to_decimal(d.price())?,
+                                quantity: fb_to_decimal(d.quantity())?,
                             })
                         })
                         .collect()
@@ -439,7 +683,11 @@ pub fn decode_market_event(data: &[u8]) -> Result<MarketEvent, &'static str> {
 
             Ok(MarketEvent::OrderCancelled {
                 order_id,
-                filled_quantity: cancelled.filled_quantity(),
+                filled_quantity: fb_to_decimal(
+                    cancelled
+                        .filled_quantity()
+                        .ok_or("Missing filled_quantity in OrderCancelled")?,
+                )?,
             })
         }
         fb::EventPayload::OrderFilled => {
@@ -455,10 +703,649 @@ pub fn decode_market_event(data: &[u8]) -> Result<MarketEvent, &'static str> {
 
             Ok(MarketEvent::OrderFilled { order_id })
         }
+        fb::EventPayload::MarketStatus => {
+            let status = event
+                .payload_as_market_status()
+                .ok_or("Missing MarketStatus payload")?;
+
+            // Symbol is required
+            let symbol = status.symbol().ok_or("Missing symbol in MarketStatus")?;
+            if symbol.is_empty() {
+                return Err("Empty symbol in MarketStatus");
+            }
+
+            Ok(MarketEvent::MarketStatus {
+                symbol: symbol.to_string(),
+                phase: status.phase().try_into().map_err(|_| "Invalid market phase")?,
+                reason: status.reason().unwrap_or_default().to_string(),
+                timestamp: status.timestamp(),
+            })
+        }
+        fb::EventPayload::OrderRejected => {
+            let rejected = event
+                .payload_as_order_rejected()
+                .ok_or("Missing OrderRejected payload")?;
+
+            // Decode order_id UUID (required)
+            let order_id = rejected
+                .order_id()
+                .map(fb_to_uuid)
+                .ok_or("Missing order_id in OrderRejected")?;
+
+            Ok(MarketEvent::OrderRejected {
+                order_id,
+                reason: rejected.reason().unwrap_or_default().to_string(),
+            })
+        }
+        fb::EventPayload::TradeBust => {
+            let bust = event.payload_as_trade_bust().ok_or("Missing TradeBust payload")?;
+
+            let symbol = bust.symbol().ok_or("Missing symbol in TradeBust")?;
+            if symbol.is_empty() {
+                return Err("Empty symbol in TradeBust");
+            }
+
+            Ok(MarketEvent::TradeBust {
+                symbol: symbol.to_string(),
+                trade_id: bust.trade_id().map(fb_to_uuid).ok_or("Missing trade_id in TradeBust")?,
+                buy_order_id: bust.buy_order_id().map(fb_to_uuid).ok_or("Missing buy_order_id in TradeBust")?,
+                sell_order_id: bust.sell_order_id().map(fb_to_uuid).ok_or("Missing sell_order_id in TradeBust")?,
+                buyer_id: bust.buyer_id().map(fb_to_uuid),
+                seller_id: bust.seller_id().map(fb_to_uuid),
+                price: fb_to_decimal(bust.price().ok_or("Missing price in TradeBust")?)?,
+                quantity: fb_to_decimal(bust.quantity().ok_or("Missing quantity in TradeBust")?)?,
+                timestamp: bust.timestamp(),
+                reason: bust.reason().unwrap_or_default().to_string(),
+            })
+        }
+        fb::EventPayload::PositionUpdate => {
+            let update = event
+                .payload_as_position_update()
+                .ok_or("Missing PositionUpdate payload")?;
+
+            let balances: Result<Vec<AssetBalance>, &'static str> = update
+                .balances()
+                .map(|v| {
+                    v.iter()
+                        .map(|b| {
+                            Ok(AssetBalance {
+                                asset: b.asset().ok_or("Missing asset in AssetBalance")?.to_string(),
+                                available: fb_to_decimal(b.available().ok_or("Missing available in AssetBalance")?)?,
+                                locked: fb_to_decimal(b.locked().ok_or("Missing locked in AssetBalance")?)?,
+                            })
+                        })
+                        .collect()
+                })
+                .unwrap_or_else(|| Ok(Vec::new()));
+
+            Ok(MarketEvent::PositionUpdate {
+                user_id: update.user_id().map(fb_to_uuid).ok_or("Missing user_id in PositionUpdate")?,
+                balances: balances?,
+                timestamp: update.timestamp(),
+            })
+        }
         _ => Err("Unknown event type"),
     }
 }
 
+/// Order commands (gateway -> matching engine)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OrderCommand {
+    /// Sent as `MessageType::OrderNew`
+    New {
+        order_id: Uuid,
+        user_id: Option<Uuid>,
+        side: Side,
+        order_type: OrderType,
+        /// Limit price, `None` for market orders
+        price: Option<Decimal>,
+        quantity: Decimal,
+    },
+    /// Sent as `MessageType::OrderCancel`
+    Cancel {
+        order_id: Uuid,
+        user_id: Option<Uuid>,
+    },
+    /// Sent as `MessageType::OrderReplace`
+    Replace {
+        order_id: Uuid,
+        user_id: Option<Uuid>,
+        /// New limit price, `None` keeps the current price
+        price: Option<Decimal>,
+        quantity: Decimal,
+    },
+    /// Asks the engine to publish a full book snapshot on the event stream
+    SnapshotRequest { symbol: String },
+}
+
+impl OrderCommand {
+    /// UDP message type this command is sent with
+    pub fn message_type(&self) -> MessageType {
+        match self {
+            OrderCommand::New { .. } => MessageType::OrderNew,
+            OrderCommand::Cancel { .. } => MessageType::OrderCancel,
+            OrderCommand::Replace { .. } => MessageType::OrderReplace,
+            OrderCommand::SnapshotRequest { .. } => MessageType::SnapshotRequest,
+        }
+    }
+}
+
+impl From<Decimal> for fb::Decimal {
+    fn from(value: Decimal) -> Self {
+        let mantissa = value.mantissa();
+        let high = (mantissa >> 64) as i64;
+        let low = mantissa as u64;
+        fb::Decimal::new(high, low, value.scale())
+    }
+}
+
+/// Decimal on the wire with a mantissa or scale rust_decimal can't hold
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecimalOutOfRange;
+
+impl std::fmt::Display for DecimalOutOfRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Decimal out of range")
+    }
+}
+
+impl std::error::Error for DecimalOutOfRange {}
+
+impl TryFrom<fb::Decimal> for Decimal {
+    type Error = DecimalOutOfRange;
+
+    fn try_from(value: fb::Decimal) -> Result<Self, Self::Error> {
+        let high = value.mantissa_high() as i128;
+        let low = value.mantissa_low() as i128;
+        let mantissa = (high << 64) | low;
+        Decimal::try_from_i128_with_scale(mantissa, value.scale()).map_err(|_| DecimalOutOfRange)
+    }
+}
+
+/// Helper to convert Decimal to FlatBuffer Decimal struct
+fn decimal_to_fb(value: &Decimal) -> fb::Decimal {
+    (*value).into()
+}
+
+/// Helper to convert FlatBuffer Decimal struct to Decimal
+fn fb_to_decimal(fb_decimal: &fb::Decimal) -> Result<Decimal, &'static str> {
+    (*fb_decimal).try_into().map_err(|_| "Decimal out of range")
+}
+
+#[allow(clippy::too_many_arguments)]
+fn create_order_new<'b>(
+    builder: &mut FlatBufferBuilder<'b>,
+    order_id: &Uuid,
+    user_id: Option<&Uuid>,
+    side: Side,
+    order_type: OrderType,
+    price: Option<&Decimal>,
+    quantity: &Decimal,
+) -> flatbuffers::WIPOffset<fb::OrderNew<'b>> {
+    let order_uuid = uuid_to_fb(order_id);
+    let user_uuid = user_id.map(uuid_to_fb);
+    let price = price.map(decimal_to_fb);
+    let quantity = decimal_to_fb(quantity);
+
+    fb::OrderNew::create(
+        builder,
+        &fb::OrderNewArgs {
+            order_id: Some(&order_uuid),
+            user_id: user_uuid.as_ref(),
+            side: side.into(),
+            order_type: order_type.into(),
+            price: price.as_ref(),
+            quantity: Some(&quantity),
+        },
+    )
+}
+
+fn create_order_cancel<'b>(
+    builder: &mut FlatBufferBuilder<'b>,
+    order_id: &Uuid,
+    user_id: Option<&Uuid>,
+) -> flatbuffers::WIPOffset<fb::OrderCancel<'b>> {
+    let order_uuid = uuid_to_fb(order_id);
+    let user_uuid = user_id.map(uuid_to_fb);
+
+    fb::OrderCancel::create(
+        builder,
+        &fb::OrderCancelArgs {
+            order_id: Some(&order_uuid),
+            user_id: user_uuid.as_ref(),
+        },
+    )
+}
+
+fn order_new_from_fb(order: &fb::OrderNew) -> Result<OrderCommand, &'static str> {
+    let order_id = order
+        .order_id()
+        .map(fb_to_uuid)
+        .ok_or("Missing order_id in OrderNew")?;
+    let quantity = order
+        .quantity()
+        .ok_or("Missing quantity in OrderNew")
+        .and_then(fb_to_decimal)?;
+    let order_type = order.order_type().try_into().map_err(|_| "Invalid order type")?;
+    let price = order.price().map(fb_to_decimal).transpose()?;
+    if order_type == OrderType::Limit && price.is_none() {
+        return Err("Missing price in limit OrderNew");
+    }
+
+    Ok(OrderCommand::New {
+        order_id,
+        user_id: order.user_id().map(fb_to_uuid),
+        side: order.side().try_into().map_err(|_| "Invalid side")?,
+        order_type,
+        price,
+        quantity,
+    })
+}
+
+fn order_cancel_from_fb(cancel: &fb::OrderCancel) -> Result<OrderCommand, &'static str> {
+    let order_id = cancel
+        .order_id()
+        .map(fb_to_uuid)
+        .ok_or("Missing order_id in OrderCancel")?;
+
+    Ok(OrderCommand::Cancel {
+        order_id,
+        user_id: cancel.user_id().map(fb_to_uuid),
+    })
+}
+
+/// Encoder for order commands
+pub struct OrderCommandEncoder {
+    builder: FlatBufferBuilder<'static>,
+}
+
+impl OrderCommandEncoder {
+    pub fn new() -> Self {
+        Self {
+            builder: FlatBufferBuilder::with_capacity(256),
+        }
+    }
+
+    /// Encode an order command and return the binary data.
+    /// Send it with `command.message_type()`.
+    pub fn encode(&mut self, command: &OrderCommand) -> &[u8] {
+        self.builder.reset();
+
+        match command {
+            OrderCommand::New {
+                order_id,
+                user_id,
+                side,
+                order_type,
+                price,
+                quantity,
+            } => {
+                let order = create_order_new(
+                    &mut self.builder,
+                    order_id,
+                    user_id.as_ref(),
+                    *side,
+                    *order_type,
+                    price.as_ref(),
+                    quantity,
+                );
+                self.builder.finish(order, None);
+            }
+            OrderCommand::Cancel { order_id, user_id } => {
+                let cancel = create_order_cancel(&mut self.builder, order_id, user_id.as_ref());
+                self.builder.finish(cancel, None);
+            }
+            OrderCommand::Replace {
+                order_id,
+                user_id,
+                price,
+                quantity,
+            } => {
+                let order_uuid = uuid_to_fb(order_id);
+                let user_uuid = user_id.as_ref().map(uuid_to_fb);
+                let price = price.as_ref().map(decimal_to_fb);
+                let quantity = decimal_to_fb(quantity);
+
+                let replace = fb::OrderReplace::create(
+                    &mut self.builder,
+                    &fb::OrderReplaceArgs {
+                        order_id: Some(&order_uuid),
+                        user_id: user_uuid.as_ref(),
+                        price: price.as_ref(),
+                        quantity: Some(&quantity),
+                    },
+                );
+                self.builder.finish(replace, None);
+            }
+            OrderCommand::SnapshotRequest { symbol } => {
+                let symbol = self.builder.create_string(symbol);
+
+                let request = fb::SnapshotRequest::create(
+                    &mut self.builder,
+                    &fb::SnapshotRequestArgs { symbol: Some(symbol) },
+                );
+                self.builder.finish(request, None);
+            }
+        }
+
+        self.builder.finished_data()
+    }
+}
+
+impl Default for OrderCommandEncoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Decode an order command from binary data.
+/// The message type selects which table the payload holds.
+pub fn decode_order_command(msg_type: MessageType, data: &[u8]) -> Result<OrderCommand, &'static str> {
+    match msg_type {
+        MessageType::OrderNew => {
+            let order = flatbuffers::root::<fb::OrderNew>(data).map_err(|_| "Invalid FlatBuffer")?;
+            order_new_from_fb(&order)
+        }
+        MessageType::OrderCancel => {
+            let cancel = flatbuffers::root::<fb::OrderCancel>(data).map_err(|_| "Invalid FlatBuffer")?;
+            order_cancel_from_fb(&cancel)
+        }
+        MessageType::OrderReplace => {
+            let replace = flatbuffers::root::<fb::OrderReplace>(data).map_err(|_| "Invalid FlatBuffer")?;
+
+            let order_id = replace
+                .order_id()
+                .map(fb_to_uuid)
+                .ok_or("Missing order_id in OrderReplace")?;
+            let quantity = replace
+                .quantity()
+                .ok_or("Missing quantity in OrderReplace")
+                .and_then(fb_to_decimal)?;
+
+            Ok(OrderCommand::Replace {
+                order_id,
+                user_id: replace.user_id().map(fb_to_uuid),
+                price: replace.price().map(fb_to_decimal).transpose()?,
+                quantity,
+            })
+        }
+        MessageType::SnapshotRequest => {
+            let request = flatbuffers::root::<fb::SnapshotRequest>(data).map_err(|_| "Invalid FlatBuffer")?;
+
+            Ok(OrderCommand::SnapshotRequest {
+                symbol: request.symbol().ok_or("Missing symbol in SnapshotRequest")?.to_string(),
+            })
+        }
+        _ => Err("Not an order command"),
+    }
+}
+
+/// Risk limits of one owner, a missing limit is not enforced
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RiskLimits {
+    pub max_order_quantity: Option<Decimal>,
+    pub max_open_orders: Option<u64>,
+    pub max_notional_per_second: Option<Decimal>,
+    pub price_band_percent: Option<Decimal>,
+}
+
+/// An operator's change to the risk controls of one owner
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RiskControl {
+    /// Override the limits, `None` goes back to the defaults
+    SetLimits { owner: Uuid, limits: Option<RiskLimits> },
+    /// Block new orders. The resting ones are cancelled by entries of their own.
+    Kill { owner: Uuid },
+    /// Lift the kill switch
+    Revive { owner: Uuid },
+}
+
+/// What a journal entry carries
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JournalRecord {
+    /// A command the primary executed. Only `New` and `Cancel` change the book.
+    Command(OrderCommand),
+    /// State of the primary's book after the command with the entry's sequence
+    Checksum {
+        hash: u64,
+        order_count: u64,
+        phase: MarketPhase,
+    },
+    /// A fill of the command with the entry's sequence. Only journal files
+    /// carry fills, the follower gets them by matching on its own.
+    Fill {
+        buy_order_id: Uuid,
+        sell_order_id: Uuid,
+        price: Decimal,
+        quantity: Decimal,
+    },
+    /// A new order the primary refused before it reached the book. It takes no
+    /// sequence of its own, so the follower's dedup window can answer a resend.
+    Rejected { order_id: Uuid, reason: String },
+    /// An operator's risk control change, in force from the next command on
+    RiskControl(RiskControl),
+}
+
+/// Replication journal entry (primary -> follower), sent as `MessageType::Journal`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JournalEntry {
+    /// Commands are numbered from 1. Checksums, rejections and risk controls
+    /// repeat the last command's sequence.
+    pub sequence: u64,
+    pub record: JournalRecord,
+}
+
+/// Encoder for journal entries
+pub struct JournalEncoder {
+    builder: FlatBufferBuilder<'static>,
+}
+
+impl JournalEncoder {
+    pub fn new() -> Self {
+        Self {
+            builder: FlatBufferBuilder::with_capacity(256),
+        }
+    }
+
+    /// Encode a journal entry and return the binary data.
+    /// Fails for commands that never reach the book (replace, snapshot requests).
+    pub fn encode(&mut self, entry: &JournalEntry) -> Result<&[u8], &'static str> {
+        self.builder.reset();
+
+        let (record_type, record) = match &entry.record {
+            JournalRecord::Command(OrderCommand::New {
+                order_id,
+                user_id,
+                side,
+                order_type,
+                price,
+                quantity,
+            }) => {
+                let order = create_order_new(
+                    &mut self.builder,
+                    order_id,
+                    user_id.as_ref(),
+                    *side,
+                    *order_type,
+                    price.as_ref(),
+                    quantity,
+                );
+                (fb::JournalRecord::OrderNew, order.as_union_value())
+            }
+            JournalRecord::Command(OrderCommand::Cancel { order_id, user_id }) => {
+                let cancel = create_order_cancel(&mut self.builder, order_id, user_id.as_ref());
+                (fb::JournalRecord::OrderCancel, cancel.as_union_value())
+            }
+            JournalRecord::Command(_) => return Err("Only new orders and cancels are journaled"),
+            JournalRecord::Checksum {
+                hash,
+                order_count,
+                phase,
+            } => {
+                let checksum = fb::BookChecksum::create(
+                    &mut self.builder,
+                    &fb::BookChecksumArgs {
+                        hash: *hash,
+                        order_count: *order_count,
+                        phase: (*phase).into(),
+                    },
+                );
+                (fb::JournalRecord::BookChecksum, checksum.as_union_value())
+            }
+            JournalRecord::Fill {
+                buy_order_id,
+                sell_order_id,
+                price,
+                quantity,
+            } => {
+                let buy_order_id = uuid_to_fb(buy_order_id);
+                let sell_order_id = uuid_to_fb(sell_order_id);
+                let price = decimal_to_fb(price);
+                let quantity = decimal_to_fb(quantity);
+                let fill = fb::JournalFill::create(
+                    &mut self.builder,
+                    &fb::JournalFillArgs {
+                        buy_order_id: Some(&buy_order_id),
+                        sell_order_id: Some(&sell_order_id),
+                        price: Some(&price),
+                        quantity: Some(&quantity),
+                    },
+                );
+                (fb::JournalRecord::JournalFill, fill.as_union_value())
+            }
+            JournalRecord::Rejected { order_id, reason } => {
+                let order_id = uuid_to_fb(order_id);
+                let reason = self.builder.create_string(reason);
+                let rejected = fb::OrderRejected::create(
+                    &mut self.builder,
+                    &fb::OrderRejectedArgs {
+                        order_id: Some(&order_id),
+                        reason: Some(reason),
+                    },
+                );
+                (fb::JournalRecord::OrderRejected, rejected.as_union_value())
+            }
+            JournalRecord::RiskControl(control) => {
+                let (owner, action, limits) = match control {
+                    RiskControl::SetLimits { owner, limits } => (owner, fb::RiskAction::SetLimits, limits.as_ref()),
+                    RiskControl::Kill { owner } => (owner, fb::RiskAction::Kill, None),
+                    RiskControl::Revive { owner } => (owner, fb::RiskAction::Revive, None),
+                };
+                let limits = limits.map(|limits| {
+                    let max_order_quantity = limits.max_order_quantity.as_ref().map(decimal_to_fb);
+                    let max_notional_per_second = limits.max_notional_per_second.as_ref().map(decimal_to_fb);
+                    let price_band_percent = limits.price_band_percent.as_ref().map(decimal_to_fb);
+                    fb::RiskLimits::create(
+                        &mut self.builder,
+                        &fb::RiskLimitsArgs {
+                            max_order_quantity: max_order_quantity.as_ref(),
+                            max_open_orders: limits.max_open_orders,
+                            max_notional_per_second: max_notional_per_second.as_ref(),
+                            price_band_percent: price_band_percent.as_ref(),
+                        },
+                    )
+                });
+                let owner = uuid_to_fb(owner);
+                let control = fb::RiskControl::create(
+                    &mut self.builder,
+                    &fb::RiskControlArgs {
+                        owner: Some(&owner),
+                        action,
+                        limits,
+                    },
+                );
+                (fb::JournalRecord::RiskControl, control.as_union_value())
+            }
+        };
+
+        let entry = fb::JournalEntry::create(
+            &mut self.builder,
+            &fb::JournalEntryArgs {
+                sequence: entry.sequence,
+                record_type,
+                record: Some(record),
+            },
+        );
+        self.builder.finish(entry, None);
+
+        Ok(self.builder.finished_data())
+    }
+}
+
+impl Default for JournalEncoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Decode a journal entry from binary data
+pub fn decode_journal_entry(data: &[u8]) -> Result<JournalEntry, &'static str> {
+    let entry = flatbuffers::root::<fb::JournalEntry>(data).map_err(|_| "Invalid FlatBuffer")?;
+
+    let record = match entry.record_type() {
+        fb::JournalRecord::OrderNew => {
+            let order = entry.record_as_order_new().ok_or("Missing OrderNew record")?;
+            JournalRecord::Command(order_new_from_fb(&order)?)
+        }
+        fb::JournalRecord::OrderCancel => {
+            let cancel = entry.record_as_order_cancel().ok_or("Missing OrderCancel record")?;
+            JournalRecord::Command(order_cancel_from_fb(&cancel)?)
+        }
+        fb::JournalRecord::BookChecksum => {
+            let checksum = entry.record_as_book_checksum().ok_or("Missing BookChecksum record")?;
+            JournalRecord::Checksum {
+                hash: checksum.hash(),
+                order_count: checksum.order_count(),
+                phase: checksum.phase().try_into().map_err(|_| "Invalid market phase")?,
+            }
+        }
+        fb::JournalRecord::JournalFill => {
+            let fill = entry.record_as_journal_fill().ok_or("Missing JournalFill record")?;
+            JournalRecord::Fill {
+                buy_order_id: fb_to_uuid(fill.buy_order_id().ok_or("Missing buy order ID in JournalFill")?),
+                sell_order_id: fb_to_uuid(fill.sell_order_id().ok_or("Missing sell order ID in JournalFill")?),
+                price: fb_to_decimal(fill.price().ok_or("Missing price in JournalFill")?)?,
+                quantity: fb_to_decimal(fill.quantity().ok_or("Missing quantity in JournalFill")?)?,
+            }
+        }
+        fb::JournalRecord::OrderRejected => {
+            let rejected = entry.record_as_order_rejected().ok_or("Missing OrderRejected record")?;
+            JournalRecord::Rejected {
+                order_id: fb_to_uuid(rejected.order_id().ok_or("Missing order_id in OrderRejected")?),
+                reason: rejected.reason().unwrap_or_default().to_string(),
+            }
+        }
+        fb::JournalRecord::RiskControl => {
+            let control = entry.record_as_risk_control().ok_or("Missing RiskControl record")?;
+            let owner = fb_to_uuid(control.owner().ok_or("Missing owner in RiskControl")?);
+            JournalRecord::RiskControl(match control.action() {
+                fb::RiskAction::SetLimits => RiskControl::SetLimits {
+                    owner,
+                    limits: control.limits().map(risk_limits_from_fb).transpose()?,
+                },
+                fb::RiskAction::Kill => RiskControl::Kill { owner },
+                fb::RiskAction::Revive => RiskControl::Revive { owner },
+                _ => return Err("Invalid risk action"),
+            })
+        }
+        _ => return Err("Unknown journal record"),
+    };
+
+    Ok(JournalEntry {
+        sequence: entry.sequence(),
+        record,
+    })
+}
+
+fn risk_limits_from_fb(limits: fb::RiskLimits) -> Result<RiskLimits, &'static str> {
+    Ok(RiskLimits {
+        max_order_quantity: limits.max_order_quantity().map(fb_to_decimal).transpose()?,
+        max_open_orders: limits.max_open_orders(),
+        max_notional_per_second: limits.max_notional_per_second().map(fb_to_decimal).transpose()?,
+        price_band_percent: limits.price_band_percent().map(fb_to_decimal).transpose()?,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -471,12 +1358,12 @@ mod tests {
             symbol: "BTC/USD".to_string(),
             sequence: 12345,
             bids: vec![
-                PriceLevel { price: 50000.0, quantity: 1.5 },
-                PriceLevel { price: 49999.0, quantity: 2.0 },
+                PriceLevel { price: Decimal::new(50000, 0), quantity: Decimal::new(15, 1) },
+                PriceLevel { price: Decimal::new(49999, 0), quantity: Decimal::new(2, 0) },
             ],
             asks: vec![
-                PriceLevel { price: 50001.0, quantity: 1.0 },
-                PriceLevel { price: 50002.0, quantity: 3.0 },
+                PriceLevel { price: Decimal::new(50001, 0), quantity: Decimal::new(1, 0) },
+                PriceLevel { price: Decimal::new(50002, 0), quantity: Decimal::new(3, 0) },
             ],
         };
 
@@ -491,7 +1378,8 @@ mod tests {
                 assert_eq!(sequence, 12345);
                 assert_eq!(bids.len(), 2);
                 assert_eq!(asks.len(), 2);
-                assert_eq!(bids[0].price, 50000.0);
+                assert_eq!(bids[0].price, Decimal::new(50000, 0));
+                assert_eq!(bids[0].quantity, Decimal::new(15, 1));
             }
             _ => panic!("Wrong event type"),
         }
@@ -508,14 +1396,14 @@ mod tests {
                 LevelDelta {
                     action: DeltaAction::Update,
                     side: Side::Bid,
-                    price: 50000.0,
-                    quantity: 2.5,
+                    price: Decimal::new(50000, 0),
+                    quantity: Decimal::new(25, 1),
                 },
                 LevelDelta {
                     action: DeltaAction::Remove,
                     side: Side::Ask,
-                    price: 50001.0,
-                    quantity: 0.0,
+                    price: Decimal::new(50001, 0),
+                    quantity: Decimal::ZERO,
                 },
             ],
         };
@@ -537,21 +1425,38 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_book_symbol() {
+        let mut encoder = MarketEventEncoder::new();
+        let delta = MarketEvent::OrderBookDelta {
+            symbol: "ETH/USD".to_string(),
+            sequence: 7,
+            deltas: vec![],
+        };
+        assert_eq!(delta.message_type(), MessageType::BookUpdate);
+        assert_eq!(book_symbol(encoder.encode(&delta)), Some("ETH/USD"));
+
+        let filled = MarketEvent::OrderFilled { order_id: Uuid::new_v4() };
+        assert_eq!(filled.message_type(), MessageType::MatchEvent);
+        assert_eq!(book_symbol(encoder.encode(&filled)), None);
+        assert_eq!(book_symbol(b"not a flatbuffer"), None);
+    }
+
     #[test]
     fn test_10_level_snapshot_size() {
         let mut encoder = MarketEventEncoder::new();
 
         let bids: Vec<PriceLevel> = (0..10)
             .map(|i| PriceLevel {
-                price: 50000.0 - i as f64,
-                quantity: 1.0 + i as f64 * 0.1,
+                price: Decimal::new(50000 - i, 0),
+                quantity: Decimal::new(10 + i, 1),
             })
             .collect();
 
         let asks: Vec<PriceLevel> = (0..10)
             .map(|i| PriceLevel {
-                price: 50001.0 + i as f64,
-                quantity: 1.0 + i as f64 * 0.1,
+                price: Decimal::new(50001 + i, 0),
+                quantity: Decimal::new(10 + i, 1),
             })
             .collect();
 
@@ -565,8 +1470,8 @@ mod tests {
         let data = encoder.encode(&event);
         println!("10-level snapshot size: {} bytes", data.len());
 
-        // Should be well under 1400 byte MTU
-        assert!(data.len() < 500, "Snapshot too large: {} bytes", data.len());
+        // Should still fit a single packet
+        assert!(data.len() <= crate::MAX_MESSAGE_PAYLOAD, "Snapshot too large: {} bytes", data.len());
     }
 
     #[test]
@@ -580,8 +1485,8 @@ mod tests {
             symbol: "BTC/USD".to_string(),
             buy_order_id: buy_uuid,
             sell_order_id: sell_uuid,
-            price: 50000.0,
-            quantity: 0.5,
+            price: Decimal::new(50000, 0),
+            quantity: Decimal::new(5, 1),
             timestamp: 1699999999000,
         };
 
@@ -602,8 +1507,8 @@ mod tests {
                 assert_eq!(symbol, "BTC/USD");
                 assert_eq!(buy_order_id, buy_uuid);
                 assert_eq!(sell_order_id, sell_uuid);
-                assert_eq!(price, 50000.0);
-                assert_eq!(quantity, 0.5);
+                assert_eq!(price, Decimal::new(50000, 0));
+                assert_eq!(quantity, Decimal::new(5, 1));
                 assert_eq!(timestamp, 1699999999000);
             }
             _ => panic!("Wrong event type"),
@@ -618,7 +1523,7 @@ mod tests {
 
         let event = MarketEvent::OrderCancelled {
             order_id: order_uuid,
-            filled_quantity: 5.5,
+            filled_quantity: Decimal::new(55, 1),
         };
 
         let data = encoder.encode(&event);
@@ -632,9 +1537,396 @@ mod tests {
                 filled_quantity,
             } => {
                 assert_eq!(order_id, order_uuid);
-                assert_eq!(filled_quantity, 5.5);
+                assert_eq!(filled_quantity, Decimal::new(55, 1));
+            }
+            _ => panic!("Wrong event type"),
+        }
+    }
+
+    #[test]
+    fn test_market_status_encoding() {
+        let mut encoder = MarketEventEncoder::new();
+
+        let event = MarketEvent::MarketStatus {
+            symbol: "KCN/EUR".to_string(),
+            phase: MarketPhase::Halted,
+            reason: "halted by operator".to_string(),
+            timestamp: 1699999999000,
+        };
+
+        let data = encoder.encode(&event);
+        println!("MarketStatus size: {} bytes", data.len());
+
+        match decode_market_event(data).unwrap() {
+            MarketEvent::MarketStatus {
+                symbol,
+                phase,
+                reason,
+                timestamp,
+            } => {
+                assert_eq!(symbol, "KCN/EUR");
+                assert_eq!(phase, MarketPhase::Halted);
+                assert_eq!(reason, "halted by operator");
+                assert_eq!(timestamp, 1699999999000);
+            }
+            _ => panic!("Wrong event type"),
+        }
+    }
+
+    #[test]
+    fn test_order_rejected_encoding() {
+        let mut encoder = MarketEventEncoder::new();
+
+        let order_id = Uuid::new_v4();
+        let event = MarketEvent::OrderRejected {
+            order_id,
+            reason: "Order quantity 50 exceeds the limit of 10".to_string(),
+        };
+
+        let data = encoder.encode(&event);
+        println!("OrderRejected size: {} bytes", data.len());
+
+        match decode_market_event(data).unwrap() {
+            MarketEvent::OrderRejected { order_id: decoded, reason } => {
+                assert_eq!(decoded, order_id);
+                assert_eq!(reason, "Order quantity 50 exceeds the limit of 10");
+            }
+            _ => panic!("Wrong event type"),
+        }
+    }
+
+    #[test]
+    fn test_trade_bust_encoding() {
+        let mut encoder = MarketEventEncoder::new();
+
+        let buyer = Uuid::new_v4();
+        let event = MarketEvent::TradeBust {
+            symbol: "KCN/EUR".to_string(),
+            trade_id: Uuid::new_v4(),
+            buy_order_id: Uuid::new_v4(),
+            sell_order_id: Uuid::new_v4(),
+            buyer_id: Some(buyer),
+            seller_id: None,
+            price: Decimal::new(10_050, 2),
+            quantity: Decimal::new(15, 1),
+            timestamp: 1_700_000_000_123,
+            reason: "erroneous price".to_string(),
+        };
+
+        let data = encoder.encode(&event);
+        println!("TradeBust size: {} bytes", data.len());
+
+        match decode_market_event(data).unwrap() {
+            MarketEvent::TradeBust {
+                buyer_id,
+                seller_id,
+                price,
+                quantity,
+                timestamp,
+                reason,
+                ..
+            } => {
+                assert_eq!(buyer_id, Some(buyer));
+                assert_eq!(seller_id, None);
+                assert_eq!(price, Decimal::new(10_050, 2));
+                assert_eq!(quantity, Decimal::new(15, 1));
+                assert_eq!(timestamp, 1_700_000_000_123);
+                assert_eq!(reason, "erroneous price");
             }
             _ => panic!("Wrong event type"),
         }
     }
+
+    #[test]
+    fn test_position_update_encoding() {
+        let mut encoder = MarketEventEncoder::new();
+
+        let user_id = Uuid::new_v4();
+        let balances = vec![
+            AssetBalance {
+                asset: "KCN".to_string(),
+                available: Decimal::new(25, 1),
+                locked: Decimal::ZERO,
+            },
+            AssetBalance {
+                asset: "EUR".to_string(),
+                available: Decimal::new(974_875, 2),
+                locked: Decimal::new(100, 0),
+            },
+        ];
+        let event = MarketEvent::PositionUpdate {
+            user_id,
+            balances: balances.clone(),
+            timestamp: 1_700_000_000_123,
+        };
+        assert_eq!(event.message_type(), MessageType::PositionUpdate);
+
+        let data = encoder.encode(&event);
+        println!("PositionUpdate size: {} bytes", data.len());
+
+        match decode_market_event(data).unwrap() {
+            MarketEvent::PositionUpdate {
+                user_id: decoded,
+                balances: decoded_balances,
+                timestamp,
+            } => {
+                assert_eq!(decoded, user_id);
+                assert_eq!(decoded_balances, balances);
+                assert_eq!(timestamp, 1_700_000_000_123);
+            }
+            _ => panic!("Wrong event type"),
+        }
+    }
+
+    #[test]
+    fn test_market_phase_roundtrip() {
+        let mut encoder = MarketEventEncoder::new();
+
+        for phase in [
+            MarketPhase::PreOpen,
+            MarketPhase::Open,
+            MarketPhase::Auction,
+            MarketPhase::Halted,
+            MarketPhase::Closed,
+            MarketPhase::Delisted,
+        ] {
+            let event = MarketEvent::MarketStatus {
+                symbol: "KCN/EUR".to_string(),
+                phase,
+                reason: String::new(),
+                timestamp: 0,
+            };
+            match decode_market_event(encoder.encode(&event)).unwrap() {
+                MarketEvent::MarketStatus { phase: decoded, .. } => assert_eq!(decoded, phase),
+                _ => panic!("Wrong event type"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_fill_roundtrip_is_exact() {
+        let mut encoder = MarketEventEncoder::new();
+
+        // Neither is representable as f64
+        let price: Decimal = "50000.123456789012345678".parse().unwrap();
+        let quantity: Decimal = "0.10".parse().unwrap();
+        let event = MarketEvent::Fill {
+            symbol: "BTC/USD".to_string(),
+            buy_order_id: Uuid::new_v4(),
+            sell_order_id: Uuid::new_v4(),
+            price,
+            quantity,
+            timestamp: 0,
+        };
+
+        match decode_market_event(encoder.encode(&event)).unwrap() {
+            MarketEvent::Fill { price: p, quantity: q, .. } => {
+                assert_eq!(p, price);
+                // The scale survives too
+                assert_eq!(q.to_string(), "0.10");
+            }
+            _ => panic!("Wrong event type"),
+        }
+    }
+
+    #[test]
+    fn test_decimal_out_of_range_rejected() {
+        let value = fb::Decimal::new(0, 1, 29);
+        assert_eq!(Decimal::try_from(value), Err(DecimalOutOfRange));
+
+        let value: fb::Decimal = Decimal::new(-12_345, 3).into();
+        assert_eq!(Decimal::try_from(value), Ok(Decimal::new(-12_345, 3)));
+    }
+
+    #[test]
+    fn test_order_new_roundtrip_is_exact() {
+        let mut encoder = OrderCommandEncoder::new();
+
+        let command = OrderCommand::New {
+            order_id: Uuid::new_v4(),
+            user_id: Some(Uuid::new_v4()),
+            side: Side::Ask,
+            order_type: OrderType::Limit,
+            // Not representable as f64
+            price: Some("50000.123456789012345678".parse().unwrap()),
+            quantity: "0.1".parse().unwrap(),
+        };
+
+        let data = encoder.encode(&command);
+        println!("OrderNew size: {} bytes", data.len());
+
+        let decoded = decode_order_command(command.message_type(), data).unwrap();
+        assert_eq!(decoded, command);
+    }
+
+    #[test]
+    fn test_market_order_without_price() {
+        let mut encoder = OrderCommandEncoder::new();
+
+        let command = OrderCommand::New {
+            order_id: Uuid::new_v4(),
+            user_id: None,
+            side: Side::Bid,
+            order_type: OrderType::Market,
+            price: None,
+            quantity: Decimal::new(5, 0),
+        };
+
+        let data = encoder.encode(&command);
+        let decoded = decode_order_command(MessageType::OrderNew, data).unwrap();
+        assert_eq!(decoded, command);
+    }
+
+    #[test]
+    fn test_order_cancel_and_replace_roundtrip() {
+        let mut encoder = OrderCommandEncoder::new();
+        let order_id = Uuid::parse_str("550e8400-e29b-41d4-a716-446655440000").unwrap();
+
+        let cancel = OrderCommand::Cancel {
+            order_id,
+            user_id: Some(Uuid::new_v4()),
+        };
+        let data = encoder.encode(&cancel).to_vec();
+        assert_eq!(cancel.message_type(), MessageType::OrderCancel);
+        assert_eq!(decode_order_command(MessageType::OrderCancel, &data).unwrap(), cancel);
+
+        let replace = OrderCommand::Replace {
+            order_id,
+            user_id: None,
+            // Extremes of the 96-bit mantissa, both signs
+            price: Some(Decimal::MIN),
+            quantity: Decimal::from_i128_with_scale(79_228_162_514_264_337_593_543_950_335, 28),
+        };
+        let data = encoder.encode(&replace).to_vec();
+        assert_eq!(replace.message_type(), MessageType::OrderReplace);
+        assert_eq!(decode_order_command(MessageType::OrderReplace, &data).unwrap(), replace);
+    }
+
+    #[test]
+    fn test_snapshot_request_roundtrip() {
+        let mut encoder = OrderCommandEncoder::new();
+        let request = OrderCommand::SnapshotRequest {
+            symbol: "KCN/EUR".to_string(),
+        };
+
+        let data = encoder.encode(&request).to_vec();
+        assert_eq!(request.message_type(), MessageType::SnapshotRequest);
+        assert_eq!(decode_order_command(MessageType::SnapshotRequest, &data).unwrap(), request);
+    }
+
+    #[test]
+    fn test_order_command_rejects_wrong_message_type() {
+        let mut encoder = OrderCommandEncoder::new();
+        let data = encoder
+            .encode(&OrderCommand::Cancel {
+                order_id: Uuid::new_v4(),
+                user_id: None,
+            })
+            .to_vec();
+
+        assert!(decode_order_command(MessageType::MatchEvent, &data).is_err());
+        assert!(decode_order_command(MessageType::OrderCancel, &[0u8; 3]).is_err());
+    }
+
+    #[test]
+    fn test_limit_order_requires_price() {
+        let mut encoder = OrderCommandEncoder::new();
+        let data = encoder.encode(&OrderCommand::New {
+            order_id: Uuid::new_v4(),
+            user_id: None,
+            side: Side::Bid,
+            order_type: OrderType::Limit,
+            price: None,
+            quantity: Decimal::ONE,
+        });
+
+        assert_eq!(
+            decode_order_command(MessageType::OrderNew, data),
+            Err("Missing price in limit OrderNew")
+        );
+    }
+
+    #[test]
+    fn test_journal_entry_roundtrip() {
+        let mut encoder = JournalEncoder::new();
+        let entries = [
+            JournalEntry {
+                sequence: 1,
+                record: JournalRecord::Command(OrderCommand::New {
+                    order_id: Uuid::new_v4(),
+                    user_id: Some(Uuid::new_v4()),
+                    side: Side::Ask,
+                    order_type: OrderType::Limit,
+                    price: Some(Decimal::new(10_050, 2)),
+                    quantity: Decimal::new(15, 1),
+                }),
+            },
+            JournalEntry {
+                sequence: 2,
+                record: JournalRecord::Command(OrderCommand::Cancel {
+                    order_id: Uuid::new_v4(),
+                    user_id: None,
+                }),
+            },
+            JournalEntry {
+                sequence: 2,
+                record: JournalRecord::Checksum {
+                    hash: u64::MAX,
+                    order_count: 1,
+                    phase: MarketPhase::Halted,
+                },
+            },
+            JournalEntry {
+                sequence: 3,
+                record: JournalRecord::Fill {
+                    buy_order_id: Uuid::new_v4(),
+                    sell_order_id: Uuid::new_v4(),
+                    price: "50000.123456789012345678".parse().unwrap(),
+                    quantity: Decimal::new(1, 28),
+                },
+            },
+            JournalEntry {
+                sequence: 3,
+                record: JournalRecord::Rejected {
+                    order_id: Uuid::new_v4(),
+                    reason: "Market halted".to_string(),
+                },
+            },
+            JournalEntry {
+                sequence: 3,
+                record: JournalRecord::RiskControl(RiskControl::SetLimits {
+                    owner: Uuid::new_v4(),
+                    limits: Some(RiskLimits {
+                        max_order_quantity: Some(Decimal::new(25, 1)),
+                        max_open_orders: Some(0),
+                        ..Default::default()
+                    }),
+                }),
+            },
+            JournalEntry {
+                sequence: 3,
+                record: JournalRecord::RiskControl(RiskControl::SetLimits {
+                    owner: Uuid::new_v4(),
+                    limits: None,
+                }),
+            },
+            JournalEntry {
+                sequence: 3,
+                record: JournalRecord::RiskControl(RiskControl::Kill { owner: Uuid::new_v4() }),
+            },
+        ];
+
+        for entry in entries {
+            let data = encoder.encode(&entry).unwrap().to_vec();
+            assert_eq!(decode_journal_entry(&data).unwrap(), entry);
+        }
+
+        let snapshot = JournalEntry {
+            sequence: 3,
+            record: JournalRecord::Command(OrderCommand::SnapshotRequest {
+                symbol: "KCN/EUR".to_string(),
+            }),
+        };
+        assert!(encoder.encode(&snapshot).is_err());
+    }
 }
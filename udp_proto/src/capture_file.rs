@@ -0,0 +1,488 @@
+//! Capture files: UDP datagrams recorded off the wire, for debugging.
+//!
+//! Captures are classic pcap files, so Wireshark and tcpdump open them too.
+//! `CaptureWriter` stores each datagram with its receive time in nanoseconds,
+//! wrapped in a made-up IP and UDP header. `CaptureReader` reads those back,
+//! as well as captures taken with `tcpdump -w` on Ethernet, loopback, `any`
+//! (Linux cooked) and raw IP interfaces. Other traffic and fragmented IP
+//! packets are skipped. pcapng files have to be converted first
+//! (`editcap -F pcap`).
+
+use std::io::{self, Read, Write};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+/// Nanosecond pcap, microsecond captures use `0xa1b2c3d4`
+const MAGIC_NANOS: u32 = 0xa1b2_3c4d;
+const MAGIC_MICROS: u32 = 0xa1b2_c3d4;
+const PCAPNG_MAGIC: u32 = 0x0a0d_0d0a;
+const SNAPLEN: u32 = 65535;
+/// Largest record accepted when reading, anything bigger means a corrupt length
+const MAX_RECORD_LEN: usize = 256 * 1024;
+
+const LINKTYPE_NULL: u32 = 0;
+const LINKTYPE_ETHERNET: u32 = 1;
+const LINKTYPE_RAW: u32 = 101;
+const LINKTYPE_LINUX_SLL: u32 = 113;
+const LINKTYPE_IPV4: u32 = 228;
+const LINKTYPE_IPV6: u32 = 229;
+const LINKTYPE_LINUX_SLL2: u32 = 276;
+
+const ETHERTYPE_IPV4: u16 = 0x0800;
+const ETHERTYPE_IPV6: u16 = 0x86dd;
+const ETHERTYPE_VLAN: u16 = 0x8100;
+const IPPROTO_UDP: u8 = 17;
+const UDP_HEADER_LEN: usize = 8;
+
+/// A UDP datagram with where and when it was seen
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapturedDatagram {
+    /// Receive time, nanoseconds since the Unix epoch
+    pub timestamp_ns: u64,
+    pub src: SocketAddr,
+    pub dst: SocketAddr,
+    pub payload: Vec<u8>,
+}
+
+/// Writes datagrams to a capture file or any other byte sink
+pub struct CaptureWriter<W: Write> {
+    inner: W,
+    buf: Vec<u8>,
+}
+
+impl<W: Write> CaptureWriter<W> {
+    /// Start a capture, writing the file header
+    pub fn new(mut inner: W) -> io::Result<Self> {
+        let mut header = [0u8; 24];
+        header[0..4].copy_from_slice(&MAGIC_NANOS.to_le_bytes());
+        header[4..6].copy_from_slice(&2u16.to_le_bytes());
+        header[6..8].copy_from_slice(&4u16.to_le_bytes());
+        // Time zone and timestamp accuracy stay 0
+        header[16..20].copy_from_slice(&SNAPLEN.to_le_bytes());
+        header[20..24].copy_from_slice(&LINKTYPE_RAW.to_le_bytes());
+        inner.write_all(&header)?;
+
+        Ok(Self {
+            inner,
+            buf: Vec::with_capacity(2048),
+        })
+    }
+
+    /// Write one datagram. Buffering is up to `W`, call `flush` to push it out.
+    pub fn write(&mut self, datagram: &CapturedDatagram) -> io::Result<()> {
+        self.buf.clear();
+        write_ip_udp(&mut self.buf, datagram)?;
+
+        let mut record = [0u8; 16];
+        record[0..4]
+            .copy_from_slice(&((datagram.timestamp_ns / 1_000_000_000) as u32).to_le_bytes());
+        record[4..8]
+            .copy_from_slice(&((datagram.timestamp_ns % 1_000_000_000) as u32).to_le_bytes());
+        record[8..12].copy_from_slice(&(self.buf.len() as u32).to_le_bytes());
+        record[12..16].copy_from_slice(&(self.buf.len() as u32).to_le_bytes());
+        self.inner.write_all(&record)?;
+        self.inner.write_all(&self.buf)
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+/// IPv4 or IPv6 and UDP headers in front of the payload. The UDP checksum
+/// is left out, which IPv4 allows and Wireshark only flags for IPv6.
+fn write_ip_udp(buf: &mut Vec<u8>, datagram: &CapturedDatagram) -> io::Result<()> {
+    let udp_len = UDP_HEADER_LEN + datagram.payload.len();
+    let too_long = || io::Error::new(io::ErrorKind::InvalidInput, "Datagram too long for UDP");
+
+    match (datagram.src.ip(), datagram.dst.ip()) {
+        (IpAddr::V4(src), IpAddr::V4(dst)) => {
+            let total_len = u16::try_from(20 + udp_len).map_err(|_| too_long())?;
+            let mut header = [0u8; 20];
+            header[0] = 0x45;
+            header[2..4].copy_from_slice(&total_len.to_be_bytes());
+            // Don't fragment
+            header[6] = 0x40;
+            header[8] = 64;
+            header[9] = IPPROTO_UDP;
+            header[12..16].copy_from_slice(&src.octets());
+            header[16..20].copy_from_slice(&dst.octets());
+            let checksum = ipv4_checksum(&header);
+            header[10..12].copy_from_slice(&checksum.to_be_bytes());
+            buf.extend_from_slice(&header);
+        }
+        (src, dst) => {
+            let payload_len = u16::try_from(udp_len).map_err(|_| too_long())?;
+            let mut header = [0u8; 40];
+            header[0] = 0x60;
+            header[4..6].copy_from_slice(&payload_len.to_be_bytes());
+            header[6] = IPPROTO_UDP;
+            header[7] = 64;
+            header[8..24].copy_from_slice(&to_ipv6(src).octets());
+            header[24..40].copy_from_slice(&to_ipv6(dst).octets());
+            buf.extend_from_slice(&header);
+        }
+    }
+
+    buf.extend_from_slice(&datagram.src.port().to_be_bytes());
+    buf.extend_from_slice(&datagram.dst.port().to_be_bytes());
+    buf.extend_from_slice(&(udp_len as u16).to_be_bytes());
+    buf.extend_from_slice(&[0, 0]);
+    buf.extend_from_slice(&datagram.payload);
+    Ok(())
+}
+
+fn to_ipv6(ip: IpAddr) -> Ipv6Addr {
+    match ip {
+        IpAddr::V4(ip) => ip.to_ipv6_mapped(),
+        IpAddr::V6(ip) => ip,
+    }
+}
+
+fn ipv4_checksum(header: &[u8; 20]) -> u16 {
+    let mut sum: u32 = header
+        .chunks_exact(2)
+        .map(|word| u16::from_be_bytes([word[0], word[1]]) as u32)
+        .sum();
+    while sum > 0xffff {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    !(sum as u16)
+}
+
+/// Reads the UDP datagrams of a capture in the order they were captured
+pub struct CaptureReader<R: Read> {
+    inner: R,
+    /// File written on a host of the other byte order
+    swapped: bool,
+    nanos: bool,
+    linktype: u32,
+    buf: Vec<u8>,
+}
+
+impl<R: Read> CaptureReader<R> {
+    /// Open a capture, reading the file header
+    pub fn new(mut inner: R) -> io::Result<Self> {
+        let mut header = [0u8; 24];
+        inner.read_exact(&mut header).map_err(|e| match e.kind() {
+            io::ErrorKind::UnexpectedEof => {
+                io::Error::new(io::ErrorKind::InvalidData, "Not a pcap capture")
+            }
+            _ => e,
+        })?;
+
+        let magic = u32::from_le_bytes(header[0..4].try_into().unwrap());
+        let (swapped, nanos) = match magic {
+            MAGIC_NANOS => (false, true),
+            MAGIC_MICROS => (false, false),
+            _ if magic.swap_bytes() == MAGIC_NANOS => (true, true),
+            _ if magic.swap_bytes() == MAGIC_MICROS => (true, false),
+            PCAPNG_MAGIC => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "pcapng capture, convert it with `editcap -F pcap`",
+                ))
+            }
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "Not a pcap capture",
+                ))
+            }
+        };
+
+        let mut reader = Self {
+            inner,
+            swapped,
+            nanos,
+            linktype: 0,
+            buf: Vec::with_capacity(2048),
+        };
+        // The top bits of the link type may carry FCS flags
+        reader.linktype = reader.u32_at(&header, 20) & 0x0fff_ffff;
+        match reader.linktype {
+            LINKTYPE_NULL | LINKTYPE_ETHERNET | LINKTYPE_RAW | LINKTYPE_LINUX_SLL
+            | LINKTYPE_IPV4 | LINKTYPE_IPV6 | LINKTYPE_LINUX_SLL2 => Ok(reader),
+            linktype => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Unsupported link type {}", linktype),
+            )),
+        }
+    }
+
+    /// Next UDP datagram, `None` at a clean end of file. A truncated last
+    /// record is reported as `UnexpectedEof`.
+    pub fn read_datagram(&mut self) -> io::Result<Option<CapturedDatagram>> {
+        loop {
+            let mut record = [0u8; 16];
+            match self.inner.read(&mut record[..1])? {
+                0 => return Ok(None),
+                _ => self.inner.read_exact(&mut record[1..])?,
+            }
+
+            let secs = self.u32_at(&record, 0) as u64;
+            let fraction = self.u32_at(&record, 4) as u64;
+            let len = self.u32_at(&record, 8) as usize;
+            if len > MAX_RECORD_LEN {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "Capture record of {} bytes exceeds max of {}",
+                        len, MAX_RECORD_LEN
+                    ),
+                ));
+            }
+            self.buf.resize(len, 0);
+            self.inner.read_exact(&mut self.buf)?;
+
+            let timestamp_ns = secs * 1_000_000_000
+                + if self.nanos {
+                    fraction
+                } else {
+                    fraction * 1000
+                };
+            if let Some((src, dst, payload)) = parse_frame(self.linktype, &self.buf) {
+                return Ok(Some(CapturedDatagram {
+                    timestamp_ns,
+                    src,
+                    dst,
+                    payload: payload.to_vec(),
+                }));
+            }
+        }
+    }
+
+    fn u32_at(&self, buf: &[u8], offset: usize) -> u32 {
+        let value = u32::from_le_bytes(buf[offset..offset + 4].try_into().unwrap());
+        if self.swapped {
+            value.swap_bytes()
+        } else {
+            value
+        }
+    }
+}
+
+impl<R: Read> Iterator for CaptureReader<R> {
+    type Item = io::Result<CapturedDatagram>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_datagram().transpose()
+    }
+}
+
+/// Source, destination and payload of a captured frame, `None` unless it is
+/// a whole UDP datagram
+fn parse_frame(linktype: u32, frame: &[u8]) -> Option<(SocketAddr, SocketAddr, &[u8])> {
+    let packet = match linktype {
+        // Address family in host order, the IP version tells the same
+        LINKTYPE_NULL => frame.get(4..)?,
+        LINKTYPE_ETHERNET => {
+            let mut ethertype = u16::from_be_bytes([*frame.get(12)?, *frame.get(13)?]);
+            let mut offset = 14;
+            if ethertype == ETHERTYPE_VLAN {
+                ethertype = u16::from_be_bytes([*frame.get(16)?, *frame.get(17)?]);
+                offset = 18;
+            }
+            if ethertype != ETHERTYPE_IPV4 && ethertype != ETHERTYPE_IPV6 {
+                return None;
+            }
+            frame.get(offset..)?
+        }
+        LINKTYPE_LINUX_SLL => frame.get(16..)?,
+        LINKTYPE_LINUX_SLL2 => frame.get(20..)?,
+        _ => frame,
+    };
+
+    let (src_ip, dst_ip, udp) = match packet.first()? >> 4 {
+        4 => {
+            let header_len = ((packet[0] & 0x0f) as usize) * 4;
+            let total_len = u16::from_be_bytes([*packet.get(2)?, *packet.get(3)?]) as usize;
+            let fragment = u16::from_be_bytes([*packet.get(6)?, *packet.get(7)?]);
+            // More fragments, or not the first one
+            if fragment & 0x3fff != 0 || *packet.get(9)? != IPPROTO_UDP {
+                return None;
+            }
+            let src: [u8; 4] = packet.get(12..16)?.try_into().ok()?;
+            let dst: [u8; 4] = packet.get(16..20)?.try_into().ok()?;
+            let end = total_len.min(packet.len());
+            (
+                IpAddr::V4(Ipv4Addr::from(src)),
+                IpAddr::V4(Ipv4Addr::from(dst)),
+                packet.get(header_len..end)?,
+            )
+        }
+        6 => {
+            // Extension headers, fragments among them, are not followed
+            if *packet.get(6)? != IPPROTO_UDP {
+                return None;
+            }
+            let payload_len = u16::from_be_bytes([*packet.get(4)?, *packet.get(5)?]) as usize;
+            let src: [u8; 16] = packet.get(8..24)?.try_into().ok()?;
+            let dst: [u8; 16] = packet.get(24..40)?.try_into().ok()?;
+            let end = (40 + payload_len).min(packet.len());
+            (
+                IpAddr::V6(Ipv6Addr::from(src)),
+                IpAddr::V6(Ipv6Addr::from(dst)),
+                packet.get(40..end)?,
+            )
+        }
+        _ => return None,
+    };
+
+    if udp.len() < UDP_HEADER_LEN {
+        return None;
+    }
+    let src_port = u16::from_be_bytes([udp[0], udp[1]]);
+    let dst_port = u16::from_be_bytes([udp[2], udp[3]]);
+    let udp_len = u16::from_be_bytes([udp[4], udp[5]]) as usize;
+    // A datagram cut short by the snap length is kept as far as it goes
+    let payload = udp.get(UDP_HEADER_LEN..udp_len.clamp(UDP_HEADER_LEN, udp.len()))?;
+
+    Some((
+        SocketAddr::new(src_ip, src_port),
+        SocketAddr::new(dst_ip, dst_port),
+        payload,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn datagrams() -> Vec<CapturedDatagram> {
+        vec![
+            CapturedDatagram {
+                timestamp_ns: 1_700_000_000_123_456_789,
+                src: "127.0.0.1:40000".parse().unwrap(),
+                dst: "239.1.2.3:9000".parse().unwrap(),
+                payload: vec![2, 24, 0, 0, 1, 0, 0, 0],
+            },
+            CapturedDatagram {
+                timestamp_ns: 1_700_000_001_000_000_000,
+                src: "[::1]:40001".parse().unwrap(),
+                dst: "[::1]:9001".parse().unwrap(),
+                payload: vec![0xab; 1400],
+            },
+        ]
+    }
+
+    #[test]
+    fn test_capture_file_roundtrip() {
+        let mut writer = CaptureWriter::new(Vec::new()).unwrap();
+        for datagram in datagrams() {
+            writer.write(&datagram).unwrap();
+        }
+        let data = writer.into_inner();
+
+        let read: Vec<CapturedDatagram> = CaptureReader::new(data.as_slice())
+            .unwrap()
+            .collect::<io::Result<_>>()
+            .unwrap();
+        assert_eq!(read, datagrams());
+    }
+
+    #[test]
+    fn test_capture_file_truncated_record() {
+        let mut writer = CaptureWriter::new(Vec::new()).unwrap();
+        for datagram in datagrams() {
+            writer.write(&datagram).unwrap();
+        }
+        let mut data = writer.into_inner();
+        data.truncate(data.len() - 10);
+
+        let mut reader = CaptureReader::new(data.as_slice()).unwrap();
+        assert_eq!(reader.next().unwrap().unwrap(), datagrams()[0]);
+        let err = reader.next().unwrap().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    /// A big-endian, microsecond tcpdump capture of an Ethernet link, with
+    /// an ARP frame before the datagram
+    #[test]
+    fn test_capture_file_reads_tcpdump_ethernet() {
+        let payload = b"hello";
+        let mut ip = vec![
+            0x45,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0x40,
+            0,
+            64,
+            IPPROTO_UDP,
+            0,
+            0,
+            10,
+            0,
+            0,
+            1,
+            10,
+            0,
+            0,
+            2,
+        ];
+        let total_len = (20 + UDP_HEADER_LEN + payload.len()) as u16;
+        ip[2..4].copy_from_slice(&total_len.to_be_bytes());
+        ip.extend_from_slice(&1234u16.to_be_bytes());
+        ip.extend_from_slice(&9000u16.to_be_bytes());
+        ip.extend_from_slice(&((UDP_HEADER_LEN + payload.len()) as u16).to_be_bytes());
+        ip.extend_from_slice(&[0, 0]);
+        ip.extend_from_slice(payload);
+
+        let mut file = Vec::new();
+        file.extend_from_slice(&MAGIC_MICROS.to_be_bytes());
+        file.extend_from_slice(&2u16.to_be_bytes());
+        file.extend_from_slice(&4u16.to_be_bytes());
+        file.extend_from_slice(&[0; 8]);
+        file.extend_from_slice(&SNAPLEN.to_be_bytes());
+        file.extend_from_slice(&LINKTYPE_ETHERNET.to_be_bytes());
+
+        let mut record = |frame: &[u8], secs: u32, micros: u32| {
+            file.extend_from_slice(&secs.to_be_bytes());
+            file.extend_from_slice(&micros.to_be_bytes());
+            file.extend_from_slice(&(frame.len() as u32).to_be_bytes());
+            file.extend_from_slice(&(frame.len() as u32).to_be_bytes());
+            file.extend_from_slice(frame);
+        };
+        let mut arp = vec![0u8; 42];
+        arp[12..14].copy_from_slice(&0x0806u16.to_be_bytes());
+        record(&arp, 10, 0);
+        // 4 bytes of Ethernet padding after the datagram
+        let mut frame = vec![0u8; 12];
+        frame.extend_from_slice(&ETHERTYPE_IPV4.to_be_bytes());
+        frame.extend_from_slice(&ip);
+        frame.extend_from_slice(&[0; 4]);
+        // Cut by the snap length in the middle of the UDP header
+        record(&frame[..14 + 20 + 6], 10, 100);
+        record(&frame, 10, 250);
+
+        let read: Vec<CapturedDatagram> = CaptureReader::new(file.as_slice())
+            .unwrap()
+            .collect::<io::Result<_>>()
+            .unwrap();
+        assert_eq!(
+            read,
+            vec![CapturedDatagram {
+                timestamp_ns: 10_000_250_000,
+                src: "10.0.0.1:1234".parse().unwrap(),
+                dst: "10.0.0.2:9000".parse().unwrap(),
+                payload: payload.to_vec(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_capture_file_rejects_other_formats() {
+        let err = CaptureReader::new(&PCAPNG_MAGIC.to_le_bytes().repeat(6)[..])
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let err = CaptureReader::new(&[0u8; 24][..]).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -8,8 +8,8 @@ pub enum ProtocolError {
     #[error("Invalid version: expected {expected}, got {got}")]
     InvalidVersion { expected: u8, got: u8 },
 
-    #[error("Invalid header length: expected {expected}, got {got}")]
-    InvalidHeaderLength { expected: u8, got: u8 },
+    #[error("Unknown packet flags: {0:#04x}")]
+    UnknownPacketFlags(u8),
 
     #[error("Packet too large: {size} bytes exceeds MTU of {mtu}")]
     PacketTooLarge { size: usize, mtu: usize },
@@ -29,6 +29,30 @@ pub enum ProtocolError {
     #[error("Stream timeout: no packets for {ms}ms")]
     StreamTimeout { ms: u64 },
 
+    #[error("Invalid control message kind: {0}")]
+    InvalidControlMessage(u8),
+
+    #[error("Invalid fragment: index {index} of {count}")]
+    InvalidFragment { index: u16, count: u16 },
+
+    #[error("Packet authentication failed")]
+    AuthenticationFailed,
+
+    #[error("Unknown or retired stream key: {0}")]
+    UnknownKey(u32),
+
+    #[error("Replayed packet: seq {packet_seq}")]
+    Replayed { packet_seq: u64 },
+
+    #[error("No stream key configured")]
+    NoStreamKey,
+
+    #[error("Not a multicast group: {0}")]
+    NotMulticast(std::net::IpAddr),
+
+    #[error("Channel full")]
+    ChannelFull,
+
     #[error("Channel closed")]
     ChannelClosed,
 }
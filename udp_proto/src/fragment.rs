@@ -0,0 +1,166 @@
+//! Reassembly of messages sent as fragments.
+//!
+//! Fragments of a message take consecutive sequence numbers, so the sequence
+//! of the first one (`seq - index`) identifies the message on its stream.
+//! Partial messages are dropped when they time out, when too many are open
+//! at once, or when their fragments disagree with each other.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::error::{ProtocolError, Result};
+use crate::protocol::*;
+
+/// A message put back together from its fragments
+#[derive(Debug)]
+pub(crate) struct Reassembled {
+    pub payload: Vec<u8>,
+    /// Sequence of the first fragment
+    pub seq: u64,
+}
+
+struct Partial {
+    msg_type: MessageType,
+    total_len: u32,
+    chunks: Vec<Option<Vec<u8>>>,
+    received: usize,
+    started: Instant,
+}
+
+pub(crate) struct Reassembler {
+    max_message_size: usize,
+    max_partial_messages: usize,
+    timeout: Duration,
+    /// By (stream_id, sequence of the first fragment)
+    partials: HashMap<(u32, u64), Partial>,
+    /// Partial messages dropped since the last `take_dropped`
+    dropped: u64,
+}
+
+impl Reassembler {
+    pub fn new(max_message_size: usize, max_partial_messages: usize, timeout: Duration) -> Self {
+        Self {
+            max_message_size,
+            max_partial_messages,
+            timeout,
+            partials: HashMap::new(),
+            dropped: 0,
+        }
+    }
+
+    /// Add a fragment, returns the message once all of its fragments are in.
+    /// The partial message is dropped on an inconsistent fragment.
+    pub fn push(
+        &mut self,
+        stream_id: u32,
+        msg_type: MessageType,
+        seq: u64,
+        payload: &[u8],
+    ) -> Result<Option<Reassembled>> {
+        let header = FragmentHeader::read_from(payload)?;
+        let chunk = &payload[FRAGMENT_HEADER_LEN..];
+        if header.total_len as usize > self.max_message_size {
+            return Err(ProtocolError::MessageTooLarge {
+                size: header.total_len as usize,
+                max: self.max_message_size,
+            });
+        }
+
+        let first_seq = seq.wrapping_sub(header.index as u64);
+        let key = (stream_id, first_seq);
+        if chunk.len() != header.chunk_len() {
+            if self.partials.remove(&key).is_some() {
+                self.dropped += 1;
+            }
+            return Err(ProtocolError::InvalidFragment {
+                index: header.index,
+                count: header.count,
+            });
+        }
+        if !self.partials.contains_key(&key) {
+            if header.count == 1 {
+                return Self::complete(first_seq, header.total_len, vec![chunk.to_vec()]).map(Some);
+            }
+            if self.partials.len() >= self.max_partial_messages {
+                self.evict_oldest();
+            }
+            self.partials.insert(
+                key,
+                Partial {
+                    msg_type,
+                    total_len: header.total_len,
+                    chunks: vec![None; header.count as usize],
+                    received: 0,
+                    started: Instant::now(),
+                },
+            );
+        }
+
+        let partial = self.partials.get_mut(&key).unwrap();
+        if partial.msg_type != msg_type
+            || partial.total_len != header.total_len
+            || partial.chunks.len() != header.count as usize
+        {
+            self.partials.remove(&key);
+            self.dropped += 1;
+            return Err(ProtocolError::InvalidFragment {
+                index: header.index,
+                count: header.count,
+            });
+        }
+
+        let slot = &mut partial.chunks[header.index as usize];
+        if slot.is_none() {
+            *slot = Some(chunk.to_vec());
+            partial.received += 1;
+        }
+        if partial.received < partial.chunks.len() {
+            return Ok(None);
+        }
+
+        let partial = self.partials.remove(&key).unwrap();
+        let chunks = partial.chunks.into_iter().flatten().collect();
+        Self::complete(first_seq, partial.total_len, chunks).map(Some)
+    }
+
+    /// Drop partial messages older than the timeout
+    pub fn expire(&mut self) {
+        let before = self.partials.len();
+        let timeout = self.timeout;
+        self.partials.retain(|_, p| p.started.elapsed() < timeout);
+        self.dropped += (before - self.partials.len()) as u64;
+    }
+
+    /// Partial messages waiting for fragments
+    pub fn pending(&self) -> usize {
+        self.partials.len()
+    }
+
+    /// Partial messages dropped since the last call
+    pub fn take_dropped(&mut self) -> u64 {
+        std::mem::take(&mut self.dropped)
+    }
+
+    fn evict_oldest(&mut self) {
+        let oldest = self
+            .partials
+            .iter()
+            .min_by_key(|(_, p)| p.started)
+            .map(|(key, _)| *key);
+        if let Some(key) = oldest {
+            self.partials.remove(&key);
+            self.dropped += 1;
+        }
+    }
+
+    fn complete(seq: u64, total_len: u32, chunks: Vec<Vec<u8>>) -> Result<Reassembled> {
+        let payload = chunks.concat();
+        if payload.len() != total_len as usize {
+            return Err(ProtocolError::InvalidFragment {
+                index: 0,
+                count: chunks.len() as u16,
+            });
+        }
+        Ok(Reassembled { payload, seq })
+    }
+}
@@ -193,56 +193,40 @@ impl<'a> flatbuffers::Verifiable for DeltaAction {
 
 impl flatbuffers::SimpleToVerifyInSlice for DeltaAction {}
 #[deprecated(since = "2.0.0", note = "Use associated constants instead. This will no longer be generated in 2021.")]
-pub const ENUM_MIN_EVENT_PAYLOAD: u8 = 0;
+pub const ENUM_MIN_ORDER_TYPE: i8 = 0;
 #[deprecated(since = "2.0.0", note = "Use associated constants instead. This will no longer be generated in 2021.")]
-pub const ENUM_MAX_EVENT_PAYLOAD: u8 = 5;
+pub const ENUM_MAX_ORDER_TYPE: i8 = 1;
 #[deprecated(since = "2.0.0", note = "Use associated constants instead. This will no longer be generated in 2021.")]
 #[allow(non_camel_case_types)]
-pub const ENUM_VALUES_EVENT_PAYLOAD: [EventPayload; 6] = [
-  EventPayload::NONE,
-  EventPayload::Fill,
-  EventPayload::OrderBookSnapshot,
-  EventPayload::OrderBookDelta,
-  EventPayload::OrderCancelled,
-  EventPayload::OrderFilled,
+pub const ENUM_VALUES_ORDER_TYPE: [OrderType; 2] = [
+  OrderType::Limit,
+  OrderType::Market,
 ];
 
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
 #[repr(transparent)]
-pub struct EventPayload(pub u8);
+pub struct OrderType(pub i8);
 #[allow(non_upper_case_globals)]
-impl EventPayload {
-  pub const NONE: Self = Self(0);
-  pub const Fill: Self = Self(1);
-  pub const OrderBookSnapshot: Self = Self(2);
-  pub const OrderBookDelta: Self = Self(3);
-  pub const OrderCancelled: Self = Self(4);
-  pub const OrderFilled: Self = Self(5);
+impl OrderType {
+  pub const Limit: Self = Self(0);
+  pub const Market: Self = Self(1);
 
-  pub const ENUM_MIN: u8 = 0;
-  pub const ENUM_MAX: u8 = 5;
+  pub const ENUM_MIN: i8 = 0;
+  pub const ENUM_MAX: i8 = 1;
   pub const ENUM_VALUES: &'static [Self] = &[
-    Self::NONE,
-    Self::Fill,
-    Self::OrderBookSnapshot,
-    Self::OrderBookDelta,
-    Self::OrderCancelled,
-    Self::OrderFilled,
+    Self::Limit,
+    Self::Market,
   ];
   /// Returns the variant's name or "" if unknown.
   pub fn variant_name(self) -> Option<&'static str> {
     match self {
-      Self::NONE => Some("NONE"),
-      Self::Fill => Some("Fill"),
-      Self::OrderBookSnapshot => Some("OrderBookSnapshot"),
-      Self::OrderBookDelta => Some("OrderBookDelta"),
-      Self::OrderCancelled => Some("OrderCancelled"),
-      Self::OrderFilled => Some("OrderFilled"),
+      Self::Limit => Some("Limit"),
+      Self::Market => Some("Market"),
       _ => None,
     }
   }
 }
-impl core::fmt::Debug for EventPayload {
+impl core::fmt::Debug for OrderType {
   fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
     if let Some(name) = self.variant_name() {
       f.write_str(name)
@@ -251,370 +235,464 @@ impl core::fmt::Debug for EventPayload {
     }
   }
 }
-impl<'a> flatbuffers::Follow<'a> for EventPayload {
+impl<'a> flatbuffers::Follow<'a> for OrderType {
   type Inner = Self;
   #[inline]
   unsafe fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
-    let b = unsafe { flatbuffers::read_scalar_at::<u8>(buf, loc) };
+    let b = unsafe { flatbuffers::read_scalar_at::<i8>(buf, loc) };
     Self(b)
   }
 }
 
-impl flatbuffers::Push for EventPayload {
-    type Output = EventPayload;
+impl flatbuffers::Push for OrderType {
+    type Output = OrderType;
     #[inline]
     unsafe fn push(&self, dst: &mut [u8], _written_len: usize) {
-        unsafe { flatbuffers::emplace_scalar::<u8>(dst, self.0); }
+        unsafe { flatbuffers::emplace_scalar::<i8>(dst, self.0); }
     }
 }
 
-impl flatbuffers::EndianScalar for EventPayload {
-  type Scalar = u8;
+impl flatbuffers::EndianScalar for OrderType {
+  type Scalar = i8;
   #[inline]
-  fn to_little_endian(self) -> u8 {
+  fn to_little_endian(self) -> i8 {
     self.0.to_le()
   }
   #[inline]
   #[allow(clippy::wrong_self_convention)]
-  fn from_little_endian(v: u8) -> Self {
-    let b = u8::from_le(v);
+  fn from_little_endian(v: i8) -> Self {
+    let b = i8::from_le(v);
     Self(b)
   }
 }
 
-impl<'a> flatbuffers::Verifiable for EventPayload {
+impl<'a> flatbuffers::Verifiable for OrderType {
   #[inline]
   fn run_verifier(
     v: &mut flatbuffers::Verifier, pos: usize
   ) -> Result<(), flatbuffers::InvalidFlatbuffer> {
     use self::flatbuffers::Verifiable;
-    u8::run_verifier(v, pos)
+    i8::run_verifier(v, pos)
   }
 }
 
-impl flatbuffers::SimpleToVerifyInSlice for EventPayload {}
-pub struct EventPayloadUnionTableOffset {}
+impl flatbuffers::SimpleToVerifyInSlice for OrderType {}
+#[deprecated(since = "2.0.0", note = "Use associated constants instead. This will no longer be generated in 2021.")]
+pub const ENUM_MIN_MARKET_PHASE: i8 = 0;
+#[deprecated(since = "2.0.0", note = "Use associated constants instead. This will no longer be generated in 2021.")]
+pub const ENUM_MAX_MARKET_PHASE: i8 = 5;
+#[deprecated(since = "2.0.0", note = "Use associated constants instead. This will no longer be generated in 2021.")]
+#[allow(non_camel_case_types)]
+pub const ENUM_VALUES_MARKET_PHASE: [MarketPhase; 6] = [
+  MarketPhase::Open,
+  MarketPhase::Halted,
+  MarketPhase::PreOpen,
+  MarketPhase::Auction,
+  MarketPhase::Closed,
+  MarketPhase::Delisted,
+];
 
-// struct PriceLevel, aligned to 8
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
 #[repr(transparent)]
-#[derive(Clone, Copy, PartialEq)]
-pub struct PriceLevel(pub [u8; 16]);
-impl Default for PriceLevel { 
-  fn default() -> Self { 
-    Self([0; 16])
+pub struct MarketPhase(pub i8);
+#[allow(non_upper_case_globals)]
+impl MarketPhase {
+  pub const Open: Self = Self(0);
+  pub const Halted: Self = Self(1);
+  pub const PreOpen: Self = Self(2);
+  pub const Auction: Self = Self(3);
+  pub const Closed: Self = Self(4);
+  pub const Delisted: Self = Self(5);
+
+  pub const ENUM_MIN: i8 = 0;
+  pub const ENUM_MAX: i8 = 5;
+  pub const ENUM_VALUES: &'static [Self] = &[
+    Self::Open,
+    Self::Halted,
+    Self::PreOpen,
+    Self::Auction,
+    Self::Closed,
+    Self::Delisted,
+  ];
+  /// Returns the variant's name or "" if unknown.
+  pub fn variant_name(self) -> Option<&'static str> {
+    match self {
+      Self::Open => Some("Open"),
+      Self::Halted => Some("Halted"),
+      Self::PreOpen => Some("PreOpen"),
+      Self::Auction => Some("Auction"),
+      Self::Closed => Some("Closed"),
+      Self::Delisted => Some("Delisted"),
+      _ => None,
+    }
   }
 }
-impl core::fmt::Debug for PriceLevel {
+impl core::fmt::Debug for MarketPhase {
   fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
-    f.debug_struct("PriceLevel")
-      .field("price", &self.price())
-      .field("quantity", &self.quantity())
-      .finish()
-  }
-}
-
-impl flatbuffers::SimpleToVerifyInSlice for PriceLevel {}
-impl<'a> flatbuffers::Follow<'a> for PriceLevel {
-  type Inner = &'a PriceLevel;
-  #[inline]
-  unsafe fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
-    unsafe { <&'a PriceLevel>::follow(buf, loc) }
+    if let Some(name) = self.variant_name() {
+      f.write_str(name)
+    } else {
+      f.write_fmt(format_args!("<UNKNOWN {:?}>", self.0))
+    }
   }
 }
-impl<'a> flatbuffers::Follow<'a> for &'a PriceLevel {
-  type Inner = &'a PriceLevel;
+impl<'a> flatbuffers::Follow<'a> for MarketPhase {
+  type Inner = Self;
   #[inline]
   unsafe fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
-    unsafe { flatbuffers::follow_cast_ref::<PriceLevel>(buf, loc) }
+    let b = unsafe { flatbuffers::read_scalar_at::<i8>(buf, loc) };
+    Self(b)
   }
 }
-impl<'b> flatbuffers::Push for PriceLevel {
-    type Output = PriceLevel;
+
+impl flatbuffers::Push for MarketPhase {
+    type Output = MarketPhase;
     #[inline]
     unsafe fn push(&self, dst: &mut [u8], _written_len: usize) {
-        let src = unsafe { ::core::slice::from_raw_parts(self as *const PriceLevel as *const u8, <Self as flatbuffers::Push>::size()) };
-        dst.copy_from_slice(src);
-    }
-    #[inline]
-    fn alignment() -> flatbuffers::PushAlignment {
-        flatbuffers::PushAlignment::new(8)
+        unsafe { flatbuffers::emplace_scalar::<i8>(dst, self.0); }
     }
 }
 
-impl<'a> flatbuffers::Verifiable for PriceLevel {
+impl flatbuffers::EndianScalar for MarketPhase {
+  type Scalar = i8;
+  #[inline]
+  fn to_little_endian(self) -> i8 {
+    self.0.to_le()
+  }
+  #[inline]
+  #[allow(clippy::wrong_self_convention)]
+  fn from_little_endian(v: i8) -> Self {
+    let b = i8::from_le(v);
+    Self(b)
+  }
+}
+
+impl<'a> flatbuffers::Verifiable for MarketPhase {
   #[inline]
   fn run_verifier(
     v: &mut flatbuffers::Verifier, pos: usize
   ) -> Result<(), flatbuffers::InvalidFlatbuffer> {
     use self::flatbuffers::Verifiable;
-    v.in_buffer::<Self>(pos)
+    i8::run_verifier(v, pos)
   }
 }
 
-impl<'a> PriceLevel {
-  #[allow(clippy::too_many_arguments)]
-  pub fn new(
-    price: f64,
-    quantity: f64,
-  ) -> Self {
-    let mut s = Self([0; 16]);
-    s.set_price(price);
-    s.set_quantity(quantity);
-    s
-  }
-
-  pub fn price(&self) -> f64 {
-    let mut mem = core::mem::MaybeUninit::<<f64 as EndianScalar>::Scalar>::uninit();
-    // Safety:
-    // Created from a valid Table for this object
-    // Which contains a valid value in this slot
-    EndianScalar::from_little_endian(unsafe {
-      core::ptr::copy_nonoverlapping(
-        self.0[0..].as_ptr(),
-        mem.as_mut_ptr() as *mut u8,
-        core::mem::size_of::<<f64 as EndianScalar>::Scalar>(),
-      );
-      mem.assume_init()
-    })
-  }
-
-  pub fn set_price(&mut self, x: f64) {
-    let x_le = x.to_little_endian();
-    // Safety:
-    // Created from a valid Table for this object
-    // Which contains a valid value in this slot
-    unsafe {
-      core::ptr::copy_nonoverlapping(
-        &x_le as *const _ as *const u8,
-        self.0[0..].as_mut_ptr(),
-        core::mem::size_of::<<f64 as EndianScalar>::Scalar>(),
-      );
-    }
-  }
+impl flatbuffers::SimpleToVerifyInSlice for MarketPhase {}
+#[deprecated(since = "2.0.0", note = "Use associated constants instead. This will no longer be generated in 2021.")]
+pub const ENUM_MIN_RISK_ACTION: i8 = 0;
+#[deprecated(since = "2.0.0", note = "Use associated constants instead. This will no longer be generated in 2021.")]
+pub const ENUM_MAX_RISK_ACTION: i8 = 2;
+#[deprecated(since = "2.0.0", note = "Use associated constants instead. This will no longer be generated in 2021.")]
+#[allow(non_camel_case_types)]
+pub const ENUM_VALUES_RISK_ACTION: [RiskAction; 3] = [
+  RiskAction::SetLimits,
+  RiskAction::Kill,
+  RiskAction::Revive,
+];
 
-  pub fn quantity(&self) -> f64 {
-    let mut mem = core::mem::MaybeUninit::<<f64 as EndianScalar>::Scalar>::uninit();
-    // Safety:
-    // Created from a valid Table for this object
-    // Which contains a valid value in this slot
-    EndianScalar::from_little_endian(unsafe {
-      core::ptr::copy_nonoverlapping(
-        self.0[8..].as_ptr(),
-        mem.as_mut_ptr() as *mut u8,
-        core::mem::size_of::<<f64 as EndianScalar>::Scalar>(),
-      );
-      mem.assume_init()
-    })
-  }
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[repr(transparent)]
+pub struct RiskAction(pub i8);
+#[allow(non_upper_case_globals)]
+impl RiskAction {
+  pub const SetLimits: Self = Self(0);
+  pub const Kill: Self = Self(1);
+  pub const Revive: Self = Self(2);
 
-  pub fn set_quantity(&mut self, x: f64) {
-    let x_le = x.to_little_endian();
-    // Safety:
-    // Created from a valid Table for this object
-    // Which contains a valid value in this slot
-    unsafe {
-      core::ptr::copy_nonoverlapping(
-        &x_le as *const _ as *const u8,
-        self.0[8..].as_mut_ptr(),
-        core::mem::size_of::<<f64 as EndianScalar>::Scalar>(),
-      );
+  pub const ENUM_MIN: i8 = 0;
+  pub const ENUM_MAX: i8 = 2;
+  pub const ENUM_VALUES: &'static [Self] = &[
+    Self::SetLimits,
+    Self::Kill,
+    Self::Revive,
+  ];
+  /// Returns the variant's name or "" if unknown.
+  pub fn variant_name(self) -> Option<&'static str> {
+    match self {
+      Self::SetLimits => Some("SetLimits"),
+      Self::Kill => Some("Kill"),
+      Self::Revive => Some("Revive"),
+      _ => None,
     }
   }
-
-}
-
-// struct LevelDelta, aligned to 8
-#[repr(transparent)]
-#[derive(Clone, Copy, PartialEq)]
-pub struct LevelDelta(pub [u8; 24]);
-impl Default for LevelDelta { 
-  fn default() -> Self { 
-    Self([0; 24])
-  }
 }
-impl core::fmt::Debug for LevelDelta {
+impl core::fmt::Debug for RiskAction {
   fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
-    f.debug_struct("LevelDelta")
-      .field("action", &self.action())
-      .field("side", &self.side())
-      .field("price", &self.price())
-      .field("quantity", &self.quantity())
-      .finish()
-  }
-}
-
-impl flatbuffers::SimpleToVerifyInSlice for LevelDelta {}
-impl<'a> flatbuffers::Follow<'a> for LevelDelta {
-  type Inner = &'a LevelDelta;
-  #[inline]
-  unsafe fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
-    unsafe { <&'a LevelDelta>::follow(buf, loc) }
+    if let Some(name) = self.variant_name() {
+      f.write_str(name)
+    } else {
+      f.write_fmt(format_args!("<UNKNOWN {:?}>", self.0))
+    }
   }
 }
-impl<'a> flatbuffers::Follow<'a> for &'a LevelDelta {
-  type Inner = &'a LevelDelta;
+impl<'a> flatbuffers::Follow<'a> for RiskAction {
+  type Inner = Self;
   #[inline]
   unsafe fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
-    unsafe { flatbuffers::follow_cast_ref::<LevelDelta>(buf, loc) }
+    let b = unsafe { flatbuffers::read_scalar_at::<i8>(buf, loc) };
+    Self(b)
   }
 }
-impl<'b> flatbuffers::Push for LevelDelta {
-    type Output = LevelDelta;
+
+impl flatbuffers::Push for RiskAction {
+    type Output = RiskAction;
     #[inline]
     unsafe fn push(&self, dst: &mut [u8], _written_len: usize) {
-        let src = unsafe { ::core::slice::from_raw_parts(self as *const LevelDelta as *const u8, <Self as flatbuffers::Push>::size()) };
-        dst.copy_from_slice(src);
-    }
-    #[inline]
-    fn alignment() -> flatbuffers::PushAlignment {
-        flatbuffers::PushAlignment::new(8)
+        unsafe { flatbuffers::emplace_scalar::<i8>(dst, self.0); }
     }
 }
 
-impl<'a> flatbuffers::Verifiable for LevelDelta {
+impl flatbuffers::EndianScalar for RiskAction {
+  type Scalar = i8;
+  #[inline]
+  fn to_little_endian(self) -> i8 {
+    self.0.to_le()
+  }
+  #[inline]
+  #[allow(clippy::wrong_self_convention)]
+  fn from_little_endian(v: i8) -> Self {
+    let b = i8::from_le(v);
+    Self(b)
+  }
+}
+
+impl<'a> flatbuffers::Verifiable for RiskAction {
   #[inline]
   fn run_verifier(
     v: &mut flatbuffers::Verifier, pos: usize
   ) -> Result<(), flatbuffers::InvalidFlatbuffer> {
     use self::flatbuffers::Verifiable;
-    v.in_buffer::<Self>(pos)
+    i8::run_verifier(v, pos)
   }
 }
 
-impl<'a> LevelDelta {
-  #[allow(clippy::too_many_arguments)]
-  pub fn new(
-    action: DeltaAction,
-    side: Side,
-    price: f64,
-    quantity: f64,
-  ) -> Self {
-    let mut s = Self([0; 24]);
-    s.set_action(action);
-    s.set_side(side);
-    s.set_price(price);
-    s.set_quantity(quantity);
-    s
-  }
+impl flatbuffers::SimpleToVerifyInSlice for RiskAction {}
+#[deprecated(since = "2.0.0", note = "Use associated constants instead. This will no longer be generated in 2021.")]
+pub const ENUM_MIN_JOURNAL_RECORD: u8 = 0;
+#[deprecated(since = "2.0.0", note = "Use associated constants instead. This will no longer be generated in 2021.")]
+pub const ENUM_MAX_JOURNAL_RECORD: u8 = 6;
+#[deprecated(since = "2.0.0", note = "Use associated constants instead. This will no longer be generated in 2021.")]
+#[allow(non_camel_case_types)]
+pub const ENUM_VALUES_JOURNAL_RECORD: [JournalRecord; 7] = [
+  JournalRecord::NONE,
+  JournalRecord::OrderNew,
+  JournalRecord::OrderCancel,
+  JournalRecord::BookChecksum,
+  JournalRecord::JournalFill,
+  JournalRecord::OrderRejected,
+  JournalRecord::RiskControl,
+];
 
-  pub fn action(&self) -> DeltaAction {
-    let mut mem = core::mem::MaybeUninit::<<DeltaAction as EndianScalar>::Scalar>::uninit();
-    // Safety:
-    // Created from a valid Table for this object
-    // Which contains a valid value in this slot
-    EndianScalar::from_little_endian(unsafe {
-      core::ptr::copy_nonoverlapping(
-        self.0[0..].as_ptr(),
-        mem.as_mut_ptr() as *mut u8,
-        core::mem::size_of::<<DeltaAction as EndianScalar>::Scalar>(),
-      );
-      mem.assume_init()
-    })
-  }
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[repr(transparent)]
+pub struct JournalRecord(pub u8);
+#[allow(non_upper_case_globals)]
+impl JournalRecord {
+  pub const NONE: Self = Self(0);
+  pub const OrderNew: Self = Self(1);
+  pub const OrderCancel: Self = Self(2);
+  pub const BookChecksum: Self = Self(3);
+  pub const JournalFill: Self = Self(4);
+  pub const OrderRejected: Self = Self(5);
+  pub const RiskControl: Self = Self(6);
 
-  pub fn set_action(&mut self, x: DeltaAction) {
-    let x_le = x.to_little_endian();
-    // Safety:
-    // Created from a valid Table for this object
-    // Which contains a valid value in this slot
-    unsafe {
-      core::ptr::copy_nonoverlapping(
-        &x_le as *const _ as *const u8,
-        self.0[0..].as_mut_ptr(),
-        core::mem::size_of::<<DeltaAction as EndianScalar>::Scalar>(),
-      );
+  pub const ENUM_MIN: u8 = 0;
+  pub const ENUM_MAX: u8 = 6;
+  pub const ENUM_VALUES: &'static [Self] = &[
+    Self::NONE,
+    Self::OrderNew,
+    Self::OrderCancel,
+    Self::BookChecksum,
+    Self::JournalFill,
+    Self::OrderRejected,
+    Self::RiskControl,
+  ];
+  /// Returns the variant's name or "" if unknown.
+  pub fn variant_name(self) -> Option<&'static str> {
+    match self {
+      Self::NONE => Some("NONE"),
+      Self::OrderNew => Some("OrderNew"),
+      Self::OrderCancel => Some("OrderCancel"),
+      Self::BookChecksum => Some("BookChecksum"),
+      Self::JournalFill => Some("JournalFill"),
+      Self::OrderRejected => Some("OrderRejected"),
+      Self::RiskControl => Some("RiskControl"),
+      _ => None,
     }
   }
-
-  pub fn side(&self) -> Side {
-    let mut mem = core::mem::MaybeUninit::<<Side as EndianScalar>::Scalar>::uninit();
-    // Safety:
-    // Created from a valid Table for this object
-    // Which contains a valid value in this slot
-    EndianScalar::from_little_endian(unsafe {
-      core::ptr::copy_nonoverlapping(
-        self.0[1..].as_ptr(),
-        mem.as_mut_ptr() as *mut u8,
-        core::mem::size_of::<<Side as EndianScalar>::Scalar>(),
-      );
-      mem.assume_init()
-    })
+}
+impl core::fmt::Debug for JournalRecord {
+  fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+    if let Some(name) = self.variant_name() {
+      f.write_str(name)
+    } else {
+      f.write_fmt(format_args!("<UNKNOWN {:?}>", self.0))
+    }
+  }
+}
+impl<'a> flatbuffers::Follow<'a> for JournalRecord {
+  type Inner = Self;
+  #[inline]
+  unsafe fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+    let b = unsafe { flatbuffers::read_scalar_at::<u8>(buf, loc) };
+    Self(b)
   }
+}
 
-  pub fn set_side(&mut self, x: Side) {
-    let x_le = x.to_little_endian();
-    // Safety:
-    // Created from a valid Table for this object
-    // Which contains a valid value in this slot
-    unsafe {
-      core::ptr::copy_nonoverlapping(
-        &x_le as *const _ as *const u8,
-        self.0[1..].as_mut_ptr(),
-        core::mem::size_of::<<Side as EndianScalar>::Scalar>(),
-      );
+impl flatbuffers::Push for JournalRecord {
+    type Output = JournalRecord;
+    #[inline]
+    unsafe fn push(&self, dst: &mut [u8], _written_len: usize) {
+        unsafe { flatbuffers::emplace_scalar::<u8>(dst, self.0); }
     }
+}
+
+impl flatbuffers::EndianScalar for JournalRecord {
+  type Scalar = u8;
+  #[inline]
+  fn to_little_endian(self) -> u8 {
+    self.0.to_le()
+  }
+  #[inline]
+  #[allow(clippy::wrong_self_convention)]
+  fn from_little_endian(v: u8) -> Self {
+    let b = u8::from_le(v);
+    Self(b)
   }
+}
 
-  pub fn price(&self) -> f64 {
-    let mut mem = core::mem::MaybeUninit::<<f64 as EndianScalar>::Scalar>::uninit();
-    // Safety:
-    // Created from a valid Table for this object
-    // Which contains a valid value in this slot
-    EndianScalar::from_little_endian(unsafe {
-      core::ptr::copy_nonoverlapping(
-        self.0[8..].as_ptr(),
-        mem.as_mut_ptr() as *mut u8,
-        core::mem::size_of::<<f64 as EndianScalar>::Scalar>(),
-      );
-      mem.assume_init()
-    })
+impl<'a> flatbuffers::Verifiable for JournalRecord {
+  #[inline]
+  fn run_verifier(
+    v: &mut flatbuffers::Verifier, pos: usize
+  ) -> Result<(), flatbuffers::InvalidFlatbuffer> {
+    use self::flatbuffers::Verifiable;
+    u8::run_verifier(v, pos)
   }
+}
 
-  pub fn set_price(&mut self, x: f64) {
-    let x_le = x.to_little_endian();
-    // Safety:
-    // Created from a valid Table for this object
-    // Which contains a valid value in this slot
-    unsafe {
-      core::ptr::copy_nonoverlapping(
-        &x_le as *const _ as *const u8,
-        self.0[8..].as_mut_ptr(),
-        core::mem::size_of::<<f64 as EndianScalar>::Scalar>(),
-      );
+impl flatbuffers::SimpleToVerifyInSlice for JournalRecord {}
+pub struct JournalRecordUnionTableOffset {}
+
+#[deprecated(since = "2.0.0", note = "Use associated constants instead. This will no longer be generated in 2021.")]
+pub const ENUM_MIN_EVENT_PAYLOAD: u8 = 0;
+#[deprecated(since = "2.0.0", note = "Use associated constants instead. This will no longer be generated in 2021.")]
+pub const ENUM_MAX_EVENT_PAYLOAD: u8 = 9;
+#[deprecated(since = "2.0.0", note = "Use associated constants instead. This will no longer be generated in 2021.")]
+#[allow(non_camel_case_types)]
+pub const ENUM_VALUES_EVENT_PAYLOAD: [EventPayload; 10] = [
+  EventPayload::NONE,
+  EventPayload::Fill,
+  EventPayload::OrderBookSnapshot,
+  EventPayload::OrderBookDelta,
+  EventPayload::OrderCancelled,
+  EventPayload::OrderFilled,
+  EventPayload::MarketStatus,
+  EventPayload::OrderRejected,
+  EventPayload::TradeBust,
+  EventPayload::PositionUpdate,
+];
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[repr(transparent)]
+pub struct EventPayload(pub u8);
+#[allow(non_upper_case_globals)]
+impl EventPayload {
+  pub const NONE: Self = Self(0);
+  pub const Fill: Self = Self(1);
+  pub const OrderBookSnapshot: Self = Self(2);
+  pub const OrderBookDelta: Self = Self(3);
+  pub const OrderCancelled: Self = Self(4);
+  pub const OrderFilled: Self = Self(5);
+  pub const MarketStatus: Self = Self(6);
+  pub const OrderRejected: Self = Self(7);
+  pub const TradeBust: Self = Self(8);
+  pub const PositionUpdate: Self = Self(9);
+
+  pub const ENUM_MIN: u8 = 0;
+  pub const ENUM_MAX: u8 = 9;
+  pub const ENUM_VALUES: &'static [Self] = &[
+    Self::NONE,
+    Self::Fill,
+    Self::OrderBookSnapshot,
+    Self::OrderBookDelta,
+    Self::OrderCancelled,
+    Self::OrderFilled,
+    Self::MarketStatus,
+    Self::OrderRejected,
+    Self::TradeBust,
+    Self::PositionUpdate,
+  ];
+  /// Returns the variant's name or "" if unknown.
+  pub fn variant_name(self) -> Option<&'static str> {
+    match self {
+      Self::NONE => Some("NONE"),
+      Self::Fill => Some("Fill"),
+      Self::OrderBookSnapshot => Some("OrderBookSnapshot"),
+      Self::OrderBookDelta => Some("OrderBookDelta"),
+      Self::OrderCancelled => Some("OrderCancelled"),
+      Self::OrderFilled => Some("OrderFilled"),
+      Self::MarketStatus => Some("MarketStatus"),
+      Self::OrderRejected => Some("OrderRejected"),
+      Self::TradeBust => Some("TradeBust"),
+      Self::PositionUpdate => Some("PositionUpdate"),
+      _ => None,
     }
   }
-
-  pub fn quantity(&self) -> f64 {
-    let mut mem = core::mem::MaybeUninit::<<f64 as EndianScalar>::Scalar>::uninit();
-    // Safety:
-    // Created from a valid Table for this object
-    // Which contains a valid value in this slot
-    EndianScalar::from_little_endian(unsafe {
-      core::ptr::copy_nonoverlapping(
-        self.0[16..].as_ptr(),
-        mem.as_mut_ptr() as *mut u8,
-        core::mem::size_of::<<f64 as EndianScalar>::Scalar>(),
-      );
-      mem.assume_init()
-    })
+}
+impl core::fmt::Debug for EventPayload {
+  fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+    if let Some(name) = self.variant_name() {
+      f.write_str(name)
+    } else {
+      f.write_fmt(format_args!("<UNKNOWN {:?}>", self.0))
+    }
+  }
+}
+impl<'a> flatbuffers::Follow<'a> for EventPayload {
+  type Inner = Self;
+  #[inline]
+  unsafe fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+    let b = unsafe { flatbuffers::read_scalar_at::<u8>(buf, loc) };
+    Self(b)
   }
+}
 
-  pub fn set_quantity(&mut self, x: f64) {
-    let x_le = x.to_little_endian();
-    // Safety:
-    // Created from a valid Table for this object
-    // Which contains a valid value in this slot
-    unsafe {
-      core::ptr::copy_nonoverlapping(
-        &x_le as *const _ as *const u8,
-        self.0[16..].as_mut_ptr(),
-        core::mem::size_of::<<f64 as EndianScalar>::Scalar>(),
-      );
+impl flatbuffers::Push for EventPayload {
+    type Output = EventPayload;
+    #[inline]
+    unsafe fn push(&self, dst: &mut [u8], _written_len: usize) {
+        unsafe { flatbuffers::emplace_scalar::<u8>(dst, self.0); }
     }
+}
+
+impl flatbuffers::EndianScalar for EventPayload {
+  type Scalar = u8;
+  #[inline]
+  fn to_little_endian(self) -> u8 {
+    self.0.to_le()
   }
+  #[inline]
+  #[allow(clippy::wrong_self_convention)]
+  fn from_little_endian(v: u8) -> Self {
+    let b = u8::from_le(v);
+    Self(b)
+  }
+}
 
+impl<'a> flatbuffers::Verifiable for EventPayload {
+  #[inline]
+  fn run_verifier(
+    v: &mut flatbuffers::Verifier, pos: usize
+  ) -> Result<(), flatbuffers::InvalidFlatbuffer> {
+    use self::flatbuffers::Verifiable;
+    u8::run_verifier(v, pos)
+  }
 }
 
+impl flatbuffers::SimpleToVerifyInSlice for EventPayload {}
+pub struct EventPayloadUnionTableOffset {}
+
 // struct Uuid, aligned to 8
 #[repr(transparent)]
 #[derive(Clone, Copy, PartialEq)]
@@ -726,692 +804,3254 @@ impl<'a> Uuid {
       mem.assume_init()
     })
   }
-
-  pub fn set_low(&mut self, x: u64) {
-    let x_le = x.to_little_endian();
-    // Safety:
-    // Created from a valid Table for this object
-    // Which contains a valid value in this slot
-    unsafe {
-      core::ptr::copy_nonoverlapping(
-        &x_le as *const _ as *const u8,
-        self.0[8..].as_mut_ptr(),
-        core::mem::size_of::<<u64 as EndianScalar>::Scalar>(),
-      );
+
+  pub fn set_low(&mut self, x: u64) {
+    let x_le = x.to_little_endian();
+    // Safety:
+    // Created from a valid Table for this object
+    // Which contains a valid value in this slot
+    unsafe {
+      core::ptr::copy_nonoverlapping(
+        &x_le as *const _ as *const u8,
+        self.0[8..].as_mut_ptr(),
+        core::mem::size_of::<<u64 as EndianScalar>::Scalar>(),
+      );
+    }
+  }
+
+}
+
+// struct Decimal, aligned to 8
+#[repr(transparent)]
+#[derive(Clone, Copy, PartialEq)]
+pub struct Decimal(pub [u8; 24]);
+impl Default for Decimal { 
+  fn default() -> Self { 
+    Self([0; 24])
+  }
+}
+impl core::fmt::Debug for Decimal {
+  fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+    f.debug_struct("Decimal")
+      .field("mantissa_high", &self.mantissa_high())
+      .field("mantissa_low", &self.mantissa_low())
+      .field("scale", &self.scale())
+      .finish()
+  }
+}
+
+impl flatbuffers::SimpleToVerifyInSlice for Decimal {}
+impl<'a> flatbuffers::Follow<'a> for Decimal {
+  type Inner = &'a Decimal;
+  #[inline]
+  unsafe fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+    unsafe { <&'a Decimal>::follow(buf, loc) }
+  }
+}
+impl<'a> flatbuffers::Follow<'a> for &'a Decimal {
+  type Inner = &'a Decimal;
+  #[inline]
+  unsafe fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+    unsafe { flatbuffers::follow_cast_ref::<Decimal>(buf, loc) }
+  }
+}
+impl<'b> flatbuffers::Push for Decimal {
+    type Output = Decimal;
+    #[inline]
+    unsafe fn push(&self, dst: &mut [u8], _written_len: usize) {
+        let src = unsafe { ::core::slice::from_raw_parts(self as *const Decimal as *const u8, <Self as flatbuffers::Push>::size()) };
+        dst.copy_from_slice(src);
+    }
+    #[inline]
+    fn alignment() -> flatbuffers::PushAlignment {
+        flatbuffers::PushAlignment::new(8)
+    }
+}
+
+impl<'a> flatbuffers::Verifiable for Decimal {
+  #[inline]
+  fn run_verifier(
+    v: &mut flatbuffers::Verifier, pos: usize
+  ) -> Result<(), flatbuffers::InvalidFlatbuffer> {
+    use self::flatbuffers::Verifiable;
+    v.in_buffer::<Self>(pos)
+  }
+}
+
+impl<'a> Decimal {
+  #[allow(clippy::too_many_arguments)]
+  pub fn new(
+    mantissa_high: i64,
+    mantissa_low: u64,
+    scale: u32,
+  ) -> Self {
+    let mut s = Self([0; 24]);
+    s.set_mantissa_high(mantissa_high);
+    s.set_mantissa_low(mantissa_low);
+    s.set_scale(scale);
+    s
+  }
+
+  pub fn mantissa_high(&self) -> i64 {
+    let mut mem = core::mem::MaybeUninit::<<i64 as EndianScalar>::Scalar>::uninit();
+    // Safety:
+    // Created from a valid Table for this object
+    // Which contains a valid value in this slot
+    EndianScalar::from_little_endian(unsafe {
+      core::ptr::copy_nonoverlapping(
+        self.0[0..].as_ptr(),
+        mem.as_mut_ptr() as *mut u8,
+        core::mem::size_of::<<i64 as EndianScalar>::Scalar>(),
+      );
+      mem.assume_init()
+    })
+  }
+
+  pub fn set_mantissa_high(&mut self, x: i64) {
+    let x_le = x.to_little_endian();
+    // Safety:
+    // Created from a valid Table for this object
+    // Which contains a valid value in this slot
+    unsafe {
+      core::ptr::copy_nonoverlapping(
+        &x_le as *const _ as *const u8,
+        self.0[0..].as_mut_ptr(),
+        core::mem::size_of::<<i64 as EndianScalar>::Scalar>(),
+      );
+    }
+  }
+
+  pub fn mantissa_low(&self) -> u64 {
+    let mut mem = core::mem::MaybeUninit::<<u64 as EndianScalar>::Scalar>::uninit();
+    // Safety:
+    // Created from a valid Table for this object
+    // Which contains a valid value in this slot
+    EndianScalar::from_little_endian(unsafe {
+      core::ptr::copy_nonoverlapping(
+        self.0[8..].as_ptr(),
+        mem.as_mut_ptr() as *mut u8,
+        core::mem::size_of::<<u64 as EndianScalar>::Scalar>(),
+      );
+      mem.assume_init()
+    })
+  }
+
+  pub fn set_mantissa_low(&mut self, x: u64) {
+    let x_le = x.to_little_endian();
+    // Safety:
+    // Created from a valid Table for this object
+    // Which contains a valid value in this slot
+    unsafe {
+      core::ptr::copy_nonoverlapping(
+        &x_le as *const _ as *const u8,
+        self.0[8..].as_mut_ptr(),
+        core::mem::size_of::<<u64 as EndianScalar>::Scalar>(),
+      );
+    }
+  }
+
+  pub fn scale(&self) -> u32 {
+    let mut mem = core::mem::MaybeUninit::<<u32 as EndianScalar>::Scalar>::uninit();
+    // Safety:
+    // Created from a valid Table for this object
+    // Which contains a valid value in this slot
+    EndianScalar::from_little_endian(unsafe {
+      core::ptr::copy_nonoverlapping(
+        self.0[16..].as_ptr(),
+        mem.as_mut_ptr() as *mut u8,
+        core::mem::size_of::<<u32 as EndianScalar>::Scalar>(),
+      );
+      mem.assume_init()
+    })
+  }
+
+  pub fn set_scale(&mut self, x: u32) {
+    let x_le = x.to_little_endian();
+    // Safety:
+    // Created from a valid Table for this object
+    // Which contains a valid value in this slot
+    unsafe {
+      core::ptr::copy_nonoverlapping(
+        &x_le as *const _ as *const u8,
+        self.0[16..].as_mut_ptr(),
+        core::mem::size_of::<<u32 as EndianScalar>::Scalar>(),
+      );
+    }
+  }
+
+}
+
+// struct PriceLevel, aligned to 8
+#[repr(transparent)]
+#[derive(Clone, Copy, PartialEq)]
+pub struct PriceLevel(pub [u8; 48]);
+impl Default for PriceLevel { 
+  fn default() -> Self { 
+    Self([0; 48])
+  }
+}
+impl core::fmt::Debug for PriceLevel {
+  fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+    f.debug_struct("PriceLevel")
+      .field("price", &self.price())
+      .field("quantity", &self.quantity())
+      .finish()
+  }
+}
+
+impl flatbuffers::SimpleToVerifyInSlice for PriceLevel {}
+impl<'a> flatbuffers::Follow<'a> for PriceLevel {
+  type Inner = &'a PriceLevel;
+  #[inline]
+  unsafe fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+    unsafe { <&'a PriceLevel>::follow(buf, loc) }
+  }
+}
+impl<'a> flatbuffers::Follow<'a> for &'a PriceLevel {
+  type Inner = &'a PriceLevel;
+  #[inline]
+  unsafe fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+    unsafe { flatbuffers::follow_cast_ref::<PriceLevel>(buf, loc) }
+  }
+}
+impl<'b> flatbuffers::Push for PriceLevel {
+    type Output = PriceLevel;
+    #[inline]
+    unsafe fn push(&self, dst: &mut [u8], _written_len: usize) {
+        let src = unsafe { ::core::slice::from_raw_parts(self as *const PriceLevel as *const u8, <Self as flatbuffers::Push>::size()) };
+        dst.copy_from_slice(src);
+    }
+    #[inline]
+    fn alignment() -> flatbuffers::PushAlignment {
+        flatbuffers::PushAlignment::new(8)
+    }
+}
+
+impl<'a> flatbuffers::Verifiable for PriceLevel {
+  #[inline]
+  fn run_verifier(
+    v: &mut flatbuffers::Verifier, pos: usize
+  ) -> Result<(), flatbuffers::InvalidFlatbuffer> {
+    use self::flatbuffers::Verifiable;
+    v.in_buffer::<Self>(pos)
+  }
+}
+
+impl<'a> PriceLevel {
+  #[allow(clippy::too_many_arguments)]
+  pub fn new(
+    price: &Decimal,
+    quantity: &Decimal,
+  ) -> Self {
+    let mut s = Self([0; 48]);
+    s.set_price(price);
+    s.set_quantity(quantity);
+    s
+  }
+
+  pub fn price(&self) -> &Decimal {
+    // Safety:
+    // Created from a valid Table for this object
+    // Which contains a valid struct in this slot
+    unsafe { &*(self.0[0..].as_ptr() as *const Decimal) }
+  }
+
+  #[allow(clippy::identity_op)]
+  pub fn set_price(&mut self, x: &Decimal) {
+    self.0[0..0 + 24].copy_from_slice(&x.0)
+  }
+
+  pub fn quantity(&self) -> &Decimal {
+    // Safety:
+    // Created from a valid Table for this object
+    // Which contains a valid struct in this slot
+    unsafe { &*(self.0[24..].as_ptr() as *const Decimal) }
+  }
+
+  #[allow(clippy::identity_op)]
+  pub fn set_quantity(&mut self, x: &Decimal) {
+    self.0[24..24 + 24].copy_from_slice(&x.0)
+  }
+
+}
+
+// struct LevelDelta, aligned to 8
+#[repr(transparent)]
+#[derive(Clone, Copy, PartialEq)]
+pub struct LevelDelta(pub [u8; 56]);
+impl Default for LevelDelta { 
+  fn default() -> Self { 
+    Self([0; 56])
+  }
+}
+impl core::fmt::Debug for LevelDelta {
+  fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+    f.debug_struct("LevelDelta")
+      .field("action", &self.action())
+      .field("side", &self.side())
+      .field("price", &self.price())
+      .field("quantity", &self.quantity())
+      .finish()
+  }
+}
+
+impl flatbuffers::SimpleToVerifyInSlice for LevelDelta {}
+impl<'a> flatbuffers::Follow<'a> for LevelDelta {
+  type Inner = &'a LevelDelta;
+  #[inline]
+  unsafe fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+    unsafe { <&'a LevelDelta>::follow(buf, loc) }
+  }
+}
+impl<'a> flatbuffers::Follow<'a> for &'a LevelDelta {
+  type Inner = &'a LevelDelta;
+  #[inline]
+  unsafe fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+    unsafe { flatbuffers::follow_cast_ref::<LevelDelta>(buf, loc) }
+  }
+}
+impl<'b> flatbuffers::Push for LevelDelta {
+    type Output = LevelDelta;
+    #[inline]
+    unsafe fn push(&self, dst: &mut [u8], _written_len: usize) {
+        let src = unsafe { ::core::slice::from_raw_parts(self as *const LevelDelta as *const u8, <Self as flatbuffers::Push>::size()) };
+        dst.copy_from_slice(src);
+    }
+    #[inline]
+    fn alignment() -> flatbuffers::PushAlignment {
+        flatbuffers::PushAlignment::new(8)
+    }
+}
+
+impl<'a> flatbuffers::Verifiable for LevelDelta {
+  #[inline]
+  fn run_verifier(
+    v: &mut flatbuffers::Verifier, pos: usize
+  ) -> Result<(), flatbuffers::InvalidFlatbuffer> {
+    use self::flatbuffers::Verifiable;
+    v.in_buffer::<Self>(pos)
+  }
+}
+
+impl<'a> LevelDelta {
+  #[allow(clippy::too_many_arguments)]
+  pub fn new(
+    action: DeltaAction,
+    side: Side,
+    price: &Decimal,
+    quantity: &Decimal,
+  ) -> Self {
+    let mut s = Self([0; 56]);
+    s.set_action(action);
+    s.set_side(side);
+    s.set_price(price);
+    s.set_quantity(quantity);
+    s
+  }
+
+  pub fn action(&self) -> DeltaAction {
+    let mut mem = core::mem::MaybeUninit::<<DeltaAction as EndianScalar>::Scalar>::uninit();
+    // Safety:
+    // Created from a valid Table for this object
+    // Which contains a valid value in this slot
+    EndianScalar::from_little_endian(unsafe {
+      core::ptr::copy_nonoverlapping(
+        self.0[0..].as_ptr(),
+        mem.as_mut_ptr() as *mut u8,
+        core::mem::size_of::<<DeltaAction as EndianScalar>::Scalar>(),
+      );
+      mem.assume_init()
+    })
+  }
+
+  pub fn set_action(&mut self, x: DeltaAction) {
+    let x_le = x.to_little_endian();
+    // Safety:
+    // Created from a valid Table for this object
+    // Which contains a valid value in this slot
+    unsafe {
+      core::ptr::copy_nonoverlapping(
+        &x_le as *const _ as *const u8,
+        self.0[0..].as_mut_ptr(),
+        core::mem::size_of::<<DeltaAction as EndianScalar>::Scalar>(),
+      );
+    }
+  }
+
+  pub fn side(&self) -> Side {
+    let mut mem = core::mem::MaybeUninit::<<Side as EndianScalar>::Scalar>::uninit();
+    // Safety:
+    // Created from a valid Table for this object
+    // Which contains a valid value in this slot
+    EndianScalar::from_little_endian(unsafe {
+      core::ptr::copy_nonoverlapping(
+        self.0[1..].as_ptr(),
+        mem.as_mut_ptr() as *mut u8,
+        core::mem::size_of::<<Side as EndianScalar>::Scalar>(),
+      );
+      mem.assume_init()
+    })
+  }
+
+  pub fn set_side(&mut self, x: Side) {
+    let x_le = x.to_little_endian();
+    // Safety:
+    // Created from a valid Table for this object
+    // Which contains a valid value in this slot
+    unsafe {
+      core::ptr::copy_nonoverlapping(
+        &x_le as *const _ as *const u8,
+        self.0[1..].as_mut_ptr(),
+        core::mem::size_of::<<Side as EndianScalar>::Scalar>(),
+      );
+    }
+  }
+
+  pub fn price(&self) -> &Decimal {
+    // Safety:
+    // Created from a valid Table for this object
+    // Which contains a valid struct in this slot
+    unsafe { &*(self.0[8..].as_ptr() as *const Decimal) }
+  }
+
+  #[allow(clippy::identity_op)]
+  pub fn set_price(&mut self, x: &Decimal) {
+    self.0[8..8 + 24].copy_from_slice(&x.0)
+  }
+
+  pub fn quantity(&self) -> &Decimal {
+    // Safety:
+    // Created from a valid Table for this object
+    // Which contains a valid struct in this slot
+    unsafe { &*(self.0[32..].as_ptr() as *const Decimal) }
+  }
+
+  #[allow(clippy::identity_op)]
+  pub fn set_quantity(&mut self, x: &Decimal) {
+    self.0[32..32 + 24].copy_from_slice(&x.0)
+  }
+
+}
+
+pub enum FillOffset {}
+#[derive(Copy, Clone, PartialEq)]
+
+pub struct Fill<'a> {
+  pub _tab: flatbuffers::Table<'a>,
+}
+
+impl<'a> flatbuffers::Follow<'a> for Fill<'a> {
+  type Inner = Fill<'a>;
+  #[inline]
+  unsafe fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+    Self { _tab: unsafe { flatbuffers::Table::new(buf, loc) } }
+  }
+}
+
+impl<'a> Fill<'a> {
+  pub const VT_SYMBOL: flatbuffers::VOffsetT = 4;
+  pub const VT_BUY_ORDER_ID: flatbuffers::VOffsetT = 6;
+  pub const VT_SELL_ORDER_ID: flatbuffers::VOffsetT = 8;
+  pub const VT_PRICE: flatbuffers::VOffsetT = 10;
+  pub const VT_QUANTITY: flatbuffers::VOffsetT = 12;
+  pub const VT_TIMESTAMP: flatbuffers::VOffsetT = 14;
+
+  #[inline]
+  pub unsafe fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
+    Fill { _tab: table }
+  }
+  #[allow(unused_mut)]
+  pub fn create<'bldr: 'args, 'args: 'mut_bldr, 'mut_bldr, A: flatbuffers::Allocator + 'bldr>(
+    _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr, A>,
+    args: &'args FillArgs<'args>
+  ) -> flatbuffers::WIPOffset<Fill<'bldr>> {
+    let mut builder = FillBuilder::new(_fbb);
+    builder.add_timestamp(args.timestamp);
+    if let Some(x) = args.quantity { builder.add_quantity(x); }
+    if let Some(x) = args.price { builder.add_price(x); }
+    if let Some(x) = args.sell_order_id { builder.add_sell_order_id(x); }
+    if let Some(x) = args.buy_order_id { builder.add_buy_order_id(x); }
+    if let Some(x) = args.symbol { builder.add_symbol(x); }
+    builder.finish()
+  }
+
+
+  #[inline]
+  pub fn symbol(&self) -> Option<&'a str> {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<flatbuffers::ForwardsUOffset<&str>>(Fill::VT_SYMBOL, None)}
+  }
+  #[inline]
+  pub fn buy_order_id(&self) -> Option<&'a Uuid> {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<Uuid>(Fill::VT_BUY_ORDER_ID, None)}
+  }
+  #[inline]
+  pub fn sell_order_id(&self) -> Option<&'a Uuid> {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<Uuid>(Fill::VT_SELL_ORDER_ID, None)}
+  }
+  #[inline]
+  pub fn price(&self) -> Option<&'a Decimal> {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<Decimal>(Fill::VT_PRICE, None)}
+  }
+  #[inline]
+  pub fn quantity(&self) -> Option<&'a Decimal> {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<Decimal>(Fill::VT_QUANTITY, None)}
+  }
+  #[inline]
+  pub fn timestamp(&self) -> u64 {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<u64>(Fill::VT_TIMESTAMP, Some(0)).unwrap()}
+  }
+}
+
+impl flatbuffers::Verifiable for Fill<'_> {
+  #[inline]
+  fn run_verifier(
+    v: &mut flatbuffers::Verifier, pos: usize
+  ) -> Result<(), flatbuffers::InvalidFlatbuffer> {
+    use self::flatbuffers::Verifiable;
+    v.visit_table(pos)?
+     .visit_field::<flatbuffers::ForwardsUOffset<&str>>("symbol", Self::VT_SYMBOL, false)?
+     .visit_field::<Uuid>("buy_order_id", Self::VT_BUY_ORDER_ID, false)?
+     .visit_field::<Uuid>("sell_order_id", Self::VT_SELL_ORDER_ID, false)?
+     .visit_field::<Decimal>("price", Self::VT_PRICE, false)?
+     .visit_field::<Decimal>("quantity", Self::VT_QUANTITY, false)?
+     .visit_field::<u64>("timestamp", Self::VT_TIMESTAMP, false)?
+     .finish();
+    Ok(())
+  }
+}
+pub struct FillArgs<'a> {
+    pub symbol: Option<flatbuffers::WIPOffset<&'a str>>,
+    pub buy_order_id: Option<&'a Uuid>,
+    pub sell_order_id: Option<&'a Uuid>,
+    pub price: Option<&'a Decimal>,
+    pub quantity: Option<&'a Decimal>,
+    pub timestamp: u64,
+}
+impl<'a> Default for FillArgs<'a> {
+  #[inline]
+  fn default() -> Self {
+    FillArgs {
+      symbol: None,
+      buy_order_id: None,
+      sell_order_id: None,
+      price: None,
+      quantity: None,
+      timestamp: 0,
+    }
+  }
+}
+
+pub struct FillBuilder<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> {
+  fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a, A>,
+  start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
+}
+impl<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> FillBuilder<'a, 'b, A> {
+  #[inline]
+  pub fn add_symbol(&mut self, symbol: flatbuffers::WIPOffset<&'b  str>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(Fill::VT_SYMBOL, symbol);
+  }
+  #[inline]
+  pub fn add_buy_order_id(&mut self, buy_order_id: &Uuid) {
+    self.fbb_.push_slot_always::<&Uuid>(Fill::VT_BUY_ORDER_ID, buy_order_id);
+  }
+  #[inline]
+  pub fn add_sell_order_id(&mut self, sell_order_id: &Uuid) {
+    self.fbb_.push_slot_always::<&Uuid>(Fill::VT_SELL_ORDER_ID, sell_order_id);
+  }
+  #[inline]
+  pub fn add_price(&mut self, price: &Decimal) {
+    self.fbb_.push_slot_always::<&Decimal>(Fill::VT_PRICE, price);
+  }
+  #[inline]
+  pub fn add_quantity(&mut self, quantity: &Decimal) {
+    self.fbb_.push_slot_always::<&Decimal>(Fill::VT_QUANTITY, quantity);
+  }
+  #[inline]
+  pub fn add_timestamp(&mut self, timestamp: u64) {
+    self.fbb_.push_slot::<u64>(Fill::VT_TIMESTAMP, timestamp, 0);
+  }
+  #[inline]
+  pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a, A>) -> FillBuilder<'a, 'b, A> {
+    let start = _fbb.start_table();
+    FillBuilder {
+      fbb_: _fbb,
+      start_: start,
+    }
+  }
+  #[inline]
+  pub fn finish(self) -> flatbuffers::WIPOffset<Fill<'a>> {
+    let o = self.fbb_.end_table(self.start_);
+    flatbuffers::WIPOffset::new(o.value())
+  }
+}
+
+impl core::fmt::Debug for Fill<'_> {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    let mut ds = f.debug_struct("Fill");
+      ds.field("symbol", &self.symbol());
+      ds.field("buy_order_id", &self.buy_order_id());
+      ds.field("sell_order_id", &self.sell_order_id());
+      ds.field("price", &self.price());
+      ds.field("quantity", &self.quantity());
+      ds.field("timestamp", &self.timestamp());
+      ds.finish()
+  }
+}
+pub enum OrderBookSnapshotOffset {}
+#[derive(Copy, Clone, PartialEq)]
+
+pub struct OrderBookSnapshot<'a> {
+  pub _tab: flatbuffers::Table<'a>,
+}
+
+impl<'a> flatbuffers::Follow<'a> for OrderBookSnapshot<'a> {
+  type Inner = OrderBookSnapshot<'a>;
+  #[inline]
+  unsafe fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+    Self { _tab: unsafe { flatbuffers::Table::new(buf, loc) } }
+  }
+}
+
+impl<'a> OrderBookSnapshot<'a> {
+  pub const VT_SYMBOL: flatbuffers::VOffsetT = 4;
+  pub const VT_SEQUENCE: flatbuffers::VOffsetT = 6;
+  pub const VT_BIDS: flatbuffers::VOffsetT = 8;
+  pub const VT_ASKS: flatbuffers::VOffsetT = 10;
+
+  #[inline]
+  pub unsafe fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
+    OrderBookSnapshot { _tab: table }
+  }
+  #[allow(unused_mut)]
+  pub fn create<'bldr: 'args, 'args: 'mut_bldr, 'mut_bldr, A: flatbuffers::Allocator + 'bldr>(
+    _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr, A>,
+    args: &'args OrderBookSnapshotArgs<'args>
+  ) -> flatbuffers::WIPOffset<OrderBookSnapshot<'bldr>> {
+    let mut builder = OrderBookSnapshotBuilder::new(_fbb);
+    builder.add_sequence(args.sequence);
+    if let Some(x) = args.asks { builder.add_asks(x); }
+    if let Some(x) = args.bids { builder.add_bids(x); }
+    if let Some(x) = args.symbol { builder.add_symbol(x); }
+    builder.finish()
+  }
+
+
+  #[inline]
+  pub fn symbol(&self) -> Option<&'a str> {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<flatbuffers::ForwardsUOffset<&str>>(OrderBookSnapshot::VT_SYMBOL, None)}
+  }
+  #[inline]
+  pub fn sequence(&self) -> u64 {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<u64>(OrderBookSnapshot::VT_SEQUENCE, Some(0)).unwrap()}
+  }
+  #[inline]
+  pub fn bids(&self) -> Option<flatbuffers::Vector<'a, PriceLevel>> {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<'a, PriceLevel>>>(OrderBookSnapshot::VT_BIDS, None)}
+  }
+  #[inline]
+  pub fn asks(&self) -> Option<flatbuffers::Vector<'a, PriceLevel>> {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<'a, PriceLevel>>>(OrderBookSnapshot::VT_ASKS, None)}
+  }
+}
+
+impl flatbuffers::Verifiable for OrderBookSnapshot<'_> {
+  #[inline]
+  fn run_verifier(
+    v: &mut flatbuffers::Verifier, pos: usize
+  ) -> Result<(), flatbuffers::InvalidFlatbuffer> {
+    use self::flatbuffers::Verifiable;
+    v.visit_table(pos)?
+     .visit_field::<flatbuffers::ForwardsUOffset<&str>>("symbol", Self::VT_SYMBOL, false)?
+     .visit_field::<u64>("sequence", Self::VT_SEQUENCE, false)?
+     .visit_field::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<'_, PriceLevel>>>("bids", Self::VT_BIDS, false)?
+     .visit_field::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<'_, PriceLevel>>>("asks", Self::VT_ASKS, false)?
+     .finish();
+    Ok(())
+  }
+}
+pub struct OrderBookSnapshotArgs<'a> {
+    pub symbol: Option<flatbuffers::WIPOffset<&'a str>>,
+    pub sequence: u64,
+    pub bids: Option<flatbuffers::WIPOffset<flatbuffers::Vector<'a, PriceLevel>>>,
+    pub asks: Option<flatbuffers::WIPOffset<flatbuffers::Vector<'a, PriceLevel>>>,
+}
+impl<'a> Default for OrderBookSnapshotArgs<'a> {
+  #[inline]
+  fn default() -> Self {
+    OrderBookSnapshotArgs {
+      symbol: None,
+      sequence: 0,
+      bids: None,
+      asks: None,
+    }
+  }
+}
+
+pub struct OrderBookSnapshotBuilder<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> {
+  fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a, A>,
+  start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
+}
+impl<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> OrderBookSnapshotBuilder<'a, 'b, A> {
+  #[inline]
+  pub fn add_symbol(&mut self, symbol: flatbuffers::WIPOffset<&'b  str>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(OrderBookSnapshot::VT_SYMBOL, symbol);
+  }
+  #[inline]
+  pub fn add_sequence(&mut self, sequence: u64) {
+    self.fbb_.push_slot::<u64>(OrderBookSnapshot::VT_SEQUENCE, sequence, 0);
+  }
+  #[inline]
+  pub fn add_bids(&mut self, bids: flatbuffers::WIPOffset<flatbuffers::Vector<'b , PriceLevel>>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(OrderBookSnapshot::VT_BIDS, bids);
+  }
+  #[inline]
+  pub fn add_asks(&mut self, asks: flatbuffers::WIPOffset<flatbuffers::Vector<'b , PriceLevel>>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(OrderBookSnapshot::VT_ASKS, asks);
+  }
+  #[inline]
+  pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a, A>) -> OrderBookSnapshotBuilder<'a, 'b, A> {
+    let start = _fbb.start_table();
+    OrderBookSnapshotBuilder {
+      fbb_: _fbb,
+      start_: start,
+    }
+  }
+  #[inline]
+  pub fn finish(self) -> flatbuffers::WIPOffset<OrderBookSnapshot<'a>> {
+    let o = self.fbb_.end_table(self.start_);
+    flatbuffers::WIPOffset::new(o.value())
+  }
+}
+
+impl core::fmt::Debug for OrderBookSnapshot<'_> {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    let mut ds = f.debug_struct("OrderBookSnapshot");
+      ds.field("symbol", &self.symbol());
+      ds.field("sequence", &self.sequence());
+      ds.field("bids", &self.bids());
+      ds.field("asks", &self.asks());
+      ds.finish()
+  }
+}
+pub enum OrderBookDeltaOffset {}
+#[derive(Copy, Clone, PartialEq)]
+
+pub struct OrderBookDelta<'a> {
+  pub _tab: flatbuffers::Table<'a>,
+}
+
+impl<'a> flatbuffers::Follow<'a> for OrderBookDelta<'a> {
+  type Inner = OrderBookDelta<'a>;
+  #[inline]
+  unsafe fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+    Self { _tab: unsafe { flatbuffers::Table::new(buf, loc) } }
+  }
+}
+
+impl<'a> OrderBookDelta<'a> {
+  pub const VT_SYMBOL: flatbuffers::VOffsetT = 4;
+  pub const VT_SEQUENCE: flatbuffers::VOffsetT = 6;
+  pub const VT_DELTAS: flatbuffers::VOffsetT = 8;
+
+  #[inline]
+  pub unsafe fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
+    OrderBookDelta { _tab: table }
+  }
+  #[allow(unused_mut)]
+  pub fn create<'bldr: 'args, 'args: 'mut_bldr, 'mut_bldr, A: flatbuffers::Allocator + 'bldr>(
+    _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr, A>,
+    args: &'args OrderBookDeltaArgs<'args>
+  ) -> flatbuffers::WIPOffset<OrderBookDelta<'bldr>> {
+    let mut builder = OrderBookDeltaBuilder::new(_fbb);
+    builder.add_sequence(args.sequence);
+    if let Some(x) = args.deltas { builder.add_deltas(x); }
+    if let Some(x) = args.symbol { builder.add_symbol(x); }
+    builder.finish()
+  }
+
+
+  #[inline]
+  pub fn symbol(&self) -> Option<&'a str> {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<flatbuffers::ForwardsUOffset<&str>>(OrderBookDelta::VT_SYMBOL, None)}
+  }
+  #[inline]
+  pub fn sequence(&self) -> u64 {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<u64>(OrderBookDelta::VT_SEQUENCE, Some(0)).unwrap()}
+  }
+  #[inline]
+  pub fn deltas(&self) -> Option<flatbuffers::Vector<'a, LevelDelta>> {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<'a, LevelDelta>>>(OrderBookDelta::VT_DELTAS, None)}
+  }
+}
+
+impl flatbuffers::Verifiable for OrderBookDelta<'_> {
+  #[inline]
+  fn run_verifier(
+    v: &mut flatbuffers::Verifier, pos: usize
+  ) -> Result<(), flatbuffers::InvalidFlatbuffer> {
+    use self::flatbuffers::Verifiable;
+    v.visit_table(pos)?
+     .visit_field::<flatbuffers::ForwardsUOffset<&str>>("symbol", Self::VT_SYMBOL, false)?
+     .visit_field::<u64>("sequence", Self::VT_SEQUENCE, false)?
+     .visit_field::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<'_, LevelDelta>>>("deltas", Self::VT_DELTAS, false)?
+     .finish();
+    Ok(())
+  }
+}
+pub struct OrderBookDeltaArgs<'a> {
+    pub symbol: Option<flatbuffers::WIPOffset<&'a str>>,
+    pub sequence: u64,
+    pub deltas: Option<flatbuffers::WIPOffset<flatbuffers::Vector<'a, LevelDelta>>>,
+}
+impl<'a> Default for OrderBookDeltaArgs<'a> {
+  #[inline]
+  fn default() -> Self {
+    OrderBookDeltaArgs {
+      symbol: None,
+      sequence: 0,
+      deltas: None,
+    }
+  }
+}
+
+pub struct OrderBookDeltaBuilder<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> {
+  fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a, A>,
+  start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
+}
+impl<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> OrderBookDeltaBuilder<'a, 'b, A> {
+  #[inline]
+  pub fn add_symbol(&mut self, symbol: flatbuffers::WIPOffset<&'b  str>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(OrderBookDelta::VT_SYMBOL, symbol);
+  }
+  #[inline]
+  pub fn add_sequence(&mut self, sequence: u64) {
+    self.fbb_.push_slot::<u64>(OrderBookDelta::VT_SEQUENCE, sequence, 0);
+  }
+  #[inline]
+  pub fn add_deltas(&mut self, deltas: flatbuffers::WIPOffset<flatbuffers::Vector<'b , LevelDelta>>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(OrderBookDelta::VT_DELTAS, deltas);
+  }
+  #[inline]
+  pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a, A>) -> OrderBookDeltaBuilder<'a, 'b, A> {
+    let start = _fbb.start_table();
+    OrderBookDeltaBuilder {
+      fbb_: _fbb,
+      start_: start,
+    }
+  }
+  #[inline]
+  pub fn finish(self) -> flatbuffers::WIPOffset<OrderBookDelta<'a>> {
+    let o = self.fbb_.end_table(self.start_);
+    flatbuffers::WIPOffset::new(o.value())
+  }
+}
+
+impl core::fmt::Debug for OrderBookDelta<'_> {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    let mut ds = f.debug_struct("OrderBookDelta");
+      ds.field("symbol", &self.symbol());
+      ds.field("sequence", &self.sequence());
+      ds.field("deltas", &self.deltas());
+      ds.finish()
+  }
+}
+pub enum OrderCancelledOffset {}
+#[derive(Copy, Clone, PartialEq)]
+
+pub struct OrderCancelled<'a> {
+  pub _tab: flatbuffers::Table<'a>,
+}
+
+impl<'a> flatbuffers::Follow<'a> for OrderCancelled<'a> {
+  type Inner = OrderCancelled<'a>;
+  #[inline]
+  unsafe fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+    Self { _tab: unsafe { flatbuffers::Table::new(buf, loc) } }
+  }
+}
+
+impl<'a> OrderCancelled<'a> {
+  pub const VT_ORDER_ID: flatbuffers::VOffsetT = 4;
+  pub const VT_FILLED_QUANTITY: flatbuffers::VOffsetT = 6;
+
+  #[inline]
+  pub unsafe fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
+    OrderCancelled { _tab: table }
+  }
+  #[allow(unused_mut)]
+  pub fn create<'bldr: 'args, 'args: 'mut_bldr, 'mut_bldr, A: flatbuffers::Allocator + 'bldr>(
+    _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr, A>,
+    args: &'args OrderCancelledArgs<'args>
+  ) -> flatbuffers::WIPOffset<OrderCancelled<'bldr>> {
+    let mut builder = OrderCancelledBuilder::new(_fbb);
+    if let Some(x) = args.filled_quantity { builder.add_filled_quantity(x); }
+    if let Some(x) = args.order_id { builder.add_order_id(x); }
+    builder.finish()
+  }
+
+
+  #[inline]
+  pub fn order_id(&self) -> Option<&'a Uuid> {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<Uuid>(OrderCancelled::VT_ORDER_ID, None)}
+  }
+  #[inline]
+  pub fn filled_quantity(&self) -> Option<&'a Decimal> {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<Decimal>(OrderCancelled::VT_FILLED_QUANTITY, None)}
+  }
+}
+
+impl flatbuffers::Verifiable for OrderCancelled<'_> {
+  #[inline]
+  fn run_verifier(
+    v: &mut flatbuffers::Verifier, pos: usize
+  ) -> Result<(), flatbuffers::InvalidFlatbuffer> {
+    use self::flatbuffers::Verifiable;
+    v.visit_table(pos)?
+     .visit_field::<Uuid>("order_id", Self::VT_ORDER_ID, false)?
+     .visit_field::<Decimal>("filled_quantity", Self::VT_FILLED_QUANTITY, false)?
+     .finish();
+    Ok(())
+  }
+}
+pub struct OrderCancelledArgs<'a> {
+    pub order_id: Option<&'a Uuid>,
+    pub filled_quantity: Option<&'a Decimal>,
+}
+impl<'a> Default for OrderCancelledArgs<'a> {
+  #[inline]
+  fn default() -> Self {
+    OrderCancelledArgs {
+      order_id: None,
+      filled_quantity: None,
+    }
+  }
+}
+
+pub struct OrderCancelledBuilder<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> {
+  fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a, A>,
+  start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
+}
+impl<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> OrderCancelledBuilder<'a, 'b, A> {
+  #[inline]
+  pub fn add_order_id(&mut self, order_id: &Uuid) {
+    self.fbb_.push_slot_always::<&Uuid>(OrderCancelled::VT_ORDER_ID, order_id);
+  }
+  #[inline]
+  pub fn add_filled_quantity(&mut self, filled_quantity: &Decimal) {
+    self.fbb_.push_slot_always::<&Decimal>(OrderCancelled::VT_FILLED_QUANTITY, filled_quantity);
+  }
+  #[inline]
+  pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a, A>) -> OrderCancelledBuilder<'a, 'b, A> {
+    let start = _fbb.start_table();
+    OrderCancelledBuilder {
+      fbb_: _fbb,
+      start_: start,
+    }
+  }
+  #[inline]
+  pub fn finish(self) -> flatbuffers::WIPOffset<OrderCancelled<'a>> {
+    let o = self.fbb_.end_table(self.start_);
+    flatbuffers::WIPOffset::new(o.value())
+  }
+}
+
+impl core::fmt::Debug for OrderCancelled<'_> {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    let mut ds = f.debug_struct("OrderCancelled");
+      ds.field("order_id", &self.order_id());
+      ds.field("filled_quantity", &self.filled_quantity());
+      ds.finish()
+  }
+}
+pub enum OrderRejectedOffset {}
+#[derive(Copy, Clone, PartialEq)]
+
+pub struct OrderRejected<'a> {
+  pub _tab: flatbuffers::Table<'a>,
+}
+
+impl<'a> flatbuffers::Follow<'a> for OrderRejected<'a> {
+  type Inner = OrderRejected<'a>;
+  #[inline]
+  unsafe fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+    Self { _tab: unsafe { flatbuffers::Table::new(buf, loc) } }
+  }
+}
+
+impl<'a> OrderRejected<'a> {
+  pub const VT_ORDER_ID: flatbuffers::VOffsetT = 4;
+  pub const VT_REASON: flatbuffers::VOffsetT = 6;
+
+  #[inline]
+  pub unsafe fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
+    OrderRejected { _tab: table }
+  }
+  #[allow(unused_mut)]
+  pub fn create<'bldr: 'args, 'args: 'mut_bldr, 'mut_bldr, A: flatbuffers::Allocator + 'bldr>(
+    _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr, A>,
+    args: &'args OrderRejectedArgs<'args>
+  ) -> flatbuffers::WIPOffset<OrderRejected<'bldr>> {
+    let mut builder = OrderRejectedBuilder::new(_fbb);
+    if let Some(x) = args.reason { builder.add_reason(x); }
+    if let Some(x) = args.order_id { builder.add_order_id(x); }
+    builder.finish()
+  }
+
+
+  #[inline]
+  pub fn order_id(&self) -> Option<&'a Uuid> {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<Uuid>(OrderRejected::VT_ORDER_ID, None)}
+  }
+  #[inline]
+  pub fn reason(&self) -> Option<&'a str> {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<flatbuffers::ForwardsUOffset<&str>>(OrderRejected::VT_REASON, None)}
+  }
+}
+
+impl flatbuffers::Verifiable for OrderRejected<'_> {
+  #[inline]
+  fn run_verifier(
+    v: &mut flatbuffers::Verifier, pos: usize
+  ) -> Result<(), flatbuffers::InvalidFlatbuffer> {
+    use self::flatbuffers::Verifiable;
+    v.visit_table(pos)?
+     .visit_field::<Uuid>("order_id", Self::VT_ORDER_ID, false)?
+     .visit_field::<flatbuffers::ForwardsUOffset<&str>>("reason", Self::VT_REASON, false)?
+     .finish();
+    Ok(())
+  }
+}
+pub struct OrderRejectedArgs<'a> {
+    pub order_id: Option<&'a Uuid>,
+    pub reason: Option<flatbuffers::WIPOffset<&'a str>>,
+}
+impl<'a> Default for OrderRejectedArgs<'a> {
+  #[inline]
+  fn default() -> Self {
+    OrderRejectedArgs {
+      order_id: None,
+      reason: None,
+    }
+  }
+}
+
+pub struct OrderRejectedBuilder<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> {
+  fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a, A>,
+  start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
+}
+impl<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> OrderRejectedBuilder<'a, 'b, A> {
+  #[inline]
+  pub fn add_order_id(&mut self, order_id: &Uuid) {
+    self.fbb_.push_slot_always::<&Uuid>(OrderRejected::VT_ORDER_ID, order_id);
+  }
+  #[inline]
+  pub fn add_reason(&mut self, reason: flatbuffers::WIPOffset<&'b  str>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(OrderRejected::VT_REASON, reason);
+  }
+  #[inline]
+  pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a, A>) -> OrderRejectedBuilder<'a, 'b, A> {
+    let start = _fbb.start_table();
+    OrderRejectedBuilder {
+      fbb_: _fbb,
+      start_: start,
+    }
+  }
+  #[inline]
+  pub fn finish(self) -> flatbuffers::WIPOffset<OrderRejected<'a>> {
+    let o = self.fbb_.end_table(self.start_);
+    flatbuffers::WIPOffset::new(o.value())
+  }
+}
+
+impl core::fmt::Debug for OrderRejected<'_> {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    let mut ds = f.debug_struct("OrderRejected");
+      ds.field("order_id", &self.order_id());
+      ds.field("reason", &self.reason());
+      ds.finish()
+  }
+}
+pub enum OrderFilledOffset {}
+#[derive(Copy, Clone, PartialEq)]
+
+pub struct OrderFilled<'a> {
+  pub _tab: flatbuffers::Table<'a>,
+}
+
+impl<'a> flatbuffers::Follow<'a> for OrderFilled<'a> {
+  type Inner = OrderFilled<'a>;
+  #[inline]
+  unsafe fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+    Self { _tab: unsafe { flatbuffers::Table::new(buf, loc) } }
+  }
+}
+
+impl<'a> OrderFilled<'a> {
+  pub const VT_ORDER_ID: flatbuffers::VOffsetT = 4;
+
+  #[inline]
+  pub unsafe fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
+    OrderFilled { _tab: table }
+  }
+  #[allow(unused_mut)]
+  pub fn create<'bldr: 'args, 'args: 'mut_bldr, 'mut_bldr, A: flatbuffers::Allocator + 'bldr>(
+    _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr, A>,
+    args: &'args OrderFilledArgs<'args>
+  ) -> flatbuffers::WIPOffset<OrderFilled<'bldr>> {
+    let mut builder = OrderFilledBuilder::new(_fbb);
+    if let Some(x) = args.order_id { builder.add_order_id(x); }
+    builder.finish()
+  }
+
+
+  #[inline]
+  pub fn order_id(&self) -> Option<&'a Uuid> {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<Uuid>(OrderFilled::VT_ORDER_ID, None)}
+  }
+}
+
+impl flatbuffers::Verifiable for OrderFilled<'_> {
+  #[inline]
+  fn run_verifier(
+    v: &mut flatbuffers::Verifier, pos: usize
+  ) -> Result<(), flatbuffers::InvalidFlatbuffer> {
+    use self::flatbuffers::Verifiable;
+    v.visit_table(pos)?
+     .visit_field::<Uuid>("order_id", Self::VT_ORDER_ID, false)?
+     .finish();
+    Ok(())
+  }
+}
+pub struct OrderFilledArgs<'a> {
+    pub order_id: Option<&'a Uuid>,
+}
+impl<'a> Default for OrderFilledArgs<'a> {
+  #[inline]
+  fn default() -> Self {
+    OrderFilledArgs {
+      order_id: None,
+    }
+  }
+}
+
+pub struct OrderFilledBuilder<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> {
+  fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a, A>,
+  start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
+}
+impl<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> OrderFilledBuilder<'a, 'b, A> {
+  #[inline]
+  pub fn add_order_id(&mut self, order_id: &Uuid) {
+    self.fbb_.push_slot_always::<&Uuid>(OrderFilled::VT_ORDER_ID, order_id);
+  }
+  #[inline]
+  pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a, A>) -> OrderFilledBuilder<'a, 'b, A> {
+    let start = _fbb.start_table();
+    OrderFilledBuilder {
+      fbb_: _fbb,
+      start_: start,
+    }
+  }
+  #[inline]
+  pub fn finish(self) -> flatbuffers::WIPOffset<OrderFilled<'a>> {
+    let o = self.fbb_.end_table(self.start_);
+    flatbuffers::WIPOffset::new(o.value())
+  }
+}
+
+impl core::fmt::Debug for OrderFilled<'_> {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    let mut ds = f.debug_struct("OrderFilled");
+      ds.field("order_id", &self.order_id());
+      ds.finish()
+  }
+}
+pub enum MarketStatusOffset {}
+#[derive(Copy, Clone, PartialEq)]
+
+pub struct MarketStatus<'a> {
+  pub _tab: flatbuffers::Table<'a>,
+}
+
+impl<'a> flatbuffers::Follow<'a> for MarketStatus<'a> {
+  type Inner = MarketStatus<'a>;
+  #[inline]
+  unsafe fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+    Self { _tab: unsafe { flatbuffers::Table::new(buf, loc) } }
+  }
+}
+
+impl<'a> MarketStatus<'a> {
+  pub const VT_SYMBOL: flatbuffers::VOffsetT = 4;
+  pub const VT_PHASE: flatbuffers::VOffsetT = 6;
+  pub const VT_REASON: flatbuffers::VOffsetT = 8;
+  pub const VT_TIMESTAMP: flatbuffers::VOffsetT = 10;
+
+  #[inline]
+  pub unsafe fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
+    MarketStatus { _tab: table }
+  }
+  #[allow(unused_mut)]
+  pub fn create<'bldr: 'args, 'args: 'mut_bldr, 'mut_bldr, A: flatbuffers::Allocator + 'bldr>(
+    _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr, A>,
+    args: &'args MarketStatusArgs<'args>
+  ) -> flatbuffers::WIPOffset<MarketStatus<'bldr>> {
+    let mut builder = MarketStatusBuilder::new(_fbb);
+    builder.add_timestamp(args.timestamp);
+    if let Some(x) = args.reason { builder.add_reason(x); }
+    if let Some(x) = args.symbol { builder.add_symbol(x); }
+    builder.add_phase(args.phase);
+    builder.finish()
+  }
+
+
+  #[inline]
+  pub fn symbol(&self) -> Option<&'a str> {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<flatbuffers::ForwardsUOffset<&str>>(MarketStatus::VT_SYMBOL, None)}
+  }
+  #[inline]
+  pub fn phase(&self) -> MarketPhase {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<MarketPhase>(MarketStatus::VT_PHASE, Some(MarketPhase::Open)).unwrap()}
+  }
+  #[inline]
+  pub fn reason(&self) -> Option<&'a str> {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<flatbuffers::ForwardsUOffset<&str>>(MarketStatus::VT_REASON, None)}
+  }
+  #[inline]
+  pub fn timestamp(&self) -> u64 {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<u64>(MarketStatus::VT_TIMESTAMP, Some(0)).unwrap()}
+  }
+}
+
+impl flatbuffers::Verifiable for MarketStatus<'_> {
+  #[inline]
+  fn run_verifier(
+    v: &mut flatbuffers::Verifier, pos: usize
+  ) -> Result<(), flatbuffers::InvalidFlatbuffer> {
+    use self::flatbuffers::Verifiable;
+    v.visit_table(pos)?
+     .visit_field::<flatbuffers::ForwardsUOffset<&str>>("symbol", Self::VT_SYMBOL, false)?
+     .visit_field::<MarketPhase>("phase", Self::VT_PHASE, false)?
+     .visit_field::<flatbuffers::ForwardsUOffset<&str>>("reason", Self::VT_REASON, false)?
+     .visit_field::<u64>("timestamp", Self::VT_TIMESTAMP, false)?
+     .finish();
+    Ok(())
+  }
+}
+pub struct MarketStatusArgs<'a> {
+    pub symbol: Option<flatbuffers::WIPOffset<&'a str>>,
+    pub phase: MarketPhase,
+    pub reason: Option<flatbuffers::WIPOffset<&'a str>>,
+    pub timestamp: u64,
+}
+impl<'a> Default for MarketStatusArgs<'a> {
+  #[inline]
+  fn default() -> Self {
+    MarketStatusArgs {
+      symbol: None,
+      phase: MarketPhase::Open,
+      reason: None,
+      timestamp: 0,
+    }
+  }
+}
+
+pub struct MarketStatusBuilder<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> {
+  fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a, A>,
+  start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
+}
+impl<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> MarketStatusBuilder<'a, 'b, A> {
+  #[inline]
+  pub fn add_symbol(&mut self, symbol: flatbuffers::WIPOffset<&'b  str>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(MarketStatus::VT_SYMBOL, symbol);
+  }
+  #[inline]
+  pub fn add_phase(&mut self, phase: MarketPhase) {
+    self.fbb_.push_slot::<MarketPhase>(MarketStatus::VT_PHASE, phase, MarketPhase::Open);
+  }
+  #[inline]
+  pub fn add_reason(&mut self, reason: flatbuffers::WIPOffset<&'b  str>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(MarketStatus::VT_REASON, reason);
+  }
+  #[inline]
+  pub fn add_timestamp(&mut self, timestamp: u64) {
+    self.fbb_.push_slot::<u64>(MarketStatus::VT_TIMESTAMP, timestamp, 0);
+  }
+  #[inline]
+  pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a, A>) -> MarketStatusBuilder<'a, 'b, A> {
+    let start = _fbb.start_table();
+    MarketStatusBuilder {
+      fbb_: _fbb,
+      start_: start,
+    }
+  }
+  #[inline]
+  pub fn finish(self) -> flatbuffers::WIPOffset<MarketStatus<'a>> {
+    let o = self.fbb_.end_table(self.start_);
+    flatbuffers::WIPOffset::new(o.value())
+  }
+}
+
+impl core::fmt::Debug for MarketStatus<'_> {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    let mut ds = f.debug_struct("MarketStatus");
+      ds.field("symbol", &self.symbol());
+      ds.field("phase", &self.phase());
+      ds.field("reason", &self.reason());
+      ds.field("timestamp", &self.timestamp());
+      ds.finish()
+  }
+}
+pub enum TradeBustOffset {}
+#[derive(Copy, Clone, PartialEq)]
+
+pub struct TradeBust<'a> {
+  pub _tab: flatbuffers::Table<'a>,
+}
+
+impl<'a> flatbuffers::Follow<'a> for TradeBust<'a> {
+  type Inner = TradeBust<'a>;
+  #[inline]
+  unsafe fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+    Self { _tab: unsafe { flatbuffers::Table::new(buf, loc) } }
+  }
+}
+
+impl<'a> TradeBust<'a> {
+  pub const VT_SYMBOL: flatbuffers::VOffsetT = 4;
+  pub const VT_TRADE_ID: flatbuffers::VOffsetT = 6;
+  pub const VT_BUY_ORDER_ID: flatbuffers::VOffsetT = 8;
+  pub const VT_SELL_ORDER_ID: flatbuffers::VOffsetT = 10;
+  pub const VT_BUYER_ID: flatbuffers::VOffsetT = 12;
+  pub const VT_SELLER_ID: flatbuffers::VOffsetT = 14;
+  pub const VT_PRICE: flatbuffers::VOffsetT = 16;
+  pub const VT_QUANTITY: flatbuffers::VOffsetT = 18;
+  pub const VT_TIMESTAMP: flatbuffers::VOffsetT = 20;
+  pub const VT_REASON: flatbuffers::VOffsetT = 22;
+
+  #[inline]
+  pub unsafe fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
+    TradeBust { _tab: table }
+  }
+  #[allow(unused_mut)]
+  pub fn create<'bldr: 'args, 'args: 'mut_bldr, 'mut_bldr, A: flatbuffers::Allocator + 'bldr>(
+    _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr, A>,
+    args: &'args TradeBustArgs<'args>
+  ) -> flatbuffers::WIPOffset<TradeBust<'bldr>> {
+    let mut builder = TradeBustBuilder::new(_fbb);
+    builder.add_timestamp(args.timestamp);
+    if let Some(x) = args.reason { builder.add_reason(x); }
+    if let Some(x) = args.quantity { builder.add_quantity(x); }
+    if let Some(x) = args.price { builder.add_price(x); }
+    if let Some(x) = args.seller_id { builder.add_seller_id(x); }
+    if let Some(x) = args.buyer_id { builder.add_buyer_id(x); }
+    if let Some(x) = args.sell_order_id { builder.add_sell_order_id(x); }
+    if let Some(x) = args.buy_order_id { builder.add_buy_order_id(x); }
+    if let Some(x) = args.trade_id { builder.add_trade_id(x); }
+    if let Some(x) = args.symbol { builder.add_symbol(x); }
+    builder.finish()
+  }
+
+
+  #[inline]
+  pub fn symbol(&self) -> Option<&'a str> {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<flatbuffers::ForwardsUOffset<&str>>(TradeBust::VT_SYMBOL, None)}
+  }
+  #[inline]
+  pub fn trade_id(&self) -> Option<&'a Uuid> {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<Uuid>(TradeBust::VT_TRADE_ID, None)}
+  }
+  #[inline]
+  pub fn buy_order_id(&self) -> Option<&'a Uuid> {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<Uuid>(TradeBust::VT_BUY_ORDER_ID, None)}
+  }
+  #[inline]
+  pub fn sell_order_id(&self) -> Option<&'a Uuid> {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<Uuid>(TradeBust::VT_SELL_ORDER_ID, None)}
+  }
+  #[inline]
+  pub fn buyer_id(&self) -> Option<&'a Uuid> {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<Uuid>(TradeBust::VT_BUYER_ID, None)}
+  }
+  #[inline]
+  pub fn seller_id(&self) -> Option<&'a Uuid> {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<Uuid>(TradeBust::VT_SELLER_ID, None)}
+  }
+  #[inline]
+  pub fn price(&self) -> Option<&'a Decimal> {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<Decimal>(TradeBust::VT_PRICE, None)}
+  }
+  #[inline]
+  pub fn quantity(&self) -> Option<&'a Decimal> {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<Decimal>(TradeBust::VT_QUANTITY, None)}
+  }
+  #[inline]
+  pub fn timestamp(&self) -> u64 {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<u64>(TradeBust::VT_TIMESTAMP, Some(0)).unwrap()}
+  }
+  #[inline]
+  pub fn reason(&self) -> Option<&'a str> {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<flatbuffers::ForwardsUOffset<&str>>(TradeBust::VT_REASON, None)}
+  }
+}
+
+impl flatbuffers::Verifiable for TradeBust<'_> {
+  #[inline]
+  fn run_verifier(
+    v: &mut flatbuffers::Verifier, pos: usize
+  ) -> Result<(), flatbuffers::InvalidFlatbuffer> {
+    use self::flatbuffers::Verifiable;
+    v.visit_table(pos)?
+     .visit_field::<flatbuffers::ForwardsUOffset<&str>>("symbol", Self::VT_SYMBOL, false)?
+     .visit_field::<Uuid>("trade_id", Self::VT_TRADE_ID, false)?
+     .visit_field::<Uuid>("buy_order_id", Self::VT_BUY_ORDER_ID, false)?
+     .visit_field::<Uuid>("sell_order_id", Self::VT_SELL_ORDER_ID, false)?
+     .visit_field::<Uuid>("buyer_id", Self::VT_BUYER_ID, false)?
+     .visit_field::<Uuid>("seller_id", Self::VT_SELLER_ID, false)?
+     .visit_field::<Decimal>("price", Self::VT_PRICE, false)?
+     .visit_field::<Decimal>("quantity", Self::VT_QUANTITY, false)?
+     .visit_field::<u64>("timestamp", Self::VT_TIMESTAMP, false)?
+     .visit_field::<flatbuffers::ForwardsUOffset<&str>>("reason", Self::VT_REASON, false)?
+     .finish();
+    Ok(())
+  }
+}
+pub struct TradeBustArgs<'a> {
+    pub symbol: Option<flatbuffers::WIPOffset<&'a str>>,
+    pub trade_id: Option<&'a Uuid>,
+    pub buy_order_id: Option<&'a Uuid>,
+    pub sell_order_id: Option<&'a Uuid>,
+    pub buyer_id: Option<&'a Uuid>,
+    pub seller_id: Option<&'a Uuid>,
+    pub price: Option<&'a Decimal>,
+    pub quantity: Option<&'a Decimal>,
+    pub timestamp: u64,
+    pub reason: Option<flatbuffers::WIPOffset<&'a str>>,
+}
+impl<'a> Default for TradeBustArgs<'a> {
+  #[inline]
+  fn default() -> Self {
+    TradeBustArgs {
+      symbol: None,
+      trade_id: None,
+      buy_order_id: None,
+      sell_order_id: None,
+      buyer_id: None,
+      seller_id: None,
+      price: None,
+      quantity: None,
+      timestamp: 0,
+      reason: None,
+    }
+  }
+}
+
+pub struct TradeBustBuilder<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> {
+  fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a, A>,
+  start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
+}
+impl<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> TradeBustBuilder<'a, 'b, A> {
+  #[inline]
+  pub fn add_symbol(&mut self, symbol: flatbuffers::WIPOffset<&'b  str>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(TradeBust::VT_SYMBOL, symbol);
+  }
+  #[inline]
+  pub fn add_trade_id(&mut self, trade_id: &Uuid) {
+    self.fbb_.push_slot_always::<&Uuid>(TradeBust::VT_TRADE_ID, trade_id);
+  }
+  #[inline]
+  pub fn add_buy_order_id(&mut self, buy_order_id: &Uuid) {
+    self.fbb_.push_slot_always::<&Uuid>(TradeBust::VT_BUY_ORDER_ID, buy_order_id);
+  }
+  #[inline]
+  pub fn add_sell_order_id(&mut self, sell_order_id: &Uuid) {
+    self.fbb_.push_slot_always::<&Uuid>(TradeBust::VT_SELL_ORDER_ID, sell_order_id);
+  }
+  #[inline]
+  pub fn add_buyer_id(&mut self, buyer_id: &Uuid) {
+    self.fbb_.push_slot_always::<&Uuid>(TradeBust::VT_BUYER_ID, buyer_id);
+  }
+  #[inline]
+  pub fn add_seller_id(&mut self, seller_id: &Uuid) {
+    self.fbb_.push_slot_always::<&Uuid>(TradeBust::VT_SELLER_ID, seller_id);
+  }
+  #[inline]
+  pub fn add_price(&mut self, price: &Decimal) {
+    self.fbb_.push_slot_always::<&Decimal>(TradeBust::VT_PRICE, price);
+  }
+  #[inline]
+  pub fn add_quantity(&mut self, quantity: &Decimal) {
+    self.fbb_.push_slot_always::<&Decimal>(TradeBust::VT_QUANTITY, quantity);
+  }
+  #[inline]
+  pub fn add_timestamp(&mut self, timestamp: u64) {
+    self.fbb_.push_slot::<u64>(TradeBust::VT_TIMESTAMP, timestamp, 0);
+  }
+  #[inline]
+  pub fn add_reason(&mut self, reason: flatbuffers::WIPOffset<&'b  str>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(TradeBust::VT_REASON, reason);
+  }
+  #[inline]
+  pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a, A>) -> TradeBustBuilder<'a, 'b, A> {
+    let start = _fbb.start_table();
+    TradeBustBuilder {
+      fbb_: _fbb,
+      start_: start,
+    }
+  }
+  #[inline]
+  pub fn finish(self) -> flatbuffers::WIPOffset<TradeBust<'a>> {
+    let o = self.fbb_.end_table(self.start_);
+    flatbuffers::WIPOffset::new(o.value())
+  }
+}
+
+impl core::fmt::Debug for TradeBust<'_> {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    let mut ds = f.debug_struct("TradeBust");
+      ds.field("symbol", &self.symbol());
+      ds.field("trade_id", &self.trade_id());
+      ds.field("buy_order_id", &self.buy_order_id());
+      ds.field("sell_order_id", &self.sell_order_id());
+      ds.field("buyer_id", &self.buyer_id());
+      ds.field("seller_id", &self.seller_id());
+      ds.field("price", &self.price());
+      ds.field("quantity", &self.quantity());
+      ds.field("timestamp", &self.timestamp());
+      ds.field("reason", &self.reason());
+      ds.finish()
+  }
+}
+pub enum AssetBalanceOffset {}
+#[derive(Copy, Clone, PartialEq)]
+
+pub struct AssetBalance<'a> {
+  pub _tab: flatbuffers::Table<'a>,
+}
+
+impl<'a> flatbuffers::Follow<'a> for AssetBalance<'a> {
+  type Inner = AssetBalance<'a>;
+  #[inline]
+  unsafe fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+    Self { _tab: unsafe { flatbuffers::Table::new(buf, loc) } }
+  }
+}
+
+impl<'a> AssetBalance<'a> {
+  pub const VT_ASSET: flatbuffers::VOffsetT = 4;
+  pub const VT_AVAILABLE: flatbuffers::VOffsetT = 6;
+  pub const VT_LOCKED: flatbuffers::VOffsetT = 8;
+
+  #[inline]
+  pub unsafe fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
+    AssetBalance { _tab: table }
+  }
+  #[allow(unused_mut)]
+  pub fn create<'bldr: 'args, 'args: 'mut_bldr, 'mut_bldr, A: flatbuffers::Allocator + 'bldr>(
+    _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr, A>,
+    args: &'args AssetBalanceArgs<'args>
+  ) -> flatbuffers::WIPOffset<AssetBalance<'bldr>> {
+    let mut builder = AssetBalanceBuilder::new(_fbb);
+    if let Some(x) = args.locked { builder.add_locked(x); }
+    if let Some(x) = args.available { builder.add_available(x); }
+    if let Some(x) = args.asset { builder.add_asset(x); }
+    builder.finish()
+  }
+
+
+  #[inline]
+  pub fn asset(&self) -> Option<&'a str> {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<flatbuffers::ForwardsUOffset<&str>>(AssetBalance::VT_ASSET, None)}
+  }
+  #[inline]
+  pub fn available(&self) -> Option<&'a Decimal> {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<Decimal>(AssetBalance::VT_AVAILABLE, None)}
+  }
+  #[inline]
+  pub fn locked(&self) -> Option<&'a Decimal> {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<Decimal>(AssetBalance::VT_LOCKED, None)}
+  }
+}
+
+impl flatbuffers::Verifiable for AssetBalance<'_> {
+  #[inline]
+  fn run_verifier(
+    v: &mut flatbuffers::Verifier, pos: usize
+  ) -> Result<(), flatbuffers::InvalidFlatbuffer> {
+    use self::flatbuffers::Verifiable;
+    v.visit_table(pos)?
+     .visit_field::<flatbuffers::ForwardsUOffset<&str>>("asset", Self::VT_ASSET, false)?
+     .visit_field::<Decimal>("available", Self::VT_AVAILABLE, false)?
+     .visit_field::<Decimal>("locked", Self::VT_LOCKED, false)?
+     .finish();
+    Ok(())
+  }
+}
+pub struct AssetBalanceArgs<'a> {
+    pub asset: Option<flatbuffers::WIPOffset<&'a str>>,
+    pub available: Option<&'a Decimal>,
+    pub locked: Option<&'a Decimal>,
+}
+impl<'a> Default for AssetBalanceArgs<'a> {
+  #[inline]
+  fn default() -> Self {
+    AssetBalanceArgs {
+      asset: None,
+      available: None,
+      locked: None,
+    }
+  }
+}
+
+pub struct AssetBalanceBuilder<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> {
+  fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a, A>,
+  start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
+}
+impl<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> AssetBalanceBuilder<'a, 'b, A> {
+  #[inline]
+  pub fn add_asset(&mut self, asset: flatbuffers::WIPOffset<&'b  str>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(AssetBalance::VT_ASSET, asset);
+  }
+  #[inline]
+  pub fn add_available(&mut self, available: &Decimal) {
+    self.fbb_.push_slot_always::<&Decimal>(AssetBalance::VT_AVAILABLE, available);
+  }
+  #[inline]
+  pub fn add_locked(&mut self, locked: &Decimal) {
+    self.fbb_.push_slot_always::<&Decimal>(AssetBalance::VT_LOCKED, locked);
+  }
+  #[inline]
+  pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a, A>) -> AssetBalanceBuilder<'a, 'b, A> {
+    let start = _fbb.start_table();
+    AssetBalanceBuilder {
+      fbb_: _fbb,
+      start_: start,
+    }
+  }
+  #[inline]
+  pub fn finish(self) -> flatbuffers::WIPOffset<AssetBalance<'a>> {
+    let o = self.fbb_.end_table(self.start_);
+    flatbuffers::WIPOffset::new(o.value())
+  }
+}
+
+impl core::fmt::Debug for AssetBalance<'_> {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    let mut ds = f.debug_struct("AssetBalance");
+      ds.field("asset", &self.asset());
+      ds.field("available", &self.available());
+      ds.field("locked", &self.locked());
+      ds.finish()
+  }
+}
+pub enum PositionUpdateOffset {}
+#[derive(Copy, Clone, PartialEq)]
+
+pub struct PositionUpdate<'a> {
+  pub _tab: flatbuffers::Table<'a>,
+}
+
+impl<'a> flatbuffers::Follow<'a> for PositionUpdate<'a> {
+  type Inner = PositionUpdate<'a>;
+  #[inline]
+  unsafe fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+    Self { _tab: unsafe { flatbuffers::Table::new(buf, loc) } }
+  }
+}
+
+impl<'a> PositionUpdate<'a> {
+  pub const VT_USER_ID: flatbuffers::VOffsetT = 4;
+  pub const VT_BALANCES: flatbuffers::VOffsetT = 6;
+  pub const VT_TIMESTAMP: flatbuffers::VOffsetT = 8;
+
+  #[inline]
+  pub unsafe fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
+    PositionUpdate { _tab: table }
+  }
+  #[allow(unused_mut)]
+  pub fn create<'bldr: 'args, 'args: 'mut_bldr, 'mut_bldr, A: flatbuffers::Allocator + 'bldr>(
+    _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr, A>,
+    args: &'args PositionUpdateArgs<'args>
+  ) -> flatbuffers::WIPOffset<PositionUpdate<'bldr>> {
+    let mut builder = PositionUpdateBuilder::new(_fbb);
+    builder.add_timestamp(args.timestamp);
+    if let Some(x) = args.balances { builder.add_balances(x); }
+    if let Some(x) = args.user_id { builder.add_user_id(x); }
+    builder.finish()
+  }
+
+
+  #[inline]
+  pub fn user_id(&self) -> Option<&'a Uuid> {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<Uuid>(PositionUpdate::VT_USER_ID, None)}
+  }
+  #[inline]
+  pub fn balances(&self) -> Option<flatbuffers::Vector<'a, flatbuffers::ForwardsUOffset<AssetBalance<'a>>>> {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<'a, flatbuffers::ForwardsUOffset<AssetBalance<'a>>>>>(PositionUpdate::VT_BALANCES, None)}
+  }
+  #[inline]
+  pub fn timestamp(&self) -> u64 {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<u64>(PositionUpdate::VT_TIMESTAMP, Some(0)).unwrap()}
+  }
+}
+
+impl flatbuffers::Verifiable for PositionUpdate<'_> {
+  #[inline]
+  fn run_verifier(
+    v: &mut flatbuffers::Verifier, pos: usize
+  ) -> Result<(), flatbuffers::InvalidFlatbuffer> {
+    use self::flatbuffers::Verifiable;
+    v.visit_table(pos)?
+     .visit_field::<Uuid>("user_id", Self::VT_USER_ID, false)?
+     .visit_field::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<'_, flatbuffers::ForwardsUOffset<AssetBalance<'_>>>>>("balances", Self::VT_BALANCES, false)?
+     .visit_field::<u64>("timestamp", Self::VT_TIMESTAMP, false)?
+     .finish();
+    Ok(())
+  }
+}
+pub struct PositionUpdateArgs<'a> {
+    pub user_id: Option<&'a Uuid>,
+    pub balances: Option<flatbuffers::WIPOffset<flatbuffers::Vector<'a, flatbuffers::ForwardsUOffset<AssetBalance<'a>>>>>,
+    pub timestamp: u64,
+}
+impl<'a> Default for PositionUpdateArgs<'a> {
+  #[inline]
+  fn default() -> Self {
+    PositionUpdateArgs {
+      user_id: None,
+      balances: None,
+      timestamp: 0,
+    }
+  }
+}
+
+pub struct PositionUpdateBuilder<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> {
+  fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a, A>,
+  start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
+}
+impl<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> PositionUpdateBuilder<'a, 'b, A> {
+  #[inline]
+  pub fn add_user_id(&mut self, user_id: &Uuid) {
+    self.fbb_.push_slot_always::<&Uuid>(PositionUpdate::VT_USER_ID, user_id);
+  }
+  #[inline]
+  pub fn add_balances(&mut self, balances: flatbuffers::WIPOffset<flatbuffers::Vector<'b , flatbuffers::ForwardsUOffset<AssetBalance<'b >>>>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(PositionUpdate::VT_BALANCES, balances);
+  }
+  #[inline]
+  pub fn add_timestamp(&mut self, timestamp: u64) {
+    self.fbb_.push_slot::<u64>(PositionUpdate::VT_TIMESTAMP, timestamp, 0);
+  }
+  #[inline]
+  pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a, A>) -> PositionUpdateBuilder<'a, 'b, A> {
+    let start = _fbb.start_table();
+    PositionUpdateBuilder {
+      fbb_: _fbb,
+      start_: start,
+    }
+  }
+  #[inline]
+  pub fn finish(self) -> flatbuffers::WIPOffset<PositionUpdate<'a>> {
+    let o = self.fbb_.end_table(self.start_);
+    flatbuffers::WIPOffset::new(o.value())
+  }
+}
+
+impl core::fmt::Debug for PositionUpdate<'_> {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    let mut ds = f.debug_struct("PositionUpdate");
+      ds.field("user_id", &self.user_id());
+      ds.field("balances", &self.balances());
+      ds.field("timestamp", &self.timestamp());
+      ds.finish()
+  }
+}
+pub enum OrderNewOffset {}
+#[derive(Copy, Clone, PartialEq)]
+
+pub struct OrderNew<'a> {
+  pub _tab: flatbuffers::Table<'a>,
+}
+
+impl<'a> flatbuffers::Follow<'a> for OrderNew<'a> {
+  type Inner = OrderNew<'a>;
+  #[inline]
+  unsafe fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+    Self { _tab: unsafe { flatbuffers::Table::new(buf, loc) } }
+  }
+}
+
+impl<'a> OrderNew<'a> {
+  pub const VT_ORDER_ID: flatbuffers::VOffsetT = 4;
+  pub const VT_USER_ID: flatbuffers::VOffsetT = 6;
+  pub const VT_SIDE: flatbuffers::VOffsetT = 8;
+  pub const VT_ORDER_TYPE: flatbuffers::VOffsetT = 10;
+  pub const VT_PRICE: flatbuffers::VOffsetT = 12;
+  pub const VT_QUANTITY: flatbuffers::VOffsetT = 14;
+
+  #[inline]
+  pub unsafe fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
+    OrderNew { _tab: table }
+  }
+  #[allow(unused_mut)]
+  pub fn create<'bldr: 'args, 'args: 'mut_bldr, 'mut_bldr, A: flatbuffers::Allocator + 'bldr>(
+    _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr, A>,
+    args: &'args OrderNewArgs<'args>
+  ) -> flatbuffers::WIPOffset<OrderNew<'bldr>> {
+    let mut builder = OrderNewBuilder::new(_fbb);
+    if let Some(x) = args.quantity { builder.add_quantity(x); }
+    if let Some(x) = args.price { builder.add_price(x); }
+    if let Some(x) = args.user_id { builder.add_user_id(x); }
+    if let Some(x) = args.order_id { builder.add_order_id(x); }
+    builder.add_order_type(args.order_type);
+    builder.add_side(args.side);
+    builder.finish()
+  }
+
+
+  #[inline]
+  pub fn order_id(&self) -> Option<&'a Uuid> {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<Uuid>(OrderNew::VT_ORDER_ID, None)}
+  }
+  #[inline]
+  pub fn user_id(&self) -> Option<&'a Uuid> {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<Uuid>(OrderNew::VT_USER_ID, None)}
+  }
+  #[inline]
+  pub fn side(&self) -> Side {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<Side>(OrderNew::VT_SIDE, Some(Side::Bid)).unwrap()}
+  }
+  #[inline]
+  pub fn order_type(&self) -> OrderType {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<OrderType>(OrderNew::VT_ORDER_TYPE, Some(OrderType::Limit)).unwrap()}
+  }
+  #[inline]
+  pub fn price(&self) -> Option<&'a Decimal> {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<Decimal>(OrderNew::VT_PRICE, None)}
+  }
+  #[inline]
+  pub fn quantity(&self) -> Option<&'a Decimal> {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<Decimal>(OrderNew::VT_QUANTITY, None)}
+  }
+}
+
+impl flatbuffers::Verifiable for OrderNew<'_> {
+  #[inline]
+  fn run_verifier(
+    v: &mut flatbuffers::Verifier, pos: usize
+  ) -> Result<(), flatbuffers::InvalidFlatbuffer> {
+    use self::flatbuffers::Verifiable;
+    v.visit_table(pos)?
+     .visit_field::<Uuid>("order_id", Self::VT_ORDER_ID, false)?
+     .visit_field::<Uuid>("user_id", Self::VT_USER_ID, false)?
+     .visit_field::<Side>("side", Self::VT_SIDE, false)?
+     .visit_field::<OrderType>("order_type", Self::VT_ORDER_TYPE, false)?
+     .visit_field::<Decimal>("price", Self::VT_PRICE, false)?
+     .visit_field::<Decimal>("quantity", Self::VT_QUANTITY, false)?
+     .finish();
+    Ok(())
+  }
+}
+pub struct OrderNewArgs<'a> {
+    pub order_id: Option<&'a Uuid>,
+    pub user_id: Option<&'a Uuid>,
+    pub side: Side,
+    pub order_type: OrderType,
+    pub price: Option<&'a Decimal>,
+    pub quantity: Option<&'a Decimal>,
+}
+impl<'a> Default for OrderNewArgs<'a> {
+  #[inline]
+  fn default() -> Self {
+    OrderNewArgs {
+      order_id: None,
+      user_id: None,
+      side: Side::Bid,
+      order_type: OrderType::Limit,
+      price: None,
+      quantity: None,
+    }
+  }
+}
+
+pub struct OrderNewBuilder<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> {
+  fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a, A>,
+  start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
+}
+impl<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> OrderNewBuilder<'a, 'b, A> {
+  #[inline]
+  pub fn add_order_id(&mut self, order_id: &Uuid) {
+    self.fbb_.push_slot_always::<&Uuid>(OrderNew::VT_ORDER_ID, order_id);
+  }
+  #[inline]
+  pub fn add_user_id(&mut self, user_id: &Uuid) {
+    self.fbb_.push_slot_always::<&Uuid>(OrderNew::VT_USER_ID, user_id);
+  }
+  #[inline]
+  pub fn add_side(&mut self, side: Side) {
+    self.fbb_.push_slot::<Side>(OrderNew::VT_SIDE, side, Side::Bid);
+  }
+  #[inline]
+  pub fn add_order_type(&mut self, order_type: OrderType) {
+    self.fbb_.push_slot::<OrderType>(OrderNew::VT_ORDER_TYPE, order_type, OrderType::Limit);
+  }
+  #[inline]
+  pub fn add_price(&mut self, price: &Decimal) {
+    self.fbb_.push_slot_always::<&Decimal>(OrderNew::VT_PRICE, price);
+  }
+  #[inline]
+  pub fn add_quantity(&mut self, quantity: &Decimal) {
+    self.fbb_.push_slot_always::<&Decimal>(OrderNew::VT_QUANTITY, quantity);
+  }
+  #[inline]
+  pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a, A>) -> OrderNewBuilder<'a, 'b, A> {
+    let start = _fbb.start_table();
+    OrderNewBuilder {
+      fbb_: _fbb,
+      start_: start,
+    }
+  }
+  #[inline]
+  pub fn finish(self) -> flatbuffers::WIPOffset<OrderNew<'a>> {
+    let o = self.fbb_.end_table(self.start_);
+    flatbuffers::WIPOffset::new(o.value())
+  }
+}
+
+impl core::fmt::Debug for OrderNew<'_> {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    let mut ds = f.debug_struct("OrderNew");
+      ds.field("order_id", &self.order_id());
+      ds.field("user_id", &self.user_id());
+      ds.field("side", &self.side());
+      ds.field("order_type", &self.order_type());
+      ds.field("price", &self.price());
+      ds.field("quantity", &self.quantity());
+      ds.finish()
+  }
+}
+pub enum OrderCancelOffset {}
+#[derive(Copy, Clone, PartialEq)]
+
+pub struct OrderCancel<'a> {
+  pub _tab: flatbuffers::Table<'a>,
+}
+
+impl<'a> flatbuffers::Follow<'a> for OrderCancel<'a> {
+  type Inner = OrderCancel<'a>;
+  #[inline]
+  unsafe fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+    Self { _tab: unsafe { flatbuffers::Table::new(buf, loc) } }
+  }
+}
+
+impl<'a> OrderCancel<'a> {
+  pub const VT_ORDER_ID: flatbuffers::VOffsetT = 4;
+  pub const VT_USER_ID: flatbuffers::VOffsetT = 6;
+
+  #[inline]
+  pub unsafe fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
+    OrderCancel { _tab: table }
+  }
+  #[allow(unused_mut)]
+  pub fn create<'bldr: 'args, 'args: 'mut_bldr, 'mut_bldr, A: flatbuffers::Allocator + 'bldr>(
+    _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr, A>,
+    args: &'args OrderCancelArgs<'args>
+  ) -> flatbuffers::WIPOffset<OrderCancel<'bldr>> {
+    let mut builder = OrderCancelBuilder::new(_fbb);
+    if let Some(x) = args.user_id { builder.add_user_id(x); }
+    if let Some(x) = args.order_id { builder.add_order_id(x); }
+    builder.finish()
+  }
+
+
+  #[inline]
+  pub fn order_id(&self) -> Option<&'a Uuid> {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<Uuid>(OrderCancel::VT_ORDER_ID, None)}
+  }
+  #[inline]
+  pub fn user_id(&self) -> Option<&'a Uuid> {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<Uuid>(OrderCancel::VT_USER_ID, None)}
+  }
+}
+
+impl flatbuffers::Verifiable for OrderCancel<'_> {
+  #[inline]
+  fn run_verifier(
+    v: &mut flatbuffers::Verifier, pos: usize
+  ) -> Result<(), flatbuffers::InvalidFlatbuffer> {
+    use self::flatbuffers::Verifiable;
+    v.visit_table(pos)?
+     .visit_field::<Uuid>("order_id", Self::VT_ORDER_ID, false)?
+     .visit_field::<Uuid>("user_id", Self::VT_USER_ID, false)?
+     .finish();
+    Ok(())
+  }
+}
+pub struct OrderCancelArgs<'a> {
+    pub order_id: Option<&'a Uuid>,
+    pub user_id: Option<&'a Uuid>,
+}
+impl<'a> Default for OrderCancelArgs<'a> {
+  #[inline]
+  fn default() -> Self {
+    OrderCancelArgs {
+      order_id: None,
+      user_id: None,
+    }
+  }
+}
+
+pub struct OrderCancelBuilder<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> {
+  fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a, A>,
+  start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
+}
+impl<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> OrderCancelBuilder<'a, 'b, A> {
+  #[inline]
+  pub fn add_order_id(&mut self, order_id: &Uuid) {
+    self.fbb_.push_slot_always::<&Uuid>(OrderCancel::VT_ORDER_ID, order_id);
+  }
+  #[inline]
+  pub fn add_user_id(&mut self, user_id: &Uuid) {
+    self.fbb_.push_slot_always::<&Uuid>(OrderCancel::VT_USER_ID, user_id);
+  }
+  #[inline]
+  pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a, A>) -> OrderCancelBuilder<'a, 'b, A> {
+    let start = _fbb.start_table();
+    OrderCancelBuilder {
+      fbb_: _fbb,
+      start_: start,
+    }
+  }
+  #[inline]
+  pub fn finish(self) -> flatbuffers::WIPOffset<OrderCancel<'a>> {
+    let o = self.fbb_.end_table(self.start_);
+    flatbuffers::WIPOffset::new(o.value())
+  }
+}
+
+impl core::fmt::Debug for OrderCancel<'_> {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    let mut ds = f.debug_struct("OrderCancel");
+      ds.field("order_id", &self.order_id());
+      ds.field("user_id", &self.user_id());
+      ds.finish()
+  }
+}
+pub enum OrderReplaceOffset {}
+#[derive(Copy, Clone, PartialEq)]
+
+pub struct OrderReplace<'a> {
+  pub _tab: flatbuffers::Table<'a>,
+}
+
+impl<'a> flatbuffers::Follow<'a> for OrderReplace<'a> {
+  type Inner = OrderReplace<'a>;
+  #[inline]
+  unsafe fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+    Self { _tab: unsafe { flatbuffers::Table::new(buf, loc) } }
+  }
+}
+
+impl<'a> OrderReplace<'a> {
+  pub const VT_ORDER_ID: flatbuffers::VOffsetT = 4;
+  pub const VT_USER_ID: flatbuffers::VOffsetT = 6;
+  pub const VT_PRICE: flatbuffers::VOffsetT = 8;
+  pub const VT_QUANTITY: flatbuffers::VOffsetT = 10;
+
+  #[inline]
+  pub unsafe fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
+    OrderReplace { _tab: table }
+  }
+  #[allow(unused_mut)]
+  pub fn create<'bldr: 'args, 'args: 'mut_bldr, 'mut_bldr, A: flatbuffers::Allocator + 'bldr>(
+    _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr, A>,
+    args: &'args OrderReplaceArgs<'args>
+  ) -> flatbuffers::WIPOffset<OrderReplace<'bldr>> {
+    let mut builder = OrderReplaceBuilder::new(_fbb);
+    if let Some(x) = args.quantity { builder.add_quantity(x); }
+    if let Some(x) = args.price { builder.add_price(x); }
+    if let Some(x) = args.user_id { builder.add_user_id(x); }
+    if let Some(x) = args.order_id { builder.add_order_id(x); }
+    builder.finish()
+  }
+
+
+  #[inline]
+  pub fn order_id(&self) -> Option<&'a Uuid> {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<Uuid>(OrderReplace::VT_ORDER_ID, None)}
+  }
+  #[inline]
+  pub fn user_id(&self) -> Option<&'a Uuid> {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<Uuid>(OrderReplace::VT_USER_ID, None)}
+  }
+  #[inline]
+  pub fn price(&self) -> Option<&'a Decimal> {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<Decimal>(OrderReplace::VT_PRICE, None)}
+  }
+  #[inline]
+  pub fn quantity(&self) -> Option<&'a Decimal> {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<Decimal>(OrderReplace::VT_QUANTITY, None)}
+  }
+}
+
+impl flatbuffers::Verifiable for OrderReplace<'_> {
+  #[inline]
+  fn run_verifier(
+    v: &mut flatbuffers::Verifier, pos: usize
+  ) -> Result<(), flatbuffers::InvalidFlatbuffer> {
+    use self::flatbuffers::Verifiable;
+    v.visit_table(pos)?
+     .visit_field::<Uuid>("order_id", Self::VT_ORDER_ID, false)?
+     .visit_field::<Uuid>("user_id", Self::VT_USER_ID, false)?
+     .visit_field::<Decimal>("price", Self::VT_PRICE, false)?
+     .visit_field::<Decimal>("quantity", Self::VT_QUANTITY, false)?
+     .finish();
+    Ok(())
+  }
+}
+pub struct OrderReplaceArgs<'a> {
+    pub order_id: Option<&'a Uuid>,
+    pub user_id: Option<&'a Uuid>,
+    pub price: Option<&'a Decimal>,
+    pub quantity: Option<&'a Decimal>,
+}
+impl<'a> Default for OrderReplaceArgs<'a> {
+  #[inline]
+  fn default() -> Self {
+    OrderReplaceArgs {
+      order_id: None,
+      user_id: None,
+      price: None,
+      quantity: None,
+    }
+  }
+}
+
+pub struct OrderReplaceBuilder<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> {
+  fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a, A>,
+  start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
+}
+impl<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> OrderReplaceBuilder<'a, 'b, A> {
+  #[inline]
+  pub fn add_order_id(&mut self, order_id: &Uuid) {
+    self.fbb_.push_slot_always::<&Uuid>(OrderReplace::VT_ORDER_ID, order_id);
+  }
+  #[inline]
+  pub fn add_user_id(&mut self, user_id: &Uuid) {
+    self.fbb_.push_slot_always::<&Uuid>(OrderReplace::VT_USER_ID, user_id);
+  }
+  #[inline]
+  pub fn add_price(&mut self, price: &Decimal) {
+    self.fbb_.push_slot_always::<&Decimal>(OrderReplace::VT_PRICE, price);
+  }
+  #[inline]
+  pub fn add_quantity(&mut self, quantity: &Decimal) {
+    self.fbb_.push_slot_always::<&Decimal>(OrderReplace::VT_QUANTITY, quantity);
+  }
+  #[inline]
+  pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a, A>) -> OrderReplaceBuilder<'a, 'b, A> {
+    let start = _fbb.start_table();
+    OrderReplaceBuilder {
+      fbb_: _fbb,
+      start_: start,
+    }
+  }
+  #[inline]
+  pub fn finish(self) -> flatbuffers::WIPOffset<OrderReplace<'a>> {
+    let o = self.fbb_.end_table(self.start_);
+    flatbuffers::WIPOffset::new(o.value())
+  }
+}
+
+impl core::fmt::Debug for OrderReplace<'_> {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    let mut ds = f.debug_struct("OrderReplace");
+      ds.field("order_id", &self.order_id());
+      ds.field("user_id", &self.user_id());
+      ds.field("price", &self.price());
+      ds.field("quantity", &self.quantity());
+      ds.finish()
+  }
+}
+pub enum SnapshotRequestOffset {}
+#[derive(Copy, Clone, PartialEq)]
+
+pub struct SnapshotRequest<'a> {
+  pub _tab: flatbuffers::Table<'a>,
+}
+
+impl<'a> flatbuffers::Follow<'a> for SnapshotRequest<'a> {
+  type Inner = SnapshotRequest<'a>;
+  #[inline]
+  unsafe fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+    Self { _tab: unsafe { flatbuffers::Table::new(buf, loc) } }
+  }
+}
+
+impl<'a> SnapshotRequest<'a> {
+  pub const VT_SYMBOL: flatbuffers::VOffsetT = 4;
+
+  #[inline]
+  pub unsafe fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
+    SnapshotRequest { _tab: table }
+  }
+  #[allow(unused_mut)]
+  pub fn create<'bldr: 'args, 'args: 'mut_bldr, 'mut_bldr, A: flatbuffers::Allocator + 'bldr>(
+    _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr, A>,
+    args: &'args SnapshotRequestArgs<'args>
+  ) -> flatbuffers::WIPOffset<SnapshotRequest<'bldr>> {
+    let mut builder = SnapshotRequestBuilder::new(_fbb);
+    if let Some(x) = args.symbol { builder.add_symbol(x); }
+    builder.finish()
+  }
+
+
+  #[inline]
+  pub fn symbol(&self) -> Option<&'a str> {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<flatbuffers::ForwardsUOffset<&str>>(SnapshotRequest::VT_SYMBOL, None)}
+  }
+}
+
+impl flatbuffers::Verifiable for SnapshotRequest<'_> {
+  #[inline]
+  fn run_verifier(
+    v: &mut flatbuffers::Verifier, pos: usize
+  ) -> Result<(), flatbuffers::InvalidFlatbuffer> {
+    use self::flatbuffers::Verifiable;
+    v.visit_table(pos)?
+     .visit_field::<flatbuffers::ForwardsUOffset<&str>>("symbol", Self::VT_SYMBOL, false)?
+     .finish();
+    Ok(())
+  }
+}
+pub struct SnapshotRequestArgs<'a> {
+    pub symbol: Option<flatbuffers::WIPOffset<&'a str>>,
+}
+impl<'a> Default for SnapshotRequestArgs<'a> {
+  #[inline]
+  fn default() -> Self {
+    SnapshotRequestArgs {
+      symbol: None,
     }
   }
+}
 
+pub struct SnapshotRequestBuilder<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> {
+  fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a, A>,
+  start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
+}
+impl<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> SnapshotRequestBuilder<'a, 'b, A> {
+  #[inline]
+  pub fn add_symbol(&mut self, symbol: flatbuffers::WIPOffset<&'b  str>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(SnapshotRequest::VT_SYMBOL, symbol);
+  }
+  #[inline]
+  pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a, A>) -> SnapshotRequestBuilder<'a, 'b, A> {
+    let start = _fbb.start_table();
+    SnapshotRequestBuilder {
+      fbb_: _fbb,
+      start_: start,
+    }
+  }
+  #[inline]
+  pub fn finish(self) -> flatbuffers::WIPOffset<SnapshotRequest<'a>> {
+    let o = self.fbb_.end_table(self.start_);
+    flatbuffers::WIPOffset::new(o.value())
+  }
 }
 
-pub enum FillOffset {}
+impl core::fmt::Debug for SnapshotRequest<'_> {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    let mut ds = f.debug_struct("SnapshotRequest");
+      ds.field("symbol", &self.symbol());
+      ds.finish()
+  }
+}
+pub enum BookChecksumOffset {}
 #[derive(Copy, Clone, PartialEq)]
 
-pub struct Fill<'a> {
+pub struct BookChecksum<'a> {
   pub _tab: flatbuffers::Table<'a>,
 }
 
-impl<'a> flatbuffers::Follow<'a> for Fill<'a> {
-  type Inner = Fill<'a>;
+impl<'a> flatbuffers::Follow<'a> for BookChecksum<'a> {
+  type Inner = BookChecksum<'a>;
   #[inline]
   unsafe fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
     Self { _tab: unsafe { flatbuffers::Table::new(buf, loc) } }
   }
 }
 
-impl<'a> Fill<'a> {
-  pub const VT_SYMBOL: flatbuffers::VOffsetT = 4;
-  pub const VT_BUY_ORDER_ID: flatbuffers::VOffsetT = 6;
-  pub const VT_SELL_ORDER_ID: flatbuffers::VOffsetT = 8;
-  pub const VT_PRICE: flatbuffers::VOffsetT = 10;
-  pub const VT_QUANTITY: flatbuffers::VOffsetT = 12;
-  pub const VT_TIMESTAMP: flatbuffers::VOffsetT = 14;
+impl<'a> BookChecksum<'a> {
+  pub const VT_HASH: flatbuffers::VOffsetT = 4;
+  pub const VT_ORDER_COUNT: flatbuffers::VOffsetT = 6;
+  pub const VT_PHASE: flatbuffers::VOffsetT = 8;
 
   #[inline]
   pub unsafe fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
-    Fill { _tab: table }
+    BookChecksum { _tab: table }
   }
   #[allow(unused_mut)]
   pub fn create<'bldr: 'args, 'args: 'mut_bldr, 'mut_bldr, A: flatbuffers::Allocator + 'bldr>(
     _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr, A>,
-    args: &'args FillArgs<'args>
-  ) -> flatbuffers::WIPOffset<Fill<'bldr>> {
-    let mut builder = FillBuilder::new(_fbb);
-    builder.add_timestamp(args.timestamp);
-    builder.add_quantity(args.quantity);
-    builder.add_price(args.price);
-    if let Some(x) = args.sell_order_id { builder.add_sell_order_id(x); }
-    if let Some(x) = args.buy_order_id { builder.add_buy_order_id(x); }
-    if let Some(x) = args.symbol { builder.add_symbol(x); }
+    args: &'args BookChecksumArgs
+  ) -> flatbuffers::WIPOffset<BookChecksum<'bldr>> {
+    let mut builder = BookChecksumBuilder::new(_fbb);
+    builder.add_order_count(args.order_count);
+    builder.add_hash(args.hash);
+    builder.add_phase(args.phase);
     builder.finish()
   }
 
 
   #[inline]
-  pub fn symbol(&self) -> Option<&'a str> {
-    // Safety:
-    // Created from valid Table for this object
-    // which contains a valid value in this slot
-    unsafe { self._tab.get::<flatbuffers::ForwardsUOffset<&str>>(Fill::VT_SYMBOL, None)}
-  }
-  #[inline]
-  pub fn buy_order_id(&self) -> Option<&'a Uuid> {
-    // Safety:
-    // Created from valid Table for this object
-    // which contains a valid value in this slot
-    unsafe { self._tab.get::<Uuid>(Fill::VT_BUY_ORDER_ID, None)}
-  }
-  #[inline]
-  pub fn sell_order_id(&self) -> Option<&'a Uuid> {
-    // Safety:
-    // Created from valid Table for this object
-    // which contains a valid value in this slot
-    unsafe { self._tab.get::<Uuid>(Fill::VT_SELL_ORDER_ID, None)}
-  }
-  #[inline]
-  pub fn price(&self) -> f64 {
+  pub fn hash(&self) -> u64 {
     // Safety:
     // Created from valid Table for this object
     // which contains a valid value in this slot
-    unsafe { self._tab.get::<f64>(Fill::VT_PRICE, Some(0.0)).unwrap()}
+    unsafe { self._tab.get::<u64>(BookChecksum::VT_HASH, Some(0)).unwrap()}
   }
   #[inline]
-  pub fn quantity(&self) -> f64 {
+  pub fn order_count(&self) -> u64 {
     // Safety:
     // Created from valid Table for this object
     // which contains a valid value in this slot
-    unsafe { self._tab.get::<f64>(Fill::VT_QUANTITY, Some(0.0)).unwrap()}
+    unsafe { self._tab.get::<u64>(BookChecksum::VT_ORDER_COUNT, Some(0)).unwrap()}
   }
   #[inline]
-  pub fn timestamp(&self) -> u64 {
+  pub fn phase(&self) -> MarketPhase {
     // Safety:
     // Created from valid Table for this object
     // which contains a valid value in this slot
-    unsafe { self._tab.get::<u64>(Fill::VT_TIMESTAMP, Some(0)).unwrap()}
+    unsafe { self._tab.get::<MarketPhase>(BookChecksum::VT_PHASE, Some(MarketPhase::Open)).unwrap()}
   }
 }
 
-impl flatbuffers::Verifiable for Fill<'_> {
+impl flatbuffers::Verifiable for BookChecksum<'_> {
   #[inline]
   fn run_verifier(
     v: &mut flatbuffers::Verifier, pos: usize
   ) -> Result<(), flatbuffers::InvalidFlatbuffer> {
     use self::flatbuffers::Verifiable;
     v.visit_table(pos)?
-     .visit_field::<flatbuffers::ForwardsUOffset<&str>>("symbol", Self::VT_SYMBOL, false)?
-     .visit_field::<Uuid>("buy_order_id", Self::VT_BUY_ORDER_ID, false)?
-     .visit_field::<Uuid>("sell_order_id", Self::VT_SELL_ORDER_ID, false)?
-     .visit_field::<f64>("price", Self::VT_PRICE, false)?
-     .visit_field::<f64>("quantity", Self::VT_QUANTITY, false)?
-     .visit_field::<u64>("timestamp", Self::VT_TIMESTAMP, false)?
+     .visit_field::<u64>("hash", Self::VT_HASH, false)?
+     .visit_field::<u64>("order_count", Self::VT_ORDER_COUNT, false)?
+     .visit_field::<MarketPhase>("phase", Self::VT_PHASE, false)?
      .finish();
     Ok(())
   }
 }
-pub struct FillArgs<'a> {
-    pub symbol: Option<flatbuffers::WIPOffset<&'a str>>,
-    pub buy_order_id: Option<&'a Uuid>,
-    pub sell_order_id: Option<&'a Uuid>,
-    pub price: f64,
-    pub quantity: f64,
-    pub timestamp: u64,
+pub struct BookChecksumArgs {
+    pub hash: u64,
+    pub order_count: u64,
+    pub phase: MarketPhase,
 }
-impl<'a> Default for FillArgs<'a> {
+impl<'a> Default for BookChecksumArgs {
   #[inline]
   fn default() -> Self {
-    FillArgs {
-      symbol: None,
-      buy_order_id: None,
-      sell_order_id: None,
-      price: 0.0,
-      quantity: 0.0,
-      timestamp: 0,
+    BookChecksumArgs {
+      hash: 0,
+      order_count: 0,
+      phase: MarketPhase::Open,
     }
   }
 }
 
-pub struct FillBuilder<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> {
+pub struct BookChecksumBuilder<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> {
   fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a, A>,
   start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
 }
-impl<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> FillBuilder<'a, 'b, A> {
-  #[inline]
-  pub fn add_symbol(&mut self, symbol: flatbuffers::WIPOffset<&'b  str>) {
-    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(Fill::VT_SYMBOL, symbol);
-  }
-  #[inline]
-  pub fn add_buy_order_id(&mut self, buy_order_id: &Uuid) {
-    self.fbb_.push_slot_always::<&Uuid>(Fill::VT_BUY_ORDER_ID, buy_order_id);
-  }
+impl<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> BookChecksumBuilder<'a, 'b, A> {
   #[inline]
-  pub fn add_sell_order_id(&mut self, sell_order_id: &Uuid) {
-    self.fbb_.push_slot_always::<&Uuid>(Fill::VT_SELL_ORDER_ID, sell_order_id);
-  }
-  #[inline]
-  pub fn add_price(&mut self, price: f64) {
-    self.fbb_.push_slot::<f64>(Fill::VT_PRICE, price, 0.0);
+  pub fn add_hash(&mut self, hash: u64) {
+    self.fbb_.push_slot::<u64>(BookChecksum::VT_HASH, hash, 0);
   }
   #[inline]
-  pub fn add_quantity(&mut self, quantity: f64) {
-    self.fbb_.push_slot::<f64>(Fill::VT_QUANTITY, quantity, 0.0);
+  pub fn add_order_count(&mut self, order_count: u64) {
+    self.fbb_.push_slot::<u64>(BookChecksum::VT_ORDER_COUNT, order_count, 0);
   }
   #[inline]
-  pub fn add_timestamp(&mut self, timestamp: u64) {
-    self.fbb_.push_slot::<u64>(Fill::VT_TIMESTAMP, timestamp, 0);
+  pub fn add_phase(&mut self, phase: MarketPhase) {
+    self.fbb_.push_slot::<MarketPhase>(BookChecksum::VT_PHASE, phase, MarketPhase::Open);
   }
   #[inline]
-  pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a, A>) -> FillBuilder<'a, 'b, A> {
+  pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a, A>) -> BookChecksumBuilder<'a, 'b, A> {
     let start = _fbb.start_table();
-    FillBuilder {
+    BookChecksumBuilder {
       fbb_: _fbb,
       start_: start,
     }
   }
   #[inline]
-  pub fn finish(self) -> flatbuffers::WIPOffset<Fill<'a>> {
+  pub fn finish(self) -> flatbuffers::WIPOffset<BookChecksum<'a>> {
     let o = self.fbb_.end_table(self.start_);
     flatbuffers::WIPOffset::new(o.value())
   }
 }
 
-impl core::fmt::Debug for Fill<'_> {
+impl core::fmt::Debug for BookChecksum<'_> {
   fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-    let mut ds = f.debug_struct("Fill");
-      ds.field("symbol", &self.symbol());
-      ds.field("buy_order_id", &self.buy_order_id());
-      ds.field("sell_order_id", &self.sell_order_id());
-      ds.field("price", &self.price());
-      ds.field("quantity", &self.quantity());
-      ds.field("timestamp", &self.timestamp());
+    let mut ds = f.debug_struct("BookChecksum");
+      ds.field("hash", &self.hash());
+      ds.field("order_count", &self.order_count());
+      ds.field("phase", &self.phase());
       ds.finish()
   }
 }
-pub enum OrderBookSnapshotOffset {}
+pub enum JournalFillOffset {}
 #[derive(Copy, Clone, PartialEq)]
 
-pub struct OrderBookSnapshot<'a> {
+pub struct JournalFill<'a> {
   pub _tab: flatbuffers::Table<'a>,
 }
 
-impl<'a> flatbuffers::Follow<'a> for OrderBookSnapshot<'a> {
-  type Inner = OrderBookSnapshot<'a>;
+impl<'a> flatbuffers::Follow<'a> for JournalFill<'a> {
+  type Inner = JournalFill<'a>;
   #[inline]
   unsafe fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
     Self { _tab: unsafe { flatbuffers::Table::new(buf, loc) } }
   }
 }
 
-impl<'a> OrderBookSnapshot<'a> {
-  pub const VT_SYMBOL: flatbuffers::VOffsetT = 4;
-  pub const VT_SEQUENCE: flatbuffers::VOffsetT = 6;
-  pub const VT_BIDS: flatbuffers::VOffsetT = 8;
-  pub const VT_ASKS: flatbuffers::VOffsetT = 10;
+impl<'a> JournalFill<'a> {
+  pub const VT_BUY_ORDER_ID: flatbuffers::VOffsetT = 4;
+  pub const VT_SELL_ORDER_ID: flatbuffers::VOffsetT = 6;
+  pub const VT_PRICE: flatbuffers::VOffsetT = 8;
+  pub const VT_QUANTITY: flatbuffers::VOffsetT = 10;
 
   #[inline]
   pub unsafe fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
-    OrderBookSnapshot { _tab: table }
+    JournalFill { _tab: table }
   }
   #[allow(unused_mut)]
   pub fn create<'bldr: 'args, 'args: 'mut_bldr, 'mut_bldr, A: flatbuffers::Allocator + 'bldr>(
     _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr, A>,
-    args: &'args OrderBookSnapshotArgs<'args>
-  ) -> flatbuffers::WIPOffset<OrderBookSnapshot<'bldr>> {
-    let mut builder = OrderBookSnapshotBuilder::new(_fbb);
-    builder.add_sequence(args.sequence);
-    if let Some(x) = args.asks { builder.add_asks(x); }
-    if let Some(x) = args.bids { builder.add_bids(x); }
-    if let Some(x) = args.symbol { builder.add_symbol(x); }
+    args: &'args JournalFillArgs<'args>
+  ) -> flatbuffers::WIPOffset<JournalFill<'bldr>> {
+    let mut builder = JournalFillBuilder::new(_fbb);
+    if let Some(x) = args.quantity { builder.add_quantity(x); }
+    if let Some(x) = args.price { builder.add_price(x); }
+    if let Some(x) = args.sell_order_id { builder.add_sell_order_id(x); }
+    if let Some(x) = args.buy_order_id { builder.add_buy_order_id(x); }
     builder.finish()
   }
 
 
   #[inline]
-  pub fn symbol(&self) -> Option<&'a str> {
+  pub fn buy_order_id(&self) -> Option<&'a Uuid> {
     // Safety:
     // Created from valid Table for this object
     // which contains a valid value in this slot
-    unsafe { self._tab.get::<flatbuffers::ForwardsUOffset<&str>>(OrderBookSnapshot::VT_SYMBOL, None)}
+    unsafe { self._tab.get::<Uuid>(JournalFill::VT_BUY_ORDER_ID, None)}
   }
   #[inline]
-  pub fn sequence(&self) -> u64 {
+  pub fn sell_order_id(&self) -> Option<&'a Uuid> {
     // Safety:
     // Created from valid Table for this object
     // which contains a valid value in this slot
-    unsafe { self._tab.get::<u64>(OrderBookSnapshot::VT_SEQUENCE, Some(0)).unwrap()}
+    unsafe { self._tab.get::<Uuid>(JournalFill::VT_SELL_ORDER_ID, None)}
   }
   #[inline]
-  pub fn bids(&self) -> Option<flatbuffers::Vector<'a, PriceLevel>> {
+  pub fn price(&self) -> Option<&'a Decimal> {
     // Safety:
     // Created from valid Table for this object
     // which contains a valid value in this slot
-    unsafe { self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<'a, PriceLevel>>>(OrderBookSnapshot::VT_BIDS, None)}
+    unsafe { self._tab.get::<Decimal>(JournalFill::VT_PRICE, None)}
   }
   #[inline]
-  pub fn asks(&self) -> Option<flatbuffers::Vector<'a, PriceLevel>> {
+  pub fn quantity(&self) -> Option<&'a Decimal> {
     // Safety:
     // Created from valid Table for this object
     // which contains a valid value in this slot
-    unsafe { self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<'a, PriceLevel>>>(OrderBookSnapshot::VT_ASKS, None)}
+    unsafe { self._tab.get::<Decimal>(JournalFill::VT_QUANTITY, None)}
   }
 }
 
-impl flatbuffers::Verifiable for OrderBookSnapshot<'_> {
+impl flatbuffers::Verifiable for JournalFill<'_> {
   #[inline]
   fn run_verifier(
     v: &mut flatbuffers::Verifier, pos: usize
   ) -> Result<(), flatbuffers::InvalidFlatbuffer> {
     use self::flatbuffers::Verifiable;
     v.visit_table(pos)?
-     .visit_field::<flatbuffers::ForwardsUOffset<&str>>("symbol", Self::VT_SYMBOL, false)?
-     .visit_field::<u64>("sequence", Self::VT_SEQUENCE, false)?
-     .visit_field::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<'_, PriceLevel>>>("bids", Self::VT_BIDS, false)?
-     .visit_field::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<'_, PriceLevel>>>("asks", Self::VT_ASKS, false)?
+     .visit_field::<Uuid>("buy_order_id", Self::VT_BUY_ORDER_ID, false)?
+     .visit_field::<Uuid>("sell_order_id", Self::VT_SELL_ORDER_ID, false)?
+     .visit_field::<Decimal>("price", Self::VT_PRICE, false)?
+     .visit_field::<Decimal>("quantity", Self::VT_QUANTITY, false)?
      .finish();
     Ok(())
   }
 }
-pub struct OrderBookSnapshotArgs<'a> {
-    pub symbol: Option<flatbuffers::WIPOffset<&'a str>>,
-    pub sequence: u64,
-    pub bids: Option<flatbuffers::WIPOffset<flatbuffers::Vector<'a, PriceLevel>>>,
-    pub asks: Option<flatbuffers::WIPOffset<flatbuffers::Vector<'a, PriceLevel>>>,
+pub struct JournalFillArgs<'a> {
+    pub buy_order_id: Option<&'a Uuid>,
+    pub sell_order_id: Option<&'a Uuid>,
+    pub price: Option<&'a Decimal>,
+    pub quantity: Option<&'a Decimal>,
 }
-impl<'a> Default for OrderBookSnapshotArgs<'a> {
+impl<'a> Default for JournalFillArgs<'a> {
   #[inline]
   fn default() -> Self {
-    OrderBookSnapshotArgs {
-      symbol: None,
-      sequence: 0,
-      bids: None,
-      asks: None,
+    JournalFillArgs {
+      buy_order_id: None,
+      sell_order_id: None,
+      price: None,
+      quantity: None,
     }
   }
 }
 
-pub struct OrderBookSnapshotBuilder<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> {
+pub struct JournalFillBuilder<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> {
   fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a, A>,
   start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
 }
-impl<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> OrderBookSnapshotBuilder<'a, 'b, A> {
+impl<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> JournalFillBuilder<'a, 'b, A> {
   #[inline]
-  pub fn add_symbol(&mut self, symbol: flatbuffers::WIPOffset<&'b  str>) {
-    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(OrderBookSnapshot::VT_SYMBOL, symbol);
+  pub fn add_buy_order_id(&mut self, buy_order_id: &Uuid) {
+    self.fbb_.push_slot_always::<&Uuid>(JournalFill::VT_BUY_ORDER_ID, buy_order_id);
   }
   #[inline]
-  pub fn add_sequence(&mut self, sequence: u64) {
-    self.fbb_.push_slot::<u64>(OrderBookSnapshot::VT_SEQUENCE, sequence, 0);
+  pub fn add_sell_order_id(&mut self, sell_order_id: &Uuid) {
+    self.fbb_.push_slot_always::<&Uuid>(JournalFill::VT_SELL_ORDER_ID, sell_order_id);
   }
   #[inline]
-  pub fn add_bids(&mut self, bids: flatbuffers::WIPOffset<flatbuffers::Vector<'b , PriceLevel>>) {
-    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(OrderBookSnapshot::VT_BIDS, bids);
+  pub fn add_price(&mut self, price: &Decimal) {
+    self.fbb_.push_slot_always::<&Decimal>(JournalFill::VT_PRICE, price);
   }
   #[inline]
-  pub fn add_asks(&mut self, asks: flatbuffers::WIPOffset<flatbuffers::Vector<'b , PriceLevel>>) {
-    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(OrderBookSnapshot::VT_ASKS, asks);
+  pub fn add_quantity(&mut self, quantity: &Decimal) {
+    self.fbb_.push_slot_always::<&Decimal>(JournalFill::VT_QUANTITY, quantity);
   }
   #[inline]
-  pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a, A>) -> OrderBookSnapshotBuilder<'a, 'b, A> {
+  pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a, A>) -> JournalFillBuilder<'a, 'b, A> {
     let start = _fbb.start_table();
-    OrderBookSnapshotBuilder {
+    JournalFillBuilder {
       fbb_: _fbb,
       start_: start,
     }
   }
   #[inline]
-  pub fn finish(self) -> flatbuffers::WIPOffset<OrderBookSnapshot<'a>> {
+  pub fn finish(self) -> flatbuffers::WIPOffset<JournalFill<'a>> {
     let o = self.fbb_.end_table(self.start_);
     flatbuffers::WIPOffset::new(o.value())
   }
 }
 
-impl core::fmt::Debug for OrderBookSnapshot<'_> {
+impl core::fmt::Debug for JournalFill<'_> {
   fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-    let mut ds = f.debug_struct("OrderBookSnapshot");
-      ds.field("symbol", &self.symbol());
-      ds.field("sequence", &self.sequence());
-      ds.field("bids", &self.bids());
-      ds.field("asks", &self.asks());
+    let mut ds = f.debug_struct("JournalFill");
+      ds.field("buy_order_id", &self.buy_order_id());
+      ds.field("sell_order_id", &self.sell_order_id());
+      ds.field("price", &self.price());
+      ds.field("quantity", &self.quantity());
       ds.finish()
   }
 }
-pub enum OrderBookDeltaOffset {}
+pub enum RiskLimitsOffset {}
 #[derive(Copy, Clone, PartialEq)]
 
-pub struct OrderBookDelta<'a> {
+pub struct RiskLimits<'a> {
   pub _tab: flatbuffers::Table<'a>,
 }
 
-impl<'a> flatbuffers::Follow<'a> for OrderBookDelta<'a> {
-  type Inner = OrderBookDelta<'a>;
+impl<'a> flatbuffers::Follow<'a> for RiskLimits<'a> {
+  type Inner = RiskLimits<'a>;
   #[inline]
   unsafe fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
     Self { _tab: unsafe { flatbuffers::Table::new(buf, loc) } }
   }
 }
 
-impl<'a> OrderBookDelta<'a> {
-  pub const VT_SYMBOL: flatbuffers::VOffsetT = 4;
-  pub const VT_SEQUENCE: flatbuffers::VOffsetT = 6;
-  pub const VT_DELTAS: flatbuffers::VOffsetT = 8;
+impl<'a> RiskLimits<'a> {
+  pub const VT_MAX_ORDER_QUANTITY: flatbuffers::VOffsetT = 4;
+  pub const VT_MAX_OPEN_ORDERS: flatbuffers::VOffsetT = 6;
+  pub const VT_MAX_NOTIONAL_PER_SECOND: flatbuffers::VOffsetT = 8;
+  pub const VT_PRICE_BAND_PERCENT: flatbuffers::VOffsetT = 10;
 
   #[inline]
   pub unsafe fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
-    OrderBookDelta { _tab: table }
+    RiskLimits { _tab: table }
   }
   #[allow(unused_mut)]
   pub fn create<'bldr: 'args, 'args: 'mut_bldr, 'mut_bldr, A: flatbuffers::Allocator + 'bldr>(
     _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr, A>,
-    args: &'args OrderBookDeltaArgs<'args>
-  ) -> flatbuffers::WIPOffset<OrderBookDelta<'bldr>> {
-    let mut builder = OrderBookDeltaBuilder::new(_fbb);
-    builder.add_sequence(args.sequence);
-    if let Some(x) = args.deltas { builder.add_deltas(x); }
-    if let Some(x) = args.symbol { builder.add_symbol(x); }
+    args: &'args RiskLimitsArgs<'args>
+  ) -> flatbuffers::WIPOffset<RiskLimits<'bldr>> {
+    let mut builder = RiskLimitsBuilder::new(_fbb);
+    if let Some(x) = args.max_open_orders { builder.add_max_open_orders(x); }
+    if let Some(x) = args.price_band_percent { builder.add_price_band_percent(x); }
+    if let Some(x) = args.max_notional_per_second { builder.add_max_notional_per_second(x); }
+    if let Some(x) = args.max_order_quantity { builder.add_max_order_quantity(x); }
     builder.finish()
   }
 
 
   #[inline]
-  pub fn symbol(&self) -> Option<&'a str> {
+  pub fn max_order_quantity(&self) -> Option<&'a Decimal> {
     // Safety:
     // Created from valid Table for this object
     // which contains a valid value in this slot
-    unsafe { self._tab.get::<flatbuffers::ForwardsUOffset<&str>>(OrderBookDelta::VT_SYMBOL, None)}
+    unsafe { self._tab.get::<Decimal>(RiskLimits::VT_MAX_ORDER_QUANTITY, None)}
   }
   #[inline]
-  pub fn sequence(&self) -> u64 {
+  pub fn max_open_orders(&self) -> Option<u64> {
     // Safety:
     // Created from valid Table for this object
     // which contains a valid value in this slot
-    unsafe { self._tab.get::<u64>(OrderBookDelta::VT_SEQUENCE, Some(0)).unwrap()}
+    unsafe { self._tab.get::<u64>(RiskLimits::VT_MAX_OPEN_ORDERS, None)}
   }
   #[inline]
-  pub fn deltas(&self) -> Option<flatbuffers::Vector<'a, LevelDelta>> {
+  pub fn max_notional_per_second(&self) -> Option<&'a Decimal> {
     // Safety:
     // Created from valid Table for this object
     // which contains a valid value in this slot
-    unsafe { self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<'a, LevelDelta>>>(OrderBookDelta::VT_DELTAS, None)}
+    unsafe { self._tab.get::<Decimal>(RiskLimits::VT_MAX_NOTIONAL_PER_SECOND, None)}
+  }
+  #[inline]
+  pub fn price_band_percent(&self) -> Option<&'a Decimal> {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<Decimal>(RiskLimits::VT_PRICE_BAND_PERCENT, None)}
   }
 }
 
-impl flatbuffers::Verifiable for OrderBookDelta<'_> {
+impl flatbuffers::Verifiable for RiskLimits<'_> {
   #[inline]
   fn run_verifier(
     v: &mut flatbuffers::Verifier, pos: usize
   ) -> Result<(), flatbuffers::InvalidFlatbuffer> {
     use self::flatbuffers::Verifiable;
     v.visit_table(pos)?
-     .visit_field::<flatbuffers::ForwardsUOffset<&str>>("symbol", Self::VT_SYMBOL, false)?
-     .visit_field::<u64>("sequence", Self::VT_SEQUENCE, false)?
-     .visit_field::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<'_, LevelDelta>>>("deltas", Self::VT_DELTAS, false)?
+     .visit_field::<Decimal>("max_order_quantity", Self::VT_MAX_ORDER_QUANTITY, false)?
+     .visit_field::<u64>("max_open_orders", Self::VT_MAX_OPEN_ORDERS, false)?
+     .visit_field::<Decimal>("max_notional_per_second", Self::VT_MAX_NOTIONAL_PER_SECOND, false)?
+     .visit_field::<Decimal>("price_band_percent", Self::VT_PRICE_BAND_PERCENT, false)?
      .finish();
     Ok(())
   }
 }
-pub struct OrderBookDeltaArgs<'a> {
-    pub symbol: Option<flatbuffers::WIPOffset<&'a str>>,
-    pub sequence: u64,
-    pub deltas: Option<flatbuffers::WIPOffset<flatbuffers::Vector<'a, LevelDelta>>>,
+pub struct RiskLimitsArgs<'a> {
+    pub max_order_quantity: Option<&'a Decimal>,
+    pub max_open_orders: Option<u64>,
+    pub max_notional_per_second: Option<&'a Decimal>,
+    pub price_band_percent: Option<&'a Decimal>,
 }
-impl<'a> Default for OrderBookDeltaArgs<'a> {
+impl<'a> Default for RiskLimitsArgs<'a> {
   #[inline]
   fn default() -> Self {
-    OrderBookDeltaArgs {
-      symbol: None,
-      sequence: 0,
-      deltas: None,
+    RiskLimitsArgs {
+      max_order_quantity: None,
+      max_open_orders: None,
+      max_notional_per_second: None,
+      price_band_percent: None,
     }
   }
 }
 
-pub struct OrderBookDeltaBuilder<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> {
+pub struct RiskLimitsBuilder<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> {
   fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a, A>,
   start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
 }
-impl<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> OrderBookDeltaBuilder<'a, 'b, A> {
+impl<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> RiskLimitsBuilder<'a, 'b, A> {
   #[inline]
-  pub fn add_symbol(&mut self, symbol: flatbuffers::WIPOffset<&'b  str>) {
-    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(OrderBookDelta::VT_SYMBOL, symbol);
+  pub fn add_max_order_quantity(&mut self, max_order_quantity: &Decimal) {
+    self.fbb_.push_slot_always::<&Decimal>(RiskLimits::VT_MAX_ORDER_QUANTITY, max_order_quantity);
   }
   #[inline]
-  pub fn add_sequence(&mut self, sequence: u64) {
-    self.fbb_.push_slot::<u64>(OrderBookDelta::VT_SEQUENCE, sequence, 0);
+  pub fn add_max_open_orders(&mut self, max_open_orders: u64) {
+    self.fbb_.push_slot_always::<u64>(RiskLimits::VT_MAX_OPEN_ORDERS, max_open_orders);
   }
   #[inline]
-  pub fn add_deltas(&mut self, deltas: flatbuffers::WIPOffset<flatbuffers::Vector<'b , LevelDelta>>) {
-    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(OrderBookDelta::VT_DELTAS, deltas);
+  pub fn add_max_notional_per_second(&mut self, max_notional_per_second: &Decimal) {
+    self.fbb_.push_slot_always::<&Decimal>(RiskLimits::VT_MAX_NOTIONAL_PER_SECOND, max_notional_per_second);
   }
   #[inline]
-  pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a, A>) -> OrderBookDeltaBuilder<'a, 'b, A> {
+  pub fn add_price_band_percent(&mut self, price_band_percent: &Decimal) {
+    self.fbb_.push_slot_always::<&Decimal>(RiskLimits::VT_PRICE_BAND_PERCENT, price_band_percent);
+  }
+  #[inline]
+  pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a, A>) -> RiskLimitsBuilder<'a, 'b, A> {
     let start = _fbb.start_table();
-    OrderBookDeltaBuilder {
+    RiskLimitsBuilder {
       fbb_: _fbb,
       start_: start,
     }
   }
   #[inline]
-  pub fn finish(self) -> flatbuffers::WIPOffset<OrderBookDelta<'a>> {
+  pub fn finish(self) -> flatbuffers::WIPOffset<RiskLimits<'a>> {
     let o = self.fbb_.end_table(self.start_);
     flatbuffers::WIPOffset::new(o.value())
   }
 }
 
-impl core::fmt::Debug for OrderBookDelta<'_> {
+impl core::fmt::Debug for RiskLimits<'_> {
   fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-    let mut ds = f.debug_struct("OrderBookDelta");
-      ds.field("symbol", &self.symbol());
-      ds.field("sequence", &self.sequence());
-      ds.field("deltas", &self.deltas());
+    let mut ds = f.debug_struct("RiskLimits");
+      ds.field("max_order_quantity", &self.max_order_quantity());
+      ds.field("max_open_orders", &self.max_open_orders());
+      ds.field("max_notional_per_second", &self.max_notional_per_second());
+      ds.field("price_band_percent", &self.price_band_percent());
       ds.finish()
   }
 }
-pub enum OrderCancelledOffset {}
+pub enum RiskControlOffset {}
 #[derive(Copy, Clone, PartialEq)]
 
-pub struct OrderCancelled<'a> {
+pub struct RiskControl<'a> {
   pub _tab: flatbuffers::Table<'a>,
 }
 
-impl<'a> flatbuffers::Follow<'a> for OrderCancelled<'a> {
-  type Inner = OrderCancelled<'a>;
+impl<'a> flatbuffers::Follow<'a> for RiskControl<'a> {
+  type Inner = RiskControl<'a>;
   #[inline]
   unsafe fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
     Self { _tab: unsafe { flatbuffers::Table::new(buf, loc) } }
   }
 }
 
-impl<'a> OrderCancelled<'a> {
-  pub const VT_ORDER_ID: flatbuffers::VOffsetT = 4;
-  pub const VT_FILLED_QUANTITY: flatbuffers::VOffsetT = 6;
+impl<'a> RiskControl<'a> {
+  pub const VT_OWNER: flatbuffers::VOffsetT = 4;
+  pub const VT_ACTION: flatbuffers::VOffsetT = 6;
+  pub const VT_LIMITS: flatbuffers::VOffsetT = 8;
 
   #[inline]
   pub unsafe fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
-    OrderCancelled { _tab: table }
+    RiskControl { _tab: table }
   }
   #[allow(unused_mut)]
   pub fn create<'bldr: 'args, 'args: 'mut_bldr, 'mut_bldr, A: flatbuffers::Allocator + 'bldr>(
     _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr, A>,
-    args: &'args OrderCancelledArgs<'args>
-  ) -> flatbuffers::WIPOffset<OrderCancelled<'bldr>> {
-    let mut builder = OrderCancelledBuilder::new(_fbb);
-    builder.add_filled_quantity(args.filled_quantity);
-    if let Some(x) = args.order_id { builder.add_order_id(x); }
+    args: &'args RiskControlArgs<'args>
+  ) -> flatbuffers::WIPOffset<RiskControl<'bldr>> {
+    let mut builder = RiskControlBuilder::new(_fbb);
+    if let Some(x) = args.limits { builder.add_limits(x); }
+    if let Some(x) = args.owner { builder.add_owner(x); }
+    builder.add_action(args.action);
     builder.finish()
   }
 
 
   #[inline]
-  pub fn order_id(&self) -> Option<&'a Uuid> {
+  pub fn owner(&self) -> Option<&'a Uuid> {
     // Safety:
     // Created from valid Table for this object
     // which contains a valid value in this slot
-    unsafe { self._tab.get::<Uuid>(OrderCancelled::VT_ORDER_ID, None)}
+    unsafe { self._tab.get::<Uuid>(RiskControl::VT_OWNER, None)}
+  }
+  #[inline]
+  pub fn action(&self) -> RiskAction {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<RiskAction>(RiskControl::VT_ACTION, Some(RiskAction::SetLimits)).unwrap()}
   }
   #[inline]
-  pub fn filled_quantity(&self) -> f64 {
+  pub fn limits(&self) -> Option<RiskLimits<'a>> {
     // Safety:
     // Created from valid Table for this object
     // which contains a valid value in this slot
-    unsafe { self._tab.get::<f64>(OrderCancelled::VT_FILLED_QUANTITY, Some(0.0)).unwrap()}
+    unsafe { self._tab.get::<flatbuffers::ForwardsUOffset<RiskLimits>>(RiskControl::VT_LIMITS, None)}
   }
 }
 
-impl flatbuffers::Verifiable for OrderCancelled<'_> {
+impl flatbuffers::Verifiable for RiskControl<'_> {
   #[inline]
   fn run_verifier(
     v: &mut flatbuffers::Verifier, pos: usize
   ) -> Result<(), flatbuffers::InvalidFlatbuffer> {
     use self::flatbuffers::Verifiable;
     v.visit_table(pos)?
-     .visit_field::<Uuid>("order_id", Self::VT_ORDER_ID, false)?
-     .visit_field::<f64>("filled_quantity", Self::VT_FILLED_QUANTITY, false)?
+     .visit_field::<Uuid>("owner", Self::VT_OWNER, false)?
+     .visit_field::<RiskAction>("action", Self::VT_ACTION, false)?
+     .visit_field::<flatbuffers::ForwardsUOffset<RiskLimits>>("limits", Self::VT_LIMITS, false)?
      .finish();
     Ok(())
   }
 }
-pub struct OrderCancelledArgs<'a> {
-    pub order_id: Option<&'a Uuid>,
-    pub filled_quantity: f64,
+pub struct RiskControlArgs<'a> {
+    pub owner: Option<&'a Uuid>,
+    pub action: RiskAction,
+    pub limits: Option<flatbuffers::WIPOffset<RiskLimits<'a>>>,
 }
-impl<'a> Default for OrderCancelledArgs<'a> {
+impl<'a> Default for RiskControlArgs<'a> {
   #[inline]
   fn default() -> Self {
-    OrderCancelledArgs {
-      order_id: None,
-      filled_quantity: 0.0,
+    RiskControlArgs {
+      owner: None,
+      action: RiskAction::SetLimits,
+      limits: None,
     }
   }
 }
 
-pub struct OrderCancelledBuilder<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> {
+pub struct RiskControlBuilder<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> {
   fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a, A>,
   start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
 }
-impl<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> OrderCancelledBuilder<'a, 'b, A> {
+impl<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> RiskControlBuilder<'a, 'b, A> {
   #[inline]
-  pub fn add_order_id(&mut self, order_id: &Uuid) {
-    self.fbb_.push_slot_always::<&Uuid>(OrderCancelled::VT_ORDER_ID, order_id);
+  pub fn add_owner(&mut self, owner: &Uuid) {
+    self.fbb_.push_slot_always::<&Uuid>(RiskControl::VT_OWNER, owner);
   }
   #[inline]
-  pub fn add_filled_quantity(&mut self, filled_quantity: f64) {
-    self.fbb_.push_slot::<f64>(OrderCancelled::VT_FILLED_QUANTITY, filled_quantity, 0.0);
+  pub fn add_action(&mut self, action: RiskAction) {
+    self.fbb_.push_slot::<RiskAction>(RiskControl::VT_ACTION, action, RiskAction::SetLimits);
   }
   #[inline]
-  pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a, A>) -> OrderCancelledBuilder<'a, 'b, A> {
+  pub fn add_limits(&mut self, limits: flatbuffers::WIPOffset<RiskLimits<'b >>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<RiskLimits>>(RiskControl::VT_LIMITS, limits);
+  }
+  #[inline]
+  pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a, A>) -> RiskControlBuilder<'a, 'b, A> {
     let start = _fbb.start_table();
-    OrderCancelledBuilder {
+    RiskControlBuilder {
       fbb_: _fbb,
       start_: start,
     }
   }
   #[inline]
-  pub fn finish(self) -> flatbuffers::WIPOffset<OrderCancelled<'a>> {
+  pub fn finish(self) -> flatbuffers::WIPOffset<RiskControl<'a>> {
     let o = self.fbb_.end_table(self.start_);
     flatbuffers::WIPOffset::new(o.value())
   }
 }
 
-impl core::fmt::Debug for OrderCancelled<'_> {
+impl core::fmt::Debug for RiskControl<'_> {
   fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-    let mut ds = f.debug_struct("OrderCancelled");
-      ds.field("order_id", &self.order_id());
-      ds.field("filled_quantity", &self.filled_quantity());
+    let mut ds = f.debug_struct("RiskControl");
+      ds.field("owner", &self.owner());
+      ds.field("action", &self.action());
+      ds.field("limits", &self.limits());
       ds.finish()
   }
 }
-pub enum OrderFilledOffset {}
+pub enum JournalEntryOffset {}
 #[derive(Copy, Clone, PartialEq)]
 
-pub struct OrderFilled<'a> {
+pub struct JournalEntry<'a> {
   pub _tab: flatbuffers::Table<'a>,
 }
 
-impl<'a> flatbuffers::Follow<'a> for OrderFilled<'a> {
-  type Inner = OrderFilled<'a>;
+impl<'a> flatbuffers::Follow<'a> for JournalEntry<'a> {
+  type Inner = JournalEntry<'a>;
   #[inline]
   unsafe fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
     Self { _tab: unsafe { flatbuffers::Table::new(buf, loc) } }
   }
 }
 
-impl<'a> OrderFilled<'a> {
-  pub const VT_ORDER_ID: flatbuffers::VOffsetT = 4;
+impl<'a> JournalEntry<'a> {
+  pub const VT_SEQUENCE: flatbuffers::VOffsetT = 4;
+  pub const VT_RECORD_TYPE: flatbuffers::VOffsetT = 6;
+  pub const VT_RECORD: flatbuffers::VOffsetT = 8;
 
   #[inline]
   pub unsafe fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
-    OrderFilled { _tab: table }
+    JournalEntry { _tab: table }
   }
   #[allow(unused_mut)]
   pub fn create<'bldr: 'args, 'args: 'mut_bldr, 'mut_bldr, A: flatbuffers::Allocator + 'bldr>(
     _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr, A>,
-    args: &'args OrderFilledArgs<'args>
-  ) -> flatbuffers::WIPOffset<OrderFilled<'bldr>> {
-    let mut builder = OrderFilledBuilder::new(_fbb);
-    if let Some(x) = args.order_id { builder.add_order_id(x); }
+    args: &'args JournalEntryArgs
+  ) -> flatbuffers::WIPOffset<JournalEntry<'bldr>> {
+    let mut builder = JournalEntryBuilder::new(_fbb);
+    builder.add_sequence(args.sequence);
+    if let Some(x) = args.record { builder.add_record(x); }
+    builder.add_record_type(args.record_type);
     builder.finish()
   }
 
 
   #[inline]
-  pub fn order_id(&self) -> Option<&'a Uuid> {
+  pub fn sequence(&self) -> u64 {
     // Safety:
     // Created from valid Table for this object
     // which contains a valid value in this slot
-    unsafe { self._tab.get::<Uuid>(OrderFilled::VT_ORDER_ID, None)}
+    unsafe { self._tab.get::<u64>(JournalEntry::VT_SEQUENCE, Some(0)).unwrap()}
+  }
+  #[inline]
+  pub fn record_type(&self) -> JournalRecord {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<JournalRecord>(JournalEntry::VT_RECORD_TYPE, Some(JournalRecord::NONE)).unwrap()}
+  }
+  #[inline]
+  pub fn record(&self) -> Option<flatbuffers::Table<'a>> {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Table<'a>>>(JournalEntry::VT_RECORD, None)}
+  }
+  #[inline]
+  #[allow(non_snake_case)]
+  pub fn record_as_order_new(&self) -> Option<OrderNew<'a>> {
+    if self.record_type() == JournalRecord::OrderNew {
+      self.record().map(|t| {
+       // Safety:
+       // Created from a valid Table for this object
+       // Which contains a valid union in this slot
+       unsafe { OrderNew::init_from_table(t) }
+     })
+    } else {
+      None
+    }
+  }
+
+  #[inline]
+  #[allow(non_snake_case)]
+  pub fn record_as_order_cancel(&self) -> Option<OrderCancel<'a>> {
+    if self.record_type() == JournalRecord::OrderCancel {
+      self.record().map(|t| {
+       // Safety:
+       // Created from a valid Table for this object
+       // Which contains a valid union in this slot
+       unsafe { OrderCancel::init_from_table(t) }
+     })
+    } else {
+      None
+    }
+  }
+
+  #[inline]
+  #[allow(non_snake_case)]
+  pub fn record_as_book_checksum(&self) -> Option<BookChecksum<'a>> {
+    if self.record_type() == JournalRecord::BookChecksum {
+      self.record().map(|t| {
+       // Safety:
+       // Created from a valid Table for this object
+       // Which contains a valid union in this slot
+       unsafe { BookChecksum::init_from_table(t) }
+     })
+    } else {
+      None
+    }
+  }
+
+  #[inline]
+  #[allow(non_snake_case)]
+  pub fn record_as_journal_fill(&self) -> Option<JournalFill<'a>> {
+    if self.record_type() == JournalRecord::JournalFill {
+      self.record().map(|t| {
+       // Safety:
+       // Created from a valid Table for this object
+       // Which contains a valid union in this slot
+       unsafe { JournalFill::init_from_table(t) }
+     })
+    } else {
+      None
+    }
+  }
+
+  #[inline]
+  #[allow(non_snake_case)]
+  pub fn record_as_order_rejected(&self) -> Option<OrderRejected<'a>> {
+    if self.record_type() == JournalRecord::OrderRejected {
+      self.record().map(|t| {
+       // Safety:
+       // Created from a valid Table for this object
+       // Which contains a valid union in this slot
+       unsafe { OrderRejected::init_from_table(t) }
+     })
+    } else {
+      None
+    }
+  }
+
+  #[inline]
+  #[allow(non_snake_case)]
+  pub fn record_as_risk_control(&self) -> Option<RiskControl<'a>> {
+    if self.record_type() == JournalRecord::RiskControl {
+      self.record().map(|t| {
+       // Safety:
+       // Created from a valid Table for this object
+       // Which contains a valid union in this slot
+       unsafe { RiskControl::init_from_table(t) }
+     })
+    } else {
+      None
+    }
   }
+
 }
 
-impl flatbuffers::Verifiable for OrderFilled<'_> {
+impl flatbuffers::Verifiable for JournalEntry<'_> {
   #[inline]
   fn run_verifier(
     v: &mut flatbuffers::Verifier, pos: usize
   ) -> Result<(), flatbuffers::InvalidFlatbuffer> {
     use self::flatbuffers::Verifiable;
     v.visit_table(pos)?
-     .visit_field::<Uuid>("order_id", Self::VT_ORDER_ID, false)?
+     .visit_field::<u64>("sequence", Self::VT_SEQUENCE, false)?
+     .visit_union::<JournalRecord, _>("record_type", Self::VT_RECORD_TYPE, "record", Self::VT_RECORD, false, |key, v, pos| {
+        match key {
+          JournalRecord::OrderNew => v.verify_union_variant::<flatbuffers::ForwardsUOffset<OrderNew>>("JournalRecord::OrderNew", pos),
+          JournalRecord::OrderCancel => v.verify_union_variant::<flatbuffers::ForwardsUOffset<OrderCancel>>("JournalRecord::OrderCancel", pos),
+          JournalRecord::BookChecksum => v.verify_union_variant::<flatbuffers::ForwardsUOffset<BookChecksum>>("JournalRecord::BookChecksum", pos),
+          JournalRecord::JournalFill => v.verify_union_variant::<flatbuffers::ForwardsUOffset<JournalFill>>("JournalRecord::JournalFill", pos),
+          JournalRecord::OrderRejected => v.verify_union_variant::<flatbuffers::ForwardsUOffset<OrderRejected>>("JournalRecord::OrderRejected", pos),
+          JournalRecord::RiskControl => v.verify_union_variant::<flatbuffers::ForwardsUOffset<RiskControl>>("JournalRecord::RiskControl", pos),
+          _ => Ok(()),
+        }
+     })?
      .finish();
     Ok(())
   }
 }
-pub struct OrderFilledArgs<'a> {
-    pub order_id: Option<&'a Uuid>,
+pub struct JournalEntryArgs {
+    pub sequence: u64,
+    pub record_type: JournalRecord,
+    pub record: Option<flatbuffers::WIPOffset<flatbuffers::UnionWIPOffset>>,
 }
-impl<'a> Default for OrderFilledArgs<'a> {
+impl<'a> Default for JournalEntryArgs {
   #[inline]
   fn default() -> Self {
-    OrderFilledArgs {
-      order_id: None,
+    JournalEntryArgs {
+      sequence: 0,
+      record_type: JournalRecord::NONE,
+      record: None,
     }
   }
 }
 
-pub struct OrderFilledBuilder<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> {
+pub struct JournalEntryBuilder<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> {
   fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a, A>,
   start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
 }
-impl<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> OrderFilledBuilder<'a, 'b, A> {
+impl<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> JournalEntryBuilder<'a, 'b, A> {
   #[inline]
-  pub fn add_order_id(&mut self, order_id: &Uuid) {
-    self.fbb_.push_slot_always::<&Uuid>(OrderFilled::VT_ORDER_ID, order_id);
+  pub fn add_sequence(&mut self, sequence: u64) {
+    self.fbb_.push_slot::<u64>(JournalEntry::VT_SEQUENCE, sequence, 0);
   }
   #[inline]
-  pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a, A>) -> OrderFilledBuilder<'a, 'b, A> {
+  pub fn add_record_type(&mut self, record_type: JournalRecord) {
+    self.fbb_.push_slot::<JournalRecord>(JournalEntry::VT_RECORD_TYPE, record_type, JournalRecord::NONE);
+  }
+  #[inline]
+  pub fn add_record(&mut self, record: flatbuffers::WIPOffset<flatbuffers::UnionWIPOffset>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(JournalEntry::VT_RECORD, record);
+  }
+  #[inline]
+  pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a, A>) -> JournalEntryBuilder<'a, 'b, A> {
     let start = _fbb.start_table();
-    OrderFilledBuilder {
+    JournalEntryBuilder {
       fbb_: _fbb,
       start_: start,
     }
   }
   #[inline]
-  pub fn finish(self) -> flatbuffers::WIPOffset<OrderFilled<'a>> {
+  pub fn finish(self) -> flatbuffers::WIPOffset<JournalEntry<'a>> {
     let o = self.fbb_.end_table(self.start_);
     flatbuffers::WIPOffset::new(o.value())
   }
 }
 
-impl core::fmt::Debug for OrderFilled<'_> {
+impl core::fmt::Debug for JournalEntry<'_> {
   fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-    let mut ds = f.debug_struct("OrderFilled");
-      ds.field("order_id", &self.order_id());
+    let mut ds = f.debug_struct("JournalEntry");
+      ds.field("sequence", &self.sequence());
+      ds.field("record_type", &self.record_type());
+      match self.record_type() {
+        JournalRecord::OrderNew => {
+          if let Some(x) = self.record_as_order_new() {
+            ds.field("record", &x)
+          } else {
+            ds.field("record", &"InvalidFlatbuffer: Union discriminant does not match value.")
+          }
+        },
+        JournalRecord::OrderCancel => {
+          if let Some(x) = self.record_as_order_cancel() {
+            ds.field("record", &x)
+          } else {
+            ds.field("record", &"InvalidFlatbuffer: Union discriminant does not match value.")
+          }
+        },
+        JournalRecord::BookChecksum => {
+          if let Some(x) = self.record_as_book_checksum() {
+            ds.field("record", &x)
+          } else {
+            ds.field("record", &"InvalidFlatbuffer: Union discriminant does not match value.")
+          }
+        },
+        JournalRecord::JournalFill => {
+          if let Some(x) = self.record_as_journal_fill() {
+            ds.field("record", &x)
+          } else {
+            ds.field("record", &"InvalidFlatbuffer: Union discriminant does not match value.")
+          }
+        },
+        JournalRecord::OrderRejected => {
+          if let Some(x) = self.record_as_order_rejected() {
+            ds.field("record", &x)
+          } else {
+            ds.field("record", &"InvalidFlatbuffer: Union discriminant does not match value.")
+          }
+        },
+        JournalRecord::RiskControl => {
+          if let Some(x) = self.record_as_risk_control() {
+            ds.field("record", &x)
+          } else {
+            ds.field("record", &"InvalidFlatbuffer: Union discriminant does not match value.")
+          }
+        },
+        _ => {
+          let x: Option<()> = None;
+          ds.field("record", &x)
+        },
+      };
       ds.finish()
   }
 }
@@ -1539,6 +4179,66 @@ impl<'a> MarketEvent<'a> {
     }
   }
 
+  #[inline]
+  #[allow(non_snake_case)]
+  pub fn payload_as_market_status(&self) -> Option<MarketStatus<'a>> {
+    if self.payload_type() == EventPayload::MarketStatus {
+      self.payload().map(|t| {
+       // Safety:
+       // Created from a valid Table for this object
+       // Which contains a valid union in this slot
+       unsafe { MarketStatus::init_from_table(t) }
+     })
+    } else {
+      None
+    }
+  }
+
+  #[inline]
+  #[allow(non_snake_case)]
+  pub fn payload_as_order_rejected(&self) -> Option<OrderRejected<'a>> {
+    if self.payload_type() == EventPayload::OrderRejected {
+      self.payload().map(|t| {
+       // Safety:
+       // Created from a valid Table for this object
+       // Which contains a valid union in this slot
+       unsafe { OrderRejected::init_from_table(t) }
+     })
+    } else {
+      None
+    }
+  }
+
+  #[inline]
+  #[allow(non_snake_case)]
+  pub fn payload_as_trade_bust(&self) -> Option<TradeBust<'a>> {
+    if self.payload_type() == EventPayload::TradeBust {
+      self.payload().map(|t| {
+       // Safety:
+       // Created from a valid Table for this object
+       // Which contains a valid union in this slot
+       unsafe { TradeBust::init_from_table(t) }
+     })
+    } else {
+      None
+    }
+  }
+
+  #[inline]
+  #[allow(non_snake_case)]
+  pub fn payload_as_position_update(&self) -> Option<PositionUpdate<'a>> {
+    if self.payload_type() == EventPayload::PositionUpdate {
+      self.payload().map(|t| {
+       // Safety:
+       // Created from a valid Table for this object
+       // Which contains a valid union in this slot
+       unsafe { PositionUpdate::init_from_table(t) }
+     })
+    } else {
+      None
+    }
+  }
+
 }
 
 impl flatbuffers::Verifiable for MarketEvent<'_> {
@@ -1555,6 +4255,10 @@ impl flatbuffers::Verifiable for MarketEvent<'_> {
           EventPayload::OrderBookDelta => v.verify_union_variant::<flatbuffers::ForwardsUOffset<OrderBookDelta>>("EventPayload::OrderBookDelta", pos),
           EventPayload::OrderCancelled => v.verify_union_variant::<flatbuffers::ForwardsUOffset<OrderCancelled>>("EventPayload::OrderCancelled", pos),
           EventPayload::OrderFilled => v.verify_union_variant::<flatbuffers::ForwardsUOffset<OrderFilled>>("EventPayload::OrderFilled", pos),
+          EventPayload::MarketStatus => v.verify_union_variant::<flatbuffers::ForwardsUOffset<MarketStatus>>("EventPayload::MarketStatus", pos),
+          EventPayload::OrderRejected => v.verify_union_variant::<flatbuffers::ForwardsUOffset<OrderRejected>>("EventPayload::OrderRejected", pos),
+          EventPayload::TradeBust => v.verify_union_variant::<flatbuffers::ForwardsUOffset<TradeBust>>("EventPayload::TradeBust", pos),
+          EventPayload::PositionUpdate => v.verify_union_variant::<flatbuffers::ForwardsUOffset<PositionUpdate>>("EventPayload::PositionUpdate", pos),
           _ => Ok(()),
         }
      })?
@@ -1644,6 +4348,34 @@ impl core::fmt::Debug for MarketEvent<'_> {
             ds.field("payload", &"InvalidFlatbuffer: Union discriminant does not match value.")
           }
         },
+        EventPayload::MarketStatus => {
+          if let Some(x) = self.payload_as_market_status() {
+            ds.field("payload", &x)
+          } else {
+            ds.field("payload", &"InvalidFlatbuffer: Union discriminant does not match value.")
+          }
+        },
+        EventPayload::OrderRejected => {
+          if let Some(x) = self.payload_as_order_rejected() {
+            ds.field("payload", &x)
+          } else {
+            ds.field("payload", &"InvalidFlatbuffer: Union discriminant does not match value.")
+          }
+        },
+        EventPayload::TradeBust => {
+          if let Some(x) = self.payload_as_trade_bust() {
+            ds.field("payload", &x)
+          } else {
+            ds.field("payload", &"InvalidFlatbuffer: Union discriminant does not match value.")
+          }
+        },
+        EventPayload::PositionUpdate => {
+          if let Some(x) = self.payload_as_position_update() {
+            ds.field("payload", &x)
+          } else {
+            ds.field("payload", &"InvalidFlatbuffer: Union discriminant does not match value.")
+          }
+        },
         _ => {
           let x: Option<()> = None;
           ds.field("payload", &x)
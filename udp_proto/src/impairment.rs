@@ -0,0 +1,518 @@
+//! Network impairment for tests.
+//!
+//! `ImpairedLink` is a loopback proxy between a sender and a receiver that
+//! drops, duplicates, reorders, delays and corrupts the packets passing it,
+//! at rates set separately for each direction and changeable while it runs.
+//! Every decision comes from a seeded RNG, so a seed impairs the same
+//! packets of the same traffic again. Which packets make up the traffic
+//! still depends on timing: heartbeats, NAKs and resends come and go with
+//! the clock.
+//!
+//! The link relays for one sender at a time, replies from the receiver go
+//! to wherever the last forward packet came from.
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashSet};
+use std::io;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+
+use crate::protocol::{PacketHeader, MAX_MTU};
+
+/// Longest a reordered packet waits for another one to overtake it
+const REORDER_HOLD: Duration = Duration::from_millis(10);
+/// Longest a relay thread waits for packets before checking for shutdown
+const MAX_WAIT: Duration = Duration::from_millis(10);
+/// Shortest wait, a zero read timeout is refused
+const MIN_WAIT: Duration = Duration::from_micros(10);
+
+/// Picks forward data packets, see `LinkConfig::lose`
+pub type PacketFilter = Box<dyn Fn(&PacketHeader) -> bool + Send>;
+
+/// Release time, queueing order and packet, earliest first
+type Scheduled = Reverse<(Instant, u64, Vec<u8>)>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// Sender to receiver
+    Forward,
+    /// Receiver to sender: NAKs and session replies
+    Backward,
+}
+
+/// What happens to packets going one way. Rates are shares of the packets,
+/// from 0.0 (none) to 1.0 (all).
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Impairment {
+    pub drop_rate: f64,
+    /// Packets sent twice
+    pub duplicate_rate: f64,
+    /// Packets held back until the next one has passed
+    pub reorder_rate: f64,
+    /// Packets with one bit flipped
+    pub corrupt_rate: f64,
+    /// Added to every packet
+    pub delay: Duration,
+    /// Up to this much more delay, picked per packet
+    pub jitter: Duration,
+}
+
+/// Configuration for `ImpairedLink`
+#[derive(Default)]
+pub struct LinkConfig {
+    pub seed: u64,
+    pub forward: Impairment,
+    pub backward: Impairment,
+    /// Forward data packets lost the first time they pass, on top of the
+    /// rates, for tests that need a particular gap. Resent copies get through.
+    pub lose: Option<PacketFilter>,
+}
+
+/// Counters of one direction of a link
+#[derive(Debug, Default)]
+pub struct ImpairmentStats {
+    /// Packets and copies sent on
+    pub forwarded: AtomicU64,
+    pub dropped: AtomicU64,
+    pub duplicated: AtomicU64,
+    pub reordered: AtomicU64,
+    pub corrupted: AtomicU64,
+}
+
+impl ImpairmentStats {
+    pub fn snapshot(&self) -> ImpairmentStatsSnapshot {
+        ImpairmentStatsSnapshot {
+            forwarded: self.forwarded.load(Ordering::Relaxed),
+            dropped: self.dropped.load(Ordering::Relaxed),
+            duplicated: self.duplicated.load(Ordering::Relaxed),
+            reordered: self.reordered.load(Ordering::Relaxed),
+            corrupted: self.corrupted.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ImpairmentStatsSnapshot {
+    pub forwarded: u64,
+    pub dropped: u64,
+    pub duplicated: u64,
+    pub reordered: u64,
+    pub corrupted: u64,
+}
+
+/// SplitMix64, the same sequence on every platform and plenty for picking
+/// packets
+struct Rng(u64);
+
+impl Rng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    fn chance(&mut self, rate: f64) -> bool {
+        ((self.next_u64() >> 11) as f64 / (1u64 << 53) as f64) < rate
+    }
+}
+
+/// The impairments of one direction
+struct Impairer {
+    rng: Rng,
+    /// Reordered packet waiting for the next one, with its release time
+    held: Option<(Instant, Vec<u8>)>,
+}
+
+impl Impairer {
+    fn new(seed: u64) -> Self {
+        Self {
+            rng: Rng(seed),
+            held: None,
+        }
+    }
+
+    /// Impair a packet, pushing what is to be sent and when to `out`
+    fn process(
+        &mut self,
+        impairment: &Impairment,
+        mut packet: Vec<u8>,
+        now: Instant,
+        stats: &ImpairmentStats,
+        out: &mut Vec<(Instant, Vec<u8>)>,
+    ) {
+        // Every roll is made for every packet, so the fate of a packet
+        // doesn't depend on the rates of the others
+        let drop = self.rng.chance(impairment.drop_rate);
+        let corrupt = self.rng.chance(impairment.corrupt_rate);
+        let duplicate = self.rng.chance(impairment.duplicate_rate);
+        let reorder = self.rng.chance(impairment.reorder_rate);
+        let jitter = self.rng.next_u64() % (impairment.jitter.as_nanos() as u64 + 1);
+        let bit = self.rng.next_u64();
+
+        if drop {
+            stats.dropped.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+        if corrupt && !packet.is_empty() {
+            let bit = (bit % (packet.len() as u64 * 8)) as usize;
+            packet[bit / 8] ^= 1 << (bit % 8);
+            stats.corrupted.fetch_add(1, Ordering::Relaxed);
+        }
+
+        let at = now + impairment.delay + Duration::from_nanos(jitter);
+        if duplicate {
+            out.push((at, packet.clone()));
+            stats.duplicated.fetch_add(1, Ordering::Relaxed);
+        }
+        if reorder && self.held.is_none() {
+            self.held = Some((at, packet));
+            stats.reordered.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+        out.push((at, packet));
+        if let Some((held_at, held)) = self.held.take() {
+            out.push((at.max(held_at), held));
+        }
+    }
+
+    /// The held packet once nothing came to overtake it
+    fn release_held(&mut self, now: Instant, out: &mut Vec<(Instant, Vec<u8>)>) {
+        if let Some((at, _)) = &self.held {
+            if now >= *at + REORDER_HOLD {
+                out.extend(self.held.take());
+            }
+        }
+    }
+}
+
+/// Loopback proxy impairing the packets between a sender and a receiver.
+/// The sender sends to `addr()` instead of the receiver. Stops when dropped.
+pub struct ImpairedLink {
+    addr: SocketAddr,
+    impairments: Arc<Mutex<[Impairment; 2]>>,
+    stats: Arc<[ImpairmentStats; 2]>,
+    running: Arc<AtomicBool>,
+    handles: Vec<JoinHandle<()>>,
+}
+
+impl ImpairedLink {
+    /// Start relaying to `target`, a thread for each direction
+    pub fn new(target: SocketAddr, config: LinkConfig) -> io::Result<Self> {
+        let loopback = match target {
+            SocketAddr::V4(_) => SocketAddr::from((Ipv4Addr::LOCALHOST, 0)),
+            SocketAddr::V6(_) => SocketAddr::from((Ipv6Addr::LOCALHOST, 0)),
+        };
+        let upstream = UdpSocket::bind(loopback)?;
+        let downstream = UdpSocket::bind(loopback)?;
+        let addr = upstream.local_addr()?;
+
+        let impairments = Arc::new(Mutex::new([config.forward, config.backward]));
+        let stats = Arc::new([ImpairmentStats::default(), ImpairmentStats::default()]);
+        let running = Arc::new(AtomicBool::new(true));
+        let sender = Arc::new(Mutex::new(None));
+        let relay = |direction, recv_socket, send_socket, dest, seed| Relay {
+            direction,
+            recv_socket,
+            send_socket,
+            dest,
+            sender: None,
+            impairments: impairments.clone(),
+            stats: stats.clone(),
+            impairer: Impairer::new(seed),
+            lose: None,
+            lost: HashSet::new(),
+            queue: BinaryHeap::new(),
+            queued: 0,
+        };
+        let forward = Relay {
+            sender: Some(sender.clone()),
+            lose: config.lose,
+            ..relay(
+                Direction::Forward,
+                upstream.try_clone()?,
+                downstream.try_clone()?,
+                Arc::new(Mutex::new(Some(target))),
+                config.seed,
+            )
+        };
+        let backward = relay(
+            Direction::Backward,
+            downstream,
+            upstream,
+            sender,
+            !config.seed,
+        );
+
+        let mut handles = Vec::with_capacity(2);
+        for (name, relay) in [
+            ("impaired-link-fwd", forward),
+            ("impaired-link-back", backward),
+        ] {
+            let running = running.clone();
+            let handle = thread::Builder::new()
+                .name(name.into())
+                .spawn(move || relay.run(&running))?;
+            handles.push(handle);
+        }
+
+        Ok(Self {
+            addr,
+            impairments,
+            stats,
+            running,
+            handles,
+        })
+    }
+
+    /// Where the sender sends to
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Change the impairments of one direction, from the next packet on
+    pub fn set_impairment(&self, direction: Direction, impairment: Impairment) {
+        self.impairments.lock()[direction as usize] = impairment;
+    }
+
+    pub fn stats(&self, direction: Direction) -> ImpairmentStatsSnapshot {
+        self.stats[direction as usize].snapshot()
+    }
+}
+
+impl Drop for ImpairedLink {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+        for handle in self.handles.drain(..) {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// One direction of a link, run by its own thread
+struct Relay {
+    direction: Direction,
+    recv_socket: UdpSocket,
+    /// The other socket of the link, so packets come from the address the
+    /// other end knows
+    send_socket: UdpSocket,
+    /// The receiver, or the sender once it sent something
+    dest: Arc<Mutex<Option<SocketAddr>>>,
+    /// The sender's address, recorded by the forward relay
+    sender: Option<Arc<Mutex<Option<SocketAddr>>>>,
+    impairments: Arc<Mutex<[Impairment; 2]>>,
+    stats: Arc<[ImpairmentStats; 2]>,
+    impairer: Impairer,
+    lose: Option<PacketFilter>,
+    /// `packet_seq`s already lost to `lose`
+    lost: HashSet<u64>,
+    /// Packets waiting for their release time, in the order they were queued
+    /// when due at the same time
+    queue: BinaryHeap<Scheduled>,
+    queued: u64,
+}
+
+impl Relay {
+    fn run(mut self, running: &AtomicBool) {
+        let mut buf = vec![0u8; MAX_MTU];
+        let mut out = Vec::new();
+
+        while running.load(Ordering::Relaxed) {
+            // Wait for the next packet until something is due
+            let now = Instant::now();
+            let wait = self
+                .next_due()
+                .map_or(MAX_WAIT, |at| at.saturating_duration_since(now))
+                .clamp(MIN_WAIT, MAX_WAIT);
+            let _ = self.recv_socket.set_read_timeout(Some(wait));
+
+            if let Ok((len, from)) = self.recv_socket.recv_from(&mut buf) {
+                if let Some(sender) = &self.sender {
+                    *sender.lock() = Some(from);
+                }
+                if !self.lose_scripted(&buf[..len]) {
+                    let impairment = self.impairments.lock()[self.direction as usize];
+                    let stats = &self.stats[self.direction as usize];
+                    self.impairer.process(
+                        &impairment,
+                        buf[..len].to_vec(),
+                        Instant::now(),
+                        stats,
+                        &mut out,
+                    );
+                }
+            }
+
+            let now = Instant::now();
+            self.impairer.release_held(now, &mut out);
+            for (at, packet) in out.drain(..) {
+                self.queue.push(Reverse((at, self.queued, packet)));
+                self.queued += 1;
+            }
+            while let Some(Reverse((at, ..))) = self.queue.peek() {
+                if *at > now {
+                    break;
+                }
+                let Reverse((_, _, packet)) = self.queue.pop().unwrap();
+                self.send(&packet);
+            }
+        }
+    }
+
+    fn next_due(&self) -> Option<Instant> {
+        let queued = self.queue.peek().map(|Reverse((at, ..))| *at);
+        let held = self
+            .impairer
+            .held
+            .as_ref()
+            .map(|(at, _)| *at + REORDER_HOLD);
+        queued.into_iter().chain(held).min()
+    }
+
+    /// Whether `lose` takes this forward packet
+    fn lose_scripted(&mut self, packet: &[u8]) -> bool {
+        let Some(lose) = &self.lose else {
+            return false;
+        };
+        let lost = match PacketHeader::read_from(packet) {
+            Ok(h) => !h.is_heartbeat() && self.lost.insert(h.packet_seq) && lose(&h),
+            Err(_) => false,
+        };
+        if lost {
+            self.stats[self.direction as usize]
+                .dropped
+                .fetch_add(1, Ordering::Relaxed);
+        }
+        lost
+    }
+
+    fn send(&self, packet: &[u8]) {
+        let Some(dest) = *self.dest.lock() else {
+            return;
+        };
+        // The other end may be gone, as it would be on a real link
+        if self.send_socket.send_to(packet, dest).is_ok() {
+            self.stats[self.direction as usize]
+                .forwarded
+                .fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Indices of the packets `impairment` lets through, in sending order
+    fn run(seed: u64, impairment: Impairment, count: u32) -> (Vec<u32>, ImpairmentStatsSnapshot) {
+        let mut impairer = Impairer::new(seed);
+        let stats = ImpairmentStats::default();
+        let now = Instant::now();
+        let mut out = Vec::new();
+        for i in 0..count {
+            impairer.process(&impairment, i.to_le_bytes().to_vec(), now, &stats, &mut out);
+        }
+        impairer.release_held(now + REORDER_HOLD, &mut out);
+
+        // Sorting is stable, packets due at the same time keep their order
+        out.sort_by_key(|(at, _)| *at);
+        let sent = out
+            .iter()
+            .map(|(_, packet)| u32::from_le_bytes(packet[..].try_into().unwrap()))
+            .collect();
+        (sent, stats.snapshot())
+    }
+
+    #[test]
+    fn test_impairment_is_deterministic() {
+        let impairment = Impairment {
+            drop_rate: 0.1,
+            duplicate_rate: 0.1,
+            reorder_rate: 0.1,
+            corrupt_rate: 0.1,
+            ..Default::default()
+        };
+
+        assert_eq!(run(7, impairment, 1000), run(7, impairment, 1000));
+        assert_ne!(run(7, impairment, 1000).0, run(8, impairment, 1000).0);
+    }
+
+    #[test]
+    fn test_impairment_rates() {
+        let (sent, stats) = run(1, Impairment::default(), 1000);
+        assert_eq!(sent, (0..1000).collect::<Vec<_>>());
+        assert_eq!(stats, ImpairmentStatsSnapshot::default());
+
+        let drop = Impairment {
+            drop_rate: 0.2,
+            ..Default::default()
+        };
+        let (sent, stats) = run(1, drop, 1000);
+        assert!((150..250).contains(&stats.dropped));
+        assert_eq!(sent.len() as u64, 1000 - stats.dropped);
+        assert!(sent.windows(2).all(|w| w[0] < w[1]));
+
+        let duplicate = Impairment {
+            duplicate_rate: 1.0,
+            ..Default::default()
+        };
+        let (sent, _) = run(1, duplicate, 3);
+        assert_eq!(sent, vec![0, 0, 1, 1, 2, 2]);
+    }
+
+    #[test]
+    fn test_reordered_packet_overtaken_once() {
+        let reorder = Impairment {
+            reorder_rate: 1.0,
+            ..Default::default()
+        };
+        // One packet is held at a time, the next one goes ahead of it
+        let (sent, stats) = run(1, reorder, 5);
+        assert_eq!(sent, vec![1, 0, 3, 2, 4]);
+        assert_eq!(stats.reordered, 3);
+    }
+
+    #[test]
+    fn test_corruption_flips_one_bit() {
+        let corrupt = Impairment {
+            corrupt_rate: 1.0,
+            ..Default::default()
+        };
+        let mut impairer = Impairer::new(3);
+        let stats = ImpairmentStats::default();
+        let mut out = Vec::new();
+        impairer.process(&corrupt, vec![0u8; 64], Instant::now(), &stats, &mut out);
+
+        let flipped: u32 = out[0].1.iter().map(|b| b.count_ones()).sum();
+        assert_eq!(flipped, 1);
+        assert_eq!(stats.snapshot().corrupted, 1);
+    }
+
+    #[test]
+    fn test_delay_and_jitter() {
+        let delay = Impairment {
+            delay: Duration::from_millis(5),
+            jitter: Duration::from_millis(2),
+            ..Default::default()
+        };
+        let mut impairer = Impairer::new(4);
+        let stats = ImpairmentStats::default();
+        let now = Instant::now();
+        let mut out = Vec::new();
+        for _ in 0..100 {
+            impairer.process(&delay, vec![0], now, &stats, &mut out);
+        }
+
+        let delays: Vec<Duration> = out.iter().map(|(at, _)| *at - now).collect();
+        assert!(delays
+            .iter()
+            .all(|d| (Duration::from_millis(5)..=Duration::from_millis(7)).contains(d)));
+        assert!(delays.iter().any(|d| *d != delays[0]));
+    }
+}
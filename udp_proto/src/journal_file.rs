@@ -0,0 +1,160 @@
+//! Journal files: the replication journal written to disk, for audits and
+//! offline replays.
+//!
+//! A file is a plain sequence of `JournalEntry` FlatBuffers, each prefixed
+//! with its length as a little-endian `u32`. Files are append only, a writer
+//! that dies mid-entry leaves a truncated last frame.
+
+use std::io::{self, Read, Write};
+
+use crate::binary::{decode_journal_entry, JournalEncoder, JournalEntry};
+
+/// Largest entry accepted when reading, anything bigger means a corrupt length
+const MAX_ENTRY_LEN: usize = 64 * 1024;
+
+/// Appends journal entries to a file or any other byte sink
+pub struct JournalWriter<W: Write> {
+    inner: W,
+    encoder: JournalEncoder,
+}
+
+impl<W: Write> JournalWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            encoder: JournalEncoder::new(),
+        }
+    }
+
+    /// Write one entry. Buffering is up to `W`, call `flush` to push it out.
+    pub fn write(&mut self, entry: &JournalEntry) -> io::Result<()> {
+        let data = self
+            .encoder
+            .encode(entry)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        self.inner.write_all(&(data.len() as u32).to_le_bytes())?;
+        self.inner.write_all(data)
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+/// Reads journal entries back in the order they were written
+pub struct JournalReader<R: Read> {
+    inner: R,
+    buf: Vec<u8>,
+}
+
+impl<R: Read> JournalReader<R> {
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            buf: Vec::with_capacity(256),
+        }
+    }
+
+    /// Next entry, `None` at a clean end of file. A truncated last frame is
+    /// reported as `UnexpectedEof`.
+    pub fn read_entry(&mut self) -> io::Result<Option<JournalEntry>> {
+        let mut len = [0u8; 4];
+        match self.inner.read(&mut len[..1])? {
+            0 => return Ok(None),
+            _ => self.inner.read_exact(&mut len[1..])?,
+        }
+        let len = u32::from_le_bytes(len) as usize;
+        if len > MAX_ENTRY_LEN {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Journal entry of {} bytes exceeds max of {}", len, MAX_ENTRY_LEN),
+            ));
+        }
+
+        self.buf.resize(len, 0);
+        self.inner.read_exact(&mut self.buf)?;
+        decode_journal_entry(&self.buf)
+            .map(Some)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+impl<R: Read> Iterator for JournalReader<R> {
+    type Item = io::Result<JournalEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_entry().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binary::{JournalRecord, MarketPhase, OrderCommand};
+    use rust_decimal::Decimal;
+    use uuid::Uuid;
+
+    fn entries() -> Vec<JournalEntry> {
+        vec![
+            JournalEntry {
+                sequence: 1,
+                record: JournalRecord::Command(OrderCommand::Cancel {
+                    order_id: Uuid::new_v4(),
+                    user_id: None,
+                }),
+            },
+            JournalEntry {
+                sequence: 1,
+                record: JournalRecord::Fill {
+                    buy_order_id: Uuid::new_v4(),
+                    sell_order_id: Uuid::new_v4(),
+                    price: Decimal::new(10_050, 2),
+                    quantity: Decimal::ONE,
+                },
+            },
+            JournalEntry {
+                sequence: 1,
+                record: JournalRecord::Checksum {
+                    hash: 42,
+                    order_count: 0,
+                    phase: MarketPhase::Open,
+                },
+            },
+        ]
+    }
+
+    #[test]
+    fn test_journal_file_roundtrip() {
+        let mut writer = JournalWriter::new(Vec::new());
+        for entry in entries() {
+            writer.write(&entry).unwrap();
+        }
+        let data = writer.into_inner();
+
+        let read: Vec<JournalEntry> = JournalReader::new(data.as_slice())
+            .collect::<io::Result<_>>()
+            .unwrap();
+        assert_eq!(read.len(), 3);
+        assert_eq!(read[2].record, entries()[2].record);
+    }
+
+    #[test]
+    fn test_journal_file_truncated_tail() {
+        let mut writer = JournalWriter::new(Vec::new());
+        for entry in entries() {
+            writer.write(&entry).unwrap();
+        }
+        let mut data = writer.into_inner();
+        data.truncate(data.len() - 3);
+
+        let mut reader = JournalReader::new(data.as_slice());
+        assert!(reader.read_entry().unwrap().is_some());
+        assert!(reader.read_entry().unwrap().is_some());
+        let err = reader.read_entry().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
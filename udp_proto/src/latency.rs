@@ -0,0 +1,231 @@
+//! One-way latency of timestamped streams.
+//!
+//! Senders with `send_timestamps` stamp every data packet and heartbeat with
+//! their monotonic clock. Two processes share no clock origin, so the
+//! receiver can't take `receive - send` at face value. It learns the offset
+//! from heartbeats instead: the smallest `receive - send` among the recent
+//! ones is the offset plus the quickest the path got. A packet's latency is
+//! what it took beyond that, jitter how much that changed from the previous
+//! packet.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+/// Buckets of a `LatencyHistogram`, the last one is unbounded
+pub const LATENCY_BUCKETS: usize = 22;
+
+/// Heartbeats the clock offset is taken over
+const OFFSET_WINDOW: usize = 32;
+
+/// Nanoseconds on this process's monotonic clock
+pub(crate) fn now_ns() -> u64 {
+    static START: OnceLock<Instant> = OnceLock::new();
+    START.get_or_init(Instant::now).elapsed().as_nanos() as u64
+}
+
+/// Histogram of durations. Bucket `i` counts those under `2^i` µs, from
+/// 1µs up to about a second, the doubling of the matching engine's command
+/// histograms.
+#[derive(Debug, Default)]
+pub struct LatencyHistogram {
+    buckets: [AtomicU64; LATENCY_BUCKETS],
+    count: AtomicU64,
+    sum_ns: AtomicU64,
+    max_ns: AtomicU64,
+}
+
+impl LatencyHistogram {
+    pub fn record(&self, ns: u64) {
+        self.buckets[bucket(ns)].fetch_add(1, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.sum_ns.fetch_add(ns, Ordering::Relaxed);
+        self.max_ns.fetch_max(ns, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> HistogramSnapshot {
+        HistogramSnapshot {
+            buckets: std::array::from_fn(|i| self.buckets[i].load(Ordering::Relaxed)),
+            count: self.count.load(Ordering::Relaxed),
+            sum_ns: self.sum_ns.load(Ordering::Relaxed),
+            max_ns: self.max_ns.load(Ordering::Relaxed),
+        }
+    }
+}
+
+fn bucket(ns: u64) -> usize {
+    let micros = ns / 1_000;
+    let index = (u64::BITS - micros.leading_zeros()) as usize;
+    index.min(LATENCY_BUCKETS - 1)
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HistogramSnapshot {
+    /// Counts per bucket, not cumulative
+    pub buckets: [u64; LATENCY_BUCKETS],
+    pub count: u64,
+    pub sum_ns: u64,
+    pub max_ns: u64,
+}
+
+impl HistogramSnapshot {
+    /// Exclusive upper bound of bucket `index`, None for the last one
+    pub fn upper_bound(index: usize) -> Option<Duration> {
+        (index < LATENCY_BUCKETS - 1).then(|| Duration::from_micros(1 << index))
+    }
+
+    pub fn mean(&self) -> Option<Duration> {
+        (self.count > 0).then(|| Duration::from_nanos(self.sum_ns / self.count))
+    }
+
+    pub fn max(&self) -> Option<Duration> {
+        (self.count > 0).then(|| Duration::from_nanos(self.max_ns))
+    }
+
+    /// Upper bound of the bucket holding quantile `q` (0.0 to 1.0), no more
+    /// than the largest value recorded
+    pub fn quantile(&self, q: f64) -> Option<Duration> {
+        if self.count == 0 {
+            return None;
+        }
+        let rank = ((q.clamp(0.0, 1.0) * self.count as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (index, count) in self.buckets.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                let max = Duration::from_nanos(self.max_ns);
+                return Some(Self::upper_bound(index).map_or(max, |bound| bound.min(max)));
+            }
+        }
+        self.max()
+    }
+}
+
+/// Offset of the sender's clock, the smallest `receive - send` over the
+/// last `OFFSET_WINDOW` heartbeats
+#[derive(Debug, Default)]
+struct ClockOffset {
+    deltas: VecDeque<i64>,
+    offset: Option<i64>,
+}
+
+impl ClockOffset {
+    fn observe(&mut self, delta: i64) {
+        if self.deltas.len() == OFFSET_WINDOW {
+            self.deltas.pop_front();
+        }
+        self.deltas.push_back(delta);
+        self.offset = self.deltas.iter().min().copied();
+    }
+}
+
+/// Latency and jitter of one stream, recorded into the receiver's stats
+#[derive(Debug, Default)]
+pub(crate) struct LatencyTracker {
+    offset: ClockOffset,
+    last_latency: Option<u64>,
+}
+
+impl LatencyTracker {
+    pub fn on_heartbeat(&mut self, send_time_ns: u64, now_ns: u64) {
+        self.offset.observe(delta(send_time_ns, now_ns));
+    }
+
+    /// Record a packet sent at `send_time_ns` and received at `now_ns`.
+    /// Nothing is recorded before the first heartbeat gave the offset.
+    pub fn on_packet(
+        &mut self,
+        send_time_ns: u64,
+        now_ns: u64,
+        latency: &LatencyHistogram,
+        jitter: &LatencyHistogram,
+    ) {
+        let Some(offset) = self.offset.offset else {
+            return;
+        };
+        // Quicker than any recent heartbeat
+        let sample = delta(send_time_ns, now_ns).saturating_sub(offset).max(0) as u64;
+        latency.record(sample);
+        if let Some(last) = self.last_latency {
+            jitter.record(sample.abs_diff(last));
+        }
+        self.last_latency = Some(sample);
+    }
+
+    /// The sender restarted on another clock
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+fn delta(send_time_ns: u64, now_ns: u64) -> i64 {
+    now_ns.wrapping_sub(send_time_ns) as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buckets_double_from_a_microsecond() {
+        assert_eq!(bucket(0), 0);
+        assert_eq!(bucket(999), 0);
+        assert_eq!(bucket(1_000), 1);
+        assert_eq!(bucket(1_999), 1);
+        assert_eq!(bucket(2_000), 2);
+        assert_eq!(bucket(1_000_000), 10);
+        assert_eq!(bucket(u64::MAX), LATENCY_BUCKETS - 1);
+        assert_eq!(
+            HistogramSnapshot::upper_bound(1),
+            Some(Duration::from_micros(2))
+        );
+        assert_eq!(HistogramSnapshot::upper_bound(LATENCY_BUCKETS - 1), None);
+    }
+
+    #[test]
+    fn test_quantiles() {
+        let histogram = LatencyHistogram::default();
+        for _ in 0..90 {
+            histogram.record(1_500);
+        }
+        for _ in 0..10 {
+            histogram.record(300_000);
+        }
+
+        let snapshot = histogram.snapshot();
+        assert_eq!(snapshot.count, 100);
+        assert_eq!(snapshot.quantile(0.5), Some(Duration::from_micros(2)));
+        assert_eq!(snapshot.quantile(0.9), Some(Duration::from_micros(2)));
+        assert_eq!(snapshot.quantile(0.99), Some(Duration::from_micros(300)));
+        assert_eq!(snapshot.max(), Some(Duration::from_micros(300)));
+        assert_eq!(snapshot.mean(), Some(Duration::from_nanos(31_350)));
+        assert_eq!(HistogramSnapshot::default().quantile(0.5), None);
+    }
+
+    #[test]
+    fn test_latency_relative_to_quickest_heartbeat() {
+        let (latency, jitter) = (LatencyHistogram::default(), LatencyHistogram::default());
+        let mut tracker = LatencyTracker::default();
+
+        // Sender clock 1s behind ours, the path takes 50µs at best
+        let skew = 1_000_000_000;
+        tracker.on_packet(0, skew, &latency, &jitter);
+        assert_eq!(latency.snapshot().count, 0);
+
+        tracker.on_heartbeat(10_000_000, 10_000_000 + skew + 80_000);
+        tracker.on_heartbeat(20_000_000, 20_000_000 + skew + 50_000);
+        tracker.on_packet(30_000_000, 30_000_000 + skew + 250_000, &latency, &jitter);
+        tracker.on_packet(40_000_000, 40_000_000 + skew + 150_000, &latency, &jitter);
+        // Quicker than the heartbeats, counts as no delay
+        tracker.on_packet(50_000_000, 50_000_000 + skew + 10_000, &latency, &jitter);
+
+        let latency = latency.snapshot();
+        assert_eq!(latency.count, 3);
+        assert_eq!(latency.sum_ns, 200_000 + 100_000);
+        assert_eq!(latency.max_ns, 200_000);
+        let jitter = jitter.snapshot();
+        assert_eq!(jitter.count, 2);
+        assert_eq!(jitter.sum_ns, 100_000 + 100_000);
+    }
+}
@@ -4,15 +4,32 @@
 //! low-latency messaging between exchange services.
 
 mod protocol;
+mod auth;
 mod sender;
 mod receiver;
 mod error;
+mod fragment;
+mod multicast;
+mod batch_io;
+mod session;
+mod latency;
+#[cfg(feature = "async")]
+mod async_udp;
 mod generated;
 pub mod binary;
+pub mod journal_file;
+pub mod capture_file;
+#[cfg(any(test, feature = "impairment"))]
+pub mod impairment;
 
 pub use protocol::*;
+pub use auth::{AuthConfig, AuthMode, Opened, Opener, Role, Sealer, StreamKey};
 pub use sender::*;
 pub use receiver::*;
+pub use session::{SessionEvent, SessionHandler};
+pub use latency::{HistogramSnapshot, LatencyHistogram, LATENCY_BUCKETS};
+#[cfg(feature = "async")]
+pub use async_udp::{AsyncUdpReceiver, AsyncUdpSender};
 pub use error::*;
 pub use generated::market_data_generated::market_data as fb;
 
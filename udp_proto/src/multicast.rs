@@ -0,0 +1,46 @@
+//! Multicast socket setup.
+//!
+//! A sender whose `target_addr` is an IPv4 multicast group publishes to
+//! every receiver that joined it. Receivers joining a group bind with
+//! `SO_REUSEADDR`, so several consumers on one host can share the port.
+//! NAKs and retransmissions stay unicast between the sender and the
+//! receiver that lost the messages.
+
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket};
+
+use socket2::{Domain, Protocol, SockRef, Socket, Type};
+
+use crate::error::{ProtocolError, Result};
+
+/// Set the TTL, interface and loopback of a socket sending to a group
+pub(crate) fn configure_sender(
+    socket: &UdpSocket,
+    ttl: u32,
+    interface: Ipv4Addr,
+    loopback: bool,
+) -> Result<()> {
+    socket.set_multicast_ttl_v4(ttl)?;
+    socket.set_multicast_loop_v4(loopback)?;
+    if !interface.is_unspecified() {
+        SockRef::from(socket).set_multicast_if_v4(&interface)?;
+    }
+    Ok(())
+}
+
+/// Bind a socket other receivers can bind too and join `group` on
+/// `interface` (unspecified = the system picks)
+pub(crate) fn bind_group(
+    bind_addr: SocketAddr,
+    group: Ipv4Addr,
+    interface: Ipv4Addr,
+) -> Result<UdpSocket> {
+    if !group.is_multicast() {
+        return Err(ProtocolError::NotMulticast(IpAddr::V4(group)));
+    }
+
+    let socket = Socket::new(Domain::for_address(bind_addr), Type::DGRAM, Some(Protocol::UDP))?;
+    socket.set_reuse_address(true)?;
+    socket.bind(&bind_addr.into())?;
+    socket.join_multicast_v4(&group, &interface)?;
+    Ok(socket.into())
+}
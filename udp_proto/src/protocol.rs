@@ -1,27 +1,51 @@
 use crate::error::{ProtocolError, Result};
+use crate::latency;
 
 // Protocol constants
-pub const PROTOCOL_VERSION: u8 = 1;
+/// Covers the packet format and the FlatBuffers payloads, see market_data.fbs
+pub const PROTOCOL_VERSION: u8 = 3;
+/// Packet header without the optional fields
 pub const PACKET_HEADER_LEN: usize = 24;
+/// Send time after the header of packets flagged `SEND_TIME`
+pub const SEND_TIME_LEN: usize = 8;
+/// Packet header with every optional field
+pub const MAX_PACKET_HEADER_LEN: usize = PACKET_HEADER_LEN + SEND_TIME_LEN;
 pub const MESSAGE_HEADER_LEN: usize = 4;
 pub const MAX_MTU: usize = 1400;
-pub const MAX_PAYLOAD: usize = MAX_MTU - PACKET_HEADER_LEN;
+/// Room kept at the end of every packet for the authentication trailer
+pub const AUTH_TRAILER_LEN: usize = 28;
+/// Message bytes per packet, the same whether the packet is timestamped or not
+pub const MAX_PAYLOAD: usize = MAX_MTU - MAX_PACKET_HEADER_LEN - AUTH_TRAILER_LEN;
 pub const HEARTBEAT_INTERVAL_MS: u64 = 100;
 pub const STREAM_TIMEOUT_MS: u64 = 500;
+pub const CONTROL_MESSAGE_LEN: usize = 17;
+pub const FRAGMENT_HEADER_LEN: usize = 8;
+/// Largest payload that still fits a packet as a single message
+pub const MAX_MESSAGE_PAYLOAD: usize = MAX_PAYLOAD - MESSAGE_HEADER_LEN;
+/// Payload bytes carried by each fragment of a larger message
+pub const MAX_FRAGMENT_LEN: usize = MAX_MESSAGE_PAYLOAD - FRAGMENT_HEADER_LEN;
+/// Default limit on fragmented messages, for senders and receivers alike
+pub const DEFAULT_MAX_MESSAGE_SIZE: usize = 1024 * 1024;
 
 /// Message types as defined in the protocol spec
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 #[repr(u8)]
 pub enum MessageType {
     OrderNew = 0x01,
     OrderCancel = 0x02,
     OrderReplace = 0x03,
+    /// Asks the engine to publish a book snapshot
+    SnapshotRequest = 0x04,
     MatchEvent = 0x10,
     BookSnapshot = 0x11,
     BookUpdate = 0x12,
+    /// Balances of one user after settlement, only delivered to that user
     PositionUpdate = 0x20,
     Heartbeat = 0x30,
+    /// Transport control, see `ControlMessage`. Not for application payloads.
     Control = 0x40,
+    /// Replication journal between engine replicas
+    Journal = 0x50,
 }
 
 impl MessageType {
@@ -30,15 +54,23 @@ impl MessageType {
             0x01 => Some(Self::OrderNew),
             0x02 => Some(Self::OrderCancel),
             0x03 => Some(Self::OrderReplace),
+            0x04 => Some(Self::SnapshotRequest),
             0x10 => Some(Self::MatchEvent),
             0x11 => Some(Self::BookSnapshot),
             0x12 => Some(Self::BookUpdate),
             0x20 => Some(Self::PositionUpdate),
             0x30 => Some(Self::Heartbeat),
             0x40 => Some(Self::Control),
+            0x50 => Some(Self::Journal),
             _ => None,
         }
     }
+
+    /// Book state, which a later message of the symbol supersedes. The only
+    /// messages a sender may drop under load.
+    pub fn is_book(&self) -> bool {
+        matches!(self, Self::BookSnapshot | Self::BookUpdate)
+    }
 }
 
 /// Message flags
@@ -49,36 +81,62 @@ impl MessageFlags {
     pub const NONE: Self = Self(0);
     pub const LAST_IN_BATCH: Self = Self(0x01);
     pub const URGENT: Self = Self(0x02);
+    /// Payload starts with a `FragmentHeader`
+    pub const FRAGMENT: Self = Self(0x04);
+
+    pub fn contains(&self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
 }
 
-/// Packet header (24 bytes)
+/// Packet flags
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PacketFlags(pub u8);
+
+impl PacketFlags {
+    pub const NONE: Self = Self(0);
+    /// The header is followed by the send time
+    pub const SEND_TIME: Self = Self(0x01);
+    /// Every flag this version knows
+    const KNOWN: Self = Self(0x01);
+
+    pub fn contains(&self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+/// Packet header (24 bytes, 32 with the send time)
 /// ```text
 /// 0  - 1   : version        (u8)
-/// 1  - 2   : header_len     (u8)
+/// 1  - 2   : flags          (u8)
 /// 2  - 4   : msg_count      (u16)
 /// 4  - 8   : stream_id      (u32)
 /// 8  - 16  : packet_seq     (u64)
 /// 16 - 24  : first_msg_seq  (u64)
+/// 24 - 32  : send_time_ns   (u64, with SEND_TIME only)
 /// ```
+/// The send time is read off the sender's monotonic clock, see
+/// `latency::now_ns`. It means nothing on its own, receivers compare it
+/// against their own clock with an offset learned from heartbeats.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct PacketHeader {
     pub version: u8,
-    pub header_len: u8,
     pub msg_count: u16,
     pub stream_id: u32,
     pub packet_seq: u64,
     pub first_msg_seq: u64,
+    pub send_time_ns: Option<u64>,
 }
 
 impl PacketHeader {
     pub fn new(stream_id: u32, packet_seq: u64, first_msg_seq: u64, msg_count: u16) -> Self {
         Self {
             version: PROTOCOL_VERSION,
-            header_len: PACKET_HEADER_LEN as u8,
             msg_count,
             stream_id,
             packet_seq,
             first_msg_seq,
+            send_time_ns: None,
         }
     }
 
@@ -86,24 +144,48 @@ impl PacketHeader {
         Self::new(stream_id, packet_seq, msg_seq, 0)
     }
 
+    pub fn with_send_time(mut self, send_time_ns: u64) -> Self {
+        self.send_time_ns = Some(send_time_ns);
+        self
+    }
+
+    pub fn flags(&self) -> PacketFlags {
+        match self.send_time_ns {
+            Some(_) => PacketFlags::SEND_TIME,
+            None => PacketFlags::NONE,
+        }
+    }
+
+    /// Bytes the header takes, messages start after it
+    pub fn encoded_len(&self) -> usize {
+        match self.send_time_ns {
+            Some(_) => MAX_PACKET_HEADER_LEN,
+            None => PACKET_HEADER_LEN,
+        }
+    }
+
     /// Write header to buffer. Returns bytes written.
     #[inline]
     pub fn write_to(&self, buf: &mut [u8]) -> Result<usize> {
-        if buf.len() < PACKET_HEADER_LEN {
+        let len = self.encoded_len();
+        if buf.len() < len {
             return Err(ProtocolError::BufferTooSmall {
-                needed: PACKET_HEADER_LEN,
+                needed: len,
                 available: buf.len(),
             });
         }
 
         buf[0] = self.version;
-        buf[1] = self.header_len;
+        buf[1] = self.flags().0;
         buf[2..4].copy_from_slice(&self.msg_count.to_le_bytes());
         buf[4..8].copy_from_slice(&self.stream_id.to_le_bytes());
         buf[8..16].copy_from_slice(&self.packet_seq.to_le_bytes());
         buf[16..24].copy_from_slice(&self.first_msg_seq.to_le_bytes());
+        if let Some(send_time_ns) = self.send_time_ns {
+            buf[24..32].copy_from_slice(&send_time_ns.to_le_bytes());
+        }
 
-        Ok(PACKET_HEADER_LEN)
+        Ok(len)
     }
 
     /// Read header from buffer.
@@ -124,17 +206,25 @@ impl PacketHeader {
             });
         }
 
-        let header_len = buf[1];
-        if header_len != PACKET_HEADER_LEN as u8 {
-            return Err(ProtocolError::InvalidHeaderLength {
-                expected: PACKET_HEADER_LEN as u8,
-                got: header_len,
-            });
+        let flags = PacketFlags(buf[1]);
+        if flags.0 & !PacketFlags::KNOWN.0 != 0 {
+            return Err(ProtocolError::UnknownPacketFlags(flags.0));
         }
 
+        let send_time_ns = if flags.contains(PacketFlags::SEND_TIME) {
+            if buf.len() < MAX_PACKET_HEADER_LEN {
+                return Err(ProtocolError::BufferTooSmall {
+                    needed: MAX_PACKET_HEADER_LEN,
+                    available: buf.len(),
+                });
+            }
+            Some(u64::from_le_bytes(buf[24..32].try_into().unwrap()))
+        } else {
+            None
+        };
+
         Ok(Self {
             version,
-            header_len,
             msg_count: u16::from_le_bytes([buf[2], buf[3]]),
             stream_id: u32::from_le_bytes([buf[4], buf[5], buf[6], buf[7]]),
             packet_seq: u64::from_le_bytes([
@@ -143,6 +233,7 @@ impl PacketHeader {
             first_msg_seq: u64::from_le_bytes([
                 buf[16], buf[17], buf[18], buf[19], buf[20], buf[21], buf[22], buf[23],
             ]),
+            send_time_ns,
         })
     }
 
@@ -151,6 +242,187 @@ impl PacketHeader {
     }
 }
 
+/// Transport control message, sent as a `MessageType::Control` message in a
+/// packet of its own. Control packets take no message sequence numbers.
+/// ```text
+/// 0 - 1  : kind      (u8)
+/// 1 - 9  : from_seq  (u64, key_id for KeyRotation, session_id for
+///                     Hello/Accept/Logout, next_seq for SequenceReset)
+/// 9 - 17 : to_seq    (u64, exclusive, version for Hello/Accept, else 0)
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControlMessage {
+    /// Receiver to sender: resend messages `from_seq..to_seq`
+    Nak { from_seq: u64, to_seq: u64 },
+    /// Sender to receiver: messages `from_seq..to_seq` are no longer kept,
+    /// the receiver has to resync from a snapshot
+    Unavailable { from_seq: u64, to_seq: u64 },
+    /// Sender to receiver: packets after this one are signed with `key_id`,
+    /// keys with lower IDs are retired
+    KeyRotation { key_id: u32 },
+    /// Sender to receiver: opens a session, the stream starts over when the
+    /// session ID changes
+    Hello { session_id: u64, version: u8 },
+    /// Receiver to sender: answers a `Hello`, or asks for one when joining
+    /// a running stream
+    Accept { session_id: u64, version: u8 },
+    /// Sender to receiver: the next message is numbered `next_seq`
+    SequenceReset { next_seq: u64 },
+    /// Either way: the session ends
+    Logout { session_id: u64 },
+}
+
+impl ControlMessage {
+    const NAK: u8 = 0x01;
+    const UNAVAILABLE: u8 = 0x02;
+    const KEY_ROTATION: u8 = 0x03;
+    const HELLO: u8 = 0x04;
+    const ACCEPT: u8 = 0x05;
+    const SEQUENCE_RESET: u8 = 0x06;
+    const LOGOUT: u8 = 0x07;
+
+    #[inline]
+    pub fn write_to(&self, buf: &mut [u8]) -> Result<usize> {
+        if buf.len() < CONTROL_MESSAGE_LEN {
+            return Err(ProtocolError::BufferTooSmall {
+                needed: CONTROL_MESSAGE_LEN,
+                available: buf.len(),
+            });
+        }
+
+        let (kind, from_seq, to_seq) = match *self {
+            Self::Nak { from_seq, to_seq } => (Self::NAK, from_seq, to_seq),
+            Self::Unavailable { from_seq, to_seq } => (Self::UNAVAILABLE, from_seq, to_seq),
+            Self::KeyRotation { key_id } => (Self::KEY_ROTATION, key_id as u64, 0),
+            Self::Hello { session_id, version } => (Self::HELLO, session_id, version as u64),
+            Self::Accept { session_id, version } => (Self::ACCEPT, session_id, version as u64),
+            Self::SequenceReset { next_seq } => (Self::SEQUENCE_RESET, next_seq, 0),
+            Self::Logout { session_id } => (Self::LOGOUT, session_id, 0),
+        };
+        buf[0] = kind;
+        buf[1..9].copy_from_slice(&from_seq.to_le_bytes());
+        buf[9..17].copy_from_slice(&to_seq.to_le_bytes());
+
+        Ok(CONTROL_MESSAGE_LEN)
+    }
+
+    #[inline]
+    pub fn read_from(buf: &[u8]) -> Result<Self> {
+        if buf.len() < CONTROL_MESSAGE_LEN {
+            return Err(ProtocolError::BufferTooSmall {
+                needed: CONTROL_MESSAGE_LEN,
+                available: buf.len(),
+            });
+        }
+
+        let from_seq = u64::from_le_bytes(buf[1..9].try_into().unwrap());
+        let to_seq = u64::from_le_bytes(buf[9..17].try_into().unwrap());
+        match buf[0] {
+            Self::NAK => Ok(Self::Nak { from_seq, to_seq }),
+            Self::UNAVAILABLE => Ok(Self::Unavailable { from_seq, to_seq }),
+            Self::KEY_ROTATION => u32::try_from(from_seq)
+                .map(|key_id| Self::KeyRotation { key_id })
+                .map_err(|_| ProtocolError::InvalidControlMessage(Self::KEY_ROTATION)),
+            Self::HELLO => u8::try_from(to_seq)
+                .map(|version| Self::Hello { session_id: from_seq, version })
+                .map_err(|_| ProtocolError::InvalidControlMessage(Self::HELLO)),
+            Self::ACCEPT => u8::try_from(to_seq)
+                .map(|version| Self::Accept { session_id: from_seq, version })
+                .map_err(|_| ProtocolError::InvalidControlMessage(Self::ACCEPT)),
+            Self::SEQUENCE_RESET => Ok(Self::SequenceReset { next_seq: from_seq }),
+            Self::LOGOUT => Ok(Self::Logout { session_id: from_seq }),
+            kind => Err(ProtocolError::InvalidControlMessage(kind)),
+        }
+    }
+}
+
+/// Fragment header (8 bytes), in front of the payload of a `FRAGMENT`
+/// message. A message too large for one packet is sent as `count` messages
+/// of its own type with consecutive sequence numbers, each carrying up to
+/// `MAX_FRAGMENT_LEN` bytes of it.
+/// ```text
+/// 0 - 2 : index      (u16)
+/// 2 - 4 : count      (u16)
+/// 4 - 8 : total_len  (u32)
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FragmentHeader {
+    pub index: u16,
+    pub count: u16,
+    pub total_len: u32,
+}
+
+impl FragmentHeader {
+    #[inline]
+    pub fn write_to(&self, buf: &mut [u8]) -> Result<usize> {
+        if buf.len() < FRAGMENT_HEADER_LEN {
+            return Err(ProtocolError::BufferTooSmall {
+                needed: FRAGMENT_HEADER_LEN,
+                available: buf.len(),
+            });
+        }
+
+        buf[0..2].copy_from_slice(&self.index.to_le_bytes());
+        buf[2..4].copy_from_slice(&self.count.to_le_bytes());
+        buf[4..8].copy_from_slice(&self.total_len.to_le_bytes());
+
+        Ok(FRAGMENT_HEADER_LEN)
+    }
+
+    #[inline]
+    pub fn read_from(buf: &[u8]) -> Result<Self> {
+        if buf.len() < FRAGMENT_HEADER_LEN {
+            return Err(ProtocolError::BufferTooSmall {
+                needed: FRAGMENT_HEADER_LEN,
+                available: buf.len(),
+            });
+        }
+
+        let header = Self {
+            index: u16::from_le_bytes([buf[0], buf[1]]),
+            count: u16::from_le_bytes([buf[2], buf[3]]),
+            total_len: u32::from_le_bytes([buf[4], buf[5], buf[6], buf[7]]),
+        };
+        // The count follows from the length, so a forged count can't size
+        // the reassembly buffer on its own
+        let expected_count = (header.total_len as usize).div_ceil(MAX_FRAGMENT_LEN);
+        if header.index >= header.count || header.count as usize != expected_count {
+            return Err(ProtocolError::InvalidFragment {
+                index: header.index,
+                count: header.count,
+            });
+        }
+        Ok(header)
+    }
+
+    /// Bytes of the message carried by this fragment, only the last one is short
+    pub fn chunk_len(&self) -> usize {
+        if self.index + 1 < self.count {
+            MAX_FRAGMENT_LEN
+        } else {
+            self.total_len as usize - (self.count as usize - 1) * MAX_FRAGMENT_LEN
+        }
+    }
+
+    /// Split a payload into fragments. Payloads over `u16::MAX` fragments
+    /// can't be represented, senders reject them up front.
+    pub fn split(payload: &[u8]) -> impl Iterator<Item = (Self, &[u8])> {
+        let count = payload.len().div_ceil(MAX_FRAGMENT_LEN) as u16;
+        let total_len = payload.len() as u32;
+        payload
+            .chunks(MAX_FRAGMENT_LEN)
+            .enumerate()
+            .map(move |(index, chunk)| {
+                let header = Self {
+                    index: index as u16,
+                    count,
+                    total_len,
+                };
+                (header, chunk)
+            })
+    }
+}
+
 /// Message header (4 bytes)
 /// ```text
 /// 0 - 1 : msg_type   (u8)
@@ -239,6 +511,11 @@ impl Message {
     pub fn message_type(&self) -> Option<MessageType> {
         self.header.message_type()
     }
+
+    /// Whether this is one fragment of a larger message
+    pub fn is_fragment(&self) -> bool {
+        MessageFlags(self.header.flags).contains(MessageFlags::FRAGMENT)
+    }
 }
 
 /// A parsed packet containing header and messages
@@ -253,7 +530,7 @@ impl Packet {
     pub fn parse(buf: &[u8]) -> Result<Self> {
         let header = PacketHeader::read_from(buf)?;
         let mut messages = Vec::with_capacity(header.msg_count as usize);
-        let mut offset = PACKET_HEADER_LEN;
+        let mut offset = header.encoded_len();
 
         for i in 0..header.msg_count {
             if offset >= buf.len() {
@@ -283,6 +560,28 @@ impl Packet {
 
         Ok(Self { header, messages })
     }
+
+    /// Whether this packet carries control messages rather than stream data
+    pub fn is_control(&self) -> bool {
+        !self.messages.is_empty()
+            && self
+                .messages
+                .iter()
+                .all(|m| m.message_type() == Some(MessageType::Control))
+    }
+
+    /// The control messages of a control packet, malformed ones skipped
+    pub fn control_messages(&self) -> impl Iterator<Item = ControlMessage> + '_ {
+        self.messages
+            .iter()
+            .filter(|m| m.message_type() == Some(MessageType::Control))
+            .filter_map(|m| ControlMessage::read_from(&m.payload).ok())
+    }
+
+    /// Sequence number after the last message of this packet
+    pub fn end_msg_seq(&self) -> u64 {
+        self.header.first_msg_seq + self.header.msg_count as u64
+    }
 }
 
 /// Buffer for building packets
@@ -292,6 +591,7 @@ pub struct PacketBuilder {
     packet_seq: u64,
     first_msg_seq: u64,
     msg_count: u16,
+    header_len: usize,
     write_offset: usize,
 }
 
@@ -304,12 +604,22 @@ impl PacketBuilder {
             packet_seq,
             first_msg_seq,
             msg_count: 0,
+            header_len: PACKET_HEADER_LEN,
             write_offset: PACKET_HEADER_LEN,
         }
     }
 
+    /// Stamp the packet with the time `finish` is called. Call before
+    /// adding messages.
+    pub fn with_send_time(mut self) -> Self {
+        debug_assert!(self.is_empty());
+        self.header_len = MAX_PACKET_HEADER_LEN;
+        self.write_offset = MAX_PACKET_HEADER_LEN;
+        self
+    }
+
     pub fn remaining_capacity(&self) -> usize {
-        MAX_MTU - self.write_offset
+        self.header_len + MAX_PAYLOAD - self.write_offset
     }
 
     pub fn is_empty(&self) -> bool {
@@ -320,7 +630,12 @@ impl PacketBuilder {
         self.msg_count
     }
 
-    /// Try to add a message. Returns false if it doesn't fit.
+    pub fn packet_seq(&self) -> u64 {
+        self.packet_seq
+    }
+
+    /// Try to add a message. Returns false if it doesn't fit, messages over
+    /// `MAX_MESSAGE_PAYLOAD` never do and have to go as fragments.
     pub fn try_add_message(&mut self, msg_type: MessageType, payload: &[u8]) -> bool {
         let needed = MESSAGE_HEADER_LEN + payload.len();
         if needed > self.remaining_capacity() {
@@ -338,6 +653,33 @@ impl PacketBuilder {
         true
     }
 
+    /// Try to add one fragment of a larger message. Returns false if it
+    /// doesn't fit.
+    pub fn try_add_fragment(
+        &mut self,
+        msg_type: MessageType,
+        fragment: FragmentHeader,
+        chunk: &[u8],
+    ) -> bool {
+        let payload_len = FRAGMENT_HEADER_LEN + chunk.len();
+        if MESSAGE_HEADER_LEN + payload_len > self.remaining_capacity() {
+            return false;
+        }
+
+        let header = MessageHeader::new(msg_type, MessageFlags::FRAGMENT, payload_len as u16);
+        header.write_to(&mut self.buf[self.write_offset..]).unwrap();
+        self.write_offset += MESSAGE_HEADER_LEN;
+
+        fragment.write_to(&mut self.buf[self.write_offset..]).unwrap();
+        self.write_offset += FRAGMENT_HEADER_LEN;
+
+        self.buf[self.write_offset..self.write_offset + chunk.len()].copy_from_slice(chunk);
+        self.write_offset += chunk.len();
+        self.msg_count += 1;
+
+        true
+    }
+
     /// Finalize and return the packet bytes
     pub fn finish(mut self) -> Vec<u8> {
         let header = PacketHeader::new(
@@ -346,6 +688,10 @@ impl PacketBuilder {
             self.first_msg_seq,
             self.msg_count,
         );
+        let header = match self.header_len {
+            PACKET_HEADER_LEN => header,
+            _ => header.with_send_time(latency::now_ns()),
+        };
         header.write_to(&mut self.buf).unwrap();
         self.buf.truncate(self.write_offset);
         self.buf
@@ -358,4 +704,18 @@ impl PacketBuilder {
         header.write_to(&mut buf).unwrap();
         buf
     }
+
+    /// Create a control packet, messages that don't fit are left out. The
+    /// packet_seq only matters on authenticated streams.
+    pub fn control(stream_id: u32, packet_seq: u64, messages: &[ControlMessage]) -> Vec<u8> {
+        let mut builder = Self::new(stream_id, packet_seq, 0);
+        let mut payload = [0u8; CONTROL_MESSAGE_LEN];
+        for message in messages {
+            message.write_to(&mut payload).unwrap();
+            if !builder.try_add_message(MessageType::Control, &payload) {
+                break;
+            }
+        }
+        builder.finish()
+    }
 }
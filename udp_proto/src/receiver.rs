@@ -1,36 +1,85 @@
-use std::collections::VecDeque;
-use std::net::{SocketAddr, UdpSocket};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::io;
+use std::net::{Ipv4Addr, SocketAddr, UdpSocket};
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
 use std::thread::{self, JoinHandle};
 use std::time::{Duration, Instant};
 
-use crossbeam_channel::{bounded, Receiver as ChannelReceiver, Sender as ChannelSender};
+use crossbeam_channel::{
+    bounded, Receiver as ChannelReceiver, RecvTimeoutError, Sender as ChannelSender, TryRecvError,
+    TrySendError,
+};
 use parking_lot::Mutex;
 
+use crate::auth::{AuthConfig, Opened, Opener, Role, Sealer, StreamKey};
+use crate::batch_io::RecvBatch;
 use crate::error::{ProtocolError, Result};
+use crate::fragment::Reassembler;
+use crate::latency::{self, HistogramSnapshot, LatencyHistogram, LatencyTracker};
+use crate::multicast;
 use crate::protocol::*;
+use crate::session::{self, SessionEvent, SessionHandler};
 
 /// Configuration for the UDP receiver
 #[derive(Debug, Clone)]
 pub struct ReceiverConfig {
-    /// Expected stream ID (0 = accept any)
+    /// Expected stream ID (0 = accept any). Every sender and stream ID is
+    /// sequenced on its own.
     pub stream_id: u32,
+    /// Streams tracked at once, a new one replaces the one down the longest
+    /// or is dropped
+    pub max_streams: usize,
     /// Channel capacity for incoming messages
     pub channel_capacity: usize,
     /// Socket receive timeout
     pub recv_timeout: Duration,
     /// Stream timeout (no packets)
     pub stream_timeout: Duration,
+    /// NAK lost messages and hold later ones back until they are resent
+    pub enable_naks: bool,
+    /// How long to wait for a retransmission before asking again
+    pub nak_timeout: Duration,
+    /// NAKs repeated before a gap is given up
+    pub nak_retries: u32,
+    /// Packets held back during recovery before the gap is given up
+    pub max_pending_packets: usize,
+    /// Largest fragmented message accepted
+    pub max_message_size: usize,
+    /// Fragmented messages being reassembled at once, the oldest is dropped
+    /// to make room
+    pub max_partial_messages: usize,
+    /// How long a fragmented message may take to complete
+    pub reassembly_timeout: Duration,
+    /// Only accept packets sealed with one of these keys (None = plain packets)
+    pub auth: Option<AuthConfig>,
+    /// IPv4 multicast group to join, other receivers may bind the same port
+    pub multicast_group: Option<Ipv4Addr>,
+    /// Interface to join the group on (unspecified = the system picks)
+    pub multicast_interface: Ipv4Addr,
+    /// Told when the sender opens, restarts or ends its session
+    pub on_session: Option<SessionHandler>,
 }
 
 impl Default for ReceiverConfig {
     fn default() -> Self {
         Self {
             stream_id: 0,
+            max_streams: 64,
             channel_capacity: 10_000,
             recv_timeout: Duration::from_millis(10),
             stream_timeout: Duration::from_millis(STREAM_TIMEOUT_MS),
+            enable_naks: true,
+            nak_timeout: Duration::from_millis(20),
+            nak_retries: 3,
+            max_pending_packets: 1024,
+            max_message_size: DEFAULT_MAX_MESSAGE_SIZE,
+            max_partial_messages: 64,
+            reassembly_timeout: Duration::from_secs(1),
+            auth: None,
+            multicast_group: None,
+            multicast_interface: Ipv4Addr::UNSPECIFIED,
+            on_session: None,
         }
     }
 }
@@ -48,9 +97,39 @@ pub enum StreamState {
     Down,
 }
 
+impl StreamState {
+    /// Rank of the state when the streams of a receiver are combined
+    fn severity(self) -> u8 {
+        match self {
+            Self::Initializing => 0,
+            Self::Active => 1,
+            Self::Degraded => 2,
+            Self::Down => 3,
+        }
+    }
+}
+
+/// Where a stream comes from. The same stream ID from two senders makes
+/// two streams.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct StreamSource {
+    pub addr: SocketAddr,
+    pub stream_id: u32,
+}
+
+/// How one stream of a receiver is doing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamStatus {
+    pub source: StreamSource,
+    pub state: StreamState,
+    /// Messages of this stream were lost for good
+    pub needs_snapshot: bool,
+}
+
 /// Gap information
 #[derive(Debug, Clone)]
 pub struct GapInfo {
+    pub source: StreamSource,
     pub expected_seq: u64,
     pub received_seq: u64,
     pub gap_size: u64,
@@ -68,6 +147,25 @@ pub struct ReceiverStats {
     pub gaps_detected: AtomicU64,
     pub total_gap_messages: AtomicU64,
     pub recv_errors: AtomicU64,
+    pub naks_sent: AtomicU64,
+    /// Recoveries that got every lost message back
+    pub gaps_recovered: AtomicU64,
+    /// Recoveries given up, the consumer needs a snapshot
+    pub gaps_unrecoverable: AtomicU64,
+    pub messages_reassembled: AtomicU64,
+    /// Fragmented messages that timed out, were evicted or were malformed
+    pub partial_messages_dropped: AtomicU64,
+    /// Packets dropped for a bad tag or an unknown or retired key
+    pub auth_failures: AtomicU64,
+    /// Authentic packets dropped because they were seen before
+    pub replays_rejected: AtomicU64,
+    /// Packets of new streams dropped because `max_streams` were live
+    pub streams_rejected: AtomicU64,
+    /// One-way latency of timestamped packets, beyond the quickest recent
+    /// heartbeat. Packets filling a gap are left out, they may be resent.
+    pub latency: LatencyHistogram,
+    /// Change in latency from one timestamped packet to the next
+    pub jitter: LatencyHistogram,
 }
 
 impl ReceiverStats {
@@ -81,6 +179,24 @@ impl ReceiverStats {
             gaps_detected: self.gaps_detected.load(Ordering::Relaxed),
             total_gap_messages: self.total_gap_messages.load(Ordering::Relaxed),
             recv_errors: self.recv_errors.load(Ordering::Relaxed),
+            naks_sent: self.naks_sent.load(Ordering::Relaxed),
+            gaps_recovered: self.gaps_recovered.load(Ordering::Relaxed),
+            gaps_unrecoverable: self.gaps_unrecoverable.load(Ordering::Relaxed),
+            messages_reassembled: self.messages_reassembled.load(Ordering::Relaxed),
+            partial_messages_dropped: self.partial_messages_dropped.load(Ordering::Relaxed),
+            auth_failures: self.auth_failures.load(Ordering::Relaxed),
+            replays_rejected: self.replays_rejected.load(Ordering::Relaxed),
+            streams_rejected: self.streams_rejected.load(Ordering::Relaxed),
+            latency: self.latency.snapshot(),
+            jitter: self.jitter.snapshot(),
+        }
+    }
+
+    /// Count a NAK packet that went out, or failed to
+    /// NAKs and session replies that failed to go out count as errors
+    pub(crate) fn record_reply(&self, result: &io::Result<usize>) {
+        if result.is_err() {
+            self.recv_errors.fetch_add(1, Ordering::Relaxed);
         }
     }
 }
@@ -95,6 +211,16 @@ pub struct ReceiverStatsSnapshot {
     pub gaps_detected: u64,
     pub total_gap_messages: u64,
     pub recv_errors: u64,
+    pub naks_sent: u64,
+    pub gaps_recovered: u64,
+    pub gaps_unrecoverable: u64,
+    pub messages_reassembled: u64,
+    pub partial_messages_dropped: u64,
+    pub auth_failures: u64,
+    pub replays_rejected: u64,
+    pub streams_rejected: u64,
+    pub latency: HistogramSnapshot,
+    pub jitter: HistogramSnapshot,
 }
 
 /// Received message with metadata
@@ -104,57 +230,191 @@ pub struct ReceivedMessage {
     pub payload: Vec<u8>,
     pub seq: u64,
     pub stream_id: u32,
+    /// Address of the sender
+    pub source: SocketAddr,
+}
+
+/// Keys of an authenticated stream
+struct ReceiverAuth {
+    opener: Mutex<Opener>,
+    /// Seals NAKs
+    sealer: Mutex<Sealer>,
 }
 
-/// UDP receiver with gap detection and sequence tracking
+/// Status of the streams being tracked
+#[derive(Default)]
+struct StreamTable {
+    streams: HashMap<StreamSource, StreamStatus>,
+    /// A stream logged out, with none left the receiver is down
+    ended: bool,
+}
+
+/// What a receiver shares with the tracking of its streams
+#[derive(Clone)]
+pub(crate) struct ReceiverHandles {
+    pub stats: Arc<ReceiverStats>,
+    pub session_id: u64,
+    streams: Arc<Mutex<StreamTable>>,
+    gaps: Arc<Mutex<VecDeque<GapInfo>>>,
+    auth: Option<Arc<ReceiverAuth>>,
+}
+
+impl ReceiverHandles {
+    /// The worst state of any stream
+    pub fn state(&self) -> StreamState {
+        let table = self.streams.lock();
+        let idle = if table.ended {
+            StreamState::Down
+        } else {
+            StreamState::Initializing
+        };
+        table
+            .streams
+            .values()
+            .map(|status| status.state)
+            .max_by_key(|state| state.severity())
+            .unwrap_or(idle)
+    }
+
+    pub fn streams(&self) -> Vec<StreamStatus> {
+        let mut streams: Vec<_> = self.streams.lock().streams.values().copied().collect();
+        streams.sort_by_key(|status| (status.source.stream_id, status.source.addr));
+        streams
+    }
+
+    pub fn gaps(&self) -> Vec<GapInfo> {
+        self.gaps.lock().iter().cloned().collect()
+    }
+
+    pub fn needs_snapshot(&self) -> bool {
+        self.streams.lock().streams.values().any(|status| status.needs_snapshot)
+    }
+
+    pub fn clear_gaps(&self) {
+        self.gaps.lock().clear();
+        for status in self.streams.lock().streams.values_mut() {
+            Self::clear(status);
+        }
+    }
+
+    pub fn clear_stream_gaps(&self, source: StreamSource) {
+        self.gaps.lock().retain(|gap| gap.source != source);
+        if let Some(status) = self.streams.lock().streams.get_mut(&source) {
+            Self::clear(status);
+        }
+    }
+
+    fn clear(status: &mut StreamStatus) {
+        status.needs_snapshot = false;
+        if status.state == StreamState::Degraded {
+            status.state = StreamState::Active;
+        }
+    }
+
+    pub fn add_key(&self, key: StreamKey) -> Result<()> {
+        let auth = self.auth.as_ref().ok_or(ProtocolError::NoStreamKey)?;
+        auth.opener.lock().add_key(&key);
+        auth.sealer.lock().add_key(&key);
+        Ok(())
+    }
+}
+
+/// Streams routed to a receiver of their own, by stream ID
+type Routes = Arc<Mutex<HashMap<u32, ChannelSender<ReceivedMessage>>>>;
+
+/// UDP receiver with gap detection and sequence tracking. Lost messages are
+/// NAKed and delivered in order once resent. When they can't be, the
+/// receiver moves on and flags that the consumer needs a snapshot.
+/// Fragmented messages are delivered whole, under the sequence of their
+/// first fragment. One socket can take many streams, each sender and
+/// stream ID is sequenced on its own and can be routed to its own consumer.
 pub struct UdpReceiver {
-    #[allow(dead_code)]
     config: ReceiverConfig,
     rx: ChannelReceiver<ReceivedMessage>,
-    stats: Arc<ReceiverStats>,
-    state: Arc<Mutex<StreamState>>,
-    gaps: Arc<Mutex<VecDeque<GapInfo>>>,
+    handles: ReceiverHandles,
+    routes: Routes,
     running: Arc<AtomicBool>,
     worker_handle: Option<JoinHandle<()>>,
 }
 
+/// Messages of one stream ID, see `UdpReceiver::route`
+pub struct StreamReceiver {
+    stream_id: u32,
+    rx: ChannelReceiver<ReceivedMessage>,
+}
+
+impl StreamReceiver {
+    pub fn stream_id(&self) -> u32 {
+        self.stream_id
+    }
+
+    /// Receive a message (blocking)
+    pub fn recv(&self) -> Result<ReceivedMessage> {
+        self.rx.recv().map_err(|_| ProtocolError::ChannelClosed)
+    }
+
+    /// Try to receive a message (non-blocking)
+    pub fn try_recv(&self) -> Result<Option<ReceivedMessage>> {
+        try_recv(&self.rx)
+    }
+
+    /// Receive with timeout
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<Option<ReceivedMessage>> {
+        recv_timeout(&self.rx, timeout)
+    }
+}
+
+fn try_recv(rx: &ChannelReceiver<ReceivedMessage>) -> Result<Option<ReceivedMessage>> {
+    match rx.try_recv() {
+        Ok(msg) => Ok(Some(msg)),
+        Err(TryRecvError::Empty) => Ok(None),
+        Err(TryRecvError::Disconnected) => Err(ProtocolError::ChannelClosed),
+    }
+}
+
+fn recv_timeout(
+    rx: &ChannelReceiver<ReceivedMessage>,
+    timeout: Duration,
+) -> Result<Option<ReceivedMessage>> {
+    match rx.recv_timeout(timeout) {
+        Ok(msg) => Ok(Some(msg)),
+        Err(RecvTimeoutError::Timeout) => Ok(None),
+        Err(RecvTimeoutError::Disconnected) => Err(ProtocolError::ChannelClosed),
+    }
+}
+
 impl UdpReceiver {
-    /// Create a new receiver bound to the specified address
+    /// Create a new receiver bound to the specified address, joining
+    /// `multicast_group` when set
     pub fn new(config: ReceiverConfig, bind_addr: SocketAddr) -> Result<Self> {
-        let socket = UdpSocket::bind(bind_addr)?;
+        let tracker = StreamTracker::new(config.clone())?;
+        let socket = tracker.bind(bind_addr)?;
         socket.set_read_timeout(Some(config.recv_timeout))?;
 
         let (tx, rx) = bounded(config.channel_capacity);
-        let stats = Arc::new(ReceiverStats::default());
-        let state = Arc::new(Mutex::new(StreamState::Initializing));
-        let gaps = Arc::new(Mutex::new(VecDeque::new()));
+        let handles = tracker.handles.clone();
+        let routes = Routes::default();
         let running = Arc::new(AtomicBool::new(true));
 
         let worker = ReceiverWorker {
-            config: config.clone(),
             socket,
             tx,
-            stats: stats.clone(),
-            state: state.clone(),
-            gaps: gaps.clone(),
+            routes: routes.clone(),
+            stats: handles.stats.clone(),
             running: running.clone(),
-            expected_packet_seq: 0,
-            expected_msg_seq: 0,
-            initialized: false,
-            last_packet_time: Instant::now(),
+            tracker,
         };
 
         let handle = thread::Builder::new()
             .name(format!("udp-receiver-{}", config.stream_id))
             .spawn(move || worker.run())
-            .map_err(|e| ProtocolError::Io(std::io::Error::new(std::io::ErrorKind::Other, e)))?;
+            .map_err(|e| ProtocolError::Io(io::Error::other(e)))?;
 
         Ok(Self {
             config,
             rx,
-            stats,
-            state,
-            gaps,
+            handles,
+            routes,
             running,
             worker_handle: Some(handle),
         })
@@ -167,46 +427,68 @@ impl UdpReceiver {
 
     /// Try to receive a message (non-blocking)
     pub fn try_recv(&self) -> Result<Option<ReceivedMessage>> {
-        match self.rx.try_recv() {
-            Ok(msg) => Ok(Some(msg)),
-            Err(crossbeam_channel::TryRecvError::Empty) => Ok(None),
-            Err(crossbeam_channel::TryRecvError::Disconnected) => Err(ProtocolError::ChannelClosed),
-        }
+        try_recv(&self.rx)
     }
 
     /// Receive with timeout
     pub fn recv_timeout(&self, timeout: Duration) -> Result<Option<ReceivedMessage>> {
-        match self.rx.recv_timeout(timeout) {
-            Ok(msg) => Ok(Some(msg)),
-            Err(crossbeam_channel::RecvTimeoutError::Timeout) => Ok(None),
-            Err(crossbeam_channel::RecvTimeoutError::Disconnected) => {
-                Err(ProtocolError::ChannelClosed)
-            }
-        }
+        recv_timeout(&self.rx, timeout)
+    }
+
+    /// Deliver the messages of `stream_id` to the returned receiver from now
+    /// on, instead of `recv`. Every sender of the stream ID goes to the same
+    /// receiver. Once it is dropped they come to `recv` again.
+    pub fn route(&self, stream_id: u32) -> StreamReceiver {
+        let (tx, rx) = bounded(self.config.channel_capacity);
+        self.routes.lock().insert(stream_id, tx);
+        StreamReceiver { stream_id, rx }
     }
 
-    /// Get current stream state
+    /// Get current stream state, the worst of all streams
     pub fn state(&self) -> StreamState {
-        *self.state.lock()
+        self.handles.state()
+    }
+
+    /// State of every stream, by stream ID
+    pub fn streams(&self) -> Vec<StreamStatus> {
+        self.handles.streams()
     }
 
     /// Get current statistics
     pub fn stats(&self) -> ReceiverStatsSnapshot {
-        self.stats.snapshot()
+        self.handles.stats.snapshot()
     }
 
     /// Get detected gaps
     pub fn gaps(&self) -> Vec<GapInfo> {
-        self.gaps.lock().iter().cloned().collect()
+        self.handles.gaps()
+    }
+
+    /// Whether messages were lost for good, so the consumer has to resync
+    /// from a snapshot. Stays set until `clear_gaps`.
+    pub fn needs_snapshot(&self) -> bool {
+        self.handles.needs_snapshot()
     }
 
     /// Clear detected gaps (after recovery)
     pub fn clear_gaps(&self) {
-        self.gaps.lock().clear();
-        let mut state = self.state.lock();
-        if *state == StreamState::Degraded {
-            *state = StreamState::Active;
-        }
+        self.handles.clear_gaps()
+    }
+
+    /// Clear the gaps of one stream, after it recovered from a snapshot
+    pub fn clear_stream_gaps(&self, source: StreamSource) {
+        self.handles.clear_stream_gaps(source)
+    }
+
+    /// Accept packets sealed with `key`. Add it before the sender rotates
+    /// to it, older keys are retired when the rotation is announced.
+    pub fn add_key(&self, key: StreamKey) -> Result<()> {
+        self.handles.add_key(key)
+    }
+
+    /// ID of this receiver's session, new every time it starts
+    pub fn session_id(&self) -> u64 {
+        self.handles.session_id
     }
 
     /// Check if the receiver is running
@@ -230,52 +512,187 @@ impl Drop for UdpReceiver {
 }
 
 struct ReceiverWorker {
-    config: ReceiverConfig,
     socket: UdpSocket,
     tx: ChannelSender<ReceivedMessage>,
+    routes: Routes,
     stats: Arc<ReceiverStats>,
-    state: Arc<Mutex<StreamState>>,
-    gaps: Arc<Mutex<VecDeque<GapInfo>>>,
     running: Arc<AtomicBool>,
-    expected_packet_seq: u64,
-    expected_msg_seq: u64,
-    initialized: bool,
-    last_packet_time: Instant,
+    tracker: StreamTracker,
 }
 
 impl ReceiverWorker {
     fn run(mut self) {
-        let mut buf = vec![0u8; MAX_MTU];
+        let mut batch = RecvBatch::new();
 
         while self.running.load(Ordering::Relaxed) {
-            match self.socket.recv_from(&mut buf) {
-                Ok((len, _addr)) => {
-                    self.last_packet_time = Instant::now();
-                    self.stats.bytes_received.fetch_add(len as u64, Ordering::Relaxed);
-
-                    if let Err(_e) = self.process_packet(&buf[..len]) {
-                        self.stats.recv_errors.fetch_add(1, Ordering::Relaxed);
-                    }
-                }
-                Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => {
-                    // Check for stream timeout
-                    if self.initialized
-                        && self.last_packet_time.elapsed() > self.config.stream_timeout
-                    {
-                        let mut state = self.state.lock();
-                        if *state != StreamState::Down {
-                            *state = StreamState::Down;
-                        }
+            match batch.recv(&self.socket) {
+                Ok(_) => {
+                    for (packet, addr) in batch.packets() {
+                        self.tracker.on_packet(packet, addr);
                     }
                 }
+                Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {}
                 Err(_) => {
                     self.stats.recv_errors.fetch_add(1, Ordering::Relaxed);
                 }
             }
+            self.tracker.tick();
+            self.dispatch();
+            self.send_replies();
+        }
+
+        self.tracker.logout();
+        self.send_replies();
+    }
+
+    /// Hand the delivered messages to their consumers
+    fn dispatch(&mut self) {
+        if self.tracker.delivered.is_empty() {
+            return;
+        }
+        let mut routes = self.routes.lock();
+        for msg in self.tracker.delivered.drain(..) {
+            // Non-blocking send - drop if channel full
+            let Some(route) = routes.get(&msg.stream_id) else {
+                let _ = self.tx.try_send(msg);
+                continue;
+            };
+            if let Err(TrySendError::Disconnected(msg)) = route.try_send(msg) {
+                routes.remove(&msg.stream_id);
+                let _ = self.tx.try_send(msg);
+            }
+        }
+    }
+
+    fn send_replies(&mut self) {
+        for (packet, addr) in self.tracker.replies.drain(..) {
+            let result = self.socket.send_to(&packet, addr);
+            self.stats.record_reply(&result);
+        }
+    }
+}
+
+/// Gap being recovered by NAKs
+struct Recovery {
+    /// Packets past the gap, held back to deliver the stream in order
+    pending: BTreeMap<u64, Packet>,
+    /// Sequence the stream is known to have reached
+    known_end: u64,
+    naks_sent: u32,
+    last_nak: Instant,
+}
+
+/// Sequencing, recovery, reassembly and authentication of the received
+/// streams, shared by the thread and the async receivers. They only do the
+/// I/O: packets go in through `on_packet`, time through `tick`, messages
+/// come out of `delivered` and NAKs and session replies out of `replies`.
+pub(crate) struct StreamTracker {
+    config: ReceiverConfig,
+    pub handles: ReceiverHandles,
+    /// Messages ready for the consumer, at most `channel_capacity`
+    pub delivered: VecDeque<ReceivedMessage>,
+    /// Control packets to send and where to
+    pub replies: VecDeque<(Vec<u8>, SocketAddr)>,
+    /// Trailer of the packet being processed
+    last_opened: Option<Opened>,
+    /// Sequence of our own control packets
+    control_seq: u64,
+    streams: HashMap<StreamSource, Stream>,
+}
+
+impl StreamTracker {
+    pub fn new(config: ReceiverConfig) -> Result<Self> {
+        let auth = match &config.auth {
+            Some(auth) => Some(Arc::new(ReceiverAuth {
+                opener: Mutex::new(Opener::new(auth)),
+                sealer: Mutex::new(Sealer::new(auth, Role::Receiver)?),
+            })),
+            None => None,
+        };
+        let handles = ReceiverHandles {
+            stats: Arc::new(ReceiverStats::default()),
+            session_id: session::new_session_id(),
+            streams: Arc::new(Mutex::new(StreamTable::default())),
+            gaps: Arc::new(Mutex::new(VecDeque::new())),
+            auth,
+        };
+
+        Ok(Self {
+            config,
+            handles,
+            delivered: VecDeque::new(),
+            replies: VecDeque::new(),
+            last_opened: None,
+            control_seq: 0,
+            streams: HashMap::new(),
+        })
+    }
+
+    /// Bind the socket the streams are received on
+    pub fn bind(&self, bind_addr: SocketAddr) -> Result<UdpSocket> {
+        match self.config.multicast_group {
+            Some(group) => multicast::bind_group(bind_addr, group, self.config.multicast_interface),
+            None => Ok(UdpSocket::bind(bind_addr)?),
+        }
+    }
+
+    /// Take in a packet from `addr`
+    pub fn on_packet(&mut self, packet: &mut [u8], addr: SocketAddr) {
+        self.handles
+            .stats
+            .bytes_received
+            .fetch_add(packet.len() as u64, Ordering::Relaxed);
+
+        if let Some(len) = self.open(packet, addr) {
+            if let Err(_e) = self.process_packet(&packet[..len], addr) {
+                self.handles.stats.recv_errors.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Time driven work: the stream timeouts, NAK retries and partial
+    /// messages that never completed
+    pub fn tick(&mut self) {
+        let (streams, mut shared) = self.split();
+        for stream in streams.values_mut() {
+            stream.tick(&mut shared);
+        }
+    }
+
+    /// Tell the senders we are going, on shutdown
+    pub fn logout(&mut self) {
+        let (streams, mut shared) = self.split();
+        for stream in streams.values_mut() {
+            stream.logout(&mut shared);
         }
     }
 
-    fn process_packet(&mut self, data: &[u8]) -> Result<()> {
+    /// Verify the packet on an authenticated stream. Returns its length
+    /// without the trailer, or None when it is rejected.
+    fn open(&mut self, packet: &mut [u8], from: SocketAddr) -> Option<usize> {
+        let Some(auth) = &self.handles.auth else {
+            return Some(packet.len());
+        };
+        let stats = &self.handles.stats;
+        match auth.opener.lock().open(packet, from) {
+            Ok(opened) => {
+                // NAKs go out under the key the stream uses
+                auth.sealer.lock().select_key(opened.key_id);
+                self.last_opened = Some(opened);
+                Some(opened.len)
+            }
+            Err(ProtocolError::Replayed { .. }) => {
+                stats.replays_rejected.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+            Err(_) => {
+                stats.auth_failures.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+        }
+    }
+
+    fn process_packet(&mut self, data: &[u8], addr: SocketAddr) -> Result<()> {
         let packet = Packet::parse(data)?;
 
         // Filter by stream_id if configured
@@ -283,71 +700,537 @@ impl ReceiverWorker {
             return Ok(());
         }
 
-        self.stats.packets_received.fetch_add(1, Ordering::Relaxed);
+        let source = StreamSource {
+            addr,
+            stream_id: packet.header.stream_id,
+        };
+        if !self.streams.contains_key(&source) && !self.add_stream(source) {
+            self.handles.stats.streams_rejected.fetch_add(1, Ordering::Relaxed);
+            return Ok(());
+        }
+        self.handles.stats.packets_received.fetch_add(1, Ordering::Relaxed);
+
+        let (streams, mut shared) = self.split();
+        let stream = streams.get_mut(&source).expect("stream added");
+        stream.process(packet, &mut shared);
+
+        // Logged out, a sender coming back starts a new stream
+        if stream.ended {
+            self.streams.remove(&source);
+            let mut table = self.handles.streams.lock();
+            table.streams.remove(&source);
+            table.ended = true;
+        }
+        Ok(())
+    }
+
+    /// Start tracking a stream, making room by dropping the stream that
+    /// has been down the longest. False when there is no room.
+    fn add_stream(&mut self, source: StreamSource) -> bool {
+        let mut table = self.handles.streams.lock();
+        if self.streams.len() >= self.config.max_streams {
+            let down = self
+                .streams
+                .values()
+                .filter(|s| table.streams.get(&s.source).map(|s| s.state) == Some(StreamState::Down))
+                .min_by_key(|s| s.last_packet_time)
+                .map(|s| s.source);
+            let Some(down) = down else {
+                return false;
+            };
+            self.streams.remove(&down);
+            table.streams.remove(&down);
+        }
+
+        table.streams.insert(
+            source,
+            StreamStatus {
+                source,
+                state: StreamState::Initializing,
+                needs_snapshot: false,
+            },
+        );
+        self.streams.insert(source, Stream::new(source, &self.config));
+        true
+    }
+
+    fn split(&mut self) -> (&mut HashMap<StreamSource, Stream>, Shared<'_>) {
+        let shared = Shared {
+            config: &self.config,
+            handles: &self.handles,
+            delivered: &mut self.delivered,
+            replies: &mut self.replies,
+            control_seq: &mut self.control_seq,
+            last_opened: self.last_opened,
+        };
+        (&mut self.streams, shared)
+    }
+}
+
+/// What the streams of a receiver share
+struct Shared<'a> {
+    config: &'a ReceiverConfig,
+    handles: &'a ReceiverHandles,
+    delivered: &'a mut VecDeque<ReceivedMessage>,
+    replies: &'a mut VecDeque<(Vec<u8>, SocketAddr)>,
+    control_seq: &'a mut u64,
+    last_opened: Option<Opened>,
+}
+
+impl Shared<'_> {
+    fn stats(&self) -> &ReceiverStats {
+        &self.handles.stats
+    }
+
+    fn status(&self, source: StreamSource, f: impl FnOnce(&mut StreamStatus)) {
+        if let Some(status) = self.handles.streams.lock().streams.get_mut(&source) {
+            f(status);
+        }
+    }
+
+    fn send_control(&mut self, source: StreamSource, messages: &[ControlMessage]) {
+        let mut packet = PacketBuilder::control(source.stream_id, *self.control_seq, messages);
+        *self.control_seq += 1;
+        if let Some(auth) = &self.handles.auth {
+            auth.sealer.lock().seal(&mut packet);
+        }
+        self.replies.push_back((packet, source.addr));
+    }
+
+    fn notify(&self, event: SessionEvent) {
+        if let Some(handler) = &self.config.on_session {
+            handler.call(event);
+        }
+    }
+}
+
+/// Sequencing, recovery and session of one stream
+struct Stream {
+    source: StreamSource,
+    /// Session of the sender, once it said `Hello`
+    session_id: Option<u64>,
+    /// Last `Accept` sent to a sender we have no session with
+    last_session_request: Option<Instant>,
+    expected_msg_seq: u64,
+    initialized: bool,
+    last_packet_time: Instant,
+    recovery: Option<Recovery>,
+    reassembler: Reassembler,
+    latency: LatencyTracker,
+    /// The sender logged out
+    ended: bool,
+}
+
+impl Stream {
+    fn new(source: StreamSource, config: &ReceiverConfig) -> Self {
+        Self {
+            source,
+            session_id: None,
+            last_session_request: None,
+            expected_msg_seq: 0,
+            initialized: false,
+            last_packet_time: Instant::now(),
+            recovery: None,
+            reassembler: Self::reassembler(config),
+            latency: LatencyTracker::default(),
+            ended: false,
+        }
+    }
+
+    fn reassembler(config: &ReceiverConfig) -> Reassembler {
+        Reassembler::new(
+            config.max_message_size,
+            config.max_partial_messages,
+            config.reassembly_timeout,
+        )
+    }
+
+    fn tick(&mut self, shared: &mut Shared) {
+        if self.initialized && self.last_packet_time.elapsed() > shared.config.stream_timeout {
+            shared.status(self.source, |status| status.state = StreamState::Down);
+        }
+
+        self.check_recovery(shared);
+        if self.reassembler.pending() > 0 {
+            self.reassembler.expire();
+            self.count_dropped_partials(shared);
+        }
+    }
+
+    fn process(&mut self, packet: Packet, shared: &mut Shared) {
+        self.last_packet_time = Instant::now();
+
+        if packet.is_control() {
+            self.handle_control(&packet, shared);
+            return;
+        }
+
+        if self.session_id.is_none() {
+            self.request_session(shared);
+        }
 
         // Handle heartbeat
         if packet.header.is_heartbeat() {
-            self.stats.heartbeats_received.fetch_add(1, Ordering::Relaxed);
-            self.update_state_active();
-            return Ok(());
+            shared.stats().heartbeats_received.fetch_add(1, Ordering::Relaxed);
+            if let Some(send_time_ns) = packet.header.send_time_ns {
+                self.latency.on_heartbeat(send_time_ns, latency::now_ns());
+            }
+            self.update_state_active(shared);
+            // A heartbeat carries the next sequence, revealing a lost tail
+            if self.initialized && shared.config.enable_naks {
+                self.detect_loss(packet.header.first_msg_seq, shared);
+            }
+            return;
         }
 
         // Initialize on first packet
         if !self.initialized {
-            self.expected_packet_seq = packet.header.packet_seq;
             self.expected_msg_seq = packet.header.first_msg_seq;
             self.initialized = true;
-            self.update_state_active();
+            self.update_state_active(shared);
         }
 
-        // Check for duplicates
-        if packet.header.packet_seq < self.expected_packet_seq {
-            self.stats.duplicates_dropped.fetch_add(1, Ordering::Relaxed);
-            return Ok(());
+        // Check for duplicates, resent packets included
+        if packet.end_msg_seq() <= self.expected_msg_seq {
+            shared.stats().duplicates_dropped.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+
+        // Resent packets carry the time they were first sent
+        let resent = self
+            .recovery
+            .as_ref()
+            .is_some_and(|r| packet.header.first_msg_seq < r.known_end);
+        if let (Some(send_time_ns), false) = (packet.header.send_time_ns, resent) {
+            let stats = shared.stats();
+            self.latency
+                .on_packet(send_time_ns, latency::now_ns(), &stats.latency, &stats.jitter);
         }
 
         // Check for gaps
-        if packet.header.packet_seq > self.expected_packet_seq {
-            let gap_size = packet.header.first_msg_seq - self.expected_msg_seq;
-            self.record_gap(self.expected_msg_seq, packet.header.first_msg_seq, gap_size);
+        if packet.header.first_msg_seq > self.expected_msg_seq {
+            if !shared.config.enable_naks {
+                let gap_size = packet.header.first_msg_seq - self.expected_msg_seq;
+                self.record_gap(self.expected_msg_seq, packet.header.first_msg_seq, gap_size, shared);
+                self.deliver(packet, shared);
+                return;
+            }
+
+            self.detect_loss(packet.header.first_msg_seq, shared);
+            if let Some(recovery) = &mut self.recovery {
+                recovery.known_end = recovery.known_end.max(packet.end_msg_seq());
+                recovery.pending.entry(packet.header.first_msg_seq).or_insert(packet);
+                if recovery.pending.len() > shared.config.max_pending_packets {
+                    self.give_up(shared);
+                }
+            }
+            return;
         }
 
-        // Process messages
+        self.deliver(packet, shared);
+        self.drain_pending(shared);
+    }
+
+    /// Forward the messages not delivered yet and move past the packet
+    fn deliver(&mut self, packet: Packet, shared: &mut Shared) {
+        let end = packet.end_msg_seq();
+
         for msg in packet.messages {
-            if let Some(msg_type) = msg.message_type() {
-                let received = ReceivedMessage {
-                    msg_type,
-                    payload: msg.payload,
-                    seq: msg.seq,
-                    stream_id: packet.header.stream_id,
-                };
-
-                self.stats.messages_received.fetch_add(1, Ordering::Relaxed);
-
-                // Non-blocking send - drop if channel full
-                let _ = self.tx.try_send(received);
+            if msg.seq < self.expected_msg_seq {
+                continue;
+            }
+            let Some(msg_type) = msg.message_type() else {
+                continue;
+            };
+
+            let (payload, seq) = if msg.is_fragment() {
+                let stream_id = packet.header.stream_id;
+                let result = self.reassembler.push(stream_id, msg_type, msg.seq, &msg.payload);
+                self.count_dropped_partials(shared);
+                match result {
+                    Ok(Some(message)) => {
+                        shared.stats().messages_reassembled.fetch_add(1, Ordering::Relaxed);
+                        (message.payload, message.seq)
+                    }
+                    Ok(None) => continue,
+                    Err(_) => {
+                        shared.stats().recv_errors.fetch_add(1, Ordering::Relaxed);
+                        continue;
+                    }
+                }
+            } else {
+                (msg.payload, msg.seq)
+            };
+
+            let received = ReceivedMessage {
+                msg_type,
+                payload,
+                seq,
+                stream_id: packet.header.stream_id,
+                source: self.source.addr,
+            };
+
+            shared.stats().messages_received.fetch_add(1, Ordering::Relaxed);
+
+            // Dropped when the consumer falls behind
+            if shared.delivered.len() < shared.config.channel_capacity {
+                shared.delivered.push_back(received);
             }
         }
 
-        // Update expected sequences
-        self.expected_packet_seq = packet.header.packet_seq + 1;
-        self.expected_msg_seq = packet.header.first_msg_seq + packet.header.msg_count as u64;
+        self.expected_msg_seq = self.expected_msg_seq.max(end);
+    }
 
-        Ok(())
+    /// Deliver held back packets the stream caught up with, and end the
+    /// recovery once nothing is missing anymore
+    fn drain_pending(&mut self, shared: &mut Shared) {
+        loop {
+            let Some(recovery) = &mut self.recovery else {
+                return;
+            };
+            match recovery.pending.first_entry() {
+                Some(entry) if *entry.key() <= self.expected_msg_seq => {
+                    let packet = entry.remove();
+                    self.deliver(packet, shared);
+                }
+                Some(_) => return,
+                None => break,
+            }
+        }
+
+        if self.recovery.as_ref().is_some_and(|r| self.expected_msg_seq >= r.known_end) {
+            self.recovery = None;
+            shared.stats().gaps_recovered.fetch_add(1, Ordering::Relaxed);
+            shared.status(self.source, |status| {
+                if status.state == StreamState::Degraded && !status.needs_snapshot {
+                    status.state = StreamState::Active;
+                }
+            });
+        }
+    }
+
+    /// The stream reached `seq`, NAK whatever before it was never seen
+    fn detect_loss(&mut self, seq: u64, shared: &mut Shared) {
+        let known_end = self
+            .recovery
+            .as_ref()
+            .map_or(self.expected_msg_seq, |r| r.known_end);
+        if seq <= known_end {
+            return;
+        }
+
+        self.record_gap(known_end, seq, seq - known_end, shared);
+        let recovery = self.recovery.get_or_insert_with(|| Recovery {
+            pending: BTreeMap::new(),
+            known_end,
+            naks_sent: 0,
+            last_nak: Instant::now(),
+        });
+        recovery.known_end = seq;
+        self.send_naks(
+            &[ControlMessage::Nak {
+                from_seq: known_end,
+                to_seq: seq,
+            }],
+            shared,
+        );
+    }
+
+    /// Ask again for what is still missing, or give up after the retries
+    fn check_recovery(&mut self, shared: &mut Shared) {
+        let Some(recovery) = &self.recovery else {
+            return;
+        };
+        if recovery.last_nak.elapsed() < shared.config.nak_timeout {
+            return;
+        }
+        if recovery.naks_sent > shared.config.nak_retries {
+            self.give_up(shared);
+            return;
+        }
+
+        // Ranges between the held back packets
+        let mut missing = Vec::new();
+        let mut cursor = self.expected_msg_seq;
+        for (first, packet) in &recovery.pending {
+            if *first > cursor {
+                missing.push(ControlMessage::Nak {
+                    from_seq: cursor,
+                    to_seq: *first,
+                });
+            }
+            cursor = cursor.max(packet.end_msg_seq());
+        }
+        if cursor < recovery.known_end {
+            missing.push(ControlMessage::Nak {
+                from_seq: cursor,
+                to_seq: recovery.known_end,
+            });
+        }
+        self.send_naks(&missing, shared);
+    }
+
+    fn send_naks(&mut self, naks: &[ControlMessage], shared: &mut Shared) {
+        let Some(recovery) = &mut self.recovery else {
+            return;
+        };
+        recovery.naks_sent += 1;
+        recovery.last_nak = Instant::now();
+
+        shared.stats().naks_sent.fetch_add(1, Ordering::Relaxed);
+        shared.send_control(self.source, naks);
+    }
+
+    fn accept(&mut self, shared: &mut Shared) {
+        let accept = ControlMessage::Accept {
+            session_id: shared.handles.session_id,
+            version: PROTOCOL_VERSION,
+        };
+        shared.send_control(self.source, &[accept]);
+    }
+
+    /// Joined a running stream: accept unprompted so the sender answers
+    /// with its `Hello`
+    fn request_session(&mut self, shared: &mut Shared) {
+        let interval = Duration::from_millis(HEARTBEAT_INTERVAL_MS);
+        if self.last_session_request.is_some_and(|at| at.elapsed() < interval) {
+            return;
+        }
+        self.last_session_request = Some(Instant::now());
+        self.accept(shared);
+    }
+
+    fn logout(&mut self, shared: &mut Shared) {
+        if self.session_id.take().is_some() {
+            let logout = ControlMessage::Logout {
+                session_id: shared.handles.session_id,
+            };
+            shared.send_control(self.source, &[logout]);
+        }
+    }
+
+    fn on_hello(&mut self, session_id: u64, version: u8, shared: &mut Shared) {
+        if version != PROTOCOL_VERSION {
+            return;
+        }
+        let peer = self.source.addr;
+        let event = match self.session_id {
+            None => Some(SessionEvent::Established { peer, session_id }),
+            // Its sequence numbers start over
+            Some(old_session_id) if old_session_id != session_id => {
+                self.restart(shared.config);
+                Some(SessionEvent::Restarted {
+                    peer,
+                    old_session_id,
+                    session_id,
+                })
+            }
+            Some(_) => None,
+        };
+
+        self.session_id = Some(session_id);
+        self.update_state_active(shared);
+        self.accept(shared);
+        if let Some(event) = event {
+            shared.notify(event);
+        }
+    }
+
+    /// Forget the sequencing of the stream, the next packet starts it again
+    fn restart(&mut self, config: &ReceiverConfig) {
+        self.initialized = false;
+        self.expected_msg_seq = 0;
+        self.recovery = None;
+        self.latency.reset();
+        self.reassembler = Self::reassembler(config);
+    }
+
+    fn handle_control(&mut self, packet: &Packet, shared: &mut Shared) {
+        let peer = self.source.addr;
+        for control in packet.control_messages() {
+            match control {
+                // The sender no longer has messages we are still waiting for
+                ControlMessage::Unavailable { to_seq, .. } => {
+                    if self.recovery.is_some() && to_seq > self.expected_msg_seq {
+                        self.give_up(shared);
+                    }
+                }
+                // Packets sent after this one no longer use older keys
+                ControlMessage::KeyRotation { key_id } => {
+                    if let (Some(auth), Some(opened)) = (&shared.handles.auth, shared.last_opened) {
+                        auth.opener.lock().retire_keys(
+                            self.source,
+                            key_id,
+                            opened,
+                            packet.header.packet_seq,
+                        );
+                    }
+                }
+                ControlMessage::Hello { session_id, version } => {
+                    self.on_hello(session_id, version, shared);
+                }
+                // Numbered from `next_seq` on, nothing before it is missing
+                ControlMessage::SequenceReset { next_seq } => {
+                    if self.session_id.is_some() {
+                        self.recovery = None;
+                        self.expected_msg_seq = next_seq;
+                        self.initialized = true;
+                        shared.notify(SessionEvent::SequenceReset { peer, next_seq });
+                    }
+                }
+                ControlMessage::Logout { session_id } => {
+                    if self.session_id == Some(session_id) {
+                        self.session_id = None;
+                        self.ended = true;
+                        shared.notify(SessionEvent::LoggedOut { peer, session_id });
+                    }
+                }
+                ControlMessage::Nak { .. } | ControlMessage::Accept { .. } => {}
+            }
+        }
+    }
+
+    /// Skip what is still missing and deliver what was held back. The
+    /// consumer can only catch up from a snapshot now.
+    fn give_up(&mut self, shared: &mut Shared) {
+        let Some(recovery) = self.recovery.take() else {
+            return;
+        };
+        shared.stats().gaps_unrecoverable.fetch_add(1, Ordering::Relaxed);
+
+        for packet in recovery.pending.into_values() {
+            self.deliver(packet, shared);
+        }
+        self.expected_msg_seq = self.expected_msg_seq.max(recovery.known_end);
+        shared.status(self.source, |status| {
+            status.needs_snapshot = true;
+            status.state = StreamState::Degraded;
+        });
     }
 
-    fn record_gap(&mut self, expected: u64, received: u64, gap_size: u64) {
-        self.stats.gaps_detected.fetch_add(1, Ordering::Relaxed);
-        self.stats.total_gap_messages.fetch_add(gap_size, Ordering::Relaxed);
+    fn count_dropped_partials(&mut self, shared: &Shared) {
+        let dropped = self.reassembler.take_dropped();
+        if dropped > 0 {
+            shared
+                .stats()
+                .partial_messages_dropped
+                .fetch_add(dropped, Ordering::Relaxed);
+        }
+    }
+
+    fn record_gap(&mut self, expected: u64, received: u64, gap_size: u64, shared: &Shared) {
+        shared.stats().gaps_detected.fetch_add(1, Ordering::Relaxed);
+        shared.stats().total_gap_messages.fetch_add(gap_size, Ordering::Relaxed);
 
         let gap = GapInfo {
+            source: self.source,
             expected_seq: expected,
             received_seq: received,
             gap_size,
             timestamp: Instant::now(),
         };
 
-        let mut gaps = self.gaps.lock();
+        let mut gaps = shared.handles.gaps.lock();
         gaps.push_back(gap);
 
         // Keep only recent gaps
@@ -355,14 +1238,14 @@ impl ReceiverWorker {
             gaps.pop_front();
         }
 
-        let mut state = self.state.lock();
-        *state = StreamState::Degraded;
+        shared.status(self.source, |status| status.state = StreamState::Degraded);
     }
 
-    fn update_state_active(&self) {
-        let mut state = self.state.lock();
-        if *state == StreamState::Initializing || *state == StreamState::Down {
-            *state = StreamState::Active;
-        }
+    fn update_state_active(&self, shared: &Shared) {
+        shared.status(self.source, |status| {
+            if status.state == StreamState::Initializing || status.state == StreamState::Down {
+                status.state = StreamState::Active;
+            }
+        });
     }
 }
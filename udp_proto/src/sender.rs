@@ -1,13 +1,21 @@
-use std::net::{SocketAddr, UdpSocket};
+use std::collections::{HashMap, VecDeque};
+use std::io;
+use std::net::{Ipv4Addr, SocketAddr, UdpSocket};
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
 use std::thread::{self, JoinHandle};
 use std::time::{Duration, Instant};
 
-use crossbeam_channel::{bounded, Receiver, Sender as ChannelSender, TrySendError};
+use bytes::Bytes;
+use parking_lot::{Condvar, Mutex};
 
+use crate::auth::{AuthConfig, Opener, Role, Sealer, StreamKey};
+use crate::batch_io;
+use crate::binary;
 use crate::error::{ProtocolError, Result};
+use crate::multicast;
 use crate::protocol::*;
+use crate::session::{self, SessionEvent, SessionHandler};
 
 /// Configuration for the UDP sender
 #[derive(Debug, Clone)]
@@ -20,8 +28,28 @@ pub struct SenderConfig {
     pub max_batch_delay: Duration,
     /// Channel capacity for outgoing messages
     pub channel_capacity: usize,
+    /// What `UdpSender::try_send` does when the channel is full
+    pub overflow: OverflowPolicy,
     /// Enable heartbeats
     pub enable_heartbeats: bool,
+    /// Packets kept to answer NAKs from receivers (0 = no retransmission)
+    pub retransmit_buffer: usize,
+    /// Largest message accepted, messages over `MAX_MESSAGE_PAYLOAD` are
+    /// sent as fragments
+    pub max_message_size: usize,
+    /// Sign or encrypt packets with a pre-shared key (None = plain packets)
+    pub auth: Option<AuthConfig>,
+    /// Hops packets to a multicast `target_addr` may take
+    pub multicast_ttl: u32,
+    /// Interface multicast goes out on (unspecified = the system picks)
+    pub multicast_interface: Ipv4Addr,
+    /// Deliver multicast to receivers on this host too
+    pub multicast_loop: bool,
+    /// Told when a receiver accepts the session, restarts or logs out
+    pub on_session: Option<SessionHandler>,
+    /// Stamp data packets and heartbeats with the send time, for receivers
+    /// to measure one-way latency
+    pub send_timestamps: bool,
 }
 
 impl Default for SenderConfig {
@@ -31,11 +59,39 @@ impl Default for SenderConfig {
             target_addr: "127.0.0.1:9000".parse().unwrap(),
             max_batch_delay: Duration::from_micros(100),
             channel_capacity: 10_000,
+            overflow: OverflowPolicy::Reject,
             enable_heartbeats: true,
+            retransmit_buffer: 1024,
+            max_message_size: DEFAULT_MAX_MESSAGE_SIZE,
+            auth: None,
+            multicast_ttl: 1,
+            multicast_interface: Ipv4Addr::UNSPECIFIED,
+            multicast_loop: true,
+            on_session: None,
+            send_timestamps: false,
         }
     }
 }
 
+/// What `UdpSender::try_send` does with a message when its channel is
+/// full. Only book messages are ever dropped, anything else is refused
+/// with `ChannelFull` when no room can be made.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Refuse the message
+    #[default]
+    Reject,
+    /// Drop the oldest queued book message, or the new one when none is
+    /// queued
+    DropOldest,
+    /// Drop the new message
+    DropNewest,
+    /// Replace the queued book message of the same type and symbol, else
+    /// as `DropOldest`. Receivers see a gap in the book sequence of the
+    /// symbol and resync it from a snapshot.
+    Coalesce,
+}
+
 /// Outgoing message to be sent
 #[derive(Debug)]
 pub struct OutgoingMessage {
@@ -51,6 +107,22 @@ pub struct SenderStats {
     pub bytes_sent: AtomicU64,
     pub heartbeats_sent: AtomicU64,
     pub send_errors: AtomicU64,
+    /// Messages too large for one packet, sent as fragments
+    pub messages_fragmented: AtomicU64,
+    pub naks_received: AtomicU64,
+    pub retransmits_sent: AtomicU64,
+    /// NAKs for messages no longer in the retransmission buffer
+    pub naks_unavailable: AtomicU64,
+    /// NAKs dropped for a bad or missing authentication tag
+    pub auth_failures: AtomicU64,
+    /// Messages that found the channel full
+    pub channel_full: AtomicU64,
+    /// Messages refused with `ChannelFull`
+    pub messages_rejected: AtomicU64,
+    /// Book messages dropped to make room, queued or new
+    pub messages_dropped: AtomicU64,
+    /// Queued book messages replaced by a newer one of their symbol
+    pub messages_coalesced: AtomicU64,
 }
 
 impl SenderStats {
@@ -61,6 +133,38 @@ impl SenderStats {
             bytes_sent: self.bytes_sent.load(Ordering::Relaxed),
             heartbeats_sent: self.heartbeats_sent.load(Ordering::Relaxed),
             send_errors: self.send_errors.load(Ordering::Relaxed),
+            messages_fragmented: self.messages_fragmented.load(Ordering::Relaxed),
+            naks_received: self.naks_received.load(Ordering::Relaxed),
+            retransmits_sent: self.retransmits_sent.load(Ordering::Relaxed),
+            naks_unavailable: self.naks_unavailable.load(Ordering::Relaxed),
+            auth_failures: self.auth_failures.load(Ordering::Relaxed),
+            channel_full: self.channel_full.load(Ordering::Relaxed),
+            messages_rejected: self.messages_rejected.load(Ordering::Relaxed),
+            messages_dropped: self.messages_dropped.load(Ordering::Relaxed),
+            messages_coalesced: self.messages_coalesced.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Count a packet that went out, or failed to
+    pub(crate) fn record(&self, packet: &OutgoingPacket, result: &io::Result<usize>) {
+        let Ok(n) = result else {
+            self.send_errors.fetch_add(1, Ordering::Relaxed);
+            return;
+        };
+        self.bytes_sent.fetch_add(*n as u64, Ordering::Relaxed);
+        match packet.kind {
+            PacketKind::Data { msg_count } => {
+                self.packets_sent.fetch_add(1, Ordering::Relaxed);
+                self.messages_sent.fetch_add(msg_count as u64, Ordering::Relaxed);
+            }
+            PacketKind::Heartbeat => {
+                self.packets_sent.fetch_add(1, Ordering::Relaxed);
+                self.heartbeats_sent.fetch_add(1, Ordering::Relaxed);
+            }
+            PacketKind::Retransmit => {
+                self.retransmits_sent.fetch_add(1, Ordering::Relaxed);
+            }
+            PacketKind::Control => {}
         }
     }
 }
@@ -72,86 +176,815 @@ pub struct SenderStatsSnapshot {
     pub bytes_sent: u64,
     pub heartbeats_sent: u64,
     pub send_errors: u64,
+    pub messages_fragmented: u64,
+    pub naks_received: u64,
+    pub retransmits_sent: u64,
+    pub naks_unavailable: u64,
+    pub auth_failures: u64,
+    pub channel_full: u64,
+    pub messages_rejected: u64,
+    pub messages_dropped: u64,
+    pub messages_coalesced: u64,
 }
 
-/// UDP sender with batching and automatic heartbeats
-pub struct UdpSender {
-    #[allow(dead_code)]
-    config: SenderConfig,
-    tx: ChannelSender<OutgoingMessage>,
+/// Recently sent packets by message sequence, for retransmission
+struct RetransmitBuffer {
+    capacity: usize,
+    /// (first_msg_seq, end_msg_seq, packet bytes), oldest first
+    packets: VecDeque<(u64, u64, Bytes)>,
+}
+
+impl RetransmitBuffer {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            packets: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    fn push(&mut self, first_msg_seq: u64, end_msg_seq: u64, data: Bytes) {
+        if self.packets.len() == self.capacity {
+            self.packets.pop_front();
+        }
+        self.packets.push_back((first_msg_seq, end_msg_seq, data));
+    }
+
+    /// Packets holding messages `from_seq..to_seq`, None if the oldest of
+    /// them are gone already
+    fn range(&self, from_seq: u64, to_seq: u64) -> Option<Vec<Bytes>> {
+        let (oldest, _, _) = self.packets.front()?;
+        if from_seq < *oldest {
+            return None;
+        }
+        Some(
+            self.packets
+                .iter()
+                .filter(|(first, end, _)| *end > from_seq && *first < to_seq)
+                .map(|(_, _, data)| data.clone())
+                .collect(),
+        )
+    }
+}
+
+/// Keys of an authenticated stream
+struct SenderAuth {
+    sealer: Mutex<Sealer>,
+    /// Checks NAKs from receivers
+    opener: Mutex<Opener>,
+}
+
+/// Session of a sender and the receivers that accepted it
+struct SenderSession {
+    id: u64,
+    /// Session IDs of the receivers by address
+    peers: Mutex<HashMap<SocketAddr, u64>>,
+}
+
+/// What a packet leaving the sender carries
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PacketKind {
+    Data { msg_count: u16 },
+    Heartbeat,
+    Retransmit,
+    Control,
+}
+
+/// A sealed packet ready to go out
+pub(crate) struct OutgoingPacket {
+    pub data: Bytes,
+    pub dest: SocketAddr,
+    pub kind: PacketKind,
+}
+
+/// State of a sending stream, shared by the batching and the NAK handling
+/// of the thread and the async senders. They only do the I/O.
+#[derive(Clone)]
+pub(crate) struct SenderState {
+    pub config: SenderConfig,
+    pub stats: Arc<SenderStats>,
+    retransmit: Option<Arc<Mutex<RetransmitBuffer>>>,
+    auth: Option<Arc<SenderAuth>>,
+    session: Arc<SenderSession>,
+    /// Control packets take numbers too
+    packet_seq: Arc<AtomicU64>,
+}
+
+impl SenderState {
+    pub fn new(config: SenderConfig) -> Result<Self> {
+        let auth = match &config.auth {
+            Some(auth) => Some(Arc::new(SenderAuth {
+                sealer: Mutex::new(Sealer::new(auth, Role::Sender)?),
+                opener: Mutex::new(Opener::new(auth)),
+            })),
+            None => None,
+        };
+        let retransmit = (config.retransmit_buffer > 0)
+            .then(|| Arc::new(Mutex::new(RetransmitBuffer::new(config.retransmit_buffer))));
+
+        Ok(Self {
+            config,
+            stats: Arc::new(SenderStats::default()),
+            retransmit,
+            auth,
+            session: Arc::new(SenderSession {
+                id: session::new_session_id(),
+                peers: Mutex::new(HashMap::new()),
+            }),
+            packet_seq: Arc::new(AtomicU64::new(0)),
+        })
+    }
+
+    pub fn session_id(&self) -> u64 {
+        self.session.id
+    }
+
+    /// Whether any receiver accepted the session
+    fn accepted(&self) -> bool {
+        !self.session.peers.lock().is_empty()
+    }
+
+    /// Bind the socket a sender sends from
+    pub fn bind(&self, bind_addr: SocketAddr) -> Result<UdpSocket> {
+        let socket = UdpSocket::bind(bind_addr)?;
+        if self.config.target_addr.ip().is_multicast() {
+            multicast::configure_sender(
+                &socket,
+                self.config.multicast_ttl,
+                self.config.multicast_interface,
+                self.config.multicast_loop,
+            )?;
+        }
+        Ok(socket)
+    }
+
+    pub fn check_size(&self, payload: &[u8]) -> Result<()> {
+        // Bounded by what a fragment header can count, too
+        let max = self
+            .config
+            .max_message_size
+            .min(u16::MAX as usize * MAX_FRAGMENT_LEN);
+        if payload.len() > max {
+            return Err(ProtocolError::MessageTooLarge {
+                size: payload.len(),
+                max,
+            });
+        }
+        Ok(())
+    }
+
+    fn next_packet_seq(&self) -> u64 {
+        self.packet_seq.fetch_add(1, Ordering::Relaxed)
+    }
+
+    fn seal(&self, packet: &mut Vec<u8>) {
+        if let Some(auth) = &self.auth {
+            auth.sealer.lock().seal(packet);
+        }
+    }
+
+    fn control(&self, messages: &[ControlMessage], dest: SocketAddr) -> OutgoingPacket {
+        let mut packet =
+            PacketBuilder::control(self.config.stream_id, self.next_packet_seq(), messages);
+        self.seal(&mut packet);
+        OutgoingPacket {
+            data: Bytes::from(packet),
+            dest,
+            kind: PacketKind::Control,
+        }
+    }
+
+    fn hello(&self, dest: SocketAddr) -> OutgoingPacket {
+        let hello = ControlMessage::Hello {
+            session_id: self.session.id,
+            version: PROTOCOL_VERSION,
+        };
+        self.control(&[hello], dest)
+    }
+
+    fn notify(&self, event: SessionEvent) {
+        if let Some(handler) = &self.config.on_session {
+            handler.call(event);
+        }
+    }
+
+    /// Seal with `key` from now on. Returns the announcement telling
+    /// receivers to retire the older keys, sealed under the old key.
+    pub fn rotate_key(&self, key: StreamKey) -> Result<OutgoingPacket> {
+        let auth = self.auth.as_ref().ok_or(ProtocolError::NoStreamKey)?;
+        let mut sealer = auth.sealer.lock();
+        if key.id <= sealer.key_id() {
+            return Err(ProtocolError::UnknownKey(key.id));
+        }
+
+        let mut announcement = PacketBuilder::control(
+            self.config.stream_id,
+            self.next_packet_seq(),
+            &[ControlMessage::KeyRotation { key_id: key.id }],
+        );
+        sealer.seal(&mut announcement);
+        auth.opener.lock().add_key(&key);
+        sealer.add_key(&key);
+        sealer.select_key(key.id);
+
+        Ok(OutgoingPacket {
+            data: Bytes::from(announcement),
+            dest: self.config.target_addr,
+            kind: PacketKind::Control,
+        })
+    }
+
+    /// Answer a control packet from the receiver at `from`: NAKs out of the
+    /// retransmission buffer, session messages with our `Hello`
+    pub fn handle_control(
+        &self,
+        buf: &mut [u8],
+        from: SocketAddr,
+        out: &mut VecDeque<OutgoingPacket>,
+    ) {
+        let len = match &self.auth {
+            Some(auth) => match auth.opener.lock().open_nak(buf, from) {
+                Ok(opened) => opened.len,
+                Err(_) => {
+                    self.stats.auth_failures.fetch_add(1, Ordering::Relaxed);
+                    return;
+                }
+            },
+            None => buf.len(),
+        };
+        let Ok(packet) = Packet::parse(&buf[..len]) else {
+            return;
+        };
+        if packet.header.stream_id != self.config.stream_id || !packet.is_control() {
+            return;
+        }
+
+        for control in packet.control_messages() {
+            match control {
+                ControlMessage::Nak { from_seq, to_seq } => {
+                    self.handle_nak(from_seq, to_seq, from, out);
+                }
+                ControlMessage::Accept { session_id, version } if version == PROTOCOL_VERSION => {
+                    let old = self.session.peers.lock().insert(from, session_id);
+                    let event = match old {
+                        None => SessionEvent::Established {
+                            peer: from,
+                            session_id,
+                        },
+                        Some(old) if old != session_id => SessionEvent::Restarted {
+                            peer: from,
+                            old_session_id: old,
+                            session_id,
+                        },
+                        Some(_) => continue,
+                    };
+                    // Joined after our `Hello`, or restarted since
+                    out.push_back(self.hello(from));
+                    self.notify(event);
+                }
+                ControlMessage::Logout { session_id } => {
+                    let mut peers = self.session.peers.lock();
+                    if peers.get(&from) == Some(&session_id) {
+                        peers.remove(&from);
+                        drop(peers);
+                        self.notify(SessionEvent::LoggedOut {
+                            peer: from,
+                            session_id,
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn handle_nak(
+        &self,
+        from_seq: u64,
+        to_seq: u64,
+        from: SocketAddr,
+        out: &mut VecDeque<OutgoingPacket>,
+    ) {
+        let Some(buffer) = &self.retransmit else {
+            return;
+        };
+        self.stats.naks_received.fetch_add(1, Ordering::Relaxed);
+
+        // Resend to whoever asked, not every receiver lost the same packets
+        let packets = buffer.lock().range(from_seq, to_seq);
+        match packets {
+            Some(packets) => {
+                out.extend(packets.into_iter().map(|data| OutgoingPacket {
+                    data,
+                    dest: from,
+                    kind: PacketKind::Retransmit,
+                }));
+            }
+            None => {
+                self.stats.naks_unavailable.fetch_add(1, Ordering::Relaxed);
+                out.push_back(self.control(&[ControlMessage::Unavailable { from_seq, to_seq }], from));
+            }
+        }
+    }
+}
+
+/// Batches messages into sealed packets
+pub(crate) struct Batcher {
+    state: SenderState,
+    builder: PacketBuilder,
+    msg_seq: u64,
+    last_send: Instant,
+    last_hello: Instant,
+}
+
+impl Batcher {
+    pub fn new(state: SenderState) -> Self {
+        let builder = Self::builder(&state, state.next_packet_seq(), 0);
+        Self {
+            state,
+            builder,
+            msg_seq: 0,
+            last_send: Instant::now(),
+            last_hello: Instant::now(),
+        }
+    }
+
+    fn builder(state: &SenderState, packet_seq: u64, first_msg_seq: u64) -> PacketBuilder {
+        let builder = PacketBuilder::new(state.config.stream_id, packet_seq, first_msg_seq);
+        if state.config.send_timestamps {
+            builder.with_send_time()
+        } else {
+            builder
+        }
+    }
+
+    /// Queue the `Hello` opening the session
+    pub fn hello(&mut self, out: &mut VecDeque<OutgoingPacket>) {
+        self.last_hello = Instant::now();
+        out.push_back(self.state.hello(self.state.config.target_addr));
+    }
+
+    /// Queue the batch and number the messages after it from `next_seq`
+    pub fn reset_sequence(&mut self, next_seq: u64, out: &mut VecDeque<OutgoingPacket>) {
+        self.flush(out);
+        self.msg_seq = next_seq;
+        self.builder = Self::builder(&self.state, self.builder.packet_seq(), next_seq);
+        // Its sequence numbers would come back
+        if let Some(buffer) = &self.state.retransmit {
+            buffer.lock().packets.clear();
+        }
+        out.push_back(self.state.control(
+            &[ControlMessage::SequenceReset { next_seq }],
+            self.state.config.target_addr,
+        ));
+    }
+
+    /// Queue what is batched and the `Logout` ending the session
+    pub fn logout(&mut self, out: &mut VecDeque<OutgoingPacket>) {
+        self.flush(out);
+        out.push_back(self.state.control(
+            &[ControlMessage::Logout {
+                session_id: self.state.session.id,
+            }],
+            self.state.config.target_addr,
+        ));
+    }
+
+    /// Add a message to the batch, queueing the batch whenever it is full
+    pub fn push(&mut self, msg: &OutgoingMessage, out: &mut VecDeque<OutgoingPacket>) {
+        self.last_send = Instant::now();
+        if msg.payload.len() <= MAX_MESSAGE_PAYLOAD {
+            if !self.builder.try_add_message(msg.msg_type, &msg.payload) {
+                self.flush(out);
+                self.builder.try_add_message(msg.msg_type, &msg.payload);
+            }
+            return;
+        }
+
+        self.state.stats.messages_fragmented.fetch_add(1, Ordering::Relaxed);
+        for (fragment, chunk) in FragmentHeader::split(&msg.payload) {
+            if !self.builder.try_add_fragment(msg.msg_type, fragment, chunk) {
+                self.flush(out);
+                self.builder.try_add_fragment(msg.msg_type, fragment, chunk);
+            }
+        }
+    }
+
+    /// Queue the batch, if it holds anything, and start the next one
+    pub fn flush(&mut self, out: &mut VecDeque<OutgoingPacket>) {
+        if self.builder.is_empty() {
+            return;
+        }
+        let next = Self::builder(
+            &self.state,
+            self.state.next_packet_seq(),
+            self.msg_seq + self.builder.msg_count() as u64,
+        );
+        let full = std::mem::replace(&mut self.builder, next);
+        let msg_count = full.msg_count();
+        self.msg_seq += msg_count as u64;
+        self.last_send = Instant::now();
+
+        let mut data = full.finish();
+        self.state.seal(&mut data);
+        let data = Bytes::from(data);
+
+        // Kept before it goes out, so a NAK never races ahead of it
+        if let Some(buffer) = &self.state.retransmit {
+            let first = self.msg_seq - msg_count as u64;
+            buffer.lock().push(first, self.msg_seq, data.clone());
+        }
+
+        out.push_back(OutgoingPacket {
+            data,
+            dest: self.state.config.target_addr,
+            kind: PacketKind::Data { msg_count },
+        });
+    }
+
+    /// Nothing was added for `max_batch_delay`: queue the partial batch, or a
+    /// heartbeat when one is due. Repeats the `Hello` until a receiver accepts.
+    pub fn idle(&mut self, out: &mut VecDeque<OutgoingPacket>) {
+        let interval = Duration::from_millis(HEARTBEAT_INTERVAL_MS);
+        if self.last_hello.elapsed() >= interval && !self.state.accepted() {
+            self.hello(out);
+        }
+
+        let config = &self.state.config;
+        let quiet = self.last_send.elapsed();
+        if quiet < config.max_batch_delay {
+            return;
+        }
+        if !self.builder.is_empty() {
+            self.flush(out);
+            return;
+        }
+        if !config.enable_heartbeats || quiet < interval {
+            return;
+        }
+
+        // The empty batch goes out as the heartbeat
+        let next = Self::builder(&self.state, self.state.next_packet_seq(), self.msg_seq);
+        let mut heartbeat = std::mem::replace(&mut self.builder, next).finish();
+        self.last_send = Instant::now();
+        self.state.seal(&mut heartbeat);
+        out.push_back(OutgoingPacket {
+            data: Bytes::from(heartbeat),
+            dest: self.state.config.target_addr,
+            kind: PacketKind::Heartbeat,
+        });
+    }
+}
+
+/// What the worker thread is asked to do, in order
+enum Command {
+    Send(OutgoingMessage),
+    ResetSequence(u64),
+}
+
+/// Commands from a `UdpSender` to its worker, bounded by
+/// `channel_capacity`
+struct SendQueue {
+    queue: Mutex<QueueState>,
+    /// Signalled when a command is queued
+    queued: Condvar,
+    /// Signalled when a command is taken or the worker is gone
+    room: Condvar,
+    capacity: usize,
+    /// Index the queued book messages, for `OverflowPolicy::Coalesce`
+    coalesce: bool,
     stats: Arc<SenderStats>,
+}
+
+struct QueueState {
+    commands: VecDeque<Queued>,
+    /// IDs of the indexed book messages by type and symbol, oldest first
+    books: HashMap<BookKey, VecDeque<u64>>,
+    next_id: u64,
+    closed: bool,
+}
+
+/// Type and symbol of a book message, a newer one supersedes it
+type BookKey = (MessageType, String);
+
+struct Queued {
+    /// Grows from the front of the queue to the back
+    id: u64,
+    /// Set when the message is in `QueueState::books`
+    book: Option<BookKey>,
+    command: Command,
+}
+
+impl QueueState {
+    fn push_back(&mut self, command: Command, book: Option<BookKey>) {
+        let id = self.next_id;
+        self.next_id += 1;
+        if let Some(key) = &book {
+            self.books.entry(key.clone()).or_default().push_back(id);
+        }
+        self.commands.push_back(Queued { id, book, command });
+    }
+
+    fn pop_front(&mut self) -> Option<Command> {
+        let queued = self.commands.pop_front()?;
+        Some(self.unindex(queued))
+    }
+
+    fn remove(&mut self, index: usize) -> Option<Command> {
+        let queued = self.commands.remove(index)?;
+        Some(self.unindex(queued))
+    }
+
+    /// Only the oldest queued message of a key is ever taken out, the rest
+    /// are behind it
+    fn unindex(&mut self, queued: Queued) -> Command {
+        if let Some(key) = queued.book {
+            if let Some(ids) = self.books.get_mut(&key) {
+                ids.pop_front();
+                if ids.is_empty() {
+                    self.books.remove(&key);
+                }
+            }
+        }
+        queued.command
+    }
+}
+
+impl SendQueue {
+    fn new(capacity: usize, coalesce: bool, stats: Arc<SenderStats>) -> Self {
+        Self {
+            queue: Mutex::new(QueueState {
+                commands: VecDeque::with_capacity(capacity.min(1024)),
+                books: HashMap::new(),
+                next_id: 0,
+                closed: false,
+            }),
+            queued: Condvar::new(),
+            room: Condvar::new(),
+            capacity,
+            coalesce,
+            stats,
+        }
+    }
+
+    /// Index key of `msg`, parsed before the lock is taken
+    fn book_key(&self, msg: &OutgoingMessage) -> Option<BookKey> {
+        if !self.coalesce || !msg.msg_type.is_book() {
+            return None;
+        }
+        let symbol = binary::book_symbol(&msg.payload)?;
+        Some((msg.msg_type, symbol.to_string()))
+    }
+
+    /// Queue `command`, waiting as long as it takes for room
+    fn push(&self, command: Command) -> Result<()> {
+        let book = match &command {
+            Command::Send(msg) => self.book_key(msg),
+            Command::ResetSequence(_) => None,
+        };
+        let mut queue = self.queue.lock();
+        while queue.commands.len() >= self.capacity && !queue.closed {
+            self.room.wait(&mut queue);
+        }
+        if queue.closed {
+            return Err(ProtocolError::ChannelClosed);
+        }
+        queue.push_back(command, book);
+        self.queued.notify_one();
+        Ok(())
+    }
+
+    /// Queue `msg`, waiting up to `timeout` for room, then refusing it
+    fn push_timeout(&self, msg: OutgoingMessage, timeout: Duration) -> Result<()> {
+        let deadline = Instant::now() + timeout;
+        let book = self.book_key(&msg);
+        let mut queue = self.queue.lock();
+        if queue.commands.len() >= self.capacity && !queue.closed {
+            self.stats.channel_full.fetch_add(1, Ordering::Relaxed);
+            while queue.commands.len() >= self.capacity && !queue.closed {
+                if self.room.wait_until(&mut queue, deadline).timed_out() {
+                    break;
+                }
+            }
+        }
+        if queue.closed {
+            return Err(ProtocolError::ChannelClosed);
+        }
+        if queue.commands.len() >= self.capacity {
+            self.stats.messages_rejected.fetch_add(1, Ordering::Relaxed);
+            return Err(ProtocolError::ChannelFull);
+        }
+        queue.push_back(Command::Send(msg), book);
+        self.queued.notify_one();
+        Ok(())
+    }
+
+    /// Queue `msg` without waiting, applying `policy` when full
+    fn offer(&self, msg: OutgoingMessage, policy: OverflowPolicy) -> Result<()> {
+        let book = self.book_key(&msg);
+        let mut queue = self.queue.lock();
+        if queue.closed {
+            return Err(ProtocolError::ChannelClosed);
+        }
+        if queue.commands.len() >= self.capacity {
+            self.stats.channel_full.fetch_add(1, Ordering::Relaxed);
+            let room = match policy {
+                OverflowPolicy::Reject | OverflowPolicy::DropNewest => false,
+                OverflowPolicy::DropOldest => self.drop_oldest(&mut queue),
+                OverflowPolicy::Coalesce => {
+                    book.as_ref().is_some_and(|key| self.coalesce(&mut queue, key)) || self.drop_oldest(&mut queue)
+                }
+            };
+            if !room {
+                return self.refuse(&msg, policy);
+            }
+        }
+        queue.push_back(Command::Send(msg), book);
+        self.queued.notify_one();
+        Ok(())
+    }
+
+    /// Drop `msg` when the policy drops messages and it is a book message
+    fn refuse(&self, msg: &OutgoingMessage, policy: OverflowPolicy) -> Result<()> {
+        let drops = matches!(
+            policy,
+            OverflowPolicy::DropNewest | OverflowPolicy::DropOldest | OverflowPolicy::Coalesce
+        );
+        if drops && msg.msg_type.is_book() {
+            self.stats.messages_dropped.fetch_add(1, Ordering::Relaxed);
+            return Ok(());
+        }
+        self.stats.messages_rejected.fetch_add(1, Ordering::Relaxed);
+        Err(ProtocolError::ChannelFull)
+    }
+
+    fn drop_oldest(&self, queue: &mut QueueState) -> bool {
+        let Some(index) = queue
+            .commands
+            .iter()
+            .position(|queued| matches!(&queued.command, Command::Send(msg) if msg.msg_type.is_book()))
+        else {
+            return false;
+        };
+        queue.remove(index);
+        self.stats.messages_dropped.fetch_add(1, Ordering::Relaxed);
+        true
+    }
+
+    /// Take out the queued message of `key`, the new one goes to the back
+    fn coalesce(&self, queue: &mut QueueState, key: &BookKey) -> bool {
+        let Some(&id) = queue.books.get(key).and_then(|ids| ids.front()) else {
+            return false;
+        };
+        let Ok(index) = queue.commands.binary_search_by_key(&id, |queued| queued.id) else {
+            return false;
+        };
+        queue.remove(index);
+        self.stats.messages_coalesced.fetch_add(1, Ordering::Relaxed);
+        true
+    }
+
+    /// Take the next command, None when none came within `timeout`
+    fn pop(&self, timeout: Duration) -> Option<Command> {
+        let deadline = Instant::now() + timeout;
+        let mut queue = self.queue.lock();
+        loop {
+            if let Some(command) = queue.pop_front() {
+                self.room.notify_one();
+                return Some(command);
+            }
+            if self.queued.wait_until(&mut queue, deadline).timed_out() {
+                return None;
+            }
+        }
+    }
+
+    /// What is left when the worker stops, later commands are refused
+    fn close(&self) -> Vec<Command> {
+        let mut queue = self.queue.lock();
+        queue.closed = true;
+        queue.books.clear();
+        self.room.notify_all();
+        std::mem::take(&mut queue.commands)
+            .into_iter()
+            .map(|queued| queued.command)
+            .collect()
+    }
+}
+
+/// UDP sender with batching and automatic heartbeats. Publishes to every
+/// receiver of a group when `target_addr` is an IPv4 multicast address.
+/// Opens its session when it starts and logs out on shutdown.
+pub struct UdpSender {
+    state: SenderState,
+    queue: Arc<SendQueue>,
+    /// For key rotation announcements
+    socket: UdpSocket,
     running: Arc<AtomicBool>,
     worker_handle: Option<JoinHandle<()>>,
+    control_handle: Option<JoinHandle<()>>,
 }
 
 impl UdpSender {
     /// Create a new sender bound to the specified local address
     pub fn new(config: SenderConfig, bind_addr: SocketAddr) -> Result<Self> {
-        let socket = UdpSocket::bind(bind_addr)?;
+        let state = SenderState::new(config)?;
+        let socket = state.bind(bind_addr)?;
         socket.set_nonblocking(false)?;
 
-        let (tx, rx) = bounded(config.channel_capacity);
-        let stats = Arc::new(SenderStats::default());
+        let queue = Arc::new(SendQueue::new(
+            state.config.channel_capacity,
+            state.config.overflow == OverflowPolicy::Coalesce,
+            state.stats.clone(),
+        ));
         let running = Arc::new(AtomicBool::new(true));
+        let stream_id = state.config.stream_id;
+
+        // NAKs and session replies arrive on the socket the stream is sent from
+        let control_socket = socket.try_clone()?;
+        control_socket.set_read_timeout(Some(Duration::from_millis(10)))?;
+        let responder = ControlResponder {
+            socket: control_socket,
+            state: state.clone(),
+            running: running.clone(),
+        };
+        let control_handle = thread::Builder::new()
+            .name(format!("udp-control-{}", stream_id))
+            .spawn(move || responder.run())
+            .map_err(|e| ProtocolError::Io(io::Error::other(e)))?;
 
         let worker = SenderWorker {
-            config: config.clone(),
-            socket,
-            rx,
-            stats: stats.clone(),
+            socket: socket.try_clone()?,
+            queue: queue.clone(),
             running: running.clone(),
-            packet_seq: 0,
-            msg_seq: 0,
+            batcher: Batcher::new(state.clone()),
+            state: state.clone(),
+            out: VecDeque::new(),
         };
 
         let handle = thread::Builder::new()
-            .name(format!("udp-sender-{}", config.stream_id))
+            .name(format!("udp-sender-{}", stream_id))
             .spawn(move || worker.run())
-            .map_err(|e| ProtocolError::Io(std::io::Error::new(std::io::ErrorKind::Other, e)))?;
+            .map_err(|e| ProtocolError::Io(io::Error::other(e)))?;
 
         Ok(Self {
-            config,
-            tx,
-            stats,
+            state,
+            queue,
+            socket,
             running,
             worker_handle: Some(handle),
+            control_handle: Some(control_handle),
         })
     }
 
-    /// Send a message (non-blocking, may fail if channel is full)
+    /// Send a message (non-blocking, when the channel is full the
+    /// `overflow` policy decides)
     pub fn try_send(&self, msg_type: MessageType, payload: Vec<u8>) -> Result<()> {
-        if payload.len() > MAX_PAYLOAD - MESSAGE_HEADER_LEN {
-            return Err(ProtocolError::MessageTooLarge {
-                size: payload.len(),
-                max: MAX_PAYLOAD - MESSAGE_HEADER_LEN,
-            });
-        }
+        self.state.check_size(&payload)?;
 
-        self.tx
-            .try_send(OutgoingMessage { msg_type, payload })
-            .map_err(|e| match e {
-                TrySendError::Full(_) => ProtocolError::ChannelClosed,
-                TrySendError::Disconnected(_) => ProtocolError::ChannelClosed,
-            })
+        self.queue
+            .offer(OutgoingMessage { msg_type, payload }, self.state.config.overflow)
+    }
+
+    /// Send a message, waiting up to `timeout` for room when the channel
+    /// is full. Refused with `ChannelFull` after that, the `overflow`
+    /// policy doesn't apply.
+    pub fn send_timeout(&self, msg_type: MessageType, payload: Vec<u8>, timeout: Duration) -> Result<()> {
+        self.state.check_size(&payload)?;
+
+        self.queue.push_timeout(OutgoingMessage { msg_type, payload }, timeout)
     }
 
     /// Send a message (blocking)
     pub fn send(&self, msg_type: MessageType, payload: Vec<u8>) -> Result<()> {
-        if payload.len() > MAX_PAYLOAD - MESSAGE_HEADER_LEN {
-            return Err(ProtocolError::MessageTooLarge {
-                size: payload.len(),
-                max: MAX_PAYLOAD - MESSAGE_HEADER_LEN,
-            });
-        }
+        self.state.check_size(&payload)?;
 
-        self.tx
-            .send(OutgoingMessage { msg_type, payload })
-            .map_err(|_| ProtocolError::ChannelClosed)
+        self.queue.push(Command::Send(OutgoingMessage { msg_type, payload }))
+    }
+
+    /// Number the messages sent after this call from `next_seq`. Receivers
+    /// are told, they don't see a gap.
+    pub fn reset_sequence(&self, next_seq: u64) -> Result<()> {
+        self.queue.push(Command::ResetSequence(next_seq))
     }
 
     /// Get current statistics
     pub fn stats(&self) -> SenderStatsSnapshot {
-        self.stats.snapshot()
+        self.state.stats.snapshot()
+    }
+
+    /// Sign packets with `key` from now on and tell receivers to retire the
+    /// older keys. Receivers need the key in their ring before this.
+    pub fn rotate_key(&self, key: StreamKey) -> Result<()> {
+        let announcement = self.state.rotate_key(key)?;
+        let result = self.socket.send_to(&announcement.data, announcement.dest);
+        self.state.stats.record(&announcement, &result);
+        result?;
+        Ok(())
+    }
+
+    /// ID of this sender's session, new every time it starts
+    pub fn session_id(&self) -> u64 {
+        self.state.session_id()
     }
 
     /// Check if the sender is running
@@ -165,6 +998,9 @@ impl UdpSender {
         if let Some(handle) = self.worker_handle.take() {
             let _ = handle.join();
         }
+        if let Some(handle) = self.control_handle.take() {
+            let _ = handle.join();
+        }
     }
 }
 
@@ -175,123 +1011,242 @@ impl Drop for UdpSender {
 }
 
 struct SenderWorker {
-    config: SenderConfig,
     socket: UdpSocket,
-    rx: Receiver<OutgoingMessage>,
-    stats: Arc<SenderStats>,
+    queue: Arc<SendQueue>,
     running: Arc<AtomicBool>,
-    packet_seq: u64,
-    msg_seq: u64,
+    batcher: Batcher,
+    state: SenderState,
+    out: VecDeque<OutgoingPacket>,
 }
 
 impl SenderWorker {
     fn run(mut self) {
-        let mut last_send = Instant::now();
-        let mut builder = PacketBuilder::new(self.config.stream_id, self.packet_seq, self.msg_seq);
+        let max_batch_delay = self.state.config.max_batch_delay;
+        self.batcher.hello(&mut self.out);
+        self.send_queued();
 
         while self.running.load(Ordering::Relaxed) {
             // Try to receive with timeout
-            match self.rx.recv_timeout(self.config.max_batch_delay) {
-                Ok(msg) => {
-                    // Try to add to current batch
-                    if !builder.try_add_message(msg.msg_type, &msg.payload) {
-                        // Batch full, send it
-                        let msg_count = builder.msg_count();
-                        self.send_packet(&builder.finish());
-                        self.packet_seq += 1;
-                        self.msg_seq += msg_count as u64;
-
-                        // Start new batch
-                        builder = PacketBuilder::new(
-                            self.config.stream_id,
-                            self.packet_seq,
-                            self.msg_seq,
-                        );
-                        builder.try_add_message(msg.msg_type, &msg.payload);
-                    }
-                    last_send = Instant::now();
-                }
-                Err(crossbeam_channel::RecvTimeoutError::Timeout) => {
-                    // Timeout - send partial batch or heartbeat
-                    if !builder.is_empty() {
-                        let msg_count = builder.msg_count();
-                        self.send_packet(&builder.finish());
-                        self.packet_seq += 1;
-                        self.msg_seq += msg_count as u64;
-                        builder = PacketBuilder::new(
-                            self.config.stream_id,
-                            self.packet_seq,
-                            self.msg_seq,
-                        );
-                        last_send = Instant::now();
-                    } else if self.config.enable_heartbeats
-                        && last_send.elapsed() >= Duration::from_millis(HEARTBEAT_INTERVAL_MS)
-                    {
-                        // Send heartbeat
-                        let heartbeat =
-                            PacketBuilder::heartbeat(self.config.stream_id, self.packet_seq, self.msg_seq);
-                        self.send_heartbeat(&heartbeat);
-                        self.packet_seq += 1;
-                        last_send = Instant::now();
-                    }
-                }
-                Err(crossbeam_channel::RecvTimeoutError::Disconnected) => {
-                    break;
-                }
+            match self.queue.pop(max_batch_delay) {
+                Some(command) => self.execute(command),
+                // Timeout - send partial batch or heartbeat
+                None => self.batcher.idle(&mut self.out),
             }
+            self.send_queued();
         }
 
         // Drain any remaining messages from the channel
-        while let Ok(msg) = self.rx.try_recv() {
-            if !builder.try_add_message(msg.msg_type, &msg.payload) {
-                // Batch full, send it
-                let msg_count = builder.msg_count();
-                self.send_packet(&builder.finish());
-                self.packet_seq += 1;
-                self.msg_seq += msg_count as u64;
-                builder = PacketBuilder::new(
-                    self.config.stream_id,
-                    self.packet_seq,
-                    self.msg_seq,
-                );
-                builder.try_add_message(msg.msg_type, &msg.payload);
-            }
+        for command in self.queue.close() {
+            self.execute(command);
         }
 
-        // Flush remaining messages in the builder
-        if !builder.is_empty() {
-            self.send_packet(&builder.finish());
+        // Flush remaining messages and end the session
+        self.batcher.logout(&mut self.out);
+        self.send_queued();
+    }
+
+    fn execute(&mut self, command: Command) {
+        match command {
+            Command::Send(msg) => self.batcher.push(&msg, &mut self.out),
+            Command::ResetSequence(next_seq) => self.batcher.reset_sequence(next_seq, &mut self.out),
         }
     }
 
-    fn send_packet(&self, data: &[u8]) {
-        match self.socket.send_to(data, self.config.target_addr) {
-            Ok(n) => {
-                self.stats.packets_sent.fetch_add(1, Ordering::Relaxed);
-                self.stats.bytes_sent.fetch_add(n as u64, Ordering::Relaxed);
-                // Parse to count messages
-                if let Ok(packet) = Packet::parse(data) {
-                    self.stats
-                        .messages_sent
-                        .fetch_add(packet.header.msg_count as u64, Ordering::Relaxed);
-                }
-            }
-            Err(_) => {
-                self.stats.send_errors.fetch_add(1, Ordering::Relaxed);
+    fn send_queued(&mut self) {
+        batch_io::send_packets(&self.socket, &mut self.out, &self.state.stats);
+    }
+}
+
+/// Answers NAKs and session messages from receivers
+struct ControlResponder {
+    socket: UdpSocket,
+    state: SenderState,
+    running: Arc<AtomicBool>,
+}
+
+impl ControlResponder {
+    fn run(self) {
+        let mut buf = vec![0u8; MAX_MTU];
+        let mut replies = VecDeque::new();
+
+        while self.running.load(Ordering::Relaxed) {
+            if let Ok((len, addr)) = self.socket.recv_from(&mut buf) {
+                self.state.handle_control(&mut buf[..len], addr, &mut replies);
+                batch_io::send_packets(&self.socket, &mut replies, &self.state.stats);
             }
         }
     }
+}
 
-    fn send_heartbeat(&self, data: &[u8]) {
-        match self.socket.send_to(data, self.config.target_addr) {
-            Ok(n) => {
-                self.stats.packets_sent.fetch_add(1, Ordering::Relaxed);
-                self.stats.heartbeats_sent.fetch_add(1, Ordering::Relaxed);
-                self.stats.bytes_sent.fetch_add(n as u64, Ordering::Relaxed);
-            }
-            Err(_) => {
-                self.stats.send_errors.fetch_add(1, Ordering::Relaxed);
-            }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binary::{MarketEvent, MarketEventEncoder};
+
+    fn queue(capacity: usize) -> SendQueue {
+        SendQueue::new(capacity, true, Arc::new(SenderStats::default()))
+    }
+
+    fn order(i: u8) -> OutgoingMessage {
+        OutgoingMessage {
+            msg_type: MessageType::OrderNew,
+            payload: vec![i],
         }
     }
+
+    fn book(msg_type: MessageType, symbol: &str, sequence: u64) -> OutgoingMessage {
+        let event = MarketEvent::OrderBookSnapshot {
+            symbol: symbol.to_string(),
+            sequence,
+            bids: vec![],
+            asks: vec![],
+        };
+        OutgoingMessage {
+            msg_type,
+            payload: MarketEventEncoder::new().encode(&event).to_vec(),
+        }
+    }
+
+    fn snapshot(symbol: &str, sequence: u64) -> OutgoingMessage {
+        book(MessageType::BookSnapshot, symbol, sequence)
+    }
+
+    /// Type and payload of the queued messages, oldest first
+    fn queued(queue: &SendQueue) -> Vec<(MessageType, Vec<u8>)> {
+        std::iter::from_fn(|| queue.pop(Duration::ZERO))
+            .map(|command| match command {
+                Command::Send(msg) => (msg.msg_type, msg.payload),
+                Command::ResetSequence(_) => panic!("not a message"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_reject_and_drop_newest() {
+        let queue = queue(1);
+        queue.offer(order(0), OverflowPolicy::Reject).unwrap();
+        assert!(matches!(
+            queue.offer(snapshot("BTC", 1), OverflowPolicy::Reject),
+            Err(ProtocolError::ChannelFull)
+        ));
+
+        // Book messages go, orders are refused all the same
+        queue.offer(snapshot("BTC", 1), OverflowPolicy::DropNewest).unwrap();
+        assert!(matches!(
+            queue.offer(order(1), OverflowPolicy::DropNewest),
+            Err(ProtocolError::ChannelFull)
+        ));
+
+        let stats = queue.stats.snapshot();
+        assert_eq!(stats.channel_full, 3);
+        assert_eq!(stats.messages_rejected, 2);
+        assert_eq!(stats.messages_dropped, 1);
+        assert_eq!(queued(&queue), vec![(MessageType::OrderNew, vec![0])]);
+    }
+
+    #[test]
+    fn test_drop_oldest_spares_orders() {
+        let queue = queue(3);
+        let btc = snapshot("BTC", 1);
+        queue.offer(order(0), OverflowPolicy::DropOldest).unwrap();
+        queue.offer(btc, OverflowPolicy::DropOldest).unwrap();
+        queue.offer(order(1), OverflowPolicy::DropOldest).unwrap();
+
+        // The book message makes room for the order
+        queue.offer(order(2), OverflowPolicy::DropOldest).unwrap();
+        // Nothing left to drop but the new book message
+        queue.offer(snapshot("ETH", 1), OverflowPolicy::DropOldest).unwrap();
+        assert!(matches!(
+            queue.offer(order(3), OverflowPolicy::DropOldest),
+            Err(ProtocolError::ChannelFull)
+        ));
+
+        let stats = queue.stats.snapshot();
+        assert_eq!(stats.messages_dropped, 2);
+        assert_eq!(stats.messages_rejected, 1);
+        let payloads: Vec<_> = queued(&queue).into_iter().map(|(_, payload)| payload).collect();
+        assert_eq!(payloads, vec![vec![0], vec![1], vec![2]]);
+    }
+
+    #[test]
+    fn test_coalesce_by_symbol() {
+        let queue = queue(3);
+        queue.offer(snapshot("BTC", 1), OverflowPolicy::Coalesce).unwrap();
+        queue.offer(snapshot("ETH", 1), OverflowPolicy::Coalesce).unwrap();
+        queue.offer(order(0), OverflowPolicy::Coalesce).unwrap();
+
+        // Replaces the BTC snapshot and goes to the back
+        let latest = snapshot("BTC", 2);
+        let latest_payload = latest.payload.clone();
+        queue.offer(latest, OverflowPolicy::Coalesce).unwrap();
+        // No ETH update queued, the oldest book message goes
+        let update = book(MessageType::BookUpdate, "ETH", 2);
+        let update_payload = update.payload.clone();
+        queue.offer(update, OverflowPolicy::Coalesce).unwrap();
+
+        let stats = queue.stats.snapshot();
+        assert_eq!(stats.messages_coalesced, 1);
+        assert_eq!(stats.messages_dropped, 1);
+        assert_eq!(
+            queued(&queue),
+            vec![
+                (MessageType::OrderNew, vec![0]),
+                (MessageType::BookSnapshot, latest_payload),
+                (MessageType::BookUpdate, update_payload),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_coalesce_oldest_of_symbol() {
+        let queue = queue(3);
+        queue.offer(snapshot("BTC", 1), OverflowPolicy::Coalesce).unwrap();
+        queue.offer(snapshot("BTC", 2), OverflowPolicy::Coalesce).unwrap();
+        queue.offer(snapshot("ETH", 1), OverflowPolicy::Coalesce).unwrap();
+
+        // Each BTC snapshot replaces the oldest one still queued
+        queue.offer(snapshot("BTC", 3), OverflowPolicy::Coalesce).unwrap();
+        let second_payload = match queue.pop(Duration::ZERO) {
+            Some(Command::Send(msg)) => msg.payload,
+            _ => panic!("not a message"),
+        };
+        assert_eq!(second_payload, snapshot("BTC", 2).payload);
+        let fourth = snapshot("BTC", 4);
+        let fourth_payload = fourth.payload.clone();
+        queue.offer(fourth, OverflowPolicy::Coalesce).unwrap();
+        let fifth = snapshot("BTC", 5);
+        let fifth_payload = fifth.payload.clone();
+        queue.offer(fifth, OverflowPolicy::Coalesce).unwrap();
+
+        assert_eq!(queue.stats.snapshot().messages_coalesced, 2);
+        let payloads: Vec<_> = queued(&queue).into_iter().map(|(_, payload)| payload).collect();
+        assert_eq!(payloads, vec![snapshot("ETH", 1).payload, fourth_payload, fifth_payload]);
+        assert!(queue.queue.lock().books.is_empty());
+    }
+
+    #[test]
+    fn test_wait_for_room() {
+        let queue = Arc::new(queue(1));
+        queue.offer(order(0), OverflowPolicy::Reject).unwrap();
+
+        assert!(matches!(
+            queue.push_timeout(order(1), Duration::from_millis(10)),
+            Err(ProtocolError::ChannelFull)
+        ));
+
+        let worker = thread::spawn({
+            let queue = queue.clone();
+            move || {
+                thread::sleep(Duration::from_millis(20));
+                queue.pop(Duration::ZERO)
+            }
+        });
+        queue.push_timeout(order(2), Duration::from_secs(5)).unwrap();
+        assert!(worker.join().unwrap().is_some());
+        assert_eq!(queued(&queue), vec![(MessageType::OrderNew, vec![2])]);
+
+        // Nobody takes commands once the worker stopped
+        queue.close();
+        assert!(matches!(queue.push(Command::ResetSequence(0)), Err(ProtocolError::ChannelClosed)));
+    }
 }
@@ -0,0 +1,57 @@
+//! Sessions between senders and receivers.
+//!
+//! Every sender and receiver picks a random session ID when it starts. The
+//! sender opens its session with a `Hello`, repeated until a receiver
+//! answers with an `Accept`. A receiver that joins a running stream accepts
+//! unprompted, and the sender answers with its `Hello`. A known peer coming
+//! back with a new session ID has restarted: a restarted sender numbers its
+//! messages from 0 again, so receivers start the stream over. `Logout` ends
+//! a session on shutdown and `SequenceReset` renumbers a running stream.
+
+use std::fmt;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+/// Session change seen by a sender or a receiver
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionEvent {
+    /// First contact with a peer
+    Established { peer: SocketAddr, session_id: u64 },
+    /// The peer came back with a new session, anything in flight from the
+    /// old one is lost
+    Restarted {
+        peer: SocketAddr,
+        old_session_id: u64,
+        session_id: u64,
+    },
+    /// The sender numbers its messages from `next_seq` on
+    SequenceReset { peer: SocketAddr, next_seq: u64 },
+    /// The peer shut down
+    LoggedOut { peer: SocketAddr, session_id: u64 },
+}
+
+/// Callback for session events, run on the thread or task doing the I/O so
+/// it should not block
+#[derive(Clone)]
+pub struct SessionHandler(Arc<dyn Fn(SessionEvent) + Send + Sync>);
+
+impl SessionHandler {
+    pub fn new(handler: impl Fn(SessionEvent) + Send + Sync + 'static) -> Self {
+        Self(Arc::new(handler))
+    }
+
+    pub(crate) fn call(&self, event: SessionEvent) {
+        (self.0)(event)
+    }
+}
+
+impl fmt::Debug for SessionHandler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SessionHandler")
+    }
+}
+
+/// A fresh random session ID
+pub(crate) fn new_session_id() -> u64 {
+    uuid::Uuid::new_v4().as_u64_pair().0
+}
@@ -1,7 +1,10 @@
-use std::net::{SocketAddr, UdpSocket};
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4, UdpSocket};
+use std::sync::Arc;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
+use crate::fragment::Reassembler;
+use crate::impairment::{Direction, ImpairedLink, Impairment, LinkConfig};
 use crate::*;
 
 // ============================================================================
@@ -32,6 +35,38 @@ fn test_packet_header_heartbeat() {
     assert!(parsed.is_heartbeat());
 }
 
+#[test]
+fn test_packet_header_send_time_roundtrip() {
+    let header = PacketHeader::new(42, 100, 1000, 5).with_send_time(123_456_789);
+    assert_eq!(header.flags(), PacketFlags::SEND_TIME);
+    let mut buf = [0u8; MAX_PACKET_HEADER_LEN];
+
+    let written = header.write_to(&mut buf).unwrap();
+    assert_eq!(written, MAX_PACKET_HEADER_LEN);
+
+    let parsed = PacketHeader::read_from(&buf).unwrap();
+    assert_eq!(parsed.send_time_ns, Some(123_456_789));
+    assert_eq!(header, parsed);
+
+    // The flag promises the send time
+    assert!(matches!(
+        PacketHeader::read_from(&buf[..PACKET_HEADER_LEN]),
+        Err(ProtocolError::BufferTooSmall { .. })
+    ));
+}
+
+#[test]
+fn test_unknown_packet_flags_rejected() {
+    let mut buf = [0u8; MAX_PACKET_HEADER_LEN];
+    PacketHeader::new(42, 100, 1000, 5).write_to(&mut buf).unwrap();
+    buf[1] = 0x80;
+
+    assert!(matches!(
+        PacketHeader::read_from(&buf),
+        Err(ProtocolError::UnknownPacketFlags(0x80))
+    ));
+}
+
 #[test]
 fn test_message_header_roundtrip() {
     let header = MessageHeader::new(MessageType::OrderNew, MessageFlags::NONE, 256);
@@ -51,12 +86,14 @@ fn test_message_types() {
         (0x01, MessageType::OrderNew),
         (0x02, MessageType::OrderCancel),
         (0x03, MessageType::OrderReplace),
+        (0x04, MessageType::SnapshotRequest),
         (0x10, MessageType::MatchEvent),
         (0x11, MessageType::BookSnapshot),
         (0x12, MessageType::BookUpdate),
         (0x20, MessageType::PositionUpdate),
         (0x30, MessageType::Heartbeat),
         (0x40, MessageType::Control),
+        (0x50, MessageType::Journal),
     ];
 
     for (byte, expected) in types {
@@ -119,6 +156,23 @@ fn test_packet_builder_respects_mtu() {
     assert!(!builder.try_add_message(MessageType::OrderNew, &large_payload));
 }
 
+#[test]
+fn test_packet_builder_send_time() {
+    let plain = PacketBuilder::new(1, 0, 0);
+    let mut builder = PacketBuilder::new(1, 0, 0).with_send_time();
+    // Timestamps don't change how much fits
+    assert_eq!(builder.remaining_capacity(), plain.remaining_capacity());
+
+    builder.try_add_message(MessageType::BookUpdate, b"update");
+    let data = builder.finish();
+    assert_eq!(data.len(), MAX_PACKET_HEADER_LEN + MESSAGE_HEADER_LEN + 6);
+
+    let packet = Packet::parse(&data).unwrap();
+    assert!(packet.header.send_time_ns.is_some());
+    assert_eq!(packet.messages.len(), 1);
+    assert_eq!(packet.messages[0].payload, b"update");
+}
+
 #[test]
 fn test_packet_builder_heartbeat() {
     let heartbeat = PacketBuilder::heartbeat(42, 10, 500);
@@ -135,15 +189,31 @@ fn test_packet_builder_heartbeat() {
 fn test_invalid_version_rejected() {
     let mut buf = [0u8; PACKET_HEADER_LEN];
     buf[0] = 99; // Invalid version
-    buf[1] = PACKET_HEADER_LEN as u8;
 
     let result = PacketHeader::read_from(&buf);
     assert!(matches!(
         result,
-        Err(ProtocolError::InvalidVersion { expected: 1, got: 99 })
+        Err(ProtocolError::InvalidVersion { expected: PROTOCOL_VERSION, got: 99 })
     ));
 }
 
+#[test]
+fn test_previous_version_rejected() {
+    // Version 1 peers send market data prices as f64, version 2 peers have
+    // the header length where the packet flags are
+    let mut builder = PacketBuilder::new(1, 0, 0);
+    builder.try_add_message(MessageType::MatchEvent, b"fill");
+    let mut packet = builder.finish();
+
+    for version in [1, 2] {
+        packet[0] = version;
+        assert!(matches!(
+            Packet::parse(&packet),
+            Err(ProtocolError::InvalidVersion { expected: 3, got }) if got == version
+        ));
+    }
+}
+
 #[test]
 fn test_buffer_too_small() {
     let small_buf = [0u8; 10];
@@ -151,6 +221,86 @@ fn test_buffer_too_small() {
     assert!(matches!(result, Err(ProtocolError::BufferTooSmall { .. })));
 }
 
+#[test]
+fn test_control_packet_roundtrip() {
+    let naks = [
+        ControlMessage::Nak { from_seq: 3, to_seq: 5 },
+        ControlMessage::Unavailable { from_seq: 9, to_seq: 12 },
+        ControlMessage::Hello { session_id: u64::MAX, version: PROTOCOL_VERSION },
+        ControlMessage::Accept { session_id: 42, version: PROTOCOL_VERSION },
+        ControlMessage::SequenceReset { next_seq: 1000 },
+        ControlMessage::Logout { session_id: 42 },
+    ];
+    let packet = Packet::parse(&PacketBuilder::control(7, 0, &naks)).unwrap();
+
+    assert!(packet.is_control());
+    assert_eq!(packet.header.stream_id, 7);
+    assert_eq!(packet.control_messages().collect::<Vec<_>>(), naks);
+
+    let mut builder = PacketBuilder::new(7, 0, 0);
+    builder.try_add_message(MessageType::OrderNew, b"data");
+    assert!(!Packet::parse(&builder.finish()).unwrap().is_control());
+
+    let mut buf = [0u8; CONTROL_MESSAGE_LEN];
+    buf[0] = 0x7f;
+    assert!(matches!(
+        ControlMessage::read_from(&buf),
+        Err(ProtocolError::InvalidControlMessage(0x7f))
+    ));
+}
+
+#[test]
+fn test_fragment_header_roundtrip() {
+    let header = FragmentHeader {
+        index: 2,
+        count: 5,
+        total_len: 6000,
+    };
+    let mut buf = [0u8; FRAGMENT_HEADER_LEN];
+
+    assert_eq!(header.write_to(&mut buf).unwrap(), FRAGMENT_HEADER_LEN);
+    assert_eq!(FragmentHeader::read_from(&buf).unwrap(), header);
+
+    buf[0..2].copy_from_slice(&5u16.to_le_bytes());
+    assert!(matches!(
+        FragmentHeader::read_from(&buf),
+        Err(ProtocolError::InvalidFragment { index: 5, count: 5 })
+    ));
+
+    // The count has to match the length
+    let forged = FragmentHeader {
+        count: u16::MAX,
+        ..header
+    };
+    forged.write_to(&mut buf).unwrap();
+    assert!(matches!(
+        FragmentHeader::read_from(&buf),
+        Err(ProtocolError::InvalidFragment { index: 2, count: u16::MAX })
+    ));
+}
+
+#[test]
+fn test_packet_builder_fragments() {
+    let payload: Vec<u8> = (0..MAX_FRAGMENT_LEN * 2 + 10).map(|i| i as u8).collect();
+    let fragments: Vec<_> = FragmentHeader::split(&payload).collect();
+
+    assert_eq!(fragments.len(), 3);
+    assert_eq!(fragments[2].1.len(), 10);
+    assert!(fragments.iter().all(|(h, _)| h.count == 3 && h.total_len == payload.len() as u32));
+
+    // A full fragment takes a packet of its own
+    let mut builder = PacketBuilder::new(1, 0, 0);
+    let (header, chunk) = fragments[0];
+    assert!(builder.try_add_fragment(MessageType::BookSnapshot, header, chunk));
+    assert!(!builder.try_add_fragment(MessageType::BookSnapshot, fragments[1].0, fragments[1].1));
+
+    let packet = Packet::parse(&builder.finish()).unwrap();
+    let msg = &packet.messages[0];
+    assert!(msg.is_fragment());
+    assert_eq!(FragmentHeader::read_from(&msg.payload).unwrap(), header);
+    assert_eq!(&msg.payload[FRAGMENT_HEADER_LEN..], chunk);
+}
+
 // ============================================================================
 // Integration Tests - Sender/Receiver
 // ============================================================================
@@ -278,7 +428,7 @@ fn test_batching() {
     let mut sender = UdpSender::new(send_config, send_addr).unwrap();
 
     // Send 100 small messages (each ~10 bytes payload + 4 byte header = ~14 bytes)
-    // MAX_PAYLOAD is 1376 bytes, so we should fit many messages per packet
+    // MAX_PAYLOAD is 1348 bytes, so we should fit many messages per packet
     for i in 0..100 {
         let payload = format!("msg{:04}", i); // 7 bytes each
         sender.send(MessageType::OrderNew, payload.into_bytes()).unwrap();
@@ -294,7 +444,7 @@ fn test_batching() {
     let send_stats = sender.stats();
 
     // 100 messages of ~11 bytes each (7 payload + 4 header) = ~1100 bytes total
-    // This should fit in 1-2 packets (MAX_PAYLOAD is 1376 bytes)
+    // This should fit in 1-2 packets (MAX_PAYLOAD is 1348 bytes)
     assert!(send_stats.packets_sent <= 2, "Expected <= 2 packets, got {}", send_stats.packets_sent);
     assert_eq!(send_stats.messages_sent, 100);
     assert!(recv_stats.messages_received >= 90, "Expected >= 90 messages received, got {}", recv_stats.messages_received);
@@ -361,10 +511,10 @@ fn test_stream_state_transitions() {
     };
 
     let receiver = UdpReceiver::new(recv_config, recv_addr).unwrap();
-    let mut sender = UdpSender::new(send_config, send_addr).unwrap();
 
     // Initially in Initializing state
     assert_eq!(receiver.state(), StreamState::Initializing);
+    let mut sender = UdpSender::new(send_config, send_addr).unwrap();
 
     // Send a message
     sender.send(MessageType::OrderNew, b"test".to_vec()).unwrap();
@@ -534,7 +684,7 @@ fn test_high_throughput() {
     let message_count = 10_000;
     let payload = vec![0u8; 100];
 
-    let start = std::time::Instant::now();
+    let start = Instant::now();
 
     // Send messages
     for _ in 0..message_count {
@@ -568,6 +718,1587 @@ fn test_high_throughput() {
     assert!(recv_stats.messages_received >= (message_count as u64 * 9) / 10);
 }
 
+#[test]
+fn test_snapshot_request_not_taken_for_control() {
+    let recv_addr: SocketAddr = format!("127.0.0.1:{}", get_free_port()).parse().unwrap();
+    let receiver = UdpReceiver::new(
+        ReceiverConfig {
+            stream_id: 1,
+            ..Default::default()
+        },
+        recv_addr,
+    )
+    .unwrap();
+    let sender = UdpSender::new(
+        SenderConfig {
+            stream_id: 1,
+            target_addr: recv_addr,
+            ..Default::default()
+        },
+        "127.0.0.1:0".parse().unwrap(),
+    )
+    .unwrap();
+
+    // Alone in its packet, like the gateway sends it
+    let request = binary::OrderCommand::SnapshotRequest {
+        symbol: "BTC-USD".to_string(),
+    };
+    let payload = binary::OrderCommandEncoder::new().encode(&request).to_vec();
+    sender.send(request.message_type(), payload).unwrap();
+
+    let msg = receiver.recv_timeout(Duration::from_secs(1)).unwrap().unwrap();
+    assert_eq!(binary::decode_order_command(msg.msg_type, &msg.payload).unwrap(), request);
+    assert_eq!(receiver.stats().gaps_detected, 0);
+}
+
+// ============================================================================
+// Retransmission Tests - Impaired Loopback
+// ============================================================================
+
+/// Sender and receiver talking through an impaired link, one message per packet
+fn impaired_stream(
+    sender_config: SenderConfig,
+    receiver_config: ReceiverConfig,
+    link_config: LinkConfig,
+) -> (UdpSender, UdpReceiver, ImpairedLink) {
+    let recv_addr: SocketAddr = format!("127.0.0.1:{}", get_free_port()).parse().unwrap();
+    let send_addr: SocketAddr = format!("127.0.0.1:{}", get_free_port()).parse().unwrap();
+
+    let receiver = UdpReceiver::new(
+        ReceiverConfig {
+            stream_id: 1,
+            auth: sender_config.auth.clone(),
+            ..receiver_config
+        },
+        recv_addr,
+    )
+    .unwrap();
+    let link = ImpairedLink::new(recv_addr, link_config).unwrap();
+    let sender = UdpSender::new(
+        SenderConfig {
+            stream_id: 1,
+            target_addr: link.addr(),
+            max_batch_delay: Duration::from_millis(1),
+            ..sender_config
+        },
+        send_addr,
+    )
+    .unwrap();
+
+    (sender, receiver, link)
+}
+
+/// Sender and receiver talking through a link that loses the data packets
+/// `lose` picks, the first time they pass
+fn lossy_stream(
+    sender_config: SenderConfig,
+    lose: impl Fn(&PacketHeader) -> bool + Send + 'static,
+) -> (UdpSender, UdpReceiver, ImpairedLink) {
+    let link_config = LinkConfig {
+        lose: Some(Box::new(lose)),
+        ..Default::default()
+    };
+    impaired_stream(sender_config, ReceiverConfig::default(), link_config)
+}
+
+/// Send one message per packet, so message `i` is `first_msg_seq == i`
+fn send_numbered(sender: &UdpSender, count: u8) {
+    for i in 0..count {
+        let sent = sender.stats().messages_sent;
+        sender.send(MessageType::MatchEvent, vec![i]).unwrap();
+        while sender.stats().messages_sent == sent {
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+}
+
+/// Payloads of the next `count` messages, the stats settle before returning
+fn receive_numbered(receiver: &UdpReceiver, count: usize) -> Vec<u8> {
+    let payloads = (0..count)
+        .map_while(|_| receiver.recv_timeout(Duration::from_secs(1)).unwrap())
+        .map(|msg| msg.payload[0])
+        .collect();
+    thread::sleep(Duration::from_millis(20));
+    payloads
+}
+
+#[test]
+fn test_nak_recovers_lost_packets() {
+    let config = SenderConfig {
+        enable_heartbeats: false,
+        ..Default::default()
+    };
+    let (sender, receiver, _link) = lossy_stream(config, |h| [2, 3, 7].contains(&h.first_msg_seq));
+
+    send_numbered(&sender, 10);
+
+    // Everything arrives, in order, despite the losses
+    assert_eq!(receive_numbered(&receiver, 10), (0..10).collect::<Vec<u8>>());
+
+    let recv_stats = receiver.stats();
+    assert_eq!(recv_stats.gaps_detected, 2);
+    assert_eq!(recv_stats.gaps_recovered, 2);
+    assert_eq!(recv_stats.gaps_unrecoverable, 0);
+    assert!(!receiver.needs_snapshot());
+    assert_eq!(receiver.state(), StreamState::Active);
+    assert_eq!(sender.stats().retransmits_sent, 3);
+}
+
+#[test]
+fn test_heartbeat_reveals_lost_tail() {
+    let (sender, receiver, _link) = lossy_stream(SenderConfig::default(), |h| h.first_msg_seq == 4);
+
+    send_numbered(&sender, 5);
+
+    // Nothing follows the last message but a heartbeat
+    assert_eq!(receive_numbered(&receiver, 5), vec![0, 1, 2, 3, 4]);
+    assert_eq!(receiver.stats().gaps_recovered, 1);
+    assert!(!receiver.needs_snapshot());
+}
+
+#[test]
+fn test_gap_outside_buffer_needs_snapshot() {
+    let config = SenderConfig {
+        enable_heartbeats: false,
+        retransmit_buffer: 1,
+        ..Default::default()
+    };
+    let (sender, receiver, _link) = lossy_stream(config, |h| h.first_msg_seq == 1);
+
+    send_numbered(&sender, 3);
+
+    // The sender only keeps the newest packet, the receiver moves on without
+    assert_eq!(receive_numbered(&receiver, 2), vec![0, 2]);
+    assert_eq!(sender.stats().naks_unavailable, 1);
+    assert_eq!(receiver.stats().gaps_unrecoverable, 1);
+    assert!(receiver.needs_snapshot());
+    assert_eq!(receiver.state(), StreamState::Degraded);
+
+    receiver.clear_gaps();
+    assert!(!receiver.needs_snapshot());
+}
+
+#[test]
+fn test_gap_given_up_without_retransmission() {
+    let config = SenderConfig {
+        enable_heartbeats: false,
+        retransmit_buffer: 0,
+        ..Default::default()
+    };
+    let (sender, receiver, _link) = lossy_stream(config, |h| h.first_msg_seq == 1);
+
+    send_numbered(&sender, 3);
+
+    // Unanswered NAKs time out, what was held back is delivered late
+    assert_eq!(receive_numbered(&receiver, 2), vec![0, 2]);
+    assert_eq!(receiver.stats().naks_sent, 4);
+    assert!(receiver.needs_snapshot());
+}
+
+// ============================================================================
+// Impairment Tests - Seeded Loss, Duplication, Reordering, Delay, Corruption
+// ============================================================================
+
+fn impaired(seed: u64, forward: Impairment, backward: Impairment) -> LinkConfig {
+    LinkConfig {
+        seed,
+        forward,
+        backward,
+        lose: None,
+    }
+}
+
+#[test]
+fn test_random_loss_recovered() {
+    let loss = Impairment {
+        drop_rate: 0.1,
+        ..Default::default()
+    };
+    let (sender, receiver, link) = impaired_stream(
+        SenderConfig::default(),
+        ReceiverConfig::default(),
+        impaired(1, loss, loss),
+    );
+
+    send_numbered(&sender, 200);
+
+    // NAKs get lost too, they are repeated until answered
+    assert_eq!(receive_numbered(&receiver, 200), (0..200).collect::<Vec<u8>>());
+    let recv_stats = receiver.stats();
+    assert!(link.stats(Direction::Forward).dropped > 0);
+    assert!(recv_stats.gaps_recovered > 0);
+    assert_eq!(recv_stats.gaps_unrecoverable, 0);
+    assert!(!receiver.needs_snapshot());
+    assert_eq!(receiver.state(), StreamState::Active);
+}
+
+#[test]
+fn test_gaps_detected_without_retransmission() {
+    let config = SenderConfig {
+        enable_heartbeats: false,
+        retransmit_buffer: 0,
+        ..Default::default()
+    };
+    let loss = Impairment {
+        drop_rate: 0.2,
+        ..Default::default()
+    };
+    let (sender, receiver, link) =
+        impaired_stream(config, ReceiverConfig::default(), impaired(2, loss, Impairment::default()));
+
+    send_numbered(&sender, 50);
+
+    // What got through is delivered in order, the rest is reported lost
+    let received = receive_numbered(&receiver, 50);
+    assert!(received.len() < 50);
+    assert!(received.windows(2).all(|w| w[0] < w[1]));
+    assert!(link.stats(Direction::Forward).dropped >= (50 - received.len()) as u64);
+    let recv_stats = receiver.stats();
+    assert!(recv_stats.gaps_detected > 0);
+    assert_eq!(recv_stats.gaps_recovered, 0);
+    assert!(receiver.needs_snapshot());
+}
+
+#[test]
+fn test_duplicated_and_reordered_packets_delivered_once() {
+    let shuffle = Impairment {
+        duplicate_rate: 0.2,
+        reorder_rate: 0.2,
+        ..Default::default()
+    };
+    let (sender, receiver, link) = impaired_stream(
+        SenderConfig::default(),
+        ReceiverConfig::default(),
+        impaired(3, shuffle, Impairment::default()),
+    );
+
+    send_numbered(&sender, 100);
+
+    assert_eq!(receive_numbered(&receiver, 100), (0..100).collect::<Vec<u8>>());
+    assert!(receiver.recv_timeout(Duration::from_millis(50)).unwrap().is_none());
+    let link_stats = link.stats(Direction::Forward);
+    assert!(link_stats.duplicated > 0);
+    assert!(link_stats.reordered > 0);
+    let recv_stats = receiver.stats();
+    assert!(recv_stats.duplicates_dropped > 0);
+    assert_eq!(recv_stats.gaps_unrecoverable, 0);
+}
+
+#[test]
+fn test_corrupted_packets_rejected_and_recovered() {
+    let config = SenderConfig {
+        auth: Some(auth_config(AuthMode::Sign)),
+        ..Default::default()
+    };
+    let corrupt = Impairment {
+        corrupt_rate: 0.1,
+        ..Default::default()
+    };
+    let (sender, receiver, link) =
+        impaired_stream(config, ReceiverConfig::default(), impaired(4, corrupt, Impairment::default()));
+
+    send_numbered(&sender, 100);
+
+    // A flipped bit fails the tag, or the header checks before it
+    assert_eq!(receive_numbered(&receiver, 100), (0..100).collect::<Vec<u8>>());
+    let corrupted = link.stats(Direction::Forward).corrupted;
+    let recv_stats = receiver.stats();
+    assert!(recv_stats.auth_failures > 0);
+    assert!(recv_stats.auth_failures <= corrupted);
+    assert!(recv_stats.gaps_recovered > 0);
+    assert_eq!(recv_stats.gaps_unrecoverable, 0);
+}
+
+#[test]
+fn test_delay_and_jitter_keep_order() {
+    let slow = Impairment {
+        delay: Duration::from_millis(20),
+        jitter: Duration::from_millis(5),
+        ..Default::default()
+    };
+    let (sender, receiver, _link) =
+        impaired_stream(SenderConfig::default(), ReceiverConfig::default(), impaired(5, slow, slow));
+
+    let start = Instant::now();
+    sender.send(MessageType::MatchEvent, vec![0]).unwrap();
+    assert!(receiver.recv_timeout(Duration::from_secs(1)).unwrap().is_some());
+    assert!(start.elapsed() >= Duration::from_millis(20));
+
+    send_numbered(&sender, 50);
+
+    // Jitter lets packets overtake each other, the receiver puts them back
+    assert_eq!(receive_numbered(&receiver, 50), (0..50).collect::<Vec<u8>>());
+    assert_eq!(receiver.stats().gaps_unrecoverable, 0);
+}
+
+#[test]
+fn test_one_way_latency_measured() {
+    let sender_config = SenderConfig {
+        send_timestamps: true,
+        ..Default::default()
+    };
+    let (sender, receiver, link) =
+        impaired_stream(sender_config, ReceiverConfig::default(), LinkConfig::default());
+
+    // Heartbeats over the undelayed link give the clock offset
+    wait_for(|| receiver.stats().heartbeats_received >= 2);
+    assert!(receiver.stats().heartbeats_received >= 2);
+
+    let slow = Impairment {
+        delay: Duration::from_millis(10),
+        jitter: Duration::from_millis(2),
+        ..Default::default()
+    };
+    link.set_impairment(Direction::Forward, slow);
+    for i in 0..10 {
+        sender.send(MessageType::MatchEvent, vec![i]).unwrap();
+        thread::sleep(Duration::from_millis(2));
+    }
+    assert_eq!(receive_numbered(&receiver, 10), (0..10).collect::<Vec<u8>>());
+
+    let stats = receiver.stats();
+    assert!(stats.latency.count >= 1);
+    assert!(stats.latency.mean().unwrap() >= Duration::from_millis(8));
+    assert!(stats.latency.max().unwrap() < Duration::from_secs(1));
+    assert_eq!(stats.jitter.count, stats.latency.count - 1);
+}
+
+#[test]
+fn test_latency_not_measured_without_timestamps() {
+    let (sender, receiver, _link) =
+        impaired_stream(SenderConfig::default(), ReceiverConfig::default(), LinkConfig::default());
+
+    wait_for(|| receiver.stats().heartbeats_received >= 1);
+    send_numbered(&sender, 5);
+    assert_eq!(receive_numbered(&receiver, 5), vec![0, 1, 2, 3, 4]);
+    assert_eq!(receiver.stats().latency, HistogramSnapshot::default());
+}
+
+#[test]
+fn test_stream_state_through_outage() {
+    let receiver_config = ReceiverConfig {
+        stream_timeout: Duration::from_millis(300),
+        ..Default::default()
+    };
+    let (sender, receiver, link) =
+        impaired_stream(SenderConfig::default(), receiver_config, LinkConfig::default());
+
+    send_numbered(&sender, 5);
+    assert_eq!(receive_numbered(&receiver, 5), vec![0, 1, 2, 3, 4]);
+    assert_eq!(receiver.state(), StreamState::Active);
+
+    // Nothing gets through, heartbeats included
+    let outage = Impairment {
+        drop_rate: 1.0,
+        ..Default::default()
+    };
+    link.set_impairment(Direction::Forward, outage);
+    send_numbered(&sender, 3);
+    wait_for(|| receiver.state() == StreamState::Down);
+    assert_eq!(receiver.state(), StreamState::Down);
+
+    // The next heartbeat brings the stream back and reveals what was missed
+    link.set_impairment(Direction::Forward, Impairment::default());
+    wait_for(|| receiver.state() == StreamState::Active);
+    assert_eq!(receiver.state(), StreamState::Active);
+    assert_eq!(receive_numbered(&receiver, 3), vec![0, 1, 2]);
+    assert!(receiver.stats().gaps_recovered > 0);
+    assert!(!receiver.needs_snapshot());
+}
+
+// ============================================================================
+// Fragmentation Tests
+// ============================================================================
+
+fn large_payload(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i % 251) as u8).collect()
+}
+
+#[test]
+fn test_large_message_reassembled() {
+    let recv_addr: SocketAddr = format!("127.0.0.1:{}", get_free_port()).parse().unwrap();
+    let send_addr: SocketAddr = format!("127.0.0.1:{}", get_free_port()).parse().unwrap();
+
+    let receiver = UdpReceiver::new(
+        ReceiverConfig {
+            stream_id: 1,
+            ..Default::default()
+        },
+        recv_addr,
+    )
+    .unwrap();
+    let sender = UdpSender::new(
+        SenderConfig {
+            stream_id: 1,
+            target_addr: recv_addr,
+            enable_heartbeats: false,
+            max_batch_delay: Duration::from_millis(1),
+            ..Default::default()
+        },
+        send_addr,
+    )
+    .unwrap();
+
+    let snapshot = large_payload(20_000);
+    sender.send(MessageType::OrderNew, b"before".to_vec()).unwrap();
+    sender.send(MessageType::BookSnapshot, snapshot.clone()).unwrap();
+    sender.send(MessageType::OrderNew, b"after".to_vec()).unwrap();
+
+    let received: Vec<_> = (0..3)
+        .map_while(|_| receiver.recv_timeout(Duration::from_secs(1)).unwrap())
+        .collect();
+    assert_eq!(received.len(), 3);
+    assert_eq!(received[0].payload, b"before");
+    assert_eq!(received[1].msg_type, MessageType::BookSnapshot);
+    assert_eq!(received[1].payload, snapshot);
+    assert_eq!(received[2].payload, b"after");
+
+    // Each fragment takes a sequence number, the message gets the first one
+    let fragments = snapshot.len().div_ceil(MAX_FRAGMENT_LEN) as u64;
+    assert_eq!(received[1].seq, 1);
+    assert_eq!(received[2].seq, 1 + fragments);
+
+    assert_eq!(sender.stats().messages_fragmented, 1);
+    let recv_stats = receiver.stats();
+    assert_eq!(recv_stats.messages_reassembled, 1);
+    assert_eq!(recv_stats.partial_messages_dropped, 0);
+}
+
+#[test]
+fn test_lost_fragment_is_resent() {
+    let config = SenderConfig {
+        enable_heartbeats: false,
+        ..Default::default()
+    };
+    let (sender, receiver, _link) = lossy_stream(config, |h| h.first_msg_seq == 2);
+
+    let snapshot = large_payload(MAX_FRAGMENT_LEN * 4);
+    sender.send(MessageType::BookSnapshot, snapshot.clone()).unwrap();
+    send_numbered(&sender, 1);
+
+    let msg = receiver.recv_timeout(Duration::from_secs(1)).unwrap().unwrap();
+    assert_eq!(msg.payload, snapshot);
+    assert_eq!(receive_numbered(&receiver, 1), vec![0]);
+    assert_eq!(receiver.stats().gaps_recovered, 1);
+}
+
+#[test]
+fn test_reassembly_drops_stale_partials() {
+    let mut reassembler = Reassembler::new(DEFAULT_MAX_MESSAGE_SIZE, 2, Duration::from_millis(20));
+    let payload = large_payload(MAX_FRAGMENT_LEN * 2);
+    let fragment = |index: usize| {
+        let (header, chunk) = FragmentHeader::split(&payload).nth(index).unwrap();
+        let mut buf = vec![0u8; FRAGMENT_HEADER_LEN];
+        header.write_to(&mut buf).unwrap();
+        buf.extend_from_slice(chunk);
+        buf
+    };
+
+    // Three messages started, only two are kept
+    for first_seq in [0, 10, 20] {
+        let partial = reassembler.push(1, MessageType::BookSnapshot, first_seq, &fragment(0));
+        assert!(partial.unwrap().is_none());
+    }
+    assert_eq!(reassembler.pending(), 2);
+    assert_eq!(reassembler.take_dropped(), 1);
+
+    // The oldest went, its last fragment starts over and evicts the next one
+    let evicted = reassembler.push(1, MessageType::BookSnapshot, 1, &fragment(1));
+    assert!(evicted.unwrap().is_none());
+    let completed = reassembler.push(1, MessageType::BookSnapshot, 21, &fragment(1));
+    assert_eq!(completed.unwrap().unwrap().payload, payload);
+
+    thread::sleep(Duration::from_millis(30));
+    reassembler.expire();
+    assert_eq!(reassembler.pending(), 0);
+    assert_eq!(reassembler.take_dropped(), 2);
+
+    // Fragments have to agree on the message they belong to
+    reassembler.push(1, MessageType::BookSnapshot, 30, &fragment(0)).unwrap();
+    assert!(matches!(
+        reassembler.push(1, MessageType::BookUpdate, 31, &fragment(1)),
+        Err(ProtocolError::InvalidFragment { .. })
+    ));
+    assert_eq!(reassembler.pending(), 0);
+
+    // And carry a full chunk unless they are the last one
+    reassembler.push(1, MessageType::BookSnapshot, 41, &fragment(1)).unwrap();
+    let mut short = fragment(0);
+    short.pop();
+    assert!(matches!(
+        reassembler.push(1, MessageType::BookSnapshot, 40, &short),
+        Err(ProtocolError::InvalidFragment { index: 0, count: 2 })
+    ));
+    assert_eq!(reassembler.pending(), 0);
+    assert_eq!(reassembler.take_dropped(), 2);
+}
+
+// ============================================================================
+// Authentication Tests
+// ============================================================================
+
+/// Where the packets opened in these tests come from
+const PEER: SocketAddr = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 9000));
+
+fn auth_config(mode: AuthMode) -> AuthConfig {
+    AuthConfig::new(mode, StreamKey::new(1, b"first stream key".to_vec()))
+}
+
+fn data_packet(packet_seq: u64, payload: &[u8]) -> Vec<u8> {
+    let mut builder = PacketBuilder::new(1, packet_seq, packet_seq);
+    builder.try_add_message(MessageType::OrderNew, payload);
+    builder.finish()
+}
+
+#[test]
+fn test_sealed_packet_roundtrip() {
+    for mode in [AuthMode::Sign, AuthMode::Encrypt] {
+        let config = auth_config(mode);
+        let mut sealer = Sealer::new(&config, Role::Sender).unwrap();
+        let mut opener = Opener::new(&config);
+
+        let plain = data_packet(0, b"secret order");
+        let mut packet = plain.clone();
+        sealer.seal(&mut packet);
+        assert_eq!(packet.len(), plain.len() + AUTH_TRAILER_LEN);
+        assert_eq!(
+            packet.windows(12).any(|w| w == b"secret order"),
+            mode == AuthMode::Sign
+        );
+
+        let opened = opener.open(&mut packet, PEER).unwrap();
+        assert_eq!(opened.key_id, 1);
+        assert_eq!(&packet[..opened.len], &plain[..]);
+    }
+}
+
+#[test]
+fn test_sealed_timestamped_packet_roundtrip() {
+    for mode in [AuthMode::Sign, AuthMode::Encrypt] {
+        let config = auth_config(mode);
+        let mut sealer = Sealer::new(&config, Role::Sender).unwrap();
+        let mut opener = Opener::new(&config);
+
+        let mut builder = PacketBuilder::new(1, 0, 0).with_send_time();
+        builder.try_add_message(MessageType::OrderNew, b"secret order");
+        let plain = builder.finish();
+        let mut packet = plain.clone();
+        sealer.seal(&mut packet);
+
+        // The send time is authenticated, not encrypted
+        let mut tampered = packet.clone();
+        tampered[PACKET_HEADER_LEN] ^= 1;
+        assert!(opener.open(&mut tampered, PEER).is_err());
+
+        let opened = opener.open(&mut packet, PEER).unwrap();
+        assert_eq!(&packet[..opened.len], &plain[..]);
+    }
+}
+
+#[test]
+fn test_tampered_packet_rejected() {
+    for mode in [AuthMode::Sign, AuthMode::Encrypt] {
+        let config = auth_config(mode);
+        let mut sealer = Sealer::new(&config, Role::Sender).unwrap();
+        let mut packet = data_packet(0, b"order");
+        sealer.seal(&mut packet);
+
+        // Any flipped bit, header and trailer included
+        for i in [0, PACKET_HEADER_LEN + 2, packet.len() - 1] {
+            let mut tampered = packet.clone();
+            tampered[i] ^= 0x01;
+            assert!(Opener::new(&config).open(&mut tampered, PEER).is_err());
+        }
+
+        // Same key ID, other secret
+        let other = AuthConfig::new(mode, StreamKey::new(1, b"another secret".to_vec()));
+        assert!(matches!(
+            Opener::new(&other).open(&mut packet.clone(), PEER),
+            Err(ProtocolError::AuthenticationFailed)
+        ));
+
+        // Unsealed, or sealed with a key the receiver doesn't have
+        let mut plain = data_packet(1, b"order");
+        assert!(Opener::new(&config).open(&mut plain, PEER).is_err());
+        let unknown = AuthConfig::new(mode, StreamKey::new(7, b"unknown".to_vec()));
+        let mut packet = data_packet(2, b"order");
+        Sealer::new(&unknown, Role::Sender).unwrap().seal(&mut packet);
+        assert!(matches!(
+            Opener::new(&config).open(&mut packet, PEER),
+            Err(ProtocolError::UnknownKey(7))
+        ));
+    }
+}
+
+#[test]
+fn test_nak_not_accepted_as_data() {
+    let config = auth_config(AuthMode::Encrypt);
+    let mut sealer = Sealer::new(&config, Role::Receiver).unwrap();
+    let mut opener = Opener::new(&config);
+
+    let mut nak = PacketBuilder::control(1, 0, &[ControlMessage::Nak { from_seq: 0, to_seq: 1 }]);
+    sealer.seal(&mut nak);
+    assert!(opener.open(&mut nak.clone(), PEER).is_err());
+    assert!(opener.open_nak(&mut nak, PEER).is_ok());
+}
+
+#[test]
+fn test_replayed_packet_rejected() {
+    let config = auth_config(AuthMode::Sign);
+    let mut sealer = Sealer::new(&config, Role::Sender).unwrap();
+    let mut opener = Opener::new(&config);
+    let sealed: Vec<Vec<u8>> = (0..4)
+        .map(|seq| {
+            let mut packet = data_packet(seq, b"order");
+            sealer.seal(&mut packet);
+            packet
+        })
+        .collect();
+
+    // Out of order is fine, seen twice is not
+    for seq in [0, 2, 1] {
+        opener.open(&mut sealed[seq].clone(), PEER).unwrap();
+    }
+    for seq in [0, 1, 2] {
+        assert!(matches!(
+            opener.open(&mut sealed[seq].clone(), PEER),
+            Err(ProtocolError::Replayed { packet_seq }) if packet_seq == seq as u64
+        ));
+    }
+    opener.open(&mut sealed[3].clone(), PEER).unwrap();
+
+    // Too far behind the newest packet to tell
+    let mut newest = data_packet(5000, b"order");
+    sealer.seal(&mut newest);
+    opener.open(&mut newest, PEER).unwrap();
+    let mut late = data_packet(4, b"order");
+    sealer.seal(&mut late);
+    assert!(matches!(opener.open(&mut late, PEER), Err(ProtocolError::Replayed { .. })));
+
+    // A restarted sender starts a new epoch, the old one is over
+    thread::sleep(Duration::from_millis(2));
+    let mut restarted = Sealer::new(&config, Role::Sender).unwrap();
+    let mut packet = data_packet(0, b"order");
+    restarted.seal(&mut packet);
+    opener.open(&mut packet, PEER).unwrap();
+    let mut stale = data_packet(5001, b"order");
+    sealer.seal(&mut stale);
+    assert!(matches!(opener.open(&mut stale, PEER), Err(ProtocolError::Replayed { .. })));
+}
+
+#[test]
+fn test_retired_key_rejected() {
+    let config = auth_config(AuthMode::Sign);
+    let new_key = StreamKey::new(2, b"second stream key".to_vec());
+    let mut sealer = Sealer::new(&config, Role::Sender).unwrap();
+    let mut opener = Opener::new(&config);
+    opener.add_key(&new_key);
+
+    let seal = |sealer: &mut Sealer, seq| {
+        let mut packet = data_packet(seq, b"order");
+        sealer.seal(&mut packet);
+        packet
+    };
+    let mut before = seal(&mut sealer, 0);
+    let mut rotation = seal(&mut sealer, 1);
+    let mut after_with_old_key = seal(&mut sealer, 3);
+    sealer.add_key(&new_key);
+    assert!(sealer.select_key(2));
+    assert!(!sealer.select_key(9));
+    let mut after = seal(&mut sealer, 2);
+
+    let opened = opener.open(&mut rotation, PEER).unwrap();
+    opener.retire_keys(
+        StreamSource {
+            addr: PEER,
+            stream_id: 1,
+        },
+        2,
+        opened,
+        1,
+    );
+    opener.open(&mut after, PEER).unwrap();
+
+    // Sent before the rotation but late is fine, the old key after it is not
+    opener.open(&mut before, PEER).unwrap();
+    assert!(matches!(
+        opener.open(&mut after_with_old_key, PEER),
+        Err(ProtocolError::UnknownKey(1))
+    ));
+
+    let no_keys = AuthConfig {
+        mode: AuthMode::Sign,
+        keys: vec![],
+    };
+    assert!(matches!(
+        Sealer::new(&no_keys, Role::Sender),
+        Err(ProtocolError::NoStreamKey)
+    ));
+}
+
+/// Sender and receiver sharing `auth`, and the receiver's address
+fn authenticated_stream(auth: AuthConfig) -> (UdpSender, UdpReceiver, SocketAddr) {
+    let recv_addr: SocketAddr = format!("127.0.0.1:{}", get_free_port()).parse().unwrap();
+    let send_addr: SocketAddr = format!("127.0.0.1:{}", get_free_port()).parse().unwrap();
+
+    let receiver = UdpReceiver::new(
+        ReceiverConfig {
+            stream_id: 1,
+            auth: Some(auth.clone()),
+            ..Default::default()
+        },
+        recv_addr,
+    )
+    .unwrap();
+    let sender = UdpSender::new(
+        SenderConfig {
+            stream_id: 1,
+            target_addr: recv_addr,
+            enable_heartbeats: false,
+            max_batch_delay: Duration::from_millis(1),
+            auth: Some(auth),
+            ..Default::default()
+        },
+        send_addr,
+    )
+    .unwrap();
+
+    (sender, receiver, recv_addr)
+}
+
+#[test]
+fn test_encrypted_stream() {
+    let (sender, receiver, _) = authenticated_stream(auth_config(AuthMode::Encrypt));
+
+    send_numbered(&sender, 10);
+    sender.send(MessageType::BookSnapshot, large_payload(5000)).unwrap();
+
+    assert_eq!(receive_numbered(&receiver, 10), (0..10).collect::<Vec<u8>>());
+    let msg = receiver.recv_timeout(Duration::from_secs(1)).unwrap().unwrap();
+    assert_eq!(msg.payload, large_payload(5000));
+    assert_eq!(receiver.stats().auth_failures, 0);
+}
+
+#[test]
+fn test_unauthenticated_sender_rejected() {
+    let (_sender, receiver, recv_addr) = authenticated_stream(auth_config(AuthMode::Sign));
+    let intruder = UdpSocket::bind("127.0.0.1:0").unwrap();
+
+    intruder.send_to(&data_packet(0, b"forged"), recv_addr).unwrap();
+
+    assert!(receiver.recv_timeout(Duration::from_millis(100)).unwrap().is_none());
+    assert_eq!(receiver.stats().auth_failures, 1);
+    assert_eq!(receiver.stats().messages_received, 0);
+}
+
+#[test]
+fn test_key_rotation() {
+    let (sender, receiver, _) = authenticated_stream(auth_config(AuthMode::Sign));
+    send_numbered(&sender, 2);
+
+    // Receivers learn the key first, then the sender switches to it
+    let key = StreamKey::new(2, b"second stream key".to_vec());
+    receiver.add_key(key.clone()).unwrap();
+    sender.rotate_key(key.clone()).unwrap();
+    assert!(matches!(sender.rotate_key(key), Err(ProtocolError::UnknownKey(2))));
+    send_numbered(&sender, 2);
+
+    assert_eq!(receive_numbered(&receiver, 4), vec![0, 1, 0, 1]);
+
+    // A key the receiver never got cuts the stream off
+    sender
+        .rotate_key(StreamKey::new(3, b"third stream key".to_vec()))
+        .unwrap();
+    send_numbered(&sender, 1);
+    assert!(receiver.recv_timeout(Duration::from_millis(100)).unwrap().is_none());
+    assert_eq!(receiver.stats().auth_failures, 1);
+}
+
+#[test]
+fn test_authenticated_senders_share_stream_id() {
+    let auth = auth_config(AuthMode::Sign);
+    let (first, receiver, recv_addr) = authenticated_stream(auth.clone());
+    send_numbered(&first, 2);
+    assert_eq!(receive_numbered(&receiver, 2), vec![0, 1]);
+
+    // A later sender starts a newer epoch on the same stream ID
+    thread::sleep(Duration::from_millis(2));
+    let send_addr: SocketAddr = format!("127.0.0.1:{}", get_free_port()).parse().unwrap();
+    let second = UdpSender::new(
+        SenderConfig {
+            stream_id: 1,
+            target_addr: recv_addr,
+            enable_heartbeats: false,
+            max_batch_delay: Duration::from_millis(1),
+            auth: Some(auth),
+            ..Default::default()
+        },
+        send_addr,
+    )
+    .unwrap();
+    second.send(MessageType::OrderNew, vec![10]).unwrap();
+    let msg = receiver.recv_timeout(Duration::from_secs(1)).unwrap().unwrap();
+    assert_eq!((msg.source, msg.payload), (send_addr, vec![10]));
+
+    // The older sender is not taken for a replay of the newer one
+    send_numbered(&first, 2);
+    assert_eq!(receive_numbered(&receiver, 2), vec![0, 1]);
+    assert_eq!(receiver.stats().replays_rejected, 0);
+    assert_eq!(receiver.stats().auth_failures, 0);
+}
+
+#[test]
+fn test_nak_recovery_on_encrypted_stream() {
+    let config = SenderConfig {
+        enable_heartbeats: false,
+        auth: Some(auth_config(AuthMode::Encrypt)),
+        ..Default::default()
+    };
+    let (sender, receiver, _link) = lossy_stream(config, |h| [2, 5].contains(&h.first_msg_seq));
+
+    send_numbered(&sender, 8);
+
+    // Resent packets are the same packets, not replays
+    assert_eq!(receive_numbered(&receiver, 8), (0..8).collect::<Vec<u8>>());
+    let recv_stats = receiver.stats();
+    assert_eq!(recv_stats.gaps_recovered, 2);
+    assert_eq!(recv_stats.replays_rejected, 0);
+    assert_eq!(sender.stats().auth_failures, 0);
+}
+
+// ============================================================================
+// Multicast Tests
+// ============================================================================
+
+/// Receiver of `group` on loopback
+fn group_receiver(group: Ipv4Addr, port: u16) -> UdpReceiver {
+    UdpReceiver::new(
+        ReceiverConfig {
+            stream_id: 1,
+            multicast_group: Some(group),
+            multicast_interface: Ipv4Addr::LOCALHOST,
+            ..Default::default()
+        },
+        SocketAddr::from((Ipv4Addr::UNSPECIFIED, port)),
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_multicast_reaches_every_receiver() {
+    let group = Ipv4Addr::new(239, 255, 41, 1);
+    let port = get_free_port();
+    let receivers = [group_receiver(group, port), group_receiver(group, port)];
+
+    let sender = UdpSender::new(
+        SenderConfig {
+            stream_id: 1,
+            target_addr: SocketAddr::from((group, port)),
+            enable_heartbeats: false,
+            max_batch_delay: Duration::from_millis(1),
+            multicast_interface: Ipv4Addr::LOCALHOST,
+            ..Default::default()
+        },
+        "127.0.0.1:0".parse().unwrap(),
+    )
+    .unwrap();
+
+    send_numbered(&sender, 5);
+
+    for receiver in &receivers {
+        assert_eq!(receive_numbered(receiver, 5), vec![0, 1, 2, 3, 4]);
+        assert_eq!(receiver.stats().gaps_detected, 0);
+    }
+    assert_eq!(sender.stats().packets_sent, 5);
+}
+
+#[test]
+fn test_multicast_group_validated() {
+    let config = ReceiverConfig {
+        multicast_group: Some(Ipv4Addr::new(10, 0, 0, 1)),
+        ..Default::default()
+    };
+    let result = UdpReceiver::new(config, "127.0.0.1:0".parse().unwrap());
+    assert!(matches!(result, Err(ProtocolError::NotMulticast(_))));
+}
+
+// ============================================================================
+// Session Tests
+// ============================================================================
+
+/// Handler recording the session events it is told about
+fn session_log() -> (SessionHandler, Arc<parking_lot::Mutex<Vec<SessionEvent>>>) {
+    let events = Arc::new(parking_lot::Mutex::new(Vec::new()));
+    let handler = SessionHandler::new({
+        let events = events.clone();
+        move |event| events.lock().push(event)
+    });
+    (handler, events)
+}
+
+fn wait_for(condition: impl Fn() -> bool) {
+    for _ in 0..200 {
+        if condition() {
+            return;
+        }
+        thread::sleep(Duration::from_millis(5));
+    }
+}
+
+fn session_receiver(on_session: SessionHandler) -> (UdpReceiver, SocketAddr) {
+    let recv_addr: SocketAddr = format!("127.0.0.1:{}", get_free_port()).parse().unwrap();
+    let receiver = UdpReceiver::new(
+        ReceiverConfig {
+            stream_id: 1,
+            on_session: Some(on_session),
+            ..Default::default()
+        },
+        recv_addr,
+    )
+    .unwrap();
+    (receiver, recv_addr)
+}
+
+fn session_sender(target_addr: SocketAddr, on_session: Option<SessionHandler>) -> UdpSender {
+    UdpSender::new(
+        SenderConfig {
+            stream_id: 1,
+            target_addr,
+            on_session,
+            ..Default::default()
+        },
+        "127.0.0.1:0".parse().unwrap(),
+    )
+    .unwrap()
+}
+
+/// The control messages among the packets waiting on `socket`
+fn recv_control(socket: &UdpSocket) -> Vec<ControlMessage> {
+    let mut buf = vec![0u8; MAX_MTU];
+    let mut messages = Vec::new();
+    while let Ok((len, _)) = socket.recv_from(&mut buf) {
+        let packet = Packet::parse(&buf[..len]).unwrap();
+        messages.extend(packet.control_messages());
+    }
+    messages
+}
+
+#[test]
+fn test_session_established() {
+    let (receiver_handler, receiver_events) = session_log();
+    let (sender_handler, sender_events) = session_log();
+    let (receiver, recv_addr) = session_receiver(receiver_handler);
+    let sender = session_sender(recv_addr, Some(sender_handler));
+
+    wait_for(|| !sender_events.lock().is_empty());
+    assert_eq!(receiver.state(), StreamState::Active);
+    assert!(matches!(
+        receiver_events.lock()[..],
+        [SessionEvent::Established { session_id, .. }] if session_id == sender.session_id()
+    ));
+    assert_eq!(
+        sender_events.lock()[..],
+        [SessionEvent::Established {
+            peer: recv_addr,
+            session_id: receiver.session_id(),
+        }]
+    );
+    assert_ne!(sender.session_id(), receiver.session_id());
+}
+
+#[test]
+fn test_logout_on_shutdown() {
+    let (receiver_handler, receiver_events) = session_log();
+    let (receiver, recv_addr) = session_receiver(receiver_handler);
+    let sender = session_sender(recv_addr, None);
+    let session_id = sender.session_id();
+
+    send_numbered(&sender, 3);
+    assert_eq!(receive_numbered(&receiver, 3), vec![0, 1, 2]);
+
+    // Down right away, not after the stream timeout
+    drop(sender);
+    wait_for(|| receiver.state() == StreamState::Down);
+    assert_eq!(receiver.state(), StreamState::Down);
+    assert_eq!(receiver.state(), StreamState::Down);
+    assert!(matches!(
+        receiver_events.lock().last(),
+        Some(SessionEvent::LoggedOut { session_id: id, .. }) if *id == session_id
+    ));
+
+    // The next sender numbers from 0 again and is not taken for duplicates
+    let sender = session_sender(recv_addr, None);
+    send_numbered(&sender, 2);
+    assert_eq!(receive_numbered(&receiver, 2), vec![0, 1]);
+    assert_eq!(receiver.stats().duplicates_dropped, 0);
+
+    // Receivers log out too
+    let (sender_handler, sender_events) = session_log();
+    let (receiver, recv_addr) = session_receiver(SessionHandler::new(|_| {}));
+    let sender = session_sender(recv_addr, Some(sender_handler));
+    wait_for(|| !sender_events.lock().is_empty());
+    let receiver_session = receiver.session_id();
+    drop(receiver);
+    wait_for(|| sender_events.lock().len() == 2);
+    assert_eq!(
+        sender_events.lock()[1],
+        SessionEvent::LoggedOut {
+            peer: recv_addr,
+            session_id: receiver_session,
+        }
+    );
+    drop(sender);
+}
+
+#[test]
+fn test_sender_restart_starts_stream_over() {
+    let (handler, events) = session_log();
+    let (receiver, recv_addr) = session_receiver(handler);
+    let peer = UdpSocket::bind("127.0.0.1:0").unwrap();
+    peer.set_read_timeout(Some(Duration::from_millis(50))).unwrap();
+    let hello = |session_id| {
+        PacketBuilder::control(1, 0, &[ControlMessage::Hello { session_id, version: PROTOCOL_VERSION }])
+    };
+
+    peer.send_to(&hello(7), recv_addr).unwrap();
+    for seq in 0..3 {
+        peer.send_to(&data_packet(seq, &[seq as u8]), recv_addr).unwrap();
+    }
+    assert_eq!(receive_numbered(&receiver, 3), vec![0, 1, 2]);
+    let accept = ControlMessage::Accept {
+        session_id: receiver.session_id(),
+        version: PROTOCOL_VERSION,
+    };
+    assert_eq!(recv_control(&peer), vec![accept]);
+
+    // Crashed without logging out, back under a new session
+    peer.send_to(&hello(8), recv_addr).unwrap();
+    peer.send_to(&data_packet(0, &[10]), recv_addr).unwrap();
+    assert_eq!(receive_numbered(&receiver, 1), vec![10]);
+    assert_eq!(recv_control(&peer), vec![accept]);
+    assert_eq!(receiver.stats().duplicates_dropped, 0);
+    assert!(matches!(
+        events.lock()[..],
+        [
+            SessionEvent::Established { session_id: 7, .. },
+            SessionEvent::Restarted { old_session_id: 7, session_id: 8, .. },
+        ]
+    ));
+}
+
+#[test]
+fn test_receiver_joining_late_gets_hello() {
+    let (handler, events) = session_log();
+    let peer = UdpSocket::bind("127.0.0.1:0").unwrap();
+    peer.set_read_timeout(Some(Duration::from_millis(50))).unwrap();
+    let sender = session_sender(peer.local_addr().unwrap(), Some(handler));
+    let hello = ControlMessage::Hello {
+        session_id: sender.session_id(),
+        version: PROTOCOL_VERSION,
+    };
+
+    // Repeated until accepted
+    thread::sleep(Duration::from_millis(250));
+    assert!(recv_control(&peer).iter().filter(|m| **m == hello).count() >= 2);
+
+    let source = {
+        sender.send(MessageType::MatchEvent, vec![1]).unwrap();
+        let mut buf = vec![0u8; MAX_MTU];
+        peer.recv_from(&mut buf).unwrap().1
+    };
+    for session_id in [42, 42, 43] {
+        let accept = ControlMessage::Accept { session_id, version: PROTOCOL_VERSION };
+        peer.send_to(&PacketBuilder::control(1, 0, &[accept]), source).unwrap();
+        thread::sleep(Duration::from_millis(20));
+    }
+
+    // Answered when new or restarted, no more repeats once accepted
+    thread::sleep(Duration::from_millis(200));
+    assert_eq!(recv_control(&peer), vec![hello, hello]);
+    let peer_addr = peer.local_addr().unwrap();
+    assert_eq!(
+        events.lock()[..],
+        [
+            SessionEvent::Established { peer: peer_addr, session_id: 42 },
+            SessionEvent::Restarted { peer: peer_addr, old_session_id: 42, session_id: 43 },
+        ]
+    );
+}
+
+#[test]
+fn test_sequence_reset() {
+    let (handler, events) = session_log();
+    let (receiver, recv_addr) = session_receiver(handler);
+    let sender = session_sender(recv_addr, None);
+
+    send_numbered(&sender, 3);
+    sender.reset_sequence(100).unwrap();
+    sender.send(MessageType::MatchEvent, vec![3]).unwrap();
+    sender.send(MessageType::MatchEvent, vec![4]).unwrap();
+
+    let seqs: Vec<u64> = (0..5)
+        .map_while(|_| receiver.recv_timeout(Duration::from_secs(1)).unwrap())
+        .map(|msg| msg.seq)
+        .collect();
+    assert_eq!(seqs, vec![0, 1, 2, 100, 101]);
+    assert_eq!(receiver.stats().gaps_detected, 0);
+    assert!(events
+        .lock()
+        .iter()
+        .any(|e| matches!(e, SessionEvent::SequenceReset { next_seq: 100, .. })));
+}
+
+// ============================================================================
+// Multiplexing Tests
+// ============================================================================
+
+fn mux_receiver(max_streams: usize) -> (UdpReceiver, SocketAddr) {
+    let recv_addr: SocketAddr = format!("127.0.0.1:{}", get_free_port()).parse().unwrap();
+    let receiver = UdpReceiver::new(
+        ReceiverConfig {
+            max_streams,
+            ..Default::default()
+        },
+        recv_addr,
+    )
+    .unwrap();
+    (receiver, recv_addr)
+}
+
+/// Send message `seq` of `stream_id` in a packet of its own
+fn send_raw(socket: &UdpSocket, stream_id: u32, seq: u64, payload: &[u8], dest: SocketAddr) {
+    let mut builder = PacketBuilder::new(stream_id, seq, seq);
+    builder.try_add_message(MessageType::MatchEvent, payload);
+    socket.send_to(&builder.finish(), dest).unwrap();
+}
+
+fn source(socket: &UdpSocket, stream_id: u32) -> StreamSource {
+    StreamSource {
+        addr: socket.local_addr().unwrap(),
+        stream_id,
+    }
+}
+
+#[test]
+fn test_streams_sequenced_independently() {
+    let (receiver, recv_addr) = mux_receiver(64);
+    let a = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let b = UdpSocket::bind("127.0.0.1:0").unwrap();
+
+    // Both senders number stream 1 from 0, stream 2 has its own numbers
+    send_raw(&a, 1, 0, b"a0", recv_addr);
+    send_raw(&b, 1, 0, b"b0", recv_addr);
+    send_raw(&a, 1, 1, b"a1", recv_addr);
+    send_raw(&a, 2, 0, b"c0", recv_addr);
+    send_raw(&b, 1, 1, b"b1", recv_addr);
+
+    let mut received: Vec<_> = (0..5)
+        .map_while(|_| receiver.recv_timeout(Duration::from_secs(1)).unwrap())
+        .map(|msg| (msg.source, msg.stream_id, msg.seq, msg.payload))
+        .collect();
+    received.sort();
+    let (a_addr, b_addr) = (a.local_addr().unwrap(), b.local_addr().unwrap());
+    let mut expected = vec![
+        (a_addr, 1, 0, b"a0".to_vec()),
+        (a_addr, 1, 1, b"a1".to_vec()),
+        (a_addr, 2, 0, b"c0".to_vec()),
+        (b_addr, 1, 0, b"b0".to_vec()),
+        (b_addr, 1, 1, b"b1".to_vec()),
+    ];
+    expected.sort();
+    assert_eq!(received, expected);
+
+    let stats = receiver.stats();
+    assert_eq!(stats.duplicates_dropped, 0);
+    assert_eq!(stats.gaps_detected, 0);
+    let streams = receiver.streams();
+    assert_eq!(streams.len(), 3);
+    assert!(streams.iter().all(|s| s.state == StreamState::Active));
+    assert_eq!(streams[2].source, source(&a, 2));
+}
+
+#[test]
+fn test_gap_confined_to_its_stream() {
+    let (receiver, recv_addr) = mux_receiver(64);
+    let a = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let b = UdpSocket::bind("127.0.0.1:0").unwrap();
+
+    send_raw(&a, 1, 0, b"a0", recv_addr);
+    send_raw(&b, 1, 0, b"b0", recv_addr);
+    thread::sleep(Duration::from_millis(20));
+    send_raw(&a, 1, 2, b"a2", recv_addr);
+    send_raw(&b, 1, 1, b"b1", recv_addr);
+    thread::sleep(Duration::from_millis(50));
+
+    let gaps = receiver.gaps();
+    assert_eq!(gaps.len(), 1);
+    assert_eq!(gaps[0].source, source(&a, 1));
+    let state_of = |socket: &UdpSocket| {
+        receiver
+            .streams()
+            .into_iter()
+            .find(|s| s.source == source(socket, 1))
+            .map(|s| s.state)
+    };
+    assert_eq!(state_of(&a), Some(StreamState::Degraded));
+    assert_eq!(state_of(&b), Some(StreamState::Active));
+    assert_eq!(receiver.state(), StreamState::Degraded);
+
+    // Clearing another stream leaves the gap alone
+    receiver.clear_stream_gaps(source(&b, 1));
+    assert_eq!(receiver.gaps().len(), 1);
+    receiver.clear_stream_gaps(source(&a, 1));
+    assert!(receiver.gaps().is_empty());
+    assert_eq!(state_of(&a), Some(StreamState::Active));
+    assert_eq!(receiver.state(), StreamState::Active);
+}
+
+#[test]
+fn test_stream_routed_to_own_receiver() {
+    let (receiver, recv_addr) = mux_receiver(64);
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let routed = receiver.route(2);
+    assert_eq!(routed.stream_id(), 2);
+
+    send_raw(&socket, 1, 0, b"one", recv_addr);
+    send_raw(&socket, 2, 0, b"two", recv_addr);
+
+    let msg = routed.recv_timeout(Duration::from_secs(1)).unwrap().unwrap();
+    assert_eq!((msg.stream_id, msg.payload), (2, b"two".to_vec()));
+    let msg = receiver.recv_timeout(Duration::from_secs(1)).unwrap().unwrap();
+    assert_eq!((msg.stream_id, msg.payload), (1, b"one".to_vec()));
+    assert!(receiver.try_recv().unwrap().is_none());
+
+    // Without its receiver the stream comes back to the main one
+    drop(routed);
+    send_raw(&socket, 2, 1, b"again", recv_addr);
+    let msg = receiver.recv_timeout(Duration::from_secs(1)).unwrap().unwrap();
+    assert_eq!((msg.stream_id, msg.seq), (2, 1));
+}
+
+#[test]
+fn test_streams_beyond_limit_rejected() {
+    let (receiver, recv_addr) = mux_receiver(2);
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+
+    for stream_id in 1..=3 {
+        send_raw(&socket, stream_id, 0, b"msg", recv_addr);
+    }
+    thread::sleep(Duration::from_millis(50));
+
+    let stats = receiver.stats();
+    assert_eq!(stats.streams_rejected, 1);
+    assert_eq!(stats.messages_received, 2);
+    let ids: Vec<u32> = receiver.streams().iter().map(|s| s.source.stream_id).collect();
+    assert_eq!(ids, vec![1, 2]);
+}
+
+// ============================================================================
+// Batched I/O Tests
+// ============================================================================
+
+fn outgoing(i: u8, dest: SocketAddr) -> crate::sender::OutgoingPacket {
+    crate::sender::OutgoingPacket {
+        data: bytes::Bytes::from(vec![i; 100 + i as usize]),
+        dest,
+        kind: crate::sender::PacketKind::Data { msg_count: 1 },
+    }
+}
+
+/// Receive `count` datagrams in as few calls as the socket allows
+fn recv_datagrams(socket: &UdpSocket, count: usize) -> Vec<(Vec<u8>, SocketAddr)> {
+    let mut batch = crate::batch_io::RecvBatch::new();
+    let mut datagrams = Vec::new();
+    while datagrams.len() < count {
+        if batch.recv(socket).is_err() {
+            break;
+        }
+        datagrams.extend(batch.packets().map(|(data, addr)| (data.to_vec(), addr)));
+    }
+    datagrams
+}
+
+#[test]
+fn test_batched_send_and_recv() {
+    let receiver = UdpSocket::bind("127.0.0.1:0").unwrap();
+    receiver.set_read_timeout(Some(Duration::from_secs(1))).unwrap();
+    let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let stats = crate::sender::SenderStats::default();
+
+    // More than a syscall takes
+    let count = crate::batch_io::MAX_BATCH as u8 + 8;
+    let mut queue = (0..count).map(|i| outgoing(i, receiver.local_addr().unwrap())).collect();
+    crate::batch_io::send_packets(&sender, &mut queue, &stats);
+    assert!(queue.is_empty());
+
+    // Loopback delivers on send, a full batch is waiting
+    #[cfg(target_os = "linux")]
+    {
+        let peek = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let mut queue = (0..count).map(|i| outgoing(i, peek.local_addr().unwrap())).collect();
+        crate::batch_io::send_packets(&sender, &mut queue, &Default::default());
+        let mut batch = crate::batch_io::RecvBatch::new();
+        assert_eq!(batch.recv(&peek).unwrap(), crate::batch_io::MAX_BATCH);
+    }
+
+    let datagrams = recv_datagrams(&receiver, count as usize);
+    assert_eq!(datagrams.len(), count as usize);
+    for (i, (data, addr)) in datagrams.iter().enumerate() {
+        assert_eq!(*data, vec![i as u8; 100 + i]);
+        assert_eq!(*addr, sender.local_addr().unwrap());
+    }
+
+    let stats = stats.snapshot();
+    assert_eq!(stats.packets_sent, count as u64);
+    assert_eq!(stats.messages_sent, count as u64);
+    assert_eq!(
+        stats.bytes_sent,
+        datagrams.iter().map(|(data, _)| data.len() as u64).sum::<u64>()
+    );
+    assert_eq!(stats.send_errors, 0);
+}
+
+#[test]
+fn test_batched_send_skips_failed_packet() {
+    let receiver = UdpSocket::bind("127.0.0.1:0").unwrap();
+    receiver.set_read_timeout(Some(Duration::from_secs(1))).unwrap();
+    let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let stats = crate::sender::SenderStats::default();
+
+    // An IPv6 destination can't be reached from an IPv4 socket
+    let dest = receiver.local_addr().unwrap();
+    let unreachable: SocketAddr = format!("[::1]:{}", dest.port()).parse().unwrap();
+    let mut queue = (0..5)
+        .map(|i| outgoing(i, if i == 2 { unreachable } else { dest }))
+        .collect();
+    crate::batch_io::send_packets(&sender, &mut queue, &stats);
+    assert!(queue.is_empty());
+
+    let firsts: Vec<u8> = recv_datagrams(&receiver, 4).iter().map(|(data, _)| data[0]).collect();
+    assert_eq!(firsts, vec![0, 1, 3, 4]);
+    assert_eq!(stats.snapshot().packets_sent, 4);
+    assert_eq!(stats.snapshot().send_errors, 1);
+}
+
+#[test]
+fn test_burst_of_fragments() {
+    let recv_addr: SocketAddr = format!("127.0.0.1:{}", get_free_port()).parse().unwrap();
+    let receiver = UdpReceiver::new(
+        ReceiverConfig {
+            stream_id: 1,
+            ..Default::default()
+        },
+        recv_addr,
+    )
+    .unwrap();
+    let sender = UdpSender::new(
+        SenderConfig {
+            stream_id: 1,
+            target_addr: recv_addr,
+            ..Default::default()
+        },
+        "127.0.0.1:0".parse().unwrap(),
+    )
+    .unwrap();
+
+    // Each message is queued as a run of packets sent together
+    let payload = large_payload(20_000);
+    for _ in 0..5 {
+        sender
+            .send(MessageType::BookSnapshot, payload.clone())
+            .unwrap();
+    }
+    for _ in 0..5 {
+        let msg = receiver.recv_timeout(Duration::from_secs(2)).unwrap().unwrap();
+        assert_eq!(msg.payload, payload);
+    }
+
+    // Counted once the send call returns
+    let fragments = 5 * payload.len().div_ceil(MAX_FRAGMENT_LEN) as u64;
+    wait_for(|| sender.stats().packets_sent == fragments);
+    assert_eq!(sender.stats().packets_sent, fragments);
+    assert_eq!(receiver.stats().messages_reassembled, 5);
+}
+
+// ============================================================================
+// Async Tests
+// ============================================================================
+
+#[cfg(feature = "async")]
+fn async_stream(
+    sender_config: SenderConfig,
+    target: impl FnOnce(SocketAddr) -> SocketAddr,
+) -> (AsyncUdpSender, AsyncUdpReceiver) {
+    let recv_addr: SocketAddr = format!("127.0.0.1:{}", get_free_port()).parse().unwrap();
+    let receiver = AsyncUdpReceiver::new(
+        ReceiverConfig {
+            stream_id: 1,
+            ..Default::default()
+        },
+        recv_addr,
+    )
+    .unwrap();
+    let sender = AsyncUdpSender::new(
+        SenderConfig {
+            stream_id: 1,
+            target_addr: target(recv_addr),
+            ..sender_config
+        },
+        "127.0.0.1:0".parse().unwrap(),
+    )
+    .unwrap();
+    (sender, receiver)
+}
+
+#[cfg(feature = "async")]
+fn numbered(i: u8) -> OutgoingMessage {
+    OutgoingMessage {
+        msg_type: MessageType::MatchEvent,
+        payload: vec![i],
+    }
+}
+
+#[cfg(feature = "async")]
+async fn next_payloads(receiver: &mut AsyncUdpReceiver, count: usize) -> Vec<u8> {
+    use futures_util::StreamExt;
+
+    let mut payloads = Vec::new();
+    while payloads.len() < count {
+        match tokio::time::timeout(Duration::from_secs(1), receiver.next()).await {
+            Ok(Some(msg)) => payloads.push(msg.payload[0]),
+            _ => break,
+        }
+    }
+    payloads
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_async_sink_and_stream() {
+    use futures_util::SinkExt;
+
+    let (mut sender, mut receiver) = async_stream(SenderConfig::default(), |addr| addr);
+
+    // Fed messages are batched, the flush sends them in one packet
+    for i in 0..10 {
+        sender.feed(numbered(i)).await.unwrap();
+    }
+    sender.flush().await.unwrap();
+    sender.send(OutgoingMessage {
+        msg_type: MessageType::BookSnapshot,
+        payload: large_payload(5000),
+    })
+    .await
+    .unwrap();
+
+    assert_eq!(next_payloads(&mut receiver, 10).await, (0..10).collect::<Vec<u8>>());
+    let snapshot = receiver.recv().await;
+    assert_eq!(snapshot.payload, large_payload(5000));
+    assert_eq!(snapshot.seq, 10);
+    assert_eq!(sender.stats().messages_sent, 10 + 5000 / MAX_FRAGMENT_LEN as u64 + 1);
+    assert_eq!(receiver.stats().messages_reassembled, 1);
+    assert_eq!(receiver.state(), StreamState::Active);
+
+    let too_large = OutgoingMessage {
+        msg_type: MessageType::BookSnapshot,
+        payload: vec![0; DEFAULT_MAX_MESSAGE_SIZE + 1],
+    };
+    assert!(matches!(
+        sender.send(too_large).await,
+        Err(ProtocolError::MessageTooLarge { .. })
+    ));
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_async_batch_sent_after_delay() {
+    use futures_util::SinkExt;
+
+    let config = SenderConfig {
+        max_batch_delay: Duration::from_millis(5),
+        ..Default::default()
+    };
+    let (mut sender, mut receiver) = async_stream(config, |addr| addr);
+
+    // Never flushed, the background task sends the partial batch
+    sender.feed(numbered(7)).await.unwrap();
+    assert_eq!(next_payloads(&mut receiver, 1).await, vec![7]);
+    assert_eq!(sender.stats().packets_sent, 1);
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_async_nak_recovery() {
+    use futures_util::SinkExt;
+
+    let config = SenderConfig {
+        enable_heartbeats: false,
+        ..Default::default()
+    };
+    let mut link = None;
+    let (mut sender, mut receiver) = async_stream(config, |addr| {
+        let link_config = LinkConfig {
+            lose: Some(Box::new(|h| [2, 3].contains(&h.first_msg_seq))),
+            ..Default::default()
+        };
+        let lossy = ImpairedLink::new(addr, link_config).unwrap();
+        let link_addr = lossy.addr();
+        link = Some(lossy);
+        link_addr
+    });
+
+    // One message per packet
+    for i in 0..6 {
+        sender.send(numbered(i)).await.unwrap();
+    }
+
+    assert_eq!(next_payloads(&mut receiver, 6).await, (0..6).collect::<Vec<u8>>());
+    assert_eq!(receiver.stats().gaps_recovered, 1);
+    assert_eq!(sender.stats().retransmits_sent, 2);
+    assert!(!receiver.needs_snapshot());
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_async_receiver_in_select() {
+    let (sender, mut receiver) = {
+        let recv_addr: SocketAddr = format!("127.0.0.1:{}", get_free_port()).parse().unwrap();
+        let receiver = AsyncUdpReceiver::new(
+            ReceiverConfig {
+                stream_id: 1,
+                ..Default::default()
+            },
+            recv_addr,
+        )
+        .unwrap();
+        // A thread sender on the other end
+        let sender = UdpSender::new(
+            SenderConfig {
+                stream_id: 1,
+                target_addr: recv_addr,
+                max_batch_delay: Duration::from_millis(1),
+                ..Default::default()
+            },
+            "127.0.0.1:0".parse().unwrap(),
+        )
+        .unwrap();
+        (sender, receiver)
+    };
+
+    // Losing the race to a timer drops nothing
+    tokio::select! {
+        _ = receiver.recv() => panic!("nothing was sent yet"),
+        _ = tokio::time::sleep(Duration::from_millis(20)) => {}
+    }
+    sender.send(MessageType::MatchEvent, vec![1]).unwrap();
+    let msg = tokio::select! {
+        msg = receiver.recv() => msg,
+        _ = tokio::time::sleep(Duration::from_secs(1)) => panic!("message not received"),
+    };
+    assert_eq!(msg.payload, vec![1]);
+    assert_eq!(msg.seq, 0);
+}
+
 // ============================================================================
 // Edge Case Tests
 // ============================================================================
@@ -611,13 +2342,15 @@ fn test_message_too_large() {
         stream_id: 1,
         target_addr: recv_addr,
         enable_heartbeats: false,
+        max_message_size: MAX_MTU * 2,
         ..Default::default()
     };
 
     let sender = UdpSender::new(send_config, send_addr).unwrap();
 
-    // Try to send message larger than MTU
-    let huge_payload = vec![0u8; MAX_MTU * 2];
+    // Larger than the MTU is fragmented, larger than the limit is refused
+    assert!(sender.send(MessageType::BookSnapshot, vec![0u8; MAX_MTU * 2]).is_ok());
+    let huge_payload = vec![0u8; MAX_MTU * 2 + 1];
     let result = sender.send(MessageType::BookSnapshot, huge_payload);
 
     assert!(matches!(result, Err(ProtocolError::MessageTooLarge { .. })));